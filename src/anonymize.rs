@@ -0,0 +1,191 @@
+/*!
+Presentation mode, hiding emails and descriptions for screenshots and recordings.
+
+The text is replaced in the rendered frame, after every widget has been
+drawn, so it applies to all output of jj and blazingjj alike and no data of
+the repository is changed. Placeholders have the same width as the text they
+replace to keep the layout. Text leaving the frame, like a
+[crash report][crate::crash_report], is replaced with [anonymize_text].
+
+Descriptions can't be recognized in arbitrary output, so the lines of the
+descriptions of the changes in the log are searched for. Lines cut off at the
+edge of a panel are recognized by their start.
+*/
+use std::sync::LazyLock;
+
+use ratatui::{buffer::Buffer, layout::Rect};
+use regex::Regex;
+
+use crate::commander::ANSI_REGEX;
+
+static EMAIL_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"[\w.+-]+@[\w-]+(\.[\w-]+)+").unwrap());
+
+/// Text replacing the letters and digits of descriptions, other characters are kept
+const PLACEHOLDER: &str = "loremipsumdolorsitametconsecteturadipiscingelit";
+/// Number of characters a cut off description line has to start with to be recognized
+const MIN_PREFIX: usize = 12;
+/// Description lines shorter than this are not replaced, as they would match everywhere
+const MIN_DESCRIPTION: usize = 3;
+
+/// Lines of the descriptions, without colors and deduplicated, to be passed to
+/// [anonymize_buffer]
+pub fn description_lines<'a>(descriptions: impl IntoIterator<Item = &'a str>) -> Vec<Vec<char>> {
+    let mut lines: Vec<Vec<char>> = descriptions
+        .into_iter()
+        .flat_map(|description| {
+            ANSI_REGEX
+                .replace_all(description, "")
+                .lines()
+                .map(|line| line.trim().chars().collect::<Vec<char>>())
+                .collect::<Vec<_>>()
+        })
+        .filter(|line| line.len() >= MIN_DESCRIPTION)
+        .collect();
+    // Longer lines first, so a line containing another one is replaced as a whole
+    lines.sort_by(|a, b| b.len().cmp(&a.len()).then(a.cmp(b)));
+    lines.dedup();
+    lines
+}
+
+/// Replace every letter and digit with a character of the placeholder
+fn mask(chars: &mut [char], placeholder: &str) {
+    let mut placeholder = placeholder.chars().cycle();
+    for char in chars {
+        if char.is_alphanumeric() {
+            *char = placeholder.next().unwrap_or('x');
+        }
+    }
+}
+
+/// Start and length of the occurrences of the description line in the row, also when the row
+/// only shows its start
+fn find_description(row: &[char], line: &[char]) -> Vec<(usize, usize)> {
+    let prefix = &line[..line.len().min(MIN_PREFIX)];
+    let mut found = vec![];
+    let mut start = 0;
+    while start + prefix.len() <= row.len() {
+        if row[start..].starts_with(prefix) {
+            let len = row[start..]
+                .iter()
+                .zip(line)
+                .take_while(|(a, b)| a == b)
+                .count();
+            found.push((start, len));
+            start += len;
+        } else {
+            start += 1;
+        }
+    }
+    found
+}
+
+/// Replace the emails and the description lines in a row of characters
+fn anonymize_chars(row: &mut [char], descriptions: &[Vec<char>]) {
+    let text: String = row.iter().collect();
+    let char_offsets: Vec<usize> = text.char_indices().map(|(offset, _)| offset).collect();
+    let to_char_index = |offset: usize| char_offsets.partition_point(|o| *o < offset);
+    for email in EMAIL_REGEX.find_iter(&text) {
+        let (start, end) = (to_char_index(email.start()), to_char_index(email.end()));
+        mask(&mut row[start..end], "x");
+    }
+
+    for line in descriptions {
+        for (start, len) in find_description(row, line) {
+            mask(&mut row[start..start + len], PLACEHOLDER);
+        }
+    }
+}
+
+/// Replace emails and, if any are given, description lines in each line of the text
+pub fn anonymize_text(text: &str, descriptions: &[Vec<char>]) -> String {
+    text.split('\n')
+        .map(|line| {
+            let mut row: Vec<char> = line.chars().collect();
+            anonymize_chars(&mut row, descriptions);
+            row.into_iter().collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Replace emails and, if any are given, description lines in the area of the buffer
+pub fn anonymize_buffer(buffer: &mut Buffer, area: Rect, descriptions: &[Vec<char>]) {
+    for y in area.top()..area.bottom() {
+        // Each cell is one character, wide characters are followed by an empty cell
+        let mut row: Vec<char> = (area.left()..area.right())
+            .map(|x| buffer[(x, y)].symbol().chars().next().unwrap_or(' '))
+            .collect();
+        let original = row.clone();
+        anonymize_chars(&mut row, descriptions);
+
+        for ((x, char), original) in (area.left()..).zip(row).zip(original) {
+            if char != original {
+                buffer[(x, y)].set_char(char);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::style::Style;
+
+    use super::*;
+
+    fn anonymize(text: &str, descriptions: &[&str]) -> String {
+        let mut row: Vec<char> = text.chars().collect();
+        anonymize_chars(&mut row, &description_lines(descriptions.iter().copied()));
+        row.into_iter().collect()
+    }
+
+    #[test]
+    fn emails() {
+        assert_eq!(
+            anonymize("│ jo.smith@acme.io 2 days ago", &[]),
+            "│ xx.xxxxx@xxxx.xx 2 days ago"
+        );
+        assert_eq!(anonymize("no email @ here", &[]), "no email @ here");
+    }
+
+    #[test]
+    fn descriptions() {
+        let descriptions = ["Fix the login page\n\nThe \x1b[1mbody\x1b[0m of it\n"];
+        assert_eq!(
+            anonymize("@  kkmp Fix the login page", &descriptions),
+            "@  kkmp lor emi psumd olor"
+        );
+        assert_eq!(anonymize("The body of it", &descriptions), "lor emip su md");
+        // Cut off at the edge of the panel
+        assert_eq!(
+            anonymize("○  kkmp Fix the login│", &descriptions),
+            "○  kkmp lor emi psumd│"
+        );
+        // Too short to be recognized when cut off
+        assert_eq!(anonymize("Fix the", &descriptions), "Fix the");
+    }
+
+    #[test]
+    fn text() {
+        assert_eq!(
+            anonymize_text(
+                "[0, 12ms] jj describe -m 'Add a test'\nAuthor: a@example.com\n",
+                &description_lines(["Add a test"])
+            ),
+            "[0, 12ms] jj describe -m 'lor e mips'\nAuthor: x@xxxxxxx.xxx\n"
+        );
+    }
+
+    #[test]
+    fn buffer() {
+        let area = Rect::new(0, 0, 20, 2);
+        let mut buffer = Buffer::empty(area);
+        buffer.set_string(0, 0, "a@example.com", Style::default());
+        buffer.set_string(0, 1, "Add a test", Style::default());
+        anonymize_buffer(&mut buffer, area, &description_lines(["Add a test"]));
+        assert_eq!(
+            buffer,
+            Buffer::with_lines(["x@xxxxxxx.xxx       ", "lor e mips          "])
+        );
+    }
+}
@@ -1,17 +1,41 @@
 use crate::{
     ComponentInputResult,
-    commander::Commander,
-    env::Env,
+    anonymize::description_lines,
+    commander::{
+        CommandError, Commander,
+        instances::is_own_operation,
+        log::WorkingCopySummary,
+        snapshot::take_refused_files,
+        warnings::{JjWarning, take_warnings},
+    },
+    doctor::Report,
+    env::{Env, Favorite, PresentationMode},
+    recent_repositories,
     ui::{
-        Component, ComponentAction, bookmarks_tab::BookmarksTab, command_popup::CommandPopup,
-        files_tab::FilesTab, log_tab::LogTab,
+        Component, ComponentAction, bookmarks_tab::BookmarksTab, command_log::command_log_popup,
+        command_popup::CommandPopup, files_tab::FilesTab, log_tab::LogTab,
+        maintenance_popup::MaintenancePopup, message_popup::MessagePopup,
+        operations_popup::OperationsPopup, read_only_popup, recovery_popup::RecoveryPopup,
+        repository_popup::RepositoryPopup, rollback_popup::RollbackPopup,
+        settings_popup::SettingsPopup, snapshot_popup::SnapshotPopup,
     },
 };
 use anyhow::{Result, anyhow};
 use core::fmt;
-use ratatui::crossterm::event::{self, Event, KeyCode, KeyModifiers};
-use std::time::Instant;
-use tracing::{info, instrument};
+use itertools::Itertools;
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyModifiers},
+    layout::Alignment,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    mem,
+    path::PathBuf,
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+    time::{Duration, Instant},
+};
+use tracing::{info, instrument, warn};
 
 #[derive(PartialEq, Copy, Clone)]
 pub enum Tab {
@@ -34,10 +58,30 @@ impl Tab {
     pub const VALUES: [Self; 3] = [Tab::Log, Tab::Files, Tab::Bookmarks];
 }
 
+/// How often the operation heads are checked for operations of other processes while idle
+pub const OP_HEADS_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
 pub struct Stats {
     pub start_time: Instant,
 }
 
+/// Tabs of a repository which is not active, restored when switching back to it
+struct RepositoryState<'a> {
+    env: Env,
+    current_tab: Tab,
+    log: Option<LogTab<'a>>,
+    files: Option<FilesTab>,
+    bookmarks: Option<BookmarksTab<'a>>,
+}
+
+/// Command which needs the terminal, so the TUI is suspended while it runs
+pub enum TerminalCommand {
+    /// jj command with these arguments
+    Jj(Vec<String>),
+    /// Pager showing this text
+    Pager(String),
+}
+
 pub struct App<'a> {
     pub env: Env,
     pub current_tab: Tab,
@@ -46,23 +90,276 @@ pub struct App<'a> {
     pub bookmarks: Option<BookmarksTab<'a>>,
     pub popup: Option<Box<dyn Component>>,
     pub stats: Stats,
+    /// Whether the terminal currently has focus
+    pub focused: bool,
+    /// Command to run with the TUI suspended, picked up by the main loop
+    pub terminal_command: Option<TerminalCommand>,
+    /// Whether the strip with the last commands is shown at the bottom
+    pub show_command_log: bool,
+    /// Key whose action failed because another instance held the lock, repeated with `r` in the
+    /// popup showing the failure
+    retry_event: Option<Event>,
+    /// Whether mouse events are captured, picked up by the main loop. Without it, the terminal
+    /// selects and copies text natively
+    pub mouse_capture: bool,
+    /// Whether blazingjj was quit with Ctrl+c, so nothing is printed on exit
+    pub cancelled: bool,
+    /// Summary of the working copy shown in the header
+    pub working_copy: Option<WorkingCopySummary>,
+    /// Operation heads the working copy summary was queried at, `None` before the first query
+    working_copy_op_heads: Option<Vec<String>>,
+    /// Working copy summary queried in the background, with the operation heads after the query
+    working_copy_rx: Option<Receiver<(Result<WorkingCopySummary>, Vec<String>)>>,
+    /// Operation heads the current tab was last checked for changes by other processes at
+    tab_op_heads: Vec<String>,
+    /// Files jj refused to snapshot which were already shown, so the popup is shown only once
+    shown_refused_files: HashSet<String>,
+    /// Warnings of jj shown as banners above the tab, oldest first
+    pub warnings: Vec<JjWarning>,
+    /// Warnings which were dismissed, so they aren't shown again when jj repeats them
+    dismissed_warnings: HashSet<JjWarning>,
+    /// Last time the user interacted or the active tab was refreshed
+    last_activity: Instant,
+    /// Inactive repositories opened in this session, by root
+    repositories: HashMap<String, RepositoryState<'a>>,
+    /// Roots of recently opened repositories, most recent first, see [recent_repositories]
+    recent_repositories: Vec<String>,
+    /// Diagnostics run on startup, shown once they are done if they found issues
+    diagnostics_rx: Option<Receiver<Report>>,
 }
 
 impl<'a> App<'a> {
     pub fn new(env: Env) -> Result<App<'a>> {
+        let popup: Option<Box<dyn Component>> = if env.config_errors.is_empty() {
+            None
+        } else {
+            Some(Box::new(MessagePopup {
+                title: "Config errors".into(),
+                messages: env.config_errors.join("\n").into(),
+                text_align: Some(Alignment::Left),
+                ..Default::default()
+            }))
+        };
+        let mouse_capture = env.config.mouse();
         Ok(App {
             env,
             current_tab: Tab::Log,
             log: None,
             files: None,
             bookmarks: None,
-            popup: None,
+            popup,
             stats: Stats {
                 start_time: Instant::now(),
             },
+            focused: true,
+            last_activity: Instant::now(),
+            terminal_command: None,
+            show_command_log: false,
+            retry_event: None,
+            mouse_capture,
+            cancelled: false,
+            working_copy: None,
+            working_copy_op_heads: None,
+            working_copy_rx: None,
+            tab_op_heads: vec![],
+            shown_refused_files: HashSet::new(),
+            warnings: vec![],
+            dismissed_warnings: HashSet::new(),
+            repositories: HashMap::new(),
+            recent_repositories: vec![],
+            diagnostics_rx: None,
         })
     }
 
+    /// Run the diagnostics in the background, as reaching the remotes may take a few seconds.
+    /// They are shown in a popup if they find issues, once no other popup is open.
+    pub fn start_diagnostics(&mut self) {
+        let (tx, rx) = mpsc::channel();
+        let commander = Commander::new(&self.env);
+        thread::spawn(move || tx.send(Report::run(&commander)));
+        self.diagnostics_rx = Some(rx);
+    }
+
+    /// Whether the diagnostics started with [App::start_diagnostics] or the working copy summary
+    /// are still queried in the background
+    pub fn background_pending(&self) -> bool {
+        self.diagnostics_rx.is_some() || self.working_copy_rx.is_some()
+    }
+
+    fn show_diagnostics(&mut self) {
+        if self.popup.is_some() {
+            return;
+        }
+        let Some(diagnostics_rx) = self.diagnostics_rx.as_ref() else {
+            return;
+        };
+        let report = match diagnostics_rx.try_recv() {
+            Ok(report) => report,
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => {
+                self.diagnostics_rx = None;
+                return;
+            }
+        };
+        self.diagnostics_rx = None;
+        if report.has_issues() {
+            self.popup = Some(Box::new(MessagePopup {
+                title: "Diagnostics".into(),
+                messages: report.to_text(),
+                text_align: Some(Alignment::Left),
+                ..Default::default()
+            }));
+        }
+    }
+
+    /// Time left until the next auto refresh. `None` if disabled or the terminal is unfocused.
+    pub fn time_until_auto_refresh(&self) -> Option<Duration> {
+        if !self.focused {
+            return None;
+        }
+        self.env
+            .config
+            .auto_refresh_interval()
+            .map(|interval| interval.saturating_sub(self.last_activity.elapsed()))
+    }
+
+    /// Refresh the active tab if auto refresh is enabled and the app has been idle long enough.
+    /// Skipped while unfocused or while a popup is open.
+    #[instrument(level = "trace", skip(self, commander))]
+    pub fn auto_refresh(&mut self, commander: &mut Commander) -> Result<()> {
+        if self.popup.is_some() || self.time_until_auto_refresh() != Some(Duration::ZERO) {
+            return Ok(());
+        }
+
+        info!("Auto refreshing {}", self.current_tab);
+        self.get_or_init_current_tab(commander)?.focus(commander)?;
+        self.last_activity = Instant::now();
+        Ok(())
+    }
+
+    /// Whether the popup or the current tab waits for background work
+    pub fn is_busy(&mut self) -> bool {
+        self.popup.is_some()
+            || self
+                .get_current_tab()
+                .is_some_and(|current_tab| current_tab.is_busy())
+    }
+
+    /// Description lines for [anonymize][crate::anonymize], None if the presentation mode is off
+    pub fn anonymized_descriptions(&self) -> Option<Vec<Vec<char>>> {
+        match self.env.config.presentation_mode() {
+            PresentationMode::Off => None,
+            PresentationMode::Emails => Some(vec![]),
+            PresentationMode::EmailsAndDescriptions => Some(description_lines(
+                self.log.iter().flat_map(|log_tab| log_tab.descriptions()),
+            )),
+        }
+    }
+
+    /// Short description of the UI state, included in crash reports
+    pub fn state_summary(&self) -> String {
+        let mut summary = format!(
+            "Repository: {}\nTab: {}\nPopup open: {}\nFocused: {}",
+            self.env.root,
+            self.current_tab,
+            self.popup.is_some(),
+            self.focused
+        );
+        if let Some(log) = self.log.as_ref() {
+            summary.push_str(&format!("\nLog head: {}", log.head().change_id.as_str()));
+        }
+        if let Some(file) = self.files.as_ref().and_then(|files| files.file.as_ref()) {
+            summary.push_str(&format!("\nFiles tab file: {:?}", file.path));
+        }
+        summary
+    }
+
+    /// Change id of the change selected in the log tab
+    pub fn selected_change_id(&self) -> Option<String> {
+        self.log
+            .as_ref()
+            .map(|log| log.head().change_id.as_str().to_owned())
+    }
+
+    /// What is selected in the current tab: the change id in the log tab, the absolute path of
+    /// the file in the files tab and the bookmark in the bookmarks tab
+    pub fn selection(&self) -> Option<String> {
+        match self.current_tab {
+            Tab::Log => self.selected_change_id(),
+            Tab::Files => {
+                let file = self.files.as_ref()?.file.as_ref()?;
+                let path = file.paths().pop()?;
+                Some(
+                    PathBuf::from(&self.env.root)
+                        .join(path)
+                        .to_string_lossy()
+                        .into_owned(),
+                )
+            }
+            Tab::Bookmarks => self
+                .bookmarks
+                .as_ref()?
+                .selected_bookmark()
+                .map(|bookmark| bookmark.to_string()),
+        }
+    }
+
+    /// Record the current repository as the most recently opened one, across sessions
+    pub fn record_repository(&mut self) {
+        self.recent_repositories = recent_repositories::record(&self.env.root);
+    }
+
+    /// Repositories for the repository switcher: recently opened ones first, then configured ones
+    fn repository_choices(&self) -> Vec<String> {
+        let mut repositories = vec![self.env.root.clone()];
+        repositories.extend(self.recent_repositories.iter().cloned());
+        repositories.extend(self.env.config.repositories());
+        repositories.into_iter().unique().collect()
+    }
+
+    /// Switch to the repository at `path`. The tabs of the current repository are kept and
+    /// restored when switching back to it.
+    pub fn switch_repository(&mut self, commander: &mut Commander, path: &str) -> Result<()> {
+        let mut env = Env::new(PathBuf::from(path), None, self.env.jj_bin.clone())?;
+        // Stay read-only for the session
+        if self.env.config.read_only() {
+            env.config.enable_read_only();
+        }
+        if env.root == self.env.root {
+            return Ok(());
+        }
+        info!("Switching repository to {}", env.root);
+
+        let state = self
+            .repositories
+            .remove(&env.root)
+            .unwrap_or(RepositoryState {
+                env,
+                current_tab: Tab::Log,
+                log: None,
+                files: None,
+                bookmarks: None,
+            });
+        let previous = RepositoryState {
+            env: mem::replace(&mut self.env, state.env),
+            current_tab: mem::replace(&mut self.current_tab, state.current_tab),
+            log: mem::replace(&mut self.log, state.log),
+            files: mem::replace(&mut self.files, state.files),
+            bookmarks: mem::replace(&mut self.bookmarks, state.bookmarks),
+        };
+
+        self.record_repository();
+        self.repositories
+            .insert(previous.env.root.clone(), previous);
+
+        *commander = Commander::new(&self.env);
+        self.tab_op_heads = vec![];
+        self.working_copy = None;
+        self.working_copy_op_heads = None;
+        self.working_copy_rx = None;
+        self.set_tab(commander, self.current_tab)
+    }
+
     pub fn get_or_init_current_tab(
         &mut self,
         commander: &mut Commander,
@@ -169,10 +466,49 @@ impl<'a> App<'a> {
                 self.set_tab(commander, Tab::Files)?;
                 self.get_files_tab(commander)?.set_head(commander, &head)?;
             }
+            ComponentAction::ReviewRange(revset) => {
+                match self
+                    .get_files_tab(commander)?
+                    .review_range(commander, &revset)
+                {
+                    Ok(()) => self.set_tab(commander, Tab::Files)?,
+                    Err(err) => {
+                        self.popup = Some(Box::new(MessagePopup {
+                            title: "Review error".into(),
+                            messages: err.to_string().into(),
+                            text_align: None,
+                            ..Default::default()
+                        }));
+                    }
+                }
+            }
+            ComponentAction::ViewFileLine {
+                head,
+                path,
+                line,
+                text,
+            } => {
+                self.set_tab(commander, Tab::Files)?;
+                self.get_files_tab(commander)?
+                    .view_file_line(commander, &head, &path, line, &text)?;
+            }
             ComponentAction::ViewLog(head) => {
-                self.get_log_tab(commander)?.set_head(commander, head);
+                self.get_log_tab(commander)?.view_head(commander, head);
                 self.set_tab(commander, Tab::Log)?;
             }
+            ComponentAction::ViewLogForPath(path) => {
+                self.get_log_tab(commander)?
+                    .set_path_filter(commander, Some(path));
+                self.set_tab(commander, Tab::Log)?;
+            }
+            ComponentAction::ViewBookmark { name, remote } => {
+                self.get_bookmarks_tab(commander)?.select_bookmark(
+                    commander,
+                    &name,
+                    remote.as_deref(),
+                );
+                self.set_tab(commander, Tab::Bookmarks)?;
+            }
             ComponentAction::ChangeHead(head) => {
                 self.get_files_tab(commander)?.set_head(commander, &head)?;
             }
@@ -185,11 +521,24 @@ impl<'a> App<'a> {
                 }
             }
             ComponentAction::RefreshTab() => {
+                // Focusing the tab keeps its selection on the latest version of the change
                 self.set_tab(commander, self.current_tab)?;
-                if self.current_tab == Tab::Log {
-                    let head = commander.get_current_head()?;
-                    self.get_log_tab(commander)?.set_head(commander, head);
-                };
+            }
+            ComponentAction::SwitchRepository(path) => {
+                if let Err(err) = self.switch_repository(commander, &path) {
+                    self.popup = Some(Box::new(MessagePopup {
+                        title: "Switch repository error".into(),
+                        messages: err.to_string().into(),
+                        text_align: None,
+                        ..Default::default()
+                    }));
+                }
+            }
+            ComponentAction::RunInTerminal(args) => {
+                self.terminal_command = Some(TerminalCommand::Jj(args));
+            }
+            ComponentAction::OpenInPager(text) => {
+                self.terminal_command = Some(TerminalCommand::Pager(text));
             }
         }
 
@@ -204,19 +553,159 @@ impl<'a> App<'a> {
             self.handle_action(component_action, commander)?;
         }
 
-        if let Some(component_action) =
-            self.get_or_init_current_tab(commander)?.update(commander)?
-        {
+        let update_result = self.get_or_init_current_tab(commander)?.update(commander);
+        if let Some(component_action) = self.catch_command_error(update_result)? {
             self.handle_action(component_action, commander)?;
         }
 
+        self.refresh_working_copy(commander);
+        self.refresh_on_external_change(commander)?;
+
+        for warning in take_warnings(&commander.env.root) {
+            if !self.dismissed_warnings.contains(&warning) && !self.warnings.contains(&warning) {
+                self.warnings.push(warning);
+            }
+        }
+
+        self.show_diagnostics();
+
+        if self.popup.is_none() {
+            let refused_files: Vec<_> = take_refused_files(&commander.env.root)
+                .into_iter()
+                .filter(|file| self.shown_refused_files.insert(file.path.clone()))
+                .collect();
+            if !refused_files.is_empty() {
+                self.popup = Some(Box::new(SnapshotPopup::new(refused_files)));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Query the working copy summary again in the background if an operation changed the
+    /// repository since the last query, once that one finished
+    fn refresh_working_copy(&mut self, commander: &Commander) {
+        if let Some(working_copy_rx) = self.working_copy_rx.as_ref() {
+            match working_copy_rx.try_recv() {
+                Ok((summary, op_heads)) => {
+                    self.working_copy = summary.inspect_err(|err| warn!("{err:#}")).ok();
+                    self.working_copy_op_heads = Some(op_heads);
+                }
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => {}
+            }
+            self.working_copy_rx = None;
+        }
+
+        if self.working_copy_op_heads.as_ref() == Some(&commander.get_op_heads()) {
+            return;
+        }
+        let (tx, rx) = mpsc::channel();
+        let commander = Commander::new(&self.env);
+        thread::spawn(move || {
+            let summary = commander.get_working_copy_summary();
+            // The query snapshots the working copy, which adds an operation if files changed
+            let _ = tx.send((summary, commander.get_op_heads()));
+        });
+        self.working_copy_rx = Some(rx);
+    }
+
+    /// Refresh the current tab when another process, like another blazingjj instance or an editor
+    /// running jj, changed the repository. Waits while the terminal is unfocused or a popup is
+    /// open, so that nothing changes under the user.
+    fn refresh_on_external_change(&mut self, commander: &mut Commander) -> Result<()> {
+        let op_heads = commander.get_op_heads();
+        if op_heads == self.tab_op_heads {
+            return Ok(());
+        }
+        if self.tab_op_heads.is_empty() || is_own_operation(&op_heads) {
+            self.tab_op_heads = op_heads;
+            return Ok(());
+        }
+        if !self.focused || self.is_busy() {
+            return Ok(());
+        }
+
+        info!(
+            "Refreshing {} after an operation of another process",
+            self.current_tab
+        );
+        self.tab_op_heads = op_heads;
+        self.get_or_init_current_tab(commander)?.focus(commander)?;
+        // The refresh can snapshot the working copy, which is not a change by another process
+        self.tab_op_heads = commander.get_op_heads();
         Ok(())
     }
 
+    /// Show a failed hook, an action refused in read-only mode or while another instance changes
+    /// the repository, or a partially applied action in a popup instead of exiting. Returns `T::default()` in that case.
+    fn catch_command_error<T: Default>(&mut self, result: Result<T>) -> Result<T> {
+        let err = match result {
+            Ok(value) => return Ok(value),
+            Err(err) => err,
+        };
+        let err = match RollbackPopup::from_error(err) {
+            Ok(popup) => {
+                self.popup = Some(popup);
+                return Ok(T::default());
+            }
+            Err(err) => err,
+        };
+        let mut message = err.to_string();
+        let title = match err.downcast_ref::<CommandError>() {
+            Some(CommandError::Hook(..)) => "Hook failed",
+            Some(CommandError::ReadOnly(..)) => "Read-only mode",
+            Some(CommandError::Locked(..)) => {
+                if self.retry_event.is_some() {
+                    message.push_str("\n\nPress r to retry");
+                }
+                "Repository busy"
+            }
+            _ => return Err(err),
+        };
+
+        self.popup = Some(Box::new(MessagePopup {
+            title: title.into(),
+            messages: message.into(),
+            text_align: Some(Alignment::Left),
+            ..Default::default()
+        }));
+        Ok(T::default())
+    }
+
+    /// Run the jj command of a favorite with the terminal suspended, showing its output
+    fn favorite_action(&self, favorite: &Favorite) -> ComponentAction {
+        if self.env.config.read_only() {
+            return read_only_popup();
+        }
+        let command = favorite.command.trim();
+        let command = command.strip_prefix("jj ").unwrap_or(command);
+        match shell_words::split(command) {
+            Ok(args) => ComponentAction::RunInTerminal(args),
+            Err(err) => ComponentAction::SetPopup(Some(Box::new(MessagePopup {
+                title: favorite.label.clone().into(),
+                messages: format!("Invalid command {:?}: {err}", favorite.command).into(),
+                text_align: None,
+                ..Default::default()
+            }))),
+        }
+    }
+
     #[instrument(level = "trace", skip(self, commander))]
     pub fn input(&mut self, event: Event, commander: &mut Commander) -> Result<bool> {
+        self.last_activity = Instant::now();
+        if event == event::Event::FocusGained {
+            self.focused = true;
+        }
+
         if let Some(popup) = self.popup.as_mut() {
-            match popup.input(commander, event.clone())? {
+            // Only the popup showing the failure can repeat the key
+            let mut retry_event = self.retry_event.take();
+            let input_result = popup.input(commander, event.clone()).map(Some);
+            let Some(input_result) = self.catch_command_error(input_result)? else {
+                return Ok(false);
+            };
+            match input_result {
                 ComponentInputResult::HandledAction(component_action) => {
                     self.handle_action(component_action, commander)?
                 }
@@ -225,6 +714,12 @@ impl<'a> App<'a> {
                     if let Event::Key(key) = event
                         && key.kind == event::KeyEventKind::Press
                     {
+                        if key.code == KeyCode::Char('r')
+                            && let Some(retry_event) = retry_event.take()
+                        {
+                            self.popup = None;
+                            return self.input(retry_event, commander);
+                        }
                         // Close
                         if matches!(
                             key.code,
@@ -240,13 +735,25 @@ impl<'a> App<'a> {
                     }
                 }
             };
+            if self.popup.is_some() {
+                self.retry_event = retry_event;
+            }
         } else if event == event::Event::FocusGained {
             self.get_or_init_current_tab(commander)?.focus(commander)?;
         } else {
-            match self
+            let input_result = self
                 .get_or_init_current_tab(commander)?
-                .input(commander, event.clone())?
+                .input(commander, event.clone())
+                .map(Some);
+            if let Err(err) = input_result.as_ref()
+                && let Some(CommandError::Locked(..)) = err.downcast_ref::<CommandError>()
             {
+                self.retry_event = Some(event.clone());
+            }
+            let Some(input_result) = self.catch_command_error(input_result)? else {
+                return Ok(false);
+            };
+            match input_result {
                 ComponentInputResult::HandledAction(component_action) => {
                     self.handle_action(component_action, commander)?
                 }
@@ -261,8 +768,20 @@ impl<'a> App<'a> {
                                 && (key.code == KeyCode::Char('c')))
                             || key.code == KeyCode::Esc
                         {
+                            self.cancelled = key.code == KeyCode::Char('c');
                             return Ok(true);
                         }
+                        // Favorites bar, before the tab switching ignoring the modifiers
+                        else if key.modifiers.contains(KeyModifiers::ALT)
+                            && let KeyCode::Char(digit) = key.code
+                            && let Some(favorite) = digit
+                                .to_digit(10)
+                                .and_then(|number| number.checked_sub(1))
+                                .and_then(|index| self.env.config.favorites().get(index as usize))
+                        {
+                            let action = self.favorite_action(favorite);
+                            self.handle_action(action, commander)?;
+                        }
                         //
                         // Tab switching
                         else if key.code == KeyCode::Char('l') {
@@ -284,6 +803,54 @@ impl<'a> App<'a> {
                         else if key.code == KeyCode::Char(':') {
                             self.popup = Some(Box::new(CommandPopup::new()));
                         }
+                        // Repository switcher
+                        else if key.code == KeyCode::Char('w')
+                            && key.modifiers.contains(KeyModifiers::CONTROL)
+                        {
+                            self.popup = Some(Box::new(RepositoryPopup::new(
+                                self.repository_choices(),
+                                self.env.root.clone(),
+                            )));
+                        }
+                        // Operation log maintenance
+                        else if key.code == KeyCode::Char('M') {
+                            self.popup = Some(Box::new(MaintenancePopup::new(commander)));
+                        }
+                        // Recovery of a broken working copy
+                        else if key.code == KeyCode::Char('!') {
+                            self.popup = Some(Box::new(RecoveryPopup::new()));
+                        }
+                        // Operation log with the repository as of each operation
+                        else if key.code == KeyCode::Char('O') {
+                            self.popup = Some(Box::new(OperationsPopup::new(commander)));
+                        }
+                        // Config options
+                        else if key.code == KeyCode::Char(',') {
+                            self.popup = Some(Box::new(SettingsPopup::new(commander)));
+                        }
+                        // Warning banners
+                        else if key.code == KeyCode::Char('x')
+                            && key.modifiers.contains(KeyModifiers::CONTROL)
+                        {
+                            if !self.warnings.is_empty() {
+                                let warning = self.warnings.remove(0);
+                                self.dismissed_warnings.insert(warning);
+                            }
+                        }
+                        // Native text selection of the terminal
+                        else if key.code == KeyCode::Char('t')
+                            && key.modifiers.contains(KeyModifiers::CONTROL)
+                        {
+                            self.mouse_capture = !self.mouse_capture;
+                        }
+                        // Command log
+                        else if key.code == KeyCode::Char('`') {
+                            self.show_command_log = !self.show_command_log;
+                        } else if key.code == KeyCode::Char('~') {
+                            self.popup = Some(Box::new(command_log_popup(
+                                self.env.config.output_processors(),
+                            )));
+                        }
                     }
                 }
             };
@@ -0,0 +1,155 @@
+/*!
+Backend drawing the TUI, a [CrosstermBackend] which asks the terminal for the
+cursor position on the tty instead of on stdout.
+
+The inline viewport starts at the cursor, which crossterm asks for by writing a
+query to stdout and waiting for the reply of the terminal. With
+`--pick-revision` inside `$(…)`, stdout is the pipe capturing the selection, so
+the query would end up in the captured output and never be answered. The TUI is
+drawn on stderr then, and the query is written to the tty instead.
+*/
+use std::io::{self, IsTerminal, Write};
+
+use ratatui::{
+    backend::{Backend, ClearType, CrosstermBackend, WindowSize},
+    buffer::Cell,
+    layout::{Position, Size},
+};
+
+pub struct UiBackend {
+    backend: CrosstermBackend<Box<dyn Write>>,
+}
+
+impl UiBackend {
+    pub fn new(output: Box<dyn Write>) -> Self {
+        Self {
+            backend: CrosstermBackend::new(output),
+        }
+    }
+}
+
+/// Position of the cursor, asked on the tty with the query crossterm writes to stdout
+#[cfg(unix)]
+fn tty_cursor_position() -> io::Result<Position> {
+    use std::{fs::OpenOptions, io::Read, sync::mpsc, thread, time::Duration};
+
+    let mut tty = OpenOptions::new().read(true).write(true).open("/dev/tty")?;
+    tty.write_all(b"\x1b[6n")?;
+    tty.flush()?;
+
+    // The reply is `ESC [ <row> ; <column> R`. A terminal which doesn't reply leaves the reading
+    // thread waiting, so the query gives up after 2 seconds like crossterm does
+    let (reply_tx, reply_rx) = mpsc::channel();
+    thread::spawn(move || {
+        let mut reply = vec![];
+        let mut byte = [0];
+        while tty.read(&mut byte).is_ok_and(|read| read == 1) {
+            reply.push(byte[0]);
+            if byte[0] == b'R' {
+                break;
+            }
+        }
+        let _ = reply_tx.send(reply);
+    });
+    let reply = reply_rx
+        .recv_timeout(Duration::from_secs(2))
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "No cursor position reply"))?;
+    parse_cursor_position(&reply).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "Unexpected cursor position reply {:?}",
+                String::from_utf8_lossy(&reply)
+            ),
+        )
+    })
+}
+
+#[cfg(not(unix))]
+fn tty_cursor_position() -> io::Result<Position> {
+    ratatui::crossterm::cursor::position().map(Position::from)
+}
+
+/// Zero-based position of the reply to a cursor position query, e.g. `ESC [ 5 ; 1 R` for the
+/// first column of the fifth row. Keys pressed before the reply are skipped.
+fn parse_cursor_position(reply: &[u8]) -> Option<Position> {
+    let reply = String::from_utf8_lossy(reply);
+    let start = reply.rfind("\x1b[")?;
+    let (row, column) = reply[start + 2..].strip_suffix('R')?.split_once(';')?;
+    Some(Position::new(
+        column.parse::<u16>().ok()?.checked_sub(1)?,
+        row.parse::<u16>().ok()?.checked_sub(1)?,
+    ))
+}
+
+impl Backend for UiBackend {
+    fn draw<'a, I>(&mut self, content: I) -> io::Result<()>
+    where
+        I: Iterator<Item = (u16, u16, &'a Cell)>,
+    {
+        self.backend.draw(content)
+    }
+
+    fn append_lines(&mut self, n: u16) -> io::Result<()> {
+        self.backend.append_lines(n)
+    }
+
+    fn hide_cursor(&mut self) -> io::Result<()> {
+        self.backend.hide_cursor()
+    }
+
+    fn show_cursor(&mut self) -> io::Result<()> {
+        self.backend.show_cursor()
+    }
+
+    fn get_cursor_position(&mut self) -> io::Result<Position> {
+        if io::stdout().is_terminal() {
+            self.backend.get_cursor_position()
+        } else {
+            tty_cursor_position()
+        }
+    }
+
+    fn set_cursor_position<P: Into<Position>>(&mut self, position: P) -> io::Result<()> {
+        self.backend.set_cursor_position(position)
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        self.backend.clear()
+    }
+
+    fn clear_region(&mut self, clear_type: ClearType) -> io::Result<()> {
+        self.backend.clear_region(clear_type)
+    }
+
+    fn size(&self) -> io::Result<Size> {
+        self.backend.size()
+    }
+
+    fn window_size(&mut self) -> io::Result<WindowSize> {
+        self.backend.window_size()
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Backend::flush(&mut self.backend)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cursor_position_reply() {
+        assert_eq!(
+            parse_cursor_position(b"\x1b[5;1R"),
+            Some(Position::new(0, 4))
+        );
+        assert_eq!(
+            parse_cursor_position(b"j\x1b[12;40R"),
+            Some(Position::new(39, 11))
+        );
+        assert_eq!(parse_cursor_position(b"\x1b[5R"), None);
+        assert_eq!(parse_cursor_position(b""), None);
+    }
+}
@@ -0,0 +1,54 @@
+/*!
+Copying text to the clipboard of the terminal.
+
+The text is sent with the OSC 52 escape sequence, which most terminals
+support and which also reaches the local clipboard over ssh and, with
+`set-clipboard on`, through tmux. Terminals which don't support it ignore
+the sequence, so nothing is copied then. Like the TUI, the sequence is written
+to stderr when stdout captures the selection printed on exit.
+*/
+use std::io::{self, Write};
+
+use crate::ui_output;
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let value = chunk.iter().enumerate().fold(0u32, |value, (i, byte)| {
+            value | ((*byte as u32) << (16 - 8 * i))
+        });
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(BASE64_ALPHABET[((value >> (18 - 6 * i)) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// Copy the text to the clipboard of the terminal
+pub fn copy(text: &str) -> io::Result<()> {
+    let mut output = ui_output();
+    write!(output, "\x1b]52;c;{}\x07", base64(text.as_bytes()))?;
+    output.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base64_padding() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+        assert_eq!(base64("zä".as_bytes()), "esOk");
+    }
+}
@@ -0,0 +1,219 @@
+/*!
+Batched execution mode, selected with `blazingjj.execution-mode = "batched"`.
+
+Every jj command is a new process, which snapshots the working copy before
+doing anything else. That is cheap on Linux, but on Windows and on network
+file systems the process startup and the snapshot dominate the latency of the
+UI. jj has no stable API or long lived server to talk to instead, so this mode
+amortizes the cost within a batch, which is one round of handling input and
+redrawing in the main loop:
+
+- Only the first query of a batch snapshots the working copy, the following
+  ones pass `--ignore-working-copy`
+- Identical queries within a batch are run once and their output is reused
+
+Any command which is not a known read-only query can change the repository, so
+it ends the batch. As with the [history][crate::commander::history], batches are
+kept in a global since any [Commander] can run commands, one per repository root.
+*/
+use std::{
+    collections::HashMap,
+    ffi::{OsStr, OsString},
+    sync::{LazyLock, Mutex, MutexGuard},
+};
+
+use crate::commander::{CommandError, Commander};
+
+/// Commands which only read the repository
+const QUERY_COMMANDS: [&str; 7] = ["log", "show", "diff", "status", "evolog", "root", "version"];
+
+/// Subcommands which only read the repository
+const QUERY_SUBCOMMANDS: [(&str, &str); 8] = [
+    ("file", "show"),
+    ("file", "list"),
+    ("bookmark", "list"),
+    ("config", "get"),
+    ("config", "list"),
+    ("op", "log"),
+    ("op", "show"),
+    ("workspace", "list"),
+];
+
+/// Subcommands which only read the repository when passed `--dry-run`
+const DRY_RUN_SUBCOMMANDS: [(&str, &str); 1] = [("git", "push")];
+
+pub(super) const IGNORE_WORKING_COPY: &str = "--ignore-working-copy";
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct QueryKey {
+    args: Vec<OsString>,
+    color: bool,
+    quiet: bool,
+    /// Environment variables set with [Commander::set_env], e.g. `COLUMNS` for diff tools
+    env_vars: Vec<(String, String)>,
+}
+
+#[derive(Debug, Default)]
+struct Batch {
+    /// Whether a query of this batch already snapshotted the working copy
+    snapshotted: bool,
+    outputs: HashMap<QueryKey, String>,
+}
+
+static BATCHES: LazyLock<Mutex<HashMap<String, Batch>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn batches() -> MutexGuard<'static, HashMap<String, Batch>> {
+    BATCHES
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Start a new batch for the repository at `root`, so that the next query snapshots the
+/// working copy again
+pub fn start_batch(root: &str) {
+    batches().remove(root);
+}
+
+/// Whether the command only reads the repository. Unknown commands are taken as changing it, so
+/// a command missing in the lists is refused in read-only mode rather than allowed.
+pub(super) fn is_query(args: &[OsString]) -> bool {
+    let command = args.first().and_then(|arg| arg.to_str());
+    let subcommand = args.get(1).and_then(|arg| arg.to_str());
+    match (command, subcommand) {
+        (Some(command), _) if QUERY_COMMANDS.contains(&command) => true,
+        (Some(command), Some(subcommand))
+            if DRY_RUN_SUBCOMMANDS.contains(&(command, subcommand)) =>
+        {
+            args[2..].iter().any(|arg| arg == OsStr::new("--dry-run"))
+        }
+        (Some(command), Some(subcommand)) => QUERY_SUBCOMMANDS.contains(&(command, subcommand)),
+        _ => false,
+    }
+}
+
+impl Commander {
+    /// Execute a jj command in the current batch. Queries are reused or run without
+    /// snapshotting, other commands end the batch.
+    pub(super) fn execute_batched_jj_command(
+        &self,
+        mut args: Vec<OsString>,
+        color: bool,
+        quiet: bool,
+    ) -> Result<String, CommandError> {
+        if !is_query(&args) {
+            let output = self.spawn_jj_command(args, color, quiet);
+            start_batch(&self.env.root);
+            return output;
+        }
+
+        let key = QueryKey {
+            args: args.clone(),
+            color,
+            quiet,
+            env_vars: self.env_var.lock().unwrap().clone(),
+        };
+        let snapshotted = {
+            let batches = batches();
+            let batch = batches.get(&self.env.root);
+            if let Some(output) = batch.and_then(|batch| batch.outputs.get(&key)) {
+                // The environment variables are meant for this command only
+                self.env_var.lock().unwrap().clear();
+                return Ok(output.clone());
+            }
+            batch.is_some_and(|batch| batch.snapshotted)
+        };
+
+        if snapshotted
+            && !args
+                .iter()
+                .any(|arg| arg == OsStr::new(IGNORE_WORKING_COPY))
+        {
+            args.push(IGNORE_WORKING_COPY.into());
+        }
+        let output = self.spawn_jj_command(args, color, quiet)?;
+
+        let mut batches = batches();
+        let batch = batches.entry(self.env.root.clone()).or_default();
+        batch.snapshotted = true;
+        batch.outputs.insert(key, output.clone());
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commander::tests::TestRepo;
+    use anyhow::Result;
+
+    fn args(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn queries() {
+        assert!(is_query(&args(&["log", "-r", "@"])));
+        assert!(is_query(&args(&["file", "list"])));
+        assert!(is_query(&args(&["bookmark", "list", "--all"])));
+        assert!(!is_query(&args(&["bookmark", "set", "main"])));
+        assert!(is_query(&args(&["git", "push", "--dry-run"])));
+        assert!(!is_query(&args(&["git", "push", "--dry-run=false"])));
+        assert!(!is_query(&args(&["git", "fetch", "--dry-run"])));
+        assert!(!is_query(&args(&["describe", "-m", "--dry-run"])));
+        assert!(!is_query(&args(&["describe", "-m", "log"])));
+        assert!(!is_query(&args(&[])));
+    }
+
+    /// The lists name commands of the installed jj, so renamed or removed ones are noticed
+    #[test]
+    fn query_commands_exist() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+        let commands = QUERY_COMMANDS.iter().map(|command| vec![*command]).chain(
+            QUERY_SUBCOMMANDS
+                .iter()
+                .chain(&DRY_RUN_SUBCOMMANDS)
+                .map(|(command, subcommand)| vec![*command, *subcommand]),
+        );
+        for mut command in commands {
+            command.push("--help");
+            test_repo
+                .commander
+                .execute_jj_command(command.clone(), false, true)
+                .map_err(|err| anyhow::anyhow!("jj {}: {err}", command.join(" ")))?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn batched_queries() -> Result<()> {
+        let mut test_repo = TestRepo::new()?;
+        test_repo.commander.env.config =
+            toml::from_str(r#""blazingjj.execution-mode" = "batched""#)?;
+        let root = test_repo.commander.env.root.clone();
+
+        start_batch(&root);
+        let head = test_repo.commander.get_current_head()?;
+        assert!(test_repo.commander.get_files(&head)?.is_empty());
+
+        // Within the batch, the working copy is not snapshotted again
+        std::fs::write(test_repo.directory.path().join("file"), "content")?;
+        assert!(test_repo.commander.get_files(&head)?.is_empty());
+        assert_eq!(test_repo.commander.get_current_head()?, head);
+
+        start_batch(&root);
+        let snapshotted_head = test_repo.commander.get_current_head()?;
+        assert_ne!(snapshotted_head, head);
+        assert_eq!(test_repo.commander.get_files(&snapshotted_head)?.len(), 1);
+
+        // Other commands end the batch
+        test_repo
+            .commander
+            .execute_void_jj_command(["describe", "-m", "batched"])?;
+        let described_head = test_repo.commander.get_current_head()?;
+        assert_ne!(described_head, snapshotted_head);
+        assert_eq!(described_head.change_id, snapshotted_head.change_id);
+
+        Ok(())
+    }
+}
@@ -7,7 +7,11 @@ other jj bookmark commands are defined in module [jj][super::jj].
 It is mostly used in the [bookmarks_tab][crate::ui::bookmarks_tab] module.
 */
 use crate::{
-    commander::{CommandError, Commander, RemoveEndLine, ids::ChangeId},
+    commander::{
+        ANSI_REGEX, CommandError, Commander, RemoveEndLine,
+        config::ConfigScope,
+        ids::{ChangeId, CommitId},
+    },
     env::DiffFormat,
 };
 use ansi_to_tui::IntoText;
@@ -15,8 +19,149 @@ use anyhow::Result;
 use itertools::Itertools;
 use ratatui::text::Text;
 use regex::Regex;
-use std::{fmt::Display, sync::LazyLock};
-use tracing::instrument;
+use serde::Deserialize;
+use std::{fmt::Display, process::Command, sync::LazyLock};
+use tracing::{instrument, warn};
+
+/// Maximum length of the slug of an issue title in a bookmark name
+const SLUG_MAX_LENGTH: usize = 40;
+
+/// Lowercase words of a title joined by `-`, cut after [SLUG_MAX_LENGTH] at a word boundary
+pub fn slugify(title: &str) -> String {
+    let mut slug = String::new();
+    for word in title
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+    {
+        let word = word.to_lowercase();
+        if !slug.is_empty() && slug.len() + 1 + word.len() > SLUG_MAX_LENGTH {
+            break;
+        }
+        if !slug.is_empty() {
+            slug.push('-');
+        }
+        slug.push_str(&word);
+    }
+    slug
+}
+
+/// Render a bookmark pattern like `feat/{issue}-{slug}`. Without a slug, it is left out along
+/// with the separator before it.
+pub fn render_bookmark_pattern(pattern: &str, issue: &str, slug: &str) -> String {
+    let issue = issue.trim().trim_start_matches('#');
+    let pattern = if slug.is_empty() {
+        ["-{slug}", "_{slug}", "/{slug}"]
+            .iter()
+            .fold(pattern.to_owned(), |pattern, placeholder| {
+                pattern.replace(placeholder, "")
+            })
+    } else {
+        pattern.to_owned()
+    };
+    pattern.replace("{issue}", issue).replace("{slug}", slug)
+}
+
+/// Render the description of a change from its bookmark, e.g. from `{title}`. `{bookmark}` is
+/// replaced with the bookmark name and `{title}` with its last path segment split into words,
+/// starting with an uppercase letter.
+pub fn render_bookmark_description(template: &str, bookmark: &str) -> String {
+    let name = bookmark.rsplit('/').next().unwrap_or(bookmark);
+    let words = name
+        .split(['-', '_', '.'])
+        .filter(|word| !word.is_empty())
+        .join(" ");
+    let mut chars = words.chars();
+    let title = match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    };
+    template
+        .replace("{bookmark}", bookmark)
+        .replace("{title}", &title)
+}
+
+/// Config options selecting the bookmarks which `jj new` and `jj commit` advance
+const ADVANCE_ENABLED: &str = "experimental-advance-branches.enabled-branches";
+const ADVANCE_DISABLED: &str = "experimental-advance-branches.disabled-branches";
+/// Patterns of [ADVANCE_ENABLED] before advancing was toggled off, restored when toggled on again
+const ADVANCE_SAVED: &str = "blazingjj.saved-advance-branches";
+
+/// Whether a bookmark name matches a jj string pattern like `glob:feat/*`. Patterns without a
+/// kind match exactly, as jj parses the advance options.
+pub fn bookmark_pattern_matches(pattern: &str, name: &str) -> bool {
+    let (kind, value) = pattern.split_once(':').unwrap_or(("exact", pattern));
+    match kind {
+        "exact" => name == value,
+        "substring" => name.contains(value),
+        "glob" => {
+            let regex = value
+                .chars()
+                .map(|c| match c {
+                    '*' => ".*".to_owned(),
+                    '?' => ".".to_owned(),
+                    c => regex::escape(&c.to_string()),
+                })
+                .collect::<String>();
+            Regex::new(&format!("^{regex}$")).is_ok_and(|regex| regex.is_match(name))
+        }
+        "regex" => Regex::new(value).is_ok_and(|regex| regex.is_match(name)),
+        // An unknown kind is part of the name, e.g. `a:b`
+        _ => name == pattern,
+    }
+}
+
+/// Effect of the bookmark auto-advance options on `jj new` of a revision. jj moves the
+/// bookmarks matching the options from the parents of the revision to the revision.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BookmarkAdvance {
+    /// Patterns of `experimental-advance-branches.enabled-branches`, empty if disabled
+    pub enabled: Vec<String>,
+    /// Patterns of `experimental-advance-branches.disabled-branches`
+    pub disabled: Vec<String>,
+    /// Local bookmarks on the parents of the revision
+    pub parent_bookmarks: Vec<String>,
+}
+
+impl BookmarkAdvance {
+    /// Parent bookmarks which `jj new` moves to the revision
+    pub fn advanced(&self) -> Vec<&str> {
+        self.parent_bookmarks
+            .iter()
+            .filter(|name| {
+                self.enabled
+                    .iter()
+                    .any(|pattern| bookmark_pattern_matches(pattern, name))
+                    && !self
+                        .disabled
+                        .iter()
+                        .any(|pattern| bookmark_pattern_matches(pattern, name))
+            })
+            .map(String::as_str)
+            .collect()
+    }
+}
+
+/// A mutable change without description which has local bookmarks
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UndescribedChange {
+    pub change_id: ChangeId,
+    pub bookmarks: Vec<String>,
+}
+
+/// A change of the stack of the working copy without a local bookmark, with the name generated
+/// for it from `blazingjj.bookmark-template`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnbookmarkedChange {
+    pub change_id: ChangeId,
+    /// First line of the description
+    pub title: String,
+    pub name: String,
+}
+
+/// Mutable ancestors of the working copy without a local bookmark, leaving out an empty working
+/// copy without description
+const UNBOOKMARKED_STACK_REVSET: &str =
+    r#"(::@ & mutable()) ~ bookmarks() ~ (@ & empty() & description(exact:""))"#;
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Bookmark {
@@ -39,6 +184,18 @@ impl Display for Bookmark {
 
 // Template which outputs `[name@remote]`. Used to parse data from bookmark list
 const BRANCH_TEMPLATE: &str = r#""[" ++ name ++ "@" ++ remote ++ "|" ++ present ++ "|" ++ self.normal_target().committer().timestamp().format("%s") ++ "]""#;
+// Template which outputs the colored line of a bookmark in the list. Unlike the builtin
+// template, it doesn't count the commits of tracked remote bookmarks ahead and behind.
+const BRANCH_LINE_TEMPLATE: &str = r#"label(if(remote, "remote_bookmark", "bookmark"), name ++ if(remote, "@" ++ remote))
+  ++ if(conflict,
+    " " ++ label("conflict", "(conflicted)"),
+    if(normal_target,
+      ": " ++ label("change_id", normal_target.change_id().shortest(8))
+        ++ " " ++ label("commit_id", normal_target.commit_id().shortest(8))
+        ++ " " ++ if(normal_target.description(),
+          normal_target.description().first_line(),
+          label("description placeholder", "(no description set)")),
+      " " ++ label("deleted", "(deleted)")))"#;
 // Regex to parse bookmark
 static BRANCH_REGEX: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"^\[(.*)@(.*)\|(true|false)\|(\d+)\]$").unwrap());
@@ -70,6 +227,44 @@ fn parse_bookmark(text: &str) -> Option<Bookmark> {
     })
 }
 
+/// Line of a bookmark in the list, rendered by [BRANCH_LINE_TEMPLATE] after the fields of
+/// [BRANCH_TEMPLATE] and a tab
+fn parse_bookmark_line(line: &str) -> BookmarkLine {
+    match line.split_once('\t') {
+        Some((fields, text)) => match parse_bookmark(&ANSI_REGEX.replace_all(fields, "")) {
+            Some(bookmark) => BookmarkLine::Parsed {
+                text: text.to_owned(),
+                bookmark,
+            },
+            None => BookmarkLine::Unparsable(line.to_owned()),
+        },
+        None => BookmarkLine::Unparsable(line.to_owned()),
+    }
+}
+
+/// Commits a tracked remote bookmark is ahead and behind of the local bookmark
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BookmarkTracking {
+    pub remote: String,
+    pub ahead: u64,
+    pub behind: u64,
+}
+
+impl Display for BookmarkTracking {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.ahead, self.behind) {
+            (0, 0) => write!(f, "@{}: in sync", self.remote),
+            (ahead, 0) => write!(f, "@{}: ahead by {ahead} commits", self.remote),
+            (0, behind) => write!(f, "@{}: behind by {behind} commits", self.remote),
+            (ahead, behind) => write!(
+                f,
+                "@{}: ahead by {ahead} commits, behind by {behind} commits",
+                self.remote
+            ),
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub enum BookmarkLine {
     Unparsable(String),
@@ -88,64 +283,77 @@ impl BookmarkLine {
 impl Commander {
     /// Get bookmarks.
     /// Maps to `jj bookmark list`
+    ///
+    /// A single template renders the parsed fields and the colored line of each bookmark. The
+    /// builtin template of jj also counts the commits each tracked remote bookmark is ahead and
+    /// behind, which is slow with thousands of bookmarks, see [Commander::get_bookmark_tracking].
     #[instrument(level = "trace", skip(self))]
     pub fn get_bookmarks(&self, show_all: bool) -> Result<Vec<BookmarkLine>, CommandError> {
-        let mut args = vec![];
+        let mut args = vec![
+            "bookmark".to_owned(),
+            "list".to_owned(),
+            "-T".to_owned(),
+            format!(r#"{BRANCH_TEMPLATE} ++ "\t" ++ {BRANCH_LINE_TEMPLATE} ++ "\n""#),
+            "--sort".to_owned(),
+            "committer-date-".to_owned(),
+        ];
         if show_all {
-            args.push("--all-remotes");
+            args.push("--all-remotes".to_owned());
         }
-        args.push("--sort");
-        args.push("committer-date-");
-        let bookmarks_colored = self.execute_jj_command(
-            [
-                vec![
-                    "bookmark",
-                    "list",
-                    "--config",
-                    // Override format_ref_targets to not list conflicts
-                    r#"template-aliases.'format_ref_targets(ref)'='''
-                        if(ref.conflict(),
-                          " " ++ label("conflict", "(conflicted)"),
-                          ": " ++ format_commit_summary_with_refs(ref.normal_target(), ""),
-                        )
-                    '''"#,
-                ],
-                args.clone(),
-            ]
-            .concat(),
-            true,
-            true,
-        )?;
 
         let bookmarks: Vec<BookmarkLine> = self
-            .execute_jj_command(
-                [
-                    vec![
-                        "bookmark",
-                        "list",
-                        "-T",
-                        &format!(r#"{BRANCH_TEMPLATE} ++ "\n""#),
-                    ],
-                    args,
-                ]
-                .concat(),
-                false,
-                true,
-            )?
+            .execute_jj_command(args, true, true)?
             .lines()
-            .zip(bookmarks_colored.lines())
-            .map(|(line, line_colored)| match parse_bookmark(line) {
-                Some(bookmark) => BookmarkLine::Parsed {
-                    text: line_colored.to_owned(),
-                    bookmark,
-                },
-                None => BookmarkLine::Unparsable(line_colored.to_owned()),
-            })
+            .map(parse_bookmark_line)
             .collect();
 
         Ok(bookmarks)
     }
 
+    /// Commits the remote bookmarks tracked by the bookmark are ahead and behind of it, or of
+    /// the local bookmark for a remote bookmark. Only computed for the selected bookmark, as it
+    /// walks the history of each one.
+    /// Maps to `jj bookmark list --all-remotes exact:<name>`
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_bookmark_tracking(
+        &self,
+        bookmark: &Bookmark,
+    ) -> Result<Vec<BookmarkTracking>, CommandError> {
+        let pattern = format!("exact:{}", bookmark.name);
+        let output = self.execute_jj_command(
+            vec![
+                "bookmark",
+                "list",
+                "--all-remotes",
+                "--ignore-working-copy",
+                &pattern,
+                "-T",
+                r#"if(remote && tracked, remote ++ " " ++ tracking_ahead_count.lower()
+                    ++ " " ++ tracking_behind_count.lower() ++ "\n")"#,
+            ],
+            false,
+            true,
+        )?;
+        Ok(output
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split(' ');
+                Some(BookmarkTracking {
+                    remote: fields.next()?.to_owned(),
+                    ahead: fields.next()?.parse().ok()?,
+                    behind: fields.next()?.parse().ok()?,
+                })
+            })
+            .filter(|tracking| tracking.remote != "git")
+            .filter(|tracking| {
+                bookmark
+                    .remote
+                    .as_ref()
+                    .is_none_or(|remote| *remote == tracking.remote)
+            })
+            .collect())
+    }
+
     #[instrument(level = "trace", skip(self))]
     pub fn get_bookmarks_list(&self, show_all: bool) -> Result<Vec<Bookmark>, CommandError> {
         let mut args = vec![
@@ -168,6 +376,31 @@ impl Commander {
         Ok(bookmarks)
     }
 
+    /// Remotes on which the bookmark is tracked, without the `git` pseudo remote of colocated
+    /// repositories. Maps to `jj bookmark list --tracked exact:<name>`
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_tracked_remotes(&self, name: &str) -> Result<Vec<String>, CommandError> {
+        let pattern = format!("exact:{name}");
+        Ok(self
+            .execute_jj_command(
+                vec![
+                    "bookmark",
+                    "list",
+                    "--tracked",
+                    &pattern,
+                    "-T",
+                    r#"if(remote, remote ++ "\n")"#,
+                ],
+                false,
+                true,
+            )?
+            .lines()
+            .filter(|remote| !remote.is_empty() && *remote != "git")
+            .map(str::to_owned)
+            .unique()
+            .collect())
+    }
+
     /// Get bookmark details.
     /// Maps to `jj show <bookmark>`
     #[instrument(level = "trace", skip(self))]
@@ -202,6 +435,187 @@ impl Commander {
             false,
         )
     }
+
+    /// Get the mutable changes without description which have local bookmarks, e.g. after
+    /// importing git branches
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_undescribed_bookmarked_changes(
+        &self,
+    ) -> Result<Vec<UndescribedChange>, CommandError> {
+        let output = self.execute_jj_command(
+            [
+                "log",
+                "--no-graph",
+                "-r",
+                r#"bookmarks() & description(exact:"") & mutable()"#,
+                "--template",
+                r#"change_id ++ "\t" ++ local_bookmarks.map(|b| b.name()).join(" ") ++ "\n""#,
+            ],
+            false,
+            true,
+        )?;
+        Ok(output
+            .lines()
+            .filter_map(|line| {
+                let (change_id, bookmarks) = line.split_once('\t')?;
+                Some(UndescribedChange {
+                    change_id: ChangeId(change_id.to_owned()),
+                    bookmarks: bookmarks.split_whitespace().map(String::from).collect(),
+                })
+            })
+            .filter(|change| !change.bookmarks.is_empty())
+            .collect())
+    }
+
+    /// Get the changes of the stack of `@` without a local bookmark, from the top, with the
+    /// names `blazingjj.bookmark-template` generates for them
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_unbookmarked_stack(&self) -> Result<Vec<UnbookmarkedChange>, CommandError> {
+        let template = format!(
+            r#"change_id ++ "\t" ++ description.first_line() ++ "\t" ++ ({}) ++ "\n""#,
+            self.env.config.bookmark_template()
+        );
+        let output = self.execute_jj_command(
+            [
+                "log",
+                "--no-graph",
+                "-r",
+                UNBOOKMARKED_STACK_REVSET,
+                "--template",
+                &template,
+            ],
+            false,
+            true,
+        )?;
+        Ok(output
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.splitn(3, '\t');
+                Some(UnbookmarkedChange {
+                    change_id: ChangeId(fields.next()?.to_owned()),
+                    title: fields.next()?.to_owned(),
+                    name: fields.next()?.trim().to_owned(),
+                })
+            })
+            .collect())
+    }
+
+    /// Create a bookmark for each of the changes, returning the number of bookmarks created.
+    /// Maps to `jj bookmark create <name> -r <change>` per change
+    #[instrument(level = "trace", skip(self))]
+    pub fn create_stack_bookmarks(
+        &self,
+        changes: &[UnbookmarkedChange],
+    ) -> Result<String, CommandError> {
+        for change in changes {
+            self.execute_void_jj_command([
+                "bookmark",
+                "create",
+                &change.name,
+                "-r",
+                change.change_id.as_str(),
+            ])?;
+        }
+        Ok(format!("Created {} bookmarks", changes.len()))
+    }
+
+    /// Patterns of a list config option, empty if it is not set
+    fn get_config_patterns(&self, name: &str) -> Result<Vec<String>, CommandError> {
+        #[derive(Deserialize)]
+        struct Patterns {
+            patterns: Vec<String>,
+        }
+
+        // `jj config get` refuses arrays, `jj config list` prints each option as TOML
+        let Some((_, value)) = self
+            .get_config_list()?
+            .into_iter()
+            .rfind(|(option, _)| option == name)
+        else {
+            return Ok(vec![]);
+        };
+        toml::from_str::<Patterns>(&format!("patterns = {value}"))
+            .map(|parsed| parsed.patterns)
+            .map_err(|err| CommandError::Status(format!("Invalid {name}: {err}"), None))
+    }
+
+    /// Which bookmarks `jj new <revision>` would advance to the revision
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_bookmark_advance(
+        &self,
+        commit_id: &CommitId,
+    ) -> Result<BookmarkAdvance, CommandError> {
+        let output = self.execute_jj_command(
+            [
+                "log",
+                "--no-graph",
+                "-r",
+                &format!("parents({})", commit_id.as_str()),
+                "--template",
+                r#"local_bookmarks.map(|b| b.name() ++ "\n").join("")"#,
+            ],
+            false,
+            true,
+        )?;
+        self.bookmark_advance(output.lines().map(String::from).unique().collect())
+    }
+
+    /// Which of the local bookmarks of the parents of a revision `jj new <revision>` would
+    /// advance, according to the auto-advance options
+    pub fn bookmark_advance(
+        &self,
+        parent_bookmarks: Vec<String>,
+    ) -> Result<BookmarkAdvance, CommandError> {
+        Ok(BookmarkAdvance {
+            enabled: self.get_config_patterns(ADVANCE_ENABLED)?,
+            disabled: self.get_config_patterns(ADVANCE_DISABLED)?,
+            parent_bookmarks,
+        })
+    }
+
+    /// Disable advancing in the repo config if it is enabled, keeping the patterns in
+    /// `blazingjj.saved-advance-branches`. Otherwise enable it again with the kept patterns, or
+    /// for all bookmarks without them. Returns whether it is enabled now.
+    /// Maps to `jj config set --repo experimental-advance-branches.enabled-branches <patterns>`
+    #[instrument(level = "trace", skip(self))]
+    pub fn toggle_bookmark_advance(&self) -> Result<bool, CommandError> {
+        let patterns = self.get_config_patterns(ADVANCE_ENABLED)?;
+        if !patterns.is_empty() {
+            let patterns = toml::Value::from(patterns).to_string();
+            self.set_config_value(ConfigScope::Repo, ADVANCE_SAVED, &patterns)?;
+            self.set_config_value(ConfigScope::Repo, ADVANCE_ENABLED, "[]")?;
+            return Ok(false);
+        }
+
+        let mut patterns = self.get_config_patterns(ADVANCE_SAVED)?;
+        if patterns.is_empty() {
+            patterns.push("glob:*".to_owned());
+        }
+        let patterns = toml::Value::from(patterns).to_string();
+        self.set_config_value(ConfigScope::Repo, ADVANCE_ENABLED, &patterns)?;
+        Ok(true)
+    }
+
+    /// Bookmark name for an issue from `pattern`. If the pattern contains `{slug}`, the issue
+    /// title is fetched with `gh issue view <issue>`. Without a title, the slug is left out.
+    #[instrument(level = "trace", skip(self))]
+    pub fn issue_bookmark_name(&self, pattern: &str, issue: &str) -> String {
+        let issue = issue.trim().trim_start_matches('#');
+        let slug = if pattern.contains("{slug}") {
+            self.execute_command(
+                Command::new("gh")
+                    .args(["issue", "view", issue, "--json", "title", "--jq", ".title"]),
+            )
+            .map(|title| slugify(&title))
+            .unwrap_or_else(|err| {
+                warn!("Failed getting the title of issue {issue}: {err}");
+                String::new()
+            })
+        } else {
+            String::new()
+        };
+        render_bookmark_pattern(pattern, issue, &slug)
+    }
 }
 
 #[cfg(test)]
@@ -270,6 +684,132 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn bookmark_lines() {
+        let line =
+            "\x1b[38;5;5m[main@|true|1700000000]\x1b[39m\t\x1b[38;5;5mmain\x1b[39m: kk 12 Fix";
+        match parse_bookmark_line(line) {
+            BookmarkLine::Parsed { text, bookmark } => {
+                assert_eq!(text, "\x1b[38;5;5mmain\x1b[39m: kk 12 Fix");
+                assert_eq!(bookmark.name, "main");
+                assert_eq!(bookmark.remote, None);
+                assert_eq!(bookmark.timestamp, 1700000000);
+            }
+            BookmarkLine::Unparsable(line) => panic!("unparsable: {line}"),
+        }
+        assert!(matches!(
+            parse_bookmark_line("Error: no tab"),
+            BookmarkLine::Unparsable(_)
+        ));
+
+        let tracking = BookmarkTracking {
+            remote: "origin".to_owned(),
+            ahead: 2,
+            behind: 0,
+        };
+        assert_eq!(tracking.to_string(), "@origin: ahead by 2 commits");
+    }
+
+    #[test]
+    fn get_bookmark_tracking() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+
+        let bookmark = test_repo.commander.create_bookmark("test")?;
+        assert!(
+            test_repo
+                .commander
+                .get_bookmark_tracking(&bookmark)?
+                .is_empty()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn issue_bookmark_names() {
+        assert_eq!(
+            slugify("Crash when the log is empty (again!)"),
+            "crash-when-the-log-is-empty-again"
+        );
+        assert_eq!(
+            slugify("A very long title which does not fit into a bookmark name"),
+            "a-very-long-title-which-does-not-fit"
+        );
+        assert_eq!(
+            render_bookmark_pattern("feat/{issue}-{slug}", "#42", "empty-log"),
+            "feat/42-empty-log"
+        );
+        assert_eq!(
+            render_bookmark_pattern("feat/{issue}-{slug}", "42", ""),
+            "feat/42"
+        );
+    }
+
+    #[test]
+    fn bookmark_descriptions() {
+        assert_eq!(
+            render_bookmark_description("{title}", "feat/add-login_page"),
+            "Add login page"
+        );
+        assert_eq!(
+            render_bookmark_description("{title} ({bookmark})", "fix.crash"),
+            "Fix crash (fix.crash)"
+        );
+        assert_eq!(render_bookmark_description("{title}", "feat/"), "");
+    }
+
+    #[test]
+    fn get_undescribed_bookmarked_changes() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+        assert!(
+            test_repo
+                .commander
+                .get_undescribed_bookmarked_changes()?
+                .is_empty()
+        );
+
+        test_repo.commander.create_bookmark("feat/login")?;
+        let changes = test_repo.commander.get_undescribed_bookmarked_changes()?;
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].bookmarks, ["feat/login"]);
+
+        test_repo
+            .commander
+            .run_describe(changes[0].change_id.as_str(), "Login")?;
+        assert!(
+            test_repo
+                .commander
+                .get_undescribed_bookmarked_changes()?
+                .is_empty()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn bookmark_stack() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+        let commander = &test_repo.commander;
+        commander.run_describe("@", "first")?;
+        commander.execute_void_jj_command(["new", "-m", "second"])?;
+        commander.execute_void_jj_command(["bookmark", "create", "second", "-r", "@"])?;
+        commander.execute_void_jj_command(["new", "-m", "third"])?;
+        commander.execute_void_jj_command(["new"])?;
+
+        let changes = commander.get_unbookmarked_stack()?;
+        let titles: Vec<&str> = changes.iter().map(|change| change.title.as_str()).collect();
+        assert_eq!(titles, ["third", "first"]);
+        for change in &changes {
+            let short_id = change.name.strip_prefix("push-").unwrap();
+            assert!(change.change_id.as_str().starts_with(short_id));
+        }
+
+        commander.create_stack_bookmarks(&changes)?;
+        assert!(commander.get_unbookmarked_stack()?.is_empty());
+
+        Ok(())
+    }
+
     #[test]
     fn get_bookmark_show() -> Result<()> {
         let test_repo = TestRepo::new()?;
@@ -290,4 +830,56 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn bookmark_pattern_matching() {
+        assert!(bookmark_pattern_matches("main", "main"));
+        assert!(!bookmark_pattern_matches("main", "main-2"));
+        assert!(bookmark_pattern_matches("exact:main", "main"));
+        assert!(bookmark_pattern_matches("glob:*", "feat/a"));
+        assert!(bookmark_pattern_matches("glob:feat/?", "feat/a"));
+        assert!(!bookmark_pattern_matches("glob:feat/*", "fix/a"));
+        assert!(bookmark_pattern_matches("substring:ai", "main"));
+        assert!(bookmark_pattern_matches("regex:^ma", "main"));
+
+        let advance = BookmarkAdvance {
+            enabled: vec!["glob:*".to_owned()],
+            disabled: vec!["main".to_owned()],
+            parent_bookmarks: vec!["main".to_owned(), "feat".to_owned()],
+        };
+        assert_eq!(advance.advanced(), ["feat"]);
+    }
+
+    #[test]
+    fn toggle_bookmark_advance() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+        test_repo.commander.create_bookmark("test")?;
+        test_repo.commander.execute_void_jj_command(["new"])?;
+        let head = test_repo.commander.get_current_head()?;
+
+        let advance = test_repo.commander.get_bookmark_advance(&head.commit_id)?;
+        assert_eq!(advance.parent_bookmarks, ["test"]);
+        assert!(advance.advanced().is_empty());
+
+        assert!(test_repo.commander.toggle_bookmark_advance()?);
+        let advance = test_repo.commander.get_bookmark_advance(&head.commit_id)?;
+        assert_eq!(advance.advanced(), ["test"]);
+
+        assert!(!test_repo.commander.toggle_bookmark_advance()?);
+        let advance = test_repo.commander.get_bookmark_advance(&head.commit_id)?;
+        assert!(advance.advanced().is_empty());
+
+        // Patterns of the user are restored when advancing is enabled again
+        test_repo.commander.set_config_value(
+            ConfigScope::Repo,
+            ADVANCE_ENABLED,
+            r#"["glob:feat/*", "main"]"#,
+        )?;
+        assert!(!test_repo.commander.toggle_bookmark_advance()?);
+        assert!(test_repo.commander.toggle_bookmark_advance()?);
+        let advance = test_repo.commander.get_bookmark_advance(&head.commit_id)?;
+        assert_eq!(advance.enabled, ["glob:feat/*", "main"]);
+
+        Ok(())
+    }
 }
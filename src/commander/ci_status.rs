@@ -0,0 +1,182 @@
+/*!
+[Commander] member functions querying the CI status of commits with the
+command of `blazingjj.ci-status-command`, shown as a badge per change in the
+[log_panel][crate::ui::panel::log_panel].
+
+The command runs in the repository root with `BLAZINGJJ_COMMIT` set to the
+commit id and prints the status, e.g. with the CLI of the forge:
+`gh run list --commit $BLAZINGJJ_COMMIT --json conclusion --jq '.[0].conclusion'`.
+It is much slower than jj, so it runs in a background thread for the changes
+on screen only. Successful and failed statuses are final, pending ones and
+commits without a run are queried again after [STATUS_TTL], once CI runs may
+have finished or started.
+*/
+use std::{
+    collections::{HashMap, HashSet},
+    sync::mpsc::{Receiver, Sender, channel},
+    thread,
+    time::{Duration, Instant},
+};
+
+use tracing::{instrument, warn};
+
+use crate::commander::{CommandError, Commander, ids::CommitId, shell_command};
+
+/// Time after which the status of a commit without a final status is queried again
+const STATUS_TTL: Duration = Duration::from_secs(60);
+
+/// Status of the CI runs of a commit
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CiStatus {
+    Success,
+    Failure,
+    Pending,
+}
+
+impl CiStatus {
+    /// Status from the first word of the output of the command, `None` if there is no run or
+    /// the word isn't known
+    pub fn parse(output: &str) -> Option<Self> {
+        let word = output.split_whitespace().next()?.to_lowercase();
+        match word.as_str() {
+            "success" | "succeeded" | "passed" | "pass" | "ok" | "green" => Some(CiStatus::Success),
+            "failure" | "failed" | "fail" | "error" | "cancelled" | "timed_out" | "red" => {
+                Some(CiStatus::Failure)
+            }
+            "pending" | "running" | "queued" | "in_progress" | "waiting" | "requested"
+            | "yellow" => Some(CiStatus::Pending),
+            _ => None,
+        }
+    }
+
+    /// Whether the CI runs finished, so the status doesn't change anymore
+    pub fn is_final(&self) -> bool {
+        matches!(self, CiStatus::Success | CiStatus::Failure)
+    }
+}
+
+impl Commander {
+    /// CI status of a commit, `None` if `blazingjj.ci-status-command` isn't set or doesn't know
+    /// the commit
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_ci_status(&self, commit_id: &CommitId) -> Result<Option<CiStatus>, CommandError> {
+        let Some(ci_status_command) = self.env.config.ci_status_command() else {
+            return Ok(None);
+        };
+        let mut command = shell_command(ci_status_command);
+        command
+            .env("BLAZINGJJ_REPO_ROOT", &self.env.root)
+            .env("BLAZINGJJ_COMMIT", commit_id.as_str());
+        Ok(CiStatus::parse(&self.execute_command(&mut command)?))
+    }
+}
+
+/// Status of a commit queried in the background
+type Queried = (CommitId, Option<CiStatus>);
+
+/// CI statuses of the commits in the log, queried in a background thread
+pub struct CiStatusCache {
+    /// Statuses by commit, with the time they were queried
+    statuses: HashMap<CommitId, (Instant, Option<CiStatus>)>,
+    /// Commits being queried
+    pending: HashSet<CommitId>,
+    tx: Sender<Queried>,
+    rx: Receiver<Queried>,
+}
+
+impl Default for CiStatusCache {
+    fn default() -> Self {
+        let (tx, rx) = channel();
+        Self {
+            statuses: HashMap::new(),
+            pending: HashSet::new(),
+            tx,
+            rx,
+        }
+    }
+}
+
+impl CiStatusCache {
+    pub fn get(&self, commit_id: &CommitId) -> Option<CiStatus> {
+        self.statuses.get(commit_id).and_then(|(_, status)| *status)
+    }
+
+    /// Move the queried statuses into the cache
+    pub fn receive(&mut self) {
+        while let Ok((commit_id, status)) = self.rx.try_recv() {
+            self.pending.remove(&commit_id);
+            self.statuses.insert(commit_id, (Instant::now(), status));
+        }
+    }
+
+    /// Query the status of the commits which are neither cached nor already being queried in a
+    /// background thread, in the order given. Final statuses are never queried again.
+    pub fn prefetch<'a>(
+        &mut self,
+        commander: &Commander,
+        commit_ids: impl IntoIterator<Item = &'a CommitId>,
+    ) {
+        self.receive();
+        let mut commit_ids: Vec<CommitId> = commit_ids
+            .into_iter()
+            .filter(|commit_id| {
+                !self.pending.contains(*commit_id)
+                    && self
+                        .statuses
+                        .get(*commit_id)
+                        .is_none_or(|(queried, status)| {
+                            !status.is_some_and(|status| status.is_final())
+                                && queried.elapsed() > STATUS_TTL
+                        })
+            })
+            .cloned()
+            .collect();
+        // A change can span several lines of the log
+        commit_ids.dedup();
+        if commit_ids.is_empty() {
+            return;
+        }
+        self.pending.extend(commit_ids.iter().cloned());
+
+        let commander = commander.background();
+        let tx = self.tx.clone();
+        thread::spawn(move || {
+            for commit_id in commit_ids {
+                let status = commander.get_ci_status(&commit_id).unwrap_or_else(|err| {
+                    warn!(
+                        "Failed getting the CI status of {}: {err}",
+                        commit_id.as_str()
+                    );
+                    None
+                });
+                if tx.send((commit_id, status)).is_err() {
+                    return;
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_ci_status() {
+        assert_eq!(CiStatus::parse("success\n"), Some(CiStatus::Success));
+        assert_eq!(CiStatus::parse("FAILURE"), Some(CiStatus::Failure));
+        assert_eq!(
+            CiStatus::parse("in_progress 3/5 jobs"),
+            Some(CiStatus::Pending)
+        );
+        assert_eq!(CiStatus::parse(""), None);
+        assert_eq!(CiStatus::parse("skipped"), None);
+    }
+
+    #[test]
+    fn final_statuses() {
+        assert!(CiStatus::Success.is_final());
+        assert!(CiStatus::Failure.is_final());
+        assert!(!CiStatus::Pending.is_final());
+    }
+}
@@ -0,0 +1,179 @@
+/*!
+Reading and writing jj config options, used by the
+[settings_popup][crate::ui::settings_popup].
+*/
+use tracing::instrument;
+
+use crate::commander::{CommandError, Commander, RemoveEndLine};
+
+/// Config file written by `jj config set`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConfigScope {
+    User,
+    Repo,
+}
+
+impl ConfigScope {
+    pub fn flag(&self) -> &'static str {
+        match self {
+            ConfigScope::User => "--user",
+            ConfigScope::Repo => "--repo",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ConfigScope::User => "user",
+            ConfigScope::Repo => "repo",
+        }
+    }
+
+    pub fn toggle(&self) -> Self {
+        match self {
+            ConfigScope::User => ConfigScope::Repo,
+            ConfigScope::Repo => ConfigScope::User,
+        }
+    }
+}
+
+/// A TOML value of `jj config list` as `jj config get` prints it: strings without quotes,
+/// everything else as is
+fn unquote_config_value(value: &str) -> String {
+    match toml::from_str::<toml::Table>(&format!("value = {value}"))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+    {
+        Some(toml::Value::String(value)) => value,
+        _ => value.to_owned(),
+    }
+}
+
+impl Commander {
+    /// Get the effective value of a config option, `None` if it is not set.
+    /// Maps to `jj config get <name>`
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_config_value(&self, name: &str) -> Result<Option<String>, CommandError> {
+        match self.execute_jj_command(vec!["config", "get", name], false, true) {
+            Ok(value) => Ok(Some(value.remove_end_line())),
+            // jj fails for options which are not set
+            Err(CommandError::Status(..)) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Get the effective config options as `(name, value)`, with the value as TOML, e.g. `"vim"`
+    /// or `["a", "b"]`. Maps to `jj config list`
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_config_list(&self) -> Result<Vec<(String, String)>, CommandError> {
+        // Without a name, as listing an option which isn't set prints a warning
+        let output = self.execute_jj_command(
+            [
+                "config",
+                "list",
+                "--template",
+                r#"name ++ " = " ++ value ++ "\n""#,
+            ],
+            false,
+            true,
+        )?;
+        Ok(output
+            .lines()
+            .filter_map(|line| line.split_once(" = "))
+            .map(|(name, value)| (name.to_owned(), value.to_owned()))
+            .collect())
+    }
+
+    /// Get the effective values of several config options with a single command, `None` for
+    /// options which are not set. Strings are unquoted like `jj config get` prints them.
+    /// Maps to `jj config list`
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_config_values(&self, names: &[&str]) -> Result<Vec<Option<String>>, CommandError> {
+        let list = self.get_config_list()?;
+        Ok(names
+            .iter()
+            .map(|name| {
+                list.iter()
+                    .rev()
+                    .find(|(option, _)| option == name)
+                    .map(|(_, value)| unquote_config_value(value))
+            })
+            .collect())
+    }
+
+    /// Set a config option in the config file of the scope. Values which are valid TOML, e.g.
+    /// `true` or `["a", "b"]`, are written as such, anything else as a string.
+    /// Maps to `jj config set --user|--repo <name> <value>`
+    #[instrument(level = "trace", skip(self))]
+    pub fn set_config_value(
+        &self,
+        scope: ConfigScope,
+        name: &str,
+        value: &str,
+    ) -> Result<(), CommandError> {
+        self.execute_void_jj_command(vec!["config", "set", scope.flag(), name, value])
+    }
+
+    /// Remove a config option from the config file of the scope.
+    /// Maps to `jj config unset --user|--repo <name>`
+    #[instrument(level = "trace", skip(self))]
+    pub fn unset_config_value(&self, scope: ConfigScope, name: &str) -> Result<(), CommandError> {
+        self.execute_void_jj_command(vec!["config", "unset", scope.flag(), name])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commander::tests::TestRepo;
+    use anyhow::Result;
+
+    #[test]
+    fn set_config_value() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+
+        assert_eq!(
+            test_repo.commander.get_config_value("blazingjj.layout")?,
+            None
+        );
+
+        test_repo
+            .commander
+            .set_config_value(ConfigScope::Repo, "blazingjj.layout", "vertical")?;
+        assert_eq!(
+            test_repo.commander.get_config_value("blazingjj.layout")?,
+            Some("vertical".to_owned())
+        );
+
+        test_repo
+            .commander
+            .unset_config_value(ConfigScope::Repo, "blazingjj.layout")?;
+        assert_eq!(
+            test_repo.commander.get_config_value("blazingjj.layout")?,
+            None
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn get_config_values() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+
+        test_repo
+            .commander
+            .set_config_value(ConfigScope::Repo, "blazingjj.layout", "vertical")?;
+        test_repo
+            .commander
+            .set_config_value(ConfigScope::Repo, "blazingjj.hint-bar", "true")?;
+        assert_eq!(
+            test_repo.commander.get_config_values(&[
+                "blazingjj.layout",
+                "blazingjj.hint-bar",
+                "blazingjj.diff-tool"
+            ])?,
+            vec![Some("vertical".to_owned()), Some("true".to_owned()), None]
+        );
+
+        Ok(())
+    }
+}
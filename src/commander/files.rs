@@ -4,10 +4,15 @@
 This module has features to parse the diff output.
 It is mostly used in the [files_tab][crate::ui::files_tab] module.
 */
-use std::sync::LazyLock;
+use std::{borrow::Cow, collections::HashMap, path::Path, process::Command, sync::LazyLock};
 
 use crate::{
-    commander::{CommandError, Commander, ids::CommitId, log::Head},
+    commander::{
+        ANSI_REGEX, CommandError, Commander,
+        ids::CommitId,
+        log::{HEAD_TEMPLATE, Head, parse_head},
+        stream::StreamingOutput,
+    },
     env::DiffFormat,
 };
 
@@ -16,11 +21,108 @@ use ratatui::style::Color;
 use regex::Regex;
 use tracing::instrument;
 
+/// Path with `/` as separator, as used in filesets and `.gitignore`. jj prints paths with the
+/// separator of the platform, which is `\` on Windows. Elsewhere, `\` is a valid character of a
+/// file name and kept.
+pub fn slash_path(path: &str) -> Cow<'_, str> {
+    if cfg!(windows) {
+        Cow::Owned(path.replace('\\', "/"))
+    } else {
+        Cow::Borrowed(path)
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct File {
     pub line: String,
     pub path: Option<String>,
     pub diff_type: Option<DiffType>,
+    /// Percentage of lines the old and new file of a rename have in common, set by
+    /// [Commander::detect_renames]
+    pub similarity: Option<u8>,
+}
+
+impl File {
+    /// A rename found by [Commander::detect_renames]
+    pub fn renamed(old: &str, new: &str, similarity: u8) -> Self {
+        Self {
+            line: format!("R {old} → {new} (similarity {similarity}%)"),
+            path: Some(format!("{{{old} => {new}}}")),
+            diff_type: Some(DiffType::Renamed),
+            similarity: Some(similarity),
+        }
+    }
+
+    /// Path of the file after the change, used to get its diff
+    pub fn diff_path(&self) -> Option<&str> {
+        let path = self.path.as_ref()?;
+        if let (true, Some(captures)) = (
+            self.diff_type == Some(DiffType::Renamed),
+            RENAME_REGEX.captures(path),
+        ) {
+            Some(captures.get(2)?.as_str())
+        } else {
+            Some(path)
+        }
+    }
+
+    /// Paths touched by the change to the file, the old and the new path for renamed files
+    pub fn paths(&self) -> Vec<String> {
+        let Some(path) = self.path.as_ref() else {
+            return vec![];
+        };
+        if self.diff_type == Some(DiffType::Renamed) && RENAME_REGEX.is_match(path) {
+            vec![
+                RENAME_REGEX.replace(path, "$1").into_owned(),
+                RENAME_REGEX.replace(path, "$2").into_owned(),
+            ]
+        } else {
+            vec![path.clone()]
+        }
+    }
+}
+
+/// Lines added and removed in a file
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FileStat {
+    pub added: usize,
+    pub removed: usize,
+}
+
+impl FileStat {
+    /// Number of changed lines
+    pub fn churn(&self) -> usize {
+        self.added + self.removed
+    }
+}
+
+/// Width of the diff stat, so that jj doesn't scale down the bars of `+` and `-` of a file unless
+/// it changes a huge number of lines
+const STAT_WIDTH: usize = 100_000;
+
+/// Stats of the files by path from the output of a diff stat like `src/main.rs | 12 +++++-------`.
+/// If jj scaled down the bars, the lines are split in their proportion.
+fn parse_file_stats(output: &str) -> HashMap<String, FileStat> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (path, stat) = line.rsplit_once(" | ")?;
+            let (total, bars) = stat.trim().split_once(' ').unwrap_or((stat.trim(), ""));
+            let total: usize = total.parse().ok()?;
+            let plus = bars.chars().filter(|c| *c == '+').count();
+            let minus = bars.chars().filter(|c| *c == '-').count();
+            let added = (total * plus + (plus + minus) / 2)
+                .checked_div(plus + minus)
+                .unwrap_or(0);
+            Some((
+                path.trim().to_owned(),
+                FileStat {
+                    added,
+                    removed: total - added,
+                },
+            ))
+        })
+        .collect()
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -29,6 +131,10 @@ pub enum DiffType {
     Modified,
     Deleted,
     Renamed,
+    /// New file in the working copy which is not tracked, with `snapshot.auto-track` off
+    Untracked,
+    /// File or directory in the working copy which is ignored by git
+    Ignored,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -43,6 +149,7 @@ impl DiffType {
             "M" => Some(DiffType::Modified),
             "D" => Some(DiffType::Deleted),
             "R" => Some(DiffType::Renamed),
+            "?" => Some(DiffType::Untracked),
             _ => None,
         }
     }
@@ -53,6 +160,8 @@ impl DiffType {
             DiffType::Modified => Color::Cyan,
             DiffType::Renamed => Color::Cyan,
             DiffType::Deleted => Color::Red,
+            DiffType::Untracked => Color::Magenta,
+            DiffType::Ignored => Color::DarkGray,
         }
     }
 }
@@ -61,6 +170,68 @@ impl DiffType {
 static FILES_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(.) (.*)").unwrap());
 static RENAME_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\{(.*?) => (.*?)\}").unwrap());
 static CONFLICTS_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(.*)    .*").unwrap());
+/// Line starting the diff of a file, in the git or the color-words format
+static DIFF_HEADER_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(?:diff --git a/.* b/(.+)|(?:Added|Modified|Removed) .*?(?:file|symlink|conflict|submodule) (.+?)(?: \(.* => .*\))?:)$",
+    )
+    .unwrap()
+});
+
+/// Line and path of each file section of the diff of a change, by the path after the change. The
+/// sections of external diff tools, whose headers vary, aren't found.
+pub fn diff_sections(diff: &str) -> Vec<(usize, String)> {
+    diff.lines()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            let line = ANSI_REGEX.replace_all(line, "");
+            let captures = DIFF_HEADER_REGEX.captures(&line)?;
+            let path = captures.get(1).or_else(|| captures.get(2))?;
+            Some((index, path.as_str().to_owned()))
+        })
+        .collect()
+}
+
+/// Index of the line of a file diff showing line `line` of the new version of the file, in the
+/// git or the color-words format
+pub fn diff_line_index(diff: &str, line: usize) -> Option<usize> {
+    // Line number of the next line of the new version in a hunk of the git format
+    let mut hunk_line: Option<usize> = None;
+    for (index, diff_line) in diff.lines().enumerate() {
+        let diff_line = ANSI_REGEX.replace_all(diff_line, "");
+        if let Some(hunk) = diff_line.strip_prefix("@@ ") {
+            // `@@ -1,5 +1,6 @@`, the new version starts at line 1
+            hunk_line = hunk
+                .split_whitespace()
+                .find_map(|range| range.strip_prefix('+'))
+                .and_then(|range| range.split(',').next())
+                .and_then(|start| start.parse().ok());
+        } else if let Some(current) = hunk_line {
+            if diff_line.starts_with('+') || diff_line.starts_with(' ') {
+                if current == line {
+                    return Some(index);
+                }
+                hunk_line = Some(current + 1);
+            } else if !diff_line.starts_with('-') && !diff_line.starts_with('\\') {
+                // The header of the next file
+                hunk_line = None;
+            }
+        } else if let Some((numbers, _)) = diff_line.split_once(": ")
+            // `   3    4: text`, with the line of the new version right before the colon, which
+            // is missing for removed lines
+            && numbers.ends_with(|c: char| c.is_ascii_digit())
+            && numbers.chars().all(|c| c.is_ascii_digit() || c == ' ')
+            && numbers
+                .split_whitespace()
+                .next_back()
+                .and_then(|number| number.parse::<usize>().ok())
+                == Some(line)
+        {
+            return Some(index);
+        }
+    }
+    None
+}
 
 impl Commander {
     /// Get list of changes files in a change. Parses the output.
@@ -89,11 +260,116 @@ impl Commander {
                     line: line.to_string(),
                     path,
                     diff_type,
+                    similarity: None,
                 }
             })
             .collect())
     }
 
+    /// Changes of a revset, oldest first, with the paths of their changed files. Used to review a
+    /// range of changes one by one.
+    /// Maps to `jj log --reversed -r <revset> --summary`
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_range_files(&self, revset: &str) -> Result<Vec<(Head, Vec<String>)>> {
+        let output = self.execute_jj_command(
+            vec![
+                "log",
+                "--no-graph",
+                "--reversed",
+                "-r",
+                revset,
+                "-T",
+                &format!(r#"{HEAD_TEMPLATE} ++ "\n""#),
+                "--summary",
+            ],
+            false,
+            true,
+        )?;
+        let mut changes: Vec<(Head, Vec<String>)> = vec![];
+        for line in output.lines() {
+            // The summary lines start with the type of the change
+            if line.starts_with('[') {
+                changes.push((parse_head(line)?, vec![]));
+            } else if let Some((_, paths)) = changes.last_mut()
+                && let Some(path) = FILES_REGEX
+                    .captures(line)
+                    .and_then(|captured| captured.get(2))
+            {
+                paths.push(path.as_str().to_owned());
+            }
+        }
+        Ok(changes)
+    }
+
+    /// Lines added and removed in each file of a change, by the path shown by
+    /// [Commander::get_files]. Binary files have no stats.
+    /// Maps to `jj log -r <revision> -T 'self.diff().stat()'`
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_file_stats(&self, head: &Head) -> Result<HashMap<String, FileStat>, CommandError> {
+        let template = format!("self.diff().stat({STAT_WIDTH})");
+        let output = self.execute_jj_command(
+            vec![
+                "log",
+                "--no-graph",
+                "--ignore-working-copy",
+                "-r",
+                head.commit_id.as_str(),
+                "-T",
+                &template,
+            ],
+            false,
+            true,
+        )?;
+        Ok(parse_file_stats(&output))
+    }
+
+    /// Get list of untracked files in the working copy, from the "Untracked paths" section of
+    /// `jj status`. Parses the output.
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_untracked_files(&self) -> Result<Vec<File>, CommandError> {
+        Ok(self
+            .execute_jj_command(vec!["status"], false, true)?
+            .lines()
+            .skip_while(|line| *line != "Untracked paths:")
+            .skip(1)
+            .map_while(|line| line.strip_prefix("? "))
+            .map(|path| File {
+                line: format!("? {path}"),
+                path: Some(path.to_owned()),
+                diff_type: Some(DiffType::Untracked),
+                similarity: None,
+            })
+            .collect())
+    }
+
+    /// Get list of files ignored by git in the working copy, ignored directories as a whole. Only
+    /// colocated repositories have a git working tree to list them, others have none.
+    /// Maps to `git status --ignored`
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_ignored_files(&self) -> Result<Vec<File>, CommandError> {
+        if !Path::new(&self.env.root).join(".git").exists() {
+            return Ok(vec![]);
+        }
+        Ok(self
+            .execute_command(
+                Command::new("git")
+                    // Don't refresh the index, which jj may be writing at the same time
+                    .env("GIT_OPTIONAL_LOCKS", "0")
+                    .args(["status", "--porcelain", "-z", "--ignored"]),
+            )?
+            .split('\0')
+            .filter_map(|entry| entry.strip_prefix("!! "))
+            // The store of jj is ignored in colocated repositories
+            .filter(|path| *path != ".jj/")
+            .map(|path| File {
+                line: format!("! {path}"),
+                path: Some(path.trim_end_matches('/').to_owned()),
+                diff_type: Some(DiffType::Ignored),
+                similarity: None,
+            })
+            .collect())
+    }
+
     /// Get list of changes files in a change. Parses the output.
     /// Maps to `jj diff --summary -r <revision>`
     #[instrument(level = "trace", skip(self))]
@@ -125,7 +401,25 @@ impl Commander {
         }
     }
 
-    /// Get diff for file change in a change.
+    /// Arguments of `jj diff` for a file change, `None` if the file has no path
+    fn file_diff_args(
+        head: &Head,
+        current_file: &File,
+        diff_format: &DiffFormat,
+        ignore_working_copy: bool,
+    ) -> Option<Vec<String>> {
+        let path = current_file.diff_path()?;
+        let fileset = Self::get_file_revset(path);
+        let mut args = vec!["diff", "-r", head.commit_id.as_str(), &fileset];
+        args.append(&mut diff_format.get_args());
+        if ignore_working_copy {
+            args.push("--ignore-working-copy");
+        }
+        Some(args.into_iter().map(String::from).collect())
+    }
+
+    /// Get diff for file change in a change. The git format of renames shows only the changed
+    /// lines, see [Commander::get_rename_diff].
     /// Maps to `jj diff -r <revision> <path>`
     #[instrument(level = "trace", skip(self))]
     pub fn get_file_diff(
@@ -135,30 +429,48 @@ impl Commander {
         diff_format: &DiffFormat,
         ignore_working_copy: bool,
     ) -> Result<Option<String>, CommandError> {
-        let Some(path) = current_file.path.as_ref() else {
+        if *diff_format == DiffFormat::Git && current_file.similarity.is_some() {
+            return self.get_rename_diff(head, current_file);
+        }
+        let Some(args) = Self::file_diff_args(head, current_file, diff_format, ignore_working_copy)
+        else {
             return Ok(None);
         };
+        self.execute_jj_command(args, true, true).map(Some)
+    }
 
-        let path = if let (true, Some(captures)) = (
-            current_file.diff_type == Some(DiffType::Renamed),
-            RENAME_REGEX.captures(path),
-        ) {
-            match captures.get(2) {
-                Some(path) => path.as_str(),
-                None => return Ok(None),
-            }
-        } else {
-            path
+    /// Like [Commander::get_file_diff], but streams the output. Used for external diff tools,
+    /// which can be slow on large files.
+    #[instrument(level = "trace", skip(self))]
+    pub fn stream_file_diff(
+        &self,
+        head: &Head,
+        current_file: &File,
+        diff_format: &DiffFormat,
+    ) -> Result<Option<StreamingOutput>, CommandError> {
+        let Some(args) = Self::file_diff_args(head, current_file, diff_format, true) else {
+            return Ok(None);
         };
+        self.execute_streaming_jj_command(args, true).map(Some)
+    }
 
-        let fileset = Self::get_file_revset(path);
-        let mut args = vec!["diff", "-r", head.commit_id.as_str(), &fileset];
+    /// Diff of all files of a change, streamed like [Commander::stream_file_diff]. Use
+    /// [diff_sections] to find the files in it.
+    /// Maps to `jj diff -r <revision>`
+    #[instrument(level = "trace", skip(self))]
+    pub fn stream_change_diff(
+        &self,
+        head: &Head,
+        diff_format: &DiffFormat,
+    ) -> Result<StreamingOutput, CommandError> {
+        let mut args = vec![
+            "diff",
+            "-r",
+            head.commit_id.as_str(),
+            "--ignore-working-copy",
+        ];
         args.append(&mut diff_format.get_args());
-        if ignore_working_copy {
-            args.push("--ignore-working-copy");
-        }
-
-        self.execute_jj_command(args, true, true).map(Some)
+        self.execute_streaming_jj_command(args, true)
     }
 
     #[instrument(level = "trace", skip(self))]
@@ -186,6 +498,18 @@ impl Commander {
         )?))
     }
 
+    /// Start tracking an untracked file, or an ignored one with `include_ignored`.
+    /// Maps to `jj file track [--include-ignored] <path>`
+    #[instrument(level = "trace", skip(self))]
+    pub fn track_file(&self, path: &str, include_ignored: bool) -> Result<(), CommandError> {
+        let fileset = Self::get_file_revset(path);
+        let mut args = vec!["file", "track", &fileset];
+        if include_ignored {
+            args.push("--include-ignored");
+        }
+        self.execute_void_jj_command(args)
+    }
+
     #[instrument(level = "trace", skip(self))]
     pub fn restore_file(&self, current_file: &File) -> Result<Option<String>, CommandError> {
         let Some(path) = current_file.path.as_ref() else {
@@ -211,10 +535,10 @@ impl Commander {
         )?))
     }
 
-    fn get_file_revset(path: &str) -> String {
+    pub(super) fn get_file_revset(path: &str) -> String {
         format!(
             "file:\"{}\"",
-            path.replace("\\", "\\\\").replace('"', "\\\"")
+            slash_path(path).replace("\\", "\\\\").replace('"', "\\\"")
         )
     }
 }
@@ -226,6 +550,204 @@ mod tests {
     use insta::assert_debug_snapshot;
     use std::fs;
 
+    #[test]
+    fn diff_line_index_by_format() {
+        let git = "\
+diff --git a/a.txt b/a.txt
+index 1111111..2222222 100644
+--- a/a.txt
++++ b/a.txt
+@@ -1,3 +1,3 @@
+ one
+-two
++\x1b[32mTODO: two\x1b[0m
+ three
+";
+        assert_eq!(diff_line_index(git, 1), Some(5));
+        assert_eq!(diff_line_index(git, 2), Some(7));
+        assert_eq!(diff_line_index(git, 3), Some(8));
+        assert_eq!(diff_line_index(git, 4), None);
+
+        let color_words = "\
+Modified regular file a.txt:
+   1    1: one
+   2     : two
+        2: TODO: two
+   3    3: three
+";
+        assert_eq!(diff_line_index(color_words, 1), Some(1));
+        assert_eq!(diff_line_index(color_words, 2), Some(3));
+        assert_eq!(diff_line_index(color_words, 3), Some(4));
+    }
+
+    #[test]
+    fn file_stats() -> Result<()> {
+        let stats = parse_file_stats(
+            "Cargo.lock                   | 8000 ++++++++++++++++++++++++++++++------------\n\
+             src/{old.rs => new.rs}       |    3 ++-\n\
+             image.png                    | (binary)\n\
+             3 files changed, 5602 insertions(+), 2401 deletions(-)\n",
+        );
+        assert_eq!(
+            stats.get("Cargo.lock"),
+            Some(&FileStat {
+                added: 5714,
+                removed: 2286
+            })
+        );
+        assert_eq!(
+            stats.get("src/{old.rs => new.rs}"),
+            Some(&FileStat {
+                added: 2,
+                removed: 1
+            })
+        );
+        assert_eq!(stats.len(), 2);
+
+        let test_repo = TestRepo::new()?;
+        fs::write(test_repo.directory.path().join("README"), b"a\nb\nc\n")?;
+        let head = test_repo.commander.get_current_head()?;
+        let stats = test_repo.commander.get_file_stats(&head)?;
+        assert_eq!(
+            stats.get("README"),
+            Some(&FileStat {
+                added: 3,
+                removed: 0
+            })
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn file_sections_of_diff() {
+        let diff = "\x1b[1mModified regular file src/main.rs:\x1b[0m\n   1    1: fn main() {}\n\
+                    Added regular file new file.txt:\n        1: text\n\
+                    Modified regular file b.rs (a.rs => b.rs):\n\
+                    diff --git a/README b/README2\nrename from README\nrename to README2\n\
+                    Removed regular file old.txt:\n";
+        assert_eq!(
+            diff_sections(diff),
+            [
+                (0, "src/main.rs".to_owned()),
+                (2, "new file.txt".to_owned()),
+                (4, "b.rs".to_owned()),
+                (5, "README2".to_owned()),
+                (8, "old.txt".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn slash_paths() {
+        if cfg!(windows) {
+            assert_eq!(slash_path(r"src\ui\mod.rs"), "src/ui/mod.rs");
+        } else {
+            assert_eq!(slash_path(r"odd\name"), r"odd\name");
+        }
+        assert_eq!(slash_path("src/ui/mod.rs"), "src/ui/mod.rs");
+    }
+
+    #[test]
+    fn ignored_files() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+        let directory = test_repo.directory.path();
+        fs::write(directory.join(".gitignore"), b"ignored\nbuild/\n")?;
+        fs::write(directory.join("ignored"), b"AAA")?;
+        fs::create_dir(directory.join("build"))?;
+        fs::write(directory.join("build").join("output"), b"AAA")?;
+
+        let paths = |files: Vec<File>| -> Vec<(String, Option<String>)> {
+            files
+                .into_iter()
+                .map(|file| (file.line, file.path))
+                .collect()
+        };
+        assert_eq!(
+            paths(test_repo.commander.get_ignored_files()?),
+            [
+                ("! build/".to_owned(), Some("build".to_owned())),
+                ("! ignored".to_owned(), Some("ignored".to_owned())),
+            ]
+        );
+
+        test_repo.commander.track_file("ignored", true)?;
+        let head = test_repo.commander.get_current_head()?;
+        assert!(
+            test_repo
+                .commander
+                .get_files(&head)?
+                .iter()
+                .any(|file| file.path.as_deref() == Some("ignored"))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn untracked_files() -> Result<()> {
+        let mut test_repo = TestRepo::new()?;
+        if let Some(jj_config_toml) = test_repo.commander.jj_config_toml.as_mut() {
+            jj_config_toml.push(r#"snapshot.auto-track="none()""#.to_owned());
+        }
+        fs::write(test_repo.directory.path().join("new file"), b"AAA")?;
+
+        let untracked = test_repo.commander.get_untracked_files()?;
+        assert_eq!(
+            untracked,
+            vec![File {
+                line: "? new file".to_owned(),
+                path: Some("new file".to_owned()),
+                diff_type: Some(DiffType::Untracked),
+                similarity: None,
+            }]
+        );
+
+        test_repo.commander.track_file("new file", false)?;
+        assert_eq!(test_repo.commander.get_untracked_files()?, vec![]);
+        let head = test_repo.commander.get_current_head()?;
+        let files = test_repo.commander.get_files(&head)?;
+        assert_eq!(files[0].path.as_deref(), Some("new file"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn get_range_files() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+        fs::write(test_repo.directory.path().join("a"), b"a")?;
+        test_repo.commander.execute_void_jj_command(vec!["new"])?;
+        fs::write(test_repo.directory.path().join("a"), b"b")?;
+        fs::write(test_repo.directory.path().join("b"), b"c")?;
+        test_repo.commander.execute_void_jj_command(vec!["new"])?;
+
+        let changes = test_repo.commander.get_range_files("root()..@")?;
+        let paths: Vec<&[String]> = changes.iter().map(|(_, paths)| paths.as_slice()).collect();
+        assert_eq!(paths, [&["a"][..], &["a", "b"], &[]]);
+        assert_eq!(changes[2].0, test_repo.commander.get_current_head()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn renamed_file_paths() {
+        let file = File {
+            line: "R src/{old.rs => new.rs}".to_owned(),
+            path: Some("src/{old.rs => new.rs}".to_owned()),
+            diff_type: Some(DiffType::Renamed),
+            similarity: None,
+        };
+        assert_eq!(file.paths(), ["src/old.rs", "src/new.rs"]);
+
+        let file = File {
+            line: "M README".to_owned(),
+            path: Some("README".to_owned()),
+            diff_type: Some(DiffType::Modified),
+            similarity: None,
+        };
+        assert_eq!(file.paths(), ["README"]);
+    }
+
     #[test]
     fn get_files() -> Result<()> {
         let test_repo = TestRepo::new()?;
@@ -250,6 +772,7 @@ mod tests {
                     line: "A README".to_owned(),
                     path: Some("README".to_owned(),),
                     diff_type: Some(DiffType::Added,),
+                    similarity: None,
                 },]
             );
         }
@@ -268,7 +791,8 @@ mod tests {
                 vec![File {
                     line: "M README".to_owned(),
                     path: Some("README".to_owned()),
-                    diff_type: Some(DiffType::Modified)
+                    diff_type: Some(DiffType::Modified),
+                    similarity: None,
                 },]
             );
         }
@@ -284,7 +808,8 @@ mod tests {
                 vec![File {
                     line: "D README".to_owned(),
                     path: Some("README".to_owned()),
-                    diff_type: Some(DiffType::Deleted)
+                    diff_type: Some(DiffType::Deleted),
+                    similarity: None,
                 },]
             );
         }
@@ -304,6 +829,7 @@ mod tests {
             let file = File {
                 path: Some("README".to_string()),
                 diff_type: Some(DiffType::Added),
+                similarity: None,
                 line: "A README".to_string(),
             };
 
@@ -331,6 +857,7 @@ mod tests {
             let file = File {
                 path: Some("README".to_string()),
                 diff_type: Some(DiffType::Modified),
+                similarity: None,
                 line: "M README".to_string(),
             };
 
@@ -361,6 +888,7 @@ mod tests {
             let file = File {
                 path: Some("{README => README2}".to_string()),
                 diff_type: Some(DiffType::Renamed),
+                similarity: None,
                 line: "R {README => README2}".to_string(),
             };
 
@@ -388,6 +916,7 @@ mod tests {
             let file = File {
                 path: Some("README2".to_string()),
                 diff_type: Some(DiffType::Deleted),
+                similarity: None,
                 line: "D README2".to_string(),
             };
 
@@ -0,0 +1,280 @@
+/*!
+[Commander] member functions linking changes and files to their page on the
+forge hosting the repository.
+
+The web URL of the repository is derived from the URL of the `origin` remote
+(or the first remote), and the page URL is built from a template for the
+kind of forge, which can be overridden with `blazingjj.forge-commit-url` and
+`blazingjj.forge-file-url` for self-hosted forges.
+*/
+use std::{
+    process::{Command, Stdio},
+    thread,
+};
+
+use tracing::{instrument, warn};
+
+use crate::commander::{CommandError, Commander, hide_console_window, ids::CommitId};
+
+/// Kind of forge, guessed from the host of the remote
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Forge {
+    GitHub,
+    GitLab,
+    /// Gitea and its fork Forgejo, e.g. Codeberg
+    Gitea,
+}
+
+impl Forge {
+    fn from_host(host: &str) -> Self {
+        if host.contains("gitlab") {
+            Forge::GitLab
+        } else if ["gitea", "forgejo", "codeberg"]
+            .iter()
+            .any(|name| host.contains(name))
+        {
+            Forge::Gitea
+        } else {
+            Forge::GitHub
+        }
+    }
+
+    fn commit_url_template(&self) -> &'static str {
+        match self {
+            Forge::GitHub | Forge::Gitea => "{repo}/commit/{commit}",
+            Forge::GitLab => "{repo}/-/commit/{commit}",
+        }
+    }
+
+    fn file_url_template(&self) -> &'static str {
+        match self {
+            Forge::GitHub => "{repo}/blob/{commit}/{path}",
+            Forge::GitLab => "{repo}/-/blob/{commit}/{path}",
+            Forge::Gitea => "{repo}/src/commit/{commit}/{path}",
+        }
+    }
+}
+
+/// Web URL of a repository from its git remote URL, e.g. `https://github.com/owner/repo` for
+/// `git@github.com:owner/repo.git`. None for local remotes.
+fn repository_url(remote_url: &str) -> Option<(String, String)> {
+    let remote_url = remote_url.trim();
+    let (scheme, host, path) = if let Some((scheme, rest)) = remote_url.split_once("://") {
+        let (authority, path) = rest.split_once('/')?;
+        let host = authority
+            .rsplit_once('@')
+            .map_or(authority, |(_, host)| host);
+        // The port of ssh is not the port of the web server
+        let host = match scheme {
+            "http" | "https" => host,
+            _ => host.split_once(':').map_or(host, |(host, _)| host),
+        };
+        let scheme = if scheme == "http" { "http" } else { "https" };
+        (scheme, host, path)
+    } else {
+        // scp-like syntax, `[user@]host:path`
+        let (authority, path) = remote_url.split_once(':')?;
+        // Local paths, including Windows drive letters
+        if authority.contains('/') || authority.len() == 1 {
+            return None;
+        }
+        let host = authority
+            .rsplit_once('@')
+            .map_or(authority, |(_, host)| host);
+        ("https", host, path)
+    };
+    if host.is_empty() {
+        return None;
+    }
+    let path = path.trim_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+    if path.is_empty() {
+        return None;
+    }
+    Some((host.to_owned(), format!("{scheme}://{host}/{path}")))
+}
+
+/// Percent-encode a path for a URL, keeping the `/` separators
+fn encode_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+/// Fill the `{repo}`, `{commit}` and `{path}` placeholders of a URL template
+fn expand_url_template(template: &str, repo: &str, commit: &CommitId, path: &str) -> String {
+    template
+        .replace("{repo}", repo)
+        .replace("{commit}", commit.as_str())
+        .replace("{path}", &encode_path(path))
+}
+
+impl Commander {
+    /// Host and web URL of the repository, from the `origin` remote or else the first remote
+    fn get_forge_repository(&self) -> Result<(String, String), CommandError> {
+        let remotes = self.get_git_remotes()?;
+        let (name, url) = remotes
+            .iter()
+            .find(|(name, _)| name == "origin")
+            .or(remotes.first())
+            .ok_or_else(|| {
+                CommandError::Status("The repository has no git remote".to_owned(), None)
+            })?;
+        repository_url(url).ok_or_else(|| {
+            CommandError::Status(
+                format!("The URL of the remote {name} is not on a forge: {url}"),
+                None,
+            )
+        })
+    }
+
+    /// URL of the page of a commit on the forge
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_forge_commit_url(&self, commit: &CommitId) -> Result<String, CommandError> {
+        let (host, repo) = self.get_forge_repository()?;
+        let template = self
+            .env
+            .config
+            .forge_commit_url()
+            .unwrap_or(Forge::from_host(&host).commit_url_template());
+        Ok(expand_url_template(template, &repo, commit, ""))
+    }
+
+    /// URL of the page of a file at a commit on the forge
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_forge_file_url(
+        &self,
+        commit: &CommitId,
+        path: &str,
+    ) -> Result<String, CommandError> {
+        let (host, repo) = self.get_forge_repository()?;
+        let template = self
+            .env
+            .config
+            .forge_file_url()
+            .unwrap_or(Forge::from_host(&host).file_url_template());
+        Ok(expand_url_template(template, &repo, commit, path))
+    }
+
+    /// Open a URL with the opener of the platform, without waiting for the browser
+    #[instrument(level = "trace", skip(self))]
+    pub fn open_in_browser(&self, url: &str) -> Result<(), CommandError> {
+        let mut command = if cfg!(windows) {
+            let mut command = Command::new("cmd");
+            // The empty argument is the window title of `start`
+            command.args(["/C", "start", ""]);
+            command
+        } else if cfg!(target_os = "macos") {
+            Command::new("open")
+        } else {
+            Command::new("xdg-open")
+        };
+        let mut child = hide_console_window(&mut command)
+            .arg(url)
+            .current_dir(&self.env.root)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+        thread::spawn(move || {
+            if let Err(err) = child.wait() {
+                warn!("Failed waiting for the browser opener: {err}");
+            }
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commander::tests::TestRepo;
+    use anyhow::Result;
+
+    #[test]
+    fn repository_urls() {
+        for (remote, expected) in [
+            (
+                "git@github.com:owner/repo.git",
+                Some("https://github.com/owner/repo"),
+            ),
+            (
+                "https://user@gitlab.com/group/sub/repo.git",
+                Some("https://gitlab.com/group/sub/repo"),
+            ),
+            (
+                "ssh://git@codeberg.org:2222/owner/repo",
+                Some("https://codeberg.org/owner/repo"),
+            ),
+            (
+                "http://git.local:3000/owner/repo/",
+                Some("http://git.local:3000/owner/repo"),
+            ),
+            ("/srv/git/repo.git", None),
+            (r"C:\src\repo", None),
+            ("file:///srv/git/repo.git", None),
+        ] {
+            assert_eq!(
+                repository_url(remote).map(|(_, url)| url).as_deref(),
+                expected,
+                "{remote}"
+            );
+        }
+    }
+
+    #[test]
+    fn forge_urls() {
+        let commit = CommitId("abc123".to_owned());
+        for (host, expected) in [
+            ("github.com", "https://x/r/blob/abc123/src/a%20b.rs"),
+            (
+                "gitlab.example.com",
+                "https://x/r/-/blob/abc123/src/a%20b.rs",
+            ),
+            ("codeberg.org", "https://x/r/src/commit/abc123/src/a%20b.rs"),
+        ] {
+            assert_eq!(
+                expand_url_template(
+                    Forge::from_host(host).file_url_template(),
+                    "https://x/r",
+                    &commit,
+                    "src/a b.rs"
+                ),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn get_forge_commit_url() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+        assert!(
+            test_repo
+                .commander
+                .get_forge_commit_url(&CommitId("abc123".to_owned()))
+                .is_err()
+        );
+
+        test_repo.commander.execute_void_jj_command([
+            "git",
+            "remote",
+            "add",
+            "origin",
+            "git@github.com:owner/repo.git",
+        ])?;
+        assert_eq!(
+            test_repo
+                .commander
+                .get_forge_commit_url(&CommitId("abc123".to_owned()))?,
+            "https://github.com/owner/repo/commit/abc123"
+        );
+        Ok(())
+    }
+}
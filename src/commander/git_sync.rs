@@ -0,0 +1,311 @@
+/*!
+[Commander] member functions syncing jj with its git repository by hand.
+
+jj imports and exports git refs itself with every command in a colocated
+repository, but tools changing the git repository directly, like the git
+integration of an IDE, can leave them out of sync until the next jj command.
+`jj git import` and `jj git export` only print what failed, so the refs are
+listed before and after to summarize what changed.
+
+`jj git fetch` has no dry run, so [Commander::git_fetch_dry_run] compares the
+branches of the remotes with their remote bookmarks in jj instead.
+*/
+use std::{collections::BTreeMap, fmt::Write, process::Command};
+
+use tracing::instrument;
+
+use crate::commander::{CommandError, Commander};
+
+/// Template listing each bookmark as `<name>[@<remote>] <commit id>`
+const BOOKMARK_TARGET_TEMPLATE: &str = r#"name ++ if(remote, "@" ++ remote) ++ " "
+  ++ if(conflict, "(conflicted)", if(normal_target, normal_target.commit_id().short(12), "(deleted)"))
+  ++ "\n""#;
+
+/// Length of the commit ids in [BOOKMARK_TARGET_TEMPLATE]
+const SHORT_ID_LENGTH: usize = 12;
+
+/// Commit of each ref, by ref name
+type Refs = BTreeMap<String, String>;
+
+fn parse_refs(output: &str) -> Refs {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (name, target) = line.rsplit_once(' ')?;
+            Some((name.to_owned(), target.to_owned()))
+        })
+        .collect()
+}
+
+/// Lines listing the added (`+`), moved (`~`) and removed (`-`) refs
+fn ref_changes(before: &Refs, after: &Refs) -> Vec<String> {
+    let mut changes = vec![];
+    for (name, target) in after {
+        match before.get(name) {
+            None => changes.push(format!("+ {name} {target}")),
+            Some(old) if old != target => changes.push(format!("~ {name} {old} → {target}")),
+            Some(_) => {}
+        }
+    }
+    for name in before.keys().filter(|name| !after.contains_key(*name)) {
+        changes.push(format!("- {name}"));
+    }
+    changes
+}
+
+/// Branches of a `git ls-remote --heads` output as `<name>@<remote>`, with their short commit ids
+fn parse_ls_remote(output: &str, remote: &str) -> Refs {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (commit_id, name) = line.split_once('\t')?;
+            let name = name.strip_prefix("refs/heads/")?;
+            let short_id = commit_id.get(..SHORT_ID_LENGTH).unwrap_or(commit_id);
+            Some((format!("{name}@{remote}"), short_id.to_owned()))
+        })
+        .collect()
+}
+
+/// Remotes `jj git fetch` fetches from: those of `git.fetch`, given as the TOML value of
+/// `jj config get`, else the only remote or `origin`
+fn default_fetch_remotes(git_fetch: Option<&str>, remotes: &[String]) -> Vec<String> {
+    match git_fetch.map(|value| toml::from_str::<toml::Table>(&format!("value = {value}"))) {
+        Some(Ok(mut table)) => match table.remove("value") {
+            Some(toml::Value::Array(values)) => values
+                .into_iter()
+                .filter_map(|value| value.as_str().map(String::from))
+                .collect(),
+            Some(toml::Value::String(value)) => vec![value],
+            _ => vec![],
+        },
+        // Strings are printed without quotes
+        Some(Err(_)) => git_fetch.into_iter().map(String::from).collect(),
+        None if remotes.len() == 1 => remotes.to_vec(),
+        None => vec!["origin".to_owned()],
+    }
+}
+
+/// Summary of a sync with the changed refs, followed by what jj printed
+fn summary(title: &str, changes: Result<Vec<String>, CommandError>, output: &str) -> String {
+    let mut summary = String::new();
+    match changes {
+        Ok(changes) if changes.is_empty() => summary.push_str("Nothing changed\n"),
+        Ok(changes) => {
+            let _ = writeln!(summary, "{title}:");
+            for change in changes {
+                let _ = writeln!(summary, "  {change}");
+            }
+        }
+        Err(err) => {
+            let _ = writeln!(summary, "Could not list the changed refs: {err}");
+        }
+    }
+    if !output.trim().is_empty() {
+        let _ = write!(summary, "\n{}", output.trim_end());
+    }
+    summary
+}
+
+impl Commander {
+    /// Local and remote bookmarks of jj with their commits
+    fn get_bookmark_targets(&self) -> Result<Refs, CommandError> {
+        let output = self.execute_jj_command(
+            vec![
+                "bookmark",
+                "list",
+                "--all-remotes",
+                "--ignore-working-copy",
+                "-T",
+                BOOKMARK_TARGET_TEMPLATE,
+            ],
+            false,
+            true,
+        )?;
+        Ok(parse_refs(&output))
+    }
+
+    /// Branches, remote branches and tags of the colocated git repository with their commits.
+    /// Maps to `git for-each-ref`
+    fn get_git_refs(&self) -> Result<Refs, CommandError> {
+        let output = self.execute_command(Command::new("git").args([
+            "for-each-ref",
+            "--format=%(refname:short) %(objectname:short)",
+            "refs/heads",
+            "refs/remotes",
+            "refs/tags",
+        ]))?;
+        Ok(parse_refs(&output))
+    }
+
+    /// Import the refs of the git repository into jj, summarizing the changed bookmarks.
+    /// Maps to `jj git import`
+    #[instrument(level = "trace", skip(self))]
+    pub fn git_import(&self) -> Result<String, CommandError> {
+        let before = self.get_bookmark_targets();
+        let output = self.execute_jj_command_stderr(vec!["git", "import"])?;
+        let changes = before.and_then(|before| {
+            let after = self.get_bookmark_targets()?;
+            Ok(ref_changes(&before, &after))
+        });
+        Ok(summary("Bookmarks changed in jj", changes, &output))
+    }
+
+    /// Export the bookmarks of jj to the git repository, summarizing the changed git refs.
+    /// Maps to `jj git export`
+    #[instrument(level = "trace", skip(self))]
+    pub fn git_export(&self) -> Result<String, CommandError> {
+        let before = self.get_git_refs();
+        let output = self.execute_jj_command_stderr(vec!["git", "export"])?;
+        let changes = before.and_then(|before| {
+            let after = self.get_git_refs()?;
+            Ok(ref_changes(&before, &after))
+        });
+        Ok(summary("Refs changed in git", changes, &output))
+    }
+
+    /// Summarize the remote bookmarks a git fetch would add, move or delete, without changing
+    /// anything. The bookmarks of the remotes are compared with the branches listed by
+    /// `git ls-remote --heads <url>`, ignoring `remotes.<name>.fetch-bookmarks`
+    #[instrument(level = "trace", skip(self))]
+    pub fn git_fetch_dry_run(&self, all_remotes: bool) -> Result<String, CommandError> {
+        let urls = self.get_git_remotes()?;
+        let names: Vec<String> = urls.iter().map(|(name, _)| name.clone()).collect();
+        let remotes = if all_remotes {
+            names
+        } else {
+            default_fetch_remotes(self.get_config_value("git.fetch")?.as_deref(), &names)
+        };
+
+        let bookmarks = self.get_bookmark_targets()?;
+        let mut before = Refs::new();
+        let mut after = Refs::new();
+        for remote in &remotes {
+            let Some((_, url)) = urls.iter().find(|(name, _)| name == remote) else {
+                return Err(CommandError::Status(
+                    format!("No git remote named '{remote}'"),
+                    None,
+                ));
+            };
+            let suffix = format!("@{remote}");
+            before.extend(
+                bookmarks
+                    .iter()
+                    .filter(|(name, _)| name.ends_with(&suffix))
+                    .map(|(name, target)| (name.clone(), target.clone())),
+            );
+            // Each remote is contacted, like by the fetch
+            self.set_git_non_interactive();
+            let output =
+                self.execute_command(Command::new("git").args(["ls-remote", "--heads", url]))?;
+            after.extend(parse_ls_remote(&output, remote));
+        }
+
+        let title = format!("Fetching from {} would change", remotes.join(", "));
+        Ok(summary(&title, Ok(ref_changes(&before, &after)), ""))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commander::tests::TestRepo;
+    use anyhow::Result;
+
+    #[test]
+    fn fetch_remotes() {
+        let remotes = ["origin".to_owned(), "upstream".to_owned()];
+        assert_eq!(default_fetch_remotes(None, &remotes), ["origin"]);
+        assert_eq!(default_fetch_remotes(None, &remotes[1..]), ["upstream"]);
+        assert_eq!(
+            default_fetch_remotes(Some("upstream"), &remotes),
+            ["upstream"]
+        );
+        assert_eq!(
+            default_fetch_remotes(Some(r#"["upstream", "origin"]"#), &remotes),
+            ["upstream", "origin"]
+        );
+    }
+
+    #[test]
+    fn ls_remote_branches() {
+        let output = "0123456789abcdef0123456789abcdef01234567\trefs/heads/main\n\
+                      89abcdef0123456789abcdef0123456789abcdef\trefs/tags/v1\n";
+        assert_eq!(
+            parse_ls_remote(output, "origin"),
+            Refs::from([("main@origin".to_owned(), "0123456789ab".to_owned())])
+        );
+    }
+
+    #[test]
+    fn changed_refs() {
+        let before = parse_refs("main abc\nold 123\nfeature@origin def\n");
+        let after = parse_refs("main abd\nfeature@origin def\nnew 456\n");
+        assert_eq!(
+            ref_changes(&before, &after),
+            ["~ main abc → abd", "+ new 456", "- old"]
+        );
+        assert_eq!(
+            summary("Refs changed in git", Ok(vec![]), ""),
+            "Nothing changed\n"
+        );
+    }
+
+    #[test]
+    fn import_and_export() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+        test_repo
+            .commander
+            .execute_void_jj_command(["describe", "-m", "first"])?;
+        test_repo.commander.execute_void_jj_command(["new"])?;
+        let commit_id = test_repo
+            .commander
+            .execute_jj_command(
+                ["log", "--no-graph", "-r", "@-", "-T", "commit_id"],
+                false,
+                true,
+            )?
+            .trim()
+            .to_owned();
+
+        test_repo
+            .commander
+            .execute_command(Command::new("git").args(["branch", "from-git", &commit_id]))?;
+        let summary = test_repo.commander.git_import()?;
+        assert!(
+            summary.starts_with("Bookmarks changed in jj:\n  + from-git "),
+            "{summary}"
+        );
+
+        // jj exported the bookmark itself when creating it in the colocated repository
+        test_repo.commander.create_bookmark("from-jj")?;
+        let summary = test_repo.commander.git_export()?;
+        assert!(summary.starts_with("Nothing changed\n"), "{summary}");
+
+        Ok(())
+    }
+
+    #[test]
+    fn fetch_dry_run() -> Result<()> {
+        let remote = TestRepo::new()?;
+        let test_repo = TestRepo::new()?;
+        let url = remote.directory.path().to_string_lossy().to_string();
+        test_repo
+            .commander
+            .execute_void_jj_command(["git", "remote", "add", "origin", &url])?;
+
+        let summary = test_repo.commander.git_fetch_dry_run(false)?;
+        assert_eq!(summary, "Nothing changed\n");
+
+        remote
+            .commander
+            .execute_void_jj_command(["describe", "-m", "remote"])?;
+        remote.commander.create_bookmark("main")?;
+        let summary = test_repo.commander.git_fetch_dry_run(false)?;
+        assert!(
+            summary.starts_with("Fetching from origin would change:\n  + main@origin "),
+            "{summary}"
+        );
+
+        Ok(())
+    }
+}
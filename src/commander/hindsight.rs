@@ -0,0 +1,174 @@
+/*!
+[Commander] member functions comparing the log with an earlier operation, used
+by the hindsight mode of the [log_panel][crate::ui::panel::log_panel].
+
+The operation seen last is kept as a baseline. Revisions of the log which
+weren't visible as of the baseline are new, or rewritten if their change
+existed then, e.g. after a fetch or a rebase by a teammate pushing into a
+shared repository. Marking the log as seen moves the baseline to the current
+operation.
+*/
+use std::collections::{HashMap, HashSet};
+
+use tracing::instrument;
+
+use crate::commander::{CommandError, Commander, ids::CommitId, log::path_fileset};
+
+/// How a revision of the log differs from the baseline operation
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RevisionChange {
+    /// Its change didn't exist as of the baseline
+    New,
+    /// Its change existed as of the baseline, with another commit
+    Rewritten,
+}
+
+/// Number of new or rewritten revisions marked, the newest ones. A fetch after a long time can
+/// bring in thousands.
+const MAX_CHANGED_REVISIONS: usize = 500;
+/// Number of changes looked up per `jj log --at-op`, keeping the revset short
+const EXISTING_CHUNK_SIZE: usize = 100;
+
+const CHANGED_TEMPLATE: &str = r#"commit_id ++ "\t" ++ change_id ++ "\n""#;
+
+impl Commander {
+    /// Id of the current operation, without snapshotting the working copy.
+    /// Maps to `jj op log --limit 1`
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_current_operation(&self) -> Result<String, CommandError> {
+        let output = self.execute_jj_command(
+            vec![
+                "op",
+                "log",
+                "--no-graph",
+                "--ignore-working-copy",
+                "--limit",
+                "1",
+                "--template",
+                "id",
+            ],
+            false,
+            true,
+        )?;
+        Ok(output.trim().to_owned())
+    }
+
+    /// The revisions of the log which are new or rewritten since the operation `baseline`, at
+    /// most [MAX_CHANGED_REVISIONS].
+    /// Maps to `jj log -r '(<revset>) ~ at_operation(<baseline>, all())'` and
+    /// `jj log --at-op <baseline>` for the changes which existed then, in chunks of
+    /// [EXISTING_CHUNK_SIZE]
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_changes_since(
+        &self,
+        baseline: &str,
+        revset: &Option<String>,
+        paths: &[String],
+    ) -> Result<HashMap<CommitId, RevisionChange>, CommandError> {
+        // Nothing changed without a new operation
+        if self.get_op_heads() == [baseline] {
+            return Ok(HashMap::new());
+        }
+
+        let revset = format!(
+            "({}) ~ at_operation({baseline}, all())",
+            self.get_log_revset(revset)
+        );
+        let limit = MAX_CHANGED_REVISIONS.to_string();
+        let mut args = vec![
+            "log",
+            "--no-graph",
+            "--limit",
+            &limit,
+            "-r",
+            &revset,
+            "-T",
+            CHANGED_TEMPLATE,
+        ];
+        let filesets: Vec<String> = paths.iter().map(|path| path_fileset(path)).collect();
+        args.extend(filesets.iter().map(String::as_str));
+        let output = self.execute_jj_command(args, false, true)?;
+        let changed: Vec<(&str, &str)> = output
+            .lines()
+            .filter_map(|line| line.split_once('\t'))
+            .collect();
+        if changed.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let mut existing_output = String::new();
+        for chunk in changed.chunks(EXISTING_CHUNK_SIZE) {
+            let existing_revset = chunk
+                .iter()
+                .map(|(_, change_id)| format!("present({change_id})"))
+                .collect::<Vec<_>>()
+                .join(" | ");
+            existing_output += &self.execute_jj_command(
+                vec![
+                    "log",
+                    "--at-op",
+                    baseline,
+                    "--ignore-working-copy",
+                    "--no-graph",
+                    "-r",
+                    &existing_revset,
+                    "-T",
+                    r#"change_id ++ "\n""#,
+                ],
+                false,
+                true,
+            )?;
+        }
+        let existing: HashSet<&str> = existing_output.lines().collect();
+
+        Ok(changed
+            .into_iter()
+            .map(|(commit_id, change_id)| {
+                let change = if existing.contains(change_id) {
+                    RevisionChange::Rewritten
+                } else {
+                    RevisionChange::New
+                };
+                (CommitId(commit_id.to_owned()), change)
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::*;
+    use crate::commander::tests::TestRepo;
+
+    #[test]
+    fn changes_since() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+        let commander = &test_repo.commander;
+
+        // Snapshot the working copy before taking the baseline
+        let first = commander.get_current_head()?;
+        let baseline = commander.get_current_operation()?;
+        assert_eq!(
+            commander.get_changes_since(&baseline, &Some("all()".to_owned()), &[])?,
+            HashMap::new()
+        );
+
+        commander.execute_void_jj_command(["describe", "-m", "first"])?;
+        commander.execute_void_jj_command(["new"])?;
+        let second = commander.get_current_head()?;
+        let described = commander.get_commit_parent(&second.commit_id)?;
+
+        let changes = commander.get_changes_since(&baseline, &Some("all()".to_owned()), &[])?;
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes.get(&second.commit_id), Some(&RevisionChange::New));
+        assert_eq!(
+            changes.get(&described.commit_id),
+            Some(&RevisionChange::Rewritten)
+        );
+        assert_eq!(changes.get(&first.commit_id), None);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,156 @@
+/*!
+Process wide record of the most recent commands run by any [Commander].
+
+Commanders are cheap to create and are cloned into background threads, so
+the history is kept in a global instead of on the struct. It is read by
+the [crash_report][crate::crash_report] and [command_log][crate::ui::command_log] modules,
+and by [output_processors][crate::commander::output_processors] to find the commands
+of an operation.
+*/
+use std::{
+    collections::VecDeque,
+    path::Path,
+    process::Command,
+    sync::{LazyLock, Mutex},
+    time::Duration,
+};
+
+use crate::commander::running;
+
+/// Number of commands kept in the history
+const COMMAND_HISTORY_LIMIT: usize = 50;
+
+static COMMAND_HISTORY: LazyLock<Mutex<VecDeque<CommandRecord>>> =
+    LazyLock::new(|| Mutex::new(VecDeque::with_capacity(COMMAND_HISTORY_LIMIT)));
+
+#[derive(Clone, Debug)]
+pub struct CommandRecord {
+    /// Program and arguments, as passed to the process
+    pub args: Vec<String>,
+    /// Exit code, `None` if the command could not be started or was killed
+    pub status: Option<i32>,
+    pub duration: Duration,
+    /// Operation the command ran in, see [running::in_operation]
+    pub operation: Option<u64>,
+    /// What the command printed on stderr, where jj, git and hooks print their messages
+    pub output: String,
+}
+
+impl CommandRecord {
+    pub fn new(command: &Command, status: Option<i32>, duration: Duration) -> Self {
+        let args = std::iter::once(command.get_program())
+            .chain(command.get_args())
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect();
+        Self {
+            args,
+            status,
+            duration,
+            operation: running::current_operation(),
+            output: String::new(),
+        }
+    }
+
+    /// Keep the stderr of the command, trimmed
+    pub fn with_output(mut self, stderr: &[u8]) -> Self {
+        self.output = String::from_utf8_lossy(stderr).trim().to_owned();
+        self
+    }
+
+    /// Shell quoted command line for display, without the program's directory and the output
+    /// and config arguments blazingjj adds to every jj command
+    pub fn command_line(&self) -> String {
+        let mut args = vec![];
+        let mut iter = self.args.iter();
+        if let Some(program) = iter.next() {
+            args.push(
+                Path::new(program)
+                    .file_name()
+                    .map_or(program.clone(), |name| name.to_string_lossy().into_owned()),
+            );
+        }
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--no-pager" | "--quiet" => {}
+                "--color" | "--config" => {
+                    iter.next();
+                }
+                _ => args.push(arg.clone()),
+            }
+        }
+        shell_words::join(args)
+    }
+
+    pub fn failed(&self) -> bool {
+        self.status != Some(0)
+    }
+}
+
+/// Add a command to the history, dropping the oldest one if full
+pub fn record_command(record: CommandRecord) {
+    // A poisoned lock only means another thread panicked while recording, the history is still
+    // usable
+    let mut history = COMMAND_HISTORY
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if history.len() == COMMAND_HISTORY_LIMIT {
+        history.pop_front();
+    }
+    history.push_back(record);
+}
+
+/// Command lines of the recorded commands of the operation, see [running::in_operation]
+pub fn operation_command_lines(operation: u64) -> Vec<String> {
+    COMMAND_HISTORY
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .iter()
+        .filter(|record| record.operation == Some(operation))
+        .map(CommandRecord::command_line)
+        .collect()
+}
+
+/// Command line of the newest recorded command which failed
+pub fn last_failed_command_line() -> Option<String> {
+    COMMAND_HISTORY
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .iter()
+        .rev()
+        .find(|record| record.failed())
+        .map(CommandRecord::command_line)
+}
+
+/// The recorded commands, oldest first
+pub fn command_history() -> Vec<CommandRecord> {
+    COMMAND_HISTORY
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .iter()
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn command_line() {
+        let mut command = Command::new("/usr/bin/jj");
+        command.args([
+            "log",
+            "-r",
+            "main@origin | @",
+            "--no-pager",
+            "--color",
+            "always",
+            "--quiet",
+            "--config",
+            r#"user.name="x""#,
+        ]);
+        let record = CommandRecord::new(&command, Some(1), Duration::from_millis(5));
+        assert_eq!(record.command_line(), "jj log -r 'main@origin | @'");
+        assert!(record.failed());
+    }
+}
@@ -0,0 +1,177 @@
+/*!
+[Commander] member functions running the user's hooks.
+
+Hooks are shell commands configured as `blazingjj.hooks.<stage>-<action>`, e.g.
+`blazingjj.hooks.pre-push`. They run in the repository root with environment
+variables describing the affected change. A failing pre hook aborts the action.
+*/
+
+use std::fmt::Display;
+
+use tracing::instrument;
+
+use crate::commander::{CommandError, Commander, shell_command};
+
+/// Actions which run hooks
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HookAction {
+    New,
+    Edit,
+    Describe,
+    Abandon,
+    Squash,
+    Push,
+    Fetch,
+}
+
+impl HookAction {
+    pub const VALUES: [Self; 7] = [
+        HookAction::New,
+        HookAction::Edit,
+        HookAction::Describe,
+        HookAction::Abandon,
+        HookAction::Squash,
+        HookAction::Push,
+        HookAction::Fetch,
+    ];
+}
+
+impl Display for HookAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HookAction::New => write!(f, "new"),
+            HookAction::Edit => write!(f, "edit"),
+            HookAction::Describe => write!(f, "describe"),
+            HookAction::Abandon => write!(f, "abandon"),
+            HookAction::Squash => write!(f, "squash"),
+            HookAction::Push => write!(f, "push"),
+            HookAction::Fetch => write!(f, "fetch"),
+        }
+    }
+}
+
+/// Whether a hook runs before or after its action
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HookStage {
+    Pre,
+    Post,
+}
+
+impl Display for HookStage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HookStage::Pre => write!(f, "pre"),
+            HookStage::Post => write!(f, "post"),
+        }
+    }
+}
+
+/// Name of a hook in the config, e.g. `pre-push`
+pub fn hook_name(stage: HookStage, action: HookAction) -> String {
+    format!("{stage}-{action}")
+}
+
+/// Whether `name` is a hook which can be configured
+pub fn is_known_hook(name: &str) -> bool {
+    [HookStage::Pre, HookStage::Post].iter().any(|stage| {
+        HookAction::VALUES
+            .iter()
+            .any(|action| hook_name(*stage, *action) == name)
+    })
+}
+
+/// Details about the action passed to hooks as environment variables
+#[derive(Clone, Debug, Default)]
+pub struct HookContext<'a> {
+    /// `BLAZINGJJ_REVISION`: The revision the action applies to
+    pub revision: Option<&'a str>,
+    /// `BLAZINGJJ_DESCRIPTION`: The new description of a describe action
+    pub description: Option<&'a str>,
+}
+
+impl Commander {
+    /// Run the hook for `action` at `stage` if one is configured. A failing hook returns
+    /// [CommandError::Hook], for pre hooks the caller must not run the action then.
+    #[instrument(level = "trace", skip(self))]
+    pub fn run_hook(
+        &self,
+        stage: HookStage,
+        action: HookAction,
+        context: &HookContext,
+    ) -> Result<(), CommandError> {
+        let name = hook_name(stage, action);
+        let Some(hook) = self.env.config.hook(&name) else {
+            return Ok(());
+        };
+
+        let mut command = shell_command(hook);
+        command
+            .env("BLAZINGJJ_HOOK", &name)
+            .env("BLAZINGJJ_REPO_ROOT", &self.env.root);
+        if let Some(revision) = context.revision {
+            command.env("BLAZINGJJ_REVISION", revision);
+        }
+        if let Some(description) = context.description {
+            command.env("BLAZINGJJ_DESCRIPTION", description);
+        }
+
+        match self.execute_command_output(&mut command) {
+            Ok(_) => Ok(()),
+            Err(CommandError::Status(output, _)) => Err(CommandError::Hook(name, output)),
+            Err(err) => Err(CommandError::Hook(name, err.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(unix)]
+mod tests {
+    use super::*;
+    use crate::commander::tests::TestRepo;
+    use anyhow::Result;
+
+    #[test]
+    fn known_hooks() {
+        assert!(is_known_hook("pre-push"));
+        assert!(is_known_hook("post-describe"));
+        assert!(!is_known_hook("pre-rebase"));
+        assert!(!is_known_hook("push"));
+    }
+
+    #[test]
+    fn failing_pre_hook_aborts_action() -> Result<()> {
+        let mut test_repo = TestRepo::new()?;
+        test_repo.commander.env.config = toml::from_str(
+            r#""blazingjj.hooks" = { pre-describe = 'echo "no $BLAZINGJJ_DESCRIPTION" >&2; exit 1' }"#,
+        )?;
+
+        let head = test_repo.commander.get_current_head()?;
+        let err = test_repo
+            .commander
+            .run_describe(head.commit_id.as_str(), "WIP")
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<CommandError>().map(ToString::to_string),
+            Some("pre-describe hook failed:\nno WIP\n".to_owned())
+        );
+        assert_eq!(test_repo.commander.get_current_head()?, head);
+
+        Ok(())
+    }
+
+    #[test]
+    fn post_hook_gets_revision() -> Result<()> {
+        let mut test_repo = TestRepo::new()?;
+        test_repo.commander.env.config = toml::from_str(
+            r#""blazingjj.hooks" = { post-new = 'echo "$BLAZINGJJ_REVISION" > hook-output' }"#,
+        )?;
+
+        let head = test_repo.commander.get_current_head()?;
+        test_repo.commander.run_new(head.commit_id.as_str())?;
+
+        let output = std::fs::read_to_string(test_repo.directory.path().join("hook-output"))?;
+        assert_eq!(output.trim(), head.commit_id.as_str());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,305 @@
+/*!
+[Commander] member functions listing the hunks of the files of a change, so
+that some of them can be extracted into a new change, see
+[Commander::run_extract].
+
+The hunks are read from the git diff of the change. To extract only some of
+them, the content of the file with the other hunks reverted is computed by
+[revert_hunks] and given to `jj split` as the selected content.
+*/
+use anyhow::Result;
+use tracing::instrument;
+
+use crate::commander::{CommandError, Commander, ids::CommitId, log::path_fileset};
+
+/// Changed lines of a file with their context, like `@@ -1,4 +1,5 @@` in a git diff
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hunk {
+    /// Header of the hunk, e.g. `@@ -1,4 +1,5 @@`
+    pub header: String,
+    /// Line of the new content the hunk starts at, starting at 1
+    pub new_start: usize,
+    /// Lines of the hunk, prefixed with ` `, `-` or `+` and without line endings
+    pub lines: Vec<String>,
+    /// Whether the last old or new line has no line ending
+    old_missing_newline: bool,
+    new_missing_newline: bool,
+}
+
+impl Hunk {
+    /// Lines of one side of the hunk, the old one without the added lines and the new one
+    /// without the removed lines, with their line endings
+    fn side(&self, new: bool) -> Vec<String> {
+        let (skipped, missing_newline) = if new {
+            ('-', self.new_missing_newline)
+        } else {
+            ('+', self.old_missing_newline)
+        };
+        let mut lines: Vec<String> = self
+            .lines
+            .iter()
+            .filter(|line| !line.starts_with(skipped))
+            .map(|line| format!("{}\n", &line[1..]))
+            .collect();
+        if missing_newline && let Some(last) = lines.last_mut() {
+            last.pop();
+        }
+        lines
+    }
+}
+
+/// Hunks of a changed file. Binary files and files changing only their mode have none.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FileHunks {
+    pub path: String,
+    pub hunks: Vec<Hunk>,
+}
+
+/// File extracted from a change by [Commander::run_extract]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExtractedFile {
+    pub path: String,
+    /// Hunks which stay in the change, none to extract the whole file
+    pub kept_hunks: Vec<Hunk>,
+}
+
+/// Start line of the new side of a hunk header like `@@ -1,4 +1,5 @@`
+fn parse_new_start(header: &str) -> Option<usize> {
+    let new_range = header.split(' ').find(|part| part.starts_with('+'))?;
+    new_range[1..].split(',').next()?.parse().ok()
+}
+
+/// Split the git diff of files into their hunks. `paths` are the files in the diff, which are
+/// matched with the `diff --git a/<old> b/<new>` lines, as paths can contain spaces.
+pub fn parse_hunks(diff: &str, paths: &[String]) -> Vec<FileHunks> {
+    let mut files: Vec<FileHunks> = vec![];
+    let mut in_hunk = false;
+    for line in diff.lines() {
+        if line.starts_with("diff --git ") {
+            in_hunk = false;
+            if let Some(path) = paths
+                .iter()
+                .find(|path| line.ends_with(&format!(" b/{path}")))
+            {
+                files.push(FileHunks {
+                    path: path.clone(),
+                    hunks: vec![],
+                });
+            }
+            continue;
+        }
+        let Some(file) = files.last_mut() else {
+            continue;
+        };
+        if line.starts_with("@@ ") {
+            if let Some(new_start) = parse_new_start(line) {
+                file.hunks.push(Hunk {
+                    header: line.to_owned(),
+                    new_start,
+                    lines: vec![],
+                    old_missing_newline: false,
+                    new_missing_newline: false,
+                });
+                in_hunk = true;
+            }
+            continue;
+        }
+        let Some(hunk) = file.hunks.last_mut().filter(|_| in_hunk) else {
+            continue;
+        };
+        if line.starts_with('\\') {
+            // `\ No newline at end of file` applies to the line before it
+            match hunk.lines.last().and_then(|last| last.chars().next()) {
+                Some('-') => hunk.old_missing_newline = true,
+                Some('+') => hunk.new_missing_newline = true,
+                _ => {
+                    hunk.old_missing_newline = true;
+                    hunk.new_missing_newline = true;
+                }
+            }
+        } else if line.starts_with([' ', '-', '+']) {
+            hunk.lines.push(line.to_owned());
+        } else if line.is_empty() {
+            // An empty context line, if the diff was trimmed
+            hunk.lines.push(" ".to_owned());
+        } else {
+            in_hunk = false;
+        }
+    }
+    files
+}
+
+/// New content of a file with the changes of `hunks` reverted
+pub fn revert_hunks(content: &str, hunks: &[Hunk]) -> String {
+    let mut lines: Vec<String> = content.split_inclusive('\n').map(String::from).collect();
+    let mut hunks: Vec<&Hunk> = hunks.iter().collect();
+    // From the end, so the start lines of the hunks before stay valid
+    hunks.sort_by_key(|hunk| std::cmp::Reverse(hunk.new_start));
+    for hunk in hunks {
+        let new_lines = hunk.side(true);
+        // A hunk without new lines starts after the line it is at
+        let start = if new_lines.is_empty() {
+            hunk.new_start
+        } else {
+            hunk.new_start.saturating_sub(1)
+        }
+        .min(lines.len());
+        let end = (start + new_lines.len()).min(lines.len());
+        lines.splice(start..end, hunk.side(false));
+    }
+    lines.concat()
+}
+
+impl Commander {
+    /// Hunks of the files at `paths` in a change, in the order of the diff.
+    /// Maps to `jj diff -r <revision> --git <paths>`
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_hunks(
+        &self,
+        commit_id: &CommitId,
+        paths: &[String],
+    ) -> Result<Vec<FileHunks>, CommandError> {
+        let filesets: Vec<String> = paths.iter().map(|path| path_fileset(path)).collect();
+        let mut args = vec!["diff", "-r", commit_id.as_str(), "--git"];
+        args.extend(filesets.iter().map(String::as_str));
+        let diff = self.execute_jj_command(args, false, true)?;
+        Ok(parse_hunks(&diff, paths))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::commander::tests::TestRepo;
+
+    #[test]
+    fn parse_and_revert() {
+        let diff = "\
+diff --git a/a b/a
+index 1..2 100644
+--- a/a
++++ b/a
+@@ -1,3 +1,3 @@
+ 1
+-2
++two
+ 3
+@@ -8,2 +8,3 @@
+ 8
+ 9
++10
+\\ No newline at end of file
+";
+        let files = parse_hunks(diff, &["a".to_owned()]);
+        assert_eq!(files.len(), 1);
+        let hunks = &files[0].hunks;
+        assert_eq!(hunks.len(), 2);
+        assert_eq!(hunks[0].new_start, 1);
+        assert_eq!(hunks[1].lines, vec![" 8", " 9", "+10"]);
+
+        let content = "1\ntwo\n3\n4\n5\n6\n7\n8\n9\n10";
+        assert_eq!(
+            revert_hunks(content, &hunks[1..]),
+            "1\ntwo\n3\n4\n5\n6\n7\n8\n9\n"
+        );
+        assert_eq!(
+            revert_hunks(content, &hunks[..1]),
+            "1\n2\n3\n4\n5\n6\n7\n8\n9\n10"
+        );
+        assert_eq!(revert_hunks(content, hunks), "1\n2\n3\n4\n5\n6\n7\n8\n9\n");
+    }
+
+    #[test]
+    fn get_hunks() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+        let lines: Vec<String> = (1..=20).map(|i| i.to_string()).collect();
+        fs::write(
+            test_repo.directory.path().join("a"),
+            lines.join("\n") + "\n",
+        )?;
+        test_repo
+            .commander
+            .execute_void_jj_command(["commit", "-m", "base"])?;
+
+        let mut changed = lines.clone();
+        changed[1] = "two".to_owned();
+        changed[17] = "eighteen".to_owned();
+        fs::write(
+            test_repo.directory.path().join("a"),
+            changed.join("\n") + "\n",
+        )?;
+
+        let head = test_repo.commander.get_current_head()?;
+        let files = test_repo
+            .commander
+            .get_hunks(&head.commit_id, &["a".to_owned()])?;
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path, "a");
+        assert_eq!(
+            files[0]
+                .hunks
+                .iter()
+                .map(|hunk| hunk.header.as_str())
+                .collect::<Vec<_>>(),
+            vec!["@@ -1,5 +1,5 @@", "@@ -15,6 +15,6 @@"]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn extract_hunk() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+        let lines: Vec<String> = (1..=20).map(|i| i.to_string()).collect();
+        fs::write(
+            test_repo.directory.path().join("a"),
+            lines.join("\n") + "\n",
+        )?;
+        fs::write(test_repo.directory.path().join("b"), "b\n")?;
+        test_repo
+            .commander
+            .execute_void_jj_command(["commit", "-m", "base"])?;
+
+        let mut changed = lines.clone();
+        changed[1] = "two".to_owned();
+        changed[17] = "eighteen".to_owned();
+        fs::write(
+            test_repo.directory.path().join("a"),
+            changed.join("\n") + "\n",
+        )?;
+        fs::write(test_repo.directory.path().join("b"), "changed\n")?;
+
+        let head = test_repo.commander.get_current_head()?;
+        let files = test_repo
+            .commander
+            .get_hunks(&head.commit_id, &["a".to_owned()])?;
+        test_repo.commander.run_extract(
+            &head.commit_id,
+            &[ExtractedFile {
+                path: "a".to_owned(),
+                kept_hunks: files[0].hunks[1..].to_vec(),
+            }],
+            "extracted",
+        )?;
+
+        let show = |revset: &str| {
+            test_repo.commander.execute_jj_command(
+                ["diff", "--git", "-r", revset, "--context", "0"],
+                false,
+                true,
+            )
+        };
+        let extracted = show("description(substring:extracted)")?;
+        assert!(extracted.contains("+two"));
+        assert!(!extracted.contains("eighteen"));
+        assert!(!extracted.contains("b/b"));
+        let remaining = show("@")?;
+        assert!(!remaining.contains("+two"));
+        assert!(remaining.contains("+eighteen"));
+        assert!(remaining.contains("+changed"));
+
+        Ok(())
+    }
+}
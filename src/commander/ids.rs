@@ -1,10 +1,11 @@
 /*!
 Helper structs [ChangeId] and [CommitId]
 */
+use serde::Deserialize;
 use std::{ffi::OsStr, fmt::Display};
 
 /// Wrapper around change ID.
-#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Deserialize)]
 pub struct ChangeId(pub String);
 
 impl ChangeId {
@@ -34,7 +35,7 @@ impl Display for ChangeId {
 }
 
 /// Wrapper around commit ID.
-#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Deserialize)]
 pub struct CommitId(pub String);
 
 impl CommitId {
@@ -0,0 +1,291 @@
+/*!
+Coordination between blazingjj instances working on the same repository, e.g.
+in two tmux panes.
+
+Commands which change the repository hold an advisory lock file in
+`.jj/blazingjj` while they run, so that instances don't run them at the same
+time. Background work waits for the lock of another instance for a while
+before giving up with [CommandError::Locked], the UI thread gives up right
+away so it doesn't freeze. [Composite actions][Commander::run_composite_action]
+hold the lock for all of their steps, and wait for it on the UI thread too,
+showing the wait with the handler set by [on_lock_wait]. Locks of processes which are gone, or which are
+older than [STALE_LOCK_AGE], are taken over. Within the process the lock is
+re-entrant, so a command started while background work like a push holds it
+doesn't wait for it.
+
+The operation heads after each of this process' own commands are remembered,
+so that the [App][crate::app::App] can tell operations of other instances, or
+of jj run by an editor, from its own and refresh for them.
+*/
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    process,
+    sync::{
+        LazyLock, Mutex, OnceLock,
+        atomic::{AtomicU64, Ordering},
+    },
+    thread::{self, ThreadId},
+    time::{Duration, SystemTime},
+};
+
+use tracing::{info, warn};
+
+use crate::commander::{CommandError, Commander};
+
+/// How long to wait for another instance to finish its command
+const LOCK_WAIT: Duration = Duration::from_secs(10);
+/// Time between checks whether the lock was released
+const LOCK_POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// Age after which a lock is assumed to be left behind by a crashed instance
+const STALE_LOCK_AGE: Duration = Duration::from_secs(600);
+
+/// Operation heads after the last command of this process which changed the repository
+static OWN_OP_HEADS: LazyLock<Mutex<Vec<String>>> = LazyLock::new(|| Mutex::new(vec![]));
+
+/// Suffix of the next file the lock is created from, unique within the process
+static NEXT_LOCK_FILE: AtomicU64 = AtomicU64::new(0);
+
+/// Number of [MutationLock]s of this process by lock file, which is removed with the last one
+static HELD_LOCKS: LazyLock<Mutex<HashMap<PathBuf, usize>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Thread drawing the UI, which doesn't wait for the lock
+static UI_THREAD: OnceLock<ThreadId> = OnceLock::new();
+
+/// Mark the current thread as the UI thread, which fails right away if another instance holds
+/// the lock instead of freezing the UI while waiting for it
+pub fn set_ui_thread() {
+    let _ = UI_THREAD.set(thread::current().id());
+}
+
+fn is_ui_thread() -> bool {
+    UI_THREAD.get() == Some(&thread::current().id())
+}
+
+/// Called with the pid of the lock holder and the time waited, returns whether to keep waiting
+type LockWaitHandler = Box<dyn FnMut(u32, Duration) -> bool>;
+
+thread_local! {
+    static LOCK_WAIT_HANDLER: RefCell<Option<LockWaitHandler>> = const { RefCell::new(None) };
+}
+
+/// Call `handler` repeatedly while the UI thread waits for the lock of another instance, see
+/// [Commander::wait_for_mutation_lock]. It returns whether to keep waiting, e.g. until Esc is
+/// pressed.
+pub fn on_lock_wait(handler: impl FnMut(u32, Duration) -> bool + 'static) {
+    LOCK_WAIT_HANDLER.set(Some(Box::new(handler)));
+}
+
+fn lock_path(root: &str) -> PathBuf {
+    Path::new(root)
+        .join(".jj")
+        .join("blazingjj")
+        .join("mutation.lock")
+}
+
+/// Whether the process which wrote the lock is gone. Only known on Linux, elsewhere the lock
+/// expires after [STALE_LOCK_AGE].
+fn is_abandoned(pid: u32) -> bool {
+    cfg!(target_os = "linux")
+        && Path::new("/proc").is_dir()
+        && !Path::new("/proc").join(pid.to_string()).exists()
+}
+
+/// Pid of the process holding the lock, `None` if the lock can be taken over
+fn lock_holder(path: &Path) -> Option<u32> {
+    let pid = fs::read_to_string(path).ok()?.trim().parse().ok()?;
+    let age = fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .unwrap_or_default();
+    if is_abandoned(pid) || age > STALE_LOCK_AGE {
+        return None;
+    }
+    Some(pid)
+}
+
+/// Lock held while a command changes the repository, released when dropped
+pub struct MutationLock<'a> {
+    commander: &'a Commander,
+    path: PathBuf,
+}
+
+impl Drop for MutationLock<'_> {
+    fn drop(&mut self) {
+        *OWN_OP_HEADS.lock().unwrap() = self.commander.get_op_heads();
+        let mut held = HELD_LOCKS.lock().unwrap();
+        let Some(count) = held.get_mut(&self.path) else {
+            return;
+        };
+        *count -= 1;
+        if *count > 0 {
+            return;
+        }
+        held.remove(&self.path);
+        if let Err(err) = fs::remove_file(&self.path) {
+            warn!("Failed removing {}: {err}", self.path.display());
+        }
+    }
+}
+
+/// Whether the operation heads are the result of this process' own commands
+pub fn is_own_operation(op_heads: &[String]) -> bool {
+    *OWN_OP_HEADS.lock().unwrap() == op_heads
+}
+
+impl Commander {
+    /// Wait until no other instance changes the repository and take the lock, or fail right away
+    /// on the UI thread. Failing to create the lock file, e.g. in a read-only directory, doesn't
+    /// prevent the command.
+    pub fn lock_mutation(&self) -> Result<Option<MutationLock<'_>>, CommandError> {
+        self.take_mutation_lock(false)
+    }
+
+    /// Like [Commander::lock_mutation], but the UI thread waits too, calling the handler set
+    /// with [on_lock_wait] while it does
+    pub fn wait_for_mutation_lock(&self) -> Result<Option<MutationLock<'_>>, CommandError> {
+        self.take_mutation_lock(true)
+    }
+
+    fn take_mutation_lock(
+        &self,
+        wait_on_ui_thread: bool,
+    ) -> Result<Option<MutationLock<'_>>, CommandError> {
+        let path = lock_path(&self.env.root);
+        // Without a repository yet, e.g. for `jj git init`, there is nothing to coordinate, and
+        // creating `.jj` would make jj refuse to create the repository
+        if !Path::new(&self.env.root).join(".jj").is_dir() {
+            return Ok(None);
+        }
+        if let Some(parent) = path.parent()
+            && let Err(err) = fs::create_dir_all(parent)
+        {
+            warn!("Failed creating {}: {err}", parent.display());
+            return Ok(None);
+        }
+
+        let mut waited = Duration::ZERO;
+        loop {
+            {
+                // Held while creating the lock file, so threads of this process don't race for it
+                let mut held = HELD_LOCKS.lock().unwrap();
+                if let Some(count) = held.get_mut(&path) {
+                    *count += 1;
+                    return Ok(Some(MutationLock {
+                        commander: self,
+                        path,
+                    }));
+                }
+
+                // Linking a file with the pid creates the lock with its content at once, so
+                // other instances never read an empty lock
+                let file = path.with_extension(format!(
+                    "{}.{}",
+                    process::id(),
+                    NEXT_LOCK_FILE.fetch_add(1, Ordering::Relaxed)
+                ));
+                let locked = fs::write(&file, process::id().to_string())
+                    .and_then(|_| fs::hard_link(&file, &path));
+                let _ = fs::remove_file(&file);
+                match locked {
+                    Ok(()) => {
+                        held.insert(path.clone(), 1);
+                        return Ok(Some(MutationLock {
+                            commander: self,
+                            path,
+                        }));
+                    }
+                    Err(err) if err.kind() == ErrorKind::AlreadyExists => {}
+                    Err(err) => {
+                        warn!("Failed creating {}: {err}", path.display());
+                        return Ok(None);
+                    }
+                }
+            }
+
+            match lock_holder(&path) {
+                None => {
+                    info!("Taking over the stale lock {}", path.display());
+                    let _ = fs::remove_file(&path);
+                }
+                Some(pid) if waited >= LOCK_WAIT => return Err(CommandError::Locked(pid)),
+                Some(pid) if is_ui_thread() => {
+                    let keep_waiting = wait_on_ui_thread
+                        && LOCK_WAIT_HANDLER.with_borrow_mut(|handler| {
+                            handler.as_mut().is_some_and(|handler| handler(pid, waited))
+                        });
+                    if !keep_waiting {
+                        return Err(CommandError::Locked(pid));
+                    }
+                    thread::sleep(LOCK_POLL_INTERVAL);
+                    waited += LOCK_POLL_INTERVAL;
+                }
+                Some(_) => {
+                    thread::sleep(LOCK_POLL_INTERVAL);
+                    waited += LOCK_POLL_INTERVAL;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commander::tests::TestRepo;
+    use anyhow::Result;
+
+    #[test]
+    fn mutation_lock() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+        let path = lock_path(&test_repo.commander.env.root);
+
+        {
+            let _lock = test_repo.commander.lock_mutation()?;
+            assert_eq!(lock_holder(&path), Some(process::id()));
+
+            // Re-entrant within the process, e.g. while a background push holds it
+            let commander = Commander::new(&test_repo.commander.env);
+            thread::spawn(move || commander.lock_mutation().map(|lock| lock.is_some()))
+                .join()
+                .unwrap()?;
+            assert!(path.exists());
+        }
+        assert!(!path.exists());
+
+        // A lock of another process which is gone is taken over
+        if cfg!(target_os = "linux") {
+            fs::write(&path, u32::MAX.to_string())?;
+            assert_eq!(lock_holder(&path), None);
+            drop(test_repo.commander.lock_mutation()?);
+
+            // The UI thread doesn't wait for a live process, pid 1 always exists
+            fs::write(&path, "1")?;
+            set_ui_thread();
+            assert!(matches!(
+                test_repo.commander.lock_mutation(),
+                Err(CommandError::Locked(1))
+            ));
+
+            // Composite actions wait on the UI thread until the handler gives up
+            let (waits_tx, waits_rx) = std::sync::mpsc::channel();
+            on_lock_wait(move |pid, waited| {
+                let _ = waits_tx.send(pid);
+                waited < LOCK_POLL_INTERVAL * 2
+            });
+            assert!(matches!(
+                test_repo.commander.wait_for_mutation_lock(),
+                Err(CommandError::Locked(1))
+            ));
+            assert_eq!(waits_rx.try_iter().collect::<Vec<_>>(), vec![1, 1, 1]);
+            fs::remove_file(&path)?;
+        }
+
+        Ok(())
+    }
+}
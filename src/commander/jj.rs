@@ -5,17 +5,130 @@ The module implementes a number of jj commands.
 Surprisingly, this module also contains jj bookmark commands.
 These functions are used everywhere (bookmark tab, log tab).
 */
-use crate::commander::{CommandError, Commander, bookmarks::Bookmark, ids::CommitId};
-
-use anyhow::{Context, Result};
+use crate::commander::{
+    CommandError, Commander,
+    bookmarks::Bookmark,
+    get_output_args,
+    history::{CommandRecord, record_command},
+    hooks::{HookAction, HookContext, HookStage},
+    hunks::{ExtractedFile, revert_hunks},
+    ids::CommitId,
+    log::{Head, LogRevision, path_fileset},
+    running, shell_command,
+};
+
+use anyhow::{Context, Result, bail};
+use std::{
+    fs,
+    io::{Read, Write},
+    process::{Command, Stdio},
+    sync::mpsc::Sender,
+    thread,
+    time::Instant,
+};
+use tempdir::TempDir;
 use tracing::instrument;
 
+/// Environment making git fail instead of hanging when it wants to prompt on the terminal used
+/// by the TUI, for credentials, an ssh passphrase or to confirm an unknown host key. A
+/// `GIT_SSH_COMMAND` of the user is kept.
+fn non_interactive_git_env() -> Vec<(&'static str, &'static str)> {
+    let mut env = vec![("GIT_TERMINAL_PROMPT", "0")];
+    if std::env::var_os("GIT_SSH_COMMAND").is_none() {
+        env.push((
+            "GIT_SSH_COMMAND",
+            "ssh -o BatchMode=yes -o ConnectTimeout=10",
+        ));
+    }
+    env
+}
+
+/// Change [Commander::run_move_files] moves the changes of files into
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FileMoveTarget {
+    Parent,
+    /// The only child of the change
+    Child,
+    /// A new change inserted after the change
+    NewChild,
+}
+
 impl Commander {
     /// Create a new change after revision. Maps to `jj new <revision>`
     #[instrument(level = "trace", skip(self))]
     pub fn run_new(&self, revision: &str) -> Result<()> {
+        let context = HookContext {
+            revision: Some(revision),
+            ..Default::default()
+        };
+        self.run_hook(HookStage::Pre, HookAction::New, &context)?;
         self.execute_void_jj_command(vec!["new", revision])
-            .context("Failed executing jj new")
+            .context("Failed executing jj new")?;
+        Ok(self.run_hook(HookStage::Post, HookAction::New, &context)?)
+    }
+
+    /// Create a new change placed relative to revision and return it. `placement` is
+    /// `--insert-after` or `--insert-before`, or None for a child of revision. Without editing
+    /// the new change, `@` stays where it is.
+    /// Maps to `jj new [--insert-after|--insert-before] <revision> [--no-edit]`, and without
+    /// editing to `jj log -r '<relatives> ~ at_operation(<operation>, <relatives>)'` for the
+    /// child or parent of revision which didn't exist before
+    #[instrument(level = "trace", skip(self))]
+    pub fn run_new_placed(
+        &self,
+        revision: &str,
+        placement: Option<&str>,
+        edit: bool,
+    ) -> Result<Head> {
+        let context = HookContext {
+            revision: Some(revision),
+            ..Default::default()
+        };
+        self.run_hook(HookStage::Pre, HookAction::New, &context)?;
+        // After snapshotting the working copy, so that only jj new changes the relatives
+        let placed_at = self.get_head(revision)?;
+        let operation = self.get_current_operation()?;
+        let mut args = vec!["new"];
+        args.extend(placement);
+        args.push(revision);
+        if !edit {
+            args.push("--no-edit");
+        }
+        self.execute_void_jj_command(args)
+            .context("Failed executing jj new")?;
+        self.run_hook(HookStage::Post, HookAction::New, &context)?;
+
+        if edit {
+            return self.get_current_head();
+        }
+        // Inserted before revision, the new change is its only parent, otherwise a new child
+        let relatives = match placement {
+            Some("--insert-before") => "parents",
+            _ => "children",
+        };
+        let relatives = format!("{relatives}(change_id({}))", placed_at.change_id.as_str());
+        self.get_head(&format!(
+            "{relatives} ~ at_operation({operation}, {relatives})"
+        ))
+        .context("Failed finding the new change")
+    }
+
+    /// Create a merge change of `revisions` with a description. Maps to
+    /// `jj new <revisions...> -m <message>`
+    #[instrument(level = "trace", skip(self))]
+    pub fn run_new_merge(&self, revisions: &[&str], message: &str) -> Result<()> {
+        let revset = revisions.join(" | ");
+        let context = HookContext {
+            revision: Some(&revset),
+            ..Default::default()
+        };
+        self.run_hook(HookStage::Pre, HookAction::New, &context)?;
+        let mut args = vec!["new"];
+        args.extend(revisions);
+        args.extend(["-m", message]);
+        self.execute_void_jj_command(args)
+            .context("Failed executing jj new")?;
+        Ok(self.run_hook(HookStage::Post, HookAction::New, &context)?)
     }
 
     /// Edit change. Maps to `jj edit <commit>`
@@ -26,22 +139,133 @@ impl Commander {
             args.push("--ignore-immutable");
         }
 
+        let context = HookContext {
+            revision: Some(revision),
+            ..Default::default()
+        };
+        self.run_hook(HookStage::Pre, HookAction::Edit, &context)?;
         self.execute_void_jj_command(args)
-            .context("Failed executing jj edit")
+            .context("Failed executing jj edit")?;
+        Ok(self.run_hook(HookStage::Post, HookAction::Edit, &context)?)
     }
 
     /// Abandon change. Maps to `jj abandon <revision>`
     #[instrument(level = "trace", skip(self))]
     pub fn run_abandon(&self, commit_id: &CommitId) -> Result<()> {
+        let context = HookContext {
+            revision: Some(commit_id.as_str()),
+            ..Default::default()
+        };
+        self.run_hook(HookStage::Pre, HookAction::Abandon, &context)?;
         self.execute_void_jj_command(vec!["abandon", commit_id.as_str()])
-            .context("Failed executing jj abandon")
+            .context("Failed executing jj abandon")?;
+        Ok(self.run_hook(HookStage::Post, HookAction::Abandon, &context)?)
     }
 
     /// Describe change. Maps to `jj describe <revision> -m <message>`
     #[instrument(level = "trace", skip(self))]
     pub fn run_describe(&self, revision: &str, message: &str) -> Result<()> {
+        let context = HookContext {
+            revision: Some(revision),
+            description: Some(message),
+        };
+        self.run_hook(HookStage::Pre, HookAction::Describe, &context)?;
         self.execute_void_jj_command(vec!["describe", revision, "-m", message])
-            .context("Failed executing jj describe")
+            .context("Failed executing jj describe")?;
+        Ok(self.run_hook(HookStage::Post, HookAction::Describe, &context)?)
+    }
+
+    /// Draft a description by piping the git diff of a change to `generator`, a shell command.
+    /// Returns the command's stdout.
+    #[instrument(level = "trace", skip(self))]
+    pub fn generate_description(
+        &self,
+        generator: &str,
+        commit_id: &CommitId,
+    ) -> Result<String, CommandError> {
+        let diff =
+            self.execute_jj_command(vec!["diff", "--git", "-r", commit_id.as_str()], false, true)?;
+
+        let mut command = shell_command(generator);
+        command
+            .current_dir(&self.env.root)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        let start = Instant::now();
+        let mut child = command.spawn()?;
+        // Write from another thread, the generator may start printing before reading all input
+        if let Some(mut stdin) = child.stdin.take() {
+            thread::spawn(move || stdin.write_all(diff.as_bytes()));
+        }
+        let output = child.wait_with_output()?;
+        record_command(
+            CommandRecord::new(&command, output.status.code(), start.elapsed())
+                .with_output(&output.stderr),
+        );
+
+        if !output.status.success() {
+            return Err(CommandError::Status(
+                String::from_utf8_lossy(&output.stderr).to_string(),
+                output.status.code(),
+            ));
+        }
+
+        Ok(String::from_utf8(output.stdout)?.trim_end().to_owned())
+    }
+
+    /// Move the changes of `files` out of a change into a new sibling change described with
+    /// `message`. Maps to `jj split -r <revision> --parallel -m <message> <paths>`.
+    /// Files keeping some hunks in the change are given to `jj split` with those hunks reverted,
+    /// copied in by `cp` as the diff editor.
+    #[instrument(level = "trace", skip(self))]
+    pub fn run_extract(
+        &self,
+        commit_id: &CommitId,
+        files: &[ExtractedFile],
+        message: &str,
+    ) -> Result<(), CommandError> {
+        let filesets: Vec<String> = files.iter().map(|file| path_fileset(&file.path)).collect();
+        let mut args: Vec<String> = [
+            "split",
+            "-r",
+            commit_id.as_str(),
+            "--parallel",
+            "-m",
+            message,
+        ]
+        .map(String::from)
+        .to_vec();
+
+        // Keep the directory until jj is done with it
+        let selected_dir = TempDir::new("blazingjj-extract")?;
+        if files.iter().any(|file| !file.kept_hunks.is_empty()) {
+            for file in files.iter().filter(|file| !file.kept_hunks.is_empty()) {
+                let content = self.get_file_content(commit_id.as_str(), &file.path)?;
+                let path = selected_dir.path().join(&file.path);
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(path, revert_hunks(&content, &file.kept_hunks))?;
+            }
+            let copy_args = toml::Value::from(vec![
+                "-R".to_owned(),
+                format!("{}/.", selected_dir.path().display()),
+                "$right".to_owned(),
+            ]);
+            args.extend([
+                "--tool".to_owned(),
+                "blazingjj-extract".to_owned(),
+                "--config".to_owned(),
+                "merge-tools.blazingjj-extract.program=\"cp\"".to_owned(),
+                "--config".to_owned(),
+                format!("merge-tools.blazingjj-extract.edit-args={copy_args}"),
+            ]);
+        }
+        args.extend(filesets);
+
+        self.execute_void_jj_command(args)
     }
 
     /// Rebase changes. Maps to `jj rebase -s <rev> -d <rev>` or similar
@@ -64,8 +288,90 @@ impl Commander {
             args.push("--ignore-immutable");
         }
 
+        let context = HookContext {
+            revision: Some(revision),
+            ..Default::default()
+        };
+        self.run_hook(HookStage::Pre, HookAction::Squash, &context)?;
         self.execute_void_jj_command(args)
-            .context("Failed executing jj squash")
+            .context("Failed executing jj squash")?;
+        Ok(self.run_hook(HookStage::Post, HookAction::Squash, &context)?)
+    }
+
+    /// Move the changes to `paths` in the working copy into `revision`, keeping its description.
+    /// Maps to `jj squash --from @ --into <revision> -u <paths>`
+    #[instrument(level = "trace", skip(self))]
+    pub fn run_amend(&self, revision: &str, paths: &[String]) -> Result<(), CommandError> {
+        let filesets: Vec<String> = paths.iter().map(|path| path_fileset(path)).collect();
+        let mut args = vec!["squash", "--from", "@", "--into", revision, "-u"];
+        args.extend(filesets.iter().map(String::as_str));
+
+        let context = HookContext {
+            revision: Some(revision),
+            ..Default::default()
+        };
+        self.run_hook(HookStage::Pre, HookAction::Squash, &context)?;
+        self.execute_void_jj_command(args)?;
+        self.run_hook(HookStage::Post, HookAction::Squash, &context)
+    }
+
+    /// Move the changes to `paths` out of a change into an adjacent change, keeping both
+    /// descriptions and the change even if it becomes empty.
+    /// Maps to `jj squash --from <commit> --into <target> -u --keep-emptied <paths>`
+    #[instrument(level = "trace", skip(self))]
+    pub fn run_move_files(
+        &self,
+        commit_id: &CommitId,
+        paths: &[String],
+        target: FileMoveTarget,
+    ) -> Result<()> {
+        let into = match target {
+            FileMoveTarget::Parent => format!("{commit_id}-"),
+            FileMoveTarget::Child => {
+                let children = self.execute_jj_command(
+                    [
+                        "log",
+                        "--no-graph",
+                        "-r",
+                        &format!("children({commit_id})"),
+                        "-T",
+                        r#"commit_id ++ "\n""#,
+                    ],
+                    false,
+                    true,
+                )?;
+                match children.lines().collect::<Vec<_>>()[..] {
+                    [child] => child.to_owned(),
+                    [] => bail!("The change has no child to move the files into"),
+                    _ => bail!("The change has several children, move the files into a new one"),
+                }
+            }
+            FileMoveTarget::NewChild => self
+                .run_new_placed(commit_id.as_str(), Some("--insert-after"), false)?
+                .commit_id
+                .to_string(),
+        };
+
+        let filesets: Vec<String> = paths.iter().map(|path| path_fileset(path)).collect();
+        let mut args = vec![
+            "squash",
+            "--from",
+            commit_id.as_str(),
+            "--into",
+            &into,
+            "-u",
+            "--keep-emptied",
+        ];
+        args.extend(filesets.iter().map(String::as_str));
+
+        let context = HookContext {
+            revision: Some(&into),
+            ..Default::default()
+        };
+        self.run_hook(HookStage::Pre, HookAction::Squash, &context)?;
+        self.execute_void_jj_command(args)
+            .context("Failed executing jj squash")?;
+        Ok(self.run_hook(HookStage::Post, HookAction::Squash, &context)?)
     }
 
     /// Create bookmark. Maps to `jj bookmark create <name>`
@@ -128,6 +434,104 @@ impl Commander {
         self.execute_void_jj_command(vec!["bookmark", "delete", name])
     }
 
+    /// Delete a bookmark locally only, keeping it on the remotes where it is tracked. The remote
+    /// bookmarks are untracked first, so the deletion isn't pushed.
+    /// Maps to `jj bookmark untrack <name>@<remote>` followed by `jj bookmark delete <name>`
+    #[instrument(level = "trace", skip(self))]
+    pub fn delete_bookmark_locally(
+        &self,
+        name: &str,
+        remotes: &[String],
+    ) -> Result<(), CommandError> {
+        for remote in remotes {
+            self.execute_void_jj_command(vec!["bookmark", "untrack", &format!("{name}@{remote}")])?;
+        }
+        self.delete_bookmark(name)
+    }
+
+    /// Arguments for pushing the deletion of a bookmark in [Commander::delete_bookmark_on_remotes]
+    pub fn git_push_delete_args(remote: &str, name: &str) -> Vec<String> {
+        [
+            "git",
+            "push",
+            "--remote",
+            remote,
+            "--bookmark",
+            &format!("exact:{name}"),
+        ]
+        .into_iter()
+        .map(String::from)
+        .collect()
+    }
+
+    /// Commit the local bookmark points to, or an error if it doesn't exist or is conflicted.
+    /// `before` ends the hint to resolve the conflict, e.g. "deleting it on the remotes"
+    fn local_bookmark_target(&self, name: &str, before: &str) -> Result<CommitId, CommandError> {
+        let revset = format!(r#"bookmarks(exact:"{name}")"#);
+        let targets = self.execute_jj_command(
+            vec![
+                "log",
+                "--no-graph",
+                "-r",
+                &revset,
+                "--template",
+                r#"commit_id ++ "\n""#,
+            ],
+            false,
+            true,
+        )?;
+        match targets.lines().collect::<Vec<_>>().as_slice() {
+            [commit_id] => Ok(CommitId((*commit_id).to_owned())),
+            [] => Err(CommandError::Status(
+                format!("The bookmark {name} doesn't exist locally"),
+                None,
+            )),
+            _ => Err(CommandError::Status(
+                format!(
+                    "The bookmark {name} is conflicted, resolve it with `jj bookmark set` before \
+                     {before}"
+                ),
+                None,
+            )),
+        }
+    }
+
+    /// Delete a bookmark on the remotes only, keeping it locally. The local bookmark is deleted,
+    /// the deletion pushed and the bookmark set again at its commit, now untracked. It is set
+    /// again if the push fails too. Conflicted bookmarks, with several commits, aren't deleted.
+    /// Maps to `jj bookmark delete <name>`, `jj git push --remote <remote> --bookmark exact:<name>`
+    /// and `jj bookmark set <name> -r <commit>`
+    #[instrument(level = "trace", skip(self))]
+    pub fn delete_bookmark_on_remotes(
+        &self,
+        name: &str,
+        remotes: &[String],
+    ) -> Result<String, CommandError> {
+        let commit_id = self.local_bookmark_target(name, "deleting it on the remotes")?;
+
+        let context = HookContext::default();
+        self.run_hook(HookStage::Pre, HookAction::Push, &context)?;
+        self.delete_bookmark(name)?;
+        let mut output = String::new();
+        let mut pushed = Ok(());
+        for remote in remotes {
+            // The environment is cleared after each command
+            self.set_git_non_interactive();
+            match self.execute_jj_command(Self::git_push_delete_args(remote, name), true, true) {
+                Ok(remote_output) => output.push_str(&remote_output),
+                Err(err) => {
+                    pushed = Err(err);
+                    break;
+                }
+            }
+        }
+        // Restored before returning the error of the push
+        self.execute_void_jj_command(vec!["bookmark", "set", name, "-r", commit_id.as_str()])?;
+        pushed?;
+        self.run_hook(HookStage::Post, HookAction::Push, &context)?;
+        Ok(output)
+    }
+
     /// Forget bookmark. Maps to `jj bookmark forget <name>`
     #[instrument(level = "trace", skip(self))]
     pub fn forget_bookmark(&self, name: &str) -> Result<(), CommandError> {
@@ -146,14 +550,12 @@ impl Commander {
         self.execute_void_jj_command(vec!["bookmark", "untrack", &bookmark.to_string()])
     }
 
-    /// Git push. Maps to `jj git push`
-    #[instrument(level = "trace", skip(self))]
-    pub fn git_push(
-        &self,
+    /// Arguments for [Commander::git_push]
+    pub fn git_push_args(
         all_bookmarks: bool,
         allow_new: bool,
         commit_id: &CommitId,
-    ) -> Result<String, CommandError> {
+    ) -> Vec<String> {
         let mut args = vec!["git", "push"];
         if allow_new {
             args.push("--allow-new");
@@ -165,25 +567,359 @@ impl Commander {
             args.push(commit_id.as_str());
         }
 
-        self.execute_jj_command(args, true, true)
+        args.into_iter().map(String::from).collect()
     }
 
-    /// Git fetch. Maps to `jj git fetch`
+    /// Git push. Maps to `jj git push`
     #[instrument(level = "trace", skip(self))]
-    pub fn git_fetch(&self, all_remotes: bool) -> Result<String, CommandError> {
+    pub fn git_push(
+        &self,
+        all_bookmarks: bool,
+        allow_new: bool,
+        commit_id: &CommitId,
+    ) -> Result<String, CommandError> {
+        let context = HookContext {
+            revision: (!all_bookmarks).then_some(commit_id.as_str()),
+            ..Default::default()
+        };
+        self.run_hook(HookStage::Pre, HookAction::Push, &context)?;
+        self.set_git_non_interactive();
+        let output = self.execute_jj_command(
+            Self::git_push_args(all_bookmarks, allow_new, commit_id),
+            true,
+            true,
+        )?;
+        self.run_hook(HookStage::Post, HookAction::Push, &context)?;
+        Ok(output)
+    }
+
+    /// Arguments for [Commander::git_push_bookmark]
+    pub fn git_push_bookmark_args(name: &str, allow_new: bool) -> Vec<String> {
+        let mut args = vec!["git".to_owned(), "push".to_owned()];
+        if allow_new {
+            args.push("--allow-new".to_owned());
+        }
+        args.extend(["--bookmark".to_owned(), format!("exact:{name}")]);
+        args
+    }
+
+    /// Git push of a single bookmark. Maps to `jj git push --bookmark exact:<name>`
+    #[instrument(level = "trace", skip(self))]
+    pub fn git_push_bookmark(&self, name: &str, allow_new: bool) -> Result<String, CommandError> {
+        let context = HookContext::default();
+        self.run_hook(HookStage::Pre, HookAction::Push, &context)?;
+        self.set_git_non_interactive();
+        let output =
+            self.execute_jj_command(Self::git_push_bookmark_args(name, allow_new), true, true)?;
+        self.run_hook(HookStage::Post, HookAction::Push, &context)?;
+        Ok(output)
+    }
+
+    /// Arguments for [Commander::git_push_rename]
+    pub fn git_push_rename_args(remote: &str, old: &str, new: &str) -> Vec<String> {
+        [
+            "git",
+            "push",
+            "--remote",
+            remote,
+            "--allow-new",
+            "--bookmark",
+            &format!("exact:{new}"),
+            "--bookmark",
+            &format!("exact:{old}"),
+        ]
+        .into_iter()
+        .map(String::from)
+        .collect()
+    }
+
+    /// Push a bookmark renamed locally with [Commander::rename_bookmark] to the remote: push the
+    /// new name and delete the old one, which is still tracked there.
+    /// Maps to `jj git push --remote <remote> --allow-new --bookmark <new> --bookmark <old>`
+    #[instrument(level = "trace", skip(self))]
+    pub fn git_push_rename(
+        &self,
+        remote: &str,
+        old: &str,
+        new: &str,
+    ) -> Result<String, CommandError> {
+        let context = HookContext::default();
+        self.run_hook(HookStage::Pre, HookAction::Push, &context)?;
+        self.set_git_non_interactive();
+        let output =
+            self.execute_jj_command(Self::git_push_rename_args(remote, old, new), true, true)?;
+        self.run_hook(HookStage::Post, HookAction::Push, &context)?;
+        Ok(output)
+    }
+
+    /// Overwrite bookmarks which moved on the remote with their local targets, after a push was
+    /// rejected. jj refuses to push them until the remote changes were fetched: they are
+    /// fetched, which makes the bookmarks conflicted, set back to their local targets and pushed.
+    /// Maps to `jj git fetch --branch exact:<name>`, `jj bookmark set <name> -r <commit>` and
+    /// `jj git push --bookmark exact:<name>`
+    #[instrument(level = "trace", skip(self))]
+    pub fn git_force_push_bookmarks(&self, names: &[String]) -> Result<String, CommandError> {
+        let targets = names
+            .iter()
+            .map(|name| self.local_bookmark_target(name, "force-pushing it"))
+            .collect::<Result<Vec<_>, _>>()?;
+        let patterns: Vec<String> = names.iter().map(|name| format!("exact:{name}")).collect();
+        self.git_fetch_bookmarks(None, &patterns)?;
+        for (name, commit_id) in names.iter().zip(&targets) {
+            self.set_bookmark_commit(name, commit_id)?;
+        }
+
+        let context = HookContext::default();
+        self.run_hook(HookStage::Pre, HookAction::Push, &context)?;
+        self.set_git_non_interactive();
+        let mut args = vec!["git".to_owned(), "push".to_owned()];
+        for pattern in patterns {
+            args.extend(["--bookmark".to_owned(), pattern]);
+        }
+        let output = self.execute_jj_command(args, true, true)?;
+        self.run_hook(HookStage::Post, HookAction::Push, &context)?;
+        Ok(output)
+    }
+
+    /// Arguments for [Commander::git_fetch]
+    pub fn git_fetch_args(all_remotes: bool) -> Vec<String> {
         let mut args = vec!["git", "fetch"];
         if all_remotes {
             args.push("--all-remotes");
         }
 
-        self.execute_jj_command(args, true, true)
+        args.into_iter().map(String::from).collect()
+    }
+
+    /// Git fetch. Maps to `jj git fetch`
+    #[instrument(level = "trace", skip(self))]
+    pub fn git_fetch(&self, all_remotes: bool) -> Result<String, CommandError> {
+        self.run_git_fetch(Self::git_fetch_args(all_remotes))
+    }
+
+    /// Arguments for [Commander::git_fetch_bookmarks]
+    pub fn git_fetch_bookmarks_args(remote: Option<&str>, patterns: &[String]) -> Vec<String> {
+        let mut args = vec!["git".to_owned(), "fetch".to_owned()];
+        if let Some(remote) = remote {
+            args.extend(["--remote".to_owned(), remote.to_owned()]);
+        }
+        for pattern in patterns {
+            args.extend(["--branch".to_owned(), pattern.clone()]);
+        }
+        args
+    }
+
+    /// Git fetch of only the bookmarks matching the patterns, e.g. `main` or
+    /// `glob:feature/*`, from `remote` or the default remotes.
+    /// Maps to `jj git fetch --remote <remote> --branch <pattern>`
+    #[instrument(level = "trace", skip(self))]
+    pub fn git_fetch_bookmarks(
+        &self,
+        remote: Option<&str>,
+        patterns: &[String],
+    ) -> Result<String, CommandError> {
+        self.run_git_fetch(Self::git_fetch_bookmarks_args(remote, patterns))
+    }
+
+    /// Git remotes of the repository with their URLs. Maps to `jj git remote list`
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_git_remotes(&self) -> Result<Vec<(String, String)>, CommandError> {
+        Ok(self
+            .execute_jj_command(vec!["git", "remote", "list"], false, true)?
+            .lines()
+            .filter_map(|line| {
+                let (name, url) = line.split_once(' ')?;
+                Some((name.to_owned(), url.trim().to_owned()))
+            })
+            .collect())
+    }
+
+    /// Change the URL of a git remote. Maps to `jj git remote set-url <name> <url>`
+    #[instrument(level = "trace", skip(self))]
+    pub fn set_git_remote_url(&self, name: &str, url: &str) -> Result<(), CommandError> {
+        self.execute_void_jj_command(vec!["git", "remote", "set-url", name, url])
     }
+
+    /// Check that a git remote can be reached without prompting for credentials.
+    /// Maps to `git ls-remote --heads <url>`
+    #[instrument(level = "trace", skip(self))]
+    pub fn git_ls_remote(&self, url: &str) -> Result<(), CommandError> {
+        self.set_git_non_interactive();
+        self.execute_command(Command::new("git").args(["ls-remote", "--heads", url]))?;
+        Ok(())
+    }
+
+    /// Set [non_interactive_git_env] for the next command
+    pub(super) fn set_git_non_interactive(&self) {
+        for (var, value) in non_interactive_git_env() {
+            self.set_env(var, value);
+        }
+    }
+
+    fn run_git_fetch(&self, args: Vec<String>) -> Result<String, CommandError> {
+        let context = HookContext::default();
+        self.run_hook(HookStage::Pre, HookAction::Fetch, &context)?;
+        self.set_git_non_interactive();
+        let output = self.execute_jj_command(args, true, true)?;
+        self.run_hook(HookStage::Post, HookAction::Fetch, &context)?;
+        Ok(output)
+    }
+
+    /// Clone a git repository into `destination`, relative to the root of the environment. Maps
+    /// to `jj git clone <url> <destination>`
+    ///
+    /// Every line jj prints to stderr is sent to `progress` while the clone runs. Returns all of
+    /// them once it finished.
+    #[instrument(level = "trace", skip(self, progress))]
+    pub fn git_clone(
+        &self,
+        url: &str,
+        destination: &str,
+        progress: Sender<String>,
+    ) -> Result<String, CommandError> {
+        let mut command = Command::new(&self.env.jj_bin);
+        command
+            .args(["git", "clone", url, destination])
+            .args(get_output_args(false, false))
+            .current_dir(&self.env.root)
+            .envs(non_interactive_git_env())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped());
+        self.add_config_args(&mut command);
+
+        let start = Instant::now();
+        let command_line = CommandRecord::new(&command, None, start.elapsed()).command_line();
+        // Started like the commands of the loader's operation, so that cancelling it kills the
+        // clone
+        let Some(child) = running::spawn(&mut command)? else {
+            return Err(CommandError::Killed(command_line, 0));
+        };
+        let mut output = vec![];
+        if let Some(mut stderr) = child.take_stderr() {
+            // Progress like "Receiving objects:  45% (123/456)" redraws its line with carriage
+            // returns, send each state as soon as it is printed instead of waiting for the line
+            let mut buffer = [0; 4096];
+            let mut line = vec![];
+            loop {
+                let read = stderr.read(&mut buffer)?;
+                for &byte in &buffer[..read] {
+                    if byte != b'\r' && byte != b'\n' {
+                        line.push(byte);
+                        continue;
+                    }
+                    let text = String::from_utf8_lossy(&line);
+                    if !text.trim().is_empty() {
+                        let _ = progress.send(text.trim_end().to_owned());
+                    }
+                    line.clear();
+                }
+                output.extend_from_slice(&buffer[..read]);
+                if read == 0 {
+                    break;
+                }
+            }
+        }
+        let output = String::from_utf8_lossy(&output).into_owned();
+        let (status, killed) = child.wait()?;
+        record_command(
+            CommandRecord::new(&command, status.code(), start.elapsed())
+                .with_output(output.as_bytes()),
+        );
+
+        if killed {
+            return Err(CommandError::Killed(
+                command_line,
+                start.elapsed().as_secs(),
+            ));
+        }
+        if !status.success() {
+            return Err(CommandError::Status(output, status.code()));
+        }
+
+        Ok(output)
+    }
+}
+
+/// Directory `jj git clone` would clone `url` into if no destination is given, i.e. the last
+/// component of the url without a `.git` suffix
+pub fn clone_destination(url: &str) -> Option<String> {
+    let name = url
+        .trim_end_matches(['/', '\\'])
+        .rsplit(['/', '\\', ':'])
+        .next()?;
+    let name = name.strip_suffix(".git").unwrap_or(name);
+    (!name.is_empty()).then(|| name.to_owned())
+}
+
+/// Render the description of a merge change from `template`. `{parents}` is replaced with the
+/// names of the parents and `{changes}` with a line per parent with its name and title. Parents
+/// are named after their first bookmark, or their change id if they have none.
+pub fn render_merge_template(template: &str, parents: &[LogRevision]) -> String {
+    let name = |revision: &LogRevision| {
+        revision
+            .bookmarks
+            .first()
+            .or(revision.remote_bookmarks.first())
+            .cloned()
+            .unwrap_or_else(|| revision.change_id.as_str().chars().take(8).collect())
+    };
+    let names = parents.iter().map(name).collect::<Vec<_>>().join(", ");
+    let changes = parents
+        .iter()
+        .map(|revision| {
+            let title = match revision.title() {
+                "" => "(no description set)",
+                title => title,
+            };
+            format!("- {}: {title}", name(revision))
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    template
+        .replace("{parents}", &names)
+        .replace("{changes}", &changes)
+        .trim_end()
+        .to_owned()
+}
+
+/// Render a reference to a change from `template`, e.g. for a commit message or a chat.
+/// `{change_id}` and `{commit_id}` are replaced with the short ids of the change, `{title}` with
+/// its title and `{url}` with `url`, its page on the forge.
+pub fn render_reference_template(template: &str, revision: &LogRevision, url: &str) -> String {
+    let title = match revision.title() {
+        "" => "(no description set)",
+        title => title,
+    };
+    template
+        .replace(
+            "{change_id}",
+            &revision
+                .change_id
+                .as_str()
+                .chars()
+                .take(8)
+                .collect::<String>(),
+        )
+        .replace(
+            "{commit_id}",
+            &revision
+                .commit_id
+                .as_str()
+                .chars()
+                .take(8)
+                .collect::<String>(),
+        )
+        .replace("{url}", url)
+        // Last, so braces in the title are kept
+        .replace("{title}", title)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::commander::tests::TestRepo;
+    use crate::commander::{ids::ChangeId, log::Head, tests::TestRepo};
+    use std::fs;
+    use tempdir::TempDir;
 
     #[test]
     fn run_new() -> Result<()> {
@@ -196,6 +932,300 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn run_new_placed() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+        test_repo
+            .commander
+            .execute_void_jj_command(["describe", "-m", "first"])?;
+        let first = test_repo.commander.get_current_head()?;
+
+        let child = test_repo
+            .commander
+            .run_new_placed(first.commit_id.as_str(), None, false)?;
+        assert_ne!(child.change_id, first.change_id);
+        assert_eq!(
+            test_repo.commander.get_current_head()?.change_id,
+            first.change_id
+        );
+        // Not the child which existed before
+        let second_child =
+            test_repo
+                .commander
+                .run_new_placed(first.commit_id.as_str(), None, false)?;
+        assert_ne!(second_child.change_id, child.change_id);
+        assert_ne!(second_child.change_id, first.change_id);
+
+        let after = test_repo.commander.run_new_placed(
+            first.commit_id.as_str(),
+            Some("--insert-after"),
+            false,
+        )?;
+        assert!(
+            ![&first, &child, &second_child]
+                .iter()
+                .any(|head| head.change_id == after.change_id)
+        );
+
+        let before = test_repo.commander.run_new_placed(
+            first.commit_id.as_str(),
+            Some("--insert-before"),
+            true,
+        )?;
+        assert_eq!(test_repo.commander.get_current_head()?, before);
+        let first_parent = || {
+            test_repo.commander.execute_jj_command(
+                [
+                    "log",
+                    "--no-graph",
+                    "-r",
+                    &format!("{}-", first.change_id.as_str()),
+                    "-T",
+                    "change_id",
+                ],
+                false,
+                true,
+            )
+        };
+        assert_eq!(first_parent()?.trim(), before.change_id.as_str());
+
+        let before = test_repo.commander.run_new_placed(
+            first.change_id.as_str(),
+            Some("--insert-before"),
+            false,
+        )?;
+        assert_eq!(first_parent()?.trim(), before.change_id.as_str());
+
+        Ok(())
+    }
+
+    #[test]
+    fn run_move_files() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+        let files = |commander: &Commander, revision: &str| -> Result<String> {
+            Ok(commander.execute_jj_command(
+                ["diff", "--name-only", "-r", revision],
+                false,
+                true,
+            )?)
+        };
+
+        test_repo
+            .commander
+            .execute_void_jj_command(["describe", "-m", "parent"])?;
+        test_repo.commander.execute_void_jj_command(["new"])?;
+        fs::write(test_repo.directory.path().join("a"), "a")?;
+        fs::write(test_repo.directory.path().join("b"), "b")?;
+        let head = test_repo.commander.get_current_head()?;
+
+        test_repo.commander.run_move_files(
+            &head.commit_id,
+            &["a".to_owned()],
+            FileMoveTarget::Parent,
+        )?;
+        assert_eq!(files(&test_repo.commander, "@-")?, "a\n");
+        assert_eq!(files(&test_repo.commander, "@")?, "b\n");
+
+        let head = test_repo.commander.get_head_latest(&head)?;
+        test_repo.commander.run_move_files(
+            &head.commit_id,
+            &["b".to_owned()],
+            FileMoveTarget::NewChild,
+        )?;
+        // The emptied change is kept, with the new change inserted after it
+        assert_eq!(files(&test_repo.commander, "@")?, "");
+        assert_eq!(files(&test_repo.commander, "children(@)")?, "b\n");
+
+        let parent = test_repo.commander.get_head("@-")?;
+        test_repo.commander.run_move_files(
+            &parent.commit_id,
+            &["a".to_owned()],
+            FileMoveTarget::Child,
+        )?;
+        assert_eq!(files(&test_repo.commander, "@")?, "a\n");
+
+        let parent = test_repo.commander.get_head("@-")?;
+        test_repo.commander.execute_void_jj_command([
+            "new",
+            "--no-edit",
+            parent.commit_id.as_str(),
+        ])?;
+        assert!(
+            test_repo
+                .commander
+                .run_move_files(&parent.commit_id, &["a".to_owned()], FileMoveTarget::Child)
+                .is_err()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn run_new_merge() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+
+        let first = test_repo.commander.get_current_head()?;
+        test_repo.commander.run_new("root()")?;
+        let second = test_repo.commander.get_current_head()?;
+        test_repo.commander.run_new_merge(
+            &[first.commit_id.as_str(), second.commit_id.as_str()],
+            "Merge",
+        )?;
+
+        let merge = test_repo.commander.get_current_head()?;
+        assert_eq!(
+            test_repo
+                .commander
+                .get_commit_description(&merge.commit_id)?,
+            "Merge"
+        );
+        let parents = test_repo.commander.execute_jj_command(
+            vec![
+                "log",
+                "--no-graph",
+                "-r",
+                "@-",
+                "-T",
+                r#"commit_id ++ "\n""#,
+            ],
+            false,
+            true,
+        )?;
+        assert_eq!(parents.lines().count(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn merge_template() {
+        let revision = |change_id: &str, description: &str, bookmarks: &[&str]| LogRevision {
+            change_id: ChangeId(change_id.to_owned()),
+            commit_id: CommitId(change_id.to_owned()),
+            description: description.to_owned(),
+            author_name: String::new(),
+            author_email: String::new(),
+            bookmarks: bookmarks
+                .iter()
+                .map(|bookmark| bookmark.to_string())
+                .collect(),
+            remote_bookmarks: vec![],
+            divergent: false,
+            immutable: false,
+            conflict: false,
+            empty: false,
+            working_copy: false,
+        };
+        let parents = [
+            revision("kkmpptxzrspx", "Add feature\n\nDetails", &["feature"]),
+            revision("zzzzzzzzzzzz", "", &[]),
+        ];
+
+        assert_eq!(
+            render_merge_template("Merge {parents}\n\n{changes}", &parents),
+            "Merge feature, zzzzzzzz\n\n- feature: Add feature\n- zzzzzzzz: (no description set)"
+        );
+        assert_eq!(
+            render_merge_template("Merge {parents}\n\n{changes}", &[]),
+            "Merge"
+        );
+    }
+
+    #[test]
+    fn reference_template() {
+        let revision = LogRevision {
+            change_id: ChangeId("kkmpptxzrspx".to_owned()),
+            commit_id: CommitId("0123456789ab".to_owned()),
+            description: "Fix {url} race\n\nDetails".to_owned(),
+            author_name: String::new(),
+            author_email: String::new(),
+            bookmarks: vec![],
+            remote_bookmarks: vec![],
+            divergent: false,
+            immutable: false,
+            conflict: false,
+            empty: false,
+            working_copy: false,
+        };
+
+        assert_eq!(
+            render_reference_template("{change_id} \"{title}\"", &revision, ""),
+            "kkmpptxz \"Fix {url} race\""
+        );
+        assert_eq!(
+            render_reference_template(
+                "[{title}]({url}) {commit_id}",
+                &revision,
+                "https://example.com/0123456789ab"
+            ),
+            "[Fix {url} race](https://example.com/0123456789ab) 01234567"
+        );
+    }
+
+    #[test]
+    fn git_push_rename_args() {
+        assert_eq!(
+            Commander::git_push_rename_args("origin", "old", "new"),
+            [
+                "git",
+                "push",
+                "--remote",
+                "origin",
+                "--allow-new",
+                "--bookmark",
+                "exact:new",
+                "--bookmark",
+                "exact:old"
+            ]
+        );
+    }
+
+    #[test]
+    fn git_push_bookmark_args() {
+        assert_eq!(
+            Commander::git_push_bookmark_args("main", true),
+            ["git", "push", "--allow-new", "--bookmark", "exact:main"]
+        );
+    }
+
+    #[test]
+    fn git_push_delete_args() {
+        assert_eq!(
+            Commander::git_push_delete_args("origin", "main"),
+            [
+                "git",
+                "push",
+                "--remote",
+                "origin",
+                "--bookmark",
+                "exact:main"
+            ]
+        );
+    }
+
+    #[test]
+    fn git_fetch_bookmarks_args() {
+        assert_eq!(
+            Commander::git_fetch_bookmarks_args(
+                Some("upstream"),
+                &["main".to_owned(), "glob:feature/*".to_owned()]
+            ),
+            [
+                "git",
+                "fetch",
+                "--remote",
+                "upstream",
+                "--branch",
+                "main",
+                "--branch",
+                "glob:feature/*"
+            ]
+        );
+        assert_eq!(
+            Commander::git_fetch_bookmarks_args(None, &["main".to_owned()]),
+            ["git", "fetch", "--branch", "main"]
+        );
+    }
+
     #[test]
     fn run_edit() -> Result<()> {
         let test_repo = TestRepo::new()?;
@@ -237,6 +1267,35 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn run_amend() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+
+        let target = test_repo.commander.get_current_head()?;
+        test_repo.commander.run_new(target.commit_id.as_str())?;
+        fs::write(test_repo.directory.path().join("a"), b"a")?;
+        fs::write(test_repo.directory.path().join("b"), b"b")?;
+
+        test_repo
+            .commander
+            .run_amend(target.change_id.as_str(), &["a".to_owned()])?;
+
+        let target = test_repo.commander.get_head_latest(&target)?;
+        let paths = |head: &Head| -> Result<Vec<Option<String>>> {
+            Ok(test_repo
+                .commander
+                .get_files(head)?
+                .into_iter()
+                .map(|file| file.path)
+                .collect())
+        };
+        assert_eq!(paths(&target)?, [Some("a".to_owned())]);
+        let head = test_repo.commander.get_current_head()?;
+        assert_eq!(paths(&head)?, [Some("b".to_owned())]);
+
+        Ok(())
+    }
+
     #[test]
     fn create_bookmark() -> Result<()> {
         let test_repo = TestRepo::new()?;
@@ -403,6 +1462,83 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn delete_bookmark_on_remotes_keeps_local() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+        let bookmark = test_repo.commander.create_bookmark("test")?;
+        let head = test_repo.commander.get_current_head()?;
+
+        // Without remotes there is nothing to push, the bookmark is set again
+        test_repo
+            .commander
+            .delete_bookmark_on_remotes(&bookmark.name, &[])?;
+
+        let bookmarks = test_repo.commander.get_bookmarks_list(false)?;
+        assert_eq!(bookmarks.len(), 1);
+        assert_eq!(bookmarks[0].name, bookmark.name);
+        assert_eq!(
+            test_repo.commander.get_head(&bookmark.name)?.commit_id,
+            head.commit_id
+        );
+
+        // The bookmark is restored if the push fails because the remote is gone
+        let remote = TempDir::new("blazingjj-remote")?;
+        Command::new("git")
+            .args(["init", "--bare"])
+            .arg(remote.path())
+            .output()?;
+        test_repo.commander.execute_void_jj_command(vec![
+            "git",
+            "remote",
+            "add",
+            "origin",
+            &remote.path().to_string_lossy(),
+        ])?;
+        test_repo.commander.run_describe("@", "test")?;
+        test_repo.commander.execute_void_jj_command(vec![
+            "git",
+            "push",
+            "--remote",
+            "origin",
+            "--bookmark",
+            &bookmark.name,
+        ])?;
+        let head = test_repo.commander.get_current_head()?;
+        remote.close()?;
+        assert!(
+            test_repo
+                .commander
+                .delete_bookmark_on_remotes(&bookmark.name, &["origin".to_owned()])
+                .is_err()
+        );
+        assert_eq!(
+            test_repo.commander.get_head(&bookmark.name)?.commit_id,
+            head.commit_id
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn set_git_remote_url() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+        test_repo.commander.execute_void_jj_command(vec![
+            "git",
+            "remote",
+            "add",
+            "origin",
+            "https://example.com/old.git",
+        ])?;
+
+        test_repo
+            .commander
+            .set_git_remote_url("origin", "git@example.com:new.git")?;
+        assert_eq!(
+            test_repo.commander.get_git_remotes()?,
+            [("origin".to_owned(), "git@example.com:new.git".to_owned())]
+        );
+        Ok(())
+    }
+
     #[test]
     fn forget_bookmark() -> Result<()> {
         let test_repo = TestRepo::new()?;
@@ -427,4 +1563,38 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn git_clone() -> Result<()> {
+        let source = TestRepo::new()?;
+        source.commander.create_bookmark("main")?;
+        let test_repo = TestRepo::new()?;
+
+        let url = source.directory.path().to_string_lossy().to_string();
+        let (tx, rx) = std::sync::mpsc::channel();
+        test_repo.commander.git_clone(&url, "clone", tx)?;
+
+        let clone = test_repo.directory.path().join("clone");
+        assert!(clone.join(".jj").is_dir());
+        assert!(rx.try_iter().count() > 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn clone_destinations() {
+        assert_eq!(
+            clone_destination("https://github.com/jj-vcs/jj.git"),
+            Some("jj".to_owned())
+        );
+        assert_eq!(
+            clone_destination("git@github.com:jj-vcs/jj"),
+            Some("jj".to_owned())
+        );
+        assert_eq!(
+            clone_destination("git@example.com:blazingjj/"),
+            Some("blazingjj".to_owned())
+        );
+        assert_eq!(clone_destination("/"), None);
+    }
 }
@@ -2,24 +2,29 @@
 [Commander] member functions related to jj log.
 
 This module has features to parse the log output to extract change id and commit id.
-It is mostly used in the [log_tab][crate::ui::log_tab] module.
+Besides the rendered graph, the log is also requested with [REVISION_TEMPLATE] to get
+structured [LogRevision]s, and optionally with [TRUNK_RELATION_TEMPLATE] to get the
+[TrunkRelation] of each revision. It is mostly used in the [log_tab][crate::ui::log_tab] module.
 */
 
 use crate::{
     commander::{
         CommandError, Commander, RemoveEndLine,
         bookmarks::Bookmark,
+        files::{File, slash_path},
         ids::{ChangeId, CommitId},
+        stream::StreamingOutput,
     },
-    env::DiffFormat,
+    env::{DiffFormat, DiffOverride, PathPattern},
 };
 
 use anyhow::{Context, Result, anyhow, bail};
 use itertools::Itertools;
 use regex::Regex;
-use std::{fmt::Display, sync::LazyLock};
+use serde::Deserialize;
+use std::{collections::HashMap, fmt::Display, sync::LazyLock};
 use thiserror::Error;
-use tracing::instrument;
+use tracing::{instrument, warn};
 
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
 pub struct Head {
@@ -29,12 +34,58 @@ pub struct Head {
     pub immutable: bool,
 }
 
+/// A revision of the log with the fields of [REVISION_TEMPLATE]
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct LogRevision {
+    pub change_id: ChangeId,
+    pub commit_id: CommitId,
+    pub description: String,
+    pub author_name: String,
+    pub author_email: String,
+    /// Local bookmarks
+    pub bookmarks: Vec<String>,
+    /// Remote bookmarks as `<name>@<remote>`
+    pub remote_bookmarks: Vec<String>,
+    pub divergent: bool,
+    pub immutable: bool,
+    pub conflict: bool,
+    pub empty: bool,
+    /// Whether this is the working copy of the current workspace
+    pub working_copy: bool,
+}
+
+impl LogRevision {
+    pub fn head(&self) -> Head {
+        Head {
+            change_id: self.change_id.clone(),
+            commit_id: self.commit_id.clone(),
+            divergent: self.divergent,
+            immutable: self.immutable,
+        }
+    }
+
+    /// First line of the description
+    pub fn title(&self) -> &str {
+        self.description.lines().next().unwrap_or_default()
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct LogOutput {
     pub graph: String,
     // Maps graph line -> heads
     pub graph_heads: Vec<Option<Head>>,
     pub heads: Vec<Head>,
+    /// Revisions in log order, empty if they could not be parsed
+    pub revisions: Vec<LogRevision>,
+}
+
+impl LogOutput {
+    pub fn revision(&self, head: &Head) -> Option<&LogRevision> {
+        self.revisions
+            .iter()
+            .find(|revision| revision.commit_id == head.commit_id)
+    }
 }
 
 #[derive(Error, Debug)]
@@ -48,14 +99,14 @@ impl Display for HeadParseError {
 
 // Template which outputs `[change_id|commit_id|divergent]`. Used to parse data from log and other
 // commands which supports templating.
-const HEAD_TEMPLATE: &str =
+pub(super) const HEAD_TEMPLATE: &str =
     r#""[" ++ change_id ++ "|" ++ commit_id ++ "|" ++ divergent ++ "|" ++ immutable ++ "]""#;
 // Regex to parse HEAD_TEMPLATE
 static HEAD_TEMPLATE_REGEX: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"\[(.*)\|(.*)\|(.*)\|(.*)\]").unwrap());
 
 // Parse a head with HEAD_TEMPLATE.
-fn parse_head(text: &str) -> Result<Head> {
+pub(super) fn parse_head(text: &str) -> Result<Head> {
     let captured = HEAD_TEMPLATE_REGEX.captures(text);
     captured
         .as_ref()
@@ -78,45 +129,436 @@ fn parse_head(text: &str) -> Result<Head> {
         })
 }
 
+// Template which outputs the fields of LogRevision as a TOML inline table, one line per revision.
+// The json() output of strings and booleans is valid TOML.
+const REVISION_TEMPLATE: &str = r#"concat(
+  "{ change_id = ", json(stringify(change_id)),
+  ", commit_id = ", json(stringify(commit_id)),
+  ", description = ", json(description),
+  ", author_name = ", json(author.name()),
+  ", author_email = ", json(stringify(author.email())),
+  ", bookmarks = [", local_bookmarks.map(|b| json(stringify(b.name()))).join(", "), "]",
+  ", remote_bookmarks = [",
+    remote_bookmarks.map(|b| json(stringify(b.name() ++ "@" ++ b.remote()))).join(", "), "]",
+  ", divergent = ", divergent,
+  ", immutable = ", immutable,
+  ", conflict = ", conflict,
+  ", empty = ", empty,
+  ", working_copy = ", current_working_copy,
+  " }\n",
+)"#;
+
+// Parse a line of REVISION_TEMPLATE output
+fn parse_revision(line: &str) -> Result<LogRevision> {
+    #[derive(Deserialize)]
+    struct RevisionLine {
+        revision: LogRevision,
+    }
+
+    let parsed: RevisionLine = toml::from_str(&format!("revision = {line}"))
+        .with_context(|| format!("Failed parsing log revision: {line}"))?;
+    Ok(parsed.revision)
+}
+
+/// Relationship of a revision to `trunk()`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrunkRelation {
+    /// Ancestor of `trunk()`, already landed
+    Merged,
+    /// Descendant of `trunk()` which has not landed yet
+    Ahead,
+    /// Neither, e.g. based on an older trunk commit or on another branch
+    Unrelated,
+}
+
+impl TrunkRelation {
+    fn parse(text: &str) -> Option<Self> {
+        match text {
+            "merged" => Some(TrunkRelation::Merged),
+            "ahead" => Some(TrunkRelation::Ahead),
+            "unrelated" => Some(TrunkRelation::Unrelated),
+            _ => None,
+        }
+    }
+}
+
+// Template which outputs `<commit_id> <relation>` for TrunkRelation, one line per revision
+const TRUNK_RELATION_TEMPLATE: &str = r#"commit_id ++ " " ++ if(self.contained_in("::trunk()"),
+  "merged",
+  if(self.contained_in("trunk()::"), "ahead", "unrelated"),
+) ++ "\n""#;
+
+/// How much information is shown per change in the log
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LogDensity {
+    /// One line per change
+    Compact,
+    /// Two lines per change, like `jj log`
+    #[default]
+    Normal,
+    /// Adds the description body and the diff stat of each change
+    Detailed,
+}
+
+// Template for LogDensity::Detailed: builtin_log_compact followed by the indented description
+// body and diff stat
+const DETAILED_LOG_TEMPLATE: &str = r#"builtin_log_compact ++ if(!root, indent("  ", concat(
+  if(description.remove_prefix(description.first_line()).trim(),
+    description.remove_prefix(description.first_line()).trim() ++ "\n"),
+  self.diff().stat(80),
+)))"#;
+
+impl LogDensity {
+    pub fn next(self) -> Self {
+        match self {
+            LogDensity::Compact => LogDensity::Normal,
+            LogDensity::Normal => LogDensity::Detailed,
+            LogDensity::Detailed => LogDensity::Compact,
+        }
+    }
+
+    /// Approximate number of lines per change, used for page scrolling
+    pub fn lines_per_change(self) -> u16 {
+        match self {
+            LogDensity::Compact => 1,
+            LogDensity::Normal => 2,
+            LogDensity::Detailed => 5,
+        }
+    }
+
+    /// Template rendering the log graph
+    fn template(self) -> String {
+        match self {
+            LogDensity::Compact => "builtin_log_oneline".to_owned(),
+            LogDensity::Normal => "builtin_log_compact".to_owned(),
+            LogDensity::Detailed => DETAILED_LOG_TEMPLATE.to_owned(),
+        }
+    }
+
+    /// Template with the same number of lines per change as [Self::template], where each line
+    /// starts with HEAD_TEMPLATE
+    fn heads_template(self) -> String {
+        match self {
+            LogDensity::Compact => format!(r#"{HEAD_TEMPLATE} ++ " " ++ bookmarks"#),
+            // Match builtin_log_compact with 2 lines per change, and 1 for the root
+            LogDensity::Normal => format!(
+                r#"{HEAD_TEMPLATE} ++ " " ++ bookmarks ++ if(!root, "\n" ++ {HEAD_TEMPLATE})"#
+            ),
+            // Empty lines are not indented and map to no head, which is fine for the body
+            LogDensity::Detailed => {
+                format!(r#"indent({HEAD_TEMPLATE} ++ " ", {DETAILED_LOG_TEMPLATE})"#)
+            }
+        }
+    }
+}
+
+impl Display for LogDensity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LogDensity::Compact => write!(f, "compact"),
+            LogDensity::Normal => write!(f, "normal"),
+            LogDensity::Detailed => write!(f, "detailed"),
+        }
+    }
+}
+
+/// Summary of the working copy, shown in the header of every tab
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WorkingCopySummary {
+    pub change_id: String,
+    /// Number of files changed in the working copy
+    pub files: usize,
+    pub empty: bool,
+    pub described: bool,
+    pub conflict: bool,
+}
+
+// Template for WorkingCopySummary: the flags on the first line, followed by one line per
+// changed file
+const WORKING_COPY_SUMMARY_TEMPLATE: &str = r#"change_id.shortest(8) ++ "\t" ++ empty ++ "\t"
+  ++ if(description, "true", "false") ++ "\t" ++ conflict ++ "\n" ++ self.diff().summary()"#;
+
+fn parse_working_copy_summary(output: &str) -> Result<WorkingCopySummary> {
+    let mut lines = output.lines();
+    let flags = lines.next().context("Empty working copy summary")?;
+    let [change_id, empty, described, conflict] = flags
+        .split('\t')
+        .collect::<Vec<_>>()
+        .try_into()
+        .map_err(|_| anyhow!("Unexpected working copy summary: {flags}"))?;
+    Ok(WorkingCopySummary {
+        change_id: change_id.to_owned(),
+        files: lines.filter(|line| !line.is_empty()).count(),
+        empty: empty == "true",
+        described: described == "true",
+        conflict: conflict == "true",
+    })
+}
+
+/// `jj status` of the working copy, shown above its diff in the log tab
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WorkingCopyStatus {
+    pub added: usize,
+    pub modified: usize,
+    pub removed: usize,
+    /// Renamed or copied files
+    pub renamed: usize,
+    /// Paths with unresolved conflicts
+    pub conflicts: Vec<String>,
+    /// Local bookmarks of the parents which the auto-advance options move to the working copy
+    /// once it is done, see [BookmarkAdvance][crate::commander::bookmarks::BookmarkAdvance]
+    pub parent_bookmarks: Vec<String>,
+}
+
+// Template for WorkingCopyStatus: whether it has conflicts and the bookmarks of the parents on
+// the first line, followed by one line per changed file
+const WORKING_COPY_STATUS_TEMPLATE: &str = r#"conflict ++ "\t"
+  ++ parents.map(|c| c.local_bookmarks().map(|b| b.name()).join(" ")).join(" ") ++ "\n"
+  ++ self.diff().summary()"#;
+
+/// Parse the output of [WORKING_COPY_STATUS_TEMPLATE], returning whether there are conflicts
+fn parse_working_copy_status(output: &str) -> Result<(bool, WorkingCopyStatus)> {
+    let mut lines = output.lines();
+    let header = lines.next().context("Empty working copy status")?;
+    let (conflict, bookmarks) = header
+        .split_once('\t')
+        .ok_or_else(|| anyhow!("Unexpected working copy status: {header}"))?;
+    let mut status = WorkingCopyStatus {
+        parent_bookmarks: bookmarks
+            .split_whitespace()
+            .unique()
+            .map(str::to_owned)
+            .collect(),
+        ..Default::default()
+    };
+    for line in lines {
+        match line.chars().next() {
+            Some('A') => status.added += 1,
+            Some('M') => status.modified += 1,
+            Some('D') => status.removed += 1,
+            Some('R' | 'C') => status.renamed += 1,
+            _ => {}
+        }
+    }
+    Ok((conflict == "true", status))
+}
+
+impl Display for WorkingCopyStatus {
+    /// Lines like `jj status`, styled with ANSI escapes like the diff below them
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let counts = [
+            (self.added, "added"),
+            (self.modified, "modified"),
+            (self.removed, "removed"),
+            (self.renamed, "renamed"),
+        ]
+        .into_iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, label)| format!("{count} {label}"))
+        .join(", ");
+        if counts.is_empty() {
+            writeln!(f, "\x1b[1mThe working copy has no changes\x1b[0m")?;
+        } else {
+            writeln!(f, "\x1b[1mWorking copy changes:\x1b[0m {counts}")?;
+        }
+        if !self.conflicts.is_empty() {
+            writeln!(f, "\x1b[31mUnresolved conflicts:\x1b[0m")?;
+            for path in &self.conflicts {
+                writeln!(f, "  \x1b[31m{path}\x1b[0m")?;
+            }
+        }
+        if !self.parent_bookmarks.is_empty() {
+            writeln!(
+                f,
+                "Bookmarks of the parent to advance: \x1b[35m{}\x1b[0m",
+                self.parent_bookmarks.join(" ")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Quick filter of the log, combined with its revset
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogFilter {
+    /// Changes authored by the user
+    Mine,
+    /// Changes not merged into `trunk()`
+    Unmerged,
+    /// The first-parent ancestry of the heads, without the changes merged into it
+    FirstParent,
+}
+
+/// Default of `revsets.log`, the base of the filters if it isn't set
+const DEFAULT_LOG_REVSET: &str =
+    "present(@) | ancestors(immutable_heads().., 2) | present(trunk())";
+
+impl LogFilter {
+    /// Revset of the filter, intersected with the `revset` it filters
+    fn revset(self, revset: &str) -> String {
+        match self {
+            LogFilter::Mine => "mine()".to_owned(),
+            LogFilter::Unmerged => "~::trunk()".to_owned(),
+            LogFilter::FirstParent => format!("first_ancestors(heads({revset}))"),
+        }
+    }
+}
+
+impl Display for LogFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LogFilter::Mine => write!(f, "mine"),
+            LogFilter::Unmerged => write!(f, "unmerged"),
+            LogFilter::FirstParent => write!(f, "first parent"),
+        }
+    }
+}
+
+/// Revset with the filters applied
+fn filtered_revset(revset: &str, filters: &[LogFilter]) -> String {
+    let revset = format!("({revset})");
+    filters.iter().fold(revset.clone(), |filtered, filter| {
+        format!("{filtered} & {}", filter.revset(&revset))
+    })
+}
+
+/// Revset adding the revisions between `id` and its nearest ancestors in `revset` to it, or the
+/// parents of `id` if `revset` contains none of its ancestors
+fn expanded_revset(revset: &str, id: &str) -> String {
+    format!("({revset}) | heads((::{id} ~ {id}) & ({revset}))::{id} | parents({id})")
+}
+
+/// Fileset matching a file or directory path relative to the repository root
+pub(crate) fn path_fileset(path: &str) -> String {
+    format!(
+        r#"root:"{}""#,
+        slash_path(path).replace('\\', r"\\").replace('"', r#"\""#)
+    )
+}
+
+/// Fileset matching a [DiffOverride] pattern relative to the repository root
+fn pattern_fileset(pattern: PathPattern) -> String {
+    let (kind, pattern) = match pattern {
+        PathPattern::Glob(glob) => ("root-glob", glob),
+        PathPattern::Exact(path) => ("root-file", path),
+    };
+    format!(
+        r#"{kind}:"{}""#,
+        pattern.replace('\\', r"\\").replace('"', r#"\""#)
+    )
+}
+
+/// Template which outputs `<commit_id> <index>` with the index of the first of the revsets
+/// containing the revision, one line per revision
+fn highlight_template(revsets: &[String]) -> String {
+    let index =
+        revsets
+            .iter()
+            .enumerate()
+            .rev()
+            .fold(r#""""#.to_owned(), |otherwise, (index, revset)| {
+                format!(
+                    r#"if(self.contained_in("{}"), "{index}", {otherwise})"#,
+                    revset.replace('\\', r"\\").replace('"', r#"\""#)
+                )
+            });
+    format!(r#"commit_id ++ " " ++ {index} ++ "\n""#)
+}
+
 impl Commander {
+    /// Revset of the log with the filters applied. Without a revset, the filters apply to
+    /// `revsets.log`.
+    pub fn get_filtered_revset(
+        &self,
+        revset: &Option<String>,
+        filters: &[LogFilter],
+    ) -> Option<String> {
+        if filters.is_empty() {
+            return revset.clone();
+        }
+        Some(filtered_revset(&self.get_log_revset(revset), filters))
+    }
+
+    /// Revset of the log with the revisions elided below `head` revealed, see
+    /// [LogPanel::expand_elided][crate::ui::panel::LogPanel::expand_elided]
+    pub fn get_expanded_revset(&self, revset: &Option<String>, head: &Head) -> String {
+        // A divergent change id is ambiguous in a revset
+        let id = if head.divergent {
+            head.commit_id.as_str()
+        } else {
+            head.change_id.as_str()
+        };
+        expanded_revset(&self.get_log_revset(revset), id)
+    }
+
+    /// The revset, or `revsets.log` without one, as read at startup
+    pub fn get_log_revset(&self, revset: &Option<String>) -> String {
+        revset.clone().unwrap_or_else(|| {
+            self.env
+                .config
+                .log_revset()
+                .unwrap_or(DEFAULT_LOG_REVSET)
+                .to_owned()
+        })
+    }
+
     /// Get log. Returns human readable log and mapping to log line to head.
+    /// If `paths` isn't empty, only changes touching one of the files or directories are shown.
+    /// With `hide_elided`, the graph has no `(elided revisions)` nodes, e.g. for the changes
+    /// merged into the first-parent ancestry.
     /// Maps to `jj log`
     #[instrument(level = "trace", skip(self))]
-    pub fn get_log(&self, revset: &Option<String>) -> Result<LogOutput, CommandError> {
-        let mut args = vec![];
+    pub fn get_log(
+        &self,
+        revset: &Option<String>,
+        paths: &[String],
+        density: LogDensity,
+        hide_elided: bool,
+    ) -> Result<LogOutput, CommandError> {
+        // The log panel wraps the lines itself, jj would wrap the templates below differently
+        let mut args = vec!["--config", "ui.log-word-wrap=false"];
+        if hide_elided {
+            args.extend(["--config", "ui.log-synthetic-elided-nodes=false"]);
+        }
 
         if let Some(revset) = revset {
             args.push("-r");
             args.push(revset);
         }
 
-        // Force builtin_log_compact which uses 2 lines per change
+        let filesets: Vec<String> = paths.iter().map(|path| path_fileset(path)).collect();
+        args.extend(filesets.iter().map(String::as_str));
+
+        // Force a template with a known number of lines per change
+        let template = density.template();
         let graph = self.execute_jj_command(
-            [
-                vec!["log", "--template", "builtin_log_compact"],
-                args.clone(),
-            ]
-            .concat(),
+            [vec!["log", "--template", &template], args.clone()].concat(),
             true,
             true,
         )?;
 
         // Extract the log one more time, but this time use a template
-        // where each line begins with Head information. Since jj has
-        // 2 lines per change, there will also be two lines with head info.
-        // The number of lines in graph and the number of items in graph_heads
+        // where each line begins with Head information. The template has
+        // the same number of lines per change as the one above, so the
+        // number of lines in graph and the number of items in graph_heads
         // should be identical.
+        let heads_template = density.heads_template();
         let graph_heads: Vec<Option<Head>> = self
+            .execute_jj_command(
+                [vec!["log", "--template", &heads_template], args.clone()].concat(),
+                false,
+                true,
+            )?
+            .lines()
+            .map(|line| parse_head(line).ok())
+            .collect();
+
+        // And a last time without the graph for the structured fields of each revision, which
+        // are listed in the same order
+        let revisions = self
             .execute_jj_command(
                 [
-                    vec![
-                        "log",
-                        "--template",
-                        // Match builtin_log_compact with 2 lines per change
-                        &format!(
-                            r#"{HEAD_TEMPLATE} ++ " " ++ bookmarks ++"\n" ++ {HEAD_TEMPLATE}"#
-                        ),
-                    ],
+                    vec!["log", "--no-graph", "--template", REVISION_TEMPLATE],
                     args,
                 ]
                 .concat(),
@@ -124,18 +566,93 @@ impl Commander {
                 true,
             )?
             .lines()
-            .map(|line| parse_head(line).ok())
-            .collect();
+            .map(parse_revision)
+            .collect::<Result<Vec<LogRevision>>>()
+            .unwrap_or_else(|err| {
+                warn!("{err:#}");
+                vec![]
+            });
 
-        let heads = graph_heads.clone().into_iter().flatten().unique().collect();
+        let heads = if revisions.is_empty() {
+            graph_heads.clone().into_iter().flatten().unique().collect()
+        } else {
+            revisions.iter().map(LogRevision::head).collect()
+        };
 
         Ok(LogOutput {
             graph,
             graph_heads,
             heads,
+            revisions,
         })
     }
 
+    /// Get the [TrunkRelation] of every revision of the log, in a single query.
+    /// Maps to `jj log --no-graph --template <TRUNK_RELATION_TEMPLATE>`
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_trunk_relations(
+        &self,
+        revset: &Option<String>,
+        paths: &[String],
+    ) -> Result<HashMap<CommitId, TrunkRelation>, CommandError> {
+        let mut args = vec!["log", "--no-graph", "--template", TRUNK_RELATION_TEMPLATE];
+
+        if let Some(revset) = revset {
+            args.push("-r");
+            args.push(revset);
+        }
+
+        let filesets: Vec<String> = paths.iter().map(|path| path_fileset(path)).collect();
+        args.extend(filesets.iter().map(String::as_str));
+
+        Ok(self
+            .execute_jj_command(args, false, true)?
+            .lines()
+            .filter_map(|line| {
+                let (commit_id, relation) = line.split_once(' ')?;
+                Some((
+                    CommitId(commit_id.to_owned()),
+                    TrunkRelation::parse(relation)?,
+                ))
+            })
+            .collect())
+    }
+
+    /// Get the index of the first of `highlight_revsets` containing each revision of the log, in
+    /// a single query. Revisions matching none are left out.
+    /// Maps to `jj log --no-graph --template <template>`
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_highlighted_revisions(
+        &self,
+        revset: &Option<String>,
+        paths: &[String],
+        highlight_revsets: &[String],
+    ) -> Result<HashMap<CommitId, usize>, CommandError> {
+        if highlight_revsets.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let template = highlight_template(highlight_revsets);
+        let mut args = vec!["log", "--no-graph", "--template", &template];
+
+        if let Some(revset) = revset {
+            args.push("-r");
+            args.push(revset);
+        }
+
+        let filesets: Vec<String> = paths.iter().map(|path| path_fileset(path)).collect();
+        args.extend(filesets.iter().map(String::as_str));
+
+        Ok(self
+            .execute_jj_command(args, false, true)?
+            .lines()
+            .filter_map(|line| {
+                let (commit_id, index) = line.split_once(' ')?;
+                Some((CommitId(commit_id.to_owned()), index.parse().ok()?))
+            })
+            .collect())
+    }
+
     /// Get commit details.
     /// Maps to `jj show <commit>`
     #[instrument(level = "trace", skip(self))]
@@ -154,6 +671,161 @@ impl Commander {
         Ok(self.execute_jj_command(args, true, true)?.remove_end_line())
     }
 
+    /// Number of lines added and removed by a commit, to tell whether its diff is large without
+    /// rendering it. Maps to `jj log -r <commit> -T 'self.diff().stat()'`
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_diff_line_count(&self, commit_id: &CommitId) -> Result<usize> {
+        let output = self
+            .execute_jj_command(
+                vec![
+                    "log",
+                    "--no-graph",
+                    "--ignore-working-copy",
+                    "-r",
+                    commit_id.as_str(),
+                    "-T",
+                    r#"self.diff().stat().total_added() ++ " " ++ self.diff().stat().total_removed()"#,
+                ],
+                false,
+                true,
+            )
+            .with_context(|| format!("Failed getting the diff size of {commit_id}"))?;
+        output
+            .split_whitespace()
+            .map(|count| {
+                count
+                    .parse::<usize>()
+                    .with_context(|| format!("Unexpected diff size: {output}"))
+            })
+            .sum()
+    }
+
+    /// Like [Commander::get_commit_show], but files matching a [DiffOverride] are shown in the
+    /// format of the first matching override, after the diff of the other files. The files of
+    /// all overrides with the same format are shown by a single command. In diffs of the lines,
+    /// renames jj shows as a deleted and an added file only show their changed lines, see
+    /// [Commander::get_missed_renames].
+    /// Maps to `jj show --no-patch <commit>` and `jj diff -r <commit> <fileset>`
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_commit_show_with_overrides(
+        &self,
+        commit_id: &CommitId,
+        diff_format: &DiffFormat,
+        overrides: &[DiffOverride],
+        ignore_working_copy: bool,
+    ) -> Result<String, CommandError> {
+        // Detecting renames reads the files, which isn't worth it for a stat or summary
+        let detected = match diff_format {
+            DiffFormat::ColorWords | DiffFormat::Git => self
+                .get_head(commit_id.as_str())
+                .and_then(|head| Ok((head.clone(), self.get_missed_renames(&head)?)))
+                .inspect_err(|err| warn!("Failed detecting the renames of {commit_id}: {err}"))
+                .ok(),
+            _ => None,
+        };
+        // Renames of files matching an override are shown as the override says
+        let renames: Vec<(&Head, File)> = detected
+            .iter()
+            .flat_map(|(head, files)| files.iter().map(move |file| (head, file.clone())))
+            .filter(|(_, file)| {
+                !file.paths().iter().any(|path| {
+                    overrides
+                        .iter()
+                        .any(|diff_override| diff_override.matches(path))
+                })
+            })
+            .collect();
+        if overrides.is_empty() && renames.is_empty() {
+            return self.get_commit_show(commit_id, diff_format, ignore_working_copy);
+        }
+
+        let run = |mut args: Vec<&str>| {
+            if ignore_working_copy {
+                args.push("--ignore-working-copy");
+            }
+            Ok::<_, CommandError>(self.execute_jj_command(args, true, true)?.remove_end_line())
+        };
+
+        // Each file is shown in the format of the first override matching it, so each override
+        // only gets the files none of the earlier ones matched
+        let mut matched: Vec<String> = vec![];
+        let mut groups: Vec<(&DiffFormat, Vec<String>)> = vec![];
+        for diff_override in overrides {
+            // Invalid patterns are reported when the config is loaded
+            let Ok(pattern) = diff_override.path_pattern() else {
+                continue;
+            };
+            let fileset = pattern_fileset(pattern);
+            let files = if matched.is_empty() {
+                fileset.clone()
+            } else {
+                format!("({fileset}) ~ ({})", matched.join(" | "))
+            };
+            match groups
+                .iter_mut()
+                .find(|(format, _)| **format == diff_override.format)
+            {
+                Some((_, filesets)) => filesets.push(files),
+                None => groups.push((&diff_override.format, vec![files])),
+            }
+            matched.push(fileset);
+        }
+
+        let header = run(vec!["show", commit_id.as_str(), "--no-patch"])?;
+
+        let mut rename_diffs = vec![];
+        for (head, file) in &renames {
+            if let Some(diff) = self.get_rename_diff(head, file)? {
+                rename_diffs.push(diff.remove_end_line());
+            }
+            matched.extend(
+                file.paths()
+                    .iter()
+                    .map(|path| pattern_fileset(PathPattern::Exact(path))),
+            );
+        }
+
+        let other_files = if matched.is_empty() {
+            "all()".to_owned()
+        } else {
+            format!("~({})", matched.join(" | "))
+        };
+        let mut args = vec!["diff", "-r", commit_id.as_str(), &other_files];
+        args.append(&mut diff_format.get_args());
+        let diff = run(args)?;
+
+        let mut override_diffs = vec![];
+        for (format, filesets) in &groups {
+            let files = filesets
+                .iter()
+                .map(|fileset| format!("({fileset})"))
+                .join(" | ");
+            let mut args = vec!["diff", "-r", commit_id.as_str(), &files];
+            args.append(&mut format.get_args());
+            override_diffs.push(run(args)?);
+        }
+
+        Ok([header, diff]
+            .into_iter()
+            .chain(rename_diffs)
+            .chain(override_diffs)
+            .filter(|section| !section.is_empty())
+            .join("\n\n"))
+    }
+
+    /// Like [Commander::get_commit_show], but streams the output. Used for external diff tools,
+    /// which can be slow on large changes.
+    #[instrument(level = "trace", skip(self))]
+    pub fn stream_commit_show(
+        &self,
+        commit_id: &CommitId,
+        diff_format: &DiffFormat,
+    ) -> Result<StreamingOutput, CommandError> {
+        let mut args = vec!["show", commit_id.as_str(), "--ignore-working-copy"];
+        args.append(&mut diff_format.get_args());
+        self.execute_streaming_jj_command(args, true)
+    }
+
     /// Get the current head.
     /// Maps to `jj log -r @`
     #[instrument(level = "trace", skip(self))]
@@ -179,11 +851,104 @@ impl Commander {
         )
     }
 
+    /// Get the head of a single revision.
+    /// Maps to `jj log -r <revision>`
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_head(&self, revision: &str) -> Result<Head> {
+        parse_head(
+            &self
+                .execute_jj_command(
+                    vec![
+                        "log",
+                        "--no-graph",
+                        "--template",
+                        &format!(r#"{HEAD_TEMPLATE} ++ "\n""#),
+                        "-r",
+                        revision,
+                        "--limit",
+                        "1",
+                    ],
+                    false,
+                    true,
+                )
+                .with_context(|| format!("Failed getting head of {revision}"))?
+                .remove_end_line(),
+        )
+    }
+
+    /// Get the summary of the working copy, snapshotting it first so that files changed since the
+    /// last command are included. Maps to `jj log -r @`
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_working_copy_summary(&self) -> Result<WorkingCopySummary> {
+        parse_working_copy_summary(
+            &self
+                .execute_jj_command(
+                    vec![
+                        "log",
+                        "--no-graph",
+                        "--template",
+                        WORKING_COPY_SUMMARY_TEMPLATE,
+                        "-r",
+                        "@",
+                    ],
+                    false,
+                    true,
+                )
+                .context("Failed getting the working copy summary")?,
+        )
+    }
+
+    /// Get the `jj status` of the working copy at `commit_id` as of its last snapshot.
+    /// Maps to `jj log -r <commit_id> --ignore-working-copy`, `jj resolve --list` and
+    /// `jj config list` for the auto-advance options
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_working_copy_status(&self, commit_id: &CommitId) -> Result<WorkingCopyStatus> {
+        let output = self
+            .execute_jj_command(
+                vec![
+                    "log",
+                    "--no-graph",
+                    "--ignore-working-copy",
+                    "--template",
+                    WORKING_COPY_STATUS_TEMPLATE,
+                    "-r",
+                    commit_id.as_str(),
+                ],
+                false,
+                true,
+            )
+            .context("Failed getting the working copy status")?;
+        let (conflict, mut status) = parse_working_copy_status(&output)?;
+        if !status.parent_bookmarks.is_empty() {
+            let advance = self.bookmark_advance(std::mem::take(&mut status.parent_bookmarks))?;
+            status.parent_bookmarks = advance.advanced().into_iter().map(str::to_owned).collect();
+        }
+        if conflict {
+            status.conflicts = self
+                .get_conflicts(commit_id)?
+                .into_iter()
+                .map(|conflict| conflict.path)
+                .collect();
+        }
+        Ok(status)
+    }
+
     /// Get the latest version of a head. Can detect evolution of divergent head.
+    /// Falls back to the current head if the change no longer exists.
     #[instrument(level = "trace", skip(self))]
     pub fn get_head_latest(&self, head: &Head) -> Result<Head> {
+        match self.find_head_latest(head)? {
+            Some(latest_head) => Ok(latest_head),
+            None => self.get_current_head(),
+        }
+    }
+
+    /// Find the latest version of a head, e.g. after it was described, squashed into or rebased.
+    /// Returns `None` if the change no longer exists, e.g. because it was abandoned.
+    #[instrument(level = "trace", skip(self))]
+    pub fn find_head_latest(&self, head: &Head) -> Result<Option<Head>> {
         // Get all heads which point to the same change ID
-        let latest_heads_res = self.execute_jj_command(
+        let Ok(latest_heads_res) = self.execute_jj_command(
             vec![
                 "log",
                 "--no-graph",
@@ -194,58 +959,49 @@ impl Commander {
             ],
             false,
             true,
-        );
-        let Ok(latest_heads_res) = latest_heads_res else {
-            return self.get_head_latest(&self.get_current_head()?);
+        ) else {
+            return Ok(None);
         };
-        if latest_heads_res.is_empty() {
-            return self.get_head_latest(&self.get_current_head()?);
-        }
         let latest_heads: Vec<Head> = latest_heads_res
             .lines()
             .map(parse_head)
             .collect::<Result<Vec<Head>>>()?;
 
         // If the current head exist, that means it wasn't updated
-        if let Some(head) = latest_heads.iter().find(|latest_head| latest_head == &head) {
-            return Ok(head.to_owned());
+        if latest_heads.contains(head) {
+            return Ok(Some(head.to_owned()));
+        }
+        if let [latest_head] = latest_heads.as_slice() {
+            return Ok(Some(latest_head.to_owned()));
         }
 
-        // Check obslog for each head. If the obslog contains the head's commit, it means
-        // there's a new commit for the head
+        // The change is divergent. Check the evolog of each head, if it contains the head's
+        // commit, the head is a successor of it
         for latest_head in latest_heads.iter() {
-            let parent_commits: Vec<ChangeId> = self
+            let predecessors: Vec<CommitId> = self
                 .execute_jj_command(
                     vec![
-                        "obslog",
+                        "evolog",
                         "--no-graph",
                         "--template",
-                        r#"commit.change_id() ++ "\n""#,
+                        r#"commit.commit_id() ++ "\n""#,
                         "-r",
                         latest_head.commit_id.as_str(),
                     ],
                     false,
                     true,
                 )
-                .context("Failed getting latest head parent commits")?
+                .context("Failed getting latest head predecessors")?
                 .lines()
-                .map(|line| ChangeId(line.to_owned()))
+                .map(|line| CommitId(line.to_owned()))
                 .collect();
 
-            if parent_commits
-                .iter()
-                .any(|parent_commit| parent_commit == &head.change_id)
-            {
-                return Ok(latest_head.to_owned());
+            if predecessors.contains(&head.commit_id) {
+                return Ok(Some(latest_head.to_owned()));
             }
         }
 
-        bail!(
-            "Could not find head latest: {} {} {:?}",
-            head.change_id,
-            head.commit_id,
-            latest_heads
-        );
+        Ok(latest_heads.into_iter().next())
     }
 
     /// Get a commit's parent.
@@ -296,6 +1052,30 @@ impl Commander {
             .remove_end_line())
     }
 
+    /// Render a template for a commit, e.g. to try out a template before putting it in the
+    /// config.
+    /// Maps to `jj log --no-graph -r <commit> -T <template>`
+    #[instrument(level = "trace", skip(self))]
+    pub fn render_template(
+        &self,
+        commit_id: &CommitId,
+        template: &str,
+    ) -> Result<String, CommandError> {
+        self.execute_jj_command(
+            vec![
+                "log",
+                "--no-graph",
+                "--ignore-working-copy",
+                "-r",
+                commit_id.as_str(),
+                "-T",
+                template,
+            ],
+            true,
+            true,
+        )
+    }
+
     /// Check if a revision is immutable
     /// Maps to `jj log -r <revision> -T immutable`
     #[instrument(level = "trace", skip(self))]
@@ -358,7 +1138,9 @@ mod tests {
     fn get_log() -> Result<()> {
         let test_repo = TestRepo::new()?;
 
-        let log = test_repo.commander.get_log(&None)?;
+        let log = test_repo
+            .commander
+            .get_log(&None, &[], LogDensity::Normal, false)?;
 
         let mut settings = insta::Settings::clone_current();
         settings.add_filter(r"[k-z]{8} .*? [0-9a-fA-F]{8}", "[LINE]");
@@ -375,6 +1157,389 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn get_trunk_relations() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+
+        let head = test_repo.commander.get_current_head()?;
+        let relations = test_repo.commander.get_trunk_relations(&None, &[])?;
+        // Without remotes trunk() is the root, which every change descends from
+        assert_eq!(relations.get(&head.commit_id), Some(&TrunkRelation::Ahead));
+
+        let root = test_repo
+            .commander
+            .get_trunk_relations(&Some("root()".to_owned()), &[])?;
+        assert_eq!(root.values().collect::<Vec<_>>(), [&TrunkRelation::Merged]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn get_log_for_path() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+
+        fs::write(test_repo.directory.path().join("README"), b"AAA")?;
+        let head = test_repo.commander.get_current_head()?;
+
+        let log = test_repo.commander.get_log(
+            &None,
+            &["README".to_owned()],
+            LogDensity::Normal,
+            false,
+        )?;
+        assert!(log.heads.contains(&head));
+
+        let log =
+            test_repo
+                .commander
+                .get_log(&None, &["OTHER".to_owned()], LogDensity::Normal, false)?;
+        assert!(!log.heads.contains(&head));
+
+        Ok(())
+    }
+
+    #[test]
+    fn get_log_densities() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+
+        fs::write(test_repo.directory.path().join("README"), b"AAA")?;
+        test_repo.commander.execute_void_jj_command(vec![
+            "describe",
+            "-m",
+            "Title\n\nBody line",
+        ])?;
+        let head = test_repo.commander.get_current_head()?;
+
+        for density in [
+            LogDensity::Compact,
+            LogDensity::Normal,
+            LogDensity::Detailed,
+        ] {
+            let log = test_repo.commander.get_log(&None, &[], density, false)?;
+            assert_eq!(
+                log.graph.lines().count(),
+                log.graph_heads.len(),
+                "{density}"
+            );
+            assert!(log.heads.contains(&head), "{density}");
+        }
+
+        let compact = test_repo
+            .commander
+            .get_log(&None, &[], LogDensity::Compact, false)?;
+        let detailed = test_repo
+            .commander
+            .get_log(&None, &[], LogDensity::Detailed, false)?;
+        assert!(!compact.graph.contains("Body line"));
+        assert!(detailed.graph.contains("Body line"));
+        assert!(detailed.graph.contains("README"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn get_log_id_length() -> Result<()> {
+        let mut test_repo = TestRepo::new()?;
+        let head = test_repo.commander.get_current_head()?;
+        let long_id = &head.change_id.as_str()[..12];
+
+        let log = test_repo
+            .commander
+            .get_log(&None, &[], LogDensity::Normal, false)?;
+        assert!(!log.graph.contains(long_id));
+
+        test_repo.commander.env.config = toml::from_str(r#""blazingjj.id-length" = 12"#)?;
+        let log = test_repo
+            .commander
+            .get_log(&None, &[], LogDensity::Normal, false)?;
+        assert!(log.graph.contains(long_id));
+
+        Ok(())
+    }
+
+    #[test]
+    fn get_log_revisions() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+
+        test_repo.commander.execute_void_jj_command(vec![
+            "describe",
+            "-m",
+            "Title \"quoted\"\n\nBody",
+        ])?;
+        fs::write(test_repo.directory.path().join("README"), b"AAA")?;
+        test_repo.commander.create_bookmark("main")?;
+        let head = test_repo.commander.get_current_head()?;
+
+        let log = test_repo
+            .commander
+            .get_log(&None, &[], LogDensity::Normal, false)?;
+        let revision = log.revision(&head).context("Missing revision")?;
+        assert_eq!(revision.head(), head);
+        assert_eq!(revision.title(), "Title \"quoted\"");
+        assert_eq!(revision.author_email, "blazingjj@example.com");
+        assert_eq!(revision.bookmarks, vec!["main"]);
+        assert!(revision.working_copy);
+        assert!(!revision.empty);
+        assert_eq!(log.heads.len(), log.revisions.len());
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_revision_line() {
+        let revision = parse_revision(
+            r#"{ change_id = "kkmpptxz", commit_id = "abc123", description = "Fix\n\n\u001b[1mbody\u001b[0m\n", author_name = "Jo", author_email = "jo@example.com", bookmarks = ["main"], remote_bookmarks = ["main@origin"], divergent = false, immutable = true, conflict = false, empty = true, working_copy = false }"#,
+        )
+        .unwrap();
+        assert_eq!(revision.change_id, ChangeId("kkmpptxz".to_owned()));
+        assert_eq!(revision.title(), "Fix");
+        assert_eq!(revision.remote_bookmarks, vec!["main@origin"]);
+        assert!(revision.immutable && revision.empty);
+
+        assert!(parse_revision("[kkmpptxz|abc123|false|false]").is_err());
+    }
+
+    #[test]
+    fn highlight_template_escaping() {
+        assert_eq!(
+            highlight_template(&["conflicts()".to_owned(), r#"description("a\b")"#.to_owned()]),
+            r#"commit_id ++ " " ++ if(self.contained_in("conflicts()"), "0", if(self.contained_in("description(\"a\\b\")"), "1", "")) ++ "\n""#
+        );
+    }
+
+    #[test]
+    fn get_highlighted_revisions() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+
+        let head = test_repo.commander.get_current_head()?;
+        let highlighted = test_repo.commander.get_highlighted_revisions(
+            &None,
+            &[],
+            &["root()".to_owned(), "@".to_owned()],
+        )?;
+        assert_eq!(highlighted.get(&head.commit_id), Some(&1));
+        assert_eq!(highlighted.values().filter(|index| **index == 0).count(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn first_parent_filter() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+        let describe = |description: &str| {
+            test_repo
+                .commander
+                .execute_void_jj_command(["describe", "-m", description])
+        };
+        describe("base")?;
+        let base = test_repo.commander.get_current_head()?;
+        test_repo.commander.execute_void_jj_command(["new"])?;
+        describe("first")?;
+        let first = test_repo.commander.get_current_head()?;
+        test_repo
+            .commander
+            .execute_void_jj_command(["new", base.commit_id.as_str()])?;
+        describe("merged")?;
+        let merged = test_repo.commander.get_current_head()?;
+        test_repo.commander.execute_void_jj_command([
+            "new",
+            first.commit_id.as_str(),
+            merged.commit_id.as_str(),
+        ])?;
+        let merge = test_repo.commander.get_current_head()?;
+
+        let revset = test_repo
+            .commander
+            .get_filtered_revset(&Some("::@ ~ root()".to_owned()), &[LogFilter::FirstParent]);
+        let log = test_repo
+            .commander
+            .get_log(&revset, &[], LogDensity::default(), true)?;
+        let change_ids: Vec<_> = log.heads.iter().map(|head| &head.change_id).collect();
+        assert_eq!(
+            change_ids,
+            [&merge.change_id, &first.change_id, &base.change_id]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn working_copy_summary() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+
+        let summary = test_repo.commander.get_working_copy_summary()?;
+        assert!(summary.empty && !summary.described && !summary.conflict);
+        assert_eq!(summary.files, 0);
+
+        fs::write(test_repo.directory.path().join("a"), b"a")?;
+        fs::write(test_repo.directory.path().join("b"), b"b")?;
+        test_repo
+            .commander
+            .execute_void_jj_command(["describe", "-m", "two files"])?;
+        let summary = test_repo.commander.get_working_copy_summary()?;
+        assert!(!summary.empty && summary.described);
+        assert_eq!(summary.files, 2);
+        assert_eq!(
+            summary.change_id,
+            test_repo
+                .commander
+                .get_current_head()?
+                .change_id
+                .as_str()
+                .chars()
+                .take(summary.change_id.len())
+                .collect::<String>()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn diff_line_count() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+        fs::write(test_repo.directory.path().join("a"), "one\ntwo\n")?;
+        test_repo.commander.execute_void_jj_command(["new"])?;
+        fs::write(test_repo.directory.path().join("a"), "one\nthree\nfour\n")?;
+
+        let head = test_repo.commander.get_current_head()?;
+        // `two` removed, `three` and `four` added
+        assert_eq!(test_repo.commander.get_diff_line_count(&head.commit_id)?, 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn working_copy_status_lines() -> Result<()> {
+        let (conflict, status) = parse_working_copy_status(
+            "true\tmain dev main\nA new.rs\nM src/main.rs\nM README\nD old.rs\nR {a => b}\n",
+        )?;
+        assert!(conflict);
+        assert_eq!(
+            status,
+            WorkingCopyStatus {
+                added: 1,
+                modified: 2,
+                removed: 1,
+                renamed: 1,
+                conflicts: vec![],
+                parent_bookmarks: vec!["main".to_owned(), "dev".to_owned()],
+            }
+        );
+        assert_eq!(
+            status.to_string(),
+            "\x1b[1mWorking copy changes:\x1b[0m 1 added, 2 modified, 1 removed, 1 renamed\n\
+             Bookmarks of the parent to advance: \x1b[35mmain dev\x1b[0m\n"
+        );
+
+        let (conflict, status) = parse_working_copy_status("false\t\n")?;
+        assert!(!conflict);
+        assert_eq!(status, WorkingCopyStatus::default());
+        Ok(())
+    }
+
+    #[test]
+    fn working_copy_status() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+        fs::write(test_repo.directory.path().join("a"), b"a")?;
+        test_repo
+            .commander
+            .execute_void_jj_command(["bookmark", "create", "main", "-r", "@"])?;
+        test_repo.commander.execute_void_jj_command(["new"])?;
+        fs::write(test_repo.directory.path().join("a"), b"b")?;
+        fs::write(test_repo.directory.path().join("b"), b"c")?;
+
+        // Snapshot the working copy, the status ignores it
+        let head = test_repo.commander.get_current_head()?;
+        let status = test_repo
+            .commander
+            .get_working_copy_status(&head.commit_id)?;
+        assert_eq!(
+            status,
+            WorkingCopyStatus {
+                added: 1,
+                modified: 1,
+                ..Default::default()
+            }
+        );
+
+        // The bookmarks of the parent are only listed if they are advanced
+        test_repo.commander.toggle_bookmark_advance()?;
+        let status = test_repo
+            .commander
+            .get_working_copy_status(&head.commit_id)?;
+        assert_eq!(status.parent_bookmarks, ["main"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn log_filters() -> Result<()> {
+        assert_eq!(
+            filtered_revset("@ | trunk()", &[LogFilter::Mine, LogFilter::Unmerged]),
+            "(@ | trunk()) & mine() & ~::trunk()"
+        );
+        assert_eq!(
+            filtered_revset("::@", &[LogFilter::FirstParent]),
+            "(::@) & first_ancestors(heads((::@)))"
+        );
+
+        let test_repo = TestRepo::new()?;
+        assert_eq!(
+            test_repo
+                .commander
+                .get_filtered_revset(&Some("all()".to_owned()), &[]),
+            Some("all()".to_owned())
+        );
+        let revset = test_repo
+            .commander
+            .get_filtered_revset(&None, &[LogFilter::Unmerged]);
+        let log = test_repo
+            .commander
+            .get_log(&revset, &[], LogDensity::default(), false)?;
+        assert_eq!(
+            log.heads,
+            vec![test_repo.commander.get_current_head()?],
+            "{revset:?}"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn expand_elided() -> Result<()> {
+        assert_eq!(
+            expanded_revset("@ | trunk()", "kkmpptxz"),
+            "(@ | trunk()) | heads((::kkmpptxz ~ kkmpptxz) & (@ | trunk()))::kkmpptxz \
+             | parents(kkmpptxz)"
+        );
+
+        let test_repo = TestRepo::new()?;
+        let commander = &test_repo.commander;
+        commander.execute_void_jj_command(["new"])?;
+        commander.execute_void_jj_command(["new"])?;
+        let head = commander.get_current_head()?;
+        let revset = Some("@ | root()".to_owned());
+        let log = commander.get_log(&revset, &[], LogDensity::default(), false)?;
+        assert_eq!(log.heads.len(), 2);
+
+        let expanded = Some(commander.get_expanded_revset(&revset, &head));
+        let log = commander.get_log(&expanded, &[], LogDensity::default(), false)?;
+        assert_eq!(log.heads.len(), 4, "{expanded:?}");
+
+        // Nothing is elided any more, only the parents are added
+        let expanded_again = Some(commander.get_expanded_revset(&expanded, &head));
+        let log = commander.get_log(&expanded_again, &[], LogDensity::default(), false)?;
+        assert_eq!(log.heads.len(), 4);
+
+        Ok(())
+    }
+
+    #[test]
+    fn path_fileset_escaping() {
+        assert_eq!(path_fileset("src/main.rs"), r#"root:"src/main.rs""#);
+        assert_eq!(path_fileset(r#"a "b"\c"#), r#"root:"a \"b\"\\c""#);
+    }
+
     #[test]
     fn get_commit_show() -> Result<()> {
         let test_repo = TestRepo::new()?;
@@ -398,6 +1563,44 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn get_commit_show_with_overrides() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+
+        fs::write(test_repo.directory.path().join("README"), b"AAA")?;
+        fs::write(test_repo.directory.path().join("Cargo.lock"), b"BBB")?;
+        fs::write(test_repo.directory.path().join("go.sum"), b"CCC")?;
+        fs::write(test_repo.directory.path().join("schema.json"), b"DDD")?;
+        let head = test_repo.commander.get_current_head()?;
+
+        let show = test_repo.commander.get_commit_show_with_overrides(
+            &head.commit_id,
+            &DiffFormat::Git,
+            &[
+                DiffOverride {
+                    pattern: "**/*.lock".to_owned(),
+                    format: DiffFormat::Summary,
+                },
+                DiffOverride {
+                    pattern: "exact:schema.json".to_owned(),
+                    format: DiffFormat::Stat,
+                },
+                DiffOverride {
+                    pattern: "glob:*.sum".to_owned(),
+                    format: DiffFormat::Summary,
+                },
+            ],
+            false,
+        )?;
+        assert!(show.contains("+AAA"));
+        assert!(!show.contains("BBB") && !show.contains("CCC") && !show.contains("DDD"));
+        // The files of both summary overrides are shown together
+        assert!(show.contains("A Cargo.lock\nA go.sum"));
+        assert!(show.contains("schema.json | 1 +"));
+
+        Ok(())
+    }
+
     #[test]
     fn get_commit_parent() -> Result<()> {
         let test_repo = TestRepo::new()?;
@@ -434,6 +1637,26 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn find_head_latest() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+
+        let old_head = test_repo.commander.get_current_head()?;
+        test_repo
+            .commander
+            .run_describe(old_head.commit_id.as_str(), "Described")?;
+        let described_head = test_repo.commander.get_current_head()?;
+        assert_eq!(
+            test_repo.commander.find_head_latest(&old_head)?,
+            Some(described_head.clone())
+        );
+
+        test_repo.commander.run_abandon(&described_head.commit_id)?;
+        assert_eq!(test_repo.commander.find_head_latest(&old_head)?, None);
+
+        Ok(())
+    }
+
     #[test]
     fn check_revision_immutable() -> Result<()> {
         let test_repo = TestRepo::new()?;
@@ -443,6 +1666,30 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn render_template() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+        test_repo
+            .commander
+            .execute_void_jj_command(["describe", "-m", "Template"])?;
+        let head = test_repo.commander.get_current_head()?;
+
+        assert_eq!(
+            test_repo
+                .commander
+                .render_template(&head.commit_id, r#"description.first_line() ++ "!""#)?,
+            "Template!"
+        );
+        assert!(
+            test_repo
+                .commander
+                .render_template(&head.commit_id, "unknown_keyword")
+                .is_err()
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn get_bookmark_head() -> Result<()> {
         let test_repo = TestRepo::new()?;
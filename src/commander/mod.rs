@@ -14,20 +14,46 @@ command implementation functions:
 
 * [Commander::new] - Create a new instance
 * [Commander::check_jj_version] - Check jj works with blazingjj
-* [Commander::execute_command] - Execute any command and log the result
+* [Commander::execute_command] - Execute any command and record it to the [history]
 * [Commander::execute_jj_command] - Execute a jj command.
 * [Commander::execute_void_jj_command] - Execute a jj command and discard the output.
 
 */
 
+pub mod batch;
 pub mod bookmarks;
+pub mod ci_status;
+pub mod config;
 pub mod files;
+pub mod forge;
+pub mod git_sync;
+pub mod hindsight;
+pub mod history;
+pub mod hooks;
+pub mod hunks;
 pub mod ids;
+pub mod instances;
 pub mod jj;
 pub mod log;
+pub mod operations;
+pub mod output_processors;
+pub mod push;
+pub mod recovery;
+pub mod remote_errors;
+pub mod renames;
+pub mod reorder;
+pub mod resolve;
+pub mod running;
+pub mod show_cache;
+pub mod snapshot;
+pub mod stats;
+pub mod stream;
+pub mod todos;
+pub mod warnings;
 
 use crate::env::DiffFormat;
 use crate::env::Env;
+use crate::env::ExecutionMode;
 
 use ansi_to_tui::IntoText;
 use anyhow::{Context, Result, bail};
@@ -35,8 +61,16 @@ use ratatui::{
     style::{Color, Stylize},
     text::{Line, Text},
 };
-use std::sync::Mutex;
-use std::{ffi::OsStr, io, process::Command, string::FromUtf8Error, sync::Arc};
+use regex::Regex;
+use std::sync::{LazyLock, Mutex};
+use std::{
+    ffi::{OsStr, OsString},
+    io,
+    process::{Command, ExitStatus, Output},
+    string::FromUtf8Error,
+    sync::Arc,
+    time::Instant,
+};
 use thiserror::Error;
 use tracing::{instrument, trace};
 use version_compare::{Cmp, compare};
@@ -67,22 +101,60 @@ pub enum CommandError {
     Status(String, Option<i32>),
     #[error("Error parsing UTF-8 output: {0}")]
     FromUtf8(#[from] FromUtf8Error),
+    #[error("{0} hook failed:\n{1}")]
+    Hook(String, String),
+    #[error("Tests failed, nothing was pushed. Press s in the push preview to push anyway.\n\n{0}")]
+    TestsFailed(String),
+    #[error("{0} would change the repository, which is disabled in read-only mode")]
+    ReadOnly(String),
+    #[error("`{0}` was killed after running for {1} seconds")]
+    Killed(String, u64),
+    #[error(
+        "Another blazingjj instance (pid {0}) is changing the repository, try again once it finished"
+    )]
+    Locked(u32),
 }
 
+/// Stderr fragments printed by git or ssh when they wanted to prompt for credentials
+const CREDENTIAL_PROMPT_ERRORS: [&str; 5] = [
+    "terminal prompts disabled",
+    "could not read Username",
+    "could not read Password",
+    "Enter passphrase",
+    "Permission denied (publickey",
+];
+
 impl CommandError {
+    /// Whether the command failed because git or ssh wanted to prompt for credentials
+    pub fn needs_credentials(&self) -> bool {
+        match self {
+            CommandError::Status(output, _) => CREDENTIAL_PROMPT_ERRORS
+                .iter()
+                .any(|prompt_error| output.contains(prompt_error)),
+            _ => false,
+        }
+    }
+
     #[expect(clippy::wrong_self_convention)]
     pub fn into_text<'a>(&self, title: &'a str) -> Result<Text<'a>, ansi_to_tui::Error> {
-        let mut lines = vec![];
-        if !title.is_empty() {
-            lines.push(Line::raw(title).bold().fg(Color::Red));
-            lines.append(&mut vec![Line::raw(""), Line::raw("")]);
-        }
-        lines.append(&mut self.to_string().into_text()?.lines);
+        Ok(titled_error_text(title, self.to_string().into_text()?))
+    }
+}
 
-        Ok(Text::from(lines))
+/// Text of an error message, below the title in red if there is one
+pub fn titled_error_text<'a>(title: &'a str, message: Text<'static>) -> Text<'a> {
+    let mut lines = vec![];
+    if !title.is_empty() {
+        lines.push(Line::raw(title).bold().fg(Color::Red));
+        lines.append(&mut vec![Line::raw(""), Line::raw("")]);
     }
+    lines.extend(message.lines);
+    Text::from(lines)
 }
 
+/// ANSI color codes, e.g. to match jj's colored output as plain text
+pub static ANSI_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\x1b\[[0-9;]*m").unwrap());
+
 /// Struct used to interact with the jj cli using commanders.
 ///
 /// Handles arguments and recording of history.
@@ -91,6 +163,9 @@ pub struct Commander {
     pub env: Env,
     /// Environment variables.
     env_var: Arc<Mutex<Vec<(String, String)>>>,
+    /// Operations of the commands of the last [operations::CompositeAction], to tell them from
+    /// the operations of other processes
+    action_tracking: Mutex<Option<operations::ActionTracking>>,
 
     // Used for testing
     pub jj_config_toml: Option<Vec<String>>,
@@ -102,6 +177,7 @@ impl Commander {
         Self {
             env: env.clone(),
             env_var: Arc::new(Mutex::new(Vec::new())),
+            action_tracking: Mutex::new(None),
             jj_config_toml: None,
             force_no_color: false,
         }
@@ -120,7 +196,7 @@ impl Commander {
     }
 
     /// Set an environment variable for the next execute_command.
-    pub fn set_env(&mut self, var: &str, value: &str) {
+    pub fn set_env(&self, var: &str, value: &str) {
         self.env_var
             .lock()
             .unwrap()
@@ -131,6 +207,21 @@ impl Commander {
     /// Environment variables can be set with set_env.
     /// They are cleared after execution.
     fn execute_command(&self, command: &mut Command) -> Result<String, CommandError> {
+        let output = self.execute_command_output(command)?;
+        Ok(String::from_utf8(output.stdout)?)
+    }
+
+    /// Like [Commander::execute_command], but returns the raw output of a successful command.
+    fn execute_command_output(&self, command: &mut Command) -> Result<Output, CommandError> {
+        if let Some(output) = self
+            .env
+            .command_runner
+            .as_ref()
+            .and_then(|runner| runner.output(command))
+        {
+            return output;
+        }
+
         // Set current directory to root
         command.current_dir(&self.env.root);
 
@@ -138,8 +229,36 @@ impl Commander {
         command.envs(self.env_var.lock().unwrap().iter().cloned());
         self.env_var.lock().unwrap().clear();
 
-        let output = command.output();
-        let output = output?;
+        let start = Instant::now();
+        let op_heads = self.op_heads_before_command();
+        let output = running::run(command);
+        if let Some(op_heads) = op_heads {
+            self.record_command_operations(op_heads);
+        }
+        let record = history::CommandRecord::new(
+            command,
+            output
+                .as_ref()
+                .ok()
+                .and_then(|(output, _)| output.status.code()),
+            start.elapsed(),
+        )
+        .with_output(
+            output
+                .as_ref()
+                .map_or(&[][..], |(output, _)| output.stderr.as_slice()),
+        );
+        let (output, killed) = output?;
+        if killed {
+            let command_line = record.command_line();
+            history::record_command(record);
+            return Err(CommandError::Killed(
+                command_line,
+                start.elapsed().as_secs(),
+            ));
+        }
+        history::record_command(record);
+        snapshot::record_refused_files(&self.env.root, &output.stderr);
 
         if !output.status.success() {
             // Return JjError if non-zero status code
@@ -148,11 +267,26 @@ impl Commander {
                 output.status.code(),
             ));
         }
+        warnings::record_warnings(&self.env.root, &output.stderr);
 
-        Ok(String::from_utf8(output.stdout)?)
+        Ok(output)
+    }
+
+    /// Pass the jj config overrides from the blazingjj config (and tests) to a jj command
+    fn add_config_args(&self, command: &mut Command) {
+        for cfg in self
+            .env
+            .config
+            .jj_config_overrides()
+            .iter()
+            .chain(self.jj_config_toml.iter().flatten())
+        {
+            command.args(["--config", cfg]);
+        }
     }
 
     /// Execute a jj command with color/quiet arguments.
+    /// In the batched [ExecutionMode], queries go through the [batch].
     pub fn execute_jj_command<I, S>(
         &self,
         args: I,
@@ -163,19 +297,127 @@ impl Commander {
         I: IntoIterator<Item = S>,
         S: AsRef<OsStr>,
     {
+        let mut args: Vec<OsString> = args
+            .into_iter()
+            .map(|arg| arg.as_ref().to_owned())
+            .collect();
+        self.check_read_only(&mut args)?;
+        let _lock = if batch::is_query(&args) {
+            None
+        } else {
+            self.lock_mutation()?
+        };
+        match self.env.config.execution_mode() {
+            ExecutionMode::Spawn => self.spawn_jj_command(args, color, quiet),
+            ExecutionMode::Batched => self.execute_batched_jj_command(args, color, quiet),
+        }
+    }
+
+    /// In read-only mode, refuse commands which can change the repository and keep queries from
+    /// snapshotting the working copy
+    fn check_read_only(&self, args: &mut Vec<OsString>) -> Result<(), CommandError> {
+        if !self.env.config.read_only() {
+            return Ok(());
+        }
+        if !batch::is_query(args) {
+            let command = args
+                .iter()
+                .take(2)
+                .map(|arg| arg.to_string_lossy())
+                .collect::<Vec<_>>()
+                .join(" ");
+            return Err(CommandError::ReadOnly(format!("`jj {command}`")));
+        }
+        if !args
+            .iter()
+            .any(|arg| arg == OsStr::new(batch::IGNORE_WORKING_COPY))
+        {
+            args.push(batch::IGNORE_WORKING_COPY.into());
+        }
+        Ok(())
+    }
+
+    /// Spawn jj for a command with color/quiet arguments.
+    fn spawn_jj_command(
+        &self,
+        args: Vec<OsString>,
+        color: bool,
+        quiet: bool,
+    ) -> Result<String, CommandError> {
         let mut command = Command::new(&self.env.jj_bin);
         command.args(args);
         command.args(get_output_args(!self.force_no_color && color, quiet));
 
-        if let Some(jj_config_toml) = &self.jj_config_toml {
-            for cfg in jj_config_toml {
-                command.args(["--config", cfg]);
-            }
-        }
+        self.add_config_args(&mut command);
 
         self.execute_command(&mut command)
     }
 
+    /// Execute a jj command without color and return its stderr, which is where jj prints status
+    /// messages like the `--dry-run` summary.
+    pub fn execute_jj_command_stderr<I, S>(&self, args: I) -> Result<String, CommandError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let mut args: Vec<OsString> = args
+            .into_iter()
+            .map(|arg| arg.as_ref().to_owned())
+            .collect();
+        self.check_read_only(&mut args)?;
+        let _lock = if batch::is_query(&args) {
+            None
+        } else {
+            self.lock_mutation()?
+        };
+
+        let mut command = Command::new(&self.env.jj_bin);
+        command.args(args);
+        command.args(get_output_args(false, false));
+
+        self.add_config_args(&mut command);
+
+        let output = self.execute_command_output(&mut command)?;
+        Ok(String::from_utf8(output.stderr)?)
+    }
+
+    /// Execute a jj command attached to the terminal, so that the user can answer prompts (e.g.
+    /// credentials). The TUI must be suspended while this runs. With `stdout_to_stderr`, the
+    /// output of jj goes to stderr, so it isn't captured with the selection printed on exit.
+    pub fn execute_interactive_jj_command<I, S>(
+        &self,
+        args: I,
+        stdout_to_stderr: bool,
+    ) -> Result<ExitStatus, CommandError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let mut args: Vec<OsString> = args
+            .into_iter()
+            .map(|arg| arg.as_ref().to_owned())
+            .collect();
+        self.check_read_only(&mut args)?;
+
+        let mut command = Command::new(&self.env.jj_bin);
+        command.args(args);
+        command.args(get_output_args(!self.force_no_color, false));
+        self.add_config_args(&mut command);
+        command.current_dir(&self.env.root);
+        if stdout_to_stderr {
+            command.stdout(io::stderr());
+        }
+
+        let start = Instant::now();
+        let status = command.status();
+        history::record_command(history::CommandRecord::new(
+            &command,
+            status.as_ref().ok().and_then(|status| status.code()),
+            start.elapsed(),
+        ));
+        Ok(status?)
+    }
+
     /// Execute a jj command without using the output.
     pub fn execute_void_jj_command<I, S>(&self, args: I) -> Result<(), CommandError>
     where
@@ -240,6 +482,32 @@ impl RemoveEndLine for String {
     }
 }
 
+/// Keep a command from opening a console window on Windows, which flashes up for every command
+/// when blazingjj runs in a terminal without its own console, e.g. Windows Terminal. Only for
+/// commands which don't use the terminal.
+pub(crate) fn hide_console_window(command: &mut Command) -> &mut Command {
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+    command
+}
+
+/// Command running `script` with the platform's shell
+pub(crate) fn shell_command(script: &str) -> Command {
+    if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.args(["/C", script]);
+        command
+    } else {
+        let mut command = Command::new("sh");
+        command.args(["-c", script]);
+        command
+    }
+}
+
 pub fn get_output_args(color: bool, quiet: bool) -> Vec<String> {
     vec![
         "--no-pager",
@@ -288,9 +556,12 @@ pub mod tests {
 
             let env = Env {
                 root: directory.path().to_string_lossy().to_string(),
+                workspace: None,
                 config: Config::default(),
                 default_revset: None,
                 jj_bin,
+                config_errors: vec![],
+                command_runner: None,
             };
 
             let mut commander = Commander::new(&env);
@@ -306,6 +577,19 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn needs_credentials() {
+        let prompt = CommandError::Status(
+            "fatal: could not read Username for 'https://example.com': terminal prompts disabled"
+                .to_owned(),
+            Some(1),
+        );
+        assert!(prompt.needs_credentials());
+
+        let conflict = CommandError::Status("Error: Refusing to push".to_owned(), Some(1));
+        assert!(!conflict.needs_credentials());
+    }
+
     #[test]
     fn test_repo() -> Result<()> {
         apply_common_filters!();
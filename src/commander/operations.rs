@@ -0,0 +1,647 @@
+/*!
+Maintenance of the operation log and the object store, shown in the
+[maintenance_popup][crate::ui::maintenance_popup].
+
+Every jj command which changes the repository adds an operation, so the
+operation log of long lived repositories grows without bound. Old operations
+can be abandoned with `jj op abandon ..<operation>`, after which `jj util gc`
+removes the objects only they referenced.
+
+The [operations_popup][crate::ui::operations_popup] lists the operations with
+the heads and bookmarks as of each, to pick one to restore to.
+
+Actions of several steps, e.g. creating a change and then describing it, are
+run as a [CompositeAction]: the operation before the first step is recorded,
+and if a later step fails, the [rollback_popup][crate::ui::rollback_popup]
+offers to restore it, undoing the completed steps as a group instead of
+leaving the repository halfway.
+*/
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use itertools::Itertools;
+use thiserror::Error;
+use tracing::{instrument, warn};
+
+use crate::commander::{CommandError, Commander};
+
+/// Id of the root operation, which can't be abandoned
+const ROOT_OPERATION_ID: &str = "000000000000";
+
+/// Most operations listed for a failed [CompositeAction]. Actions only have a few steps, so the
+/// operation log of long lived repositories isn't read in full.
+const MAX_ACTION_OPERATIONS: usize = 100;
+
+/// An operation of the operation log
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Operation {
+    pub id: String,
+    /// When the operation started, e.g. `3 weeks ago`
+    pub age: String,
+    /// First line of the description, e.g. `describe commit 0123abcd`
+    pub description: String,
+}
+
+/// The repository as of an operation, to judge which operation to restore to
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OperationSummary {
+    /// Visible heads with their short change id and description, the working copy marked with `@`
+    pub heads: Vec<String>,
+    /// Local bookmarks with the short change id they point to
+    pub bookmarks: Vec<String>,
+}
+
+/// An action of several steps in progress, see [Commander::begin_action]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CompositeAction {
+    /// What the action does, e.g. `Move files into a new change`
+    pub name: String,
+    /// Operation before the first step, restoring it rolls the action back
+    pub start_operation: String,
+}
+
+/// Operation heads before and after a command of a [CompositeAction] which changed the
+/// repository. The operations in between are the command's, e.g. a snapshot and a rebase.
+#[derive(Debug)]
+struct CommandOperations {
+    before: Vec<String>,
+    after: Vec<String>,
+}
+
+/// Operations of the commands run since [Commander::begin_action], see
+/// [Commander::roll_back_action]
+#[derive(Debug)]
+pub struct ActionTracking {
+    start_operation: String,
+    commands: Vec<CommandOperations>,
+}
+
+/// A step of a [CompositeAction] failed after earlier steps changed the repository
+#[derive(Debug, Error)]
+#[error("{} failed after {} operations: {error:#}", action.name, operations.len())]
+pub struct PartialActionError {
+    pub action: CompositeAction,
+    /// Operations of the completed steps, newest first
+    pub operations: Vec<Operation>,
+    pub error: anyhow::Error,
+}
+
+/// Operations in the output of `jj op log` with [OPERATION_TEMPLATE], without the root operation
+fn parse_operations(output: &str) -> Vec<Operation> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, '\t');
+            Some(Operation {
+                id: parts.next()?.to_owned(),
+                age: parts.next()?.to_owned(),
+                description: parts.next().unwrap_or_default().to_owned(),
+            })
+        })
+        .filter(|operation| operation.id != ROOT_OPERATION_ID)
+        .collect()
+}
+
+const OPERATION_TEMPLATE: &str =
+    r#"id.short() ++ "\t" ++ time.start().ago() ++ "\t" ++ description.first_line() ++ "\n""#;
+
+/// Size of the operation log and the object store
+#[derive(Clone, Debug, Default)]
+pub struct MaintenanceInfo {
+    /// Operations, newest first and without the root operation
+    pub operations: Vec<Operation>,
+    /// Size in bytes of the operation store, if it could be determined
+    pub op_store_size: Option<u64>,
+    /// Size in bytes of the object store, if it could be determined
+    pub store_size: Option<u64>,
+}
+
+/// Total size of the files in a directory, recursively
+pub fn directory_size(path: &Path) -> Option<u64> {
+    let mut size = 0;
+    for entry in fs::read_dir(path).ok()? {
+        let entry = entry.ok()?;
+        let metadata = entry.metadata().ok()?;
+        size += if metadata.is_dir() {
+            directory_size(&entry.path())?
+        } else {
+            metadata.len()
+        };
+    }
+    Some(size)
+}
+
+impl Commander {
+    /// Directory of the repository store. In secondary workspaces, `.jj/repo` is a file with the
+    /// path of the store relative to `.jj`.
+    pub fn repo_store_path(&self) -> PathBuf {
+        let jj_path = PathBuf::from(&self.env.root).join(".jj");
+        let repo_path = jj_path.join("repo");
+        match fs::read_to_string(&repo_path) {
+            Ok(content) => jj_path.join(content.trim()),
+            Err(_) => repo_path,
+        }
+    }
+
+    /// Ids of the current operation heads, read from the store without running jj. They change
+    /// with every operation, so they tell cheaply whether the repository changed.
+    pub fn get_op_heads(&self) -> Vec<String> {
+        let Ok(entries) = fs::read_dir(self.repo_store_path().join("op_heads").join("heads"))
+        else {
+            return vec![];
+        };
+        let mut op_heads: Vec<String> = entries
+            .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+            .collect();
+        op_heads.sort();
+        op_heads
+    }
+
+    /// Get the operations of the operation log, newest first and without the root operation.
+    /// Maps to `jj op log`
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_operations(&self) -> Result<Vec<Operation>, CommandError> {
+        let output = self.execute_jj_command(
+            vec!["op", "log", "--no-graph", "--template", OPERATION_TEMPLATE],
+            false,
+            true,
+        )?;
+        Ok(parse_operations(&output))
+    }
+
+    /// Get the `limit` newest operations, without the root operation. Fewer operations than
+    /// `limit` means these are all of them. Maps to `jj op log --limit <limit>`
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_recent_operations(&self, limit: usize) -> Result<Vec<Operation>, CommandError> {
+        let output = self.execute_jj_command(
+            vec![
+                "op",
+                "log",
+                "--no-graph",
+                "--limit",
+                &limit.to_string(),
+                "--template",
+                OPERATION_TEMPLATE,
+            ],
+            false,
+            true,
+        )?;
+        Ok(parse_operations(&output))
+    }
+
+    /// Get the heads and bookmarks of the repository as of an operation. Maps to
+    /// `jj log --at-op <operation>` and `jj bookmark list --at-op <operation>`
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_operation_summary(&self, id: &str) -> Result<OperationSummary, CommandError> {
+        let heads = self.execute_jj_command(
+            vec![
+                "log",
+                "--at-op",
+                id,
+                "--ignore-working-copy",
+                "--no-graph",
+                "-r",
+                "visible_heads()",
+                "-T",
+                r#"if(current_working_copy, "@ ", "  ") ++ change_id.shortest(8) ++ " " ++ if(description, description.first_line(), "(no description set)") ++ "\n""#,
+            ],
+            false,
+            true,
+        )?;
+        let bookmarks = self.execute_jj_command(
+            vec![
+                "bookmark",
+                "list",
+                "--at-op",
+                id,
+                "--ignore-working-copy",
+                "-T",
+                r#"if(!remote, name ++ " " ++ if(normal_target, normal_target.change_id().shortest(8), "(conflicted)") ++ "\n")"#,
+            ],
+            false,
+            true,
+        )?;
+        Ok(OperationSummary {
+            heads: heads.lines().map(str::to_owned).collect(),
+            bookmarks: bookmarks.lines().map(str::to_owned).collect(),
+        })
+    }
+
+    /// Restore the repository to the state as of an operation. Maps to `jj op restore <operation>`
+    #[instrument(level = "trace", skip(self))]
+    pub fn restore_operation(&self, id: &str) -> Result<String, CommandError> {
+        self.execute_jj_command_stderr(vec!["op", "restore", id])
+    }
+
+    /// Start an action of several steps, recording the current operation after snapshotting
+    /// the working copy. Maps to `jj op log --limit 1`
+    #[instrument(level = "trace", skip(self))]
+    pub fn begin_action(&self, name: &str) -> Result<CompositeAction, CommandError> {
+        let output = self.execute_jj_command(
+            vec![
+                "op",
+                "log",
+                "--no-graph",
+                "--limit",
+                "1",
+                "--template",
+                "id.short()",
+            ],
+            false,
+            true,
+        )?;
+        let start_operation = output.trim().to_owned();
+        *self.action_tracking.lock().unwrap() = Some(ActionTracking {
+            start_operation: start_operation.clone(),
+            commands: vec![],
+        });
+        Ok(CompositeAction {
+            name: name.to_owned(),
+            start_operation,
+        })
+    }
+
+    /// Operation heads before a command, if an action is tracked, see
+    /// [Commander::record_command_operations]
+    pub(super) fn op_heads_before_command(&self) -> Option<Vec<String>> {
+        self.action_tracking
+            .lock()
+            .unwrap()
+            .as_ref()
+            .filter(|tracking| tracking.commands.len() < MAX_ACTION_OPERATIONS)
+            .map(|_| self.get_op_heads())
+    }
+
+    /// Record the operations of a command of the tracked action, if it changed the repository
+    pub(super) fn record_command_operations(&self, before: Vec<String>) {
+        let after = self.get_op_heads();
+        if after == before {
+            return;
+        }
+        if let Some(tracking) = self.action_tracking.lock().unwrap().as_mut() {
+            tracking.commands.push(CommandOperations { before, after });
+        }
+    }
+
+    /// Operations since the start of an action, newest first. Maps to
+    /// `jj op log --limit <MAX_ACTION_OPERATIONS>`
+    fn get_action_operations(
+        &self,
+        action: &CompositeAction,
+    ) -> Result<Vec<Operation>, CommandError> {
+        // Without snapshotting, which would add an operation of its own
+        let output = self.execute_jj_command(
+            vec![
+                "op",
+                "log",
+                "--no-graph",
+                "--ignore-working-copy",
+                "--limit",
+                &MAX_ACTION_OPERATIONS.to_string(),
+                "--template",
+                OPERATION_TEMPLATE,
+            ],
+            false,
+            true,
+        )?;
+        let mut operations = parse_operations(&output);
+        let Some(start) = operations
+            .iter()
+            .position(|operation| operation.id == action.start_operation)
+        else {
+            return Err(CommandError::Status(
+                format!(
+                    "The operation before {} is not among the last {MAX_ACTION_OPERATIONS} \
+                     operations",
+                    action.name
+                ),
+                None,
+            ));
+        };
+        operations.truncate(start);
+        Ok(operations)
+    }
+
+    /// The operations of another process, like jj in a terminal or a background refresh, among
+    /// the operations since the start of an action
+    fn foreign_operations<'a>(
+        &self,
+        action: &CompositeAction,
+        operations: &'a [Operation],
+    ) -> Vec<&'a Operation> {
+        let tracking = self.action_tracking.lock().unwrap();
+        let commands = tracking
+            .as_ref()
+            .filter(|tracking| tracking.start_operation == action.start_operation)
+            .map_or(&[][..], |tracking| tracking.commands.as_slice());
+        let position = |heads: &[String]| {
+            operations.iter().position(|operation| {
+                heads
+                    .iter()
+                    .any(|head| head.starts_with(operation.id.as_str()))
+            })
+        };
+        let mut own = vec![false; operations.len()];
+        for command in commands {
+            let Some(after) = position(&command.after) else {
+                continue;
+            };
+            // The start operation isn't listed
+            let before = position(&command.before).unwrap_or(operations.len());
+            for own in own.iter_mut().take(before).skip(after) {
+                *own = true;
+            }
+        }
+        operations
+            .iter()
+            .zip(own)
+            .filter(|(_, own)| !own)
+            .map(|(operation, _)| operation)
+            .collect()
+    }
+
+    /// Error for a failed step of an action: a [PartialActionError] if earlier steps added
+    /// operations, otherwise `error` itself as there is nothing to roll back. Maps to
+    /// `jj op log --limit <MAX_ACTION_OPERATIONS>`
+    #[instrument(level = "trace", skip(self))]
+    pub fn fail_action(&self, action: CompositeAction, error: anyhow::Error) -> anyhow::Error {
+        let operations = match self.get_action_operations(&action) {
+            Ok(operations) => operations,
+            Err(err) => {
+                warn!("Failed listing the operations of {}: {err}", action.name);
+                return error;
+            }
+        };
+        if operations.is_empty() {
+            return error;
+        }
+        PartialActionError {
+            action,
+            operations,
+            error,
+        }
+        .into()
+    }
+
+    /// Run an action of several steps, see [Commander::begin_action] and
+    /// [Commander::fail_action]. Other instances can't change the repository between the steps,
+    /// as the action holds the [mutation lock][Commander::wait_for_mutation_lock] throughout.
+    pub fn run_composite_action<T>(
+        &self,
+        name: &str,
+        action: impl FnOnce(&Self) -> Result<T>,
+    ) -> Result<T> {
+        let _lock = self.wait_for_mutation_lock()?;
+        let composite = self.begin_action(name)?;
+        action(self).map_err(|err| self.fail_action(composite, err))
+    }
+
+    /// Roll back an action by restoring the operation before its first step. Refused if other
+    /// processes added operations since, as restoring would undo them too.
+    /// Maps to `jj op log --limit <MAX_ACTION_OPERATIONS>` and `jj op restore <operation>`
+    #[instrument(level = "trace", skip(self))]
+    pub fn roll_back_action(&self, action: &CompositeAction) -> Result<String, CommandError> {
+        let operations = self.get_action_operations(action)?;
+        let foreign = self.foreign_operations(action, &operations);
+        if !foreign.is_empty() {
+            return Err(CommandError::Status(
+                format!(
+                    "Not rolled back, as restoring the operation before {} would also undo these \
+                     operations of other processes:\n{}\nUndo the operations of the action one \
+                     by one in the operations popup instead",
+                    action.name,
+                    foreign
+                        .iter()
+                        .map(|operation| format!(
+                            "{} {} {}",
+                            operation.id, operation.age, operation.description
+                        ))
+                        .join("\n")
+                ),
+                None,
+            ));
+        }
+        self.restore_operation(&action.start_operation)
+    }
+
+    /// Get the operations and the size of the stores
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_maintenance_info(&self) -> Result<MaintenanceInfo, CommandError> {
+        let store_path = self.repo_store_path();
+        Ok(MaintenanceInfo {
+            operations: self.get_operations()?,
+            op_store_size: directory_size(&store_path.join("op_store")),
+            store_size: directory_size(&store_path.join("store")),
+        })
+    }
+
+    /// Abandon all but the `keep` newest operations. Maps to `jj op abandon ..<operation>`
+    #[instrument(level = "trace", skip(self))]
+    pub fn abandon_old_operations(&self, keep: usize) -> Result<String, CommandError> {
+        let operations = self.get_operations()?;
+        let Some(operation) = operations.get(keep) else {
+            return Ok(format!(
+                "Nothing to abandon, there are only {} operations",
+                operations.len()
+            ));
+        };
+        self.execute_jj_command_stderr(vec!["op", "abandon", &format!("..{}", operation.id)])
+    }
+
+    /// Remove objects no longer referenced by any operation. Maps to
+    /// `jj util gc --expire=now`
+    #[instrument(level = "trace", skip(self))]
+    pub fn run_gc(&self) -> Result<String, CommandError> {
+        self.execute_jj_command_stderr(vec!["util", "gc", "--expire=now"])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commander::tests::TestRepo;
+    use anyhow::Result;
+
+    #[test]
+    fn abandon_old_operations() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+        for description in ["first", "second", "third"] {
+            test_repo
+                .commander
+                .execute_void_jj_command(["describe", "-m", description])?;
+        }
+
+        let info = test_repo.commander.get_maintenance_info()?;
+        assert!(info.operations.len() > 2);
+        assert!(info.op_store_size.is_some_and(|size| size > 0));
+        assert!(info.store_size.is_some());
+
+        test_repo.commander.abandon_old_operations(2)?;
+        assert_eq!(test_repo.commander.get_operations()?.len(), 2);
+
+        let output = test_repo.commander.abandon_old_operations(2)?;
+        assert!(output.starts_with("Nothing to abandon"));
+
+        test_repo.commander.run_gc()?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn recent_operations() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+        for description in ["first", "second", "third"] {
+            test_repo
+                .commander
+                .execute_void_jj_command(["describe", "-m", description])?;
+        }
+
+        let operations = test_repo.commander.get_operations()?;
+        let recent = test_repo.commander.get_recent_operations(2)?;
+        assert_eq!(
+            recent.iter().map(|op| &op.id).collect::<Vec<_>>(),
+            operations
+                .iter()
+                .take(2)
+                .map(|op| &op.id)
+                .collect::<Vec<_>>()
+        );
+        // Without the root operation, asking for more returns all of them
+        let all = test_repo
+            .commander
+            .get_recent_operations(operations.len() + 1)?;
+        assert_eq!(all.len(), operations.len());
+
+        Ok(())
+    }
+
+    #[test]
+    fn op_heads() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+
+        let op_heads = test_repo.commander.get_op_heads();
+        assert_eq!(op_heads.len(), 1);
+        assert!(op_heads[0].starts_with(&test_repo.commander.get_operations()?[0].id));
+
+        test_repo
+            .commander
+            .execute_void_jj_command(["describe", "-m", "changed"])?;
+        assert_ne!(test_repo.commander.get_op_heads(), op_heads);
+
+        Ok(())
+    }
+
+    #[test]
+    fn operation_summary() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+        test_repo
+            .commander
+            .execute_void_jj_command(["bookmark", "create", "-r", "@", "first"])?;
+        test_repo
+            .commander
+            .execute_void_jj_command(["describe", "-m", "first"])?;
+        let operation = test_repo.commander.get_operations()?.remove(0);
+        assert!(operation.description.starts_with("describe"));
+
+        test_repo
+            .commander
+            .execute_void_jj_command(["bookmark", "delete", "first"])?;
+        test_repo
+            .commander
+            .execute_void_jj_command(["new", "-m", "second"])?;
+
+        let summary = test_repo.commander.get_operation_summary(&operation.id)?;
+        assert_eq!(summary.heads.len(), 1);
+        assert!(summary.heads[0].starts_with("@ "));
+        assert!(summary.heads[0].ends_with(" first"));
+        assert_eq!(summary.bookmarks.len(), 1);
+        assert!(summary.bookmarks[0].starts_with("first "));
+
+        test_repo.commander.restore_operation(&operation.id)?;
+        assert_eq!(
+            test_repo
+                .commander
+                .get_operation_summary(&test_repo.commander.get_operations()?[0].id)?,
+            summary
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn composite_action() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+        let describe = |commander: &Commander, description: &str| {
+            commander.execute_void_jj_command(["describe", "-m", description])
+        };
+
+        // Failing before any change leaves nothing to roll back
+        let err = test_repo
+            .commander
+            .run_composite_action("Nothing", |_| -> Result<()> { anyhow::bail!("failed") })
+            .unwrap_err();
+        assert!(err.downcast_ref::<PartialActionError>().is_none());
+
+        let err = test_repo
+            .commander
+            .run_composite_action("Describe and fail", |commander| -> Result<()> {
+                describe(commander, "first")?;
+                describe(commander, "second")?;
+                anyhow::bail!("third step failed")
+            })
+            .unwrap_err();
+        let partial = err.downcast::<PartialActionError>()?;
+        assert_eq!(partial.operations.len(), 2);
+        assert!(partial.operations[0].description.starts_with("describe"));
+        assert_eq!(partial.error.to_string(), "third step failed");
+
+        // A start operation beyond the listed operations can't be rolled back to
+        let err = test_repo.commander.fail_action(
+            CompositeAction {
+                name: "Unknown start".to_owned(),
+                start_operation: "ffffffffffff".to_owned(),
+            },
+            anyhow::anyhow!("failed"),
+        );
+        assert!(err.downcast_ref::<PartialActionError>().is_none());
+
+        test_repo.commander.roll_back_action(&partial.action)?;
+        let head = test_repo.commander.get_current_head()?;
+        assert_eq!(
+            test_repo
+                .commander
+                .get_commit_description(&head.commit_id)?,
+            ""
+        );
+
+        // Restoring would also undo the operations of other processes
+        let err = test_repo
+            .commander
+            .run_composite_action("Describe and fail", |commander| -> Result<()> {
+                describe(commander, "mine")?;
+                describe(&commander.background(), "other process")?;
+                anyhow::bail!("failed")
+            })
+            .unwrap_err();
+        let partial = err.downcast::<PartialActionError>()?;
+        assert_eq!(partial.operations.len(), 2);
+        let err = test_repo
+            .commander
+            .roll_back_action(&partial.action)
+            .unwrap_err();
+        assert!(err.to_string().contains("Not rolled back"));
+        assert!(err.to_string().contains("describe commit"));
+        let head = test_repo.commander.get_current_head()?;
+        assert_eq!(
+            test_repo
+                .commander
+                .get_commit_description(&head.commit_id)?,
+            "other process"
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,162 @@
+/*!
+Rewriting the output of commands with the `blazingjj.output-processors` before
+it is shown in a popup or the command log, e.g. to strip the progress lines of
+a chatty remote or hook, or to turn issue numbers into links.
+
+The processors only change what is shown, the output is parsed by the
+[Commander][crate::commander::Commander] functions as jj printed it. They are
+chosen by the command lines of the commands which produced the output, see
+[history::operation_command_lines].
+*/
+use ansi_to_tui::IntoText;
+use ratatui::text::Text;
+
+use crate::{
+    commander::{ANSI_REGEX, CommandError, Commander, history, titled_error_text},
+    env::OutputProcessor,
+};
+
+/// Apply the processors applying to one of the command lines to each line of the output. The
+/// patterns match the lines without their colors, so a rewritten line loses its colors, the other
+/// lines keep them. Lines which are emptied by a processor are removed.
+pub fn process_output(
+    processors: &[OutputProcessor],
+    command_lines: &[String],
+    output: &str,
+) -> String {
+    let processors: Vec<&OutputProcessor> = processors
+        .iter()
+        .filter(|processor| processor.applies_to(command_lines))
+        .collect();
+    if processors.is_empty() {
+        return output.to_owned();
+    }
+
+    let mut processed = String::new();
+    for line in output.lines() {
+        let plain_line = ANSI_REGEX.replace_all(line, "");
+        let mut new_line = plain_line.clone().into_owned();
+        for processor in &processors {
+            new_line = processor
+                .pattern
+                .regex()
+                .replace_all(&new_line, processor.replacement.as_str())
+                .into_owned();
+        }
+        if new_line.trim().is_empty() && !plain_line.trim().is_empty() {
+            continue;
+        }
+        if new_line == plain_line {
+            processed.push_str(line);
+        } else {
+            processed.push_str(&new_line);
+        }
+        processed.push('\n');
+    }
+    if !output.ends_with('\n') {
+        processed.pop();
+    }
+    processed
+}
+
+/// [process_output] for the output of an operation of a
+/// [loader_popup][crate::ui::loader_popup], with the processors applying to its commands
+pub fn process_operation_output(
+    processors: &[OutputProcessor],
+    operation: u64,
+    output: &str,
+) -> String {
+    if processors.is_empty() {
+        return output.to_owned();
+    }
+    process_output(
+        processors,
+        &history::operation_command_lines(operation),
+        output,
+    )
+}
+
+impl Commander {
+    /// [process_output] with the processors of the config, for the output of a command shown in a
+    /// popup
+    pub fn process_output(&self, command_line: &str, output: &str) -> String {
+        process_output(
+            self.env.config.output_processors(),
+            &[command_line.to_owned()],
+            output,
+        )
+    }
+
+    /// [CommandError::into_text] with the message rewritten by the processors applying to the
+    /// last failed command, which is the failed command for the errors of the commands run while
+    /// handling input
+    pub fn error_text<'a>(
+        &self,
+        err: &CommandError,
+        title: &'a str,
+    ) -> Result<Text<'a>, ansi_to_tui::Error> {
+        let processors = self.env.config.output_processors();
+        if processors.is_empty() {
+            return err.into_text(title);
+        }
+        let command_lines: Vec<String> = history::last_failed_command_line().into_iter().collect();
+        let message = process_output(processors, &command_lines, &err.to_string());
+        Ok(titled_error_text(title, message.into_text()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn processor(commands: &[&str], pattern: &str, replacement: &str) -> OutputProcessor {
+        OutputProcessor {
+            commands: commands.iter().map(|command| command.to_string()).collect(),
+            pattern: pattern.to_owned().try_into().unwrap(),
+            replacement: replacement.to_owned(),
+        }
+    }
+
+    #[test]
+    fn process_lines() {
+        let processors = [
+            processor(
+                &["jj git push", "jj git fetch"],
+                r"^remote: (Counting|Compressing).*",
+                "",
+            ),
+            processor(&[], r"#(\d+)", "#$1 (https://example.com/issues/$1)"),
+        ];
+        let output =
+            "remote: Counting objects: 3\nremote: Compressing objects: 100%\n\nFixes #12\n";
+
+        assert_eq!(
+            process_output(
+                &processors,
+                &["jj git push --bookmark main".to_owned()],
+                output
+            ),
+            "\nFixes #12 (https://example.com/issues/12)\n"
+        );
+        // Only the processors without commands apply to other commands
+        assert_eq!(
+            process_output(&processors, &["jj describe".to_owned()], output),
+            "remote: Counting objects: 3\nremote: Compressing objects: 100%\n\n\
+             Fixes #12 (https://example.com/issues/12)\n"
+        );
+        assert_eq!(process_output(&[], &[], "a\nb"), "a\nb");
+        assert_eq!(
+            process_output(&processors[1..], &[], "#1"),
+            "#1 (https://example.com/issues/1)"
+        );
+        // The patterns match the lines without colors, the lines which aren't rewritten keep them
+        assert_eq!(
+            process_output(
+                &processors,
+                &["jj git fetch".to_owned()],
+                "\x1b[1mremote: Counting\x1b[0m objects\n\x1b[1mFixes\x1b[0m #\x1b[32m3\x1b[0m\n\x1b[1mdone\x1b[0m"
+            ),
+            "Fixes #3 (https://example.com/issues/3)\n\x1b[1mdone\x1b[0m"
+        );
+    }
+}
@@ -0,0 +1,279 @@
+/*!
+[Commander] member functions to preview a `jj git push`.
+
+The preview is built from the `jj git push --dry-run` summary, which lists
+the bookmark updates jj would send, plus the commits that are not yet on
+the remote. It is shown by the
+[push_preview_popup][crate::ui::push_preview_popup] before pushing.
+
+The preview then runs `blazingjj.push-test-command`, if configured, and only
+pushes if it succeeds.
+*/
+use crate::commander::{CommandError, Commander, ids::CommitId, shell_command};
+
+use regex::Regex;
+use std::sync::LazyLock;
+use tracing::instrument;
+
+/// Maximum number of commits listed in a push preview
+const PUSH_PREVIEW_COMMIT_LIMIT: usize = 50;
+
+/// How a bookmark is updated on the remote
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BookmarkPushKind {
+    /// Creates a new bookmark on the remote
+    Add,
+    MoveForward,
+    MoveSideways,
+    MoveBackward,
+    /// Older jj versions report non fast-forward moves as "Force bookmark"
+    Force,
+    Delete,
+}
+
+impl BookmarkPushKind {
+    /// Whether the update rewrites history on the remote
+    pub fn is_forced(&self) -> bool {
+        matches!(
+            self,
+            BookmarkPushKind::MoveSideways
+                | BookmarkPushKind::MoveBackward
+                | BookmarkPushKind::Force
+        )
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BookmarkPush {
+    pub name: String,
+    pub kind: BookmarkPushKind,
+    /// Commit the bookmark currently points to on the remote
+    pub old_target: Option<String>,
+    /// Commit the bookmark will point to on the remote
+    pub new_target: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PushPreview {
+    pub remote: Option<String>,
+    pub bookmarks: Vec<BookmarkPush>,
+    /// Commits which are not on the remote yet, formatted as `<commit id> <description>`
+    pub commits: Vec<String>,
+}
+
+impl PushPreview {
+    pub fn is_empty(&self) -> bool {
+        self.bookmarks.is_empty()
+    }
+
+    pub fn is_forced(&self) -> bool {
+        self.bookmarks
+            .iter()
+            .any(|bookmark| bookmark.kind.is_forced())
+    }
+}
+
+static REMOTE_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^Changes to push to (.+):$").unwrap());
+static BOOKMARK_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(Add|Move forward|Move sideways|Move backward|Force|Delete) bookmark (\S+)(?: from (\S+))?(?: to (\S+))?$",
+    )
+    .unwrap()
+});
+
+/// Parse the summary printed by `jj git push --dry-run`
+pub fn parse_push_dry_run(output: &str) -> PushPreview {
+    let mut preview = PushPreview::default();
+    for line in output.lines().map(str::trim) {
+        if let Some(captures) = REMOTE_REGEX.captures(line) {
+            preview.remote = Some(captures[1].to_owned());
+        } else if let Some(captures) = BOOKMARK_REGEX.captures(line) {
+            let kind = match &captures[1] {
+                "Add" => BookmarkPushKind::Add,
+                "Move forward" => BookmarkPushKind::MoveForward,
+                "Move sideways" => BookmarkPushKind::MoveSideways,
+                "Move backward" => BookmarkPushKind::MoveBackward,
+                "Force" => BookmarkPushKind::Force,
+                _ => BookmarkPushKind::Delete,
+            };
+            preview.bookmarks.push(BookmarkPush {
+                name: captures[2].to_owned(),
+                kind,
+                old_target: captures.get(3).map(|m| m.as_str().to_owned()),
+                new_target: captures.get(4).map(|m| m.as_str().to_owned()),
+            });
+        }
+    }
+    preview
+}
+
+impl Commander {
+    /// Summary of what a git push would do, without changing anything.
+    /// Maps to `jj git push --dry-run`
+    #[instrument(level = "trace", skip(self))]
+    pub fn git_push_dry_run(
+        &self,
+        all_bookmarks: bool,
+        allow_new: bool,
+        commit_id: &CommitId,
+    ) -> Result<String, CommandError> {
+        let mut args = Self::git_push_args(all_bookmarks, allow_new, commit_id);
+        args.push("--dry-run".to_owned());
+
+        // Same as for the actual push, the dry run contacts the remote
+        self.set_git_non_interactive();
+        self.execute_jj_command_stderr(args)
+    }
+
+    /// Preview a git push. Maps to `jj git push --dry-run` followed by a `jj log` of the commits
+    /// missing on the remote
+    #[instrument(level = "trace", skip(self))]
+    pub fn git_push_preview(
+        &self,
+        all_bookmarks: bool,
+        allow_new: bool,
+        commit_id: &CommitId,
+    ) -> Result<PushPreview, CommandError> {
+        let mut preview =
+            parse_push_dry_run(&self.git_push_dry_run(all_bookmarks, allow_new, commit_id)?);
+
+        let new_targets: Vec<&str> = preview
+            .bookmarks
+            .iter()
+            .filter_map(|bookmark| bookmark.new_target.as_deref())
+            .collect();
+        if let (Some(remote), false) = (preview.remote.as_ref(), new_targets.is_empty()) {
+            let revset = format!(
+                r#"remote_bookmarks(remote=exact:"{remote}")..({})"#,
+                new_targets.join(" | ")
+            );
+            let limit = PUSH_PREVIEW_COMMIT_LIMIT.to_string();
+            preview.commits = self
+                .execute_jj_command(
+                    vec![
+                        "log",
+                        "--no-graph",
+                        "-r",
+                        revset.as_str(),
+                        "--limit",
+                        limit.as_str(),
+                        "-T",
+                        r#"commit_id.short() ++ " " ++ if(description, description.first_line(), "(no description set)") ++ "\n""#,
+                    ],
+                    false,
+                    true,
+                )?
+                .lines()
+                .map(str::to_owned)
+                .collect();
+        }
+
+        Ok(preview)
+    }
+
+    /// Run `blazingjj.push-test-command` in the repository root, with `BLAZINGJJ_REVISION` set
+    /// to the pushed revision unless all bookmarks are pushed. Returns
+    /// [CommandError::TestsFailed] with the output if it fails.
+    #[instrument(level = "trace", skip(self))]
+    pub fn run_push_test(&self, revision: Option<&CommitId>) -> Result<(), CommandError> {
+        let Some(test_command) = self.env.config.push_test_command() else {
+            return Ok(());
+        };
+
+        // Test runners print their results to stdout, but only stderr is kept on failure
+        let script = if cfg!(windows) {
+            format!("({test_command}) 1>&2")
+        } else {
+            format!("exec 1>&2\n{test_command}")
+        };
+        let mut command = shell_command(&script);
+        command.env("BLAZINGJJ_REPO_ROOT", &self.env.root);
+        if let Some(revision) = revision {
+            command.env("BLAZINGJJ_REVISION", revision.as_str());
+        }
+
+        match self.execute_command_output(&mut command) {
+            Ok(_) => Ok(()),
+            Err(CommandError::Status(output, _)) => Err(CommandError::TestsFailed(output)),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commander::tests::TestRepo;
+    use anyhow::Result;
+
+    #[test]
+    fn parse_dry_run() {
+        let preview = parse_push_dry_run(
+            "Changes to push to origin:
+  Add bookmark feature to 0123456789ab
+  Move forward bookmark main from 0123456789ab to 89abcdef0123
+  Move sideways bookmark topic from 0123456789ab to 89abcdef0123
+  Delete bookmark old from 0123456789ab
+Dry-run requested, not pushing.
+",
+        );
+
+        assert_eq!(preview.remote.as_deref(), Some("origin"));
+        assert_eq!(
+            preview.bookmarks,
+            vec![
+                BookmarkPush {
+                    name: "feature".to_owned(),
+                    kind: BookmarkPushKind::Add,
+                    old_target: None,
+                    new_target: Some("0123456789ab".to_owned()),
+                },
+                BookmarkPush {
+                    name: "main".to_owned(),
+                    kind: BookmarkPushKind::MoveForward,
+                    old_target: Some("0123456789ab".to_owned()),
+                    new_target: Some("89abcdef0123".to_owned()),
+                },
+                BookmarkPush {
+                    name: "topic".to_owned(),
+                    kind: BookmarkPushKind::MoveSideways,
+                    old_target: Some("0123456789ab".to_owned()),
+                    new_target: Some("89abcdef0123".to_owned()),
+                },
+                BookmarkPush {
+                    name: "old".to_owned(),
+                    kind: BookmarkPushKind::Delete,
+                    old_target: Some("0123456789ab".to_owned()),
+                    new_target: None,
+                },
+            ]
+        );
+        assert!(preview.is_forced());
+    }
+
+    #[test]
+    fn parse_dry_run_nothing_changed() {
+        let preview = parse_push_dry_run("Nothing changed.\n");
+        assert!(preview.is_empty());
+        assert!(!preview.is_forced());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn push_test_gates_push() -> Result<()> {
+        let mut test_repo = TestRepo::new()?;
+        test_repo.commander.run_push_test(None)?;
+
+        test_repo.commander.env.config = toml::from_str(
+            r#""blazingjj.push-test-command" = 'echo "broken $BLAZINGJJ_REVISION"; exit 1'"#,
+        )?;
+        let err = test_repo
+            .commander
+            .run_push_test(Some(&CommitId("abc".to_owned())))
+            .unwrap_err();
+        assert!(matches!(err, CommandError::TestsFailed(output) if output.contains("broken abc")));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,125 @@
+/*!
+[Commander] member functions recovering a working copy which jj refuses to
+use, offered by the [recovery_popup][crate::ui::recovery_popup].
+
+A working copy becomes stale when another workspace, or a command
+interrupted at the wrong time, changed the repository without updating it.
+Its state in `.jj/working_copy` can also be corrupted, e.g. by a crash or a
+full disk. Restoring an earlier operation is done in the
+[operations_popup][crate::ui::operations_popup].
+*/
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use tracing::{instrument, warn};
+
+use crate::commander::{CommandError, Commander};
+
+/// Files of the working copy state describing which working copy and operation are checked out,
+/// as opposed to the tree state of the files on disk
+const WORKING_COPY_KEPT_FILES: [&str; 2] = ["type", "checkout"];
+
+impl Commander {
+    fn working_copy_state_path(&self) -> PathBuf {
+        PathBuf::from(&self.env.root)
+            .join(".jj")
+            .join("working_copy")
+    }
+
+    /// Update a stale working copy to the change it should have checked out. Maps to
+    /// `jj workspace update-stale`
+    #[instrument(level = "trace", skip(self))]
+    pub fn update_stale_workspace(&self) -> Result<String, CommandError> {
+        self.execute_jj_command_stderr(vec!["workspace", "update-stale"])
+    }
+
+    fn recreate_working_copy_state(&self, state: &Path, backup: &Path) -> Result<(), CommandError> {
+        fs::create_dir(state)?;
+        for file in WORKING_COPY_KEPT_FILES {
+            if backup.join(file).is_file() {
+                fs::copy(backup.join(file), state.join(file))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Check out the working copy change anew, for a corrupted working copy state. The state is
+    /// moved aside to `.jj/working_copy.broken-<timestamp>` and recreated by
+    /// `jj workspace update-stale`, which keeps the files on disk in a recovery change. Only the
+    /// [WORKING_COPY_KEPT_FILES] are copied back, which jj needs to load the working copy at all.
+    /// If that fails, the state is moved back.
+    #[instrument(level = "trace", skip(self))]
+    pub fn recheckout_working_copy(&self) -> Result<String, CommandError> {
+        let state = self.working_copy_state_path();
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let backup = state.with_extension(format!("broken-{timestamp}"));
+        fs::rename(&state, &backup)?;
+
+        match self
+            .recreate_working_copy_state(&state, &backup)
+            .and_then(|()| self.update_stale_workspace())
+        {
+            Ok(output) => Ok(format!(
+                "{output}\nThe previous working copy state was kept in {}",
+                backup.display()
+            )),
+            Err(err) => {
+                // jj may have created a partial state
+                let _ = fs::remove_dir_all(&state);
+                if let Err(restore_err) = fs::rename(&backup, &state) {
+                    warn!("Failed moving {} back: {restore_err}", backup.display());
+                }
+                Err(err)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use crate::commander::tests::TestRepo;
+    use anyhow::Result;
+
+    #[test]
+    fn update_stale_workspace() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+        test_repo.commander.update_stale_workspace()?;
+        Ok(())
+    }
+
+    #[test]
+    fn recheckout_working_copy() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+        let root = Path::new(&test_repo.commander.env.root);
+        fs::write(root.join("file"), "content")?;
+        test_repo.commander.get_current_head()?;
+
+        let output = test_repo.commander.recheckout_working_copy()?;
+        assert!(output.contains("The previous working copy state was kept in"));
+
+        // The state is recreated next to the broken one, and the files are kept
+        assert!(test_repo.commander.working_copy_state_path().is_dir());
+        let backups = fs::read_dir(root.join(".jj"))?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                entry
+                    .file_name()
+                    .to_string_lossy()
+                    .starts_with("working_copy.broken-")
+            })
+            .count();
+        assert_eq!(backups, 1);
+        assert_eq!(fs::read_to_string(root.join("file"))?, "content");
+        // jj can load the working copy again
+        test_repo.commander.get_current_head()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,236 @@
+/*!
+Classification of the common ways fetching from or pushing to a git remote
+fails, from what git and ssh print to stderr. The
+[remote_error_popup][crate::ui::remote_error_popup] shows what went wrong
+and what to do about it above the full output.
+*/
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use crate::commander::CommandError;
+
+/// Bookmarks refused by the remote, in the output of jj (`refs/heads/<name>`) and git
+/// (`[rejected] <local> -> <name>`)
+static REJECTED_BOOKMARK_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"refs/heads/([^\s,]+)|\[rejected\]\s+\S+\s+->\s+(\S+)").unwrap());
+
+/// A common cause of a failed fetch or push
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RemoteError {
+    /// The remote refused the credentials, or git or ssh wanted to prompt for them
+    Authentication,
+    /// ssh doesn't know or doesn't trust the key of the host
+    HostKey,
+    /// The remote couldn't be reached
+    Network,
+    /// The remote has changes which the push would overwrite
+    Rejected,
+}
+
+/// Stderr fragments of each kind, in the order they are checked. Host key errors come first, as
+/// ssh also reports them as failing to read from the remote.
+const REMOTE_ERRORS: [(RemoteError, &[&str]); 4] = [
+    (
+        RemoteError::HostKey,
+        &[
+            "Host key verification failed",
+            "REMOTE HOST IDENTIFICATION HAS CHANGED",
+            "host key is not known",
+            "No ED25519 host key is known",
+            "No RSA host key is known",
+            "No ECDSA host key is known",
+        ],
+    ),
+    (
+        RemoteError::Authentication,
+        &[
+            "Authentication failed",
+            // Only the ssh messages, local file system errors are also "Permission denied"
+            "Permission denied (publickey",
+            "Permission denied (password",
+            "Permission denied (keyboard-interactive",
+            "Permission denied, please try again",
+            "Invalid username or password",
+            "terminal prompts disabled",
+            "could not read Username",
+            "could not read Password",
+            "Enter passphrase",
+            "The requested URL returned error: 403",
+            "The requested URL returned error: 401",
+        ],
+    ),
+    (
+        RemoteError::Rejected,
+        &[
+            "non-fast-forward",
+            "[rejected]",
+            "fetch first",
+            "unexpectedly moved on the remote",
+            "stale info",
+        ],
+    ),
+    (
+        RemoteError::Network,
+        &[
+            "Could not resolve host",
+            "Network is unreachable",
+            "Connection timed out",
+            "Connection refused",
+            "Operation timed out",
+            "Failed to connect",
+            "Could not connect",
+            "Temporary failure in name resolution",
+        ],
+    ),
+];
+
+impl RemoteError {
+    pub fn title(&self) -> &'static str {
+        match self {
+            RemoteError::Authentication => "Authentication failed",
+            RemoteError::HostKey => "Host key verification failed",
+            RemoteError::Network => "Remote unreachable",
+            RemoteError::Rejected => "Push rejected",
+        }
+    }
+
+    /// What likely went wrong and how to fix it
+    pub fn hint(&self) -> &'static str {
+        match self {
+            RemoteError::Authentication => {
+                "The remote refused the credentials, or git needs to ask for them. Check that your \
+                 ssh key is loaded in the ssh agent (ssh-add -l) or that a credential helper is \
+                 set up, or retry in the terminal to enter them."
+            }
+            RemoteError::HostKey => {
+                "ssh doesn't trust the key of the host, e.g. on the first connection or after \
+                 the key changed. Retry in the terminal to check and accept the key, or fix \
+                 ~/.ssh/known_hosts."
+            }
+            RemoteError::Network => {
+                "The remote couldn't be reached. Check the network connection, VPN or proxy and \
+                 the URL of the remote, then retry."
+            }
+            RemoteError::Rejected => {
+                "The remote has changes which the push would overwrite. Fetch them, rebase your \
+                 changes onto them if needed and push again. jj only overwrites changes on the \
+                 remote that were fetched before."
+            }
+        }
+    }
+
+    /// Whether the URLs of the remotes are worth showing to find the problem
+    pub fn shows_remotes(&self) -> bool {
+        !matches!(self, RemoteError::Rejected)
+    }
+}
+
+impl CommandError {
+    /// Why a fetch or push failed, if the output of git or ssh matches a common cause
+    pub fn remote_error(&self) -> Option<RemoteError> {
+        let CommandError::Status(output, _) = self else {
+            return None;
+        };
+        REMOTE_ERRORS
+            .iter()
+            .find(|(_, fragments)| fragments.iter().any(|fragment| output.contains(fragment)))
+            .map(|(kind, _)| *kind)
+    }
+
+    /// Bookmarks a rejected push would have overwritten on the remote
+    pub fn rejected_bookmarks(&self) -> Vec<String> {
+        let CommandError::Status(output, _) = self else {
+            return vec![];
+        };
+        let mut bookmarks: Vec<String> = REJECTED_BOOKMARK_REGEX
+            .captures_iter(output)
+            .filter_map(|captures| captures.get(1).or(captures.get(2)))
+            .map(|name| name.as_str().to_owned())
+            .collect();
+        bookmarks.sort();
+        bookmarks.dedup();
+        bookmarks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn remote_error(stderr: &str) -> Option<RemoteError> {
+        CommandError::Status(stderr.to_owned(), Some(1)).remote_error()
+    }
+
+    #[test]
+    fn classify_remote_errors() {
+        assert_eq!(
+            remote_error(
+                "Host key verification failed.\r\nfatal: Could not read from remote repository."
+            ),
+            Some(RemoteError::HostKey)
+        );
+        assert_eq!(
+            remote_error("git@github.com: Permission denied (publickey)."),
+            Some(RemoteError::Authentication)
+        );
+        assert_eq!(
+            remote_error(
+                "remote: Invalid username or password.\nfatal: Authentication failed for 'https://example.com/repo.git/'"
+            ),
+            Some(RemoteError::Authentication)
+        );
+        assert_eq!(
+            remote_error("ssh: Could not resolve hostname example.com: Name or service not known"),
+            Some(RemoteError::Network)
+        );
+        assert_eq!(
+            remote_error(
+                "Error: Refusing to push a bookmark that unexpectedly moved on the remote. Affected refs: refs/heads/main"
+            ),
+            Some(RemoteError::Rejected)
+        );
+        assert_eq!(
+            remote_error(" ! [rejected]        main -> main (non-fast-forward)"),
+            Some(RemoteError::Rejected)
+        );
+        assert_eq!(
+            remote_error("git@github.com: Permission denied, please try again."),
+            Some(RemoteError::Authentication)
+        );
+        assert_eq!(
+            remote_error(
+                "Error: Failed to snapshot the working copy\nCaused by: Permission denied (os error 13)"
+            ),
+            None
+        );
+        assert_eq!(remote_error("Error: No git remote named 'origin'"), None);
+        assert_eq!(
+            CommandError::ReadOnly("Push".to_owned()).remote_error(),
+            None
+        );
+    }
+
+    #[test]
+    fn find_rejected_bookmarks() {
+        let err = CommandError::Status(
+            "Error: Refusing to push a bookmark that unexpectedly moved on the remote. Affected \
+             refs: refs/heads/main, refs/heads/feature/a"
+                .to_owned(),
+            Some(1),
+        );
+        assert_eq!(err.rejected_bookmarks(), ["feature/a", "main"]);
+
+        let err = CommandError::Status(
+            " ! [rejected]        main -> main (fetch first)".to_owned(),
+            Some(1),
+        );
+        assert_eq!(err.rejected_bookmarks(), ["main"]);
+
+        assert!(
+            CommandError::Status("Host key verification failed".to_owned(), Some(1))
+                .rejected_bookmarks()
+                .is_empty()
+        );
+    }
+}
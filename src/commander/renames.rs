@@ -0,0 +1,600 @@
+/*!
+[Commander] member functions detecting renamed files and showing the changes
+to their content.
+
+jj doesn't always pair a deleted and an added file as a rename, e.g. when the
+moved file was changed as well, so a large move shows up as the whole file
+deleted and added again. [Commander::detect_renames] pairs them by the lines
+they have in common, like `git diff -M`, and [Commander::get_rename_diff]
+shows only the changed lines of the file.
+
+The details of a change show such renames with their changed lines too, see
+[Commander::get_missed_renames], and [Commander::follow_renames] uses them to
+follow a file across renames, so its history doesn't end where it was moved.
+*/
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+};
+
+use anyhow::Result;
+use tracing::instrument;
+
+use crate::commander::{
+    CommandError, Commander,
+    files::{DiffType, File},
+    log::{Head, path_fileset},
+};
+
+/// Minimum similarity of a deleted and an added file to be shown as a rename, like `git diff -M`
+const RENAME_SIMILARITY: u8 = 50;
+/// Number of deleted or added files above which renames aren't detected, as each pair of them
+/// is compared
+const MAX_RENAME_CANDIDATES: usize = 50;
+/// Number of renames followed back in the history of a file
+const MAX_FOLLOWED_RENAMES: usize = 20;
+/// Number of unchanged lines shown around the changed lines
+const CONTEXT_LINES: usize = 3;
+/// Size of the table comparing the changed lines above which they are shown as all replaced
+const MAX_DIFF_CELLS: usize = 1_000_000;
+
+/// Percentage of lines the old and the new content have in common
+pub fn similarity(old: &str, new: &str) -> u8 {
+    let total = old.lines().count() + new.lines().count();
+    if total == 0 {
+        return 100;
+    }
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for line in old.lines() {
+        *counts.entry(line).or_default() += 1;
+    }
+    let mut common = 0;
+    for line in new.lines() {
+        if let Some(count) = counts.get_mut(line)
+            && *count > 0
+        {
+            *count -= 1;
+            common += 1;
+        }
+    }
+    (common * 2 * 100 / total) as u8
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum DiffLine {
+    /// Index of the line in the old and the new content
+    Equal(usize, usize),
+    Deleted(usize),
+    Inserted(usize),
+}
+
+/// Longest common subsequence of the lines, with `offset` added to the indices
+fn lcs_diff(old: &[&str], new: &[&str], offset: usize) -> Vec<DiffLine> {
+    let width = new.len() + 1;
+    // Length of the longest common subsequence of old[i..] and new[j..] at i * width + j
+    let mut lengths = vec![0u32; (old.len() + 1) * width];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i * width + j] = if old[i] == new[j] {
+                lengths[(i + 1) * width + j + 1] + 1
+            } else {
+                lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut lines = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            lines.push(DiffLine::Equal(offset + i, offset + j));
+            i += 1;
+            j += 1;
+        } else if lengths[(i + 1) * width + j] >= lengths[i * width + j + 1] {
+            lines.push(DiffLine::Deleted(offset + i));
+            i += 1;
+        } else {
+            lines.push(DiffLine::Inserted(offset + j));
+            j += 1;
+        }
+    }
+    lines.extend((i..old.len()).map(|i| DiffLine::Deleted(offset + i)));
+    lines.extend((j..new.len()).map(|j| DiffLine::Inserted(offset + j)));
+    lines
+}
+
+fn diff_lines(old: &[&str], new: &[&str]) -> Vec<DiffLine> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_changed = &old[prefix..old.len() - suffix];
+    let new_changed = &new[prefix..new.len() - suffix];
+
+    let mut lines: Vec<DiffLine> = (0..prefix).map(|i| DiffLine::Equal(i, i)).collect();
+    if old_changed.len() * new_changed.len() <= MAX_DIFF_CELLS {
+        lines.extend(lcs_diff(old_changed, new_changed, prefix));
+    } else {
+        lines.extend((prefix..prefix + old_changed.len()).map(DiffLine::Deleted));
+        lines.extend((prefix..prefix + new_changed.len()).map(DiffLine::Inserted));
+    }
+    lines.extend(
+        (0..suffix).map(|i| DiffLine::Equal(old.len() - suffix + i, new.len() - suffix + i)),
+    );
+    lines
+}
+
+/// Hunk header with the start and number of lines, the start is the line before for empty
+/// ranges like in `git diff`
+fn hunk_range(before: usize, count: usize) -> String {
+    if count == 0 {
+        format!("{before},0")
+    } else {
+        format!("{},{count}", before + 1)
+    }
+}
+
+/// Unified diff of the lines, colored like the git format of jj
+pub fn unified_diff(old: &str, new: &str) -> String {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    let lines = diff_lines(&old, &new);
+
+    let mut output = String::new();
+    let mut changes = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| !matches!(line, DiffLine::Equal(..)))
+        .map(|(index, _)| index)
+        .peekable();
+    while let Some(first) = changes.next() {
+        // Changes separated by less than twice the context are in the same hunk
+        let mut last = first;
+        while let Some(&next) = changes.peek()
+            && next - last <= 2 * CONTEXT_LINES + 1
+        {
+            last = next;
+            changes.next();
+        }
+        let start = first.saturating_sub(CONTEXT_LINES);
+        let end = (last + CONTEXT_LINES + 1).min(lines.len());
+
+        let old_before = lines[..start]
+            .iter()
+            .filter(|line| !matches!(line, DiffLine::Inserted(_)))
+            .count();
+        let new_before = lines[..start]
+            .iter()
+            .filter(|line| !matches!(line, DiffLine::Deleted(_)))
+            .count();
+        let hunk = &lines[start..end];
+        let old_count = hunk
+            .iter()
+            .filter(|line| !matches!(line, DiffLine::Inserted(_)))
+            .count();
+        let new_count = hunk
+            .iter()
+            .filter(|line| !matches!(line, DiffLine::Deleted(_)))
+            .count();
+
+        output.push_str(&format!(
+            "\x1b[36m@@ -{} +{} @@\x1b[0m\n",
+            hunk_range(old_before, old_count),
+            hunk_range(new_before, new_count)
+        ));
+        for line in hunk {
+            match *line {
+                DiffLine::Equal(i, _) => output.push_str(&format!(" {}\n", old[i])),
+                DiffLine::Deleted(i) => output.push_str(&format!("\x1b[31m-{}\x1b[0m\n", old[i])),
+                DiffLine::Inserted(j) => output.push_str(&format!("\x1b[32m+{}\x1b[0m\n", new[j])),
+            }
+        }
+    }
+    output
+}
+
+impl Commander {
+    /// Content of a file in a revision. Maps to `jj file show -r <revision> <path>`
+    pub(super) fn get_file_content(
+        &self,
+        revision: &str,
+        path: &str,
+    ) -> Result<String, CommandError> {
+        let fileset = Self::get_file_revset(path);
+        self.execute_jj_command(vec!["file", "show", "-r", revision, &fileset], false, true)
+    }
+
+    /// Whether the change has more than one parent.
+    /// Maps to `jj log -r <revision> -T 'parents.len()'`
+    fn is_merge(&self, head: &Head) -> Result<bool, CommandError> {
+        let parents = self.execute_jj_command(
+            [
+                "log",
+                "--no-graph",
+                "--ignore-working-copy",
+                "-r",
+                head.commit_id.as_str(),
+                "-T",
+                "parents.len()",
+            ],
+            false,
+            true,
+        )?;
+        Ok(parents
+            .trim()
+            .parse::<usize>()
+            .is_ok_and(|parents| parents > 1))
+    }
+
+    /// Pair deleted and added files with similar content as renames, and add the similarity to
+    /// the renames found by jj. Files of merges are returned unchanged, as their content before
+    /// the change isn't in a single parent.
+    #[instrument(level = "trace", skip(self, files))]
+    pub fn detect_renames(&self, head: &Head, files: Vec<File>) -> Vec<File> {
+        if self.is_merge(head).unwrap_or(true) {
+            return files;
+        }
+        let revision = head.commit_id.as_str();
+        let parent = format!("{revision}-");
+        let content = |revision: &str, path: &str| self.get_file_content(revision, path).ok();
+
+        let mut files: Vec<File> = files
+            .into_iter()
+            .map(|file| {
+                if file.diff_type == Some(DiffType::Renamed)
+                    && let [old, new] = file.paths().as_slice()
+                    && let (Some(old_content), Some(new_content)) = (
+                        content(&parent, old.as_str()),
+                        content(revision, new.as_str()),
+                    )
+                {
+                    File::renamed(old, new, similarity(&old_content, &new_content))
+                } else {
+                    file
+                }
+            })
+            .collect();
+
+        let candidates = |diff_type: DiffType, revision: &str| -> Vec<(usize, String)> {
+            files
+                .iter()
+                .enumerate()
+                .filter(|(_, file)| file.diff_type.as_ref() == Some(&diff_type))
+                .filter_map(|(index, file)| {
+                    Some((index, content(revision, file.path.as_deref()?)?))
+                })
+                .collect()
+        };
+        let deleted_count = files
+            .iter()
+            .filter(|file| file.diff_type == Some(DiffType::Deleted))
+            .count();
+        let added_count = files
+            .iter()
+            .filter(|file| file.diff_type == Some(DiffType::Added))
+            .count();
+        if deleted_count == 0
+            || added_count == 0
+            || deleted_count > MAX_RENAME_CANDIDATES
+            || added_count > MAX_RENAME_CANDIDATES
+        {
+            return files;
+        }
+        let deleted = candidates(DiffType::Deleted, &parent);
+        let added = candidates(DiffType::Added, revision);
+
+        let mut pairs = vec![];
+        for (deleted_index, old) in &deleted {
+            for (added_index, new) in &added {
+                let similarity = similarity(old, new);
+                if similarity >= RENAME_SIMILARITY {
+                    pairs.push((similarity, *deleted_index, *added_index));
+                }
+            }
+        }
+        // Like git, the most similar pairs win and each file is part of at most one rename
+        pairs.sort_by_key(|pair| Reverse(pair.0));
+        let mut paired = HashSet::new();
+        for (similarity, deleted_index, added_index) in pairs {
+            if paired.contains(&deleted_index) || paired.contains(&added_index) {
+                continue;
+            }
+            paired.insert(deleted_index);
+            paired.insert(added_index);
+            if let (Some(old), Some(new)) = (
+                files[deleted_index].path.clone(),
+                files[added_index].path.clone(),
+            ) {
+                files[added_index] = File::renamed(&old, &new, similarity);
+            }
+        }
+
+        files
+            .into_iter()
+            .enumerate()
+            .filter(|(index, file)| {
+                !(paired.contains(index) && file.diff_type == Some(DiffType::Deleted))
+            })
+            .map(|(_, file)| file)
+            .collect()
+    }
+
+    /// Renames found by [Commander::detect_renames] which jj shows as a deleted and an added
+    /// file, as its own renames are shown as such already
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_missed_renames(&self, head: &Head) -> Result<Vec<File>, CommandError> {
+        let files = self.get_files(head)?;
+        let added = |path: &str| {
+            files.iter().any(|file| {
+                file.diff_type == Some(DiffType::Added) && file.path.as_deref() == Some(path)
+            })
+        };
+        if !files
+            .iter()
+            .any(|file| file.diff_type == Some(DiffType::Deleted))
+            || !files
+                .iter()
+                .any(|file| file.diff_type == Some(DiffType::Added))
+        {
+            return Ok(vec![]);
+        }
+        Ok(self
+            .detect_renames(head, files.clone())
+            .into_iter()
+            .filter(|file| {
+                file.diff_type == Some(DiffType::Renamed)
+                    && matches!(file.paths().as_slice(), [_, new] if added(new))
+            })
+            .collect())
+    }
+
+    /// Earlier paths of a file in the ancestors of the working copy, from the newest. Goes back
+    /// to the oldest changes touching the path and, if one of them added the file as a rename,
+    /// continues with the old path before that change. Calls `found` with each earlier path as
+    /// soon as it is found, as following the renames takes a few commands per change. Stops
+    /// once `found` returns false.
+    /// Maps to `jj log -r 'roots(::@ & files(<path>))'`
+    #[instrument(level = "trace", skip(self, found))]
+    pub fn follow_renames(&self, path: &str, mut found: impl FnMut(&str) -> bool) -> Result<()> {
+        let mut earlier_paths: Vec<String> = vec![];
+        let mut current = path.to_owned();
+        let mut ancestors = "::@".to_owned();
+        while earlier_paths.len() < MAX_FOLLOWED_RENAMES {
+            let revset = format!("roots(({ancestors}) & files({}))", path_fileset(&current));
+            let output = self.execute_jj_command(
+                [
+                    "log",
+                    "--no-graph",
+                    "-r",
+                    &revset,
+                    "--template",
+                    r#"commit_id ++ "\n""#,
+                ],
+                false,
+                true,
+            )?;
+
+            let mut rename = None;
+            for commit_id in output.lines() {
+                let head = self.get_head(commit_id)?;
+                let files = self.detect_renames(&head, self.get_files(&head)?);
+                rename = files.iter().find_map(|file| match file.paths().as_slice() {
+                    [old, new] if file.diff_type == Some(DiffType::Renamed) && *new == current => {
+                        Some((old.clone(), commit_id.to_owned()))
+                    }
+                    _ => None,
+                });
+                if rename.is_some() {
+                    break;
+                }
+            }
+
+            let Some((old, commit_id)) = rename else {
+                break;
+            };
+            // A file renamed back and forth
+            if old == path || earlier_paths.contains(&old) {
+                break;
+            }
+            if !found(&old) {
+                break;
+            }
+            earlier_paths.push(old.clone());
+            current = old;
+            ancestors = format!("::{commit_id}-");
+        }
+        Ok(())
+    }
+
+    /// Git format diff of a rename found by [Commander::detect_renames], a single
+    /// `renamed <old> → <new> (similarity <n>%)` line followed by the changed lines
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_rename_diff(
+        &self,
+        head: &Head,
+        file: &File,
+    ) -> Result<Option<String>, CommandError> {
+        let paths = file.paths();
+        let (Some(similarity), [old, new]) = (file.similarity, paths.as_slice()) else {
+            return Ok(None);
+        };
+        let revision = head.commit_id.as_str();
+        let old_content = self.get_file_content(&format!("{revision}-"), old)?;
+        let new_content = self.get_file_content(revision, new)?;
+        Ok(Some(format!(
+            "\x1b[1mrenamed {old} → {new} (similarity {similarity}%)\x1b[0m\n{}",
+            unified_diff(&old_content, &new_content)
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        commander::{log::LogDensity, tests::TestRepo},
+        env::DiffFormat,
+    };
+    use anyhow::Result;
+    use std::fs;
+
+    #[test]
+    fn line_similarity() {
+        assert_eq!(similarity("", ""), 100);
+        assert_eq!(similarity("a\nb\n", "a\nb\n"), 100);
+        assert_eq!(similarity("a\nb\nc\nd\n", "a\nb\nc\nx\n"), 75);
+        assert_eq!(similarity("a\n", "b\n"), 0);
+    }
+
+    #[test]
+    fn unified_diff_hunks() {
+        let old = (1..=20).map(|i| format!("{i}\n")).collect::<String>();
+        let new = (1..=20)
+            .filter(|i| *i != 18)
+            .map(|i| match i {
+                2 => "two\n".to_owned(),
+                i => format!("{i}\n"),
+            })
+            .collect::<String>();
+        assert_eq!(
+            unified_diff(&old, &new),
+            "\x1b[36m@@ -1,5 +1,5 @@\x1b[0m\n 1\n\x1b[31m-2\x1b[0m\n\x1b[32m+two\x1b[0m\n 3\n 4\n 5\n\
+             \x1b[36m@@ -15,6 +15,5 @@\x1b[0m\n 15\n 16\n 17\n\x1b[31m-18\x1b[0m\n 19\n 20\n"
+        );
+        assert_eq!(unified_diff(&old, &old), "");
+        assert_eq!(
+            unified_diff("", "a\n"),
+            "\x1b[36m@@ -0,0 +1,1 @@\x1b[0m\n\x1b[32m+a\x1b[0m\n"
+        );
+    }
+
+    #[test]
+    fn detect_no_renames_in_merges() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+        let content = (1..=10).map(|i| format!("line {i}\n")).collect::<String>();
+        fs::write(test_repo.directory.path().join("old"), &content)?;
+        test_repo
+            .commander
+            .execute_void_jj_command(["bookmark", "create", "-r", "@", "left"])?;
+        test_repo
+            .commander
+            .execute_void_jj_command(["new", "root()"])?;
+        fs::write(test_repo.directory.path().join("right"), "right\n")?;
+        test_repo
+            .commander
+            .execute_void_jj_command(["new", "left", "@"])?;
+
+        fs::remove_file(test_repo.directory.path().join("old"))?;
+        fs::write(test_repo.directory.path().join("new"), &content)?;
+
+        let head = test_repo.commander.get_current_head()?;
+        let files = test_repo.commander.get_files(&head)?;
+        assert_eq!(
+            test_repo.commander.detect_renames(&head, files.clone()),
+            files
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn detect_changed_rename() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+        let content = (1..=10).map(|i| format!("line {i}\n")).collect::<String>();
+        fs::write(test_repo.directory.path().join("old"), &content)?;
+        fs::write(test_repo.directory.path().join("other"), "other\n")?;
+        test_repo.commander.execute_void_jj_command(["new"])?;
+
+        fs::remove_file(test_repo.directory.path().join("old"))?;
+        // Half of the lines are kept, a rename for git but not for jj
+        let changed = (1..=10)
+            .map(|i| match i {
+                1..=5 => format!("line {i}\n"),
+                _ => format!("changed {i}\n"),
+            })
+            .collect::<String>();
+        fs::write(test_repo.directory.path().join("new"), changed)?;
+        fs::remove_file(test_repo.directory.path().join("other"))?;
+        fs::write(test_repo.directory.path().join("unrelated"), "unrelated\n")?;
+
+        let head = test_repo.commander.get_current_head()?;
+        let files = test_repo
+            .commander
+            .detect_renames(&head, test_repo.commander.get_files(&head)?);
+        let rename = files
+            .iter()
+            .find(|file| file.diff_type == Some(DiffType::Renamed))
+            .expect("rename should be detected");
+        assert_eq!(rename.line, "R old → new (similarity 50%)");
+        assert_eq!(rename.paths(), ["old", "new"]);
+        assert_eq!(files.len(), 3);
+
+        let diff = test_repo
+            .commander
+            .get_file_diff(&head, rename, &DiffFormat::Git, true)?
+            .unwrap();
+        assert!(diff.starts_with("\x1b[1mrenamed old → new (similarity 50%)\x1b[0m\n"));
+        assert!(diff.contains("\x1b[31m-line 6\x1b[0m\n"));
+        assert!(diff.contains("\x1b[32m+changed 6\x1b[0m\n"));
+        assert!(!diff.contains("-line 1\n"));
+
+        // The details of the change show the rename instead of the deleted and added file
+        assert_eq!(
+            test_repo.commander.get_missed_renames(&head)?.as_slice(),
+            std::slice::from_ref(rename)
+        );
+        let show = test_repo.commander.get_commit_show_with_overrides(
+            &head.commit_id,
+            &DiffFormat::Git,
+            &[],
+            true,
+        )?;
+        assert!(show.contains("renamed old → new (similarity 50%)"));
+        assert!(show.contains("unrelated"));
+        assert!(!show.contains("line 1\n"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn follow_renames() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+        let content = (1..=10).map(|i| format!("line {i}\n")).collect::<String>();
+        fs::write(test_repo.directory.path().join("first"), &content)?;
+        test_repo.commander.execute_void_jj_command(["new"])?;
+        fs::rename(
+            test_repo.directory.path().join("first"),
+            test_repo.directory.path().join("second"),
+        )?;
+        test_repo.commander.execute_void_jj_command(["new"])?;
+        fs::remove_file(test_repo.directory.path().join("second"))?;
+        fs::write(
+            test_repo.directory.path().join("third"),
+            content.replace("line 5\n", "line five\n"),
+        )?;
+        test_repo.commander.execute_void_jj_command(["new"])?;
+
+        let earlier_paths = |path: &str| -> Result<Vec<String>> {
+            let mut earlier_paths = vec![];
+            test_repo.commander.follow_renames(path, |earlier_path| {
+                earlier_paths.push(earlier_path.to_owned());
+                true
+            })?;
+            Ok(earlier_paths)
+        };
+        assert_eq!(earlier_paths("third")?, ["second", "first"]);
+        assert!(earlier_paths("first")?.is_empty());
+
+        // The history of the file goes back to the change adding it as `first`
+        let paths = ["third".to_owned(), "second".to_owned(), "first".to_owned()];
+        let log = test_repo
+            .commander
+            .get_log(&None, &paths, LogDensity::Normal, false)?;
+        assert_eq!(log.heads.len(), 3);
+
+        Ok(())
+    }
+}
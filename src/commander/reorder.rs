@@ -0,0 +1,263 @@
+/*!
+[Commander] member functions reordering a linear stack of changes, used by
+the [reorder_popup][crate::ui::reorder_popup].
+
+The stack of a change is the mutable part of its ancestors and descendants,
+which must form a single line. The new order is applied with one
+`jj rebase -r` per change which has to move, leaving the changes which are
+already in the right relative order in place. The changes are addressed by
+change id, as their commit ids change with every rebase. The popup runs the
+rebases as a [composite action][Commander::run_composite_action], so the
+earlier ones can be rolled back if one fails.
+*/
+use anyhow::{Result, bail};
+use tracing::instrument;
+
+use crate::commander::{
+    CommandError, Commander,
+    ids::{ChangeId, CommitId},
+};
+
+const STACK_TEMPLATE: &str = r#"change_id ++ "\t" ++ commit_id ++ "\t" ++ divergent ++ "\t"
+    ++ parents.map(|c| c.commit_id()).join(",") ++ "\t" ++ description.first_line() ++ "\n""#;
+
+/// A change of a stack
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StackChange {
+    pub change_id: ChangeId,
+    pub commit_id: CommitId,
+    /// First line of the description
+    pub title: String,
+}
+
+/// A `jj rebase -r` moving a change of a linear stack
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StackMove<T> {
+    /// Move the change right after the second one, `-A`
+    After(T, T),
+    /// Move the change right before the second one, `-B`
+    Before(T, T),
+}
+
+/// Moves turning the stack `original` into `reordered`, both listed from the bottom. The
+/// changes in the longest subsequence which is already in order stay in place, every other
+/// change is moved once, right after its new parent.
+pub fn reorder_moves<T: Clone + PartialEq>(original: &[T], reordered: &[T]) -> Vec<StackMove<T>> {
+    let positions: Vec<usize> = reordered
+        .iter()
+        .filter_map(|change| original.iter().position(|other| other == change))
+        .collect();
+
+    // Longest increasing subsequence of the original positions
+    let mut length = vec![1; positions.len()];
+    let mut previous = vec![None; positions.len()];
+    for i in 0..positions.len() {
+        for j in 0..i {
+            if positions[j] < positions[i] && length[j] + 1 > length[i] {
+                length[i] = length[j] + 1;
+                previous[i] = Some(j);
+            }
+        }
+    }
+    let mut kept = vec![false; positions.len()];
+    let mut next = (0..positions.len()).max_by_key(|&i| length[i]);
+    while let Some(i) = next {
+        kept[i] = true;
+        next = previous[i];
+    }
+
+    // The stack as it is after each move, to skip moves which don't change anything
+    let mut current = original.to_vec();
+    let mut moves = vec![];
+    for (i, change) in reordered.iter().enumerate() {
+        if kept[i] {
+            continue;
+        }
+        let Some(from) = current.iter().position(|other| other == change) else {
+            continue;
+        };
+        current.remove(from);
+        let to = match i {
+            0 => 0,
+            _ => {
+                current
+                    .iter()
+                    .position(|other| *other == reordered[i - 1])
+                    .unwrap_or(0)
+                    + 1
+            }
+        };
+        if to != from {
+            moves.push(match i {
+                0 => StackMove::Before(change.clone(), current[0].clone()),
+                _ => StackMove::After(change.clone(), reordered[i - 1].clone()),
+            });
+        }
+        current.insert(to, change.clone());
+    }
+    moves
+}
+
+impl Commander {
+    /// The linear stack of the revision, from the bottom. Fails if the stack branches, contains
+    /// merges or divergent changes.
+    /// Maps to `jj log -r '(::<revision> | <revision>::) & mutable()'`
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_stack(&self, revision: &str) -> Result<Vec<StackChange>> {
+        let revset = format!("(::{revision} | {revision}::) & mutable()");
+        let output = self.execute_jj_command(
+            [
+                "log",
+                "--no-graph",
+                "-r",
+                &revset,
+                "--template",
+                STACK_TEMPLATE,
+            ],
+            false,
+            true,
+        )?;
+
+        // Listed from the top
+        let mut stack = vec![];
+        let mut parents = vec![];
+        for line in output.lines() {
+            let fields: Vec<&str> = line.splitn(5, '\t').collect();
+            let &[change_id, commit_id, divergent, change_parents, title] = fields.as_slice()
+            else {
+                bail!("Failed parsing the stack: {line}");
+            };
+            if divergent == "true" {
+                bail!("The stack contains the divergent change {change_id}");
+            }
+            stack.push(StackChange {
+                change_id: ChangeId(change_id.to_owned()),
+                commit_id: CommitId(commit_id.to_owned()),
+                title: title.to_owned(),
+            });
+            parents.push(change_parents.to_owned());
+        }
+        for (i, change_parents) in parents.iter().enumerate() {
+            if change_parents.contains(',') {
+                bail!(
+                    "The stack contains the merge {}",
+                    stack[i].change_id.as_str()
+                );
+            }
+            if let Some(parent) = stack.get(i + 1)
+                && *change_parents != parent.commit_id.as_str()
+            {
+                bail!(
+                    "The stack isn't linear, it branches at {}",
+                    parent.change_id.as_str()
+                );
+            }
+        }
+        if stack.len() < 2 {
+            bail!("The stack has less than two mutable changes, there is nothing to reorder");
+        }
+
+        stack.reverse();
+        Ok(stack)
+    }
+
+    /// Reorder the stack `original` to `reordered`, both listed from the bottom. Runs a
+    /// `jj rebase -r <change> -A <parent>` or `-B <child>` per change which moves, see
+    /// [reorder_moves]
+    #[instrument(level = "trace", skip(self))]
+    pub fn run_reorder(
+        &self,
+        original: &[ChangeId],
+        reordered: &[ChangeId],
+    ) -> Result<String, CommandError> {
+        let moves = reorder_moves(original, reordered);
+        for stack_move in &moves {
+            let (change, placement, other) = match stack_move {
+                StackMove::After(change, parent) => (change, "-A", parent),
+                StackMove::Before(change, child) => (change, "-B", child),
+            };
+            self.execute_void_jj_command([
+                "rebase",
+                "-r",
+                change.as_str(),
+                placement,
+                other.as_str(),
+            ])?;
+        }
+        Ok(format!("Reordered the stack with {} rebases", moves.len()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commander::tests::TestRepo;
+
+    /// The stack after applying the moves
+    fn apply(original: &[&str], moves: &[StackMove<&str>]) -> Vec<String> {
+        let mut stack: Vec<&str> = original.to_vec();
+        for stack_move in moves {
+            let (change, other, offset) = match stack_move {
+                StackMove::After(change, parent) => (change, parent, 1),
+                StackMove::Before(change, child) => (change, child, 0),
+            };
+            stack.retain(|other| other != change);
+            let to = stack.iter().position(|c| c == other).unwrap() + offset;
+            stack.insert(to, *change);
+        }
+        stack.into_iter().map(str::to_owned).collect()
+    }
+
+    #[test]
+    fn minimal_moves() {
+        let original = ["a", "b", "c", "d"];
+        assert_eq!(reorder_moves(&original, &original), vec![]);
+
+        // Moving the bottom change to the top is a single rebase
+        let reordered = ["b", "c", "d", "a"];
+        let moves = reorder_moves(&original, &reordered);
+        assert_eq!(moves, vec![StackMove::After("a", "d")]);
+        assert_eq!(apply(&original, &moves), reordered);
+
+        let reordered = ["d", "a", "b", "c"];
+        let moves = reorder_moves(&original, &reordered);
+        assert_eq!(moves, vec![StackMove::Before("d", "a")]);
+        assert_eq!(apply(&original, &moves), reordered);
+
+        for reordered in [
+            ["d", "c", "b", "a"],
+            ["b", "a", "d", "c"],
+            ["c", "a", "d", "b"],
+        ] {
+            let moves = reorder_moves(&original, &reordered);
+            assert_eq!(apply(&original, &moves), reordered);
+        }
+        assert_eq!(reorder_moves(&original, &["d", "c", "b", "a"]).len(), 3);
+    }
+
+    #[test]
+    fn reorder_stack() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+        let commander = &test_repo.commander;
+        for description in ["first", "second", "third"] {
+            commander.execute_void_jj_command(["new", "-m", description])?;
+        }
+
+        let stack = commander.get_stack("@")?;
+        let titles: Vec<&str> = stack.iter().map(|change| change.title.as_str()).collect();
+        assert_eq!(titles, ["", "first", "second", "third"]);
+
+        let original: Vec<ChangeId> = stack.iter().map(|c| c.change_id.clone()).collect();
+        let mut reordered = original.clone();
+        reordered.swap(1, 3);
+        commander.run_reorder(&original, &reordered)?;
+
+        let titles: Vec<String> = commander
+            .get_stack("@")?
+            .into_iter()
+            .map(|change| change.title)
+            .collect();
+        assert_eq!(titles, ["", "third", "second", "first"]);
+        Ok(())
+    }
+}
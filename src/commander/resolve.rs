@@ -0,0 +1,254 @@
+/*!
+[Commander] member functions resolving conflicts with `jj resolve`.
+
+Conflicts of a file can be resolved with any merge tool of `merge-tools.*`,
+so that e.g. lockfiles and images don't have to use the same tool as source
+code.
+
+[Commander::get_revset_conflicts] lists every conflicted file of the
+revisions of the log for the [conflicts_popup][crate::ui::conflicts_popup],
+e.g. to work through the conflicts left by a large rebase.
+*/
+use std::sync::LazyLock;
+
+use anyhow::{Result, bail};
+use regex::Regex;
+use tracing::instrument;
+
+use crate::commander::{
+    CommandError, Commander,
+    ids::{ChangeId, CommitId},
+    log::Head,
+};
+
+/// Tools built into jj. `:ours` and `:theirs` resolve without user interaction.
+const BUILTIN_MERGE_TOOLS: [&str; 3] = [":builtin", ":ours", ":theirs"];
+
+/// Matches the `merge-args` of a tool in `jj config list` output, with the name of the tool,
+/// quoted if it contains special characters
+static MERGE_ARGS_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"^merge-tools\.("(?:[^"\\]|\\.)*"|[^.\s]+)\.merge-args\s*="#).unwrap()
+});
+
+/// Names of the tools with `merge-args` in `jj config list merge-tools` output, sorted
+fn parse_merge_tools(output: &str) -> Vec<String> {
+    let mut tools: Vec<String> = output
+        .lines()
+        .filter_map(|line| {
+            let name = MERGE_ARGS_REGEX.captures(line)?.get(1)?.as_str();
+            let quoted = name
+                .strip_prefix('"')
+                .and_then(|name| name.strip_suffix('"'));
+            Some(match quoted {
+                Some(quoted) => quoted.replace("\\\"", "\"").replace("\\\\", "\\"),
+                None => name.to_owned(),
+            })
+        })
+        .collect();
+    tools.sort();
+    tools.dedup();
+    tools
+}
+
+const CONFLICTED_REVISION_TEMPLATE: &str = r#"change_id ++ "\t" ++ commit_id ++ "\t" ++ divergent
+    ++ "\t" ++ immutable ++ "\t" ++ description.first_line() ++ "\n""#;
+
+/// A revision with conflicts and its conflicted files
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConflictedRevision {
+    pub head: Head,
+    /// First line of the description
+    pub title: String,
+    pub paths: Vec<String>,
+}
+
+/// Whether the tool resolves the conflicts without the terminal
+pub fn is_non_interactive_tool(tool: &str) -> bool {
+    matches!(tool, ":ours" | ":theirs")
+}
+
+impl Commander {
+    /// Merge tools which can resolve conflicts: `ui.merge-editor` first, then the tools of
+    /// `merge-tools.*` with `merge-args` and the tools built into jj.
+    /// Maps to `jj config list --include-defaults merge-tools`
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_merge_tools(&self) -> Result<Vec<String>, CommandError> {
+        let output = self.execute_jj_command(
+            vec![
+                "config",
+                "list",
+                "--include-defaults",
+                "--ignore-working-copy",
+                "merge-tools",
+            ],
+            false,
+            true,
+        )?;
+        let mut tools = parse_merge_tools(&output);
+        tools.extend(BUILTIN_MERGE_TOOLS.map(String::from));
+
+        if let Some(default) = self.get_config_value("ui.merge-editor")? {
+            // The value is a name or a command line, only names can be passed to --tool
+            let default = default.trim_matches('"').to_owned();
+            if let Some(index) = tools.iter().position(|tool| *tool == default) {
+                let tool = tools.remove(index);
+                tools.insert(0, tool);
+            }
+        }
+        Ok(tools)
+    }
+
+    /// The revisions of the revset with conflicts, in the order of the log, with their
+    /// conflicted files. Maps to `jj log -r '(<revset>) & conflicts()'` and
+    /// `jj resolve --list -r <revision>` for each of them
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_revset_conflicts(&self, revset: &str) -> Result<Vec<ConflictedRevision>> {
+        let revset = format!("({revset}) & conflicts()");
+        let output = self.execute_jj_command(
+            [
+                "log",
+                "--no-graph",
+                "-r",
+                &revset,
+                "--template",
+                CONFLICTED_REVISION_TEMPLATE,
+            ],
+            false,
+            true,
+        )?;
+
+        let mut revisions = vec![];
+        for line in output.lines() {
+            let fields: Vec<&str> = line.splitn(5, '\t').collect();
+            let &[change_id, commit_id, divergent, immutable, title] = fields.as_slice() else {
+                bail!("Failed parsing the conflicted revision: {line}");
+            };
+            let head = Head {
+                change_id: ChangeId(change_id.to_owned()),
+                commit_id: CommitId(commit_id.to_owned()),
+                divergent: divergent == "true",
+                immutable: immutable == "true",
+            };
+            let paths = self
+                .get_conflicts(&head.commit_id)?
+                .into_iter()
+                .map(|conflict| conflict.path)
+                .collect();
+            revisions.push(ConflictedRevision {
+                head,
+                title: title.to_owned(),
+                paths,
+            });
+        }
+        Ok(revisions)
+    }
+
+    /// Arguments for [Commander::run_resolve]
+    pub fn resolve_args(commit_id: &CommitId, path: &str, tool: &str) -> Vec<String> {
+        vec![
+            "resolve".to_owned(),
+            "-r".to_owned(),
+            commit_id.as_str().to_owned(),
+            "--tool".to_owned(),
+            tool.to_owned(),
+            Self::get_file_revset(path),
+        ]
+    }
+
+    /// Resolve the conflicts of a file with a tool which doesn't need the terminal, see
+    /// [is_non_interactive_tool].
+    /// Maps to `jj resolve -r <commit> --tool <tool> <path>`
+    #[instrument(level = "trace", skip(self))]
+    pub fn run_resolve(
+        &self,
+        commit_id: &CommitId,
+        path: &str,
+        tool: &str,
+    ) -> Result<(), CommandError> {
+        self.execute_void_jj_command(Self::resolve_args(commit_id, path, tool))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commander::tests::TestRepo;
+    use std::fs;
+
+    #[test]
+    fn merge_tools_from_config() {
+        let output = r#"merge-tools.meld.program = "meld"
+merge-tools.meld.merge-args = ["$left", "$base", "$right", "-o", "$output"]
+merge-tools.difft.diff-args = ["$left", "$right"]
+merge-tools."vs code".merge-args = ["--wait", "--merge", "$left", "$right", "$base", "$output"]
+merge-tools.kdiff3.merge-args = ["$base", "$left", "$right", "-o", "$output"]
+"#;
+        assert_eq!(parse_merge_tools(output), ["kdiff3", "meld", "vs code"]);
+    }
+
+    #[test]
+    fn resolve_args() {
+        assert_eq!(
+            Commander::resolve_args(&CommitId("abc".to_owned()), "Cargo.lock", ":ours"),
+            [
+                "resolve",
+                "-r",
+                "abc",
+                "--tool",
+                ":ours",
+                "file:\"Cargo.lock\""
+            ]
+        );
+    }
+
+    #[test]
+    fn resolve_with_ours() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+        let file_path = test_repo.directory.path().join("README");
+
+        let head0 = test_repo.commander.get_current_head()?;
+        test_repo.commander.run_new(head0.commit_id.as_str())?;
+        let head1 = test_repo.commander.get_current_head()?;
+        fs::write(&file_path, b"AAA")?;
+        test_repo.commander.run_new(head0.commit_id.as_str())?;
+        let head2 = test_repo.commander.get_current_head()?;
+        fs::write(&file_path, b"BBB")?;
+        test_repo.commander.execute_void_jj_command([
+            "rebase",
+            "-s",
+            head2.change_id.as_str(),
+            "-d",
+            head1.change_id.as_str(),
+        ])?;
+
+        let head = test_repo.commander.get_current_head()?;
+        assert_eq!(test_repo.commander.get_conflicts(&head.commit_id)?.len(), 1);
+        assert_eq!(
+            test_repo.commander.get_revset_conflicts("all()")?,
+            [ConflictedRevision {
+                head: head.clone(),
+                title: String::new(),
+                paths: vec!["README".to_owned()],
+            }]
+        );
+
+        assert!(
+            test_repo
+                .commander
+                .get_merge_tools()?
+                .contains(&":ours".to_owned())
+        );
+        test_repo
+            .commander
+            .run_resolve(&head.commit_id, "README", ":ours")?;
+        let head = test_repo.commander.get_current_head()?;
+        assert!(
+            test_repo
+                .commander
+                .get_conflicts(&head.commit_id)?
+                .is_empty()
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,509 @@
+/*!
+Process wide registry of the jj commands which are currently running.
+
+Commands run by [Commander::execute_command][crate::commander::Commander] are
+registered here while they run, so that a command which hangs, e.g. a
+`jj git fetch` waiting for ssh, can be found and killed from the UI thread.
+The [loader_popup][crate::ui::loader_popup] offers this once a command runs
+longer than the configured timeout. Commands run on the UI thread block its
+event loop, a handler set with [on_hung_command] is called while they wait
+instead.
+
+Commands can also be grouped into an operation by running them within
+[in_operation], so that a cancelled operation kills all of its commands. The
+commands it runs after it was cancelled, e.g. the push after the tests, fail
+without being started.
+
+On unix, each command runs in its own process group and killing it kills the
+whole group, e.g. the test runner started by the shell of a push test command
+or the ssh started by jj.
+
+A [CommandRunner] set in the [Env][crate::env::Env] can answer commands
+instead, e.g. to script outputs jj can't produce in UI tests.
+*/
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashSet,
+    fmt::Debug,
+    io::{self, Read},
+    process::{Child, ChildStderr, ChildStdout, Command, ExitStatus, Output, Stdio},
+    sync::{
+        Arc, LazyLock, Mutex, MutexGuard,
+        atomic::{AtomicU64, Ordering},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::commander::{CommandError, hide_console_window, history::CommandRecord};
+
+/// Answers the commands of the commanders of an [Env][crate::env::Env] instead of running them
+pub trait CommandRunner: Debug + Send + Sync {
+    /// Result of the command, or `None` to run it
+    fn output(&self, command: &Command) -> Option<Result<Output, CommandError>>;
+}
+
+/// Longest time between checks whether a command exited
+const MAX_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+struct RunningCommand {
+    id: u64,
+    /// Operation the command runs in, see [in_operation]
+    operation: Option<u64>,
+    command_line: String,
+    started: Instant,
+    child: Arc<Mutex<Child>>,
+    killed: bool,
+}
+
+#[derive(Default)]
+struct Registry {
+    next_id: u64,
+    commands: Vec<RunningCommand>,
+    /// Operations cancelled while they run, see [cancel_operation]
+    cancelled: HashSet<u64>,
+}
+
+static RUNNING_COMMANDS: LazyLock<Mutex<Registry>> =
+    LazyLock::new(|| Mutex::new(Registry::default()));
+
+static NEXT_OPERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Called while a command run on the thread runs longer than the timeout
+type HungHandler = Box<dyn FnMut(&HungCommand)>;
+
+thread_local! {
+    static OPERATION: Cell<Option<u64>> = const { Cell::new(None) };
+    static HUNG_HANDLER: RefCell<Option<(Duration, HungHandler)>> = const { RefCell::new(None) };
+}
+
+/// Call `handler` repeatedly while a command run on the current thread runs longer than
+/// `timeout`, e.g. to offer killing it on the UI thread. A `timeout` of `None` removes it.
+pub fn on_hung_command(timeout: Option<Duration>, handler: impl FnMut(&HungCommand) + 'static) {
+    HUNG_HANDLER.set(timeout.map(|timeout| (timeout, Box::new(handler) as HungHandler)));
+}
+
+/// Id for a new operation, see [in_operation]
+pub fn new_operation() -> u64 {
+    NEXT_OPERATION.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Run `f` on the current thread with the commands it runs belonging to the operation, so that
+/// they can be killed with [cancel_operation]
+pub fn in_operation<T>(operation: u64, f: impl FnOnce() -> T) -> T {
+    let previous = OPERATION.replace(Some(operation));
+    let result = f();
+    OPERATION.set(previous);
+    registry().cancelled.remove(&operation);
+    result
+}
+
+/// The operation of the commands run on the current thread, see [in_operation]
+pub fn current_operation() -> Option<u64> {
+    OPERATION.get()
+}
+
+/// Kill the running commands of the operation. They, and the commands the operation runs
+/// afterwards, fail with [CommandError::Killed][super::CommandError].
+pub fn cancel_operation(operation: u64) {
+    let mut registry = registry();
+    registry.cancelled.insert(operation);
+    for running in registry
+        .commands
+        .iter_mut()
+        .filter(|running| running.operation == Some(operation))
+    {
+        running.killed = true;
+        kill(&mut running.child.lock().unwrap());
+    }
+}
+
+/// Kill the child and, on unix, the processes it started in its process group
+fn kill(child: &mut Child) {
+    #[cfg(unix)]
+    {
+        // The process group id is the id of the child, see [run]
+        let _ = Command::new("kill")
+            .args(["-KILL", "--", &format!("-{}", child.id())])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+    }
+    let _ = child.kill();
+}
+
+fn registry() -> MutexGuard<'static, Registry> {
+    // A poisoned lock only means another thread panicked while registering, the registry is
+    // still usable
+    RUNNING_COMMANDS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// A command which has been running for a while
+#[derive(Clone, Debug)]
+pub struct HungCommand {
+    id: u64,
+    pub command_line: String,
+    pub elapsed: Duration,
+}
+
+impl HungCommand {
+    /// Likely reasons why the command takes so long
+    pub fn hint(&self) -> &'static str {
+        let networked = ["git fetch", "git push", "git clone"]
+            .iter()
+            .any(|subcommand| self.command_line.contains(subcommand));
+        if networked {
+            "It may be waiting for the network or for ssh, e.g. to confirm an unknown host key or \
+             to enter a passphrase. Try running it in a terminal."
+        } else if self.command_line.starts_with("jj ") {
+            "jj may be snapshotting a large working copy, e.g. new build output. Check the \
+             .gitignore and the snapshot.max-new-file-size option."
+        } else {
+            "It may be waiting for input, which commands run by blazingjj don't get."
+        }
+    }
+
+    /// Kill the command. The command fails with [CommandError::Killed][super::CommandError].
+    pub fn kill(&self) {
+        kill_command(self.id);
+    }
+}
+
+/// Kill the registered command and the processes it started
+fn kill_command(id: u64) {
+    let mut registry = registry();
+    if let Some(running) = registry
+        .commands
+        .iter_mut()
+        .find(|running| running.id == id)
+    {
+        running.killed = true;
+        kill(&mut running.child.lock().unwrap());
+    }
+}
+
+/// Kills a command started with [spawn] while another thread waits for it
+#[derive(Clone, Debug)]
+pub struct KillHandle {
+    id: u64,
+}
+
+impl KillHandle {
+    /// Kill the command and the processes it started, if it is still running
+    pub fn kill(&self) {
+        kill_command(self.id);
+    }
+}
+
+/// The longest running command of the operation, if it has been running for longer than
+/// `timeout`. Other commands, e.g. of background work, are left alone.
+pub fn hung_command(operation: u64, timeout: Duration) -> Option<HungCommand> {
+    registry()
+        .commands
+        .iter()
+        .filter(|running| !running.killed && running.operation == Some(operation))
+        .map(|running| HungCommand {
+            id: running.id,
+            command_line: running.command_line.clone(),
+            elapsed: running.started.elapsed(),
+        })
+        .filter(|hung| hung.elapsed > timeout)
+        .max_by_key(|hung| hung.elapsed)
+}
+
+fn read_all(pipe: Option<impl Read>) -> Vec<u8> {
+    let mut output = vec![];
+    if let Some(mut pipe) = pipe {
+        let _ = pipe.read_to_end(&mut output);
+    }
+    output
+}
+
+/// A command started with [spawn], which can be killed like the commands of [run] until it
+/// exited
+pub struct SpawnedCommand {
+    id: u64,
+    command_line: String,
+    started: Instant,
+    child: Arc<Mutex<Child>>,
+}
+
+/// Start a command in its own process group and register it, so that it can be killed through
+/// [hung_command] or its operation. Returns `None` without starting it if the operation of the
+/// thread was cancelled.
+pub fn spawn(command: &mut Command) -> io::Result<Option<SpawnedCommand>> {
+    hide_console_window(command).stdin(Stdio::null());
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+    let operation = OPERATION.get();
+    if operation.is_some_and(|operation| registry().cancelled.contains(&operation)) {
+        return Ok(None);
+    }
+    let child = Arc::new(Mutex::new(command.spawn()?));
+
+    let command_line = CommandRecord::new(command, None, Duration::ZERO).command_line();
+    let started = Instant::now();
+    let mut registry = registry();
+    let id = registry.next_id;
+    registry.next_id += 1;
+    // The operation may have been cancelled while the command was started
+    let killed = operation.is_some_and(|operation| registry.cancelled.contains(&operation));
+    if killed {
+        kill(&mut child.lock().unwrap());
+    }
+    registry.commands.push(RunningCommand {
+        id,
+        operation,
+        command_line: command_line.clone(),
+        started,
+        child: child.clone(),
+        killed,
+    });
+    Ok(Some(SpawnedCommand {
+        id,
+        command_line,
+        started,
+        child,
+    }))
+}
+
+impl SpawnedCommand {
+    pub fn take_stdout(&self) -> Option<ChildStdout> {
+        self.child.lock().unwrap().stdout.take()
+    }
+
+    pub fn take_stderr(&self) -> Option<ChildStderr> {
+        self.child.lock().unwrap().stderr.take()
+    }
+
+    pub fn kill_handle(&self) -> KillHandle {
+        KillHandle { id: self.id }
+    }
+
+    /// Wait for the command to exit. Returns its status and whether it was killed.
+    pub fn wait(self) -> io::Result<(ExitStatus, bool)> {
+        // Don't block on the lock while waiting, so that the command can be killed
+        let mut poll_interval = Duration::from_millis(1);
+        let status = loop {
+            match self.child.lock().unwrap().try_wait() {
+                Ok(Some(status)) => break Ok(status),
+                Ok(None) => {}
+                Err(err) => break Err(err),
+            }
+            thread::sleep(poll_interval);
+            poll_interval = (poll_interval * 2).min(MAX_POLL_INTERVAL);
+            HUNG_HANDLER.with_borrow_mut(|handler| {
+                if let Some((timeout, handler)) = handler
+                    && self.started.elapsed() > *timeout
+                {
+                    handler(&HungCommand {
+                        id: self.id,
+                        command_line: self.command_line.clone(),
+                        elapsed: self.started.elapsed(),
+                    });
+                }
+            });
+        };
+
+        let killed = {
+            let mut registry = registry();
+            let index = registry
+                .commands
+                .iter()
+                .position(|running| running.id == self.id);
+            index.is_some_and(|index| registry.commands.remove(index).killed)
+        };
+        Ok((status?, killed))
+    }
+}
+
+/// Run a command to completion like [Command::output], while it can be killed through
+/// [hung_command]. Returns the output and whether the command was killed.
+pub fn run(command: &mut Command) -> io::Result<(Output, bool)> {
+    command.stdout(Stdio::piped()).stderr(Stdio::piped());
+    let Some(spawned) = spawn(command)? else {
+        let output = Output {
+            status: ExitStatus::default(),
+            stdout: vec![],
+            stderr: vec![],
+        };
+        return Ok((output, true));
+    };
+
+    // Read both pipes in the background, so that neither blocks the command when it is full
+    let stdout = spawned.take_stdout();
+    let stderr = spawned.take_stderr();
+    let stdout_reader = thread::spawn(move || read_all(stdout));
+    let stderr_reader = thread::spawn(move || read_all(stderr));
+
+    let (status, killed) = spawned.wait()?;
+    if killed {
+        // Processes started by the command, like ssh, may still hold the pipes open, so don't
+        // wait for the readers
+        return Ok((
+            Output {
+                status,
+                stdout: vec![],
+                stderr: vec![],
+            },
+            true,
+        ));
+    }
+
+    let output = Output {
+        status,
+        stdout: stdout_reader.join().unwrap_or_default(),
+        stderr: stderr_reader.join().unwrap_or_default(),
+    };
+    Ok((output, false))
+}
+
+#[cfg(test)]
+#[cfg(unix)]
+mod tests {
+    use super::*;
+    use crate::commander::shell_command;
+
+    /// The commands which have been running for longer than `timeout`, longest first
+    fn hung_commands(timeout: Duration) -> Vec<HungCommand> {
+        let mut hung_commands: Vec<HungCommand> = registry()
+            .commands
+            .iter()
+            .filter(|running| !running.killed)
+            .map(|running| HungCommand {
+                id: running.id,
+                command_line: running.command_line.clone(),
+                elapsed: running.started.elapsed(),
+            })
+            .filter(|hung| hung.elapsed > timeout)
+            .collect();
+        hung_commands.sort_by_key(|hung| std::cmp::Reverse(hung.elapsed));
+        hung_commands
+    }
+
+    #[test]
+    fn run_command() -> io::Result<()> {
+        let (output, killed) = run(&mut shell_command("echo out && echo err >&2"))?;
+        assert!(output.status.success());
+        assert_eq!(output.stdout.trim_ascii(), b"out");
+        assert_eq!(output.stderr.trim_ascii(), b"err");
+        assert!(!killed);
+        Ok(())
+    }
+
+    #[test]
+    fn kill_hung_command() -> io::Result<()> {
+        let runner = thread::spawn(|| run(&mut shell_command("sleep 30")));
+
+        // Other tests run jj commands in parallel
+        let hung = loop {
+            if let Some(hung) = hung_commands(Duration::ZERO)
+                .into_iter()
+                .find(|hung| hung.command_line.contains("sleep 30"))
+            {
+                break hung;
+            }
+            thread::sleep(Duration::from_millis(10));
+        };
+        hung.kill();
+
+        let (output, killed) = runner.join().unwrap()?;
+        assert!(!output.status.success());
+        assert!(killed);
+        Ok(())
+    }
+
+    #[test]
+    fn kill_started_processes() -> io::Result<()> {
+        let dir = tempdir::TempDir::new("blazingjj")?;
+        let pid_file = dir.path().join("pid");
+        let script = format!("sleep 33 & echo $! > '{}'; wait", pid_file.display());
+        let runner = thread::spawn(move || run(&mut shell_command(&script)));
+
+        let hung = loop {
+            if let Some(hung) = hung_commands(Duration::ZERO)
+                .into_iter()
+                .find(|hung| hung.command_line.contains("sleep 33"))
+                && std::fs::read_to_string(&pid_file).is_ok_and(|pid| pid.ends_with('\n'))
+            {
+                break hung;
+            }
+            thread::sleep(Duration::from_millis(10));
+        };
+        hung.kill();
+        let (_, killed) = runner.join().unwrap()?;
+        assert!(killed);
+
+        // The orphaned sleep is reaped by init shortly after it was killed
+        let pid = std::fs::read_to_string(&pid_file)?;
+        let is_running = || {
+            Command::new("kill")
+                .args(["-0", pid.trim()])
+                .stderr(Stdio::null())
+                .status()
+                .is_ok_and(|status| status.success())
+        };
+        let started = Instant::now();
+        while is_running() && started.elapsed() < Duration::from_secs(5) {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(!is_running());
+        Ok(())
+    }
+
+    #[test]
+    fn kill_from_hung_handler() -> io::Result<()> {
+        let runner = thread::spawn(|| {
+            on_hung_command(Some(Duration::from_millis(50)), |hung| hung.kill());
+            run(&mut shell_command("sleep 34"))
+        });
+
+        let (_, killed) = runner.join().unwrap()?;
+        assert!(killed);
+        Ok(())
+    }
+
+    #[test]
+    fn cancel_operation_commands() -> io::Result<()> {
+        let operation = new_operation();
+        let runner =
+            thread::spawn(move || in_operation(operation, || run(&mut shell_command("sleep 31"))));
+
+        while !hung_commands(Duration::ZERO)
+            .iter()
+            .any(|hung| hung.command_line.contains("sleep 31"))
+        {
+            thread::sleep(Duration::from_millis(10));
+        }
+        cancel_operation(operation);
+
+        let (_, killed) = runner.join().unwrap()?;
+        assert!(killed);
+        Ok(())
+    }
+
+    #[test]
+    fn cancelled_operation_runs_no_more_commands() -> io::Result<()> {
+        let dir = tempdir::TempDir::new("blazingjj")?;
+        let marker = dir.path().join("pushed");
+        let script = format!("touch '{}'", marker.display());
+        let operation = new_operation();
+        let (output, killed) = in_operation(operation, || {
+            cancel_operation(operation);
+            run(&mut shell_command(&script))
+        })?;
+        assert!(killed);
+        assert!(output.stdout.is_empty());
+        assert!(!marker.exists());
+
+        // The cancellation ends with the operation
+        let (_, killed) = in_operation(operation, || run(&mut shell_command("true")))?;
+        assert!(!killed);
+        Ok(())
+    }
+}
@@ -0,0 +1,302 @@
+/*!
+Cache of the details of changes shown in the log tab, the output of `jj show`.
+
+The details of the changes above and below the selected one are prefetched in
+a background thread, so moving through the log with `j`/`k` shows them without
+waiting for jj. Only a few outputs are kept, and all of them are dropped when
+the repository changes, detected with [Commander::get_op_heads], or when the
+details are shown in another format. Outputs of other widths are kept, so
+switching between a full screen and a split terminal doesn't run jj again.
+*/
+use std::{
+    collections::VecDeque,
+    sync::{
+        Arc, Mutex,
+        mpsc::{Receiver, Sender, channel},
+    },
+    thread,
+};
+
+use crate::{
+    commander::{Commander, ids::CommitId},
+    env::{DiffFormat, DiffOverride},
+};
+
+/// Number of outputs kept, the least recently used is dropped first
+const CAPACITY: usize = 16;
+
+/// What the output of `jj show` depends on besides the commit
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShowContext {
+    pub diff_format: DiffFormat,
+    pub diff_overrides: Vec<DiffOverride>,
+    pub width: usize,
+    /// Operation heads of the repository, which change with bookmarks shown in the details
+    pub op_heads: Vec<String>,
+}
+
+impl ShowContext {
+    /// Whether the outputs of the other context are valid in this one, if produced for the
+    /// same width
+    fn is_compatible(&self, other: &ShowContext) -> bool {
+        self.diff_format == other.diff_format
+            && self.diff_overrides == other.diff_overrides
+            && self.op_heads == other.op_heads
+    }
+}
+
+/// Output of a prefetched commit, `None` if `jj show` failed
+type Prefetched = (ShowContext, CommitId, Option<String>);
+
+pub struct ShowCache {
+    context: Option<ShowContext>,
+    /// Outputs by width and commit, the most recently used last
+    entries: VecDeque<(usize, CommitId, String)>,
+    /// Commits being prefetched, with the width
+    pending: Vec<(usize, CommitId)>,
+    tx: Sender<Prefetched>,
+    rx: Receiver<Prefetched>,
+}
+
+impl Default for ShowCache {
+    fn default() -> Self {
+        let (tx, rx) = channel();
+        Self {
+            context: None,
+            entries: VecDeque::new(),
+            pending: vec![],
+            tx,
+            rx,
+        }
+    }
+}
+
+impl ShowCache {
+    /// Drop the outputs if they were produced in an incompatible context
+    fn set_context(&mut self, context: &ShowContext) {
+        if !self
+            .context
+            .as_ref()
+            .is_some_and(|current| current.is_compatible(context))
+        {
+            self.entries.clear();
+            self.pending.clear();
+        }
+        self.context = Some(context.clone());
+    }
+
+    /// Cached output of `jj show` for the commit
+    pub fn get(&mut self, context: &ShowContext, commit_id: &CommitId) -> Option<String> {
+        self.set_context(context);
+        self.receive();
+        let index = self
+            .entries
+            .iter()
+            .position(|(width, id, _)| *width == context.width && id == commit_id)?;
+        let entry = self.entries.remove(index)?;
+        let output = entry.2.clone();
+        self.entries.push_back(entry);
+        Some(output)
+    }
+
+    pub fn insert(&mut self, context: &ShowContext, commit_id: CommitId, output: String) {
+        self.set_context(context);
+        let width = context.width;
+        self.entries
+            .retain(|(other_width, id, _)| *other_width != width || *id != commit_id);
+        self.pending
+            .retain(|(other_width, id)| *other_width != width || *id != commit_id);
+        self.entries.push_back((width, commit_id, output));
+        while self.entries.len() > CAPACITY {
+            self.entries.pop_front();
+        }
+    }
+
+    /// Move the prefetched outputs into the cache, dropping those of an outdated context
+    fn receive(&mut self) {
+        while let Ok((context, commit_id, output)) = self.rx.try_recv() {
+            if !self
+                .context
+                .as_ref()
+                .is_some_and(|current| current.is_compatible(&context))
+            {
+                continue;
+            }
+            self.pending
+                .retain(|(width, id)| *width != context.width || *id != commit_id);
+            if let Some(output) = output {
+                self.insert(&context, commit_id, output);
+            }
+        }
+    }
+
+    /// Run `jj show` in a background thread for the commits which are neither cached nor
+    /// already being prefetched
+    pub fn prefetch(
+        &mut self,
+        commander: &Commander,
+        context: &ShowContext,
+        commit_ids: Vec<CommitId>,
+    ) {
+        self.set_context(context);
+        self.receive();
+        let commit_ids: Vec<CommitId> = commit_ids
+            .into_iter()
+            .filter(|commit_id| {
+                !self
+                    .pending
+                    .iter()
+                    .any(|(width, id)| *width == context.width && id == commit_id)
+                    && !self
+                        .entries
+                        .iter()
+                        .any(|(width, id, _)| *width == context.width && id == commit_id)
+            })
+            .collect();
+        if commit_ids.is_empty() {
+            return;
+        }
+        self.pending.extend(
+            commit_ids
+                .iter()
+                .map(|commit_id| (context.width, commit_id.clone())),
+        );
+
+        let mut commander = commander.background();
+        let context = context.clone();
+        let tx = self.tx.clone();
+        thread::spawn(move || {
+            for commit_id in commit_ids {
+                commander.limit_width(context.width);
+                let output = commander
+                    .get_commit_show_with_overrides(
+                        &commit_id,
+                        &context.diff_format,
+                        &context.diff_overrides,
+                        true,
+                    )
+                    .ok();
+                if tx.send((context.clone(), commit_id, output)).is_err() {
+                    return;
+                }
+            }
+        });
+    }
+}
+
+impl Commander {
+    /// Copy for a background thread. Environment variables set with [Commander::set_env] apply
+    /// to the next command, so the copy has its own.
+    pub fn background(&self) -> Self {
+        Self {
+            env: self.env.clone(),
+            env_var: Arc::new(Mutex::new(Vec::new())),
+            action_tracking: Mutex::new(None),
+            jj_config_toml: self.jj_config_toml.clone(),
+            force_no_color: self.force_no_color,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commander::tests::TestRepo;
+    use anyhow::Result;
+    use std::time::{Duration, Instant};
+
+    fn show_context(width: usize) -> ShowContext {
+        ShowContext {
+            diff_format: DiffFormat::Git,
+            diff_overrides: vec![],
+            width,
+            op_heads: vec![],
+        }
+    }
+
+    #[test]
+    fn least_recently_used_is_dropped() {
+        let mut cache = ShowCache::default();
+        let context = show_context(80);
+        for i in 0..CAPACITY {
+            cache.insert(&context, CommitId(i.to_string()), i.to_string());
+        }
+        assert_eq!(
+            cache.get(&context, &CommitId("0".to_owned())),
+            Some("0".to_owned())
+        );
+
+        cache.insert(&context, CommitId("new".to_owned()), "new".to_owned());
+        assert_eq!(cache.get(&context, &CommitId("1".to_owned())), None);
+        assert_eq!(
+            cache.get(&context, &CommitId("0".to_owned())),
+            Some("0".to_owned())
+        );
+
+        assert_eq!(
+            cache.get(&show_context(100), &CommitId("0".to_owned())),
+            None
+        );
+    }
+
+    #[test]
+    fn outputs_are_kept_per_width() {
+        let mut cache = ShowCache::default();
+        let (narrow, wide) = (show_context(80), show_context(160));
+        let commit_id = CommitId("0".to_owned());
+        cache.insert(&narrow, commit_id.clone(), "narrow".to_owned());
+        cache.insert(&wide, commit_id.clone(), "wide".to_owned());
+        assert_eq!(cache.get(&narrow, &commit_id), Some("narrow".to_owned()));
+        assert_eq!(cache.get(&wide, &commit_id), Some("wide".to_owned()));
+
+        // Another format drops the outputs of every width
+        let color_words = ShowContext {
+            diff_format: DiffFormat::ColorWords,
+            ..show_context(80)
+        };
+        assert_eq!(cache.get(&color_words, &commit_id), None);
+        assert_eq!(cache.get(&narrow, &commit_id), None);
+    }
+
+    #[test]
+    fn prefetch() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+        test_repo
+            .commander
+            .execute_void_jj_command(["describe", "-m", "first"])?;
+        test_repo.commander.execute_void_jj_command(["new"])?;
+        let head = test_repo.commander.get_current_head()?;
+        let parent = test_repo
+            .commander
+            .execute_jj_command(
+                ["log", "--no-graph", "-r", "@-", "-T", "commit_id"],
+                false,
+                true,
+            )?
+            .trim()
+            .to_owned();
+        let commit_ids = vec![head.commit_id.clone(), CommitId(parent)];
+
+        let mut cache = ShowCache::default();
+        let context = show_context(80);
+        cache.prefetch(&test_repo.commander, &context, commit_ids.clone());
+
+        let started = Instant::now();
+        while cache.get(&context, &commit_ids[1]).is_none() {
+            assert!(started.elapsed() < Duration::from_secs(10));
+            thread::sleep(Duration::from_millis(10));
+        }
+        for commit_id in &commit_ids {
+            assert_eq!(
+                cache.get(&context, commit_id),
+                Some(
+                    test_repo
+                        .commander
+                        .get_commit_show(commit_id, &DiffFormat::Git, true)?
+                )
+            );
+        }
+
+        Ok(())
+    }
+}
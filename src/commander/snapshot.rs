@@ -0,0 +1,186 @@
+/*!
+Files jj refused to snapshot because they exceed `snapshot.max-new-file-size`.
+
+jj only prints a warning for them and leaves them untracked. As with the
+[history][crate::commander::history], the refused files are kept in a global by
+the root of the repository, since any [Commander] can run the snapshotting
+command. The app shows them in
+the [snapshot_popup][crate::ui::snapshot_popup].
+*/
+use std::{
+    collections::HashMap,
+    fs::OpenOptions,
+    io::Write,
+    path::Path,
+    sync::{LazyLock, Mutex},
+};
+
+use regex::Regex;
+use tracing::instrument;
+
+use crate::commander::{CommandError, Commander, files::slash_path, log::path_fileset};
+
+/// A new file which is too large to be snapshotted
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RefusedFile {
+    /// Path relative to the repository root
+    pub path: String,
+    /// Size in bytes
+    pub size: u64,
+    /// Value of `snapshot.max-new-file-size` in bytes
+    pub max_size: u64,
+}
+
+static REFUSED_FILES: LazyLock<Mutex<HashMap<String, Vec<RefusedFile>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+// Matches the lines below jj's "Refused to snapshot some files:" warning
+static REFUSED_FILE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?m)^\s+(.+): .* \((\d+) bytes\); the maximum size allowed is .* \((\d+) bytes\)$")
+        .unwrap()
+});
+
+/// Parse the files listed in jj's "Refused to snapshot some files" warning
+pub fn parse_refused_files(stderr: &str) -> Vec<RefusedFile> {
+    if !stderr.contains("Refused to snapshot some files") {
+        return vec![];
+    }
+
+    REFUSED_FILE_REGEX
+        .captures_iter(stderr)
+        .filter_map(|captures| {
+            Some(RefusedFile {
+                path: captures.get(1)?.as_str().to_owned(),
+                size: captures.get(2)?.as_str().parse().ok()?,
+                max_size: captures.get(3)?.as_str().parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+/// Remember the files refused in the stderr of a command run in the repository at `root`,
+/// replacing earlier ones
+pub(super) fn record_refused_files(root: &str, stderr: &[u8]) {
+    let refused_files = parse_refused_files(&String::from_utf8_lossy(stderr));
+    if refused_files.is_empty() {
+        return;
+    }
+    REFUSED_FILES
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .insert(root.to_owned(), refused_files);
+}
+
+/// Take the files refused in the repository at `root` since the last call
+pub fn take_refused_files(root: &str) -> Vec<RefusedFile> {
+    REFUSED_FILES
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .remove(root)
+        .unwrap_or_default()
+}
+
+impl Commander {
+    /// Track files despite their size. Unlike raising `snapshot.max-new-file-size`, this doesn't
+    /// track other refused files up to the same size.
+    /// Maps to `jj file track --include-ignored <paths>`
+    #[instrument(level = "trace", skip(self))]
+    pub fn track_refused_files(&self, files: &[RefusedFile]) -> Result<(), CommandError> {
+        let filesets: Vec<String> = files.iter().map(|file| path_fileset(&file.path)).collect();
+
+        let mut args = vec!["file", "track", "--include-ignored"];
+        args.extend(filesets.iter().map(String::as_str));
+        self.execute_void_jj_command(args)
+    }
+
+    /// Append paths to the `.gitignore` in the repository root
+    #[instrument(level = "trace", skip(self))]
+    pub fn ignore_files(&self, paths: &[&str]) -> Result<(), CommandError> {
+        if self.env.config.read_only() {
+            return Err(CommandError::ReadOnly("Editing .gitignore".to_owned()));
+        }
+        let gitignore = Path::new(&self.env.root).join(".gitignore");
+        let needs_newline = std::fs::read_to_string(&gitignore)
+            .is_ok_and(|content| !content.is_empty() && !content.ends_with('\n'));
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(gitignore)?;
+        if needs_newline {
+            writeln!(file)?;
+        }
+        for path in paths {
+            // Anchor to the root, so only this file is ignored
+            writeln!(file, "/{}", slash_path(path))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commander::tests::TestRepo;
+    use anyhow::Result;
+
+    const WARNING: &str = r#"Warning: Refused to snapshot some files:
+  big.bin: 1.2MiB (1258291 bytes); the maximum size allowed is 1.0MiB (1048576 bytes)
+  assets/video file.mp4: 20.0MiB (20971520 bytes); the maximum size allowed is 1.0MiB (1048576 bytes)
+Hint: This is to prevent large files from being added by accident. You can fix this by:
+  - Adding the file to `.gitignore`
+"#;
+
+    #[test]
+    fn parse_warning() {
+        assert_eq!(
+            parse_refused_files(WARNING),
+            vec![
+                RefusedFile {
+                    path: "big.bin".to_owned(),
+                    size: 1258291,
+                    max_size: 1048576,
+                },
+                RefusedFile {
+                    path: "assets/video file.mp4".to_owned(),
+                    size: 20971520,
+                    max_size: 1048576,
+                },
+            ]
+        );
+        assert_eq!(parse_refused_files("Working copy now at: abc"), vec![]);
+    }
+
+    #[test]
+    fn track_and_ignore_refused_files() -> Result<()> {
+        let mut test_repo = TestRepo::new()?;
+        if let Some(jj_config_toml) = test_repo.commander.jj_config_toml.as_mut() {
+            jj_config_toml.push("snapshot.max-new-file-size=10".to_owned());
+        }
+
+        let root = test_repo.directory.path();
+        std::fs::write(root.join("big"), "more than ten bytes")?;
+        std::fs::write(root.join("other"), "more than ten bytes")?;
+        let head = test_repo.commander.get_current_head()?;
+        assert!(test_repo.commander.get_files(&head)?.is_empty());
+
+        let file = RefusedFile {
+            path: "big".to_owned(),
+            size: 19,
+            max_size: 10,
+        };
+        test_repo.commander.track_refused_files(&[file])?;
+        test_repo.commander.ignore_files(&["other"])?;
+
+        let head = test_repo.commander.get_current_head()?;
+        let files: Vec<_> = test_repo
+            .commander
+            .get_files(&head)?
+            .into_iter()
+            .filter_map(|file| file.path)
+            .collect();
+        assert_eq!(files, vec![".gitignore", "big"]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,175 @@
+/*!
+[Commander] member functions summarizing the changes of a revset for the
+[stats_popup][crate::ui::stats_popup]: commits per author and per week and
+the most frequently changed files.
+
+A single `jj log` lists the author and week of every commit followed by its
+diff summary, so the stats are counted without a command per commit.
+*/
+use std::collections::HashMap;
+
+use itertools::Itertools;
+use tracing::instrument;
+
+use crate::commander::{CommandError, Commander, log::path_fileset};
+
+/// Maximum number of commits counted, as diffing many commits is slow
+pub const STATS_COMMIT_LIMIT: usize = 2000;
+
+/// Number of most frequently changed files listed
+const STATS_FILE_LIMIT: usize = 20;
+
+/// Prefix of the line starting a commit, the diff summary lines start with a status letter
+const COMMIT_PREFIX: &str = "@\t";
+
+/// Template printing `@\t<author>\t<week>` for every commit, followed by its diff summary
+const STATS_TEMPLATE: &str = r#""@\t" ++ author.name() ++ "\t"
+  ++ author.timestamp().local().format("%G-W%V") ++ "\n"
+  ++ self.diff().summary()"#;
+
+/// Counts of the commits of a revset
+#[derive(Debug, Default, PartialEq)]
+pub struct RevsetStats {
+    pub commits: usize,
+    /// Commits by author, most commits first
+    pub authors: Vec<(String, u64)>,
+    /// Commits by ISO week (e.g. `2024-W07`), oldest first
+    pub weeks: Vec<(String, u64)>,
+    /// Commits by changed file, most commits first
+    pub files: Vec<(String, u64)>,
+    /// Whether the revset has more than [STATS_COMMIT_LIMIT] commits, which weren't counted
+    pub truncated: bool,
+}
+
+/// Counts sorted by count, most first, then by name
+fn sorted_by_count(counts: HashMap<String, u64>) -> Vec<(String, u64)> {
+    counts
+        .into_iter()
+        .sorted_by(|(a_name, a_count), (b_name, b_count)| {
+            b_count.cmp(a_count).then_with(|| a_name.cmp(b_name))
+        })
+        .collect()
+}
+
+fn parse_stats(output: &str) -> RevsetStats {
+    let mut commits = 0;
+    let mut authors: HashMap<String, u64> = HashMap::new();
+    let mut weeks: HashMap<String, u64> = HashMap::new();
+    let mut files: HashMap<String, u64> = HashMap::new();
+    let mut truncated = false;
+    for line in output.lines() {
+        if let Some(commit) = line.strip_prefix(COMMIT_PREFIX) {
+            if commits == STATS_COMMIT_LIMIT {
+                truncated = true;
+                break;
+            }
+            commits += 1;
+            let (author, week) = commit.rsplit_once('\t').unwrap_or((commit, ""));
+            let author = if author.is_empty() {
+                "(no author)"
+            } else {
+                author
+            };
+            *authors.entry(author.to_owned()).or_default() += 1;
+            *weeks.entry(week.to_owned()).or_default() += 1;
+        } else if let Some((_, path)) = line.split_once(' ') {
+            *files.entry(path.to_owned()).or_default() += 1;
+        }
+    }
+
+    let mut files = sorted_by_count(files);
+    files.truncate(STATS_FILE_LIMIT);
+    RevsetStats {
+        commits,
+        authors: sorted_by_count(authors),
+        weeks: weeks.into_iter().sorted().collect(),
+        files,
+        truncated,
+    }
+}
+
+impl Commander {
+    /// Count the commits of a revset by author, week and changed file. If `path` is set, only
+    /// commits touching that file or directory are counted.
+    /// Maps to `jj log -r <revset> [<path>]`
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_revset_stats(
+        &self,
+        revset: &str,
+        path: Option<&str>,
+    ) -> Result<RevsetStats, CommandError> {
+        // One commit more than counted, to tell whether the revset was truncated
+        let limit = (STATS_COMMIT_LIMIT + 1).to_string();
+        let mut args = vec![
+            "log",
+            "--no-graph",
+            "-r",
+            revset,
+            "--limit",
+            &limit,
+            "-T",
+            STATS_TEMPLATE,
+        ];
+        let fileset = path.map(path_fileset);
+        if let Some(fileset) = fileset.as_ref() {
+            args.push(fileset);
+        }
+        Ok(parse_stats(&self.execute_jj_command(args, false, true)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commander::tests::TestRepo;
+    use anyhow::Result;
+    use std::fs;
+
+    #[test]
+    fn stats_lines() {
+        let stats = parse_stats(
+            "@\tAlice\t2024-W07\nM src/main.rs\nA README.md\n\
+             @\tBob\t2024-W07\nM src/main.rs\n\
+             @\tAlice\t2024-W05\n\
+             @\t\t2024-W06\nD old.rs\n",
+        );
+        assert_eq!(
+            stats,
+            RevsetStats {
+                commits: 4,
+                authors: vec![
+                    ("Alice".to_owned(), 2),
+                    ("(no author)".to_owned(), 1),
+                    ("Bob".to_owned(), 1),
+                ],
+                weeks: vec![
+                    ("2024-W05".to_owned(), 1),
+                    ("2024-W06".to_owned(), 1),
+                    ("2024-W07".to_owned(), 2),
+                ],
+                files: vec![
+                    ("src/main.rs".to_owned(), 2),
+                    ("README.md".to_owned(), 1),
+                    ("old.rs".to_owned(), 1),
+                ],
+                truncated: false,
+            }
+        );
+    }
+
+    #[test]
+    fn get_revset_stats() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+        fs::write(test_repo.directory.path().join("README"), "first")?;
+        test_repo.commander.execute_void_jj_command(["new"])?;
+        fs::write(test_repo.directory.path().join("README"), "second")?;
+
+        let stats = test_repo.commander.get_revset_stats("::@ ~ root()", None)?;
+        assert_eq!(stats.commits, 2);
+        assert_eq!(stats.authors.len(), 1);
+        assert_eq!(stats.files, [("README".to_owned(), 2)]);
+        assert!(!stats.truncated);
+
+        Ok(())
+    }
+}
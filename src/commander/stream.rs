@@ -0,0 +1,234 @@
+/*!
+Commands whose output is shown while they are still running.
+
+External diff tools like difftastic can take many seconds on large files.
+Waiting for them would freeze the UI, so their output is read in a background
+thread and passed on line by line. Dropping a [StreamingOutput], e.g. because
+another change was selected, kills the command with the processes it started,
+like the diff tool.
+*/
+use std::{
+    ffi::OsStr,
+    io::{BufRead, BufReader, Read},
+    process::{Command, Stdio},
+    sync::mpsc::{Receiver, channel},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::commander::{
+    CommandError, Commander, get_output_args,
+    history::{CommandRecord, record_command},
+    running::{self, KillHandle},
+};
+
+enum StreamMessage {
+    Output(String),
+    /// The command exited, with its stderr if it failed
+    Exited(Option<(String, Option<i32>)>),
+}
+
+/// Output of a running command, see [Commander::execute_streaming_jj_command]
+pub struct StreamingOutput {
+    output: String,
+    rx: Receiver<StreamMessage>,
+    command: KillHandle,
+    started: Instant,
+    running: bool,
+    /// Stderr and exit code if the command failed
+    error: Option<(String, Option<i32>)>,
+}
+
+impl StreamingOutput {
+    /// Receive the output printed since the last call. Returns whether anything changed.
+    pub fn poll(&mut self) -> bool {
+        // After cancelling, the rest of the output and the exit status are ignored
+        let mut changed = false;
+        while self.running
+            && let Ok(message) = self.rx.try_recv()
+        {
+            changed = true;
+            match message {
+                StreamMessage::Output(line) => self.output.push_str(&line),
+                StreamMessage::Exited(error) => {
+                    self.running = false;
+                    self.error = error;
+                }
+            }
+        }
+        changed
+    }
+
+    /// The output received so far, or the error if the command failed
+    pub fn result(&self) -> Result<&str, CommandError> {
+        match self.error.as_ref() {
+            Some((stderr, code)) => Err(CommandError::Status(stderr.clone(), *code)),
+            None => Ok(&self.output),
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    /// Time since the command was started
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Kill the command, keeping the output received so far
+    pub fn cancel(&mut self) {
+        if !self.running {
+            return;
+        }
+        self.command.kill();
+        self.running = false;
+        self.output.push_str("\n(cancelled)");
+    }
+}
+
+impl Drop for StreamingOutput {
+    fn drop(&mut self) {
+        if self.running {
+            self.command.kill();
+        }
+    }
+}
+
+impl Commander {
+    /// Start a jj command in the background and stream its output. Use
+    /// [StreamingOutput::poll] to receive it.
+    pub fn execute_streaming_jj_command<I, S>(
+        &self,
+        args: I,
+        color: bool,
+    ) -> Result<StreamingOutput, CommandError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let mut args = args
+            .into_iter()
+            .map(|arg| arg.as_ref().to_owned())
+            .collect();
+        self.check_read_only(&mut args)?;
+
+        let mut command = Command::new(&self.env.jj_bin);
+        command
+            .args(args)
+            .args(get_output_args(!self.force_no_color && color, true))
+            .current_dir(&self.env.root)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        self.add_config_args(&mut command);
+        command.envs(self.env_var.lock().unwrap().iter().cloned());
+        self.env_var.lock().unwrap().clear();
+
+        let started = Instant::now();
+        let mut record = CommandRecord::new(&command, None, Duration::ZERO);
+        // Started in its own process group, so that cancelling also kills the diff tool
+        let child = match running::spawn(&mut command) {
+            Ok(Some(child)) => child,
+            Ok(None) => {
+                let command_line = record.command_line();
+                record_command(record);
+                return Err(CommandError::Killed(command_line, 0));
+            }
+            Err(err) => {
+                record_command(record);
+                return Err(err.into());
+            }
+        };
+        let stdout = child.take_stdout();
+        let stderr = child.take_stderr();
+        let kill_handle = child.kill_handle();
+
+        let (tx, rx) = channel();
+        thread::spawn(move || {
+            // Read stderr separately, so that a full stderr pipe doesn't block the command
+            let stderr_reader = thread::spawn(move || {
+                let mut stderr_output = String::new();
+                if let Some(mut stderr) = stderr {
+                    let _ = stderr.read_to_string(&mut stderr_output);
+                }
+                stderr_output
+            });
+            if let Some(stdout) = stdout {
+                let mut reader = BufReader::new(stdout);
+                let mut line = vec![];
+                while reader
+                    .read_until(b'\n', &mut line)
+                    .is_ok_and(|read| read > 0)
+                {
+                    let output = String::from_utf8_lossy(&line).into_owned();
+                    if tx.send(StreamMessage::Output(output)).is_err() {
+                        break;
+                    }
+                    line.clear();
+                }
+            }
+            let stderr_output = stderr_reader.join().unwrap_or_default();
+            let status = child.wait().map(|(status, _)| status);
+
+            record.status = status.as_ref().ok().and_then(|status| status.code());
+            record.duration = started.elapsed();
+            record_command(record);
+
+            let error = match status {
+                Ok(status) if status.success() => None,
+                Ok(status) => Some((stderr_output, status.code())),
+                Err(err) => Some((err.to_string(), None)),
+            };
+            let _ = tx.send(StreamMessage::Exited(error));
+        });
+
+        Ok(StreamingOutput {
+            output: String::new(),
+            rx,
+            command: kill_handle,
+            started,
+            running: true,
+            error: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commander::tests::TestRepo;
+    use anyhow::Result;
+
+    fn wait(stream: &mut StreamingOutput) {
+        while stream.is_running() {
+            stream.poll();
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn streaming_output() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+
+        let mut stream = test_repo
+            .commander
+            .execute_streaming_jj_command(["log", "--no-graph", "-T", "description"], false)?;
+        wait(&mut stream);
+        assert_eq!(
+            stream.result()?,
+            test_repo.commander.execute_jj_command(
+                ["log", "--no-graph", "-T", "description"],
+                false,
+                true
+            )?
+        );
+
+        let mut stream = test_repo
+            .commander
+            .execute_streaming_jj_command(["log", "-r", "missing"], false)?;
+        wait(&mut stream);
+        assert!(stream.result().is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,189 @@
+/*!
+[Commander] member functions scanning the diff of a change for added
+TODO/FIXME/XXX markers, listed by the [todos_popup][crate::ui::todos_popup]
+as a quick review before pushing.
+
+Only added lines are scanned, so markers which were already there don't show
+up. The patterns are regexes from `blazingjj.todo-patterns`.
+*/
+use regex::Regex;
+use tracing::instrument;
+
+use crate::commander::{CommandError, Commander, ids::CommitId};
+
+/// Patterns of `blazingjj.todo-patterns` if it isn't set
+pub const DEFAULT_TODO_PATTERNS: &[&str] = &[r"\bTODO\b", r"\bFIXME\b", r"\bXXX\b"];
+
+/// A marker on an added line
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TodoMarker {
+    pub path: String,
+    /// Line number in the new version of the file, starting at 1
+    pub line: usize,
+    /// The text matching the pattern, e.g. `TODO`
+    pub marker: String,
+    /// The added line, without leading whitespace
+    pub text: String,
+}
+
+/// Regex matching any of the patterns
+pub fn todo_regex(patterns: &[String]) -> Result<Regex, regex::Error> {
+    Regex::new(
+        &patterns
+            .iter()
+            .map(|pattern| format!("(?:{pattern})"))
+            .collect::<Vec<_>>()
+            .join("|"),
+    )
+}
+
+/// Markers on the added lines of a diff in git format
+fn parse_added_todos(diff: &str, regex: &Regex) -> Vec<TodoMarker> {
+    let mut markers = vec![];
+    let mut path: Option<&str> = None;
+    // Whether the lines are the header of a file, before its first hunk
+    let mut in_header = false;
+    let mut line = 0;
+    for diff_line in diff.lines() {
+        if diff_line.starts_with("diff --git ") {
+            path = None;
+            in_header = true;
+        } else if in_header && let Some(new_path) = diff_line.strip_prefix("+++ ") {
+            // Deleted files have no new version
+            path = new_path.strip_prefix("b/");
+        } else if let Some(hunk) = diff_line.strip_prefix("@@ ") {
+            in_header = false;
+            // `@@ -1,5 +1,6 @@`, the new version starts at line 1
+            line = hunk
+                .split_whitespace()
+                .find_map(|range| range.strip_prefix('+'))
+                .and_then(|range| range.split(',').next())
+                .and_then(|start| start.parse().ok())
+                .unwrap_or(0);
+        } else if in_header {
+            continue;
+        } else if let Some(added) = diff_line.strip_prefix('+') {
+            if let Some(path) = path
+                && let Some(found) = regex.find(added)
+            {
+                markers.push(TodoMarker {
+                    path: path.to_owned(),
+                    line,
+                    marker: found.as_str().to_owned(),
+                    text: added.trim().to_owned(),
+                });
+            }
+            line += 1;
+        } else if diff_line.starts_with(' ') {
+            line += 1;
+        }
+    }
+    markers
+}
+
+impl Commander {
+    /// Markers matching `blazingjj.todo-patterns` on the lines added by a change.
+    /// Maps to `jj diff --git -r <commit>`
+    #[instrument(level = "trace", skip(self))]
+    pub fn get_added_todos(&self, commit_id: &CommitId) -> Result<Vec<TodoMarker>, CommandError> {
+        let regex = todo_regex(&self.env.config.todo_patterns()).map_err(|err| {
+            CommandError::Status(format!("Invalid blazingjj.todo-patterns: {err}"), None)
+        })?;
+        let diff =
+            self.execute_jj_command(vec!["diff", "--git", "-r", commit_id.as_str()], false, true)?;
+        Ok(parse_added_todos(&diff, &regex))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commander::tests::TestRepo;
+    use anyhow::Result;
+    use std::fs;
+
+    fn default_regex() -> Regex {
+        let patterns: Vec<String> = DEFAULT_TODO_PATTERNS
+            .iter()
+            .map(|pattern| pattern.to_string())
+            .collect();
+        todo_regex(&patterns).unwrap()
+    }
+
+    #[test]
+    fn added_todos() {
+        let diff = "\
+diff --git a/src/main.rs b/src/main.rs
+index 1111111..2222222 100644
+--- a/src/main.rs
++++ b/src/main.rs
+@@ -10,3 +10,4 @@ fn main() {
+     let a = 1;
+-    // TODO: removed
++    // FIXME: handle errors
+     let b = 2;
++    let todos = 3; // XXX
+diff --git a/old.rs b/old.rs
+deleted file mode 100644
+--- a/old.rs
++++ /dev/null
+@@ -1,1 +0,0 @@
+-// TODO: gone
+diff --git a/new.md b/new.md
+new file mode 100644
+--- /dev/null
++++ b/new.md
+@@ -0,0 +1,2 @@
++TODOS are not markers
++TODO write docs
+";
+        assert_eq!(
+            parse_added_todos(diff, &default_regex()),
+            [
+                TodoMarker {
+                    path: "src/main.rs".to_owned(),
+                    line: 11,
+                    marker: "FIXME".to_owned(),
+                    text: "// FIXME: handle errors".to_owned(),
+                },
+                TodoMarker {
+                    path: "src/main.rs".to_owned(),
+                    line: 13,
+                    marker: "XXX".to_owned(),
+                    text: "let todos = 3; // XXX".to_owned(),
+                },
+                TodoMarker {
+                    path: "new.md".to_owned(),
+                    line: 2,
+                    marker: "TODO".to_owned(),
+                    text: "TODO write docs".to_owned(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn get_added_todos() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+        fs::write(test_repo.directory.path().join("a.txt"), "one\nTODO: two\n")?;
+        test_repo.commander.execute_void_jj_command(["new"])?;
+        fs::write(
+            test_repo.directory.path().join("a.txt"),
+            "one\nTODO: two\nFIXME: three\n",
+        )?;
+
+        let head = test_repo.commander.get_current_head()?;
+        let markers = test_repo.commander.get_added_todos(&head.commit_id)?;
+        assert_eq!(
+            markers,
+            [TodoMarker {
+                path: "a.txt".to_owned(),
+                line: 3,
+                marker: "FIXME".to_owned(),
+                text: "FIXME: three".to_owned(),
+            }]
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,172 @@
+/*!
+Warnings jj prints on stderr next to the output of successful commands, like
+a deprecated config option or a concurrent modification of the repository.
+
+The output of most commands is shown without their stderr, so the warnings
+would be lost. As with the refused files of the
+[snapshot][crate::commander::snapshot], they are kept in a global by the root
+of the repository, since any [Commander][crate::commander::Commander] can run
+the command. The app shows them as banners above the tab until they are
+dismissed.
+*/
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, Mutex},
+};
+
+/// What a warning is about, for the label of its banner
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum WarningKind {
+    ConcurrentModification,
+    Deprecation,
+    BookmarkBackwards,
+    Other,
+}
+
+impl WarningKind {
+    fn of(message: &str) -> Self {
+        let message = message.to_lowercase();
+        if message.contains("concurrent modification") {
+            WarningKind::ConcurrentModification
+        } else if message.contains("deprecated") {
+            WarningKind::Deprecation
+        } else if message.contains("backwards") || message.contains("sideways") {
+            WarningKind::BookmarkBackwards
+        } else {
+            WarningKind::Other
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            WarningKind::ConcurrentModification => "Concurrent modification",
+            WarningKind::Deprecation => "Deprecated",
+            WarningKind::BookmarkBackwards => "Bookmark moved backwards",
+            WarningKind::Other => "Warning",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct JjWarning {
+    pub kind: WarningKind,
+    /// The warning without the `Warning: ` prefix, with the indented lines following it
+    pub message: String,
+}
+
+/// Warnings which have their own popup, see [crate::commander::snapshot]
+const HANDLED_WARNINGS: [&str; 1] = ["Refused to snapshot some files"];
+
+/// Warnings some versions of jj print without the `Warning: ` prefix
+const UNPREFIXED_WARNINGS: [&str; 1] = ["Concurrent modification detected"];
+
+static WARNINGS: LazyLock<Mutex<HashMap<String, Vec<JjWarning>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Parse the `Warning: ` lines of jj's stderr, with the indented lines continuing them
+pub fn parse_warnings(stderr: &str) -> Vec<JjWarning> {
+    let mut messages: Vec<String> = vec![];
+    // Whether the previous line belongs to a warning
+    let mut in_warning = false;
+    for line in stderr.lines() {
+        if let Some(message) = line.strip_prefix("Warning: ") {
+            messages.push(message.trim().to_owned());
+            in_warning = true;
+        } else if UNPREFIXED_WARNINGS
+            .iter()
+            .any(|warning| line.starts_with(warning))
+        {
+            messages.push(line.trim().to_owned());
+            in_warning = true;
+        } else if in_warning
+            && line.starts_with(char::is_whitespace)
+            && !line.trim().is_empty()
+            && let Some(message) = messages.last_mut()
+        {
+            message.push('\n');
+            message.push_str(line.trim());
+        } else {
+            in_warning = false;
+        }
+    }
+
+    messages
+        .into_iter()
+        .filter(|message| {
+            !HANDLED_WARNINGS
+                .iter()
+                .any(|handled| message.starts_with(handled))
+        })
+        .map(|message| JjWarning {
+            kind: WarningKind::of(&message),
+            message,
+        })
+        .collect()
+}
+
+/// Remember the warnings in the stderr of a command run in the repository at `root`
+pub(super) fn record_warnings(root: &str, stderr: &[u8]) {
+    let warnings = parse_warnings(&String::from_utf8_lossy(stderr));
+    if warnings.is_empty() {
+        return;
+    }
+    let mut warnings_by_root = WARNINGS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let recorded = warnings_by_root.entry(root.to_owned()).or_default();
+    for warning in warnings {
+        if !recorded.contains(&warning) {
+            recorded.push(warning);
+        }
+    }
+}
+
+/// Take the warnings recorded for the repository at `root` since the last call
+pub fn take_warnings(root: &str) -> Vec<JjWarning> {
+    WARNINGS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .remove(root)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_jj_warnings() {
+        let stderr = "\
+Concurrent modification detected, resolving automatically.
+Warning: Deprecated config: ui.default-revset is renamed to revsets.log
+Working copy  (@) now at: kkmpptxz 3fa1b2c4 (empty) (no description set)
+Warning: Refused to snapshot some files:
+  big.bin: 2.0MiB (2097152 bytes); the maximum size allowed is 1.0MiB (1048576 bytes)
+Warning: The working-copy commit in workspace 'second' became immutable, so a new commit
+  has been created on top of it.
+Hint: Use `jj workspace update-stale` to update it.
+";
+        assert_eq!(
+            parse_warnings(stderr),
+            [
+                JjWarning {
+                    kind: WarningKind::ConcurrentModification,
+                    message: "Concurrent modification detected, resolving automatically."
+                        .to_owned(),
+                },
+                JjWarning {
+                    kind: WarningKind::Deprecation,
+                    message: "Deprecated config: ui.default-revset is renamed to revsets.log"
+                        .to_owned(),
+                },
+                JjWarning {
+                    kind: WarningKind::Other,
+                    message: "The working-copy commit in workspace 'second' became immutable, \
+                              so a new commit\nhas been created on top of it."
+                        .to_owned(),
+                },
+            ]
+        );
+        assert_eq!(parse_warnings("Nothing changed.\n"), []);
+    }
+}
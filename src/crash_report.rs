@@ -0,0 +1,117 @@
+/*!
+Crash reports written from the panic hook.
+
+Besides the panic message and backtrace, a report contains the versions of
+blazingjj and jj, the last jj commands from the
+[command history][crate::commander::history] and a snapshot of the UI state,
+so that "it crashed" issues can be acted on. In presentation mode, the
+emails and descriptions in it are replaced as on the screen.
+*/
+use std::{
+    backtrace::Backtrace,
+    fmt::Write as _,
+    fs,
+    panic::PanicHookInfo,
+    path::PathBuf,
+    process::Command,
+    sync::{LazyLock, Mutex},
+};
+
+use anyhow::Result;
+
+use crate::{anonymize::anonymize_text, commander::history::command_history};
+
+#[derive(Default)]
+struct CrashContext {
+    jj_bin: Option<String>,
+    /// Snapshot of the UI state, updated by the main loop
+    state: String,
+    /// Description lines to replace, None if the presentation mode is off
+    anonymized_descriptions: Option<Vec<Vec<char>>>,
+}
+
+static CRASH_CONTEXT: LazyLock<Mutex<CrashContext>> =
+    LazyLock::new(|| Mutex::new(CrashContext::default()));
+
+fn context() -> std::sync::MutexGuard<'static, CrashContext> {
+    CRASH_CONTEXT
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Set the jj binary used to query the jj version for the report
+pub fn set_jj_bin(jj_bin: &str) {
+    context().jj_bin = Some(jj_bin.to_owned());
+}
+
+/// Update the UI state snapshot included in the report, and the descriptions replaced in it
+/// in presentation mode
+pub fn set_state(state: String, anonymized_descriptions: Option<Vec<Vec<char>>>) {
+    let mut context = context();
+    context.state = state;
+    context.anonymized_descriptions = anonymized_descriptions;
+}
+
+fn jj_version(jj_bin: Option<&str>) -> String {
+    jj_bin
+        .and_then(|jj_bin| Command::new(jj_bin).arg("version").output().ok())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_owned())
+        .unwrap_or_else(|| "unknown".to_owned())
+}
+
+fn format_report(info: &PanicHookInfo) -> String {
+    let context = context();
+    let mut report = String::new();
+
+    let _ = writeln!(report, "blazingjj crash report");
+    let _ = writeln!(report);
+    let _ = writeln!(report, "blazingjj version: {}", env!("CARGO_PKG_VERSION"));
+    let _ = writeln!(
+        report,
+        "jj version: {}",
+        jj_version(context.jj_bin.as_deref())
+    );
+    let _ = writeln!(
+        report,
+        "Platform: {} {}",
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    );
+    let _ = writeln!(report, "Time: {}", chrono::Local::now().to_rfc3339());
+    let _ = writeln!(report);
+    let _ = writeln!(report, "{info}");
+    let _ = writeln!(report);
+    let _ = writeln!(report, "State:");
+    let _ = writeln!(report, "{}", context.state);
+    let _ = writeln!(report);
+    let _ = writeln!(report, "Last jj commands (oldest first):");
+    for record in command_history() {
+        let status = record
+            .status
+            .map_or_else(|| "-".to_owned(), |status| status.to_string());
+        let _ = writeln!(
+            report,
+            "  [{status}, {}ms] {}",
+            record.duration.as_millis(),
+            shell_words::join(&record.args)
+        );
+    }
+    let _ = writeln!(report);
+    if let Some(descriptions) = context.anonymized_descriptions.as_ref() {
+        report = anonymize_text(&report, descriptions);
+    }
+    let _ = writeln!(report, "Backtrace:");
+    let _ = writeln!(report, "{}", Backtrace::force_capture());
+
+    report
+}
+
+/// Write a crash report for the panic to the temp directory and return its path
+pub fn write_crash_report(info: &PanicHookInfo) -> Result<PathBuf> {
+    let path = std::env::temp_dir().join(format!(
+        "blazingjj-crash-{}.txt",
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    ));
+    fs::write(&path, format_report(info))?;
+    Ok(path)
+}
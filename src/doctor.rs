@@ -0,0 +1,349 @@
+/*!
+Diagnostics of the jj installation, the config and the repository.
+
+The report is printed by `blazingjj --doctor` and, with
+`blazingjj.startup-diagnostics`, shown in a popup on startup when it finds
+issues, so that "it doesn't work" reports come with the likely cause.
+*/
+use std::{fmt, path::Path};
+
+use ratatui::{
+    style::{Color, Stylize},
+    text::{Line, Span, Text},
+};
+
+use crate::{
+    commander::{CommandError, Commander, operations::directory_size},
+    ui::utils::human_size,
+};
+
+/// Size of the object store above which the repository is considered large
+const LARGE_STORE_SIZE: u64 = 1024 * 1024 * 1024;
+/// Size of the operation store above which trimming the operation log is suggested
+const LARGE_OP_STORE_SIZE: u64 = 256 * 1024 * 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Ok,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    fn label(&self) -> &'static str {
+        match self {
+            Severity::Ok => "ok",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+
+    fn color(&self) -> Color {
+        match self {
+            Severity::Ok => Color::Green,
+            Severity::Warning => Color::Yellow,
+            Severity::Error => Color::Red,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Check {
+    pub name: String,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Check {
+    fn new(name: impl Into<String>, severity: Severity, message: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            severity,
+            message: message.into(),
+        }
+    }
+}
+
+/// Result of all checks
+#[derive(Clone, Debug, Default)]
+pub struct Report {
+    pub checks: Vec<Check>,
+}
+
+/// First line of an error, jj and git add hints on the following lines
+fn first_line(err: &impl fmt::Display) -> String {
+    err.to_string()
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .to_owned()
+}
+
+impl Report {
+    /// Run all checks. Reaching the remotes may take a few seconds.
+    pub fn run(commander: &Commander) -> Self {
+        let mut checks = vec![check_jj_version(commander), check_jj_features(commander)];
+        checks.extend(check_config(commander));
+        checks.extend(check_repository_size(commander));
+        checks.push(check_colocation(commander));
+        checks.extend(check_remotes(commander));
+        Self { checks }
+    }
+
+    /// Run the checks which don't need a repository, after setting up the environment failed
+    /// with `err`, e.g. outside of a repository or with a config jj can't read
+    pub fn run_without_env(commander: &Commander, err: &anyhow::Error) -> Self {
+        Self {
+            checks: vec![
+                check_jj_version(commander),
+                Check::new("repository", Severity::Error, format!("{err:#}")),
+            ],
+        }
+    }
+
+    /// Whether any check found a warning or an error
+    pub fn has_issues(&self) -> bool {
+        self.checks
+            .iter()
+            .any(|check| check.severity != Severity::Ok)
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.checks
+            .iter()
+            .any(|check| check.severity == Severity::Error)
+    }
+
+    /// Colored report for the popup shown on startup
+    pub fn to_text(&self) -> Text<'static> {
+        self.checks
+            .iter()
+            .map(|check| {
+                Line::from(vec![
+                    Span::raw(format!("[{}] ", check.severity.label()))
+                        .fg(check.severity.color())
+                        .bold(),
+                    Span::raw(format!("{}: ", check.name)).bold(),
+                    Span::raw(check.message.clone()),
+                ])
+            })
+            .collect::<Vec<_>>()
+            .into()
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for check in &self.checks {
+            writeln!(
+                f,
+                "[{}] {}: {}",
+                check.severity.label(),
+                check.name,
+                check.message
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn check_jj_version(commander: &Commander) -> Check {
+    let version = match commander.execute_jj_command(vec!["version"], false, true) {
+        Ok(version) => version.trim().to_owned(),
+        Err(err) => {
+            return Check::new(
+                "jj version",
+                Severity::Error,
+                format!("Could not run jj: {}", first_line(&err)),
+            );
+        }
+    };
+    match commander.check_jj_version() {
+        Ok(()) => Check::new("jj version", Severity::Ok, version),
+        Err(err) => Check::new("jj version", Severity::Error, first_line(&err)),
+    }
+}
+
+/// Evaluate revsets and templates used by blazingjj, which fail with unsupported jj versions
+/// and broken revset aliases like `trunk()`
+fn check_jj_features(commander: &Commander) -> Check {
+    let result = commander.execute_jj_command(
+        vec![
+            "log",
+            "--no-graph",
+            "--ignore-working-copy",
+            "-r",
+            "@ | trunk()",
+            "-T",
+            r#"self.contained_in("::trunk()") ++ change_id.shortest() ++ "\n""#,
+        ],
+        false,
+        true,
+    );
+    match result {
+        Ok(_) => Check::new("jj features", Severity::Ok, "Revsets and templates work"),
+        Err(err) => Check::new(
+            "jj features",
+            Severity::Warning,
+            format!(
+                "Revsets or templates used by blazingjj fail, check revset-aliases.trunk(): {}",
+                first_line(&err)
+            ),
+        ),
+    }
+}
+
+fn check_config(commander: &Commander) -> Vec<Check> {
+    if commander.env.config_errors.is_empty() {
+        return vec![Check::new("config", Severity::Ok, "No issues")];
+    }
+    commander
+        .env
+        .config_errors
+        .iter()
+        .map(|error| Check::new("config", Severity::Warning, error.clone()))
+        .collect()
+}
+
+fn check_repository_size(commander: &Commander) -> Vec<Check> {
+    let store_path = commander.repo_store_path();
+    let mut checks = vec![];
+
+    match directory_size(&store_path.join("store")) {
+        Some(size) if size > LARGE_STORE_SIZE => checks.push(Check::new(
+            "repository size",
+            Severity::Warning,
+            format!(
+                "The store is {}, the log and diffs may be slow",
+                human_size(size)
+            ),
+        )),
+        Some(size) => checks.push(Check::new(
+            "repository size",
+            Severity::Ok,
+            format!("The store is {}", human_size(size)),
+        )),
+        None => checks.push(Check::new(
+            "repository size",
+            Severity::Warning,
+            format!("Could not read {}", store_path.join("store").display()),
+        )),
+    }
+
+    if let Some(size) = directory_size(&store_path.join("op_store"))
+        && size > LARGE_OP_STORE_SIZE
+    {
+        checks.push(Check::new(
+            "operation log",
+            Severity::Warning,
+            format!(
+                "The operation log is {}, trim it in the maintenance popup (M)",
+                human_size(size)
+            ),
+        ));
+    }
+
+    checks
+}
+
+fn check_colocation(commander: &Commander) -> Check {
+    // Secondary workspaces share the git repository of the default workspace, next to the store
+    let default_root = match commander.env.workspace {
+        Some(_) => commander.repo_store_path().join("..").join(".."),
+        None => Path::new(&commander.env.root).to_path_buf(),
+    };
+    if default_root.join(".git").exists() {
+        Check::new("colocation", Severity::Ok, "Colocated with git")
+    } else {
+        Check::new(
+            "colocation",
+            Severity::Ok,
+            "Not colocated, git tools don't see the repository",
+        )
+    }
+}
+
+fn check_remotes(commander: &Commander) -> Vec<Check> {
+    let remotes = match commander.get_git_remotes() {
+        Ok(remotes) => remotes,
+        Err(err) => {
+            return vec![Check::new(
+                "remotes",
+                Severity::Warning,
+                format!("Could not list the git remotes: {}", first_line(&err)),
+            )];
+        }
+    };
+    if remotes.is_empty() {
+        return vec![Check::new("remotes", Severity::Ok, "No git remotes")];
+    }
+
+    remotes
+        .into_iter()
+        .map(|(name, url)| match commander.git_ls_remote(&url) {
+            Ok(()) => Check::new(
+                format!("remote {name}"),
+                Severity::Ok,
+                format!("{url} is reachable"),
+            ),
+            Err(err) if err.needs_credentials() => Check::new(
+                format!("remote {name}"),
+                Severity::Warning,
+                format!("{url} needs credentials, push and fetch offer to retry in the terminal"),
+            ),
+            Err(CommandError::Output(err)) => Check::new(
+                format!("remote {name}"),
+                Severity::Warning,
+                format!("Could not run git to reach {url}: {err}"),
+            ),
+            Err(err) => Check::new(
+                format!("remote {name}"),
+                Severity::Warning,
+                format!("{url} is not reachable: {}", first_line(&err)),
+            ),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commander::tests::TestRepo;
+    use anyhow::Result;
+
+    #[test]
+    fn report() -> Result<()> {
+        let test_repo = TestRepo::new()?;
+
+        let report = Report::run(&test_repo.commander);
+        assert!(!report.has_errors(), "{report}");
+        assert!(report.checks.contains(&Check::new(
+            "colocation",
+            Severity::Ok,
+            "Colocated with git"
+        )));
+        assert!(
+            report
+                .checks
+                .contains(&Check::new("remotes", Severity::Ok, "No git remotes"))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn format_report() {
+        let report = Report {
+            checks: vec![
+                Check::new("jj version", Severity::Ok, "jj 0.33.0"),
+                Check::new("config", Severity::Warning, "blazingjj.foo: unknown key"),
+            ],
+        };
+        assert!(report.has_issues());
+        assert!(!report.has_errors());
+        assert_eq!(
+            report.to_string(),
+            "[ok] jj version: jj 0.33.0\n[warning] config: blazingjj.foo: unknown key\n"
+        );
+    }
+}
@@ -1,35 +1,115 @@
-use std::{path::PathBuf, process::Command};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::Arc,
+    time::Duration,
+};
 
 use anyhow::{Context, Result, bail};
 use ratatui::style::Color;
-use serde::Deserialize;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    commander::{RemoveEndLine, get_output_args},
-    keybinds::KeybindsConfig,
+    commander::{
+        RemoveEndLine, files::slash_path, get_output_args, hide_console_window,
+        running::CommandRunner, todos::DEFAULT_TODO_PATTERNS,
+    },
+    keybinds::{KeybindPreset, KeybindsConfig},
 };
 
+mod validate;
+
 // TODO: After 0.18, remove Config and replace with JjConfig
 #[derive(Deserialize, Debug, Clone, Default)]
 pub struct Config {
     #[serde(rename = "blazingjj.highlight-color")]
     blazingjj_highlight_color: Option<Color>,
+    #[serde(rename = "blazingjj.focus-color")]
+    blazingjj_focus_color: Option<Color>,
+    #[serde(rename = "blazingjj.highlight-revsets")]
+    blazingjj_highlight_revsets: Option<Vec<HighlightRevset>>,
     #[serde(rename = "blazingjj.diff-format")]
     blazingjj_diff_format: Option<DiffFormat>,
     #[serde(rename = "blazingjj.diff-tool")]
     blazingjj_diff_tool: Option<String>,
+    #[serde(rename = "blazingjj.pager")]
+    blazingjj_pager: Option<String>,
+    #[serde(rename = "blazingjj.diff-overrides")]
+    blazingjj_diff_overrides: Option<Vec<DiffOverride>>,
     #[serde(rename = "blazingjj.bookmark-template")]
     blazingjj_bookmark_template: Option<String>,
+    #[serde(rename = "blazingjj.bookmark-pattern")]
+    blazingjj_bookmark_pattern: Option<String>,
+    #[serde(rename = "blazingjj.merge-template")]
+    blazingjj_merge_template: Option<String>,
+    #[serde(rename = "blazingjj.new-work-base")]
+    blazingjj_new_work_base: Option<String>,
+    #[serde(rename = "blazingjj.bookmark-description-template")]
+    blazingjj_bookmark_description_template: Option<String>,
     #[serde(rename = "blazingjj.layout")]
     blazingjj_layout: Option<JJLayout>,
     #[serde(rename = "blazingjj.layout-percent")]
     blazingjj_layout_percent: Option<u16>,
     #[serde(rename = "blazingjj.keybinds")]
     blazingjj_keybinds: Option<KeybindsConfig>,
+    #[serde(rename = "blazingjj.keybind-preset")]
+    blazingjj_keybind_preset: Option<KeybindPreset>,
+    #[serde(rename = "blazingjj.auto-refresh-seconds")]
+    blazingjj_auto_refresh_seconds: Option<u64>,
+    #[serde(rename = "blazingjj.command-timeout-seconds")]
+    blazingjj_command_timeout_seconds: Option<u64>,
+    #[serde(rename = "blazingjj.hint-bar")]
+    blazingjj_hint_bar: Option<bool>,
+    #[serde(rename = "blazingjj.trunk-relation")]
+    blazingjj_trunk_relation: Option<bool>,
+    #[serde(rename = "blazingjj.hindsight")]
+    blazingjj_hindsight: Option<bool>,
+    #[serde(rename = "blazingjj.mouse")]
+    blazingjj_mouse: Option<bool>,
+    #[serde(rename = "blazingjj.startup-diagnostics")]
+    blazingjj_startup_diagnostics: Option<bool>,
+    #[serde(rename = "blazingjj.describe-generator")]
+    blazingjj_describe_generator: Option<String>,
+    #[serde(rename = "blazingjj.repositories")]
+    blazingjj_repositories: Option<Vec<String>>,
+    #[serde(rename = "blazingjj.id-length")]
+    blazingjj_id_length: Option<IdLength>,
+    #[serde(rename = "blazingjj.hooks")]
+    blazingjj_hooks: Option<HashMap<String, String>>,
+    #[serde(rename = "blazingjj.execution-mode")]
+    blazingjj_execution_mode: Option<ExecutionMode>,
+    #[serde(rename = "blazingjj.read-only")]
+    blazingjj_read_only: Option<bool>,
+    #[serde(rename = "blazingjj.presentation-mode")]
+    blazingjj_presentation_mode: Option<PresentationMode>,
+    #[serde(rename = "blazingjj.push-test-command")]
+    blazingjj_push_test_command: Option<String>,
+    #[serde(rename = "blazingjj.ci-status-command")]
+    blazingjj_ci_status_command: Option<String>,
+    #[serde(rename = "blazingjj.forge-commit-url")]
+    blazingjj_forge_commit_url: Option<String>,
+    #[serde(rename = "blazingjj.copy-reference-template")]
+    blazingjj_copy_reference_template: Option<String>,
+    #[serde(rename = "blazingjj.forge-file-url")]
+    blazingjj_forge_file_url: Option<String>,
+    #[serde(rename = "blazingjj.todo-patterns")]
+    blazingjj_todo_patterns: Option<Vec<String>>,
+    #[serde(rename = "blazingjj.large-diff-lines")]
+    blazingjj_large_diff_lines: Option<u64>,
+    #[serde(rename = "blazingjj.output-processors")]
+    blazingjj_output_processors: Option<Vec<OutputProcessor>>,
+    #[serde(rename = "blazingjj.favorites")]
+    blazingjj_favorites: Option<Vec<Favorite>>,
     #[serde(rename = "ui.diff.format")]
     ui_diff_format: Option<DiffFormat>,
     #[serde(rename = "ui.diff.tool")]
     ui_diff_tool: Option<()>,
+    #[serde(rename = "ui.log-word-wrap")]
+    ui_log_word_wrap: Option<bool>,
+    #[serde(rename = "revsets.log")]
+    revsets_log: Option<String>,
     #[serde(rename = "templates.git_push_bookmark")]
     git_push_bookmark_template: Option<String>,
 }
@@ -39,6 +119,7 @@ pub struct Config {
 pub struct JjConfig {
     blazingjj: Option<JjConfigBlazingjj>,
     ui: Option<JjConfigUi>,
+    revsets: Option<JjConfigRevsets>,
     templates: Option<JjConfigTemplates>,
 }
 
@@ -46,18 +127,51 @@ pub struct JjConfig {
 #[serde(rename_all = "kebab-case")]
 pub struct JjConfigBlazingjj {
     highlight_color: Option<Color>,
+    focus_color: Option<Color>,
+    highlight_revsets: Option<Vec<HighlightRevset>>,
     diff_format: Option<DiffFormat>,
     diff_tool: Option<String>,
+    diff_overrides: Option<Vec<DiffOverride>>,
+    pager: Option<String>,
     bookmark_prefix: Option<String>,
+    bookmark_pattern: Option<String>,
+    merge_template: Option<String>,
+    new_work_base: Option<String>,
+    bookmark_description_template: Option<String>,
     layout: Option<JJLayout>,
     layout_percent: Option<u16>,
     keybinds: Option<KeybindsConfig>,
+    keybind_preset: Option<KeybindPreset>,
+    auto_refresh_seconds: Option<u64>,
+    command_timeout_seconds: Option<u64>,
+    hint_bar: Option<bool>,
+    trunk_relation: Option<bool>,
+    hindsight: Option<bool>,
+    mouse: Option<bool>,
+    startup_diagnostics: Option<bool>,
+    describe_generator: Option<String>,
+    repositories: Option<Vec<String>>,
+    id_length: Option<IdLength>,
+    hooks: Option<HashMap<String, String>>,
+    execution_mode: Option<ExecutionMode>,
+    read_only: Option<bool>,
+    presentation_mode: Option<PresentationMode>,
+    push_test_command: Option<String>,
+    ci_status_command: Option<String>,
+    forge_commit_url: Option<String>,
+    copy_reference_template: Option<String>,
+    forge_file_url: Option<String>,
+    todo_patterns: Option<Vec<String>>,
+    large_diff_lines: Option<u64>,
+    output_processors: Option<Vec<OutputProcessor>>,
+    favorites: Option<Vec<Favorite>>,
 }
 
 #[derive(Deserialize, Debug, Clone, Default)]
 #[serde(rename_all = "kebab-case")]
 pub struct JjConfigUi {
     diff: Option<JjConfigUiDiff>,
+    log_word_wrap: Option<bool>,
 }
 
 #[derive(Deserialize, Debug, Clone, Default)]
@@ -67,6 +181,11 @@ pub struct JjConfigUiDiff {
     tool: Option<toml::Value>,
 }
 
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct JjConfigRevsets {
+    log: Option<String>,
+}
+
 #[derive(Deserialize, Debug, Clone, Default)]
 pub struct JjConfigTemplates {
     git_push_bookmark: Option<String>,
@@ -96,11 +215,38 @@ impl Config {
         None
     }
 
+    /// Whether jj wraps the lines of the log to the width of the terminal
+    pub fn log_word_wrap(&self) -> bool {
+        self.ui_log_word_wrap.unwrap_or(false)
+    }
+
+    /// `revsets.log`, the revset of the log without one given
+    pub fn log_revset(&self) -> Option<&str> {
+        self.revsets_log.as_deref()
+    }
+
+    /// Update `revsets.log` after it was changed with `jj config set`
+    pub fn set_log_revset(&mut self, revset: Option<String>) {
+        self.revsets_log = revset;
+    }
+
     pub fn highlight_color(&self) -> Color {
         self.blazingjj_highlight_color
             .unwrap_or(Color::Rgb(50, 50, 150))
     }
 
+    /// Border color of the focused panel
+    pub fn focus_color(&self) -> Color {
+        self.blazingjj_focus_color.unwrap_or(Color::Green)
+    }
+
+    /// Colors of the revisions of the log matching a revset, in order of priority
+    pub fn highlight_revsets(&self) -> &[HighlightRevset] {
+        self.blazingjj_highlight_revsets
+            .as_deref()
+            .unwrap_or_default()
+    }
+
     pub fn bookmark_template(&self) -> String {
         self.blazingjj_bookmark_template
             .clone()
@@ -108,6 +254,35 @@ impl Config {
             .unwrap_or("'push-' ++ change_id.short()".to_string())
     }
 
+    /// Pattern for bookmark names of issues, e.g. `feat/{issue}-{slug}`
+    pub fn bookmark_pattern(&self) -> Option<&str> {
+        self.blazingjj_bookmark_pattern.as_deref()
+    }
+
+    /// Template for the description of merge changes, see [render_merge_template]
+    ///
+    /// [render_merge_template]: crate::commander::jj::render_merge_template
+    pub fn merge_template(&self) -> &str {
+        self.blazingjj_merge_template
+            .as_deref()
+            .unwrap_or("Merge {parents}\n\n{changes}")
+    }
+
+    /// Revset new work is started on, regardless of the selected change
+    pub fn new_work_base(&self) -> &str {
+        self.blazingjj_new_work_base.as_deref().unwrap_or("trunk()")
+    }
+
+    /// Template for descriptions derived from bookmark names, see
+    /// [render_bookmark_description]
+    ///
+    /// [render_bookmark_description]: crate::commander::bookmarks::render_bookmark_description
+    pub fn bookmark_description_template(&self) -> &str {
+        self.blazingjj_bookmark_description_template
+            .as_deref()
+            .unwrap_or("{title}")
+    }
+
     pub fn layout(&self) -> JJLayout {
         self.blazingjj_layout.unwrap_or(JJLayout::Horizontal)
     }
@@ -116,23 +291,270 @@ impl Config {
         self.blazingjj_layout_percent.unwrap_or(50)
     }
 
+    /// Shell command showing text from stdin, from `blazingjj.pager` or `$PAGER`
+    pub fn pager(&self) -> String {
+        self.blazingjj_pager
+            .clone()
+            .or_else(|| std::env::var("PAGER").ok())
+            .unwrap_or_else(|| "less -R".to_owned())
+    }
+
+    /// Diff formats used instead of the default one for matching files, in order of priority
+    pub fn diff_overrides(&self) -> &[DiffOverride] {
+        self.blazingjj_diff_overrides.as_deref().unwrap_or_default()
+    }
+
+    /// The first diff override matching `path`
+    pub fn diff_override(&self, path: &str) -> Option<&DiffOverride> {
+        self.diff_overrides()
+            .iter()
+            .find(|diff_override| diff_override.matches(path))
+    }
+
+    /// Rewrites of the output of commands shown in popups and the command log
+    pub fn output_processors(&self) -> &[OutputProcessor] {
+        self.blazingjj_output_processors
+            .as_deref()
+            .unwrap_or_default()
+    }
+
+    /// Actions of the favorites bar, run with Alt and their number
+    pub fn favorites(&self) -> &[Favorite] {
+        let favorites = self.blazingjj_favorites.as_deref().unwrap_or_default();
+        &favorites[..favorites.len().min(MAX_FAVORITES)]
+    }
+
     pub fn keybinds(&self) -> Option<&KeybindsConfig> {
         self.blazingjj_keybinds.as_ref()
     }
+
+    pub fn keybind_preset(&self) -> KeybindPreset {
+        self.blazingjj_keybind_preset.unwrap_or_default()
+    }
+
+    /// Interval for refreshing the active tab while idle. `None` (or 0) disables it.
+    pub fn auto_refresh_interval(&self) -> Option<Duration> {
+        self.blazingjj_auto_refresh_seconds
+            .filter(|seconds| *seconds > 0)
+            .map(Duration::from_secs)
+    }
+
+    /// Time after which a running jj command is considered hung. `None` (or 0) disables it.
+    pub fn command_timeout(&self) -> Option<Duration> {
+        Some(self.blazingjj_command_timeout_seconds.unwrap_or(30))
+            .filter(|seconds| *seconds > 0)
+            .map(Duration::from_secs)
+    }
+
+    pub fn hint_bar(&self) -> bool {
+        self.blazingjj_hint_bar.unwrap_or(false)
+    }
+
+    /// Whether the log shows the relationship of each change to `trunk()` by default
+    pub fn trunk_relation(&self) -> bool {
+        self.blazingjj_trunk_relation.unwrap_or(false)
+    }
+
+    /// Whether the log marks the changes new or rewritten since it was last marked as seen
+    pub fn hindsight(&self) -> bool {
+        self.blazingjj_hindsight.unwrap_or(false)
+    }
+
+    /// Whether the mouse is captured on startup. Without it, the terminal selects text natively
+    pub fn mouse(&self) -> bool {
+        self.blazingjj_mouse.unwrap_or(true)
+    }
+
+    /// Whether diagnostics are run on startup, showing a popup if they find issues
+    pub fn startup_diagnostics(&self) -> bool {
+        self.blazingjj_startup_diagnostics.unwrap_or(false)
+    }
+
+    /// Repositories offered by the repository switcher, with a leading `~` expanded
+    pub fn repositories(&self) -> Vec<String> {
+        let home = std::env::var("HOME").ok();
+        self.blazingjj_repositories
+            .iter()
+            .flatten()
+            .map(
+                |repository| match (repository.strip_prefix("~/"), home.as_ref()) {
+                    (Some(path), Some(home)) => format!("{home}/{path}"),
+                    _ => repository.clone(),
+                },
+            )
+            .collect()
+    }
+
+    /// Shell command which drafts a description from a diff on stdin
+    pub fn describe_generator(&self) -> Option<&str> {
+        self.blazingjj_describe_generator.as_deref()
+    }
+
+    /// Shell command of the hook `name`, e.g. `pre-push`
+    pub fn hook(&self, name: &str) -> Option<&str> {
+        self.blazingjj_hooks
+            .as_ref()
+            .and_then(|hooks| hooks.get(name))
+            .map(String::as_str)
+    }
+
+    pub fn execution_mode(&self) -> ExecutionMode {
+        self.blazingjj_execution_mode.unwrap_or_default()
+    }
+
+    /// Whether actions changing the repository are disabled
+    pub fn read_only(&self) -> bool {
+        self.blazingjj_read_only.unwrap_or(false)
+    }
+
+    /// Disable actions changing the repository, e.g. for the `--read-only` flag
+    pub fn enable_read_only(&mut self) {
+        self.blazingjj_read_only = Some(true);
+    }
+
+    /// What is hidden in the rendered output, for screenshots and recordings
+    pub fn presentation_mode(&self) -> PresentationMode {
+        self.blazingjj_presentation_mode.unwrap_or_default()
+    }
+
+    /// Hide emails unless more is hidden already, e.g. for the `--presentation` flag
+    pub fn enable_presentation_mode(&mut self) {
+        if self.presentation_mode() == PresentationMode::Off {
+            self.blazingjj_presentation_mode = Some(PresentationMode::Emails);
+        }
+    }
+
+    /// Shell command testing the changes before they are pushed
+    pub fn push_test_command(&self) -> Option<&str> {
+        self.blazingjj_push_test_command
+            .as_deref()
+            .filter(|command| !command.trim().is_empty())
+    }
+
+    /// Shell command printing the CI status of the commit in `BLAZINGJJ_COMMIT`
+    pub fn ci_status_command(&self) -> Option<&str> {
+        self.blazingjj_ci_status_command
+            .as_deref()
+            .filter(|command| !command.trim().is_empty())
+    }
+
+    /// Template of the URL of a commit on the forge, overriding the one of the detected forge
+    pub fn forge_commit_url(&self) -> Option<&str> {
+        self.blazingjj_forge_commit_url.as_deref()
+    }
+
+    /// Template of the reference copied to the clipboard, see [render_reference_template]
+    ///
+    /// [render_reference_template]: crate::commander::jj::render_reference_template
+    pub fn copy_reference_template(&self) -> &str {
+        self.blazingjj_copy_reference_template
+            .as_deref()
+            .unwrap_or("{change_id} \"{title}\"")
+    }
+
+    /// Template of the URL of a file at a commit on the forge
+    pub fn forge_file_url(&self) -> Option<&str> {
+        self.blazingjj_forge_file_url.as_deref()
+    }
+
+    /// Regexes of the markers listed by the todo scanner, e.g. `\bTODO\b`
+    pub fn todo_patterns(&self) -> Vec<String> {
+        self.blazingjj_todo_patterns.clone().unwrap_or_else(|| {
+            DEFAULT_TODO_PATTERNS
+                .iter()
+                .map(|pattern| pattern.to_string())
+                .collect()
+        })
+    }
+
+    /// Number of changed lines above which the diff of a change in the log is only rendered on
+    /// request. None if disabled with 0
+    pub fn large_diff_lines(&self) -> Option<usize> {
+        match self.blazingjj_large_diff_lines.unwrap_or(10_000) {
+            0 => None,
+            lines => Some(lines as usize),
+        }
+    }
+
+    /// `--config` values passed to every jj command
+    pub fn jj_config_overrides(&self) -> Vec<String> {
+        self.blazingjj_id_length
+            .iter()
+            .map(|id_length| {
+                // Used by the builtin templates for change and commit ids
+                format!(
+                    r#"template-aliases."format_short_id(id)"="{}""#,
+                    id_length.template("id")
+                )
+            })
+            .collect()
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct Env {
     pub config: Config,
+    /// Root of the workspace, found by jj from the directory blazingjj was started in
     pub root: String,
+    /// Name of the workspace if it isn't the default one
+    pub workspace: Option<String>,
     pub default_revset: Option<String>,
     pub jj_bin: String,
+    /// Problems found while validating the config, shown at startup
+    pub config_errors: Vec<String>,
+    /// Answers commands instead of running them, see [CommandRunner]
+    pub command_runner: Option<Arc<dyn CommandRunner>>,
+}
+
+/// Name of the workspace of the working copy at `root`, if it is a secondary workspace. Those
+/// are recognized without running jj by their `.jj/repo`, a file with the path of the store of
+/// the repository instead of the store itself.
+fn get_workspace_name(jj_bin: &str, root: &str) -> Option<String> {
+    if !Path::new(root).join(".jj").join("repo").is_file() {
+        return None;
+    }
+    let output = hide_console_window(&mut Command::new(jj_bin))
+        .args([
+            "log",
+            "--no-graph",
+            "--ignore-working-copy",
+            "-r",
+            "@",
+            "-T",
+            "working_copies",
+        ])
+        .args(get_output_args(false, true))
+        .current_dir(root)
+        .output()
+        .ok()?;
+    parse_workspace_name(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// First workspace of the `working_copies` of a commit, e.g. `feature` for `feature@`
+fn parse_workspace_name(working_copies: &str) -> Option<String> {
+    let name = working_copies.split_whitespace().next()?;
+    let name = name.strip_suffix('@').unwrap_or(name);
+    (name != "default").then(|| name.to_owned())
 }
 
 impl Env {
+    /// Environment running jj in `directory` without a repository or a config, e.g. to clone a
+    /// repository or to diagnose why [Env::new] failed
+    pub fn without_repository(directory: String, jj_bin: String) -> Env {
+        Env {
+            config: Config::default(),
+            root: directory,
+            workspace: None,
+            default_revset: None,
+            jj_bin,
+            config_errors: vec![],
+            command_runner: None,
+        }
+    }
+
     pub fn new(path: PathBuf, default_revset: Option<String>, jj_bin: String) -> Result<Env> {
         // Get jj repository root
-        let root_output = Command::new(&jj_bin)
+        let root_output = hide_console_window(&mut Command::new(&jj_bin))
             .arg("root")
             .args(get_output_args(false, true))
             .current_dir(&path)
@@ -141,10 +563,11 @@ impl Env {
             bail!("No jj repository found in {}", path.to_str().unwrap_or(""))
         }
         let root = String::from_utf8(root_output.stdout)?.remove_end_line();
+        let workspace = get_workspace_name(&jj_bin, &root);
 
         // Read/parse jj config
         let config_toml = String::from_utf8(
-            Command::new(&jj_bin)
+            hide_console_window(&mut Command::new(&jj_bin))
                 .arg("config")
                 .arg("list")
                 .arg("--template")
@@ -156,11 +579,15 @@ impl Env {
                 .stdout,
         )?;
         // Prior to https://github.com/martinvonz/jj/pull/3728, keys were not TOML-escaped.
-        let config = match toml::from_str::<Config>(&config_toml) {
+        let mut config_errors = vec![];
+        let config = match toml::from_str::<toml::Table>(&config_toml).and_then(|mut table| {
+            config_errors = validate::validate_config(&mut table);
+            table.try_into::<Config>()
+        }) {
             Ok(config) => config,
             Err(_) => {
                 let config_toml = String::from_utf8(
-                    Command::new(&jj_bin)
+                    hide_console_window(&mut Command::new(&jj_bin))
                         .arg("config")
                         .arg("list")
                         .args(get_output_args(false, true))
@@ -176,6 +603,14 @@ impl Env {
                             .blazingjj
                             .as_ref()
                             .and_then(|blazingjj| blazingjj.highlight_color),
+                        blazingjj_focus_color: config
+                            .blazingjj
+                            .as_ref()
+                            .and_then(|blazingjj| blazingjj.focus_color),
+                        blazingjj_highlight_revsets: config
+                            .blazingjj
+                            .as_ref()
+                            .and_then(|blazingjj| blazingjj.highlight_revsets.clone()),
                         blazingjj_diff_format: config
                             .blazingjj
                             .as_ref()
@@ -184,10 +619,34 @@ impl Env {
                             .blazingjj
                             .as_ref()
                             .and_then(|blazingjj| blazingjj.diff_tool.clone()),
+                        blazingjj_pager: config
+                            .blazingjj
+                            .as_ref()
+                            .and_then(|blazingjj| blazingjj.pager.clone()),
+                        blazingjj_diff_overrides: config
+                            .blazingjj
+                            .as_ref()
+                            .and_then(|blazingjj| blazingjj.diff_overrides.clone()),
                         blazingjj_bookmark_template: config
                             .blazingjj
                             .as_ref()
                             .and_then(|blazingjj| blazingjj.bookmark_prefix.clone()),
+                        blazingjj_bookmark_pattern: config
+                            .blazingjj
+                            .as_ref()
+                            .and_then(|blazingjj| blazingjj.bookmark_pattern.clone()),
+                        blazingjj_merge_template: config
+                            .blazingjj
+                            .as_ref()
+                            .and_then(|blazingjj| blazingjj.merge_template.clone()),
+                        blazingjj_new_work_base: config
+                            .blazingjj
+                            .as_ref()
+                            .and_then(|blazingjj| blazingjj.new_work_base.clone()),
+                        blazingjj_bookmark_description_template: config
+                            .blazingjj
+                            .as_ref()
+                            .and_then(|blazingjj| blazingjj.bookmark_description_template.clone()),
                         blazingjj_layout: config
                             .blazingjj
                             .as_ref()
@@ -200,6 +659,102 @@ impl Env {
                             .blazingjj
                             .as_ref()
                             .and_then(|blazingjj| blazingjj.keybinds.clone()),
+                        blazingjj_keybind_preset: config
+                            .blazingjj
+                            .as_ref()
+                            .and_then(|blazingjj| blazingjj.keybind_preset),
+                        blazingjj_auto_refresh_seconds: config
+                            .blazingjj
+                            .as_ref()
+                            .and_then(|blazingjj| blazingjj.auto_refresh_seconds),
+                        blazingjj_command_timeout_seconds: config
+                            .blazingjj
+                            .as_ref()
+                            .and_then(|blazingjj| blazingjj.command_timeout_seconds),
+                        blazingjj_hint_bar: config
+                            .blazingjj
+                            .as_ref()
+                            .and_then(|blazingjj| blazingjj.hint_bar),
+                        blazingjj_trunk_relation: config
+                            .blazingjj
+                            .as_ref()
+                            .and_then(|blazingjj| blazingjj.trunk_relation),
+                        blazingjj_hindsight: config
+                            .blazingjj
+                            .as_ref()
+                            .and_then(|blazingjj| blazingjj.hindsight),
+                        blazingjj_mouse: config
+                            .blazingjj
+                            .as_ref()
+                            .and_then(|blazingjj| blazingjj.mouse),
+                        blazingjj_startup_diagnostics: config
+                            .blazingjj
+                            .as_ref()
+                            .and_then(|blazingjj| blazingjj.startup_diagnostics),
+                        blazingjj_describe_generator: config
+                            .blazingjj
+                            .as_ref()
+                            .and_then(|blazingjj| blazingjj.describe_generator.clone()),
+                        blazingjj_repositories: config
+                            .blazingjj
+                            .as_ref()
+                            .and_then(|blazingjj| blazingjj.repositories.clone()),
+                        blazingjj_id_length: config
+                            .blazingjj
+                            .as_ref()
+                            .and_then(|blazingjj| blazingjj.id_length),
+                        blazingjj_hooks: config
+                            .blazingjj
+                            .as_ref()
+                            .and_then(|blazingjj| blazingjj.hooks.clone()),
+                        blazingjj_execution_mode: config
+                            .blazingjj
+                            .as_ref()
+                            .and_then(|blazingjj| blazingjj.execution_mode),
+                        blazingjj_read_only: config
+                            .blazingjj
+                            .as_ref()
+                            .and_then(|blazingjj| blazingjj.read_only),
+                        blazingjj_presentation_mode: config
+                            .blazingjj
+                            .as_ref()
+                            .and_then(|blazingjj| blazingjj.presentation_mode),
+                        blazingjj_push_test_command: config
+                            .blazingjj
+                            .as_ref()
+                            .and_then(|blazingjj| blazingjj.push_test_command.clone()),
+                        blazingjj_ci_status_command: config
+                            .blazingjj
+                            .as_ref()
+                            .and_then(|blazingjj| blazingjj.ci_status_command.clone()),
+                        blazingjj_forge_commit_url: config
+                            .blazingjj
+                            .as_ref()
+                            .and_then(|blazingjj| blazingjj.forge_commit_url.clone()),
+                        blazingjj_copy_reference_template: config
+                            .blazingjj
+                            .as_ref()
+                            .and_then(|blazingjj| blazingjj.copy_reference_template.clone()),
+                        blazingjj_forge_file_url: config
+                            .blazingjj
+                            .as_ref()
+                            .and_then(|blazingjj| blazingjj.forge_file_url.clone()),
+                        blazingjj_todo_patterns: config
+                            .blazingjj
+                            .as_ref()
+                            .and_then(|blazingjj| blazingjj.todo_patterns.clone()),
+                        blazingjj_large_diff_lines: config
+                            .blazingjj
+                            .as_ref()
+                            .and_then(|blazingjj| blazingjj.large_diff_lines),
+                        blazingjj_output_processors: config
+                            .blazingjj
+                            .as_ref()
+                            .and_then(|blazingjj| blazingjj.output_processors.clone()),
+                        blazingjj_favorites: config
+                            .blazingjj
+                            .as_ref()
+                            .and_then(|blazingjj| blazingjj.favorites.clone()),
                         ui_diff_format: config
                             .ui
                             .as_ref()
@@ -209,6 +764,8 @@ impl Env {
                                 .as_ref()
                                 .and_then(|diff| diff.tool.as_ref().map(|_| ()))
                         }),
+                        ui_log_word_wrap: config.ui.as_ref().and_then(|ui| ui.log_word_wrap),
+                        revsets_log: config.revsets.and_then(|revsets| revsets.log),
                         git_push_bookmark_template: config
                             .templates
                             .and_then(|templates| templates.git_push_bookmark),
@@ -218,26 +775,220 @@ impl Env {
 
         Ok(Env {
             root,
+            workspace,
             config,
             default_revset,
             jj_bin,
+            config_errors,
+            command_runner: None,
         })
     }
 }
 
-#[derive(Clone, Debug, Deserialize, Default, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Serialize, Default, PartialEq)]
 #[serde(rename_all = "kebab-case")]
 pub enum DiffFormat {
     #[default]
     ColorWords,
     Git,
     DiffTool(Option<String>),
-    // Unused
     Summary,
     Stat,
 }
 
+/// Color of the revisions of the log matching a revset, e.g. red for `conflicts()`
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct HighlightRevset {
+    pub revset: String,
+    pub color: Color,
+}
+
+/// Diff format for the files matching a pattern, e.g. a stat line for lockfiles
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct DiffOverride {
+    /// Path pattern relative to the repository root, with the kinds of jj's string patterns:
+    /// `glob:` (the default) or `exact:`. In globs `*` doesn't match `/`, `**` matches any number
+    /// of directories
+    pub pattern: String,
+    pub format: DiffFormat,
+}
+
+/// [DiffOverride] pattern split from its kind
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PathPattern<'a> {
+    Glob(&'a str),
+    Exact(&'a str),
+}
+
+impl DiffOverride {
+    /// The pattern without its kind prefix, like jj parses `kind:pattern`. Fails on kinds other
+    /// than `glob` and `exact`.
+    pub fn path_pattern(&self) -> Result<PathPattern<'_>, String> {
+        match self.pattern.split_once(':') {
+            Some(("glob", glob)) => Ok(PathPattern::Glob(glob)),
+            Some(("exact", path)) => Ok(PathPattern::Exact(path)),
+            Some((kind, _))
+                if !kind.is_empty()
+                    && kind
+                        .chars()
+                        .all(|char| char.is_ascii_alphanumeric() || char == '-' || char == '_') =>
+            {
+                Err(format!(
+                    "invalid pattern kind {kind:?} in {:?}, expected \"glob\" or \"exact\"",
+                    self.pattern
+                ))
+            }
+            _ => Ok(PathPattern::Glob(&self.pattern)),
+        }
+    }
+
+    pub fn matches(&self, path: &str) -> bool {
+        let path = slash_path(path);
+        match self.path_pattern() {
+            Ok(PathPattern::Glob(glob)) => glob_regex(glob).is_match(&path),
+            Ok(PathPattern::Exact(exact)) => slash_path(exact) == path,
+            Err(_) => false,
+        }
+    }
+}
+
+/// Regex rewriting the lines of the output of some commands before it is shown, e.g. to strip
+/// the progress lines of a chatty remote. Lines which the rewrite empties are removed.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct OutputProcessor {
+    /// Prefixes of the command lines the processor applies to, as shown in the command log, e.g.
+    /// `jj git push`. Applies to all commands if empty.
+    #[serde(default)]
+    pub commands: Vec<String>,
+    /// Matched against the lines without their colors
+    pub pattern: ProcessorPattern,
+    /// Replacement of the matches, with `$1` for the first group
+    #[serde(default)]
+    pub replacement: String,
+}
+
+/// Regex of an [OutputProcessor], compiled once when the config is read
+#[derive(Clone, Debug, Deserialize)]
+#[serde(try_from = "String")]
+pub struct ProcessorPattern(Regex);
+
+impl ProcessorPattern {
+    pub fn regex(&self) -> &Regex {
+        &self.0
+    }
+}
+
+impl TryFrom<String> for ProcessorPattern {
+    type Error = String;
+
+    fn try_from(pattern: String) -> Result<Self, Self::Error> {
+        Regex::new(&pattern)
+            .map(Self)
+            .map_err(|err| format!("invalid pattern {pattern:?}: {err}"))
+    }
+}
+
+impl PartialEq for ProcessorPattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_str() == other.0.as_str()
+    }
+}
+
+impl OutputProcessor {
+    /// Whether the processor applies to the output of the commands
+    pub fn applies_to(&self, command_lines: &[String]) -> bool {
+        self.commands.is_empty()
+            || command_lines.iter().any(|command_line| {
+                self.commands
+                    .iter()
+                    .any(|command| command_line.starts_with(command.as_str()))
+            })
+    }
+}
+
+/// Number of favorites, numbered from 1 so each has a digit key
+pub const MAX_FAVORITES: usize = 5;
+
+/// jj command of the favorites bar, e.g. to rebase onto trunk or push the stack. Usually set in
+/// the repo config (`jj config set --repo`), so each repository has the actions of its workflow.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct Favorite {
+    pub label: String,
+    /// Arguments of jj as on the command line, without the `jj` prefix
+    pub command: String,
+}
+
+/// Regex matching the same paths as the jj glob `pattern`: `*` and `?` don't match `/`, `**` as
+/// a whole path component matches any number of directories, `[...]` matches a character of a
+/// class and `\` escapes the next character
+fn glob_regex(pattern: &str) -> Regex {
+    let mut expression = String::from("^");
+    let mut chars = pattern.chars().peekable();
+    // Whether the next character starts a path component
+    let mut component_start = true;
+    while let Some(char) = chars.next() {
+        match char {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if !component_start || chars.peek().is_some_and(|next| *next != '/') {
+                    expression.push_str("[^/]*");
+                } else if chars.next_if_eq(&'/').is_some() {
+                    expression.push_str("(.*/)?");
+                    continue;
+                } else {
+                    expression.push_str(".*");
+                }
+            }
+            '*' => expression.push_str("[^/]*"),
+            '?' => expression.push_str("[^/]"),
+            '[' => {
+                let mut class = String::new();
+                let mut closed = false;
+                if chars.next_if_eq(&'!').is_some() {
+                    class.push('^');
+                }
+                // A `]` right after the opening bracket is part of the class
+                if let Some(bracket) = chars.next_if_eq(&']') {
+                    class.push_str(&regex::escape(bracket.encode_utf8(&mut [0; 4])));
+                }
+                for char in chars.by_ref() {
+                    match char {
+                        ']' => {
+                            closed = true;
+                            break;
+                        }
+                        '-' => class.push('-'),
+                        char => class.push_str(&regex::escape(char.encode_utf8(&mut [0; 4]))),
+                    }
+                }
+                if !closed {
+                    // jj rejects unclosed classes, match nothing like an invalid pattern
+                    expression = String::from("[^\\s\\S]");
+                    break;
+                }
+                expression.push_str(&format!("[[{class}]&&[^/]]"));
+            }
+            '\\' => {
+                if let Some(escaped) = chars.next() {
+                    expression.push_str(&regex::escape(escaped.encode_utf8(&mut [0; 4])));
+                }
+            }
+            // All other characters are escaped
+            char => expression.push_str(&regex::escape(char.encode_utf8(&mut [0; 4]))),
+        }
+        component_start = char == '/';
+    }
+    expression.push('$');
+    Regex::new(&expression).unwrap()
+}
+
 impl DiffFormat {
+    /// Whether the output depends on the width passed with `COLUMNS`, so it must be produced
+    /// again when the panel is resized
+    pub fn depends_on_width(&self) -> bool {
+        matches!(self, DiffFormat::DiffTool(_) | DiffFormat::Stat)
+    }
+
     pub fn get_next(&self, diff_tool: Option<Option<String>>) -> DiffFormat {
         match self {
             DiffFormat::ColorWords => DiffFormat::Git,
@@ -261,6 +1012,70 @@ pub enum JJLayout {
     Vertical,
 }
 
+/// How jj commands are run, see [batch][crate::commander::batch]
+#[derive(Clone, Debug, Deserialize, Default, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ExecutionMode {
+    /// Every query spawns jj, which snapshots the working copy
+    #[default]
+    Spawn,
+    /// Queries within one update share a single snapshot and identical queries run once
+    Batched,
+}
+
+/// What the presentation mode hides in the rendered output, see [anonymize][crate::anonymize]
+#[derive(Clone, Debug, Deserialize, Default, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum PresentationMode {
+    #[default]
+    Off,
+    /// Emails are replaced with placeholders
+    Emails,
+    /// Emails and the descriptions of the changes in the log are replaced with placeholders
+    EmailsAndDescriptions,
+}
+
+/// Displayed length of change and commit ids
+#[derive(Clone, Debug, Deserialize, Copy, PartialEq)]
+#[serde(try_from = "RawIdLength")]
+pub enum IdLength {
+    /// At least this many characters, more if needed to be unique
+    Fixed(usize),
+    /// Only the shortest unique prefix
+    Shortest,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawIdLength {
+    Fixed(usize),
+    Named(String),
+}
+
+impl TryFrom<RawIdLength> for IdLength {
+    type Error = String;
+
+    fn try_from(raw: RawIdLength) -> Result<Self, Self::Error> {
+        match raw {
+            RawIdLength::Fixed(length) => Ok(IdLength::Fixed(length)),
+            RawIdLength::Named(name) if name == "shortest" => Ok(IdLength::Shortest),
+            RawIdLength::Named(name) => Err(format!(
+                r#"invalid id length "{name}", expected a number or "shortest""#
+            )),
+        }
+    }
+}
+
+impl IdLength {
+    /// Template expression shortening the id `id`. The unique prefix is highlighted by jj.
+    pub fn template(&self, id: &str) -> String {
+        match self {
+            IdLength::Fixed(length) => format!("{id}.shortest({length})"),
+            IdLength::Shortest => format!("{id}.shortest()"),
+        }
+    }
+}
+
 // Impl into for JJLayout to ratatui's Direction
 impl From<JJLayout> for ratatui::layout::Direction {
     fn from(layout: JJLayout) -> Self {
@@ -0,0 +1,440 @@
+/*!
+Validation of the `blazingjj.*` keys of the flat `jj config list` table.
+
+Invalid entries are removed from the table and reported, so that a single
+typo doesn't prevent the rest of the config from loading.
+*/
+use std::str::FromStr;
+
+use ratatui::style::Color;
+use regex::Regex;
+use serde::de::DeserializeOwned;
+use toml::{Table, Value};
+
+use crate::{
+    commander::hooks::is_known_hook,
+    env::{
+        DiffFormat, DiffOverride, ExecutionMode, Favorite, HighlightRevset, IdLength, JJLayout,
+        MAX_FAVORITES, OutputProcessor, PresentationMode,
+    },
+    keybinds::{KeybindPreset, LogTabKeybindsConfig, Shortcut},
+};
+
+const PREFIX: &str = "blazingjj.";
+
+/// Validate the `blazingjj.*` keys of `table`, removing invalid ones.
+///
+/// The flat `blazingjj.keybinds.<tab>.<name>` and `blazingjj.hooks.<name>` keys are collected
+/// into nested `blazingjj.keybinds` and `blazingjj.hooks` tables. Returns one message per invalid
+/// entry.
+pub fn validate_config(table: &mut Table) -> Vec<String> {
+    let mut errors = vec![];
+    let mut keybinds = Table::new();
+    let mut hooks = Table::new();
+
+    let keys: Vec<String> = table
+        .keys()
+        .filter(|key| key.starts_with(PREFIX))
+        .cloned()
+        .collect();
+    for key in keys {
+        let Some(value) = table.remove(&key) else {
+            continue;
+        };
+        let name = &key[PREFIX.len()..];
+
+        if name == "keybinds" || name.starts_with("keybinds.") {
+            let path = name.split('.').skip(1).map(str::to_owned).collect();
+            for (path, value) in table_leaves(path, value) {
+                if let Err(message) = add_keybind(&mut keybinds, &path, value) {
+                    errors.push(format!("{PREFIX}keybinds.{}: {message}", path.join(".")));
+                }
+            }
+            continue;
+        }
+
+        if name == "hooks" || name.starts_with("hooks.") {
+            let path = name.split('.').skip(1).map(str::to_owned).collect();
+            for (path, value) in table_leaves(path, value) {
+                if let Err(message) = add_hook(&mut hooks, &path, value) {
+                    errors.push(format!("{PREFIX}hooks.{}: {message}", path.join(".")));
+                }
+            }
+            continue;
+        }
+
+        match check_value(name, &value) {
+            Ok(()) => {
+                table.insert(key, value);
+            }
+            Err(message) => errors.push(format!("{key}: {message}")),
+        }
+    }
+
+    if !keybinds.is_empty() {
+        table.insert(format!("{PREFIX}keybinds"), Value::Table(keybinds));
+    }
+    if !hooks.is_empty() {
+        table.insert(format!("{PREFIX}hooks"), Value::Table(hooks));
+    }
+
+    errors
+}
+
+fn expect<T: DeserializeOwned>(value: &Value) -> Result<T, String> {
+    value
+        .clone()
+        .try_into::<T>()
+        .map_err(|err| err.message().to_owned())
+}
+
+fn check_value(name: &str, value: &Value) -> Result<(), String> {
+    match name {
+        "highlight-color" | "focus-color" => expect::<Color>(value).map(|_| ()).map_err(|_| {
+            format!(
+                "invalid color {value}, expected a color name like \"blue\" or a hex color like \"#323264\""
+            )
+        }),
+        "diff-format" => expect::<DiffFormat>(value).map(|_| ()),
+        "diff-overrides" => expect::<Vec<DiffOverride>>(value)?
+            .iter()
+            .try_for_each(|diff_override| diff_override.path_pattern().map(|_| ())),
+        "highlight-revsets" => expect::<Vec<HighlightRevset>>(value).map(|_| ()),
+        "diff-tool" | "bookmark-template" | "bookmark-pattern" | "merge-template"
+        | "new-work-base" | "bookmark-description-template" | "describe-generator" | "pager"
+        | "push-test-command" | "forge-commit-url" | "forge-file-url"
+        | "copy-reference-template" | "ci-status-command" => {
+            expect::<String>(value).map(|_| ())
+        }
+        "layout" => expect::<JJLayout>(value).map(|_| ()),
+        "layout-percent" => match expect::<u16>(value) {
+            Ok(percent) if percent <= 100 => Ok(()),
+            _ => Err(format!(
+                "invalid percentage {value}, expected a number between 0 and 100"
+            )),
+        },
+        "auto-refresh-seconds" | "command-timeout-seconds" | "large-diff-lines" => {
+            expect::<u64>(value).map(|_| ())
+        }
+        "hint-bar" | "trunk-relation" | "hindsight" | "mouse" | "startup-diagnostics"
+        | "read-only" => expect::<bool>(value).map(|_| ()),
+        "repositories" | "saved-advance-branches" => expect::<Vec<String>>(value).map(|_| ()),
+        "todo-patterns" => expect::<Vec<String>>(value)?
+            .iter()
+            .try_for_each(|pattern| match Regex::new(pattern) {
+                Ok(_) => Ok(()),
+                Err(err) => Err(format!("invalid pattern {pattern:?}: {err}")),
+            }),
+        // The patterns are compiled while deserializing
+        "output-processors" => expect::<Vec<OutputProcessor>>(value).map(|_| ()),
+        "favorites" => {
+            let favorites = expect::<Vec<Favorite>>(value)?;
+            if favorites.len() > MAX_FAVORITES {
+                return Err(format!(
+                    "{} favorites, expected at most {MAX_FAVORITES}",
+                    favorites.len()
+                ));
+            }
+            favorites
+                .iter()
+                .try_for_each(|favorite| match shell_words::split(&favorite.command) {
+                    Ok(_) => Ok(()),
+                    Err(err) => Err(format!("invalid command {:?}: {err}", favorite.command)),
+                })
+        }
+        "id-length" => expect::<IdLength>(value).map(|_| ()),
+        "execution-mode" => expect::<ExecutionMode>(value).map(|_| ()),
+        "keybind-preset" => expect::<KeybindPreset>(value).map(|_| ()),
+        "presentation-mode" => expect::<PresentationMode>(value).map(|_| ()),
+        _ => Err("unknown key".to_owned()),
+    }
+}
+
+/// Flatten nested tables into `(path, value)` leaves
+fn table_leaves(path: Vec<String>, value: Value) -> Vec<(Vec<String>, Value)> {
+    match value {
+        Value::Table(table) => table
+            .into_iter()
+            .flat_map(|(key, value)| {
+                let mut path = path.clone();
+                path.push(key);
+                table_leaves(path, value)
+            })
+            .collect(),
+        value => vec![(path, value)],
+    }
+}
+
+fn add_keybind(keybinds: &mut Table, path: &[String], value: Value) -> Result<(), String> {
+    let [tab, name] = path else {
+        return Err("unknown key, expected `keybinds.<tab>.<name>`".to_owned());
+    };
+    if tab != "log_tab" {
+        return Err(format!("unknown tab `{tab}`, expected `log_tab`"));
+    }
+    if !LogTabKeybindsConfig::is_known(name) {
+        return Err(format!("unknown keybind `{name}`"));
+    }
+    check_keybind(&value)?;
+
+    if let Value::Table(tab_keybinds) = keybinds
+        .entry(tab.clone())
+        .or_insert_with(|| Value::Table(Table::new()))
+    {
+        tab_keybinds.insert(name.clone(), value);
+    }
+    Ok(())
+}
+
+fn add_hook(hooks: &mut Table, path: &[String], value: Value) -> Result<(), String> {
+    let [name] = path else {
+        return Err("unknown key, expected `hooks.<name>`".to_owned());
+    };
+    if !is_known_hook(name) {
+        return Err(format!(
+            "unknown hook `{name}`, expected `pre-<action>` or `post-<action>`"
+        ));
+    }
+    expect::<String>(&value)?;
+
+    hooks.insert(name.clone(), value);
+    Ok(())
+}
+
+fn check_keybind(value: &Value) -> Result<(), String> {
+    let check_shortcut = |shortcut: &str| {
+        Shortcut::from_str(shortcut)
+            .map(|_| ())
+            .map_err(|err| format!("invalid key \"{shortcut}\": {err}"))
+    };
+    match value {
+        Value::Boolean(_) => Ok(()),
+        Value::String(shortcut) => check_shortcut(shortcut),
+        Value::Array(shortcuts) if shortcuts.iter().all(Value::is_str) => shortcuts
+            .iter()
+            .filter_map(Value::as_str)
+            .try_for_each(check_shortcut),
+        _ => Err(format!(
+            "invalid keybind {value}, expected a key like \"ctrl+s\", a list of keys or a boolean"
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::env::Config;
+
+    fn validate(config_toml: &str) -> (Table, Vec<String>) {
+        let mut table = toml::from_str::<Table>(config_toml).unwrap();
+        let errors = validate_config(&mut table);
+        (table, errors)
+    }
+
+    #[test]
+    fn valid_config() {
+        let (table, errors) = validate(
+            r##"
+"blazingjj.highlight-color" = "#323264"
+"blazingjj.layout" = "vertical"
+"blazingjj.layout-percent" = 30
+"blazingjj.hint-bar" = false
+"blazingjj.hindsight" = true
+"blazingjj.mouse" = false
+"blazingjj.new-work-base" = "main@origin"
+"blazingjj.id-length" = "shortest"
+"blazingjj.execution-mode" = "batched"
+"blazingjj.keybind-preset" = "lazygit"
+"blazingjj.presentation-mode" = "emails-and-descriptions"
+"blazingjj.diff-overrides" = [{ pattern = "**/Cargo.lock", format = "stat" }, { pattern = "glob:gen/*", format = "summary" }, { pattern = "exact:docs/api.md", format = "summary" }]
+"blazingjj.highlight-revsets" = [{ revset = "conflicts()", color = "red" }, { revset = "mine()", color = "#00c8c8" }]
+"blazingjj.keybinds.log_tab.save" = "ctrl+s"
+"blazingjj.keybinds.log_tab.scroll-down" = ["j", "down"]
+"blazingjj.keybinds.log_tab.abandon" = false
+"blazingjj.hooks.pre-push" = "make check"
+"blazingjj.push-test-command" = "cargo test"
+"blazingjj.ci-status-command" = "ci-status $BLAZINGJJ_COMMIT"
+"blazingjj.forge-commit-url" = "https://git.example.com/{commit}"
+"blazingjj.copy-reference-template" = "[{title}]({url})"
+"blazingjj.todo-patterns" = ["\\bHACK\\b", "NOCOMMIT"]
+"blazingjj.large-diff-lines" = 0
+"blazingjj.output-processors" = [{ commands = ["jj git push"], pattern = "^remote: Counting.*" }]
+"blazingjj.favorites" = [{ label = "rebase onto trunk", command = "rebase -d 'trunk()'" }]
+"ui.diff.format" = "git"
+"##,
+        );
+        assert_eq!(errors, Vec::<String>::new());
+
+        let config = table.try_into::<Config>().unwrap();
+        assert_eq!(config.layout_percent(), 30);
+        assert!(!config.hint_bar());
+        assert_eq!(config.execution_mode(), ExecutionMode::Batched);
+        assert_eq!(config.keybind_preset(), KeybindPreset::Lazygit);
+        assert_eq!(config.push_test_command(), Some("cargo test"));
+        assert_eq!(
+            config.ci_status_command(),
+            Some("ci-status $BLAZINGJJ_COMMIT")
+        );
+        assert_eq!(
+            config.forge_commit_url(),
+            Some("https://git.example.com/{commit}")
+        );
+        assert_eq!(config.copy_reference_template(), "[{title}]({url})");
+        assert_eq!(config.todo_patterns(), [r"\bHACK\b", "NOCOMMIT"]);
+        assert_eq!(config.large_diff_lines(), None);
+        assert_eq!(
+            config.output_processors(),
+            [OutputProcessor {
+                commands: vec!["jj git push".to_owned()],
+                pattern: "^remote: Counting.*".to_owned().try_into().unwrap(),
+                replacement: String::new(),
+            }]
+        );
+        assert_eq!(
+            config.favorites(),
+            [Favorite {
+                label: "rebase onto trunk".to_owned(),
+                command: "rebase -d 'trunk()'".to_owned(),
+            }]
+        );
+        assert_eq!(
+            config.presentation_mode(),
+            PresentationMode::EmailsAndDescriptions
+        );
+        assert_eq!(
+            config.diff_override("Cargo.lock").map(|o| &o.format),
+            Some(&DiffFormat::Stat)
+        );
+        assert_eq!(
+            config.diff_override("sub/Cargo.lock").map(|o| &o.format),
+            Some(&DiffFormat::Stat)
+        );
+        assert_eq!(
+            config.diff_override("gen/api.rs").map(|o| &o.format),
+            Some(&DiffFormat::Summary)
+        );
+        assert_eq!(config.diff_override("gen/sub/api.rs"), None);
+        assert_eq!(
+            config.diff_override("docs/api.md").map(|o| &o.format),
+            Some(&DiffFormat::Summary)
+        );
+        assert_eq!(config.diff_override("docs/api.mdx"), None);
+        assert_eq!(config.diff_override("Cargo.lock.bak"), None);
+        assert_eq!(
+            config.highlight_revsets(),
+            [
+                HighlightRevset {
+                    revset: "conflicts()".to_owned(),
+                    color: Color::Red,
+                },
+                HighlightRevset {
+                    revset: "mine()".to_owned(),
+                    color: Color::Rgb(0, 200, 200),
+                },
+            ]
+        );
+        assert_eq!(
+            config.jj_config_overrides(),
+            vec![r#"template-aliases."format_short_id(id)"="id.shortest()""#]
+        );
+        let log_tab = config.keybinds().unwrap().log_tab.as_ref().unwrap();
+        assert!(log_tab.save.is_some());
+        assert!(log_tab.scroll_down.is_some());
+        assert!(log_tab.abandon.is_some());
+        assert_eq!(config.hook("pre-push"), Some("make check"));
+    }
+
+    #[test]
+    fn invalid_entries_are_reported_and_removed() {
+        let (table, errors) = validate(
+            r#"
+"blazingjj.highlight-colour" = "blue"
+"blazingjj.highlight-color" = "blurple"
+"blazingjj.layout" = "diagonal"
+"blazingjj.layout-percent" = 150
+"blazingjj.hint-bar" = "yes"
+"blazingjj.id-length" = "longest"
+"blazingjj.hooks.pre-rebase" = "true"
+"blazingjj.keybinds.log_tab.save" = "qq"
+"blazingjj.keybinds.log_tab.sav" = "ctrl+s"
+"blazingjj.keybinds.files_tab.save" = "ctrl+s"
+"blazingjj.keybinds.log_tab.push" = ["p", "f99999"]
+"blazingjj.keybinds.log_tab.cancel" = "esc"
+"blazingjj.keybinds.log_tab.refresh" = "shift+1"
+"blazingjj.todo-patterns" = "TODO"
+"blazingjj.favorites" = [{ label = "push", command = "git push -b 'stack" }]
+"blazingjj.diff-overrides" = [{ pattern = "regex:lock$", format = "stat" }]
+"blazingjj.output-processors" = [{ pattern = "issue (\\d+" }]
+"#,
+        );
+        assert_eq!(
+            errors,
+            vec![
+                r#"blazingjj.diff-overrides: invalid pattern kind "regex" in "regex:lock$", expected "glob" or "exact""#,
+                r#"blazingjj.favorites: invalid command "git push -b 'stack": missing closing quote"#,
+                r##"blazingjj.highlight-color: invalid color "blurple", expected a color name like "blue" or a hex color like "#323264""##,
+                "blazingjj.highlight-colour: unknown key",
+                "blazingjj.hint-bar: invalid type: string \"yes\", expected a boolean",
+                "blazingjj.hooks.pre-rebase: unknown hook `pre-rebase`, expected `pre-<action>` or `post-<action>`",
+                r#"blazingjj.id-length: invalid id length "longest", expected a number or "shortest""#,
+                "blazingjj.keybinds.files_tab.save: unknown tab `files_tab`, expected `log_tab`",
+                "blazingjj.keybinds.log_tab.push: invalid key \"f99999\": invalid number after f",
+                "blazingjj.keybinds.log_tab.refresh: invalid key \"shift+1\": shift can't be combined with a digit or symbol, bind the symbol it types instead",
+                "blazingjj.keybinds.log_tab.sav: unknown keybind `sav`",
+                "blazingjj.keybinds.log_tab.save: invalid key \"qq\": no key specified",
+                "blazingjj.layout: unknown variant `diagonal`, expected `horizontal` or `vertical`",
+                "blazingjj.layout-percent: invalid percentage 150, expected a number between 0 and 100",
+                "blazingjj.output-processors: invalid pattern \"issue (\\\\d+\": regex parse error:\n    issue (\\d+\n          ^\nerror: unclosed group",
+                "blazingjj.todo-patterns: invalid type: string \"TODO\", expected a sequence",
+            ]
+        );
+
+        let config = table.try_into::<Config>().unwrap();
+        let log_tab = config.keybinds().unwrap().log_tab.as_ref().unwrap();
+        assert!(log_tab.cancel.is_some());
+        assert!(log_tab.save.is_none());
+    }
+
+    #[test]
+    fn keybinds_table() {
+        let (table, errors) = validate(
+            r#"
+"blazingjj.keybinds" = { log_tab = { save = "ctrl+s", unknown = "x" } }
+"#,
+        );
+        assert_eq!(
+            errors,
+            vec!["blazingjj.keybinds.log_tab.unknown: unknown keybind `unknown`"]
+        );
+
+        let config = table.try_into::<Config>().unwrap();
+        let log_tab = config.keybinds().unwrap().log_tab.as_ref().unwrap();
+        assert!(log_tab.save.is_some());
+    }
+
+    #[test]
+    fn diff_override_patterns() {
+        let matches = |pattern: &str, path: &str| {
+            DiffOverride {
+                pattern: pattern.to_owned(),
+                format: DiffFormat::Stat,
+            }
+            .matches(path)
+        };
+        assert!(matches("*.lock", "Cargo.lock"));
+        assert!(!matches("*.lock", "sub/Cargo.lock"));
+        assert!(matches("**/*.lock", "sub/dir/Cargo.lock"));
+        assert!(matches("gen/**", "gen/sub/api.rs"));
+        assert!(matches("gen/**/api.rs", "gen/api.rs"));
+        assert!(!matches("gen**", "gen/api.rs"));
+        assert!(matches("file?.txt", "file1.txt"));
+        assert!(!matches("file?.txt", "file/.txt"));
+        assert!(matches("file[0-9].txt", "file7.txt"));
+        assert!(!matches("file[!0-9].txt", "file7.txt"));
+        assert!(matches(r"glob:\*.txt", "*.txt"));
+        assert!(!matches(r"glob:\*.txt", "a.txt"));
+        assert!(matches("exact:gen/*", "gen/*"));
+        assert!(!matches("exact:gen/*", "gen/api.rs"));
+        assert!(!matches("regex:.*", "Cargo.lock"));
+    }
+}
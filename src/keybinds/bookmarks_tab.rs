@@ -0,0 +1,158 @@
+use std::str::FromStr;
+
+use ratatui::crossterm::event::KeyEvent;
+
+use crate::{make_keybinds_help, set_keybinds};
+
+use super::{Shortcut, enabled_hints, keybinds_store::KeybindsStore};
+
+#[derive(Debug)]
+pub struct BookmarksTabKeybinds {
+    keys: KeybindsStore<BookmarksTabEvent>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum BookmarksTabEvent {
+    ScrollDown,
+    ScrollUp,
+    ScrollDownHalf,
+    ScrollUpHalf,
+
+    Refresh,
+    ToggleDiffFormat,
+    ToggleShowAll,
+    ViewInLog,
+
+    Create,
+    Rename,
+    Delete,
+    Forget,
+    Track,
+    Untrack,
+    Fetch,
+    FetchGlob,
+    CreateNew { describe: bool },
+    EditChange { ignore_immutable: bool },
+
+    OpenHelp,
+
+    Unbound,
+}
+
+impl BookmarksTabEvent {
+    /// Whether the action changes the repository, which is disabled in read-only mode
+    pub fn is_mutating(&self) -> bool {
+        matches!(
+            self,
+            BookmarksTabEvent::Create
+                | BookmarksTabEvent::Rename
+                | BookmarksTabEvent::Delete
+                | BookmarksTabEvent::Forget
+                | BookmarksTabEvent::Track
+                | BookmarksTabEvent::Untrack
+                | BookmarksTabEvent::Fetch
+                | BookmarksTabEvent::FetchGlob
+                | BookmarksTabEvent::CreateNew { .. }
+                | BookmarksTabEvent::EditChange { .. }
+        )
+    }
+}
+
+impl Default for BookmarksTabKeybinds {
+    fn default() -> Self {
+        let mut keys = KeybindsStore::<BookmarksTabEvent>::default();
+        set_keybinds!(
+            keys,
+            BookmarksTabEvent::ScrollDown => "j",
+            BookmarksTabEvent::ScrollDown => "down",
+            BookmarksTabEvent::ScrollUp => "k",
+            BookmarksTabEvent::ScrollUp => "up",
+            BookmarksTabEvent::ScrollDownHalf => "shift+j",
+            BookmarksTabEvent::ScrollUpHalf => "shift+k",
+            BookmarksTabEvent::Refresh => "shift+r",
+            BookmarksTabEvent::Refresh => "f5",
+            BookmarksTabEvent::ToggleDiffFormat => "w",
+            BookmarksTabEvent::ToggleShowAll => "a",
+            BookmarksTabEvent::ViewInLog => "enter",
+            BookmarksTabEvent::Create => "c",
+            BookmarksTabEvent::Rename => "r",
+            BookmarksTabEvent::Delete => "d",
+            BookmarksTabEvent::Forget => "f",
+            BookmarksTabEvent::Track => "t",
+            BookmarksTabEvent::Untrack => "shift+t",
+            BookmarksTabEvent::Fetch => "shift+f",
+            BookmarksTabEvent::FetchGlob => "shift+g",
+            BookmarksTabEvent::CreateNew { describe: false } => "n",
+            BookmarksTabEvent::CreateNew { describe: true } => "shift+n",
+            BookmarksTabEvent::EditChange { ignore_immutable: false } => "e",
+            BookmarksTabEvent::EditChange { ignore_immutable: true } => "shift+e",
+            BookmarksTabEvent::OpenHelp => "?",
+        );
+
+        Self { keys }
+    }
+}
+
+impl BookmarksTabKeybinds {
+    pub fn match_event(&self, event: KeyEvent) -> BookmarksTabEvent {
+        if let Some(action) = self.keys.match_event(event) {
+            action
+        } else {
+            BookmarksTabEvent::Unbound
+        }
+    }
+    /// Shortcuts of the actions changing the repository, see [BookmarksTabEvent::is_mutating]
+    pub fn mutating_shortcuts(&self) -> Vec<String> {
+        self.keys
+            .get_shortcuts_where(BookmarksTabEvent::is_mutating)
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+    pub fn make_main_panel_help(&self) -> Vec<(String, String)> {
+        make_keybinds_help!(
+            self.keys,
+            BookmarksTabEvent::ScrollDown => "scroll down",
+            BookmarksTabEvent::ScrollUp => "scroll up",
+            BookmarksTabEvent::ScrollDownHalf => "scroll down by ½ page",
+            BookmarksTabEvent::ScrollUpHalf => "scroll up by ½ page",
+            BookmarksTabEvent::ToggleShowAll => "show all remotes",
+            BookmarksTabEvent::Create => "create bookmark",
+            BookmarksTabEvent::Rename => "rename bookmark",
+            BookmarksTabEvent::Delete => "delete bookmark",
+            BookmarksTabEvent::Forget => "forget bookmark",
+            BookmarksTabEvent::Track => "track bookmark",
+            BookmarksTabEvent::Untrack => "untrack bookmark",
+            BookmarksTabEvent::Fetch => "fetch bookmark",
+            BookmarksTabEvent::FetchGlob => "fetch bookmarks matching a glob",
+            BookmarksTabEvent::ViewInLog => "view in log",
+            BookmarksTabEvent::CreateNew { describe: false } => "new from bookmark",
+            BookmarksTabEvent::CreateNew { describe: true } => "new and describe",
+            BookmarksTabEvent::EditChange { ignore_immutable: false } => "edit bookmark",
+            BookmarksTabEvent::EditChange { ignore_immutable: true } => "edit bookmark ignoring immutability",
+        )
+    }
+    /// Hints for the hint bar
+    pub fn make_hints(&self) -> Vec<(String, String)> {
+        enabled_hints(make_keybinds_help!(
+            self.keys,
+            BookmarksTabEvent::Create => "create",
+            BookmarksTabEvent::Rename => "rename",
+            BookmarksTabEvent::Delete => "delete",
+            BookmarksTabEvent::Forget => "forget",
+            BookmarksTabEvent::Fetch => "fetch",
+            BookmarksTabEvent::FetchGlob => "fetch glob",
+            BookmarksTabEvent::ViewInLog => "view in log",
+            BookmarksTabEvent::OpenHelp => "help",
+        ))
+    }
+    /// Hints for the hint bar in read-only mode, without the actions changing the repository
+    pub fn make_read_only_hints(&self) -> Vec<(String, String)> {
+        enabled_hints(make_keybinds_help!(
+            self.keys,
+            BookmarksTabEvent::ToggleShowAll => "show all",
+            BookmarksTabEvent::ViewInLog => "view in log",
+            BookmarksTabEvent::OpenHelp => "help",
+        ))
+    }
+}
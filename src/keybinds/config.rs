@@ -5,7 +5,7 @@ pub struct KeybindsConfig {
     pub log_tab: Option<LogTabKeybindsConfig>,
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
 #[serde(untagged)]
 pub enum Keybind {
     Single(Shortcut),
@@ -13,11 +13,12 @@ pub enum Keybind {
     Enable(bool),
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, serde::Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct LogTabKeybindsConfig {
     pub save: Option<Keybind>,
     pub cancel: Option<Keybind>,
+    pub generate_description: Option<Keybind>,
 
     pub close_popup: Option<Keybind>,
 
@@ -27,19 +28,47 @@ pub struct LogTabKeybindsConfig {
     pub scroll_up_half: Option<Keybind>,
 
     pub focus_current: Option<Keybind>,
+    pub toggle_follow: Option<Keybind>,
+    pub jump_back: Option<Keybind>,
+    pub jump_forward: Option<Keybind>,
     pub toggle_diff_format: Option<Keybind>,
 
     pub refresh: Option<Keybind>,
     pub create_new: Option<Keybind>,
     pub create_new_describe: Option<Keybind>,
+    pub start_new_work: Option<Keybind>,
     pub squash: Option<Keybind>,
     pub squash_ignore_immutable: Option<Keybind>,
+    pub amend: Option<Keybind>,
     pub edit_change: Option<Keybind>,
     pub edit_change_ignore_immutable: Option<Keybind>,
     pub abandon: Option<Keybind>,
     pub describe: Option<Keybind>,
+    pub describe_from_bookmarks: Option<Keybind>,
     pub edit_revset: Option<Keybind>,
+    pub clear_path_filter: Option<Keybind>,
+    pub expand_elided: Option<Keybind>,
+    pub show_conflicts: Option<Keybind>,
+    pub toggle_mine_filter: Option<Keybind>,
+    pub toggle_unmerged_filter: Option<Keybind>,
+    pub toggle_first_parent_filter: Option<Keybind>,
+    pub cycle_log_density: Option<Keybind>,
+    pub toggle_minimap: Option<Keybind>,
+    pub toggle_trunk_relation: Option<Keybind>,
+    pub mark_seen: Option<Keybind>,
+    pub open_in_pager: Option<Keybind>,
+    pub open_in_browser: Option<Keybind>,
+    pub preview_template: Option<Keybind>,
+    pub show_stats: Option<Keybind>,
+    pub show_todos: Option<Keybind>,
+    pub show_changed_files: Option<Keybind>,
+    pub review_range: Option<Keybind>,
+    pub copy_reference: Option<Keybind>,
+    pub reorder: Option<Keybind>,
+    pub toggle_mark: Option<Keybind>,
     pub set_bookmark: Option<Keybind>,
+    pub bookmark_stack: Option<Keybind>,
+    pub view_bookmark: Option<Keybind>,
     pub open_files: Option<Keybind>,
     pub rebase: Option<Keybind>,
 
@@ -47,8 +76,23 @@ pub struct LogTabKeybindsConfig {
     pub push_new: Option<Keybind>,
     pub push_all: Option<Keybind>,
     pub push_all_new: Option<Keybind>,
+    pub push_dry_run: Option<Keybind>,
+    pub push_all_dry_run: Option<Keybind>,
     pub fetch: Option<Keybind>,
     pub fetch_all: Option<Keybind>,
+    pub fetch_dry_run: Option<Keybind>,
+    pub fetch_all_dry_run: Option<Keybind>,
 
     pub open_help: Option<Keybind>,
 }
+
+impl LogTabKeybindsConfig {
+    /// Whether `name` is a configurable log tab keybind
+    pub fn is_known(name: &str) -> bool {
+        let mut table = toml::Table::new();
+        table.insert(name.to_owned(), toml::Value::Boolean(false));
+        table
+            .try_into::<Self>()
+            .is_ok_and(|config| config != Self::default())
+    }
+}
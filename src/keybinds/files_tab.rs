@@ -0,0 +1,214 @@
+use std::str::FromStr;
+
+use ratatui::crossterm::event::KeyEvent;
+
+use crate::{make_keybinds_help, set_keybinds};
+
+use super::{Shortcut, enabled_hints, keybinds_store::KeybindsStore};
+
+#[derive(Debug)]
+pub struct FilesTabKeybinds {
+    keys: KeybindsStore<FilesTabEvent>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum FilesTabEvent {
+    ScrollDown,
+    ScrollUp,
+    ScrollDownHalf,
+    ScrollUpHalf,
+
+    FocusCurrent,
+    Refresh,
+    ToggleDiffFormat,
+    ToggleSortByChurn,
+    ToggleLinkedDiff,
+    ShowFullDiff,
+
+    Untrack,
+    Track,
+    Ignore,
+    Restore,
+    ToggleMark,
+    Extract,
+    MoveToParent,
+    MoveToChild,
+    MoveToNewChild,
+    Resolve,
+
+    ToggleViewed,
+    ToggleReview,
+    NextReviewChange,
+    PreviousReviewChange,
+    ViewFileLog,
+    ViewDirectoryLog,
+    OpenInPager,
+    OpenInBrowser,
+
+    OpenHelp,
+
+    Unbound,
+}
+
+impl FilesTabEvent {
+    /// Whether the action changes the repository, which is disabled in read-only mode
+    pub fn is_mutating(&self) -> bool {
+        matches!(
+            self,
+            FilesTabEvent::Untrack
+                | FilesTabEvent::Track
+                | FilesTabEvent::Ignore
+                | FilesTabEvent::Restore
+                | FilesTabEvent::Extract
+                | FilesTabEvent::MoveToParent
+                | FilesTabEvent::MoveToChild
+                | FilesTabEvent::MoveToNewChild
+                | FilesTabEvent::Resolve
+        )
+    }
+}
+
+impl Default for FilesTabKeybinds {
+    fn default() -> Self {
+        let mut keys = KeybindsStore::<FilesTabEvent>::default();
+        set_keybinds!(
+            keys,
+            FilesTabEvent::ScrollDown => "j",
+            FilesTabEvent::ScrollDown => "down",
+            FilesTabEvent::ScrollUp => "k",
+            FilesTabEvent::ScrollUp => "up",
+            FilesTabEvent::ScrollDownHalf => "shift+j",
+            FilesTabEvent::ScrollUpHalf => "shift+k",
+            FilesTabEvent::FocusCurrent => "@",
+            FilesTabEvent::Refresh => "shift+r",
+            FilesTabEvent::Refresh => "f5",
+            FilesTabEvent::ToggleDiffFormat => "w",
+            FilesTabEvent::ToggleSortByChurn => "s",
+            FilesTabEvent::ToggleLinkedDiff => "d",
+            FilesTabEvent::ShowFullDiff => "enter",
+            FilesTabEvent::Untrack => "x",
+            FilesTabEvent::Track => "t",
+            FilesTabEvent::Ignore => "i",
+            FilesTabEvent::Restore => "r",
+            FilesTabEvent::ToggleMark => "space",
+            FilesTabEvent::Extract => "e",
+            FilesTabEvent::MoveToParent => "p",
+            FilesTabEvent::MoveToChild => "c",
+            FilesTabEvent::MoveToNewChild => "n",
+            FilesTabEvent::Resolve => "m",
+            FilesTabEvent::ToggleViewed => "v",
+            FilesTabEvent::ToggleReview => "shift+v",
+            FilesTabEvent::NextReviewChange => "shift+n",
+            FilesTabEvent::PreviousReviewChange => "shift+p",
+            // Not `l`, which changes the tab
+            FilesTabEvent::ViewFileLog => "f",
+            FilesTabEvent::ViewDirectoryLog => "shift+l",
+            FilesTabEvent::OpenInPager => "|",
+            // Not `O`, which opens the operation log
+            FilesTabEvent::OpenInBrowser => "g",
+            FilesTabEvent::OpenHelp => "?",
+        );
+
+        Self { keys }
+    }
+}
+
+impl FilesTabKeybinds {
+    pub fn match_event(&self, event: KeyEvent) -> FilesTabEvent {
+        if let Some(action) = self.keys.match_event(event) {
+            action
+        } else {
+            FilesTabEvent::Unbound
+        }
+    }
+    /// Shortcuts of the actions changing the repository, see [FilesTabEvent::is_mutating]
+    pub fn mutating_shortcuts(&self) -> Vec<String> {
+        self.keys
+            .get_shortcuts_where(FilesTabEvent::is_mutating)
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+    pub fn make_main_panel_help(&self) -> Vec<(String, String)> {
+        make_keybinds_help!(
+            self.keys,
+            FilesTabEvent::ScrollDown => "scroll down",
+            FilesTabEvent::ScrollUp => "scroll up",
+            FilesTabEvent::ScrollDownHalf => "scroll down by ½ page",
+            FilesTabEvent::ScrollUpHalf => "scroll up by ½ page",
+            FilesTabEvent::ToggleSortByChurn => "toggle sorting by changed lines",
+            FilesTabEvent::ToggleLinkedDiff => "toggle the diff of all files, following the selection",
+            FilesTabEvent::Untrack => "untrack file",
+            FilesTabEvent::Restore => "restore file",
+            FilesTabEvent::Track => "track untracked file",
+            FilesTabEvent::Ignore => "add untracked file to .gitignore",
+            FilesTabEvent::ToggleMark => "mark file for extract",
+            FilesTabEvent::MoveToParent => "move marked or selected files into the parent",
+            FilesTabEvent::MoveToChild => "move marked or selected files into the child",
+            FilesTabEvent::MoveToNewChild => "move marked or selected files into a new child",
+            FilesTabEvent::Extract => "extract marked files into new sibling change",
+            FilesTabEvent::Resolve => "resolve conflicts of file with a merge tool",
+            FilesTabEvent::ToggleViewed => "mark file as viewed (review)",
+            FilesTabEvent::ToggleReview => "toggle review mode",
+            FilesTabEvent::NextReviewChange => "next change of the reviewed range",
+            FilesTabEvent::PreviousReviewChange => "previous change of the reviewed range",
+            FilesTabEvent::FocusCurrent => "view current change files",
+            FilesTabEvent::ViewFileLog => "log of changes touching file",
+            FilesTabEvent::ViewDirectoryLog => "log of changes touching directory",
+            FilesTabEvent::OpenInPager => "open diff in the pager",
+            FilesTabEvent::OpenInBrowser => "open file at the change on the forge",
+        )
+    }
+    /// Hints for the hint bar
+    pub fn make_hints(&self) -> Vec<(String, String)> {
+        enabled_hints(make_keybinds_help!(
+            self.keys,
+            FilesTabEvent::ToggleDiffFormat => "diff format",
+            FilesTabEvent::Untrack => "untrack",
+            FilesTabEvent::Restore => "restore",
+            FilesTabEvent::Track => "track",
+            FilesTabEvent::Ignore => "ignore",
+            FilesTabEvent::ToggleMark => "mark",
+            FilesTabEvent::Extract => "extract",
+            FilesTabEvent::MoveToParent => "to parent",
+            FilesTabEvent::MoveToChild => "to child",
+            FilesTabEvent::MoveToNewChild => "to new child",
+            FilesTabEvent::ToggleViewed => "review",
+            FilesTabEvent::FocusCurrent => "current change",
+            FilesTabEvent::ViewFileLog => "file log",
+            FilesTabEvent::OpenHelp => "help",
+        ))
+    }
+    /// Hints for the hint bar in read-only mode, without the actions changing the repository
+    pub fn make_read_only_hints(&self) -> Vec<(String, String)> {
+        enabled_hints(make_keybinds_help!(
+            self.keys,
+            FilesTabEvent::ToggleDiffFormat => "diff format",
+            FilesTabEvent::ToggleViewed => "review",
+            FilesTabEvent::FocusCurrent => "current change",
+            FilesTabEvent::ViewFileLog => "file log",
+            FilesTabEvent::OpenHelp => "help",
+        ))
+    }
+    /// Hints for the hint bar in review mode, with the changes of the range if `range` is set
+    pub fn make_review_hints(&self, range: bool) -> Vec<(String, String)> {
+        let mut hints = enabled_hints(make_keybinds_help!(
+            self.keys,
+            FilesTabEvent::ToggleViewed => "mark viewed",
+            FilesTabEvent::ToggleReview => "exit review",
+            FilesTabEvent::ToggleDiffFormat => "diff format",
+        ));
+        if range {
+            hints.extend(enabled_hints(make_keybinds_help!(
+                self.keys,
+                FilesTabEvent::NextReviewChange => "next change",
+                FilesTabEvent::PreviousReviewChange => "previous change",
+            )));
+        }
+        hints.extend(enabled_hints(make_keybinds_help!(
+            self.keys,
+            FilesTabEvent::OpenHelp => "help",
+        )));
+        hints
+    }
+}
@@ -28,6 +28,14 @@ where
             .map(|(s, _)| *s)
             .collect()
     }
+    /// Shortcuts of the actions for which `matches` is true
+    pub fn get_shortcuts_where(&self, matches: impl Fn(&A) -> bool) -> Vec<Shortcut> {
+        self.shortcut_actions
+            .iter()
+            .filter(|(_, a)| matches(a))
+            .map(|(s, _)| *s)
+            .collect()
+    }
     pub fn replace_action_from_config(&mut self, action: A, key: &Keybind) {
         // just ignore this case
         if matches!(key, Keybind::Enable(true)) {
@@ -4,7 +4,10 @@ use ratatui::crossterm::event::KeyEvent;
 
 use crate::{make_keybinds_help, set_keybinds, update_keybinds};
 
-use super::{Shortcut, config::LogTabKeybindsConfig, keybinds_store::KeybindsStore};
+use super::{
+    KeybindPreset, Shortcut, config::LogTabKeybindsConfig, enabled_hints,
+    keybinds_store::KeybindsStore,
+};
 
 #[derive(Debug)]
 pub struct LogTabKeybinds {
@@ -16,6 +19,7 @@ pub struct LogTabKeybinds {
 pub enum LogTabEvent {
     Save,
     Cancel,
+    GenerateDescription,
 
     ClosePopup,
 
@@ -25,38 +29,95 @@ pub enum LogTabEvent {
     ScrollUpHalf,
 
     FocusCurrent,
+    ToggleFollow,
+    JumpBack,
+    JumpForward,
     ToggleDiffFormat,
 
     Refresh,
     CreateNew {
         describe: bool,
     },
+    StartNewWork,
     Rebase,
     Squash {
         ignore_immutable: bool,
     },
+    Amend,
     EditChange {
         ignore_immutable: bool,
     },
     Abandon,
     Describe,
+    DescribeFromBookmarks,
     EditRevset,
+    ClearPathFilter,
+    ExpandElided,
+    ShowConflicts,
+    ToggleMineFilter,
+    ToggleUnmergedFilter,
+    ToggleFirstParentFilter,
+    CycleLogDensity,
+    ToggleMinimap,
+    ToggleTrunkRelation,
+    MarkSeen,
+    OpenInPager,
+    OpenInBrowser,
+    PreviewTemplate,
+    ShowStats,
+    ShowTodos,
+    ShowChangedFiles,
+    ReviewRange,
+    CopyReference,
+    Reorder,
+    ToggleMark,
     SetBookmark,
+    BookmarkStack,
+    ViewBookmark,
     OpenFiles,
 
     Push {
         all_bookmarks: bool,
         allow_new: bool,
     },
+    PushDryRun {
+        all_bookmarks: bool,
+    },
     Fetch {
         all_remotes: bool,
     },
+    FetchDryRun {
+        all_remotes: bool,
+    },
 
     OpenHelp,
 
     Unbound,
 }
 
+impl LogTabEvent {
+    /// Whether the action changes the repository, which is disabled in read-only mode
+    pub fn is_mutating(&self) -> bool {
+        matches!(
+            self,
+            LogTabEvent::CreateNew { .. }
+                | LogTabEvent::StartNewWork
+                | LogTabEvent::Rebase
+                | LogTabEvent::Reorder
+                | LogTabEvent::Squash { .. }
+                | LogTabEvent::Amend
+                | LogTabEvent::EditChange { .. }
+                | LogTabEvent::Abandon
+                | LogTabEvent::Describe
+                | LogTabEvent::DescribeFromBookmarks
+                | LogTabEvent::SetBookmark
+                | LogTabEvent::BookmarkStack
+                | LogTabEvent::Push { .. }
+                | LogTabEvent::Fetch { .. }
+        )
+    }
+}
+
 impl Default for LogTabKeybinds {
     fn default() -> Self {
         let mut keys = KeybindsStore::<LogTabEvent>::default();
@@ -64,6 +125,7 @@ impl Default for LogTabKeybinds {
             keys,
             LogTabEvent::Save => "ctrl+s",
             LogTabEvent::Cancel => "esc",
+            LogTabEvent::GenerateDescription => "ctrl+g",
             LogTabEvent::ClosePopup => "q",
             LogTabEvent::ScrollDown => "j",
             LogTabEvent::ScrollDown => "down",
@@ -72,28 +134,64 @@ impl Default for LogTabKeybinds {
             LogTabEvent::ScrollDownHalf => "shift+j",
             LogTabEvent::ScrollUpHalf => "shift+k",
             LogTabEvent::FocusCurrent => "@",
+            LogTabEvent::ToggleFollow => "alt+@",
+            LogTabEvent::JumpBack => "ctrl+o",
+            LogTabEvent::JumpBack => "alt+left",
+            // Arrives as `tab` without the keyboard enhancement of the terminal
+            LogTabEvent::JumpForward => "ctrl+i",
+            LogTabEvent::JumpForward => "alt+right",
             // todo: move to DetailsKeybindings
             LogTabEvent::ToggleDiffFormat => "w",
             LogTabEvent::Refresh => "shift+r",
             LogTabEvent::Refresh => "f5",
             LogTabEvent::CreateNew { describe: false } => "n",
             LogTabEvent::CreateNew { describe: true } => "shift+n",
+            LogTabEvent::StartNewWork => "alt+n",
             LogTabEvent::Rebase => "ctrl+r",
             LogTabEvent::Squash { ignore_immutable: false } => "s",
             LogTabEvent::Squash { ignore_immutable: true } => "shift+s",
+            LogTabEvent::Amend => "shift+a",
             LogTabEvent::EditChange { ignore_immutable: false } => "e",
             LogTabEvent::EditChange { ignore_immutable: true } => "shift+e",
             LogTabEvent::Abandon => "a",
             LogTabEvent::Describe => "d",
+            LogTabEvent::DescribeFromBookmarks => "shift+d",
             LogTabEvent::EditRevset => "r",
+            LogTabEvent::ClearPathFilter => "shift+l",
+            LogTabEvent::ExpandElided => "z",
+            LogTabEvent::ShowConflicts => "shift+x",
+            LogTabEvent::ToggleMineFilter => "alt+m",
+            LogTabEvent::ToggleUnmergedFilter => "alt+u",
+            LogTabEvent::ToggleFirstParentFilter => "alt+f",
+            LogTabEvent::CycleLogDensity => "v",
+            LogTabEvent::ToggleMinimap => "m",
+            LogTabEvent::ToggleTrunkRelation => "t",
+            LogTabEvent::MarkSeen => "alt+h",
+            LogTabEvent::OpenInPager => "|",
+            LogTabEvent::OpenInBrowser => "g",
+            LogTabEvent::PreviewTemplate => "alt+t",
+            LogTabEvent::ShowStats => "alt+s",
+            LogTabEvent::ShowTodos => "alt+x",
+            LogTabEvent::ShowChangedFiles => "alt+c",
+            LogTabEvent::ReviewRange => "alt+r",
+            LogTabEvent::CopyReference => "y",
+            LogTabEvent::Reorder => "alt+o",
+            LogTabEvent::ToggleMark => "space",
             LogTabEvent::SetBookmark => "b",
+            LogTabEvent::BookmarkStack => "alt+shift+b",
+            LogTabEvent::ViewBookmark => "alt+b",
             LogTabEvent::OpenFiles => "enter",
             event_push(false, false) => "p",
             event_push(false, true) => "ctrl+p",
             event_push(true, false) => "shift+p",
             event_push(true, true) => "ctrl+shift+p",
+            LogTabEvent::PushDryRun { all_bookmarks: false } => "alt+p",
+            LogTabEvent::PushDryRun { all_bookmarks: true } => "alt+shift+p",
             LogTabEvent::Fetch { all_remotes: false } => "f",
             LogTabEvent::Fetch { all_remotes: true } => "shift+f",
+            // Not `alt+f`, which toggles the first parent filter
+            LogTabEvent::FetchDryRun { all_remotes: false } => "alt+shift+f",
+            LogTabEvent::FetchDryRun { all_remotes: true } => "ctrl+alt+f",
             LogTabEvent::OpenHelp => "?",
         );
 
@@ -102,6 +200,15 @@ impl Default for LogTabKeybinds {
 }
 
 impl LogTabKeybinds {
+    /// The default keybinds, overridden by the preset and then by the user's keybinds
+    pub fn new(preset: KeybindPreset, config: Option<&LogTabKeybindsConfig>) -> Self {
+        let mut keybinds = Self::default();
+        keybinds.extend_from_config(&preset.log_tab());
+        if let Some(config) = config {
+            keybinds.extend_from_config(config);
+        }
+        keybinds
+    }
     pub fn match_event(&self, event: KeyEvent) -> LogTabEvent {
         if let Some(action) = self.keys.match_event(event) {
             action
@@ -114,35 +221,76 @@ impl LogTabKeybinds {
             self.keys,
             LogTabEvent::Save => config.save,
             LogTabEvent::Cancel => config.cancel,
+            LogTabEvent::GenerateDescription => config.generate_description,
             LogTabEvent::ClosePopup => config.close_popup,
             LogTabEvent::ScrollDown => config.scroll_down,
             LogTabEvent::ScrollUp => config.scroll_up,
             LogTabEvent::ScrollDownHalf => config.scroll_down_half,
             LogTabEvent::ScrollUpHalf => config.scroll_up_half,
             LogTabEvent::FocusCurrent => config.focus_current,
+            LogTabEvent::ToggleFollow => config.toggle_follow,
+            LogTabEvent::JumpBack => config.jump_back,
+            LogTabEvent::JumpForward => config.jump_forward,
             LogTabEvent::ToggleDiffFormat => config.toggle_diff_format,
             LogTabEvent::Refresh => config.refresh,
             LogTabEvent::CreateNew { describe: false } => config.create_new,
             LogTabEvent::CreateNew { describe: true } => config.create_new_describe,
+            LogTabEvent::StartNewWork => config.start_new_work,
             LogTabEvent::Squash { ignore_immutable: false } => config.squash,
             LogTabEvent::Squash { ignore_immutable: true } => config.squash_ignore_immutable,
+            LogTabEvent::Amend => config.amend,
             LogTabEvent::EditChange { ignore_immutable: false } => config.edit_change,
             LogTabEvent::EditChange { ignore_immutable: true } => config.edit_change_ignore_immutable,
             LogTabEvent::Abandon => config.abandon,
             LogTabEvent::Describe => config.describe,
+            LogTabEvent::DescribeFromBookmarks => config.describe_from_bookmarks,
             LogTabEvent::EditRevset => config.edit_revset,
+            LogTabEvent::ClearPathFilter => config.clear_path_filter,
+            LogTabEvent::ExpandElided => config.expand_elided,
+            LogTabEvent::ShowConflicts => config.show_conflicts,
+            LogTabEvent::ToggleMineFilter => config.toggle_mine_filter,
+            LogTabEvent::ToggleUnmergedFilter => config.toggle_unmerged_filter,
+            LogTabEvent::ToggleFirstParentFilter => config.toggle_first_parent_filter,
+            LogTabEvent::CycleLogDensity => config.cycle_log_density,
+            LogTabEvent::ToggleMinimap => config.toggle_minimap,
+            LogTabEvent::ToggleTrunkRelation => config.toggle_trunk_relation,
+            LogTabEvent::MarkSeen => config.mark_seen,
+            LogTabEvent::OpenInPager => config.open_in_pager,
+            LogTabEvent::OpenInBrowser => config.open_in_browser,
+            LogTabEvent::PreviewTemplate => config.preview_template,
+            LogTabEvent::ShowStats => config.show_stats,
+            LogTabEvent::ShowTodos => config.show_todos,
+            LogTabEvent::ShowChangedFiles => config.show_changed_files,
+            LogTabEvent::ReviewRange => config.review_range,
+            LogTabEvent::CopyReference => config.copy_reference,
+            LogTabEvent::Reorder => config.reorder,
+            LogTabEvent::ToggleMark => config.toggle_mark,
             LogTabEvent::SetBookmark => config.set_bookmark,
+            LogTabEvent::BookmarkStack => config.bookmark_stack,
+            LogTabEvent::ViewBookmark => config.view_bookmark,
             LogTabEvent::OpenFiles => config.open_files,
             LogTabEvent::Rebase => config.rebase,
             event_push(false, false) => config.push,
             event_push(false, true) => config.push_new,
             event_push(true, false) => config.push_all,
             event_push(true, true) => config.push_all_new,
+            LogTabEvent::PushDryRun { all_bookmarks: false } => config.push_dry_run,
+            LogTabEvent::PushDryRun { all_bookmarks: true } => config.push_all_dry_run,
             LogTabEvent::Fetch { all_remotes: false } => config.fetch,
             LogTabEvent::Fetch { all_remotes: true } => config.fetch_all,
+            LogTabEvent::FetchDryRun { all_remotes: false } => config.fetch_dry_run,
+            LogTabEvent::FetchDryRun { all_remotes: true } => config.fetch_all_dry_run,
             LogTabEvent::OpenHelp => config.open_help,
         );
     }
+    /// Shortcuts of the actions changing the repository, see [LogTabEvent::is_mutating]
+    pub fn mutating_shortcuts(&self) -> Vec<String> {
+        self.keys
+            .get_shortcuts_where(LogTabEvent::is_mutating)
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
     pub fn make_main_panel_help(&self) -> Vec<(String, String)> {
         make_keybinds_help!(
             self.keys,
@@ -152,25 +300,119 @@ impl LogTabKeybinds {
             LogTabEvent::ScrollUpHalf => "scroll up by ½ page",
             LogTabEvent::OpenFiles => "see files",
             LogTabEvent::FocusCurrent => "current change",
+            LogTabEvent::ToggleFollow => "keep the selection on @ as it moves",
+            LogTabEvent::JumpBack => "jump back to the previously selected change",
+            LogTabEvent::JumpForward => "jump forward in the selected changes",
             LogTabEvent::EditRevset => "set revset",
+            LogTabEvent::ClearPathFilter => "clear path filter",
+            LogTabEvent::ExpandElided => "expand the revisions elided below the change",
+            LogTabEvent::ShowConflicts => "list the conflicted files of the log",
+            LogTabEvent::ToggleMineFilter => "only show my changes (mine())",
+            LogTabEvent::ToggleUnmergedFilter => "only show changes not in trunk() (~::trunk())",
+            LogTabEvent::ToggleFirstParentFilter => "only show the first-parent ancestry, hiding merged changes (first_ancestors())",
+            LogTabEvent::CycleLogDensity => "cycle log density",
+            LogTabEvent::ToggleMinimap => "toggle minimap",
+            LogTabEvent::ToggleTrunkRelation => "toggle relationship to trunk()",
+            LogTabEvent::MarkSeen => "mark the log as seen, new and rewritten changes are marked from now on",
+            LogTabEvent::OpenInPager => "open the log or the details of the change in the pager",
+            LogTabEvent::OpenInBrowser => "open the change on the forge in the browser",
+            LogTabEvent::PreviewTemplate => "try out a template on the change",
+            LogTabEvent::ShowStats => "commits per author and week, most changed files",
+            LogTabEvent::ShowTodos => "list the TODO/FIXME/XXX markers added by the change",
+            LogTabEvent::ShowChangedFiles => "list the files changed by the change",
+            LogTabEvent::ReviewRange => "review the changes of the stack, or between the marked changes, one by one",
+            LogTabEvent::CopyReference => "copy a reference to the change",
+            LogTabEvent::Reorder => "reorder the stack of the change",
+            LogTabEvent::ToggleMark => "mark change as merge parent",
             LogTabEvent::Describe => "describe change",
+            LogTabEvent::DescribeFromBookmarks => "describe changes without description from their bookmarks",
             LogTabEvent::EditChange { ignore_immutable: false } => "edit change",
             LogTabEvent::EditChange { ignore_immutable: true } => "edit change ignoring immutability",
-            LogTabEvent::CreateNew { describe: false } => "new change",
+            LogTabEvent::CreateNew { describe: false } => "new change, a merge of the marked changes if any",
             LogTabEvent::CreateNew { describe: true } => "new with message",
+            LogTabEvent::StartNewWork => "start new work: new change on trunk() or blazingjj.new-work-base, then describe it",
             LogTabEvent::Abandon => "abandon change",
             LogTabEvent::Rebase => "rebase @ to the selected change",
             LogTabEvent::Squash { ignore_immutable: false } => "squash @ into the selected change",
             LogTabEvent::Squash { ignore_immutable: true } => "squash @ into the selected change ignoring immutability",
+            LogTabEvent::Amend => "move some or all files of @ into the selected change",
             LogTabEvent::SetBookmark => "set bookmark",
+            LogTabEvent::BookmarkStack => "create bookmarks for the changes of the stack of @",
+            LogTabEvent::ViewBookmark => "view the bookmark of the change in the bookmarks tab",
             LogTabEvent::Fetch { all_remotes: false } => "git fetch",
             LogTabEvent::Fetch { all_remotes: true } => "git fetch all remotes",
             event_push(false, false) => "git push",
             event_push(false, true) => "git push with new bookmarks",
             event_push(true, false) => "git push all bookmarks, except new",
             event_push(true, true) => "git push all bookmarks",
+            LogTabEvent::PushDryRun { all_bookmarks: false } => "git push dry run",
+            LogTabEvent::PushDryRun { all_bookmarks: true } => "git push all bookmarks dry run",
+            LogTabEvent::FetchDryRun { all_remotes: false } => "git fetch dry run",
+            LogTabEvent::FetchDryRun { all_remotes: true } => "git fetch all remotes dry run",
         )
     }
+    /// Hints for the hint bar when no popup is open
+    pub fn make_hints(&self) -> Vec<(String, String)> {
+        enabled_hints(make_keybinds_help!(
+            self.keys,
+            LogTabEvent::OpenFiles => "files",
+            LogTabEvent::Describe => "describe",
+            LogTabEvent::CreateNew { describe: false } => "new",
+            LogTabEvent::EditChange { ignore_immutable: false } => "edit",
+            LogTabEvent::Squash { ignore_immutable: false } => "squash",
+            LogTabEvent::Rebase => "rebase",
+            LogTabEvent::SetBookmark => "bookmark",
+            LogTabEvent::Fetch { all_remotes: false } => "fetch",
+            event_push(false, false) => "push",
+            LogTabEvent::OpenHelp => "help",
+        ))
+    }
+    /// Hints for the hint bar in read-only mode, without the actions changing the repository
+    pub fn make_read_only_hints(&self) -> Vec<(String, String)> {
+        enabled_hints(make_keybinds_help!(
+            self.keys,
+            LogTabEvent::OpenFiles => "files",
+            LogTabEvent::FocusCurrent => "current change",
+            LogTabEvent::EditRevset => "revset",
+            LogTabEvent::CycleLogDensity => "density",
+            event_push_dry_run() => "push dry run",
+            LogTabEvent::OpenHelp => "help",
+        ))
+    }
+    /// Hints for the hint bar while a text editor is open
+    pub fn make_editor_hints(&self) -> Vec<(String, String)> {
+        enabled_hints(make_keybinds_help!(
+            self.keys,
+            LogTabEvent::Save => "save",
+            LogTabEvent::Cancel => "cancel",
+        ))
+    }
+    /// Hints for the hint bar while the describe editor is open
+    pub fn make_describe_hints(&self, has_generator: bool) -> Vec<(String, String)> {
+        let mut hints = self.make_editor_hints();
+        if has_generator {
+            hints.extend(enabled_hints(make_keybinds_help!(
+                self.keys,
+                LogTabEvent::GenerateDescription => "generate",
+            )));
+        }
+        hints
+    }
+    /// Hints for the hint bar while a confirmation dialog is open
+    pub fn make_confirm_hints(&self) -> Vec<(String, String)> {
+        let mut hints = vec![("y/n".to_owned(), "confirm".to_owned())];
+        hints.extend(enabled_hints(make_keybinds_help!(
+            self.keys,
+            LogTabEvent::ClosePopup => "close",
+        )));
+        hints
+    }
+}
+
+fn event_push_dry_run() -> LogTabEvent {
+    LogTabEvent::PushDryRun {
+        all_bookmarks: false,
+    }
 }
 
 fn event_push(all_bookmarks: bool, allow_new: bool) -> LogTabEvent {
@@ -2,12 +2,19 @@ use std::{fmt::Display, str::FromStr};
 
 use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
-pub use config::{Keybind, KeybindsConfig};
+pub use bookmarks_tab::{BookmarksTabEvent, BookmarksTabKeybinds};
+pub use config::{Keybind, KeybindsConfig, LogTabKeybindsConfig};
+pub use files_tab::{FilesTabEvent, FilesTabKeybinds};
 pub use log_tab::{LogTabEvent, LogTabKeybinds};
+pub use preset::KeybindPreset;
 
+mod bookmarks_tab;
 mod config;
+mod files_tab;
 mod keybinds_store;
 mod log_tab;
+pub mod new_popup;
+mod preset;
 pub mod rebase_popup;
 
 /*#[derive(Debug)]
@@ -66,6 +73,13 @@ macro_rules! make_keybinds_help {
     };
 }
 
+/// Drop disabled entries from help made by [`make_keybinds_help`], for use in the hint bar
+pub fn enabled_hints(help: Vec<(String, String)>) -> Vec<(String, String)> {
+    help.into_iter()
+        .filter(|(shortcuts, _)| shortcuts != "[disabled]")
+        .collect()
+}
+
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, serde_with::DeserializeFromStr)]
 pub struct Shortcut {
     key: KeyCode,
@@ -77,13 +91,25 @@ impl Shortcut {
         Self { key, modifiers }
     }
     pub fn from_event(event: KeyEvent) -> Self {
+        let mut modifiers = event.modifiers;
+        if let KeyCode::Char(c) = event.code
+            && !c.is_ascii_alphabetic()
+        {
+            // Symbols like `@` or `?` already include shift, which Windows and terminals with
+            // keyboard enhancement report as a modifier
+            modifiers.remove(KeyModifiers::SHIFT);
+            // Windows reports AltGr, used to type symbols on many keyboard layouts, as ctrl+alt
+            if modifiers.contains(KeyModifiers::CONTROL | KeyModifiers::ALT) {
+                modifiers.remove(KeyModifiers::CONTROL | KeyModifiers::ALT);
+            }
+        }
         Self {
             key: match event.code {
                 // when shift is pressed character is in upper case, so normalize it here
                 KeyCode::Char(c) => KeyCode::Char(c.to_ascii_lowercase()),
                 c => c,
             },
-            modifiers: event.modifiers,
+            modifiers,
         }
     }
 }
@@ -98,8 +124,10 @@ impl FromStr for Shortcut {
             match s {
                 "ctrl" => modifiers |= KeyModifiers::CONTROL,
                 "shift" => modifiers |= KeyModifiers::SHIFT,
+                "alt" => modifiers |= KeyModifiers::ALT,
                 "enter" => key = Some(KeyCode::Enter),
                 "esc" => key = Some(KeyCode::Esc),
+                "space" => key = Some(KeyCode::Char(' ')),
                 "left" => key = Some(KeyCode::Left),
                 "right" => key = Some(KeyCode::Right),
                 "up" => key = Some(KeyCode::Up),
@@ -119,6 +147,15 @@ impl FromStr for Shortcut {
             }
         }
 
+        // Terminals report the symbol typed with shift instead, e.g. `!` for shift+1, which
+        // depends on the keyboard layout
+        if let Some(KeyCode::Char(c)) = key
+            && !c.is_ascii_alphabetic()
+            && modifiers.contains(KeyModifiers::SHIFT)
+        {
+            return Err(ShortcutParseError::ShiftedSymbol);
+        }
+
         if let Some(key) = key {
             Ok(Self::new_mod_key(modifiers, key))
         } else {
@@ -129,10 +166,13 @@ impl FromStr for Shortcut {
 
 impl Display for Shortcut {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut parts = Vec::with_capacity(3);
+        let mut parts = Vec::with_capacity(4);
         if self.modifiers.contains(KeyModifiers::CONTROL) {
             parts.push("Control".to_string());
         }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            parts.push("Alt".to_string());
+        }
         if self.modifiers.contains(KeyModifiers::SHIFT) {
             parts.push("Shift".to_string());
         }
@@ -143,6 +183,7 @@ impl Display for Shortcut {
             KeyCode::Up => "Up".to_string(),
             KeyCode::Down => "Down".to_string(),
             KeyCode::F(n) => format!("F{n}"),
+            KeyCode::Char(' ') => "Space".to_string(),
             KeyCode::Char(c) => c.to_string(),
             KeyCode::Esc => "Esc".to_string(),
             _ => "Unknown".to_string(),
@@ -159,6 +200,8 @@ pub enum ShortcutParseError {
     InvalidF,
     #[error("no key specified")]
     NoKey,
+    #[error("shift can't be combined with a digit or symbol, bind the symbol it types instead")]
+    ShiftedSymbol,
 }
 
 #[cfg(test)]
@@ -175,6 +218,16 @@ mod tests {
         pub fn new_key(key: KeyCode) -> Self {
             Self::new_mod_key(KeyModifiers::empty(), key)
         }
+        /// Event of pressing the keys, with the upper case character a terminal sends for shift
+        pub fn key_event(&self) -> KeyEvent {
+            let key = match self.key {
+                KeyCode::Char(c) if self.modifiers.contains(KeyModifiers::SHIFT) => {
+                    KeyCode::Char(c.to_ascii_uppercase())
+                }
+                key => key,
+            };
+            KeyEvent::new(key, self.modifiers)
+        }
     }
 
     #[test]
@@ -194,6 +247,11 @@ mod tests {
             ("ctrl+Q", Ok(Shortcut::new_mod_char(ctrl, 'q'))),
             ("ctrl+ctrl+q", Ok(Shortcut::new_mod_char(ctrl, 'q'))),
             ("ctrl+shift+q", Ok(Shortcut::new_mod_char(ctrl_shift, 'q'))),
+            ("alt+p", Ok(Shortcut::new_mod_char(KeyModifiers::ALT, 'p'))),
+            (
+                "alt+shift+p",
+                Ok(Shortcut::new_mod_char(KeyModifiers::ALT | shift, 'p')),
+            ),
             (
                 "ctrl+shift+f5",
                 Ok(Shortcut::new_mod_key(ctrl_shift, KeyCode::F(5))),
@@ -208,12 +266,16 @@ mod tests {
                 Ok(Shortcut::new_mod_key(ctrl, KeyCode::Enter)),
             ),
             ("esc", Ok(Shortcut::new_key(KeyCode::Esc))),
+            ("space", Ok(Shortcut::new_char(' '))),
             ("left", Ok(Shortcut::new_key(KeyCode::Left))),
             ("right", Ok(Shortcut::new_key(KeyCode::Right))),
             ("up", Ok(Shortcut::new_key(KeyCode::Up))),
             ("down", Ok(Shortcut::new_key(KeyCode::Down))),
             ("ctrl+ff", Err(ShortcutParseError::InvalidF)),
             ("qq", Err(ShortcutParseError::NoKey)),
+            ("shift+1", Err(ShortcutParseError::ShiftedSymbol)),
+            ("ctrl+shift+/", Err(ShortcutParseError::ShiftedSymbol)),
+            ("shift+space", Err(ShortcutParseError::ShiftedSymbol)),
             ("", Err(ShortcutParseError::NoKey)),
         ];
 
@@ -225,4 +287,26 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_shortcut_from_event() {
+        let ctrl_alt = KeyModifiers::CONTROL | KeyModifiers::ALT;
+        let table = [
+            (KeyCode::Char('P'), KeyModifiers::SHIFT, "shift+p"),
+            (KeyCode::Char('@'), KeyModifiers::SHIFT, "@"),
+            (KeyCode::Char('?'), KeyModifiers::SHIFT, "?"),
+            // AltGr on Windows
+            (KeyCode::Char('@'), ctrl_alt, "@"),
+            (KeyCode::Char('p'), ctrl_alt, "ctrl+alt+p"),
+            (KeyCode::Char('1'), KeyModifiers::CONTROL, "ctrl+1"),
+        ];
+
+        for (code, modifiers, expected) in table {
+            assert_eq!(
+                Shortcut::from_event(KeyEvent::new(code, modifiers)),
+                Shortcut::from_str(expected).unwrap(),
+                "Shortcut::from_event({code:?}, {modifiers:?})"
+            );
+        }
+    }
 }
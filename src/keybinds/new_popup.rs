@@ -0,0 +1,80 @@
+/*! Key bindings specific for new popup */
+
+use ratatui::crossterm::event::KeyEvent;
+use std::str::FromStr; // used by set_keybinds macro
+
+use super::{Shortcut, enabled_hints, keybinds_store::KeybindsStore};
+use crate::{make_keybinds_help, set_keybinds};
+
+/// Where should the new change be placed relative to the selected revision
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum NewPlacement {
+    Child,        // jj new <rev>
+    InsertAfter,  // -A
+    InsertBefore, // -B
+}
+
+/// Actions available inside a NewPopup
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum PopupAction {
+    None,
+    Ok,
+    Cancel,
+    SetPlacement(NewPlacement),
+    ToggleEdit,
+    ToggleAdvance,
+}
+
+fn default_keybinds() -> KeybindsStore<PopupAction> {
+    let mut keys = KeybindsStore::<PopupAction>::default();
+    set_keybinds!(
+        keys,
+        PopupAction::Ok => "enter",
+        PopupAction::Ok => "y",
+        PopupAction::Cancel => "esc",
+        PopupAction::Cancel => "q",
+        PopupAction::SetPlacement(NewPlacement::Child) => "c",
+        PopupAction::SetPlacement(NewPlacement::InsertAfter) => "shift+a",
+        PopupAction::SetPlacement(NewPlacement::InsertBefore) => "shift+b",
+        PopupAction::ToggleEdit => "e",
+        PopupAction::ToggleAdvance => "a",
+    );
+    keys
+}
+
+#[derive(Debug)]
+pub struct Keybinds {
+    keys: KeybindsStore<PopupAction>,
+}
+
+impl Default for Keybinds {
+    fn default() -> Self {
+        Self {
+            keys: default_keybinds(),
+        }
+    }
+}
+
+impl Keybinds {
+    pub fn match_event(&self, event: KeyEvent) -> PopupAction {
+        if let Some(action) = self.keys.match_event(event) {
+            action
+        } else {
+            PopupAction::None
+        }
+    }
+
+    /// Hints for the hint bar
+    pub fn make_hints(&self) -> Vec<(String, String)> {
+        enabled_hints(make_keybinds_help!(
+            self.keys,
+            PopupAction::Ok => "new",
+            PopupAction::Cancel => "cancel",
+            PopupAction::SetPlacement(NewPlacement::Child) => "child",
+            PopupAction::SetPlacement(NewPlacement::InsertAfter) => "-A",
+            PopupAction::SetPlacement(NewPlacement::InsertBefore) => "-B",
+            PopupAction::ToggleEdit => "edit",
+            PopupAction::ToggleAdvance => "advance bookmarks",
+        ))
+    }
+}
@@ -0,0 +1,158 @@
+/*!
+Keybind presets selected with `blazingjj.keybind-preset`.
+
+A preset is a layer of keybinds on top of the defaults, written like the
+user's `blazingjj.keybinds` config. The user's explicit keybinds are applied
+on top of the preset, so the layers are: defaults, preset, user config.
+*/
+use std::str::FromStr;
+
+use super::{Keybind, LogTabKeybindsConfig, Shortcut};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum KeybindPreset {
+    /// The default keybinds, which already use `j`/`k` for scrolling and `Ctrl+d`/`Ctrl+u` in
+    /// the details panel
+    #[default]
+    Default,
+    /// Adds `/` to set the revset, `o` for a new change, `i` to describe and `x` to abandon
+    Vim,
+    /// `Ctrl+n`/`Ctrl+p` for scrolling, `Ctrl+v`/`Alt+v` by half a page and `Ctrl+g` to cancel
+    Emacs,
+    /// Keys of the lazygit commits panel: `Space` to edit, `r` to describe, `d` to abandon, `P`
+    /// to push and `p` to fetch
+    Lazygit,
+}
+
+fn keys(shortcuts: &[&str]) -> Option<Keybind> {
+    let shortcuts = shortcuts
+        .iter()
+        .map(|shortcut| Shortcut::from_str(shortcut).expect("preset shortcuts should be valid"))
+        .collect();
+    Some(Keybind::Multiple(shortcuts))
+}
+
+impl KeybindPreset {
+    /// Log tab keybinds the preset changes, applied on top of the defaults
+    pub fn log_tab(&self) -> LogTabKeybindsConfig {
+        match self {
+            KeybindPreset::Default => LogTabKeybindsConfig::default(),
+            KeybindPreset::Vim => LogTabKeybindsConfig {
+                edit_revset: keys(&["r", "/"]),
+                create_new: keys(&["n", "o"]),
+                describe: keys(&["d", "i"]),
+                abandon: keys(&["a", "x"]),
+                ..Default::default()
+            },
+            KeybindPreset::Emacs => LogTabKeybindsConfig {
+                scroll_down: keys(&["ctrl+n", "down"]),
+                scroll_up: keys(&["ctrl+p", "up"]),
+                scroll_down_half: keys(&["ctrl+v"]),
+                scroll_up_half: keys(&["alt+v"]),
+                cancel: keys(&["ctrl+g", "esc"]),
+                generate_description: keys(&["alt+g"]),
+                push_new: keys(&["ctrl+alt+p"]),
+                ..Default::default()
+            },
+            KeybindPreset::Lazygit => LogTabKeybindsConfig {
+                edit_change: keys(&["space", "e"]),
+                toggle_mark: keys(&["shift+b"]),
+                describe: keys(&["r"]),
+                edit_revset: keys(&["/"]),
+                abandon: keys(&["d"]),
+                push: keys(&["shift+p"]),
+                push_all: keys(&["ctrl+alt+p"]),
+                fetch: keys(&["p", "f"]),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keybinds::{LogTabEvent, LogTabKeybinds};
+    use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn presets_keep_every_action_bound() {
+        for preset in [
+            KeybindPreset::Default,
+            KeybindPreset::Vim,
+            KeybindPreset::Emacs,
+            KeybindPreset::Lazygit,
+        ] {
+            let keybinds = LogTabKeybinds::new(preset, None);
+            for (shortcuts, description) in keybinds.make_main_panel_help() {
+                assert_ne!(
+                    shortcuts, "[disabled]",
+                    "{description} is not bound with {preset:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn presets_leave_global_keys_to_the_app() {
+        // Keys handled by `App::input` when the tab doesn't handle them, except for quitting
+        const GLOBAL_KEYS: [&str; 20] = [
+            "h", "l", "1", "2", "3", ":", "shift+m", "!", "shift+o", ",", "`", "~", "ctrl+w",
+            "ctrl+x", "ctrl+t", "alt+1", "alt+2", "alt+3", "alt+4", "alt+5",
+        ];
+        for preset in [
+            KeybindPreset::Default,
+            KeybindPreset::Vim,
+            KeybindPreset::Emacs,
+            KeybindPreset::Lazygit,
+        ] {
+            let keybinds = LogTabKeybinds::new(preset, None);
+            for global_key in GLOBAL_KEYS {
+                let event = Shortcut::from_str(global_key).unwrap().key_event();
+                assert_eq!(
+                    keybinds.match_event(event),
+                    LogTabEvent::Unbound,
+                    "{global_key} is bound with {preset:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn user_keybinds_override_preset() {
+        let keybinds = LogTabKeybinds::new(KeybindPreset::Lazygit, None);
+        assert_eq!(
+            keybinds.match_event(key(KeyCode::Char('d'), KeyModifiers::NONE)),
+            LogTabEvent::Abandon
+        );
+        assert_eq!(
+            keybinds.match_event(key(KeyCode::Char(' '), KeyModifiers::NONE)),
+            LogTabEvent::EditChange {
+                ignore_immutable: false
+            }
+        );
+
+        let config = LogTabKeybindsConfig {
+            abandon: keys(&["x"]),
+            ..Default::default()
+        };
+        let keybinds = LogTabKeybinds::new(KeybindPreset::Lazygit, Some(&config));
+        assert_eq!(
+            keybinds.match_event(key(KeyCode::Char('x'), KeyModifiers::NONE)),
+            LogTabEvent::Abandon
+        );
+        assert_eq!(
+            keybinds.match_event(key(KeyCode::Char('d'), KeyModifiers::NONE)),
+            LogTabEvent::Unbound
+        );
+        assert_eq!(
+            keybinds.match_event(key(KeyCode::Char('r'), KeyModifiers::NONE)),
+            LogTabEvent::Describe
+        );
+    }
+}
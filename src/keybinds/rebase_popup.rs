@@ -3,8 +3,8 @@
 use ratatui::crossterm::event::KeyEvent;
 use std::str::FromStr; // used by set_keybinds macro
 
-use super::{Shortcut, keybinds_store::KeybindsStore};
-use crate::set_keybinds;
+use super::{Shortcut, enabled_hints, keybinds_store::KeybindsStore};
+use crate::{make_keybinds_help, set_keybinds};
 
 /// How should rebase cut revisions from source
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
@@ -70,4 +70,19 @@ impl Keybinds {
             PopupAction::None
         }
     }
+
+    /// Hints for the hint bar
+    pub fn make_hints(&self) -> Vec<(String, String)> {
+        enabled_hints(make_keybinds_help!(
+            self.keys,
+            PopupAction::Ok => "rebase",
+            PopupAction::Cancel => "cancel",
+            PopupAction::SetSourceMode(CutOption::IncludeDescendants) => "-s",
+            PopupAction::SetSourceMode(CutOption::IncludeBranch) => "-b",
+            PopupAction::SetSourceMode(CutOption::SingleRevision) => "-r",
+            PopupAction::SetTargetMode(PasteOption::NewBranch) => "-d",
+            PopupAction::SetTargetMode(PasteOption::InsertAfter) => "-A",
+            PopupAction::SetTargetMode(PasteOption::InsertBefore) => "-B",
+        ))
+    }
 }
@@ -1,46 +1,78 @@
 extern crate thiserror;
 
 use std::{
+    collections::VecDeque,
     env::current_dir,
-    fs::{OpenOptions, canonicalize},
-    io::{self, ErrorKind},
-    process::Command,
+    fs::{OpenOptions, canonicalize, remove_dir_all},
+    io::{self, ErrorKind, IsTerminal, Write},
+    path::Path,
+    process::{Command, Stdio},
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, AtomicU16, Ordering},
+        mpsc,
+    },
     time::{Duration, Instant},
 };
 
 use anyhow::{Context, Result, bail};
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use ratatui::{
-    Terminal,
-    backend::{Backend, CrosstermBackend},
+    Terminal, TerminalOptions, Viewport,
+    backend::Backend,
     crossterm::{
+        cursor::{MoveTo, RestorePosition, SavePosition},
         event::{
-            self, DisableFocusChange, DisableMouseCapture, EnableFocusChange, EnableMouseCapture,
-            Event, KeyboardEnhancementFlags, MouseEvent, MouseEventKind,
+            self, DisableBracketedPaste, DisableFocusChange, DisableMouseCapture,
+            EnableBracketedPaste, EnableFocusChange, EnableMouseCapture, Event, KeyCode,
+            KeyEventKind, KeyModifiers, KeyboardEnhancementFlags, MouseEvent, MouseEventKind,
             PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
         },
         execute,
+        style::{PrintStyledContent, Stylize},
         terminal::{
-            EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
-            supports_keyboard_enhancement,
+            Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode,
+            enable_raw_mode, size, supports_keyboard_enhancement,
         },
     },
+    layout::Rect,
 };
-use tracing::info;
+use tracing::{info, warn};
 use tracing_chrome::ChromeLayerBuilder;
 use tracing_subscriber::layer::SubscriberExt;
 
+mod anonymize;
 mod app;
+mod backend;
+mod clipboard;
 mod commander;
+mod crash_report;
+mod doctor;
 mod env;
 mod keybinds;
+mod oneshot;
+mod panel_settings;
+mod recent_repositories;
+mod review;
+#[cfg(test)]
+mod test_harness;
 mod ui;
+mod word_diff;
 
 use crate::{
-    app::App,
-    commander::Commander,
+    app::{App, OP_HEADS_POLL_INTERVAL, TerminalCommand},
+    backend::UiBackend,
+    commander::{
+        Commander,
+        batch::start_batch,
+        instances::{self, set_ui_thread},
+        jj::clone_destination,
+        running::{self, HungCommand},
+        shell_command,
+    },
+    doctor::Report,
     env::Env,
-    ui::{ComponentAction, ui},
+    ui::{Component, ComponentAction, loader_popup::LoaderPopup, ui},
 };
 
 /// Simple program to greet a person
@@ -62,6 +94,62 @@ struct Args {
     /// Do not exit if jj version check fails
     #[arg(long)]
     ignore_jj_version: bool,
+
+    /// Disable all actions which change the repository, including snapshotting the working copy
+    #[arg(long)]
+    read_only: bool,
+
+    /// Hide emails in the rendered output, for screenshots and recordings. Set
+    /// `blazingjj.presentation-mode` to also hide descriptions
+    #[arg(long)]
+    presentation: bool,
+
+    /// Check jj, the config and the repository, print the issues found and exit
+    #[arg(long)]
+    doctor: bool,
+
+    /// When quitting, print the selection of the current tab to stdout: the change id, the path
+    /// of the file or the bookmark. The UI is drawn on stderr. Quitting with Ctrl+c prints
+    /// nothing and exits with an error
+    #[arg(long)]
+    print_on_exit: bool,
+
+    /// Like --print-on-exit, but always print the change id of the change selected in the log,
+    /// e.g. `jj new $(blazingjj --pick-revision)`
+    #[arg(long, conflicts_with = "print_on_exit")]
+    pick_revision: bool,
+
+    /// Draw below the prompt instead of on the alternate screen, with a height of HEIGHT lines
+    /// or the terminal if it is smaller. The lines are cleared on exit
+    #[arg(
+        long,
+        value_name = "HEIGHT",
+        num_args = 0..=1,
+        default_missing_value = "20",
+        value_parser = clap::value_parser!(u16).range(MIN_INLINE_HEIGHT as i64..)
+    )]
+    inline: Option<u16>,
+
+    /// Run a single action without the TUI, e.g. `--command fetch` or
+    /// `--command "push --bookmark main"`, and exit with the exit code of jj. Hooks and the push
+    /// test command apply as in the TUI. `--command help` lists the actions
+    #[arg(long = "command", value_name = "ACTION")]
+    run_command: Option<String>,
+
+    #[command(subcommand)]
+    command: Option<CliCommand>,
+}
+
+#[derive(Subcommand, Debug)]
+enum CliCommand {
+    /// Clone a git repository with `jj git clone` and open it
+    Clone {
+        /// URL of the git repository
+        url: String,
+
+        /// Directory to clone into. Defaults to the last component of the URL
+        path: Option<String>,
+    },
 }
 
 fn main() -> Result<()> {
@@ -104,17 +192,15 @@ fn main() -> Result<()> {
 
     info!("Starting blazingjj");
 
-    // Parse arguments and determine path
+    // Parse arguments
     let args = Args::parse();
-    let path = match args.path {
-        Some(path) => {
-            canonicalize(&path).with_context(|| format!("Could not find path {}", &path))?
-        }
-        None => current_dir()?,
-    };
-
     let jj_bin = args.jj_bin.unwrap_or("jj".to_string());
 
+    // Keep stdout free for the selection printed on exit
+    let print_on_exit = args.print_on_exit || args.pick_revision;
+    UI_ON_STDERR.store(print_on_exit, Ordering::Relaxed);
+    INLINE_HEIGHT.store(args.inline.unwrap_or(0), Ordering::Relaxed);
+
     // Check that jj exists
     if let Err(err) = Command::new(&jj_bin).arg("help").output()
         && err.kind() == ErrorKind::NotFound
@@ -124,42 +210,258 @@ fn main() -> Result<()> {
         );
     }
 
+    crash_report::set_jj_bin(&jj_bin);
+    install_panic_hook();
+
+    // Determine path, cloning the repository first if requested
+    let path = match args.command {
+        Some(CliCommand::Clone { url, path }) => {
+            let Some(destination) = path.or_else(|| clone_destination(&url)) else {
+                bail!("Could not determine a directory name for {url}, please pass a path");
+            };
+            clone_repository(&jj_bin, &url, &destination)?;
+            canonicalize(&destination)
+                .with_context(|| format!("Could not find cloned repository {destination}"))?
+        }
+        None => match args.path {
+            Some(path) => {
+                canonicalize(&path).with_context(|| format!("Could not find path {}", &path))?
+            }
+            None => current_dir()?,
+        },
+    };
+
+    // Diagnose before setting up the environment, so that a missing repository or a broken
+    // config is reported instead of failing
+    if args.doctor {
+        let report = match Env::new(path.clone(), None, jj_bin.clone()) {
+            Ok(env) => Report::run(&Commander::new(&env)),
+            Err(err) => {
+                let env = Env::without_repository(path.to_string_lossy().to_string(), jj_bin);
+                Report::run_without_env(&Commander::new(&env), &err)
+            }
+        };
+        print!("{report}");
+        std::process::exit(if report.has_errors() { 1 } else { 0 });
+    }
+
     // Setup environment
-    let env = Env::new(path, args.revisions, jj_bin)?;
+    let mut env = Env::new(path, args.revisions, jj_bin)?;
+    if args.read_only {
+        env.config.enable_read_only();
+    }
+    if args.presentation {
+        env.config.enable_presentation_mode();
+    }
     let mut commander = Commander::new(&env);
 
     if !args.ignore_jj_version {
         commander.check_jj_version()?;
     }
 
+    if let Some(command) = &args.run_command {
+        std::process::exit(oneshot::run(&mut commander, command));
+    }
+
     // Setup app
     let mut app = App::new(env.clone())?;
+    app.record_repository();
+    if env.config.startup_diagnostics() {
+        app.start_diagnostics();
+    }
 
+    MOUSE_CAPTURE.store(app.mouse_capture, Ordering::Relaxed);
     let mut terminal = setup_terminal()?;
-    install_panic_hook();
 
     // Run app
+    set_ui_thread();
     let res = run_app(&mut terminal, &mut app, &mut commander);
-    restore_terminal()?;
+    suspend_terminal(&mut terminal)?;
     res?;
 
+    if print_on_exit {
+        let selection = if args.pick_revision {
+            app.selected_change_id()
+        } else {
+            app.selection()
+        };
+        match selection.filter(|_| !app.cancelled) {
+            Some(selection) => println!("{selection}"),
+            None => std::process::exit(1),
+        }
+    }
+
     Ok(())
 }
 
+/// Whether the TUI is drawn on stderr, keeping stdout for the selection printed on exit
+static UI_ON_STDERR: AtomicBool = AtomicBool::new(false);
+
+/// Height of the inline viewport drawn below the prompt, or 0 to use the alternate screen
+static INLINE_HEIGHT: AtomicU16 = AtomicU16::new(0);
+
+/// Row the inline viewport starts at, to clear it after a panic
+static INLINE_TOP: AtomicU16 = AtomicU16::new(0);
+
+/// Smallest height of the inline viewport fitting the header and a few lines of a tab
+const MIN_INLINE_HEIGHT: u16 = 8;
+
+/// Whether the mouse is captured while the TUI is shown, see [App::mouse_capture]
+static MOUSE_CAPTURE: AtomicBool = AtomicBool::new(true);
+
+/// Whether keyboard enhancement flags were pushed and have to be popped when restoring the
+/// terminal
+static KEYBOARD_ENHANCEMENT: AtomicBool = AtomicBool::new(false);
+
+/// Stream the TUI and the messages shown while it is suspended are written to
+fn ui_output() -> Box<dyn Write> {
+    if UI_ON_STDERR.load(Ordering::Relaxed) {
+        Box::new(io::stderr())
+    } else {
+        Box::new(io::stdout())
+    }
+}
+
+/// Number of consecutive failed draws after which the terminal is considered gone
+const MAX_DRAW_FAILURES: usize = 10;
+
+/// Whether a terminal IO error is likely to go away when retrying, e.g. a write interrupted by
+/// a signal while resizing
+fn is_temporary_error(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        ErrorKind::Interrupted | ErrorKind::WouldBlock | ErrorKind::TimedOut
+    )
+}
+
+/// Events read while the UI thread was blocked, handled by the event loop once it continues
+static QUEUED_EVENTS: Mutex<VecDeque<Event>> = Mutex::new(VecDeque::new());
+
+/// Whether the key was pressed while the UI thread is blocked. The other events are queued for
+/// the event loop.
+fn key_pressed_while_blocked(code: KeyCode) -> bool {
+    let mut pressed = false;
+    while let Ok(true) = event::poll(Duration::ZERO) {
+        match event::read() {
+            Ok(Event::Key(key)) if key.kind == KeyEventKind::Press && key.code == code => {
+                pressed = true;
+            }
+            Ok(event) => QUEUED_EVENTS.lock().unwrap().push_back(event),
+            Err(_) => break,
+        }
+    }
+    pressed
+}
+
+/// Whether a command blocking the UI thread, or its wait for the lock of another instance, was
+/// shown over the UI, which must then be redrawn
+static HUNG_COMMAND_SHOWN: AtomicBool = AtomicBool::new(false);
+
+/// Offer to kill a command which blocks the UI thread. The event loop can't draw while it waits,
+/// so the command is shown over the bottom line of the terminal, updated every second.
+fn offer_kill_hung_command(hung: &HungCommand, shown_seconds: &mut Option<u64>) {
+    // The command of the UI thread, other commands like background work keep running
+    if key_pressed_while_blocked(KeyCode::Char('k')) {
+        hung.kill();
+        return;
+    }
+
+    let seconds = hung.elapsed.as_secs();
+    if *shown_seconds == Some(seconds) {
+        return;
+    }
+    *shown_seconds = Some(seconds);
+    show_over_bottom_line(&format!(
+        " `{}` has been running for {seconds}s. {} Press k to kill it ",
+        hung.command_line,
+        hung.hint()
+    ));
+}
+
+/// Show the UI thread waiting for another instance to finish changing the repository, see
+/// [instances::on_lock_wait]. Returns whether to keep waiting.
+fn show_lock_wait(pid: u32, waited: Duration, shown_seconds: &mut Option<u64>) -> bool {
+    if key_pressed_while_blocked(KeyCode::Esc) {
+        return false;
+    }
+
+    let seconds = waited.as_secs();
+    if *shown_seconds != Some(seconds) {
+        *shown_seconds = Some(seconds);
+        show_over_bottom_line(&format!(
+            " Waiting {seconds}s for blazingjj (pid {pid}) to finish changing the repository. \
+             Press Esc to stop waiting "
+        ));
+    }
+    true
+}
+
+/// Show a message over the bottom line of the terminal while the UI thread is blocked
+fn show_over_bottom_line(message: &str) {
+    HUNG_COMMAND_SHOWN.store(true, Ordering::Relaxed);
+
+    let Ok((width, height)) = size() else {
+        return;
+    };
+    let message: String = message.chars().take(width as usize).collect();
+    let _ = execute!(
+        ui_output(),
+        SavePosition,
+        MoveTo(0, height.saturating_sub(1)),
+        Clear(ClearType::CurrentLine),
+        PrintStyledContent(message.black().on_yellow()),
+        RestorePosition
+    );
+}
+
 fn run_app<B: Backend>(
     terminal: &mut Terminal<B>,
     app: &mut App,
     commander: &mut Commander,
 ) -> Result<()> {
     let mut wait_duration = Duration::from_millis(0);
+    // Whether the whole screen must be redrawn, e.g. after resizing or a failed draw
+    let mut full_redraw = false;
+    let mut draw_failures = 0;
     loop {
-        if event::poll(wait_duration)? {
-            match event::read()? {
-                event::Event::FocusLost => continue,
+        // Queries of one round of input handling and drawing share a batch
+        start_batch(&commander.env.root);
+        let mut shown_seconds = None;
+        running::on_hung_command(commander.env.config.command_timeout(), move |hung| {
+            offer_kill_hung_command(hung, &mut shown_seconds)
+        });
+        let mut shown_seconds = None;
+        instances::on_lock_wait(move |pid, waited| show_lock_wait(pid, waited, &mut shown_seconds));
+
+        let queued = QUEUED_EVENTS.lock().unwrap().pop_front();
+        let has_event = queued.is_some()
+            || match event::poll(wait_duration) {
+                Ok(has_event) => has_event,
+                Err(err) if is_temporary_error(&err) => continue,
+                Err(err) => return Err(err.into()),
+            };
+        if has_event {
+            let event = match queued {
+                Some(event) => event,
+                None => event::read()?,
+            };
+            match event {
+                event::Event::FocusLost => {
+                    app.focused = false;
+                    continue;
+                }
                 Event::Mouse(MouseEvent {
                     kind: MouseEventKind::Moved,
                     ..
                 }) => continue,
+                Event::Resize(..) => {
+                    full_redraw = true;
+                    // Resizing a window sends a storm of events, only draw the last size
+                    if event::poll(Duration::ZERO)? {
+                        wait_duration = Duration::ZERO;
+                        continue;
+                    }
+                }
                 event => {
                     app.stats.start_time = Instant::now();
                     if app.input(event, commander)? {
@@ -169,55 +471,287 @@ fn run_app<B: Backend>(
             }
         }
 
+        if app.mouse_capture != MOUSE_CAPTURE.swap(app.mouse_capture, Ordering::Relaxed) {
+            if app.mouse_capture {
+                execute!(ui_output(), EnableMouseCapture)?;
+            } else {
+                execute!(ui_output(), DisableMouseCapture)?;
+            }
+        }
+
+        match app.terminal_command.take() {
+            Some(TerminalCommand::Jj(args)) => {
+                run_in_terminal(terminal, commander, &args)?;
+                app.handle_action(ComponentAction::RefreshTab(), commander)?;
+            }
+            // The pager doesn't change the repository, the tab is kept as it is
+            Some(TerminalCommand::Pager(text)) => run_pager(terminal, commander, &text)?,
+            None => {}
+        }
+
+        app.auto_refresh(commander)?;
         app.update(commander)?;
-        terminal.draw(|f| {
+        crash_report::set_state(app.state_summary(), app.anonymized_descriptions());
+        if HUNG_COMMAND_SHOWN.swap(false, Ordering::Relaxed) {
+            full_redraw = true;
+        }
+        if full_redraw {
+            // Clearing resizes the buffers and avoids artifacts of the previous size
+            terminal.autoresize()?;
+            terminal.clear()?;
+            full_redraw = false;
+        }
+        match terminal.draw(|f| {
             let _ = ui(f, app);
-        })?;
+        }) {
+            Ok(frame) => {
+                draw_failures = 0;
+                INLINE_TOP.store(frame.area.y, Ordering::Relaxed);
+            }
+            Err(err) if is_temporary_error(&err) && draw_failures < MAX_DRAW_FAILURES => {
+                warn!("Failed to draw, retrying: {err}");
+                draw_failures += 1;
+                full_redraw = true;
+                wait_duration = Duration::from_millis(50);
+                continue;
+            }
+            Err(err) => return Err(err.into()),
+        }
 
-        // Allow popups like the fetch animation to update every 100ms, if there is no popup, just
-        // wait for an incoming event
-        wait_duration = if app.popup.is_none() {
-            Duration::MAX
-        } else {
+        // Allow popups like the fetch animation and background work, like the diagnostics run on
+        // startup or the working copy summary, to update every 100ms, otherwise just wait for an incoming event, the next auto
+        // refresh or the next check for operations of other processes
+        wait_duration = if app.is_busy() || app.background_pending() {
             Duration::from_millis(100)
+        } else {
+            app.time_until_auto_refresh()
+                .unwrap_or(Duration::MAX)
+                .min(OP_HEADS_POLL_INTERVAL)
         };
+        // A command run while drawing was shown over the UI
+        if HUNG_COMMAND_SHOWN.load(Ordering::Relaxed) {
+            wait_duration = Duration::ZERO;
+        }
     }
 }
 
-fn setup_terminal() -> Result<Terminal<CrosstermBackend<io::Stdout>>> {
+/// Clone `url` into `destination` with `jj git clone`, showing jj's progress in a loader popup.
+/// If the clone fails, the error is shown until a key is pressed.
+fn clone_repository(jj_bin: &str, url: &str, destination: &str) -> Result<()> {
+    // There is no repository yet, run jj in the current directory without a config
+    let env = Env::without_repository(
+        current_dir()?.to_string_lossy().to_string(),
+        jj_bin.to_owned(),
+    );
+    let mut commander = Commander::new(&env);
+
+    let mut terminal = setup_terminal()?;
+    let res = run_clone(&mut terminal, &mut commander, url, destination);
+    suspend_terminal(&mut terminal)?;
+    res
+}
+
+fn run_clone<B: Backend>(
+    terminal: &mut Terminal<B>,
+    commander: &mut Commander,
+    url: &str,
+    destination: &str,
+) -> Result<()> {
+    let (progress_tx, progress_rx) = mpsc::channel();
+    let commander_clone = Commander::new(&commander.env);
+    let (url_clone, destination_clone) = (url.to_owned(), destination.to_owned());
+    let mut loader = LoaderPopup::new(format!("Cloning {url}"), move || {
+        commander_clone.git_clone(&url_clone, &destination_clone, progress_tx)
+    })
+    .with_progress(progress_rx)
+    .cancellable();
+    // Only a directory created by the clone is removed when it is cancelled
+    let existed = Path::new(destination).exists();
+
+    let mut popup: Box<dyn Component> = loop {
+        while event::poll(Duration::ZERO)? {
+            // Ctrl+C cancels like Esc, there is no app to quit yet
+            let event = match event::read()? {
+                Event::Key(key)
+                    if key.modifiers.contains(KeyModifiers::CONTROL)
+                        && key.code == KeyCode::Char('c') =>
+                {
+                    Event::Key(KeyCode::Esc.into())
+                }
+                event => event,
+            };
+            if let ComponentInputResult::HandledAction(ComponentAction::SetPopup(None)) =
+                loader.input(commander, event)?
+            {
+                if !existed {
+                    let _ = remove_dir_all(destination);
+                }
+                bail!("Cancelled cloning {url}");
+            }
+        }
+
+        match loader.update(commander)? {
+            // The operation succeeded and asks to refresh the (not yet existing) tab
+            Some(ComponentAction::Multiple(_)) => return Ok(()),
+            Some(ComponentAction::SetPopup(Some(popup))) => break popup,
+            _ => {}
+        }
+
+        terminal.draw(|f| {
+            let _ = loader.draw(f, f.area());
+        })?;
+        std::thread::sleep(Duration::from_millis(100));
+    };
+
+    loop {
+        terminal.draw(|f| {
+            let _ = popup.draw(f, f.area());
+        })?;
+        if let Event::Key(_) = event::read()? {
+            bail!("Could not clone {url}");
+        }
+    }
+}
+
+/// Suspend the TUI and run a jj command attached to the terminal, e.g. so git can prompt for
+/// credentials. Waits for Enter before returning to the TUI.
+fn run_in_terminal<B: Backend>(
+    terminal: &mut Terminal<B>,
+    commander: &Commander,
+    args: &[String],
+) -> Result<()> {
+    suspend_terminal(terminal)?;
+
+    let mut output = ui_output();
+    writeln!(output, "$ jj {}", args.join(" "))?;
+    match commander.execute_interactive_jj_command(args, UI_ON_STDERR.load(Ordering::Relaxed)) {
+        Ok(status) => writeln!(output, "\njj exited with {status}")?,
+        Err(err) => writeln!(output, "\n{err}")?,
+    }
+    writeln!(output, "Press Enter to return to blazingjj")?;
+    io::stdin().read_line(&mut String::new())?;
+
+    reopen_terminal(terminal)
+}
+
+/// Suspend the TUI and show `text` in the configured pager until it exits
+fn run_pager<B: Backend>(
+    terminal: &mut Terminal<B>,
+    commander: &Commander,
+    text: &str,
+) -> Result<()> {
+    suspend_terminal(terminal)?;
+
+    let pager = commander.env.config.pager();
+    let mut command = shell_command(&pager);
+    command
+        .current_dir(&commander.env.root)
+        .stdin(Stdio::piped());
+    if UI_ON_STDERR.load(Ordering::Relaxed) {
+        command.stdout(io::stderr());
+    }
+    let result = command.spawn().and_then(|mut child| {
+        if let Some(mut stdin) = child.stdin.take() {
+            // The pager may exit before reading everything
+            let _ = stdin.write_all(text.as_bytes());
+        }
+        child.wait()
+    });
+    let failure = match result {
+        Ok(status) if status.success() => None,
+        Ok(status) => Some(format!("{pager} exited with {status}")),
+        Err(err) => Some(format!("Failed to run {pager}: {err}")),
+    };
+    if let Some(failure) = failure {
+        let mut output = ui_output();
+        writeln!(output, "\n{failure}")?;
+        writeln!(output, "Press Enter to return to blazingjj")?;
+        io::stdin().read_line(&mut String::new())?;
+    }
+
+    reopen_terminal(terminal)
+}
+
+fn setup_terminal() -> Result<Terminal<UiBackend>> {
+    resume_terminal()?;
+    let backend = UiBackend::new(ui_output());
+    let viewport = match INLINE_HEIGHT.load(Ordering::Relaxed) {
+        0 => Viewport::Fullscreen,
+        // Shrunk to the terminal by ratatui, scrolling the prompt up to make room below it
+        height => Viewport::Inline(height),
+    };
+    let mut terminal = Terminal::with_options(backend, TerminalOptions { viewport })?;
+    INLINE_TOP.store(terminal.get_frame().area().y, Ordering::Relaxed);
+    Ok(terminal)
+}
+
+/// Restore the terminal, e.g. to run a command in it or on exit. The inline viewport is cleared
+/// and the output continues where it started.
+fn suspend_terminal<B: Backend>(terminal: &mut Terminal<B>) -> Result<()> {
+    if INLINE_HEIGHT.load(Ordering::Relaxed) > 0 {
+        let top = terminal.get_frame().area().as_position();
+        terminal.clear()?;
+        terminal.set_cursor_position(top)?;
+    }
+    restore_terminal()
+}
+
+/// Resume the TUI after [suspend_terminal]. The inline viewport is placed below the output of
+/// the command.
+fn reopen_terminal<B: Backend>(terminal: &mut Terminal<B>) -> Result<()> {
+    resume_terminal()?;
+    if INLINE_HEIGHT.load(Ordering::Relaxed) > 0 {
+        let size = terminal.size()?;
+        terminal.resize(Rect::new(0, 0, size.width, size.height))?;
+        INLINE_TOP.store(terminal.get_frame().area().y, Ordering::Relaxed);
+    }
+    terminal.clear()?;
+    Ok(())
+}
+
+fn resume_terminal() -> Result<()> {
     enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(
-        stdout,
-        EnterAlternateScreen,
-        EnableMouseCapture,
-        EnableFocusChange
-    )?;
+    let mut output = ui_output();
+    if INLINE_HEIGHT.load(Ordering::Relaxed) == 0 {
+        execute!(output, EnterAlternateScreen)?;
+    }
+    execute!(output, EnableFocusChange, EnableBracketedPaste)?;
+    if MOUSE_CAPTURE.load(Ordering::Relaxed) {
+        execute!(output, EnableMouseCapture)?;
+    }
 
-    if supports_keyboard_enhancement()? {
+    // The query fails on some terminals, like the legacy Windows console, which report keys
+    // natively or without modifiers instead. It is written to stdout, which captures the
+    // selection printed on exit when it isn't a terminal.
+    let keyboard_enhancement =
+        io::stdout().is_terminal() && supports_keyboard_enhancement().unwrap_or(false);
+    KEYBOARD_ENHANCEMENT.store(keyboard_enhancement, Ordering::Relaxed);
+    if keyboard_enhancement {
         execute!(
-            stdout,
+            output,
             // required to properly detect ctrl+shift
             PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES)
         )?;
     }
 
-    let backend = CrosstermBackend::new(stdout);
-    Ok(Terminal::new(backend)?)
+    Ok(())
 }
 
 fn restore_terminal() -> Result<()> {
     disable_raw_mode()?;
-    let mut stdout = io::stdout();
+    let mut output = ui_output();
+    if INLINE_HEIGHT.load(Ordering::Relaxed) == 0 {
+        execute!(output, LeaveAlternateScreen)?;
+    }
     execute!(
-        stdout,
-        LeaveAlternateScreen,
+        output,
         DisableMouseCapture,
-        DisableFocusChange
+        DisableFocusChange,
+        DisableBracketedPaste
     )?;
 
-    if supports_keyboard_enhancement()? {
-        execute!(stdout, PopKeyboardEnhancementFlags)?;
+    if KEYBOARD_ENHANCEMENT.swap(false, Ordering::Relaxed) {
+        execute!(output, PopKeyboardEnhancementFlags)?;
     }
 
     Ok(())
@@ -226,10 +760,26 @@ fn restore_terminal() -> Result<()> {
 fn install_panic_hook() {
     let original_hook = std::panic::take_hook();
     std::panic::set_hook(Box::new(move |info| {
+        // Clear the inline viewport, like on exit, so the message starts where blazingjj started
+        if INLINE_HEIGHT.load(Ordering::Relaxed) > 0 {
+            let top = INLINE_TOP.load(Ordering::Relaxed);
+            let _ = execute!(
+                ui_output(),
+                MoveTo(0, top),
+                Clear(ClearType::FromCursorDown)
+            );
+        }
         if let Err(err) = restore_terminal() {
             eprintln!("Failed to restore terminal: {err}");
         }
         original_hook(info);
+        match crash_report::write_crash_report(info) {
+            Ok(path) => eprintln!(
+                "A crash report was written to {}\nPlease attach it when reporting this issue",
+                path.display()
+            ),
+            Err(err) => eprintln!("Failed to write crash report: {err}"),
+        }
     }));
 }
 
@@ -0,0 +1,232 @@
+/*!
+Running a single action without the TUI, e.g. `blazingjj --command fetch` or
+`blazingjj --command "push --bookmark main"`.
+
+The actions go through the same [Commander] functions as in the TUI, so
+hooks, the push test command and read-only mode apply, which makes them
+usable from scripts and editor tasks. The output of jj is printed instead of
+shown in a popup, and blazingjj exits with the exit code of jj.
+*/
+use std::io::{self, IsTerminal};
+
+use anyhow::{Result, bail};
+use clap::{ArgGroup, Parser};
+
+use crate::commander::{CommandError, Commander};
+
+/// Exit code for an action which couldn't be parsed, same as for invalid arguments
+const USAGE_EXIT_CODE: i32 = 2;
+
+#[derive(Parser, Debug, PartialEq, Eq)]
+#[command(name = "--command", no_binary_name = true)]
+pub enum Action {
+    /// Fetch from the git remotes
+    Fetch {
+        /// Fetch from all remotes instead of the default ones
+        #[arg(long)]
+        all_remotes: bool,
+    },
+    /// Push to the git remote, after running `blazingjj.push-test-command`
+    #[command(group(ArgGroup::new("target").required(true).args(["revision", "bookmark", "all"])))]
+    Push {
+        /// Push the bookmarks pointing to the revision
+        #[arg(short, long)]
+        revision: Option<String>,
+        /// Push the bookmark
+        #[arg(short, long)]
+        bookmark: Option<String>,
+        /// Push all bookmarks
+        #[arg(long)]
+        all: bool,
+        /// Allow pushing bookmarks which are new on the remote
+        #[arg(long)]
+        allow_new: bool,
+        /// Don't run `blazingjj.push-test-command`
+        #[arg(long)]
+        no_test: bool,
+    },
+    /// Create a new change on top of the revision
+    New {
+        #[arg(default_value = "@")]
+        revision: String,
+    },
+    /// Edit the revision
+    Edit {
+        revision: String,
+        #[arg(long)]
+        ignore_immutable: bool,
+    },
+    /// Set the description of the revision
+    Describe {
+        #[arg(default_value = "@")]
+        revision: String,
+        #[arg(short, long)]
+        message: String,
+    },
+    /// Abandon the revision
+    Abandon {
+        #[arg(default_value = "@")]
+        revision: String,
+    },
+}
+
+impl Action {
+    /// Run the action, returning the output of jj
+    fn run(&self, commander: &Commander) -> Result<String> {
+        match self {
+            Action::Fetch { all_remotes } => Ok(commander.git_fetch(*all_remotes)?),
+            Action::Push {
+                revision,
+                bookmark,
+                all: _,
+                allow_new,
+                no_test,
+            } => {
+                let commit_id = match revision {
+                    Some(revision) => Some(commander.get_head(revision)?.commit_id),
+                    None => None,
+                };
+                if !no_test {
+                    match commander.run_push_test(commit_id.as_ref()) {
+                        Err(CommandError::TestsFailed(output)) => {
+                            bail!(
+                                "Tests failed, nothing was pushed. Add --no-test to push anyway.\n\n{output}"
+                            )
+                        }
+                        result => result?,
+                    }
+                }
+                Ok(match (bookmark, commit_id) {
+                    (Some(bookmark), _) => commander.git_push_bookmark(bookmark, *allow_new)?,
+                    (None, Some(commit_id)) => commander.git_push(false, *allow_new, &commit_id)?,
+                    // --all, the commit is ignored
+                    (None, None) => {
+                        let head = commander.get_current_head()?;
+                        commander.git_push(true, *allow_new, &head.commit_id)?
+                    }
+                })
+            }
+            Action::New { revision } => {
+                commander.run_new(revision)?;
+                Ok(String::new())
+            }
+            Action::Edit {
+                revision,
+                ignore_immutable,
+            } => {
+                commander.run_edit(revision, *ignore_immutable)?;
+                Ok(String::new())
+            }
+            Action::Describe { revision, message } => {
+                commander.run_describe(revision, message)?;
+                Ok(String::new())
+            }
+            Action::Abandon { revision } => {
+                let head = commander.get_head(revision)?;
+                commander.run_abandon(&head.commit_id)?;
+                Ok(String::new())
+            }
+        }
+    }
+}
+
+/// Exit code of jj if the error comes from a jj command, 1 otherwise
+fn exit_code(err: &anyhow::Error) -> i32 {
+    err.chain()
+        .find_map(|cause| match cause.downcast_ref::<CommandError>() {
+            Some(CommandError::Status(_, Some(code))) => Some(*code),
+            _ => None,
+        })
+        .unwrap_or(1)
+}
+
+/// Parse and run the action of `--command`, printing its output. Returns the exit code.
+/// The output of jj is colored only if it goes to a terminal.
+pub fn run(commander: &mut Commander, command: &str) -> i32 {
+    let words = match shell_words::split(command) {
+        Ok(words) => words,
+        Err(err) => {
+            eprintln!("Error parsing the command: {err}");
+            return USAGE_EXIT_CODE;
+        }
+    };
+    let action = match Action::try_parse_from(words) {
+        Ok(action) => action,
+        Err(err) => {
+            let _ = err.print();
+            return err.exit_code();
+        }
+    };
+
+    if !io::stdout().is_terminal() {
+        commander.force_no_color = true;
+    }
+    match action.run(commander) {
+        Ok(output) => {
+            print!("{output}");
+            0
+        }
+        Err(err) => {
+            eprintln!("{err:#}");
+            exit_code(&err)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commander::tests::TestRepo;
+
+    fn parse(command: &str) -> Result<Action, clap::Error> {
+        Action::try_parse_from(shell_words::split(command).unwrap())
+    }
+
+    #[test]
+    fn parse_actions() {
+        assert_eq!(
+            parse("fetch").unwrap(),
+            Action::Fetch { all_remotes: false }
+        );
+        assert_eq!(
+            parse("push --bookmark main").unwrap(),
+            Action::Push {
+                revision: None,
+                bookmark: Some("main".to_owned()),
+                all: false,
+                allow_new: false,
+                no_test: false,
+            }
+        );
+        assert_eq!(
+            parse("describe -m 'Fix the thing'").unwrap(),
+            Action::Describe {
+                revision: "@".to_owned(),
+                message: "Fix the thing".to_owned(),
+            }
+        );
+        // A push needs a target, and only one
+        assert!(parse("push").is_err());
+        assert!(parse("push --all -r @-").is_err());
+        assert!(parse("rebase").is_err());
+    }
+
+    #[test]
+    fn run_new() -> Result<()> {
+        let mut test_repo = TestRepo::new()?;
+        let head = test_repo.commander.get_current_head()?;
+
+        assert_eq!(run(&mut test_repo.commander, "new"), 0);
+        let new_head = test_repo.commander.get_current_head()?;
+        assert_ne!(new_head.change_id, head.change_id);
+        Ok(())
+    }
+
+    #[test]
+    fn jj_exit_code() -> Result<()> {
+        let mut test_repo = TestRepo::new()?;
+        assert_ne!(run(&mut test_repo.commander, "edit does-not-exist"), 0);
+        assert_eq!(run(&mut test_repo.commander, "frobnicate"), USAGE_EXIT_CODE);
+        Ok(())
+    }
+}
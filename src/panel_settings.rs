@@ -0,0 +1,135 @@
+/*!
+Diff format, wrapping and size of the details panel of each tab.
+
+They are stored per tab in `.jj/blazingjj/panels.toml` in the repository, so
+that e.g. the log can show the git diff of changes while the files tab keeps
+the color words diff of files, across sessions. The file is read before every
+change, as each tab saves its own settings.
+*/
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::env::DiffFormat;
+
+/// Tab whose details panel the settings are for
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Panel {
+    Log,
+    Files,
+    Bookmarks,
+}
+
+impl Panel {
+    fn key(&self) -> &'static str {
+        match self {
+            Panel::Log => "log",
+            Panel::Files => "files",
+            Panel::Bookmarks => "bookmarks",
+        }
+    }
+}
+
+/// Settings changed in a panel, unset ones come from the config
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub struct PanelSettings {
+    pub diff_format: Option<DiffFormat>,
+    pub wrap: Option<bool>,
+    /// Percentage of the tab used by the main panel, see `blazingjj.layout-percent`
+    pub layout_percent: Option<u16>,
+}
+
+fn settings_path(root: &str) -> PathBuf {
+    Path::new(root)
+        .join(".jj")
+        .join("blazingjj")
+        .join("panels.toml")
+}
+
+/// Settings of all panels by [Panel::key]. A missing or invalid file is treated as no settings.
+fn load_all(path: &Path) -> BTreeMap<String, PanelSettings> {
+    match fs::read_to_string(path) {
+        Ok(content) => toml::from_str(&content).unwrap_or_else(|err| {
+            warn!("Ignoring invalid panel settings {}: {err}", path.display());
+            BTreeMap::new()
+        }),
+        Err(_) => BTreeMap::new(),
+    }
+}
+
+/// Load the settings of a panel in the repository at `root`
+pub fn load(root: &str, panel: Panel) -> PanelSettings {
+    load_all(&settings_path(root))
+        .remove(panel.key())
+        .unwrap_or_default()
+}
+
+/// Change the settings of a panel in the repository at `root` and save them
+pub fn update(root: &str, panel: Panel, f: impl FnOnce(&mut PanelSettings)) -> Result<()> {
+    let path = settings_path(root);
+    let mut all = load_all(&path);
+    f(all.entry(panel.key().to_owned()).or_default());
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, toml::to_string(&all)?)
+        .with_context(|| format!("Failed saving panel settings {}", path.display()))
+}
+
+/// Save a setting of a panel, logging failures as the setting still applies to this session
+pub fn save(root: &str, panel: Panel, f: impl FnOnce(&mut PanelSettings)) {
+    if let Err(err) = update(root, panel, f) {
+        warn!("{err:?}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    #[test]
+    fn panels_are_independent() -> Result<()> {
+        let directory = TempDir::new("blazingjj")?;
+        let root = directory.path().to_string_lossy().to_string();
+
+        assert_eq!(load(&root, Panel::Log), PanelSettings::default());
+
+        update(&root, Panel::Log, |settings| {
+            settings.diff_format = Some(DiffFormat::Git)
+        })?;
+        update(&root, Panel::Files, |settings| settings.wrap = Some(false))?;
+        update(&root, Panel::Log, |settings| settings.wrap = Some(true))?;
+        update(&root, Panel::Files, |settings| {
+            settings.layout_percent = Some(35)
+        })?;
+
+        assert_eq!(
+            load(&root, Panel::Log),
+            PanelSettings {
+                diff_format: Some(DiffFormat::Git),
+                wrap: Some(true),
+                layout_percent: None,
+            }
+        );
+        assert_eq!(
+            load(&root, Panel::Files),
+            PanelSettings {
+                diff_format: None,
+                wrap: Some(false),
+                layout_percent: Some(35),
+            }
+        );
+        assert_eq!(load(&root, Panel::Bookmarks), PanelSettings::default());
+
+        Ok(())
+    }
+}
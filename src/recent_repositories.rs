@@ -0,0 +1,121 @@
+/*!
+Repositories recently opened with blazingjj, offered by the repository switcher.
+
+They are stored for the user in `$XDG_STATE_HOME/blazingjj/repositories.toml`,
+defaulting to `~/.local/state/blazingjj/repositories.toml`, so that they are
+offered across sessions. The file is read again before every change, as other
+instances may have opened repositories meanwhile.
+*/
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+/// Number of repositories kept, the least recently opened is dropped first
+const MAX_REPOSITORIES: usize = 20;
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct RecentRepositories {
+    /// Roots of the repositories, most recent first
+    repositories: Vec<String>,
+}
+
+fn recent_repositories_path() -> Option<PathBuf> {
+    let state_dir = std::env::var_os("XDG_STATE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("state"))
+        })?;
+    Some(state_dir.join("blazingjj").join("repositories.toml"))
+}
+
+/// Roots of the repositories, most recent first. A missing or invalid file is treated as none.
+fn load_from(path: &Path) -> Vec<String> {
+    match fs::read_to_string(path) {
+        Ok(content) => toml::from_str::<RecentRepositories>(&content)
+            .map(|recent| recent.repositories)
+            .unwrap_or_else(|err| {
+                warn!(
+                    "Ignoring invalid recent repositories {}: {err}",
+                    path.display()
+                );
+                vec![]
+            }),
+        Err(_) => vec![],
+    }
+}
+
+/// Move `root` first in the repositories at `path`
+fn with_root_first(path: &Path, root: &str) -> Vec<String> {
+    let mut repositories = load_from(path);
+    repositories.retain(|repository| repository != root);
+    repositories.insert(0, root.to_owned());
+    repositories.truncate(MAX_REPOSITORIES);
+    repositories
+}
+
+/// Move `root` first in the repositories at `path` and save them
+fn record_in(path: &Path, root: &str) -> Result<Vec<String>> {
+    let repositories = with_root_first(path, root);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let recent = RecentRepositories { repositories };
+    fs::write(path, toml::to_string(&recent)?)
+        .with_context(|| format!("Failed saving recent repositories {}", path.display()))?;
+    Ok(recent.repositories)
+}
+
+/// Record the repository at `root` as the most recently opened one, returning the roots of the
+/// recent repositories. Failures are logged, as the repository is still remembered for the session.
+pub fn record(root: &str) -> Vec<String> {
+    let Some(path) = recent_repositories_path() else {
+        return vec![root.to_owned()];
+    };
+    record_in(&path, root).unwrap_or_else(|err| {
+        warn!("{err:?}");
+        with_root_first(&path, root)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    #[test]
+    fn most_recent_first() -> Result<()> {
+        let directory = TempDir::new("blazingjj")?;
+        let path = directory.path().join("state").join("repositories.toml");
+
+        assert_eq!(load_from(&path), Vec::<String>::new());
+
+        record_in(&path, "/a")?;
+        record_in(&path, "/b")?;
+        assert_eq!(record_in(&path, "/a")?, vec!["/a", "/b"]);
+        assert_eq!(load_from(&path), vec!["/a", "/b"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn oldest_are_dropped() -> Result<()> {
+        let directory = TempDir::new("blazingjj")?;
+        let path = directory.path().join("repositories.toml");
+
+        for i in 0..MAX_REPOSITORIES + 2 {
+            record_in(&path, &format!("/{i}"))?;
+        }
+        let repositories = load_from(&path);
+        assert_eq!(repositories.len(), MAX_REPOSITORIES);
+        assert_eq!(repositories.first().map(String::as_str), Some("/21"));
+        assert_eq!(repositories.last().map(String::as_str), Some("/2"));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,109 @@
+/*!
+Files marked as viewed in the review mode of the [files tab][crate::ui::files_tab].
+
+The viewed files are stored per commit id in `.jj/blazingjj/reviewed.toml` in
+the repository, so they are kept across sessions. Rewriting a commit starts its
+review over, like a force push does on GitHub.
+*/
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use tracing::warn;
+
+#[derive(Debug)]
+pub struct ReviewState {
+    path: PathBuf,
+    /// Viewed file paths by commit id
+    viewed: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl ReviewState {
+    /// Load the review state of the repository at `root`. A missing or invalid file is treated
+    /// as no file being viewed.
+    pub fn load(root: &str) -> Self {
+        let path = Path::new(root)
+            .join(".jj")
+            .join("blazingjj")
+            .join("reviewed.toml");
+        let viewed = match fs::read_to_string(&path) {
+            Ok(content) => toml::from_str(&content).unwrap_or_else(|err| {
+                warn!("Ignoring invalid review state {}: {err}", path.display());
+                BTreeMap::new()
+            }),
+            Err(_) => BTreeMap::new(),
+        };
+        Self { path, viewed }
+    }
+
+    pub fn is_viewed(&self, commit_id: &str, path: &str) -> bool {
+        self.viewed
+            .get(commit_id)
+            .is_some_and(|paths| paths.contains(path))
+    }
+
+    /// Number of `paths` viewed in the commit
+    pub fn viewed_count<'a>(
+        &self,
+        commit_id: &str,
+        paths: impl IntoIterator<Item = &'a str>,
+    ) -> usize {
+        paths
+            .into_iter()
+            .filter(|path| self.is_viewed(commit_id, path))
+            .count()
+    }
+
+    /// Toggle whether a file is viewed and save the state
+    pub fn toggle_viewed(&mut self, commit_id: &str, path: &str) -> Result<()> {
+        let paths = self.viewed.entry(commit_id.to_owned()).or_default();
+        if !paths.remove(path) {
+            paths.insert(path.to_owned());
+        }
+        if paths.is_empty() {
+            self.viewed.remove(commit_id);
+        }
+        self.save()
+    }
+
+    fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, toml::to_string(&self.viewed)?)
+            .with_context(|| format!("Failed saving review state {}", self.path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    #[test]
+    fn toggle_and_reload() -> Result<()> {
+        let directory = TempDir::new("blazingjj")?;
+        let root = directory.path().to_string_lossy().to_string();
+
+        let mut state = ReviewState::load(&root);
+        assert!(!state.is_viewed("abc", "src/main.rs"));
+
+        state.toggle_viewed("abc", "src/main.rs")?;
+        state.toggle_viewed("abc", "README.md")?;
+        state.toggle_viewed("def", "README.md")?;
+        state.toggle_viewed("def", "README.md")?;
+
+        let state = ReviewState::load(&root);
+        assert!(state.is_viewed("abc", "src/main.rs"));
+        assert!(!state.is_viewed("def", "README.md"));
+        assert_eq!(
+            state.viewed_count("abc", ["src/main.rs", "README.md", "Cargo.toml"]),
+            2
+        );
+
+        Ok(())
+    }
+}
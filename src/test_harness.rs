@@ -0,0 +1,413 @@
+/*!
+Harness for UI tests, driving the [App] the way the main loop does and
+rendering it into a [TestBackend] instead of a terminal.
+
+An [AppHarness] wraps a [TestRepo], so jj runs for real against a temporary
+repository. The [fixtures] build repositories of the common log shapes. Outputs
+jj can't produce in a test, like a fetch failing because the remote is
+unreachable, are scripted with [AppHarness::script]: the harness sets a
+[CommandRunner] in the environment of the repository, so commands starting with
+the scripted arguments get the canned result instead of being run, including
+commands of background work which uses a commander of its own.
+
+[AppHarness::render] returns the screen as text with ids, timestamps and the
+name of the temporary repository replaced by placeholders, so it can be
+compared with `insta::assert_snapshot!`. The crate has no library target, so
+the harness is only compiled for its own tests.
+*/
+use std::{
+    ffi::OsStr,
+    path::Path,
+    process::{Command, ExitStatus, Output},
+    str::FromStr,
+    sync::{Arc, LazyLock, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Result, bail};
+use ratatui::{Terminal, backend::TestBackend, crossterm::event::Event};
+use regex::Regex;
+
+use crate::{
+    app::App,
+    commander::{CommandError, running::CommandRunner, tests::TestRepo},
+    keybinds::Shortcut,
+    ui::ui,
+};
+
+/// How long background work like a fetch may take before a test fails
+const SETTLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A command with a canned result, see [AppHarness::script]
+#[derive(Debug)]
+struct ScriptedCommand {
+    args: Vec<String>,
+    /// Stdout of the command, or stderr if it fails
+    result: Result<String, String>,
+}
+
+/// Runner answering the scripted commands, running all others
+#[derive(Debug, Default)]
+struct ScriptedRunner {
+    scripts: Mutex<Vec<ScriptedCommand>>,
+}
+
+impl CommandRunner for ScriptedRunner {
+    fn output(&self, command: &Command) -> Option<Result<Output, CommandError>> {
+        let scripts = self.scripts.lock().unwrap();
+        let args: Vec<&OsStr> = command.get_args().collect();
+        let scripted = scripts.iter().find(|scripted| {
+            scripted.args.len() <= args.len()
+                && scripted
+                    .args
+                    .iter()
+                    .zip(&args)
+                    .all(|(scripted_arg, arg)| OsStr::new(scripted_arg) == *arg)
+        })?;
+        Some(match &scripted.result {
+            Ok(stdout) => Ok(Output {
+                status: ExitStatus::default(),
+                stdout: stdout.clone().into_bytes(),
+                stderr: vec![],
+            }),
+            Err(stderr) => Err(CommandError::Status(stderr.clone(), Some(1))),
+        })
+    }
+}
+
+/// Placeholders for the parts of the screen which differ between runs
+static NORMALIZATIONS: LazyLock<Vec<(Regex, &str)>> = LazyLock::new(|| {
+    [
+        (r"\d{4}-\d{2}-\d{2} \d{2}:\d{2}:\d{2}", "[TIMESTAMP]"),
+        (r"\d+ (second|minute)s? ago", "[AGE]"),
+        (r"\b\d+ms\b", "[DURATION]"),
+        (r"\b([k-z]{32}|[k-z]{8})\b", "[CHANGE_ID]"),
+        (r"\b([0-9a-f]{40}|[0-9a-f]{8})\b", "[COMMIT_ID]"),
+    ]
+    .into_iter()
+    .map(|(regex, placeholder)| (Regex::new(regex).unwrap(), placeholder))
+    .collect()
+});
+
+pub struct AppHarness<'a> {
+    pub test_repo: TestRepo,
+    pub app: App<'a>,
+    terminal: Terminal<TestBackend>,
+    runner: Arc<ScriptedRunner>,
+}
+
+impl AppHarness<'_> {
+    pub fn new(test_repo: TestRepo) -> Result<Self> {
+        // Wide enough for the ids and timestamps not to be cut off or wrapped
+        Self::with_size(test_repo, 160, 30)
+    }
+
+    pub fn with_size(mut test_repo: TestRepo, width: u16, height: u16) -> Result<Self> {
+        let runner = Arc::new(ScriptedRunner::default());
+        test_repo.commander.env.command_runner = Some(runner.clone());
+        let app = App::new(test_repo.commander.env.clone())?;
+        let mut harness = Self {
+            test_repo,
+            app,
+            terminal: Terminal::new(TestBackend::new(width, height))?,
+            runner,
+        };
+        harness.settle()?;
+        Ok(harness)
+    }
+
+    /// Give commands starting with `args`, e.g. `["git", "fetch"]`, a canned result instead of
+    /// running them: their stdout, or their stderr if they fail
+    pub fn script(&self, args: &[&str], result: Result<&str, &str>) {
+        self.runner.scripts.lock().unwrap().push(ScriptedCommand {
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            result: result.map(str::to_owned).map_err(str::to_owned),
+        });
+    }
+
+    /// Press the keys, separated by spaces in the format of the keybind config, e.g. `j j enter`
+    /// or `ctrl+p`. Waits for background work started by each key.
+    pub fn press(&mut self, keys: &str) -> Result<()> {
+        for key in keys.split_whitespace() {
+            let event = Event::Key(Shortcut::from_str(key)?.key_event());
+            if self.app.input(event, &mut self.test_repo.commander)? {
+                bail!("The app quit after {key}");
+            }
+            self.settle()?;
+        }
+        Ok(())
+    }
+
+    /// Update and draw the app until neither the popup nor the tab waits for background work
+    fn settle(&mut self) -> Result<()> {
+        let start = Instant::now();
+        loop {
+            self.app.update(&mut self.test_repo.commander)?;
+            let mut result = Ok(());
+            self.terminal.draw(|f| result = ui(f, &mut self.app))?;
+            result?;
+
+            let busy = self.app.popup.as_ref().is_some_and(|popup| popup.is_busy())
+                || self.app.get_current_tab().is_some_and(|tab| tab.is_busy())
+                || self.app.background_pending();
+            if !busy {
+                return Ok(());
+            }
+            if start.elapsed() > SETTLE_TIMEOUT {
+                bail!("The app was still busy after {SETTLE_TIMEOUT:?}");
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    /// The screen as text, with the parts which differ between runs replaced by placeholders
+    pub fn render(&self) -> String {
+        let buffer = self.terminal.backend().buffer();
+        let screen = buffer
+            .content
+            .chunks(buffer.area.width as usize)
+            .map(|row| {
+                row.iter()
+                    .map(|cell| cell.symbol())
+                    .collect::<String>()
+                    .trim_end()
+                    .to_owned()
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let root = &self.test_repo.commander.env.root;
+        let repository = Path::new(root)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or(root);
+        NORMALIZATIONS.iter().fold(
+            screen.replace(repository, "[REPO]"),
+            |screen, (regex, placeholder)| regex.replace_all(&screen, *placeholder).into_owned(),
+        )
+    }
+}
+
+/// Repositories of the common log shapes
+pub mod fixtures {
+    use std::fs;
+
+    use anyhow::Result;
+
+    use crate::commander::tests::TestRepo;
+
+    /// Only the empty working copy on the root
+    pub fn empty() -> Result<TestRepo> {
+        TestRepo::new()
+    }
+
+    /// The changes `first`, `second` and `third` on top of each other, with `third` as the
+    /// working copy
+    pub fn linear() -> Result<TestRepo> {
+        let test_repo = TestRepo::new()?;
+        let path = test_repo.directory.path().join("README");
+        for (i, description) in ["first", "second", "third"].into_iter().enumerate() {
+            if i > 0 {
+                test_repo.commander.execute_void_jj_command(["new"])?;
+            }
+            fs::write(&path, description)?;
+            test_repo
+                .commander
+                .execute_void_jj_command(["describe", "-m", description])?;
+        }
+        Ok(test_repo)
+    }
+
+    /// The changes `left` and `right` on `base`, merged by the working copy `merge`
+    pub fn merge() -> Result<TestRepo> {
+        let test_repo = TestRepo::new()?;
+        let commander = &test_repo.commander;
+        let directory = test_repo.directory.path();
+
+        let base = commander.get_current_head()?;
+        fs::write(directory.join("README"), "base")?;
+        commander.execute_void_jj_command(["describe", "-m", "base"])?;
+
+        commander.run_new(base.change_id.as_str())?;
+        let left = commander.get_current_head()?;
+        fs::write(directory.join("left"), "left")?;
+        commander.execute_void_jj_command(["describe", "-m", "left"])?;
+
+        commander.run_new(base.change_id.as_str())?;
+        let right = commander.get_current_head()?;
+        fs::write(directory.join("right"), "right")?;
+        commander.execute_void_jj_command(["describe", "-m", "right"])?;
+
+        commander.execute_void_jj_command([
+            "new",
+            left.change_id.as_str(),
+            right.change_id.as_str(),
+            "-m",
+            "merge",
+        ])?;
+        Ok(test_repo)
+    }
+
+    /// The working copy `conflicted`, rebased onto `other` which changed the same file
+    pub fn conflict() -> Result<TestRepo> {
+        let test_repo = TestRepo::new()?;
+        let commander = &test_repo.commander;
+        let path = test_repo.directory.path().join("README");
+
+        let base = commander.get_current_head()?;
+        commander.run_new(base.change_id.as_str())?;
+        let other = commander.get_current_head()?;
+        fs::write(&path, "AAA")?;
+        commander.execute_void_jj_command(["describe", "-m", "other"])?;
+
+        commander.run_new(base.change_id.as_str())?;
+        let conflicted = commander.get_current_head()?;
+        fs::write(&path, "BBB")?;
+        commander.execute_void_jj_command(["describe", "-m", "conflicted"])?;
+
+        commander.execute_void_jj_command([
+            "rebase",
+            "-s",
+            conflicted.change_id.as_str(),
+            "-d",
+            other.change_id.as_str(),
+        ])?;
+        Ok(test_repo)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_log() -> Result<()> {
+        let harness = AppHarness::new(fixtures::empty()?)?;
+        insta::assert_snapshot!(harness.render());
+        Ok(())
+    }
+
+    #[test]
+    fn linear_log_selection() -> Result<()> {
+        let mut harness = AppHarness::new(fixtures::linear()?)?;
+        insta::assert_snapshot!(harness.render());
+
+        let working_copy = harness.app.selected_change_id();
+        harness.press("j")?;
+        assert_ne!(harness.app.selected_change_id(), working_copy);
+        harness.press("k")?;
+        assert_eq!(harness.app.selected_change_id(), working_copy);
+        Ok(())
+    }
+
+    #[test]
+    fn merge_log() -> Result<()> {
+        let harness = AppHarness::new(fixtures::merge()?)?;
+        insta::assert_snapshot!(harness.render());
+        Ok(())
+    }
+
+    #[test]
+    fn conflict_log() -> Result<()> {
+        let harness = AppHarness::new(fixtures::conflict()?)?;
+        insta::assert_snapshot!(harness.render());
+        Ok(())
+    }
+
+    #[test]
+    fn ctrl_x_in_files_tab_does_not_untrack() -> Result<()> {
+        let test_repo = fixtures::empty()?;
+        let directory = test_repo.directory.path();
+        std::fs::write(directory.join("secret"), "secret")?;
+        // Snapshot the file as tracked before ignoring it, so `x` could untrack it
+        test_repo.commander.get_current_head()?;
+        std::fs::write(directory.join(".gitignore"), "secret\n")?;
+
+        let mut harness = AppHarness::new(test_repo)?;
+        harness.press("2")?;
+        // The files are `.gitignore` and `secret`
+        if !harness
+            .app
+            .selection()
+            .is_some_and(|path| path.ends_with("secret"))
+        {
+            harness.press("j")?;
+        }
+        assert!(
+            harness
+                .app
+                .selection()
+                .is_some_and(|path| path.ends_with("secret"))
+        );
+        harness.press("ctrl+x")?;
+
+        let files =
+            harness
+                .test_repo
+                .commander
+                .execute_jj_command(["file", "list"], false, true)?;
+        assert!(files.lines().any(|file| file == "secret"));
+        Ok(())
+    }
+
+    #[test]
+    fn ctrl_t_toggles_the_mouse_in_every_tab() -> Result<()> {
+        let mut harness = AppHarness::new(fixtures::linear()?)?;
+        let list_files = |harness: &AppHarness| {
+            harness
+                .test_repo
+                .commander
+                .execute_jj_command(["file", "list"], false, true)
+        };
+        let files = list_files(&harness)?;
+        for tab in ["1", "2", "3"] {
+            harness.press(tab)?;
+            let mouse_capture = harness.app.mouse_capture;
+            harness.press("ctrl+t")?;
+            assert_eq!(harness.app.mouse_capture, !mouse_capture, "tab {tab}");
+            assert!(harness.app.popup.is_none(), "tab {tab}");
+        }
+        assert_eq!(list_files(&harness)?, files);
+        Ok(())
+    }
+
+    #[test]
+    fn global_keys_work_in_every_tab() -> Result<()> {
+        let mut harness = AppHarness::new(fixtures::linear()?)?;
+        for tab in ["1", "2", "3"] {
+            for (key, title) in [
+                ("shift+o", "Operations"),
+                ("shift+m", "Maintenance"),
+                (",", "Settings"),
+                ("!", "Recovery"),
+            ] {
+                harness.press(tab)?;
+                harness.press(key)?;
+                assert!(harness.render().contains(title), "{key} in tab {tab}");
+                harness.press("esc")?;
+                assert!(harness.app.popup.is_none(), "{key} in tab {tab}");
+            }
+
+            let current_tab = harness.app.current_tab;
+            harness.press("l")?;
+            assert!(harness.app.current_tab != current_tab, "l in tab {tab}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn scripted_fetch_error() -> Result<()> {
+        let mut harness = AppHarness::new(fixtures::empty()?)?;
+        harness.script(
+            &["git", "fetch"],
+            Err("ssh: Could not resolve hostname example.com: Name or service not known"),
+        );
+        harness.press("f")?;
+        insta::assert_snapshot!(harness.render());
+
+        harness.press("esc")?;
+        assert!(harness.app.popup.is_none());
+        Ok(())
+    }
+}
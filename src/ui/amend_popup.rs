@@ -0,0 +1,215 @@
+//! Popup previewing the files of the working copy which are moved into the selected change with
+//! `jj squash --from @ --into <revision>`. Files can be deselected to keep them in the working
+//! copy.
+
+use anyhow::Result;
+use ratatui::{
+    Frame,
+    crossterm::event::{Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Clear, List, ListState, Paragraph},
+};
+
+use crate::{
+    ComponentInputResult,
+    commander::{Commander, files::File, log::Head},
+    ui::{
+        Component, ComponentAction, message_popup::MessagePopup, styles::create_popup_block,
+        utils::centered_rect,
+    },
+};
+
+struct AmendFile {
+    file: File,
+    /// Whether the file is moved into the change
+    selected: bool,
+}
+
+pub struct AmendPopup {
+    target: Head,
+    files: Result<Vec<AmendFile>>,
+    list_state: ListState,
+}
+
+impl AmendPopup {
+    pub fn new(commander: &Commander, target: Head) -> Self {
+        let files = commander
+            .get_current_head()
+            .and_then(|working_copy| Ok(commander.get_files(&working_copy)?))
+            .map(|files| {
+                files
+                    .into_iter()
+                    .filter(|file| file.path.is_some())
+                    .map(|file| AmendFile {
+                        file,
+                        selected: true,
+                    })
+                    .collect()
+            });
+        Self {
+            target,
+            files,
+            list_state: ListState::default().with_selected(Some(0)),
+        }
+    }
+
+    fn selected_paths(&self) -> Vec<String> {
+        self.files
+            .as_ref()
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .filter(|file| file.selected)
+            .flat_map(|file| file.file.paths())
+            .collect()
+    }
+
+    /// Move the selected files into the change, returning a popup with the error if it failed
+    fn amend(&self, commander: &Commander) -> Result<ComponentAction> {
+        let paths = self.selected_paths();
+        if paths.is_empty() {
+            return Ok(ComponentAction::SetPopup(None));
+        }
+        let action = match commander.run_amend(self.target.commit_id.as_str(), &paths) {
+            Ok(()) => ComponentAction::SetPopup(None),
+            Err(err) => ComponentAction::SetPopup(Some(Box::new(MessagePopup {
+                title: "Amend error".into(),
+                messages: commander.error_text(&err, "")?,
+                text_align: None,
+                ..Default::default()
+            }))),
+        };
+        Ok(ComponentAction::Multiple(vec![
+            action,
+            ComponentAction::RefreshTab(),
+        ]))
+    }
+}
+
+impl Component for AmendPopup {
+    fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+        let area = centered_rect(area, 60, 50);
+        f.render_widget(Clear, area);
+
+        let block = create_popup_block("Amend");
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let [title_area, list_area, help_area] = Layout::vertical([
+            Constraint::Length(2),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+        ])
+        .areas(inner);
+
+        let title = vec![
+            Span::raw("Move from @ into "),
+            Span::raw(
+                self.target
+                    .change_id
+                    .as_str()
+                    .chars()
+                    .take(8)
+                    .collect::<String>(),
+            )
+            .magenta(),
+        ];
+        f.render_widget(Paragraph::new(Line::from(title)), title_area);
+
+        match self.files.as_ref() {
+            Ok(files) if files.is_empty() => {
+                f.render_widget(Paragraph::new("The working copy has no changes"), list_area);
+            }
+            Ok(files) => {
+                let items = files.iter().map(|file| {
+                    let color = file
+                        .file
+                        .diff_type
+                        .as_ref()
+                        .map(|diff_type| diff_type.color())
+                        .unwrap_or_default();
+                    let line = Line::from(vec![
+                        Span::raw(if file.selected { "[x] " } else { "[ ] " }),
+                        Span::raw(file.file.line.clone()).fg(color),
+                    ]);
+                    if file.selected {
+                        line
+                    } else {
+                        line.fg(Color::DarkGray)
+                    }
+                });
+                let list = List::new(items).highlight_style(Style::default().reversed());
+                f.render_stateful_widget(list, list_area, &mut self.list_state);
+            }
+            Err(err) => {
+                f.render_widget(
+                    Paragraph::new(format!("Error getting files: {err:#}")).fg(Color::Red),
+                    list_area,
+                );
+            }
+        }
+
+        f.render_widget(
+            Paragraph::new(
+                Line::from("Space: toggle | a: toggle all | Enter: amend | Esc: cancel")
+                    .fg(Color::DarkGray),
+            )
+            .centered(),
+            help_area,
+        );
+
+        Ok(())
+    }
+
+    fn input(&mut self, commander: &mut Commander, event: Event) -> Result<ComponentInputResult> {
+        let Event::Key(key) = event else {
+            return Ok(ComponentInputResult::Handled);
+        };
+        if key.kind != KeyEventKind::Press {
+            return Ok(ComponentInputResult::Handled);
+        }
+
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => self.list_state.select_next(),
+            KeyCode::Char('k') | KeyCode::Up => self.list_state.select_previous(),
+            KeyCode::Char(' ') => {
+                if let (Some(selected), Ok(files)) =
+                    (self.list_state.selected(), self.files.as_mut())
+                    && let Some(file) = files.get_mut(selected)
+                {
+                    file.selected = !file.selected;
+                }
+            }
+            KeyCode::Char('a') => {
+                if let Ok(files) = self.files.as_mut() {
+                    let selected = !files.iter().all(|file| file.selected);
+                    for file in files {
+                        file.selected = selected;
+                    }
+                }
+            }
+            KeyCode::Enter => {
+                return Ok(ComponentInputResult::HandledAction(self.amend(commander)?));
+            }
+            KeyCode::Esc | KeyCode::Char('q') => {
+                return Ok(ComponentInputResult::HandledAction(
+                    ComponentAction::SetPopup(None),
+                ));
+            }
+            _ => return Ok(ComponentInputResult::NotHandled),
+        }
+        Ok(ComponentInputResult::Handled)
+    }
+
+    fn hints(&self) -> Vec<(String, String)> {
+        vec![
+            ("j/k".to_owned(), "select".to_owned()),
+            ("Space".to_owned(), "toggle".to_owned()),
+            ("a".to_owned(), "toggle all".to_owned()),
+            ("Enter".to_owned(), "amend".to_owned()),
+            ("Esc".to_owned(), "cancel".to_owned()),
+        ]
+    }
+}
@@ -13,15 +13,16 @@ use tui_textarea::TextArea;
 use crate::{
     ComponentInputResult,
     commander::{
-        Commander,
+        CommandError, Commander,
         bookmarks::Bookmark,
         ids::{ChangeId, CommitId},
     },
     env::Config,
     ui::{
         Component, ComponentAction,
+        loader_popup::LoaderPopup,
         styles::create_popup_block,
-        utils::{centered_rect, centered_rect_line_height},
+        utils::{centered_rect, centered_rect_line_height, textarea_input},
     },
 };
 
@@ -29,6 +30,8 @@ enum BookmarkSetOption {
     CreateBookmark,
     // Name, exists
     GeneratedName(String, bool),
+    // Pattern of `blazingjj.bookmark-pattern`
+    IssueBookmark(String),
     Bookmark(Bookmark),
     Error(String),
 }
@@ -41,6 +44,8 @@ pub struct BookmarkSetPopup<'a> {
     list_height: u16,
     config: Config,
     creating: Option<TextArea<'a>>,
+    /// Whether the text being entered is an issue id for `blazingjj.bookmark-pattern`
+    creating_issue: bool,
     tx: std::sync::mpsc::Sender<bool>,
 }
 
@@ -66,6 +71,10 @@ fn generate_options(
         options.push(BookmarkSetOption::GeneratedName(generated_name, exists));
     }
 
+    if let Some(pattern) = commander.env.config.bookmark_pattern() {
+        options.push(BookmarkSetOption::IssueBookmark(pattern.to_owned()));
+    }
+
     match bookmarks.as_ref() {
         Ok(bookmarks) => {
             for bookmark in bookmarks
@@ -81,6 +90,25 @@ fn generate_options(
     options
 }
 
+/// Move the bookmark `name` to the commit, creating it if it doesn't exist
+fn create_or_set_bookmark(
+    commander: &Commander,
+    name: &str,
+    commit_id: &CommitId,
+) -> Result<(), CommandError> {
+    if commander
+        .get_bookmarks_list(false)?
+        .iter()
+        .any(|bookmark| bookmark.name == name)
+    {
+        commander.set_bookmark_commit(name, commit_id)
+    } else {
+        commander
+            .create_bookmark_commit(name, commit_id)
+            .map(|_| ())
+    }
+}
+
 fn generate_name(commander: &Commander, change_id: &ChangeId) -> String {
     commander
         .generate_bookmark_name(change_id)
@@ -103,6 +131,7 @@ impl BookmarkSetPopup<'_> {
             config,
             commit_id,
             creating: None,
+            creating_issue: false,
             tx,
         }
     }
@@ -119,19 +148,38 @@ impl BookmarkSetPopup<'_> {
 
     fn on_creating(&mut self) {
         self.creating = Some(TextArea::default());
+        self.creating_issue = false;
+    }
+
+    fn on_creating_issue(&mut self) {
+        self.creating = Some(TextArea::default());
+        self.creating_issue = true;
     }
 
     fn create_bookmark(&self, commander: &mut Commander, name: &str) -> Result<()> {
-        if commander
-            .get_bookmarks_list(false)?
-            .iter()
-            .any(|bookmark| bookmark.name == name)
-        {
-            commander.set_bookmark_commit(name, &self.commit_id)?;
-        } else {
-            commander.create_bookmark_commit(name, &self.commit_id)?;
-        }
-        Ok(())
+        Ok(create_or_set_bookmark(commander, name, &self.commit_id)?)
+    }
+
+    /// Create the bookmark for an issue in the background, as fetching the issue title for
+    /// `{slug}` waits for the network
+    fn create_issue_bookmark(
+        &self,
+        commander: &Commander,
+        pattern: &str,
+        issue: &str,
+    ) -> ComponentAction {
+        let commander = Commander::new(&commander.env);
+        let pattern = pattern.to_owned();
+        let issue = issue.to_owned();
+        let commit_id = self.commit_id.clone();
+        ComponentAction::SetPopup(Some(Box::new(LoaderPopup::new(
+            "Create issue bookmark".to_owned(),
+            move || {
+                let name = commander.issue_bookmark_name(&pattern, &issue);
+                create_or_set_bookmark(&commander, &name, &commit_id)?;
+                Ok(String::new())
+            },
+        ))))
     }
     fn generate_bookmark(&self, commander: &mut Commander) -> Result<()> {
         if let Some(change_id) = self.change_id.as_ref() {
@@ -155,7 +203,11 @@ impl BookmarkSetPopup<'_> {
 impl Component for BookmarkSetPopup<'_> {
     fn draw(&mut self, f: &mut ratatui::prelude::Frame<'_>, area: Rect) -> Result<()> {
         if let Some(creating) = self.creating.as_ref() {
-            let block = create_popup_block("Create bookmark");
+            let block = create_popup_block(if self.creating_issue {
+                "Issue id"
+            } else {
+                "Create bookmark"
+            });
             let area = centered_rect_line_height(area, 30, 5);
             f.render_widget(Clear, area);
             f.render_widget(&block, area);
@@ -207,6 +259,9 @@ impl Component for BookmarkSetPopup<'_> {
                     }
                     Text::raw(text).fg(Color::Yellow)
                 }
+                BookmarkSetOption::IssueBookmark(pattern) => {
+                    Text::raw(format!("(I)ssue bookmark: {pattern}")).fg(Color::Yellow)
+                }
                 BookmarkSetOption::Bookmark(bookmark) => {
                     Text::raw(bookmark.to_string()).fg(Color::Magenta)
                 }
@@ -249,12 +304,23 @@ impl Component for BookmarkSetPopup<'_> {
                         && key.modifiers.contains(KeyModifiers::CONTROL))
                         || (key.code == KeyCode::Enter) =>
                     {
-                        let name = &creating.lines().join("\n");
-                        if name.trim().is_empty() {
+                        let input = creating.lines().join("\n");
+                        if input.trim().is_empty() {
                             return Ok(ComponentInputResult::Handled);
                         }
+                        let name = match self.config.bookmark_pattern() {
+                            Some(pattern) if self.creating_issue && pattern.contains("{slug}") => {
+                                return Ok(ComponentInputResult::HandledAction(
+                                    self.create_issue_bookmark(commander, pattern, &input),
+                                ));
+                            }
+                            Some(pattern) if self.creating_issue => {
+                                commander.issue_bookmark_name(pattern, &input)
+                            }
+                            _ => input,
+                        };
 
-                        self.create_bookmark(commander, name)?;
+                        self.create_bookmark(commander, &name)?;
                         self.tx.send(true)?;
                         return Ok(ComponentInputResult::HandledAction(
                             ComponentAction::SetPopup(None),
@@ -269,7 +335,7 @@ impl Component for BookmarkSetPopup<'_> {
                 }
             }
 
-            creating.input(event);
+            textarea_input(creating, event, false);
             return Ok(ComponentInputResult::Handled);
         }
 
@@ -297,6 +363,9 @@ impl Component for BookmarkSetPopup<'_> {
                 KeyCode::Char('c') => {
                     self.on_creating();
                 }
+                KeyCode::Char('i') if self.config.bookmark_pattern().is_some() => {
+                    self.on_creating_issue();
+                }
                 KeyCode::Enter => {
                     if let Some(action) = self
                         .list_state
@@ -314,6 +383,9 @@ impl Component for BookmarkSetPopup<'_> {
                                     ComponentAction::SetPopup(None),
                                 ));
                             }
+                            BookmarkSetOption::IssueBookmark(_) => {
+                                self.on_creating_issue();
+                            }
                             BookmarkSetOption::Bookmark(bookmark) => {
                                 commander.set_bookmark_commit(&bookmark.name, &self.commit_id)?;
                                 self.tx.send(true)?;
@@ -341,4 +413,24 @@ impl Component for BookmarkSetPopup<'_> {
 
         Ok(ComponentInputResult::NotHandled)
     }
+
+    fn hints(&self) -> Vec<(String, String)> {
+        if self.creating.is_some() {
+            return vec![
+                ("Ctrl+s".to_owned(), "save".to_owned()),
+                ("Esc".to_owned(), "cancel".to_owned()),
+            ];
+        }
+        let mut hints = vec![
+            ("j/k".to_owned(), "scroll".to_owned()),
+            ("Enter".to_owned(), "select".to_owned()),
+            ("c".to_owned(), "create".to_owned()),
+            ("g".to_owned(), "generate".to_owned()),
+        ];
+        if self.config.bookmark_pattern().is_some() {
+            hints.push(("i".to_owned(), "from issue".to_owned()));
+        }
+        hints.push(("Esc".to_owned(), "cancel".to_owned()));
+        hints
+    }
 }
@@ -0,0 +1,299 @@
+//! Popup creating a bookmark for each change of the stack of the working copy which has none,
+//! named with `blazingjj.bookmark-template`. The names are shown in a table to review and edit
+//! before creating them, which prepares the whole stack for a push in one step. Names of existing
+//! bookmarks are flagged, and if creating one fails, the bookmarks created before it can be rolled
+//! back.
+
+use std::collections::{HashMap, HashSet};
+
+use anyhow::Result;
+use ratatui::{
+    Frame,
+    crossterm::event::{Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{Block, BorderType, Borders, Cell, Clear, Paragraph, Row, Table, TableState},
+};
+use tui_textarea::{CursorMove, TextArea};
+
+use crate::{
+    ComponentInputResult,
+    commander::{CommandError, Commander, bookmarks::UnbookmarkedChange},
+    ui::{
+        Component, ComponentAction,
+        loader_popup::LoaderPopup,
+        read_only_popup,
+        styles::create_popup_block,
+        utils::{centered_rect, textarea_input},
+    },
+};
+
+struct BookmarkRow {
+    change: UnbookmarkedChange,
+    /// Whether the bookmark is created
+    selected: bool,
+}
+
+pub struct BookmarkStackPopup<'a> {
+    /// The changes from the top of the stack
+    rows: Result<Vec<BookmarkRow>, CommandError>,
+    table_state: TableState,
+    /// Input for the name of the selected row
+    textarea: Option<TextArea<'a>>,
+    /// Names of the local bookmarks, which can't be created again
+    existing: HashSet<String>,
+}
+
+impl BookmarkStackPopup<'_> {
+    pub fn new(commander: &Commander) -> Self {
+        let rows = commander.get_unbookmarked_stack().map(|changes| {
+            changes
+                .into_iter()
+                .map(|change| BookmarkRow {
+                    selected: !change.name.is_empty(),
+                    change,
+                })
+                .collect()
+        });
+        let existing = commander
+            .get_bookmarks_list(false)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|bookmark| bookmark.remote.is_none())
+            .map(|bookmark| bookmark.name)
+            .collect();
+        Self {
+            rows,
+            table_state: TableState::default().with_selected(Some(0)),
+            textarea: None,
+            existing,
+        }
+    }
+
+    fn selected_row(&mut self) -> Option<&mut BookmarkRow> {
+        let selected = self.table_state.selected()?;
+        self.rows.as_mut().ok()?.get_mut(selected)
+    }
+
+    /// Why the bookmark of a row can't be created, for names which are empty, used by an existing
+    /// bookmark or used by another selected row
+    fn invalid_reason(&self, row: &BookmarkRow) -> Option<&'static str> {
+        let rows = self.rows.as_ref().ok()?;
+        if row.change.name.is_empty() {
+            return Some("empty name");
+        }
+        if self.existing.contains(&row.change.name) {
+            return Some("exists");
+        }
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for other in rows.iter().filter(|other| other.selected) {
+            *counts.entry(other.change.name.as_str()).or_default() += 1;
+        }
+        let duplicate = counts
+            .get(row.change.name.as_str())
+            .is_some_and(|count| *count > 1);
+        (row.selected && duplicate).then_some("duplicate")
+    }
+
+    /// The changes to create a bookmark for
+    fn changes_to_bookmark(&self) -> Vec<UnbookmarkedChange> {
+        let rows = self.rows.as_ref().map(Vec::as_slice).unwrap_or_default();
+        rows.iter()
+            .filter(|row| row.selected && self.invalid_reason(row).is_none())
+            .map(|row| row.change.clone())
+            .collect()
+    }
+
+    fn apply(&self, commander: &Commander) -> ComponentAction {
+        let changes = self.changes_to_bookmark();
+        if changes.is_empty() {
+            return ComponentAction::SetPopup(None);
+        }
+        let commander = Commander::new(&commander.env);
+        ComponentAction::SetPopup(Some(Box::new(LoaderPopup::new_action(
+            "Creating bookmarks".to_owned(),
+            move || {
+                commander.run_composite_action("Bookmark the stack", |commander| {
+                    Ok(commander.create_stack_bookmarks(&changes)?)
+                })
+            },
+        ))))
+    }
+}
+
+impl Component for BookmarkStackPopup<'_> {
+    fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+        let area = centered_rect(area, 80, 60);
+        f.render_widget(Clear, area);
+
+        let block = create_popup_block("Bookmark the stack");
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let [table_area, input_area, help_area] = Layout::vertical([
+            Constraint::Fill(1),
+            Constraint::Length(if self.textarea.is_some() { 3 } else { 0 }),
+            Constraint::Length(1),
+        ])
+        .areas(inner);
+
+        match self.rows.as_ref() {
+            Ok(rows) if rows.is_empty() => {
+                f.render_widget(
+                    Paragraph::new("Every mutable change of the stack of @ has a bookmark"),
+                    table_area,
+                );
+            }
+            Ok(rows) => {
+                let table_rows = rows.iter().map(|row| {
+                    let style = if row.selected {
+                        Style::default()
+                    } else {
+                        Style::default().fg(Color::DarkGray)
+                    };
+                    let name = match self.invalid_reason(row) {
+                        Some(reason) => Cell::from(format!("{} ({reason})", row.change.name)).red(),
+                        None => Cell::from(row.change.name.clone()),
+                    };
+                    let title = if row.change.title.is_empty() {
+                        Cell::from("(no description set)").dark_gray()
+                    } else {
+                        Cell::from(row.change.title.clone())
+                    };
+                    Row::new(vec![
+                        Cell::from(if row.selected { "[x]" } else { "[ ]" }),
+                        Cell::from(
+                            row.change
+                                .change_id
+                                .as_str()
+                                .chars()
+                                .take(8)
+                                .collect::<String>(),
+                        )
+                        .magenta(),
+                        title,
+                        name,
+                    ])
+                    .style(style)
+                });
+                let table = Table::new(
+                    table_rows,
+                    [
+                        Constraint::Length(3),
+                        Constraint::Length(8),
+                        Constraint::Fill(2),
+                        Constraint::Fill(1),
+                    ],
+                )
+                .header(Row::new(vec!["", "Change", "Description", "Bookmark"]).bold())
+                .row_highlight_style(Style::default().reversed());
+                f.render_stateful_widget(table, table_area, &mut self.table_state);
+            }
+            Err(err) => {
+                f.render_widget(
+                    Paragraph::new(format!("Error getting the stack: {err}")).fg(Color::Red),
+                    table_area,
+                );
+            }
+        }
+
+        if let Some(textarea) = self.textarea.as_mut() {
+            textarea.set_block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded)
+                    .title(" Bookmark "),
+            );
+            f.render_widget(&*textarea, input_area);
+        }
+
+        let help = if self.textarea.is_some() {
+            "Enter: save | Esc: cancel".to_owned()
+        } else {
+            format!(
+                "Space: toggle | e: edit | Enter: create {} bookmarks | Esc: close",
+                self.changes_to_bookmark().len()
+            )
+        };
+        f.render_widget(
+            Paragraph::new(Line::from(help).fg(Color::DarkGray)).centered(),
+            help_area,
+        );
+
+        Ok(())
+    }
+
+    fn input(&mut self, commander: &mut Commander, event: Event) -> Result<ComponentInputResult> {
+        if let Some(textarea) = self.textarea.as_mut() {
+            if let Event::Key(key) = event {
+                match key.code {
+                    KeyCode::Enter => {
+                        let name = textarea.lines().join("").trim().to_owned();
+                        self.textarea = None;
+                        if let Some(row) = self.selected_row() {
+                            row.change.name = name;
+                            row.selected = true;
+                        }
+                        return Ok(ComponentInputResult::Handled);
+                    }
+                    KeyCode::Esc => {
+                        self.textarea = None;
+                        return Ok(ComponentInputResult::Handled);
+                    }
+                    _ => {}
+                }
+            }
+            textarea_input(textarea, event, false);
+            return Ok(ComponentInputResult::Handled);
+        }
+
+        let Event::Key(key) = event else {
+            return Ok(ComponentInputResult::Handled);
+        };
+        if key.kind != KeyEventKind::Press {
+            return Ok(ComponentInputResult::Handled);
+        }
+
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => self.table_state.select_next(),
+            KeyCode::Char('k') | KeyCode::Up => self.table_state.select_previous(),
+            KeyCode::Char(' ') => {
+                if let Some(row) = self.selected_row() {
+                    row.selected = !row.selected;
+                }
+            }
+            KeyCode::Char('e') => {
+                if let Some(row) = self.selected_row() {
+                    let mut textarea = TextArea::new(vec![row.change.name.clone()]);
+                    textarea.move_cursor(CursorMove::End);
+                    self.textarea = Some(textarea);
+                }
+            }
+            KeyCode::Enter if commander.env.config.read_only() => {
+                return Ok(ComponentInputResult::HandledAction(read_only_popup()));
+            }
+            KeyCode::Enter => {
+                return Ok(ComponentInputResult::HandledAction(self.apply(commander)));
+            }
+            _ => return Ok(ComponentInputResult::NotHandled),
+        }
+        Ok(ComponentInputResult::Handled)
+    }
+
+    fn hints(&self) -> Vec<(String, String)> {
+        if self.textarea.is_some() {
+            return vec![
+                ("Enter".to_owned(), "save".to_owned()),
+                ("Esc".to_owned(), "cancel".to_owned()),
+            ];
+        }
+        vec![
+            ("j/k".to_owned(), "select".to_owned()),
+            ("Space".to_owned(), "toggle".to_owned()),
+            ("e".to_owned(), "edit".to_owned()),
+            ("Enter".to_owned(), "create".to_owned()),
+            ("Esc".to_owned(), "close".to_owned()),
+        ]
+    }
+}
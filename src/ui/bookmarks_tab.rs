@@ -2,23 +2,41 @@
 
 use crate::{
     ComponentInputResult,
-    commander::{CommandError, Commander, bookmarks::BookmarkLine, ids::ChangeId},
+    commander::{
+        CommandError, Commander,
+        bookmarks::{Bookmark, BookmarkLine, BookmarkTracking},
+        ids::ChangeId,
+    },
     env::{Config, DiffFormat},
+    keybinds::{BookmarksTabEvent, BookmarksTabKeybinds},
+    panel_settings::{self, Panel},
     ui::{
         Component, ComponentAction,
+        delete_bookmark_popup::DeleteBookmarkPopup,
         help_popup::HelpPopup,
+        loader_popup::LoaderPopup,
         message_popup::MessagePopup,
         panel::DetailsPanel,
-        utils::{centered_rect, centered_rect_line_height, tabs_to_spaces},
+        read_only_popup,
+        styles::{panel_border_style, panel_content_style},
+        utils::{
+            centered_rect, centered_rect_line_height, has_command_modifier, tabs_to_spaces,
+            textarea_input,
+        },
     },
 };
 use ansi_to_tui::IntoText;
 use anyhow::Result;
+use itertools::Itertools;
 use ratatui::{
     crossterm::event::{Event, KeyCode, KeyEventKind, KeyModifiers},
     prelude::*,
     widgets::*,
 };
+use std::{
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+};
 use tracing::instrument;
 use tui_confirm_dialog::{ButtonLabel, ConfirmDialog, ConfirmDialogState, Listener};
 use tui_textarea::{CursorMove, TextArea};
@@ -32,6 +50,23 @@ struct RenameBookmark<'a> {
     textarea: TextArea<'a>,
     name: String,
     error: Option<anyhow::Error>,
+    /// Remotes on which the bookmark is tracked
+    remotes: Vec<String>,
+    /// Whether the rename is pushed to the remotes
+    propagate: bool,
+}
+
+/// Rename waiting for confirmation, which is pushed to the remotes
+struct RenameOnRemotes {
+    old: String,
+    new: String,
+    remotes: Vec<String>,
+}
+
+struct FetchBookmarks<'a> {
+    textarea: TextArea<'a>,
+    /// Remote of the selected bookmark to fetch from, the default remotes if `None`
+    remote: Option<String>,
 }
 
 struct DeleteBookmark {
@@ -46,12 +81,15 @@ const DELETE_BRANCH_POPUP_ID: u16 = 1;
 const FORGET_BRANCH_POPUP_ID: u16 = 2;
 const NEW_POPUP_ID: u16 = 3;
 const EDIT_POPUP_ID: u16 = 4;
+const RENAME_ON_REMOTES_POPUP_ID: u16 = 5;
 
 /// Bookmarks tab. Shows bookmarks in main panel and selected bookmark current change in details panel.
 pub struct BookmarksTab<'a> {
     bookmarks_output: Result<Vec<BookmarkLine>, CommandError>,
     bookmarks_list_state: ListState,
     bookmarks_height: u16,
+    /// Index of the first visible bookmark
+    bookmarks_offset: usize,
 
     show_all: bool,
 
@@ -59,9 +97,15 @@ pub struct BookmarksTab<'a> {
 
     bookmark_panel: DetailsPanel,
     bookmark_output: Option<Result<String, CommandError>>,
+    /// Ahead/behind counts of the selected bookmark, prepended to its output once counted
+    tracking_rx: Option<Receiver<Result<Vec<BookmarkTracking>, CommandError>>>,
 
     create: Option<CreateBookmark<'a>>,
     rename: Option<RenameBookmark<'a>>,
+    rename_on_remotes: Option<RenameOnRemotes>,
+    /// Local bookmark to select once the tab is refreshed, e.g. after renaming it in the background
+    select_on_refresh: Option<String>,
+    fetch: Option<FetchBookmarks<'a>>,
     delete: Option<DeleteBookmark>,
     forget: Option<ForgetBookmark>,
 
@@ -78,6 +122,7 @@ pub struct BookmarksTab<'a> {
     diff_format: DiffFormat,
 
     config: Config,
+    keybinds: BookmarksTabKeybinds,
 }
 
 fn get_current_bookmark_index(
@@ -110,10 +155,42 @@ fn get_current_bookmark_index(
     }
 }
 
+/// Number of bookmarks kept visible above and below the selected one
+const SCROLL_PADDING: usize = 3;
+
+/// Index of the first visible bookmark, scrolled as little as possible from `offset` to show the
+/// selected bookmark with [SCROLL_PADDING] around it
+fn visible_offset(offset: usize, selected: Option<usize>, count: usize, height: usize) -> usize {
+    let max_offset = count.saturating_sub(height);
+    let Some(selected) = selected else {
+        return offset.min(max_offset);
+    };
+    let padding = SCROLL_PADDING.min(height.saturating_sub(1) / 2);
+    let offset = offset.min(selected.saturating_sub(padding));
+    let offset = offset.max((selected + padding + 1).saturating_sub(height));
+    offset.min(max_offset)
+}
+
+/// Remote to fetch a bookmark from. The `git` pseudo remote of colocated repositories can't be
+/// fetched from, so the default remotes are used for it and for local bookmarks.
+fn fetch_remote(bookmark: &Bookmark) -> Option<String> {
+    bookmark.remote.clone().filter(|remote| remote != "git")
+}
+
 impl BookmarksTab<'_> {
+    /// Keys greyed out in the help in read-only mode
+    fn read_only_keys(&self) -> Vec<String> {
+        if !self.config.read_only() {
+            return vec![];
+        }
+        self.keybinds.mutating_shortcuts()
+    }
+
     #[instrument(level = "info", name = "Initializing bookmarks tab", parent = None, skip(commander))]
     pub fn new(commander: &mut Commander) -> Result<Self> {
-        let diff_format = commander.env.config.diff_format();
+        let diff_format = panel_settings::load(&commander.env.root, Panel::Bookmarks)
+            .diff_format
+            .unwrap_or_else(|| commander.env.config.diff_format());
 
         let show_all = false;
 
@@ -145,14 +222,23 @@ impl BookmarksTab<'_> {
             bookmark,
             bookmarks_list_state,
             bookmarks_height: 0,
+            bookmarks_offset: 0,
 
             show_all,
 
-            bookmark_panel: DetailsPanel::new(),
+            bookmark_panel: DetailsPanel::with_settings(
+                &commander.env.root,
+                Panel::Bookmarks,
+                commander.env.config.layout_percent(),
+            ),
             bookmark_output,
+            tracking_rx: None,
 
             create: None,
             rename: None,
+            rename_on_remotes: None,
+            select_on_refresh: None,
+            fetch: None,
             delete: None,
             forget: None,
 
@@ -169,9 +255,18 @@ impl BookmarksTab<'_> {
             diff_format,
 
             config: commander.env.config.clone(),
+            keybinds: BookmarksTabKeybinds::default(),
         })
     }
 
+    /// The highlighted bookmark, if it could be parsed
+    pub fn selected_bookmark(&self) -> Option<&Bookmark> {
+        match self.bookmark.as_ref()? {
+            BookmarkLine::Parsed { bookmark, .. } => Some(bookmark),
+            BookmarkLine::Unparsable(_) => None,
+        }
+    }
+
     pub fn get_current_bookmark_index(&self) -> Option<usize> {
         get_current_bookmark_index(self.bookmark.as_ref(), &self.bookmarks_output)
     }
@@ -183,6 +278,8 @@ impl BookmarksTab<'_> {
     pub fn refresh_bookmark(&mut self, commander: &mut Commander) {
         let inner_width = self.bookmark_panel.columns() as usize;
         commander.limit_width(inner_width);
+        self.bookmark_panel.set_content_columns();
+        self.bookmark_panel.content_changed();
         self.bookmark_output = self.bookmark.as_ref().and_then(|bookmark| match bookmark {
             BookmarkLine::Parsed { bookmark, .. } => Some(
                 commander
@@ -192,9 +289,102 @@ impl BookmarksTab<'_> {
             _ => None,
         });
 
+        // Counting takes a jj command per remote bookmark, the result of an earlier selection is
+        // dropped with its receiver
+        self.tracking_rx = self.selected_bookmark().map(|bookmark| {
+            let (tx, rx) = mpsc::channel();
+            let commander = Commander::new(&commander.env);
+            let bookmark = bookmark.clone();
+            thread::spawn(move || {
+                let _ = tx.send(commander.get_bookmark_tracking(&bookmark));
+            });
+            rx
+        });
+
         self.bookmark_panel.scroll_to(0);
     }
 
+    /// Select the local bookmark with the name, if it exists
+    fn select_bookmark_named(&mut self, commander: &mut Commander, name: &str) {
+        self.select_bookmark(commander, name, None);
+    }
+
+    /// Select the bookmark with the name, a remote bookmark if the remote is set. Selecting a
+    /// remote bookmark shows all remotes.
+    pub fn select_bookmark(&mut self, commander: &mut Commander, name: &str, remote: Option<&str>) {
+        if remote.is_some() && !self.show_all {
+            self.show_all = true;
+            self.refresh_bookmarks(commander);
+        }
+        if let Some(bookmark) = self
+            .bookmarks_output
+            .as_ref()
+            .ok()
+            .and_then(|bookmarks_output| {
+                bookmarks_output.iter().find(|bookmark| match bookmark {
+                    BookmarkLine::Unparsable(_) => false,
+                    BookmarkLine::Parsed { bookmark, .. } => {
+                        bookmark.name == name && bookmark.remote.as_deref() == remote
+                    }
+                })
+            })
+        {
+            self.bookmark = Some(bookmark.clone());
+        }
+
+        self.refresh_bookmark(commander);
+    }
+
+    /// Rename the bookmark locally, then push the rename to the remotes. A failed push offers to
+    /// roll back the local rename.
+    fn rename_on_remotes(
+        &mut self,
+        commander: &mut Commander,
+        rename: RenameOnRemotes,
+    ) -> Result<Option<ComponentAction>> {
+        let RenameOnRemotes { old, new, remotes } = rename;
+        self.select_on_refresh = Some(new.clone());
+        let mut loader = LoaderPopup::new_action(
+            format!("Renaming {old} to {new} on {}", remotes.join(", ")),
+            {
+                let commander = Commander::new(&commander.env);
+                let (old, new, remotes) = (old.clone(), new.clone(), remotes.clone());
+                move || {
+                    let name = format!("Rename {old} to {new} on the remotes");
+                    commander.run_composite_action(&name, |commander| {
+                        commander.rename_bookmark(&old, &new)?;
+                        let mut output = String::new();
+                        for remote in &remotes {
+                            output.push_str(&commander.git_push_rename(remote, &old, &new)?);
+                        }
+                        Ok(output)
+                    })
+                }
+            },
+        );
+        // A single command can be retried in a terminal, e.g. to enter a passphrase
+        if let [remote] = remotes.as_slice() {
+            loader =
+                loader.with_terminal_retry(Commander::git_push_rename_args(remote, &old, &new));
+        }
+        Ok(Some(ComponentAction::SetPopup(Some(Box::new(loader)))))
+    }
+
+    /// Fetch only the bookmarks matching the patterns with `jj git fetch --branch`
+    fn fetch_bookmarks(
+        commander: &Commander,
+        remote: Option<String>,
+        patterns: Vec<String>,
+    ) -> ComponentInputResult {
+        let commander = Commander::new(&commander.env);
+        let terminal_args = Commander::git_fetch_bookmarks_args(remote.as_deref(), &patterns);
+        let loader = LoaderPopup::new(format!("Fetching {}", patterns.join(" ")), move || {
+            commander.git_fetch_bookmarks(remote.as_deref(), &patterns)
+        })
+        .with_terminal_retry(terminal_args);
+        ComponentInputResult::HandledAction(ComponentAction::SetPopup(Some(Box::new(loader))))
+    }
+
     fn scroll_bookmarks(&mut self, commander: &mut Commander, scroll: isize) {
         let bookmarks = Vec::new();
         let bookmarks = self.bookmarks_output.as_ref().unwrap_or(&bookmarks);
@@ -219,11 +409,37 @@ impl BookmarksTab<'_> {
 impl Component for BookmarksTab<'_> {
     fn focus(&mut self, commander: &mut Commander) -> Result<()> {
         self.refresh_bookmarks(commander);
-        self.refresh_bookmark(commander);
+        match self.select_on_refresh.take() {
+            Some(name) => self.select_bookmark_named(commander, &name),
+            None => self.refresh_bookmark(commander),
+        }
         Ok(())
     }
 
     fn update(&mut self, commander: &mut Commander) -> Result<Option<ComponentAction>> {
+        // The output of diff tools depends on the width, produce it again once resizing settled
+        if self.diff_format.depends_on_width() && self.bookmark_panel.is_resized() {
+            self.refresh_bookmark(commander);
+        }
+
+        if let Some(rx) = self.tracking_rx.as_ref() {
+            match rx.try_recv() {
+                Ok(tracking) => {
+                    self.tracking_rx = None;
+                    if let Ok(tracking) = tracking
+                        && !tracking.is_empty()
+                        && let Some(Ok(output)) = self.bookmark_output.as_mut()
+                    {
+                        let tracking = tracking.iter().map(ToString::to_string).join("\n");
+                        *output = format!("{tracking}\n\n{output}");
+                    }
+                }
+                Err(TryRecvError::Empty) => {}
+                // The counts are left out, as before they could be counted
+                Err(TryRecvError::Disconnected) => self.tracking_rx = None,
+            }
+        }
+
         // Check for popup action
         if let Ok(res) = self.popup_rx.try_recv()
             && res.1.unwrap_or(false)
@@ -247,6 +463,7 @@ impl Component for BookmarksTab<'_> {
                                         title: "Delete error".into(),
                                         messages: err.to_string().into_text()?,
                                         text_align: None,
+                                        ..Default::default()
                                     },
                                 )))));
                             }
@@ -271,6 +488,7 @@ impl Component for BookmarksTab<'_> {
                                         title: "Forget error".into(),
                                         messages: err.to_string().into_text()?,
                                         text_align: None,
+                                        ..Default::default()
                                     },
                                 )))));
                             }
@@ -299,6 +517,11 @@ impl Component for BookmarksTab<'_> {
                         return Ok(Some(ComponentAction::ViewLog(head)));
                     }
                 }
+                RENAME_ON_REMOTES_POPUP_ID => {
+                    if let Some(rename) = self.rename_on_remotes.take() {
+                        return self.rename_on_remotes(commander, rename);
+                    }
+                }
                 _ => {}
             }
         }
@@ -314,8 +537,8 @@ impl Component for BookmarksTab<'_> {
         let chunks = Layout::default()
             .direction(self.config.layout().into())
             .constraints([
-                Constraint::Percentage(self.config.layout_percent()),
-                Constraint::Percentage(100 - self.config.layout_percent()),
+                Constraint::Percentage(self.bookmark_panel.layout_percent()),
+                Constraint::Percentage(100 - self.bookmark_panel.layout_percent()),
             ])
             .split(area);
 
@@ -323,62 +546,91 @@ impl Component for BookmarksTab<'_> {
         {
             let current_bookmark_index = self.get_current_bookmark_index();
 
-            let bookmark_lines: Vec<Line> = match self.bookmarks_output.as_ref() {
-                Ok(bookmarks_output) => bookmarks_output
-                    .iter()
-                    .enumerate()
-                    .map(|(i, bookmark)| -> Result<Vec<Line>, ansi_to_tui::Error> {
-                        let bookmark_text = bookmark.to_text()?;
-                        Ok(bookmark_text
+            let focused = !self.bookmark_panel.is_focused();
+            let bookmarks_block = Block::bordered()
+                .title(" Bookmarks ")
+                .border_type(BorderType::Rounded)
+                .border_style(panel_border_style(focused, self.config.focus_color()));
+            self.bookmarks_height = bookmarks_block.inner(chunks[0]).height;
+            let height = self.bookmarks_height as usize;
+
+            // Only the visible bookmarks are rendered, as there can be thousands of them
+            let (bookmark_lines, bookmark_count): (Vec<Line>, usize) =
+                match self.bookmarks_output.as_ref() {
+                    Ok(bookmarks_output) => {
+                        self.bookmarks_offset = visible_offset(
+                            self.bookmarks_offset,
+                            current_bookmark_index,
+                            bookmarks_output.len(),
+                            height,
+                        );
+                        let lines = bookmarks_output
                             .iter()
-                            .map(|line| {
-                                let mut line = line.to_owned();
-
-                                // Add padding at start
-                                line.spans.insert(0, Span::from(" "));
-
-                                if current_bookmark_index == Some(i) {
-                                    line = line.bg(self.config.highlight_color());
-
-                                    line.spans = line
-                                        .spans
-                                        .iter_mut()
-                                        .map(|span| {
-                                            span.to_owned().bg(self.config.highlight_color())
-                                        })
-                                        .collect();
-                                }
+                            .enumerate()
+                            .skip(self.bookmarks_offset)
+                            .take(height)
+                            .map(|(i, bookmark)| -> Result<Vec<Line>, ansi_to_tui::Error> {
+                                let bookmark_text = bookmark.to_text()?;
+                                Ok(bookmark_text
+                                    .iter()
+                                    .map(|line| {
+                                        let mut line = line.to_owned();
+
+                                        // Add padding at start
+                                        line.spans.insert(0, Span::from(" "));
+
+                                        if current_bookmark_index == Some(i) {
+                                            line = line.bg(self.config.highlight_color());
+
+                                            line.spans = line
+                                                .spans
+                                                .iter_mut()
+                                                .map(|span| {
+                                                    span.to_owned()
+                                                        .bg(self.config.highlight_color())
+                                                })
+                                                .collect();
+                                        }
 
-                                line
+                                        line
+                                    })
+                                    .collect::<Vec<Line>>())
                             })
-                            .collect::<Vec<Line>>())
-                    })
-                    .collect::<Result<Vec<Vec<Line>>, ansi_to_tui::Error>>()?
-                    .into_iter()
-                    .flatten()
-                    .collect(),
-                Err(err) => [
-                    vec![Line::raw("Error getting bookmarks").bold().fg(Color::Red)],
-                    // TODO: Remove when jj 0.20 is released
-                    if let CommandError::Status(output, _) = err {
-                        if output.contains("unexpected argument '-T' found") {
-                            vec![
-                                Line::raw(""),
-                                Line::raw("Please update jj to >0.18 for -T support to bookmarks")
-                                    .bold()
-                                    .fg(Color::Red),
-                            ]
-                        } else {
-                            vec![]
-                        }
-                    } else {
-                        vec![]
-                    },
-                    vec![Line::raw(""), Line::raw("")],
-                    err.to_string().into_text()?.lines,
-                ]
-                .concat(),
-            };
+                            .collect::<Result<Vec<Vec<Line>>, ansi_to_tui::Error>>()?
+                            .into_iter()
+                            .flatten()
+                            .collect();
+                        (lines, bookmarks_output.len())
+                    }
+                    Err(err) => {
+                        self.bookmarks_offset = 0;
+                        let lines: Vec<Line> = [
+                            vec![Line::raw("Error getting bookmarks").bold().fg(Color::Red)],
+                            // TODO: Remove when jj 0.20 is released
+                            if let CommandError::Status(output, _) = err {
+                                if output.contains("unexpected argument '-T' found") {
+                                    vec![
+                                        Line::raw(""),
+                                        Line::raw(
+                                            "Please update jj to >0.18 for -T support to bookmarks",
+                                        )
+                                        .bold()
+                                        .fg(Color::Red),
+                                    ]
+                                } else {
+                                    vec![]
+                                }
+                            } else {
+                                vec![]
+                            },
+                            vec![Line::raw(""), Line::raw("")],
+                            err.to_string().into_text()?.lines,
+                        ]
+                        .concat();
+                        let count = lines.len();
+                        (lines, count)
+                    }
+                };
 
             let lines = if bookmark_lines.is_empty() {
                 vec![Line::from(" No bookmarks").fg(Color::DarkGray).italic()]
@@ -386,17 +638,16 @@ impl Component for BookmarksTab<'_> {
                 bookmark_lines
             };
 
-            let bookmarks_block = Block::bordered()
-                .title(" Bookmarks ")
-                .border_type(BorderType::Rounded);
-            self.bookmarks_height = bookmarks_block.inner(chunks[0]).height;
-            let bookmark_count = lines.len();
-            let bookmarks = List::new(lines).block(bookmarks_block).scroll_padding(3);
-            *self.bookmarks_list_state.selected_mut() = current_bookmark_index;
+            let bookmarks = List::new(lines)
+                .style(panel_content_style(focused))
+                .block(bookmarks_block);
+            *self.bookmarks_list_state.selected_mut() =
+                current_bookmark_index.and_then(|index| index.checked_sub(self.bookmarks_offset));
+            *self.bookmarks_list_state.offset_mut() = 0;
             f.render_stateful_widget(bookmarks, chunks[0], &mut self.bookmarks_list_state);
 
             // Draw scrollbar on left panel
-            if bookmark_count > self.bookmarks_height.into() {
+            if bookmark_count > height {
                 let index = current_bookmark_index.unwrap_or(0);
                 let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight);
                 let mut scrollbar_state = ScrollbarState::default()
@@ -430,6 +681,7 @@ impl Component for BookmarksTab<'_> {
             self.bookmark_panel
                 .render_context()
                 .title(title)
+                .focus_color(self.config.focus_color())
                 .content(bookmark_content)
                 .draw(f, chunks[1]);
         }
@@ -528,7 +780,9 @@ impl Component for BookmarksTab<'_> {
                 } else {
                     0
                 };
-                let area = centered_rect_line_height(area, 30, 5 + error_height as u16);
+                let propagate_height = if rename.remotes.is_empty() { 0 } else { 2 };
+                let area =
+                    centered_rect_line_height(area, 30, 5 + error_height as u16 + propagate_height);
                 f.render_widget(Clear, area);
                 f.render_widget(&block, area);
 
@@ -536,6 +790,7 @@ impl Component for BookmarksTab<'_> {
                     .direction(Direction::Vertical)
                     .constraints([
                         Constraint::Fill(1),
+                        Constraint::Length(propagate_height),
                         Constraint::Length(error_height as u16),
                         Constraint::Length(2),
                     ])
@@ -543,6 +798,22 @@ impl Component for BookmarksTab<'_> {
 
                 f.render_widget(&rename.textarea, popup_chunks[0]);
 
+                if !rename.remotes.is_empty() {
+                    let checkbox = if rename.propagate { "[x]" } else { "[ ]" };
+                    let propagate = Paragraph::new(format!(
+                        "{checkbox} Also rename on {}",
+                        rename.remotes.join(", ")
+                    ))
+                    .block(
+                        Block::default()
+                            .borders(Borders::TOP)
+                            .border_type(BorderType::Rounded)
+                            .border_style(Style::default().fg(Color::DarkGray)),
+                    );
+
+                    f.render_widget(propagate, popup_chunks[1]);
+                }
+
                 if let Some(error_lines) = error_lines {
                     let help = Paragraph::new(error_lines).block(
                         Block::default()
@@ -551,10 +822,15 @@ impl Component for BookmarksTab<'_> {
                             .border_style(Style::default().fg(Color::DarkGray)),
                     );
 
-                    f.render_widget(help, popup_chunks[1]);
+                    f.render_widget(help, popup_chunks[2]);
                 }
 
-                let help = Paragraph::new(vec!["Ctrl+s: save | Escape: cancel".into()])
+                let help = if rename.remotes.is_empty() {
+                    "Ctrl+s: save | Escape: cancel"
+                } else {
+                    "Ctrl+s: save | Tab: toggle remotes | Escape: cancel"
+                };
+                let help = Paragraph::new(vec![help.into()])
                     .fg(Color::DarkGray)
                     .alignment(Alignment::Center)
                     .block(
@@ -564,7 +840,46 @@ impl Component for BookmarksTab<'_> {
                             .border_style(Style::default().fg(Color::DarkGray)),
                     );
 
-                f.render_widget(help, popup_chunks[2]);
+                f.render_widget(help, popup_chunks[3]);
+            }
+        }
+
+        // Draw fetch textarea
+        {
+            if let Some(fetch) = self.fetch.as_ref() {
+                let title = match fetch.remote.as_ref() {
+                    Some(remote) => format!(" Fetch bookmarks from {remote} "),
+                    None => " Fetch bookmarks ".to_owned(),
+                };
+                let block = Block::bordered()
+                    .title(Span::styled(title, Style::new().bold().cyan()))
+                    .title_alignment(Alignment::Center)
+                    .border_type(BorderType::Rounded)
+                    .border_style(Style::default().fg(Color::Green));
+                let area = centered_rect_line_height(area, 40, 5);
+                f.render_widget(Clear, area);
+                f.render_widget(&block, area);
+
+                let popup_chunks = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Fill(1), Constraint::Length(2)])
+                    .split(block.inner(area));
+
+                f.render_widget(&fetch.textarea, popup_chunks[0]);
+
+                let help = Paragraph::new(vec![
+                    "Patterns separated by spaces | Enter: fetch | Escape: cancel".into(),
+                ])
+                .fg(Color::DarkGray)
+                .alignment(Alignment::Center)
+                .block(
+                    Block::default()
+                        .borders(Borders::TOP)
+                        .border_type(BorderType::Rounded)
+                        .border_style(Style::default().fg(Color::DarkGray)),
+                );
+
+                f.render_widget(help, popup_chunks[1]);
             }
         }
 
@@ -604,6 +919,11 @@ impl Component for BookmarksTab<'_> {
         Ok(())
     }
 
+    fn is_busy(&self) -> bool {
+        self.tracking_rx.is_some()
+            || (self.diff_format.depends_on_width() && self.bookmark_panel.is_resizing())
+    }
+
     fn input(&mut self, commander: &mut Commander, event: Event) -> Result<ComponentInputResult> {
         if let Some(create) = self.create.as_mut() {
             if let Event::Key(key) = event {
@@ -656,7 +976,7 @@ impl Component for BookmarksTab<'_> {
                     _ => {}
                 }
             }
-            create.textarea.input(event);
+            textarea_input(&mut create.textarea, event, false);
             return Ok(ComponentInputResult::Handled);
         }
 
@@ -677,34 +997,54 @@ impl Component for BookmarksTab<'_> {
 
                         let old = rename.name.clone();
 
+                        if rename.propagate && !rename.remotes.is_empty() {
+                            if commander.env.config.read_only() {
+                                return Ok(ComponentInputResult::HandledAction(read_only_popup()));
+                            }
+
+                            let remotes = rename.remotes.clone();
+                            let mut lines = vec![
+                                Line::from(format!("Rename {old} to {new} locally, then on:")),
+                                Line::default(),
+                            ];
+                            for remote in &remotes {
+                                lines.push(Line::from(format!(
+                                    "{remote}: push {new} and delete {old}"
+                                )));
+                            }
+                            lines.push(Line::default());
+                            lines.push(Line::from(
+                                "Pull requests using the old name are not moved.",
+                            ));
+                            self.rename = None;
+                            self.rename_on_remotes = Some(RenameOnRemotes { old, new, remotes });
+                            self.popup = ConfirmDialogState::new(
+                                RENAME_ON_REMOTES_POPUP_ID,
+                                Span::styled(" Rename on remotes ", Style::new().bold().cyan()),
+                                Text::from(lines),
+                            );
+                            self.popup
+                                .with_yes_button(ButtonLabel::YES.clone())
+                                .with_no_button(ButtonLabel::NO.clone())
+                                .with_listener(Some(self.popup_tx.clone()))
+                                .open();
+                            return Ok(ComponentInputResult::Handled);
+                        }
+
                         if let Err(err) = commander.rename_bookmark(&old, &new) {
                             rename.error = Some(anyhow::Error::new(err));
                             return Ok(ComponentInputResult::Handled);
                         }
                         self.rename = None;
                         self.refresh_bookmarks(commander);
-
-                        // Select new bookmark
-                        if let Some(bookmark) =
-                            self.bookmarks_output
-                                .as_ref()
-                                .ok()
-                                .and_then(|bookmarks_output| {
-                                    bookmarks_output.iter().find(|bookmark| match bookmark {
-                                        BookmarkLine::Unparsable(_) => false,
-                                        BookmarkLine::Parsed { bookmark, .. } => {
-                                            bookmark.name == new
-                                        }
-                                    })
-                                })
-                        {
-                            self.bookmark = Some(bookmark.clone());
-                        }
-
-                        self.refresh_bookmark(commander);
+                        self.select_bookmark_named(commander, &new);
 
                         return Ok(ComponentInputResult::Handled);
                     }
+                    KeyCode::Tab if !rename.remotes.is_empty() => {
+                        rename.propagate = !rename.propagate;
+                        return Ok(ComponentInputResult::Handled);
+                    }
                     KeyCode::Esc => {
                         self.rename = None;
                         return Ok(ComponentInputResult::Handled);
@@ -712,7 +1052,36 @@ impl Component for BookmarksTab<'_> {
                     _ => {}
                 }
             }
-            rename.textarea.input(event);
+            textarea_input(&mut rename.textarea, event, false);
+            return Ok(ComponentInputResult::Handled);
+        }
+
+        if let Some(fetch) = self.fetch.as_mut() {
+            if let Event::Key(key) = event {
+                match key.code {
+                    KeyCode::Enter => {
+                        let patterns = fetch
+                            .textarea
+                            .lines()
+                            .join(" ")
+                            .split_whitespace()
+                            .map(String::from)
+                            .collect::<Vec<_>>();
+                        let remote = fetch.remote.take();
+                        self.fetch = None;
+                        if patterns.is_empty() {
+                            return Ok(ComponentInputResult::Handled);
+                        }
+                        return Ok(Self::fetch_bookmarks(commander, remote, patterns));
+                    }
+                    KeyCode::Esc => {
+                        self.fetch = None;
+                        return Ok(ComponentInputResult::Handled);
+                    }
+                    _ => {}
+                }
+            }
+            textarea_input(&mut fetch.textarea, event, false);
             return Ok(ComponentInputResult::Handled);
         }
 
@@ -742,7 +1111,7 @@ impl Component for BookmarksTab<'_> {
                     _ => {}
                 }
             }
-            describe_textarea.input(event);
+            textarea_input(describe_textarea, event, true);
             return Ok(ComponentInputResult::Handled);
         }
 
@@ -764,31 +1133,44 @@ impl Component for BookmarksTab<'_> {
                 return Ok(ComponentInputResult::Handled);
             }
 
-            match key.code {
-                KeyCode::Char('j') | KeyCode::Down => self.scroll_bookmarks(commander, 1),
-                KeyCode::Char('k') | KeyCode::Up => self.scroll_bookmarks(commander, -1),
-                KeyCode::Char('J') => {
+            if has_command_modifier(&key) {
+                return Ok(ComponentInputResult::NotHandled);
+            }
+
+            let bookmarks_tab_event = self.keybinds.match_event(key);
+            if self.config.read_only() && bookmarks_tab_event.is_mutating() {
+                return Ok(ComponentInputResult::HandledAction(read_only_popup()));
+            }
+
+            match bookmarks_tab_event {
+                BookmarksTabEvent::ScrollDown => self.scroll_bookmarks(commander, 1),
+                BookmarksTabEvent::ScrollUp => self.scroll_bookmarks(commander, -1),
+                BookmarksTabEvent::ScrollDownHalf => {
                     self.scroll_bookmarks(commander, self.bookmarks_height as isize / 2);
                 }
-                KeyCode::Char('K') => {
+                BookmarksTabEvent::ScrollUpHalf => {
                     self.scroll_bookmarks(
                         commander,
                         (self.bookmarks_height as isize / 2).saturating_neg(),
                     );
                 }
-                KeyCode::Char('w') => {
+                BookmarksTabEvent::ToggleDiffFormat => {
                     self.diff_format = self.diff_format.get_next(self.config.diff_tool());
+                    let diff_format = self.diff_format.clone();
+                    panel_settings::save(&commander.env.root, Panel::Bookmarks, |settings| {
+                        settings.diff_format = Some(diff_format)
+                    });
                     self.refresh_bookmark(commander);
                 }
-                KeyCode::Char('R') | KeyCode::F(5) => {
+                BookmarksTabEvent::Refresh => {
                     self.refresh_bookmarks(commander);
                     self.refresh_bookmark(commander);
                 }
-                KeyCode::Char('a') => {
+                BookmarksTabEvent::ToggleShowAll => {
                     self.show_all = !self.show_all;
                     self.refresh_bookmarks(commander);
                 }
-                KeyCode::Char('c') => {
+                BookmarksTabEvent::Create => {
                     let textarea = TextArea::default();
                     self.create = Some(CreateBookmark {
                         textarea,
@@ -796,20 +1178,42 @@ impl Component for BookmarksTab<'_> {
                     });
                     return Ok(ComponentInputResult::Handled);
                 }
-                KeyCode::Char('r') => {
+                BookmarksTabEvent::Rename => {
                     if let Some(BookmarkLine::Parsed { bookmark, .. }) = self.bookmark.as_ref() {
                         let mut textarea = TextArea::new(vec![bookmark.name.clone()]);
                         textarea.move_cursor(CursorMove::End);
+                        // Renames of remote bookmarks are local, only offer to push local ones
+                        let remotes = if bookmark.remote.is_none() {
+                            commander
+                                .get_tracked_remotes(&bookmark.name)
+                                .unwrap_or_default()
+                        } else {
+                            vec![]
+                        };
                         self.rename = Some(RenameBookmark {
                             textarea,
                             name: bookmark.name.clone(),
                             error: None,
+                            propagate: !remotes.is_empty(),
+                            remotes,
                         });
                         return Ok(ComponentInputResult::Handled);
                     }
                 }
-                KeyCode::Char('d') => {
+                BookmarksTabEvent::Delete => {
                     if let Some(BookmarkLine::Parsed { bookmark, .. }) = self.bookmark.as_ref() {
+                        // Ask where to delete a tracked bookmark, as deleting it locally also
+                        // deletes it on the remotes with the next push
+                        let remotes = commander
+                            .get_tracked_remotes(&bookmark.name)
+                            .unwrap_or_default();
+                        if !remotes.is_empty() {
+                            return Ok(ComponentInputResult::HandledAction(
+                                ComponentAction::SetPopup(Some(Box::new(
+                                    DeleteBookmarkPopup::new(bookmark.name.clone(), remotes),
+                                ))),
+                            ));
+                        }
                         self.delete = Some(DeleteBookmark {
                             name: bookmark.name.clone(),
                         });
@@ -828,7 +1232,7 @@ impl Component for BookmarksTab<'_> {
                             .open();
                     }
                 }
-                KeyCode::Char('f') => {
+                BookmarksTabEvent::Forget => {
                     if let Some(BookmarkLine::Parsed { bookmark, .. }) = self.bookmark.as_ref() {
                         self.forget = Some(ForgetBookmark {
                             name: bookmark.name.clone(),
@@ -848,8 +1252,35 @@ impl Component for BookmarksTab<'_> {
                             .open();
                     }
                 }
+                BookmarksTabEvent::Fetch => {
+                    if let Some(BookmarkLine::Parsed { bookmark, .. }) = self.bookmark.as_ref() {
+                        return Ok(Self::fetch_bookmarks(
+                            commander,
+                            fetch_remote(bookmark),
+                            vec![format!("exact:{}", bookmark.name)],
+                        ));
+                    }
+                }
+                BookmarksTabEvent::FetchGlob => {
+                    // Suggest the bookmarks sharing the prefix of the selected one, e.g.
+                    // `glob:feature/*` for `feature/login`
+                    let (pattern, remote) = match self.bookmark.as_ref() {
+                        Some(BookmarkLine::Parsed { bookmark, .. }) => {
+                            let pattern = match bookmark.name.rsplit_once('/') {
+                                Some((prefix, _)) => format!("glob:{prefix}/*"),
+                                None => format!("glob:{}", bookmark.name),
+                            };
+                            (pattern, fetch_remote(bookmark))
+                        }
+                        _ => ("glob:".to_owned(), None),
+                    };
+                    let mut textarea = TextArea::new(vec![pattern]);
+                    textarea.move_cursor(CursorMove::End);
+                    self.fetch = Some(FetchBookmarks { textarea, remote });
+                    return Ok(ComponentInputResult::Handled);
+                }
                 // TODO: Ask for confirmation?
-                KeyCode::Char('t') => {
+                BookmarksTabEvent::Track => {
                     if let Some(BookmarkLine::Parsed { bookmark, .. }) = self.bookmark.as_ref()
                         && bookmark.remote.is_some()
                         && bookmark.present
@@ -859,7 +1290,7 @@ impl Component for BookmarksTab<'_> {
                         self.refresh_bookmark(commander);
                     }
                 }
-                KeyCode::Char('T') => {
+                BookmarksTabEvent::Untrack => {
                     if let Some(BookmarkLine::Parsed { bookmark, .. }) = self.bookmark.as_ref()
                         && bookmark.remote.is_some()
                         && bookmark.present
@@ -869,7 +1300,7 @@ impl Component for BookmarksTab<'_> {
                         self.refresh_bookmark(commander);
                     }
                 }
-                KeyCode::Char('n') | KeyCode::Char('N') => {
+                BookmarksTabEvent::CreateNew { describe } => {
                     if let Some(BookmarkLine::Parsed { bookmark, .. }) = self.bookmark.as_ref()
                         && bookmark.present
                     {
@@ -887,47 +1318,47 @@ impl Component for BookmarksTab<'_> {
                             .with_listener(Some(self.popup_tx.clone()))
                             .open();
 
-                        self.describe_after_new = key.code == KeyCode::Char('N');
+                        self.describe_after_new = describe;
                     }
                 }
-                KeyCode::Char('e') | KeyCode::Char('E') => {
-                    if let Some(BookmarkLine::Parsed { bookmark, .. }) = self.bookmark.as_ref() {
-                        let ignore_immutable = key.code == KeyCode::Char('E');
-                        if bookmark.present {
-                            if commander.check_revision_immutable(&bookmark.to_string())?
-                                && !ignore_immutable
-                            {
-                                return Ok(ComponentInputResult::HandledAction(
-                                    ComponentAction::SetPopup(Some(Box::new(MessagePopup {
-                                        title: "Edit".into(),
-                                        messages: vec![
-                                            "The change cannot be edited because it is immutable."
-                                                .into(),
-                                        ]
-                                        .into(),
-                                        text_align: None,
-                                    }))),
-                                ));
-                            }
-
-                            self.popup = ConfirmDialogState::new(
-                                EDIT_POPUP_ID,
-                                Span::styled(" Edit ", Style::new().bold().cyan()),
-                                Text::from(vec![
-                                    Line::from("Are you sure you want to edit an existing change?"),
-                                    Line::from(format!("Bookmark: {bookmark}")),
-                                ]),
-                            );
-                            self.popup
-                                .with_yes_button(ButtonLabel::YES.clone())
-                                .with_no_button(ButtonLabel::NO.clone())
-                                .with_listener(Some(self.popup_tx.clone()))
-                                .open();
-                            self.edit_ignore_immutable = ignore_immutable;
+                BookmarksTabEvent::EditChange { ignore_immutable } => {
+                    if let Some(BookmarkLine::Parsed { bookmark, .. }) = self.bookmark.as_ref()
+                        && bookmark.present
+                    {
+                        if commander.check_revision_immutable(&bookmark.to_string())?
+                            && !ignore_immutable
+                        {
+                            return Ok(ComponentInputResult::HandledAction(
+                                ComponentAction::SetPopup(Some(Box::new(MessagePopup {
+                                    title: "Edit".into(),
+                                    messages: vec![
+                                        "The change cannot be edited because it is immutable."
+                                            .into(),
+                                    ]
+                                    .into(),
+                                    text_align: None,
+                                    ..Default::default()
+                                }))),
+                            ));
                         }
+
+                        self.popup = ConfirmDialogState::new(
+                            EDIT_POPUP_ID,
+                            Span::styled(" Edit ", Style::new().bold().cyan()),
+                            Text::from(vec![
+                                Line::from("Are you sure you want to edit an existing change?"),
+                                Line::from(format!("Bookmark: {bookmark}")),
+                            ]),
+                        );
+                        self.popup
+                            .with_yes_button(ButtonLabel::YES.clone())
+                            .with_no_button(ButtonLabel::NO.clone())
+                            .with_listener(Some(self.popup_tx.clone()))
+                            .open();
+                        self.edit_ignore_immutable = ignore_immutable;
                     }
                 }
-                KeyCode::Enter => {
+                BookmarksTabEvent::ViewInLog => {
                     if let Some(BookmarkLine::Parsed { bookmark, .. }) = self.bookmark.as_ref()
                         && bookmark.present
                     {
@@ -936,36 +1367,15 @@ impl Component for BookmarksTab<'_> {
                         ));
                     }
                 }
-                KeyCode::Char('?') => {
+                BookmarksTabEvent::OpenHelp => {
                     return Ok(ComponentInputResult::HandledAction(
-                        ComponentAction::SetPopup(Some(Box::new(HelpPopup::new(
-                            vec![
-                                ("j/k".to_owned(), "scroll down/up".to_owned()),
-                                ("J/K".to_owned(), "scroll down by ½ page".to_owned()),
-                                ("a".to_owned(), "show all remotes".to_owned()),
-                                ("c".to_owned(), "create bookmark".to_owned()),
-                                ("r".to_owned(), "rename bookmark".to_owned()),
-                                ("d/f".to_owned(), "delete/forget bookmark".to_owned()),
-                                ("t/T".to_owned(), "track/untrack bookmark".to_owned()),
-                                ("Enter".to_owned(), "view in log".to_owned()),
-                                ("n".to_owned(), "new from bookmark".to_owned()),
-                                ("N".to_owned(), "new and describe".to_owned()),
-                                ("e".to_owned(), "edit bookmark".to_owned()),
-                            ],
-                            vec![
-                                ("Ctrl+e/Ctrl+y".to_owned(), "scroll down/up".to_owned()),
-                                (
-                                    "Ctrl+d/Ctrl+u".to_owned(),
-                                    "scroll down/up by ½ page".to_owned(),
-                                ),
-                                (
-                                    "Ctrl+f/Ctrl+b".to_owned(),
-                                    "scroll down/up by page".to_owned(),
-                                ),
-                                ("w".to_owned(), "toggle diff format".to_owned()),
-                                ("W".to_owned(), "toggle wrapping".to_owned()),
-                            ],
-                        )))),
+                        ComponentAction::SetPopup(Some(Box::new(
+                            HelpPopup::new(
+                                self.keybinds.make_main_panel_help(),
+                                DetailsPanel::help(),
+                            )
+                            .read_only(&self.read_only_keys()),
+                        ))),
                     ));
                 }
                 _ => return Ok(ComponentInputResult::NotHandled),
@@ -981,4 +1391,38 @@ impl Component for BookmarksTab<'_> {
 
         Ok(ComponentInputResult::Handled)
     }
+
+    fn hints(&self) -> Vec<(String, String)> {
+        if self.fetch.is_some() {
+            return vec![
+                ("Enter".to_owned(), "fetch".to_owned()),
+                ("Esc".to_owned(), "cancel".to_owned()),
+            ];
+        }
+        if let Some(rename) = self.rename.as_ref()
+            && !rename.remotes.is_empty()
+        {
+            return vec![
+                ("Ctrl+s".to_owned(), "save".to_owned()),
+                ("Tab".to_owned(), "toggle remotes".to_owned()),
+                ("Esc".to_owned(), "cancel".to_owned()),
+            ];
+        }
+        if self.create.is_some() || self.rename.is_some() || self.describe_textarea.is_some() {
+            return vec![
+                ("Ctrl+s".to_owned(), "save".to_owned()),
+                ("Esc".to_owned(), "cancel".to_owned()),
+            ];
+        }
+        if self.popup.is_opened() {
+            return vec![
+                ("y/n".to_owned(), "confirm".to_owned()),
+                ("q".to_owned(), "close".to_owned()),
+            ];
+        }
+        if self.config.read_only() {
+            return self.keybinds.make_read_only_hints();
+        }
+        self.keybinds.make_hints()
+    }
 }
@@ -0,0 +1,142 @@
+//! Popup listing the files changed by a change, without their diff, for a quick look at a change
+//! while the details panel keeps showing another diff. Enter shows the file in the files tab.
+
+use anyhow::Result;
+use ratatui::{
+    Frame,
+    crossterm::event::{Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{Clear, List, ListState, Paragraph},
+};
+
+use crate::{
+    ComponentInputResult,
+    commander::{CommandError, Commander, files::File, log::Head},
+    ui::{Component, ComponentAction, styles::create_popup_block, utils::centered_rect},
+};
+
+pub struct ChangedFilesPopup {
+    head: Head,
+    files: Result<Vec<File>, CommandError>,
+    list_state: ListState,
+}
+
+impl ChangedFilesPopup {
+    pub fn new(commander: &Commander, head: Head) -> Self {
+        let files = commander.get_files(&head);
+        Self {
+            head,
+            files,
+            list_state: ListState::default().with_selected(Some(0)),
+        }
+    }
+
+    fn selected_file(&self) -> Option<&File> {
+        self.files.as_ref().ok()?.get(self.list_state.selected()?)
+    }
+}
+
+impl Component for ChangedFilesPopup {
+    fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+        let area = centered_rect(area, 60, 60);
+        f.render_widget(Clear, area);
+
+        let change_id: String = self.head.change_id.as_str().chars().take(8).collect();
+        let title = match self.files.as_ref() {
+            Ok(files) => format!("{} files changed by {change_id}", files.len()),
+            Err(_) => format!("Files changed by {change_id}"),
+        };
+        let block = create_popup_block(&title);
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let [list_area, help_area] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(inner);
+
+        match self.files.as_ref() {
+            Ok(files) if files.is_empty() => {
+                f.render_widget(
+                    Paragraph::new("The change has no changed files").fg(Color::DarkGray),
+                    list_area,
+                );
+            }
+            Ok(files) => {
+                let items = files.iter().map(|file| {
+                    let color = file
+                        .diff_type
+                        .as_ref()
+                        .map(|diff_type| diff_type.color())
+                        .unwrap_or_default();
+                    Line::raw(file.line.clone()).fg(color)
+                });
+                let list = List::new(items).highlight_style(Style::default().reversed());
+                f.render_stateful_widget(list, list_area, &mut self.list_state);
+            }
+            Err(err) => {
+                f.render_widget(Paragraph::new(err.into_text("")?), list_area);
+            }
+        }
+
+        f.render_widget(
+            Paragraph::new(Line::from("Enter: show in files tab | Esc: close").fg(Color::DarkGray))
+                .centered(),
+            help_area,
+        );
+
+        Ok(())
+    }
+
+    fn input(&mut self, _commander: &mut Commander, event: Event) -> Result<ComponentInputResult> {
+        let Event::Key(key) = event else {
+            return Ok(ComponentInputResult::Handled);
+        };
+        if key.kind != KeyEventKind::Press {
+            return Ok(ComponentInputResult::Handled);
+        }
+
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => self.list_state.select_next(),
+            KeyCode::Char('k') | KeyCode::Up => self.list_state.select_previous(),
+            KeyCode::Enter => {
+                if let Some(path) = self.selected_file().and_then(|file| file.path.clone()) {
+                    return Ok(ComponentInputResult::HandledAction(
+                        ComponentAction::Multiple(vec![
+                            ComponentAction::SetPopup(None),
+                            // Any line, so the diff starts at the top
+                            ComponentAction::ViewFileLine {
+                                head: self.head.clone(),
+                                path,
+                                line: None,
+                                text: String::new(),
+                            },
+                        ]),
+                    ));
+                }
+            }
+            KeyCode::Esc | KeyCode::Char('q') => {
+                return Ok(ComponentInputResult::HandledAction(
+                    ComponentAction::SetPopup(None),
+                ));
+            }
+            _ => return Ok(ComponentInputResult::NotHandled),
+        }
+        // select_next can move past the last file
+        if let Ok(files) = self.files.as_ref()
+            && let Some(selected) = self.list_state.selected()
+            && selected >= files.len()
+        {
+            self.list_state.select(files.len().checked_sub(1));
+        }
+        Ok(ComponentInputResult::Handled)
+    }
+
+    fn hints(&self) -> Vec<(String, String)> {
+        vec![
+            ("j/k".to_owned(), "select".to_owned()),
+            ("Enter".to_owned(), "show in files tab".to_owned()),
+            ("Esc".to_owned(), "close".to_owned()),
+        ]
+    }
+}
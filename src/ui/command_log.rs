@@ -0,0 +1,90 @@
+/*!
+The command log lists the most recent commands blazingjj ran, either in a strip
+at the bottom of the screen or expanded in a popup.
+*/
+
+use ansi_to_tui::IntoText;
+use ratatui::{
+    Frame,
+    layout::{Alignment, Rect},
+    style::{Color, Style, Stylize},
+    text::{Line, Span, Text},
+    widgets::{Block, BorderType, Paragraph},
+};
+
+use crate::{
+    commander::{
+        history::{CommandRecord, command_history},
+        output_processors::process_output,
+    },
+    env::OutputProcessor,
+    ui::message_popup::MessagePopup,
+};
+
+/// Number of commands shown in the strip
+pub const COMMAND_LOG_STRIP_COMMANDS: u16 = 4;
+
+fn record_line(record: &CommandRecord) -> Line<'static> {
+    let mut spans = vec![
+        Span::raw(format!("{:>6}ms ", record.duration.as_millis())).fg(Color::DarkGray),
+        Span::raw(record.command_line()),
+    ];
+    if record.failed() {
+        spans[1] = spans[1].clone().fg(Color::Red);
+        if let Some(status) = record.status {
+            spans.push(Span::raw(format!(" (exit {status})")).fg(Color::Red));
+        }
+    }
+    Line::from(spans)
+}
+
+/// Draw the last commands with their durations, newest at the bottom
+pub fn draw_command_log_strip(f: &mut Frame<'_>, area: Rect) {
+    let history = command_history();
+    let lines: Vec<Line> = history
+        .iter()
+        .skip(
+            history
+                .len()
+                .saturating_sub(COMMAND_LOG_STRIP_COMMANDS as usize),
+        )
+        .map(record_line)
+        .collect();
+
+    let paragraph = Paragraph::new(lines).block(
+        Block::bordered()
+            .title(" Command log ")
+            .title_bottom(Line::from(" `: hide | ~: expand ").right_aligned())
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(Color::DarkGray)),
+    );
+    f.render_widget(paragraph, area);
+}
+
+/// Popup listing all recorded commands, newest first, each with its output rewritten by the
+/// `blazingjj.output-processors`
+pub fn command_log_popup(processors: &[OutputProcessor]) -> MessagePopup<'static> {
+    let mut lines: Vec<Line> = vec![];
+    for record in command_history().iter().rev() {
+        lines.push(record_line(record));
+        let output = process_output(processors, &[record.command_line()], &record.output);
+        let Ok(text) = output.into_text() else {
+            continue;
+        };
+        for line in text.lines {
+            let mut spans = vec![Span::raw("         ")];
+            spans.extend(line.spans);
+            lines.push(Line::from(spans).fg(Color::DarkGray));
+        }
+    }
+    MessagePopup {
+        title: "Command log".into(),
+        messages: if lines.is_empty() {
+            Text::from("No commands run yet")
+        } else {
+            Text::from(lines)
+        },
+        text_align: Some(Alignment::Left),
+        ..Default::default()
+    }
+}
@@ -13,7 +13,9 @@ use crate::{
     ComponentInputResult,
     commander::Commander,
     ui::{
-        Component, ComponentAction, message_popup::MessagePopup, utils::centered_rect_line_height,
+        Component, ComponentAction,
+        message_popup::MessagePopup,
+        utils::{centered_rect_line_height, textarea_input},
     },
 };
 
@@ -102,6 +104,8 @@ impl Component for CommandPopup<'_> {
                         ]
                         .join("\n"),
                     };
+                    let message =
+                        commander.process_output(&format!("jj {command_input}"), &message);
 
                     if message.trim().is_empty() {
                         return Ok(ComponentInputResult::HandledAction(
@@ -118,6 +122,7 @@ impl Component for CommandPopup<'_> {
                                 title: format!("jj {command_input}").into(),
                                 messages: message.into(),
                                 text_align: Alignment::Left.into(),
+                                ..Default::default()
                             }))),
                             ComponentAction::RefreshTab(),
                         ]),
@@ -131,7 +136,14 @@ impl Component for CommandPopup<'_> {
                 _ => {}
             }
         };
-        self.command_textarea.input(event);
+        textarea_input(&mut self.command_textarea, event, false);
         Ok(ComponentInputResult::Handled)
     }
+
+    fn hints(&self) -> Vec<(String, String)> {
+        vec![
+            ("Enter".to_owned(), "run".to_owned()),
+            ("Esc".to_owned(), "cancel".to_owned()),
+        ]
+    }
 }
@@ -0,0 +1,261 @@
+//! Popup listing the conflicted files of every revision of the log, e.g. after a large rebase.
+//! Enter shows a revision in the log or a file in the files tab, and a file can be resolved with
+//! the [ResolvePopup] right away. The conflicts are listed in a background thread, as it takes a
+//! `jj resolve --list` per conflicted revision.
+
+use std::{
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+    time::Instant,
+};
+
+use anyhow::Result;
+use ratatui::{
+    Frame,
+    crossterm::event::{Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Clear, List, ListState, Paragraph},
+};
+
+use crate::{
+    ComponentInputResult,
+    commander::{Commander, resolve::ConflictedRevision},
+    ui::{
+        Component, ComponentAction, read_only_popup,
+        resolve_popup::ResolvePopup,
+        styles::create_popup_block,
+        utils::{centered_rect, spinner},
+    },
+};
+
+/// A line of the list, with the indices into the revisions and their paths
+#[derive(Clone, Copy)]
+enum ConflictRow {
+    Revision(usize),
+    Path(usize, usize),
+}
+
+pub struct ConflictsPopup {
+    /// None while the conflicts are being listed
+    revisions: Option<Result<Vec<ConflictedRevision>, String>>,
+    revisions_rx: Option<Receiver<Result<Vec<ConflictedRevision>, String>>>,
+    started: Instant,
+    rows: Vec<ConflictRow>,
+    list_state: ListState,
+}
+
+impl ConflictsPopup {
+    pub fn new(commander: &Commander, revset: &str) -> Self {
+        let (tx, rx) = mpsc::channel();
+        let commander = Commander::new(&commander.env);
+        let revset = revset.to_owned();
+        thread::spawn(move || {
+            let _ = tx.send(
+                commander
+                    .get_revset_conflicts(&revset)
+                    .map_err(|err| err.to_string()),
+            );
+        });
+        Self {
+            revisions: None,
+            revisions_rx: Some(rx),
+            started: Instant::now(),
+            rows: Vec::new(),
+            list_state: ListState::default().with_selected(Some(0)),
+        }
+    }
+
+    fn set_revisions(&mut self, revisions: Result<Vec<ConflictedRevision>, String>) {
+        self.rows = revisions
+            .as_ref()
+            .map(|revisions| {
+                revisions
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(revision, conflicted)| {
+                        std::iter::once(ConflictRow::Revision(revision)).chain(
+                            (0..conflicted.paths.len())
+                                .map(move |path| ConflictRow::Path(revision, path)),
+                        )
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        self.revisions = Some(revisions);
+        self.revisions_rx = None;
+    }
+
+    /// The revision of the selected line, and the path if a file is selected
+    fn selected(&self) -> Option<(&ConflictedRevision, Option<&String>)> {
+        let revisions = self.revisions.as_ref()?.as_ref().ok()?;
+        match self.rows.get(self.list_state.selected()?)? {
+            ConflictRow::Revision(revision) => Some((revisions.get(*revision)?, None)),
+            ConflictRow::Path(revision, path) => {
+                let revision = revisions.get(*revision)?;
+                Some((revision, Some(revision.paths.get(*path)?)))
+            }
+        }
+    }
+}
+
+impl Component for ConflictsPopup {
+    fn update(&mut self, _commander: &mut Commander) -> Result<Option<ComponentAction>> {
+        let Some(rx) = self.revisions_rx.as_ref() else {
+            return Ok(None);
+        };
+        match rx.try_recv() {
+            Ok(revisions) => self.set_revisions(revisions),
+            Err(TryRecvError::Empty) => {}
+            Err(TryRecvError::Disconnected) => {
+                self.set_revisions(Err("the conflicts thread stopped".to_owned()))
+            }
+        }
+        Ok(None)
+    }
+
+    fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+        let area = centered_rect(area, 70, 60);
+        f.render_widget(Clear, area);
+
+        let title = match self.revisions.as_ref() {
+            Some(Ok(revisions)) => format!(
+                "{} conflicted files in {} revisions",
+                revisions
+                    .iter()
+                    .map(|revision| revision.paths.len())
+                    .sum::<usize>(),
+                revisions.len()
+            ),
+            _ => "Conflicts".to_owned(),
+        };
+        let block = create_popup_block(&title);
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let [list_area, help_area] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(inner);
+
+        match self.revisions.as_ref() {
+            None => {
+                f.render_widget(
+                    Paragraph::new(format!(
+                        "{} Listing the conflicts",
+                        spinner(self.started.elapsed())
+                    )),
+                    list_area,
+                );
+            }
+            Some(Ok(revisions)) if revisions.is_empty() => {
+                f.render_widget(
+                    Paragraph::new("No revision of the log has conflicts").fg(Color::DarkGray),
+                    list_area,
+                );
+            }
+            Some(Ok(revisions)) => {
+                let items = self.rows.iter().map(|row| match *row {
+                    ConflictRow::Revision(revision) => {
+                        let revision = &revisions[revision];
+                        let change_id: String =
+                            revision.head.change_id.as_str().chars().take(8).collect();
+                        let title = if revision.title.is_empty() {
+                            Span::raw("(no description set)").dark_gray()
+                        } else {
+                            Span::raw(revision.title.clone())
+                        };
+                        Line::from(vec![Span::raw(change_id).magenta(), Span::raw(" "), title])
+                    }
+                    ConflictRow::Path(revision, path) => {
+                        Line::raw(format!("  {}", revisions[revision].paths[path])).red()
+                    }
+                });
+                let list = List::new(items).highlight_style(Style::default().reversed());
+                f.render_stateful_widget(list, list_area, &mut self.list_state);
+            }
+            Some(Err(err)) => {
+                f.render_widget(
+                    Paragraph::new(format!("Error getting the conflicts: {err}")).fg(Color::Red),
+                    list_area,
+                );
+            }
+        }
+
+        f.render_widget(
+            Paragraph::new(
+                Line::from("Enter: show | m: resolve file | Esc: close").fg(Color::DarkGray),
+            )
+            .centered(),
+            help_area,
+        );
+
+        Ok(())
+    }
+
+    fn input(&mut self, commander: &mut Commander, event: Event) -> Result<ComponentInputResult> {
+        let Event::Key(key) = event else {
+            return Ok(ComponentInputResult::Handled);
+        };
+        if key.kind != KeyEventKind::Press {
+            return Ok(ComponentInputResult::Handled);
+        }
+
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => self.list_state.select_next(),
+            KeyCode::Char('k') | KeyCode::Up => self.list_state.select_previous(),
+            KeyCode::Enter => {
+                let action = match self.selected() {
+                    Some((revision, None)) => ComponentAction::ViewLog(revision.head.clone()),
+                    Some((revision, Some(path))) => ComponentAction::ViewFileLine {
+                        head: revision.head.clone(),
+                        path: path.clone(),
+                        line: None,
+                        // The first conflict of the file
+                        text: "<<<<<<<".to_owned(),
+                    },
+                    None => return Ok(ComponentInputResult::Handled),
+                };
+                return Ok(ComponentInputResult::HandledAction(
+                    ComponentAction::Multiple(vec![ComponentAction::SetPopup(None), action]),
+                ));
+            }
+            KeyCode::Char('m') => {
+                if let Some((revision, Some(path))) = self.selected() {
+                    if commander.env.config.read_only() {
+                        return Ok(ComponentInputResult::HandledAction(read_only_popup()));
+                    }
+                    let popup = ResolvePopup::new(commander, revision.head.clone(), path.clone());
+                    return Ok(ComponentInputResult::HandledAction(
+                        ComponentAction::SetPopup(Some(Box::new(popup))),
+                    ));
+                }
+            }
+            KeyCode::Esc | KeyCode::Char('q') => {
+                return Ok(ComponentInputResult::HandledAction(
+                    ComponentAction::SetPopup(None),
+                ));
+            }
+            _ => return Ok(ComponentInputResult::NotHandled),
+        }
+        // select_next can move past the last line
+        if let Some(selected) = self.list_state.selected()
+            && selected >= self.rows.len()
+        {
+            self.list_state.select(self.rows.len().checked_sub(1));
+        }
+        Ok(ComponentInputResult::Handled)
+    }
+
+    fn is_busy(&self) -> bool {
+        self.revisions_rx.is_some()
+    }
+
+    fn hints(&self) -> Vec<(String, String)> {
+        vec![
+            ("j/k".to_owned(), "select".to_owned()),
+            ("Enter".to_owned(), "show".to_owned()),
+            ("m".to_owned(), "resolve file".to_owned()),
+            ("Esc".to_owned(), "close".to_owned()),
+        ]
+    }
+}
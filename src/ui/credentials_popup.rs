@@ -0,0 +1,70 @@
+//! Shown when a remote operation failed because git or ssh wanted to prompt for credentials. Offers
+//! to rerun the operation with the TUI suspended, so the prompt can be answered in the terminal.
+
+use anyhow::Result;
+use ratatui::{
+    Frame,
+    crossterm::event::{Event, KeyCode, KeyEventKind},
+    layout::Rect,
+    style::Stylize,
+    text::Line,
+};
+
+use crate::{
+    ComponentInputResult,
+    commander::{CommandError, Commander},
+    ui::{Component, ComponentAction, message_popup::MessagePopup},
+};
+
+pub struct CredentialsPopup {
+    message: MessagePopup<'static>,
+    /// jj arguments to rerun in the terminal
+    args: Vec<String>,
+}
+
+impl CredentialsPopup {
+    pub fn new(operation_name: &str, err: &CommandError, args: Vec<String>) -> Result<Self> {
+        let mut messages = err.into_text("")?;
+        messages.push_line(Line::raw(""));
+        messages.push_line(Line::raw("Press r to retry in the terminal").bold());
+
+        Ok(Self {
+            message: MessagePopup {
+                title: format!("{operation_name} needs credentials").into(),
+                messages,
+                text_align: None,
+                ..Default::default()
+            },
+            args,
+        })
+    }
+}
+
+impl Component for CredentialsPopup {
+    fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+        self.message.draw(f, area)
+    }
+
+    fn input(&mut self, _commander: &mut Commander, event: Event) -> Result<ComponentInputResult> {
+        if let Event::Key(key) = event
+            && key.kind == KeyEventKind::Press
+            && key.code == KeyCode::Char('r')
+        {
+            return Ok(ComponentInputResult::HandledAction(
+                ComponentAction::Multiple(vec![
+                    ComponentAction::SetPopup(None),
+                    ComponentAction::RunInTerminal(self.args.clone()),
+                ]),
+            ));
+        }
+
+        Ok(ComponentInputResult::NotHandled)
+    }
+
+    fn hints(&self) -> Vec<(String, String)> {
+        vec![
+            ("r".to_owned(), "retry in terminal".to_owned()),
+            ("Enter/Esc".to_owned(), "close".to_owned()),
+        ]
+    }
+}
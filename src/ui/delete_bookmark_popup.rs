@@ -0,0 +1,193 @@
+//! Popup asking where a bookmark tracked on remotes is deleted: only locally, only on the remotes
+//! or both. `jj bookmark delete` alone deletes it on the remotes with the next push, which is
+//! easy to miss.
+
+use anyhow::Result;
+use ratatui::{
+    Frame,
+    crossterm::event::{Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Clear, List, ListState, Paragraph},
+};
+
+use crate::{
+    ComponentInputResult,
+    commander::Commander,
+    ui::{
+        Component, ComponentAction, loader_popup::LoaderPopup, read_only_popup,
+        styles::create_popup_block, utils::centered_rect_line_height,
+    },
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Deletion {
+    Locally,
+    OnRemotes,
+    Everywhere,
+}
+
+const DELETIONS: [Deletion; 3] = [Deletion::Locally, Deletion::OnRemotes, Deletion::Everywhere];
+
+impl Deletion {
+    fn key(&self) -> char {
+        match self {
+            Deletion::Locally => 'l',
+            Deletion::OnRemotes => 'r',
+            Deletion::Everywhere => 'b',
+        }
+    }
+
+    fn description(&self, remotes: &str) -> String {
+        match self {
+            Deletion::Locally => format!("Only locally, keep it on {remotes}"),
+            Deletion::OnRemotes => format!("Only on {remotes} now, keep it locally"),
+            Deletion::Everywhere => {
+                format!("Locally, and on {remotes} with the next push")
+            }
+        }
+    }
+}
+
+pub struct DeleteBookmarkPopup {
+    name: String,
+    /// Remotes on which the bookmark is tracked
+    remotes: Vec<String>,
+    list_state: ListState,
+}
+
+impl DeleteBookmarkPopup {
+    pub fn new(name: String, remotes: Vec<String>) -> Self {
+        Self {
+            name,
+            remotes,
+            list_state: ListState::default().with_selected(Some(0)),
+        }
+    }
+
+    fn delete(&self, commander: &Commander, deletion: Deletion) -> ComponentAction {
+        let commander = Commander::new(&commander.env);
+        let (name, remotes) = (self.name.clone(), self.remotes.clone());
+        let loader = match deletion {
+            Deletion::Locally => LoaderPopup::new(format!("Deleting {name} locally"), move || {
+                commander.delete_bookmark_locally(&name, &remotes)?;
+                Ok(String::new())
+            }),
+            Deletion::OnRemotes => LoaderPopup::new(
+                format!("Deleting {name} on {}", self.remotes.join(", ")),
+                move || commander.delete_bookmark_on_remotes(&name, &remotes),
+            ),
+            Deletion::Everywhere => LoaderPopup::new(format!("Deleting {name}"), move || {
+                commander.delete_bookmark(&name)?;
+                Ok(String::new())
+            }),
+        };
+        ComponentAction::SetPopup(Some(Box::new(loader)))
+    }
+}
+
+impl Component for DeleteBookmarkPopup {
+    fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+        let area = centered_rect_line_height(area, 60, DELETIONS.len() as u16 + 5);
+        f.render_widget(Clear, area);
+
+        let title = format!("Delete {}", self.name);
+        let block = create_popup_block(&title);
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let [question_area, list_area, help_area] = Layout::vertical([
+            Constraint::Length(2),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+        ])
+        .areas(inner);
+
+        f.render_widget(
+            Paragraph::new(format!(
+                "{} is tracked on {}. Delete it:",
+                self.name,
+                self.remotes.join(", ")
+            )),
+            question_area,
+        );
+
+        let remotes = self.remotes.join(", ");
+        let items = DELETIONS.iter().map(|deletion| {
+            Line::from(vec![
+                Span::raw(format!(" {} ", deletion.key())).fg(Color::Cyan),
+                Span::raw(deletion.description(&remotes)),
+            ])
+        });
+        let list = List::new(items).highlight_style(Style::default().reversed());
+        f.render_stateful_widget(list, list_area, &mut self.list_state);
+
+        f.render_widget(
+            Paragraph::new(Line::from("Enter: delete | Esc: cancel").fg(Color::DarkGray))
+                .centered(),
+            help_area,
+        );
+
+        Ok(())
+    }
+
+    fn input(&mut self, commander: &mut Commander, event: Event) -> Result<ComponentInputResult> {
+        let Event::Key(key) = event else {
+            return Ok(ComponentInputResult::Handled);
+        };
+        if key.kind != KeyEventKind::Press {
+            return Ok(ComponentInputResult::Handled);
+        }
+
+        let deletion = match key.code {
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.list_state.select_next();
+                if self
+                    .list_state
+                    .selected()
+                    .is_some_and(|selected| selected >= DELETIONS.len())
+                {
+                    self.list_state.select(Some(DELETIONS.len() - 1));
+                }
+                return Ok(ComponentInputResult::Handled);
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.list_state.select_previous();
+                return Ok(ComponentInputResult::Handled);
+            }
+            KeyCode::Esc | KeyCode::Char('q') => {
+                return Ok(ComponentInputResult::HandledAction(
+                    ComponentAction::SetPopup(None),
+                ));
+            }
+            KeyCode::Enter => self
+                .list_state
+                .selected()
+                .and_then(|selected| DELETIONS.get(selected).copied()),
+            KeyCode::Char(c) => DELETIONS
+                .iter()
+                .find(|deletion| deletion.key() == c)
+                .copied(),
+            _ => None,
+        };
+        let Some(deletion) = deletion else {
+            return Ok(ComponentInputResult::NotHandled);
+        };
+        if commander.env.config.read_only() {
+            return Ok(ComponentInputResult::HandledAction(read_only_popup()));
+        }
+        Ok(ComponentInputResult::HandledAction(
+            self.delete(commander, deletion),
+        ))
+    }
+
+    fn hints(&self) -> Vec<(String, String)> {
+        vec![
+            ("l".to_owned(), "locally".to_owned()),
+            ("r".to_owned(), "on remotes".to_owned()),
+            ("b".to_owned(), "both".to_owned()),
+            ("Esc".to_owned(), "cancel".to_owned()),
+        ]
+    }
+}
@@ -0,0 +1,263 @@
+//! Popup describing the changes without description which have bookmarks, with descriptions
+//! derived from the bookmark names. The descriptions are shown in a table to review and edit
+//! before applying them.
+
+use anyhow::Result;
+use ratatui::{
+    Frame,
+    crossterm::event::{Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{Block, BorderType, Borders, Cell, Clear, Paragraph, Row, Table, TableState},
+};
+use tui_textarea::{CursorMove, TextArea};
+
+use crate::{
+    ComponentInputResult,
+    commander::{
+        CommandError, Commander,
+        bookmarks::{UndescribedChange, render_bookmark_description},
+    },
+    ui::{
+        Component, ComponentAction,
+        message_popup::MessagePopup,
+        read_only_popup,
+        styles::create_popup_block,
+        utils::{centered_rect, textarea_input},
+    },
+};
+
+struct DescribeRow {
+    change: UndescribedChange,
+    description: String,
+    /// Whether the description is applied
+    selected: bool,
+}
+
+pub struct DescribeBookmarksPopup<'a> {
+    rows: Result<Vec<DescribeRow>, CommandError>,
+    table_state: TableState,
+    /// Input for the description of the selected row
+    textarea: Option<TextArea<'a>>,
+}
+
+impl DescribeBookmarksPopup<'_> {
+    pub fn new(commander: &Commander) -> Self {
+        let template = commander.env.config.bookmark_description_template();
+        let rows = commander
+            .get_undescribed_bookmarked_changes()
+            .map(|changes| {
+                changes
+                    .into_iter()
+                    .map(|change| DescribeRow {
+                        description: render_bookmark_description(template, &change.bookmarks[0]),
+                        change,
+                        selected: true,
+                    })
+                    .collect()
+            });
+        Self {
+            rows,
+            table_state: TableState::default().with_selected(Some(0)),
+            textarea: None,
+        }
+    }
+
+    fn selected_row(&mut self) -> Option<&mut DescribeRow> {
+        let selected = self.table_state.selected()?;
+        self.rows.as_mut().ok()?.get_mut(selected)
+    }
+
+    /// Describe the selected changes, returning a popup with the result
+    fn apply(&self, commander: &Commander) -> ComponentAction {
+        let rows = self.rows.as_ref().map(Vec::as_slice).unwrap_or_default();
+        let mut described = 0;
+        let mut errors = vec![];
+        for row in rows.iter().filter(|row| row.selected) {
+            match commander.run_describe(row.change.change_id.as_str(), &row.description) {
+                Ok(()) => described += 1,
+                Err(err) => errors.push(format!("{}: {err:#}", row.change.change_id)),
+            }
+        }
+
+        let mut messages = vec![format!("Described {described} changes")];
+        if !errors.is_empty() {
+            messages.push(String::new());
+            messages.extend(errors);
+        }
+        ComponentAction::Multiple(vec![
+            ComponentAction::SetPopup(Some(Box::new(MessagePopup {
+                title: "Describe from bookmarks".into(),
+                messages: messages.join("\n").into(),
+                text_align: None,
+                ..Default::default()
+            }))),
+            ComponentAction::RefreshTab(),
+        ])
+    }
+}
+
+impl Component for DescribeBookmarksPopup<'_> {
+    fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+        let area = centered_rect(area, 80, 60);
+        f.render_widget(Clear, area);
+
+        let block = create_popup_block("Describe from bookmarks");
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let [table_area, input_area, help_area] = Layout::vertical([
+            Constraint::Fill(1),
+            Constraint::Length(if self.textarea.is_some() { 3 } else { 0 }),
+            Constraint::Length(1),
+        ])
+        .areas(inner);
+
+        match self.rows.as_ref() {
+            Ok(rows) if rows.is_empty() => {
+                f.render_widget(
+                    Paragraph::new("No changes without description have bookmarks"),
+                    table_area,
+                );
+            }
+            Ok(rows) => {
+                let table_rows = rows.iter().map(|row| {
+                    let style = if row.selected {
+                        Style::default()
+                    } else {
+                        Style::default().fg(Color::DarkGray)
+                    };
+                    Row::new(vec![
+                        Cell::from(if row.selected { "[x]" } else { "[ ]" }),
+                        Cell::from(
+                            row.change
+                                .change_id
+                                .as_str()
+                                .chars()
+                                .take(8)
+                                .collect::<String>(),
+                        )
+                        .magenta(),
+                        Cell::from(row.change.bookmarks.join(" ")),
+                        Cell::from(row.description.clone()),
+                    ])
+                    .style(style)
+                });
+                let table = Table::new(
+                    table_rows,
+                    [
+                        Constraint::Length(3),
+                        Constraint::Length(8),
+                        Constraint::Fill(1),
+                        Constraint::Fill(2),
+                    ],
+                )
+                .header(Row::new(vec!["", "Change", "Bookmarks", "Description"]).bold())
+                .row_highlight_style(Style::default().reversed());
+                f.render_stateful_widget(table, table_area, &mut self.table_state);
+            }
+            Err(err) => {
+                f.render_widget(
+                    Paragraph::new(format!("Error getting changes: {err}")).fg(Color::Red),
+                    table_area,
+                );
+            }
+        }
+
+        if let Some(textarea) = self.textarea.as_mut() {
+            textarea.set_block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded)
+                    .title(" Description "),
+            );
+            f.render_widget(&*textarea, input_area);
+        }
+
+        let help = if self.textarea.is_some() {
+            "Enter: save | Esc: cancel"
+        } else {
+            "Space: toggle | e: edit | Enter: apply | Esc: close"
+        };
+        f.render_widget(
+            Paragraph::new(Line::from(help).fg(Color::DarkGray)).centered(),
+            help_area,
+        );
+
+        Ok(())
+    }
+
+    fn input(&mut self, commander: &mut Commander, event: Event) -> Result<ComponentInputResult> {
+        if let Some(textarea) = self.textarea.as_mut() {
+            if let Event::Key(key) = event {
+                match key.code {
+                    KeyCode::Enter => {
+                        let description = textarea.lines().join(" ");
+                        self.textarea = None;
+                        if let Some(row) = self.selected_row() {
+                            row.description = description;
+                            row.selected = true;
+                        }
+                        return Ok(ComponentInputResult::Handled);
+                    }
+                    KeyCode::Esc => {
+                        self.textarea = None;
+                        return Ok(ComponentInputResult::Handled);
+                    }
+                    _ => {}
+                }
+            }
+            textarea_input(textarea, event, false);
+            return Ok(ComponentInputResult::Handled);
+        }
+
+        let Event::Key(key) = event else {
+            return Ok(ComponentInputResult::Handled);
+        };
+        if key.kind != KeyEventKind::Press {
+            return Ok(ComponentInputResult::Handled);
+        }
+
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => self.table_state.select_next(),
+            KeyCode::Char('k') | KeyCode::Up => self.table_state.select_previous(),
+            KeyCode::Char(' ') => {
+                if let Some(row) = self.selected_row() {
+                    row.selected = !row.selected;
+                }
+            }
+            KeyCode::Char('e') => {
+                if let Some(row) = self.selected_row() {
+                    let mut textarea = TextArea::new(vec![row.description.clone()]);
+                    textarea.move_cursor(CursorMove::End);
+                    self.textarea = Some(textarea);
+                }
+            }
+            KeyCode::Enter if commander.env.config.read_only() => {
+                return Ok(ComponentInputResult::HandledAction(read_only_popup()));
+            }
+            KeyCode::Enter => {
+                return Ok(ComponentInputResult::HandledAction(self.apply(commander)));
+            }
+            _ => return Ok(ComponentInputResult::NotHandled),
+        }
+        Ok(ComponentInputResult::Handled)
+    }
+
+    fn hints(&self) -> Vec<(String, String)> {
+        if self.textarea.is_some() {
+            return vec![
+                ("Enter".to_owned(), "save".to_owned()),
+                ("Esc".to_owned(), "cancel".to_owned()),
+            ];
+        }
+        vec![
+            ("j/k".to_owned(), "select".to_owned()),
+            ("Space".to_owned(), "toggle".to_owned()),
+            ("e".to_owned(), "edit".to_owned()),
+            ("Enter".to_owned(), "apply".to_owned()),
+            ("Esc".to_owned(), "close".to_owned()),
+        ]
+    }
+}
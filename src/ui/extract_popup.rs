@@ -0,0 +1,336 @@
+//! Popup selecting the hunks of the selected files of a change and asking for the description
+//! of a new sibling change, which they are extracted into.
+
+use anyhow::Result;
+use ratatui::{
+    crossterm::event::{Event, KeyCode, KeyEventKind, KeyModifiers},
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::{Color, Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Clear, Paragraph},
+};
+use tui_textarea::TextArea;
+
+use crate::{
+    ComponentInputResult,
+    commander::{
+        Commander,
+        hunks::{ExtractedFile, FileHunks},
+        ids::CommitId,
+    },
+    ui::{
+        Component, ComponentAction,
+        message_popup::MessagePopup,
+        utils::{centered_rect, textarea_input},
+    },
+};
+
+pub struct ExtractPopup<'a> {
+    commit_id: CommitId,
+    paths: Vec<String>,
+    /// Hunks of the files with changed lines. Files without, like binary files, are extracted
+    /// whole.
+    files: Vec<FileHunks>,
+    /// Whether each hunk of `files` is extracted, all are at first
+    selected: Vec<Vec<bool>>,
+    /// Hunk under the cursor, as the index of the file and of its hunk
+    cursor: (usize, usize),
+    /// Whether the keys select hunks instead of editing the description
+    hunks_focused: bool,
+    description_textarea: TextArea<'a>,
+}
+
+impl ExtractPopup<'_> {
+    pub fn new(commander: &Commander, commit_id: CommitId, paths: Vec<String>) -> Result<Self> {
+        let files: Vec<FileHunks> = commander
+            .get_hunks(&commit_id, &paths)?
+            .into_iter()
+            .filter(|file| !file.hunks.is_empty())
+            .collect();
+        let selected = files
+            .iter()
+            .map(|file| vec![true; file.hunks.len()])
+            .collect();
+        // Whole files are extracted unless a file has several hunks to choose from
+        let hunks_focused = files.iter().any(|file| file.hunks.len() > 1);
+        Ok(Self {
+            commit_id,
+            paths,
+            files,
+            selected,
+            cursor: (0, 0),
+            hunks_focused,
+            description_textarea: TextArea::default(),
+        })
+    }
+
+    /// Positions of all hunks, in the order they are shown
+    fn hunk_positions(&self) -> Vec<(usize, usize)> {
+        self.files
+            .iter()
+            .enumerate()
+            .flat_map(|(file_index, file)| {
+                (0..file.hunks.len()).map(move |hunk_index| (file_index, hunk_index))
+            })
+            .collect()
+    }
+
+    fn scroll(&mut self, offset: isize) {
+        let positions = self.hunk_positions();
+        if let Some(index) = positions
+            .iter()
+            .position(|position| *position == self.cursor)
+        {
+            let index = index.saturating_add_signed(offset).min(positions.len() - 1);
+            self.cursor = positions[index];
+        }
+    }
+
+    fn toggle_hunk(&mut self) {
+        let (file_index, hunk_index) = self.cursor;
+        if let Some(selected) = self
+            .selected
+            .get_mut(file_index)
+            .and_then(|file| file.get_mut(hunk_index))
+        {
+            *selected = !*selected;
+        }
+    }
+
+    /// Select all hunks, or none if all are selected
+    fn toggle_all(&mut self) {
+        let select = !self.selected.iter().flatten().all(|selected| *selected);
+        self.selected
+            .iter_mut()
+            .flatten()
+            .for_each(|selected| *selected = select);
+    }
+
+    /// Files to extract with the hunks which stay in the change, without files none of whose
+    /// hunks are selected
+    fn extracted_files(&self) -> Vec<ExtractedFile> {
+        self.paths
+            .iter()
+            .filter_map(|path| {
+                let Some(index) = self.files.iter().position(|file| &file.path == path) else {
+                    return Some(ExtractedFile {
+                        path: path.clone(),
+                        kept_hunks: vec![],
+                    });
+                };
+                let selected = &self.selected[index];
+                if !selected.contains(&true) {
+                    return None;
+                }
+                Some(ExtractedFile {
+                    path: path.clone(),
+                    kept_hunks: self.files[index]
+                        .hunks
+                        .iter()
+                        .zip(selected)
+                        .filter(|(_, selected)| !**selected)
+                        .map(|(hunk, _)| hunk.clone())
+                        .collect(),
+                })
+            })
+            .collect()
+    }
+
+    /// Lines of the hunks with their selection, and the index of the line of the hunk under the
+    /// cursor
+    fn hunk_lines(&self) -> (Vec<Line<'static>>, usize) {
+        let mut lines = vec![];
+        let mut cursor_line = 0;
+        for (file_index, file) in self.files.iter().enumerate() {
+            lines.push(Line::from(file.path.clone()).bold());
+            for (hunk_index, hunk) in file.hunks.iter().enumerate() {
+                let checkbox = if self.selected[file_index][hunk_index] {
+                    "[x] "
+                } else {
+                    "[ ] "
+                };
+                let mut header = Line::from(format!("{checkbox}{}", hunk.header)).cyan();
+                if (file_index, hunk_index) == self.cursor {
+                    cursor_line = lines.len();
+                    if self.hunks_focused {
+                        header = header.add_modifier(Modifier::REVERSED);
+                    }
+                }
+                lines.push(header);
+                lines.extend(hunk.lines.iter().map(|line| {
+                    let style = match line.chars().next() {
+                        Some('+') => Style::default().fg(Color::Green),
+                        Some('-') => Style::default().fg(Color::Red),
+                        _ => Style::default(),
+                    };
+                    Line::styled(format!("    {line}"), style)
+                }));
+            }
+        }
+        (lines, cursor_line)
+    }
+
+    fn help(&self) -> &'static str {
+        match (self.files.is_empty(), self.hunks_focused) {
+            (true, _) => "Ctrl+s: extract | Escape: cancel",
+            (false, true) => {
+                "j/k: move | Space: toggle hunk | a: toggle all | Tab: description | Ctrl+s: extract | Escape: cancel"
+            }
+            (false, false) => "Tab: hunks | Ctrl+s: extract | Escape: cancel",
+        }
+    }
+}
+
+impl Component for ExtractPopup<'_> {
+    fn draw(
+        &mut self,
+        f: &mut ratatui::Frame<'_>,
+        area: ratatui::prelude::Rect,
+    ) -> anyhow::Result<()> {
+        let title = match self.paths.as_slice() {
+            [path] => format!(" Extract {path} into new change "),
+            paths => format!(" Extract {} files into new change ", paths.len()),
+        };
+        let block = Block::bordered()
+            .title(Span::styled(title, Style::new().bold().cyan()))
+            .title_alignment(Alignment::Center)
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(Color::Green));
+        let area = if self.files.is_empty() {
+            centered_rect(area, 50, 50)
+        } else {
+            centered_rect(area, 80, 80)
+        };
+        f.render_widget(Clear, area);
+        f.render_widget(&block, area);
+
+        let popup_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(if self.files.is_empty() {
+                vec![Constraint::Fill(1), Constraint::Length(2)]
+            } else {
+                vec![
+                    Constraint::Fill(3),
+                    Constraint::Fill(1),
+                    Constraint::Length(2),
+                ]
+            })
+            .split(block.inner(area));
+
+        if !self.files.is_empty() {
+            let focused_style = |focused: bool| {
+                Style::default().fg(if focused {
+                    Color::Green
+                } else {
+                    Color::DarkGray
+                })
+            };
+            let (lines, cursor_line) = self.hunk_lines();
+            let hunks_area = popup_chunks[0];
+            // Keep the hunk under the cursor in view, with the file above it if possible
+            let scroll = cursor_line.saturating_sub(hunks_area.height as usize / 4);
+            let hunks = Paragraph::new(lines).scroll((scroll as u16, 0)).block(
+                Block::default()
+                    .borders(Borders::BOTTOM)
+                    .border_type(BorderType::Rounded)
+                    .border_style(focused_style(self.hunks_focused)),
+            );
+            f.render_widget(hunks, hunks_area);
+
+            let description_area = popup_chunks[1];
+            let description_block = Block::default()
+                .title("Description")
+                .title_style(focused_style(!self.hunks_focused));
+            f.render_widget(&description_block, description_area);
+            f.render_widget(
+                &self.description_textarea,
+                description_block.inner(description_area),
+            );
+        } else {
+            f.render_widget(&self.description_textarea, popup_chunks[0]);
+        }
+
+        let help = Paragraph::new(vec![self.help().into()])
+            .fg(Color::DarkGray)
+            .alignment(Alignment::Center)
+            .block(
+                Block::default()
+                    .borders(Borders::TOP)
+                    .border_type(BorderType::Rounded)
+                    .border_style(Style::default().fg(Color::DarkGray)),
+            );
+
+        f.render_widget(help, popup_chunks[popup_chunks.len() - 1]);
+        Ok(())
+    }
+
+    fn input(&mut self, commander: &mut Commander, event: Event) -> Result<ComponentInputResult> {
+        if let Event::Key(key) = event {
+            if key.kind != KeyEventKind::Press {
+                return Ok(ComponentInputResult::Handled);
+            }
+            match key.code {
+                KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    let files = self.extracted_files();
+                    if files.is_empty() {
+                        return Ok(ComponentInputResult::Handled);
+                    }
+                    let description = self.description_textarea.lines().join("\n");
+                    let action = match commander.run_extract(&self.commit_id, &files, &description)
+                    {
+                        Ok(()) => ComponentAction::SetPopup(None),
+                        Err(err) => ComponentAction::SetPopup(Some(Box::new(MessagePopup {
+                            title: "Extract error".into(),
+                            messages: commander.error_text(&err, "")?,
+                            text_align: None,
+                            ..Default::default()
+                        }))),
+                    };
+                    return Ok(ComponentInputResult::HandledAction(
+                        ComponentAction::Multiple(vec![action, ComponentAction::RefreshTab()]),
+                    ));
+                }
+                KeyCode::Esc => {
+                    return Ok(ComponentInputResult::HandledAction(
+                        ComponentAction::SetPopup(None),
+                    ));
+                }
+                KeyCode::Tab if !self.files.is_empty() => {
+                    self.hunks_focused = !self.hunks_focused;
+                    return Ok(ComponentInputResult::Handled);
+                }
+                _ => {}
+            }
+            if self.hunks_focused {
+                match key.code {
+                    KeyCode::Char('j') | KeyCode::Down => self.scroll(1),
+                    KeyCode::Char('k') | KeyCode::Up => self.scroll(-1),
+                    KeyCode::Char(' ') => self.toggle_hunk(),
+                    KeyCode::Char('a') => self.toggle_all(),
+                    _ => {}
+                }
+                return Ok(ComponentInputResult::Handled);
+            }
+        };
+        textarea_input(&mut self.description_textarea, event, true);
+        Ok(ComponentInputResult::Handled)
+    }
+
+    fn hints(&self) -> Vec<(String, String)> {
+        let mut hints = vec![];
+        if self.hunks_focused {
+            hints.extend([
+                ("Space".to_owned(), "toggle hunk".to_owned()),
+                ("a".to_owned(), "toggle all".to_owned()),
+            ]);
+        }
+        if !self.files.is_empty() {
+            hints.push(("Tab".to_owned(), "switch focus".to_owned()));
+        }
+        hints.extend([
+            ("Ctrl+s".to_owned(), "extract".to_owned()),
+            ("Esc".to_owned(), "cancel".to_owned()),
+        ]);
+        hints
+    }
+}
@@ -1,45 +1,108 @@
-use std::vec;
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread, vec,
+};
 
-use anyhow::Result;
-use tracing::instrument;
+use anyhow::{Result, bail};
+use tracing::{instrument, warn};
 
 use crate::{
     ComponentInputResult,
     commander::{
         CommandError, Commander,
-        files::{Conflict, File},
+        files::{Conflict, DiffType, File, FileStat, diff_line_index, diff_sections},
+        ids::CommitId,
+        jj::FileMoveTarget,
         log::Head,
+        stream::StreamingOutput,
     },
-    env::{Config, DiffFormat},
+    env::{Config, DiffFormat, DiffOverride},
+    keybinds::{FilesTabEvent, FilesTabKeybinds},
+    panel_settings::{self, Panel},
+    review::ReviewState,
     ui::{
-        Component, ComponentAction, help_popup::HelpPopup, message_popup::MessagePopup,
-        panel::DetailsPanel, utils::tabs_to_spaces,
+        Component, ComponentAction,
+        extract_popup::ExtractPopup,
+        help_popup::HelpPopup,
+        message_popup::MessagePopup,
+        panel::DetailsPanel,
+        read_only_popup,
+        resolve_popup::ResolvePopup,
+        rollback_popup::RollbackPopup,
+        styles::{panel_border_style, panel_content_style},
+        utils::{has_command_modifier, spinner, tabs_to_spaces},
     },
 };
 
 use ansi_to_tui::IntoText;
+use itertools::Itertools;
 use ratatui::{
     crossterm::event::{Event, KeyCode, KeyEventKind},
     prelude::*,
     widgets::*,
 };
 
+/// Range of changes reviewed one by one, with the paths of their files to count the viewed files
+/// of the whole range
+struct ReviewRange {
+    revset: String,
+    changes: Vec<(Head, Vec<String>)>,
+}
+
+impl ReviewRange {
+    /// Index of the change in the range
+    fn position(&self, head: &Head) -> Option<usize> {
+        self.changes
+            .iter()
+            .position(|(change, _)| change.commit_id == head.commit_id)
+    }
+}
+
 /// Files tab. Shows files in selected change in main panel and selected file diff in details panel
 pub struct FilesTab {
     head: Head,
     is_current_head: bool,
 
     files_output: Result<Vec<File>, CommandError>,
+    /// Lines added and removed by path, shown in a column of the files list
+    file_stats: HashMap<String, FileStat>,
+    /// Whether the files with the most changed lines are listed first
+    sort_by_churn: bool,
     conflicts_output: Vec<Conflict>,
     files_list_state: ListState,
     files_height: u16,
+    /// Paths of files marked for extraction
+    marked_files: HashSet<String>,
+    /// Viewed files, set while in review mode
+    review: Option<ReviewState>,
+    /// Changes reviewed one by one, see [FilesTab::review_range]
+    review_range: Option<ReviewRange>,
 
     pub file: Option<File>,
     diff_panel: DetailsPanel,
     diff_output: Result<Option<String>, CommandError>,
+    /// Output of the external diff tool, shown while it is still running
+    diff_stream: Option<StreamingOutput>,
     diff_format: DiffFormat,
+    /// Whether the diff panel shows the diffs of all files, scrolled to the selected file and
+    /// selecting the file scrolled to
+    linked_diff: bool,
+    /// Path of the file of each section of the diff of all files
+    diff_sections: Vec<String>,
+    /// Commit whose large diff of all files was requested to be rendered anyway
+    full_diff: Option<CommitId>,
+    /// Line of the new version of the selected file and text to scroll the diff to once it
+    /// arrives, see [FilesTab::view_file_line]
+    diff_target: Option<(Option<usize>, String)>,
+
+    /// Files with the detected renames by commit, see [FilesTab::detect_renames]
+    renames: HashMap<CommitId, Vec<File>>,
+    renames_rx: Option<(CommitId, Receiver<Vec<File>>)>,
 
     config: Config,
+    keybinds: FilesTabKeybinds,
 }
 
 fn get_current_file_index(
@@ -57,15 +120,115 @@ fn get_current_file_index(
     None
 }
 
+/// Whether the file is untracked or ignored, so it has no diff
+fn is_untracked(file: &File) -> bool {
+    matches!(
+        file.diff_type,
+        Some(DiffType::Untracked | DiffType::Ignored)
+    )
+}
+
+/// The diff override for a file. Overrides only apply to the default diff format, toggling it
+/// shows the full diff.
+fn get_diff_override<'a>(
+    config: &'a Config,
+    diff_format: &DiffFormat,
+    file: &File,
+) -> Option<&'a DiffOverride> {
+    if *diff_format != config.diff_format() {
+        return None;
+    }
+    config.diff_override(file.diff_path()?)
+}
+
+/// Diff of a file, in the format of its diff override if any
+fn get_file_diff(
+    commander: &Commander,
+    head: &Head,
+    file: &File,
+    diff_format: &DiffFormat,
+) -> Result<Option<String>, CommandError> {
+    let config = &commander.env.config;
+    let Some(diff_override) = get_diff_override(config, diff_format, file) else {
+        return commander
+            .get_file_diff(head, file, diff_format, true)
+            .map(|diff| diff.map(|diff| tabs_to_spaces(&diff)));
+    };
+    commander
+        .get_file_diff(head, file, &diff_override.format, true)
+        .map(|diff| {
+            diff.map(|diff| {
+                format!(
+                    "Shortened by the diff override `{}`, toggle the diff format for the full \
+                     diff\n\n{}",
+                    diff_override.pattern,
+                    tabs_to_spaces(&diff)
+                )
+            })
+        })
+}
+
+/// Files changed in the change, with the detected renames once known, followed by the untracked
+/// files for the working copy
+fn get_files(
+    commander: &Commander,
+    head: &Head,
+    is_current_head: bool,
+    renames: Option<&Vec<File>>,
+) -> Result<Vec<File>, CommandError> {
+    let mut files = match renames {
+        Some(files) => files.clone(),
+        None => commander.get_files(head)?,
+    };
+    if is_current_head {
+        files.extend(commander.get_untracked_files()?);
+        // The files are listed without the ignored ones if git fails. Files added to the working
+        // copy by jj are still ignored by git, which only sees the files of its parent.
+        let ignored: Vec<File> = commander
+            .get_ignored_files()
+            .inspect_err(|err| warn!("Failed getting ignored files: {err}"))
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|ignored| !files.iter().any(|file| file.path == ignored.path))
+            .collect();
+        files.extend(ignored);
+    }
+    Ok(files)
+}
+
+/// Stats of the files in the change. The files are listed without them if they can't be read.
+fn get_file_stats(commander: &Commander, head: &Head) -> HashMap<String, FileStat> {
+    commander
+        .get_file_stats(head)
+        .inspect_err(|err| warn!("Failed getting file stats: {err}"))
+        .unwrap_or_default()
+}
+
+/// Sort the files with the most changed lines first. Files without stats, like untracked files,
+/// keep their order at the end.
+fn sort_by_churn(files: &mut [File], stats: &HashMap<String, FileStat>) {
+    files.sort_by_key(|file| {
+        std::cmp::Reverse(
+            file.path
+                .as_ref()
+                .and_then(|path| stats.get(path))
+                .map(FileStat::churn),
+        )
+    });
+}
+
 impl FilesTab {
     #[instrument(level = "info", name = "Initializing files tab", parent = None, skip(commander))]
     pub fn new(commander: &mut Commander, head: &Head) -> Result<Self> {
         let head = head.clone();
         let is_current_head = head == commander.get_current_head()?;
 
-        let diff_format = commander.env.config.diff_format();
+        let diff_format = panel_settings::load(&commander.env.root, Panel::Files)
+            .diff_format
+            .unwrap_or_else(|| commander.env.config.diff_format());
 
-        let files_output = commander.get_files(&head);
+        let files_output = get_files(commander, &head, is_current_head, None);
+        let file_stats = get_file_stats(commander, &head);
         let conflicts_output = commander.get_conflicts(&head.commit_id)?;
         let current_file = files_output
             .as_ref()
@@ -74,38 +237,118 @@ impl FilesTab {
             .map(|file| file.to_owned());
         let diff_output = current_file
             .as_ref()
-            .map(|current_change| {
-                commander.get_file_diff(&head, current_change, &diff_format, true)
-            })
-            .map_or(Ok(None), |r| {
-                r.map(|diff| diff.map(|diff| tabs_to_spaces(&diff)))
-            });
+            .filter(|current_file| !is_untracked(current_file))
+            .map(|current_file| get_file_diff(commander, &head, current_file, &diff_format))
+            .unwrap_or(Ok(None));
 
         let files_list_state = ListState::default().with_selected(get_current_file_index(
             current_file.as_ref(),
             files_output.as_ref(),
         ));
 
-        Ok(Self {
+        let mut files_tab = Self {
             head,
             is_current_head,
 
             files_output,
+            file_stats,
+            sort_by_churn: false,
             file: current_file,
             files_list_state,
             files_height: 0,
+            marked_files: HashSet::new(),
+            review: None,
+            review_range: None,
 
             conflicts_output,
 
             diff_output,
+            diff_stream: None,
             diff_format,
-            diff_panel: DetailsPanel::new(),
+            linked_diff: false,
+            diff_sections: vec![],
+            full_diff: None,
+            diff_target: None,
+            diff_panel: DetailsPanel::with_settings(
+                &commander.env.root,
+                Panel::Files,
+                commander.env.config.layout_percent(),
+            ),
+
+            renames: HashMap::new(),
+            renames_rx: None,
 
             config: commander.env.config.clone(),
-        })
+            keybinds: FilesTabKeybinds::default(),
+        };
+        files_tab.detect_renames(commander);
+        Ok(files_tab)
+    }
+
+    /// Detect the renames of the change in the background, as it compares the content of the
+    /// deleted and added files. The files are updated in [FilesTab::update] once known.
+    fn detect_renames(&mut self, commander: &Commander) {
+        let commit_id = &self.head.commit_id;
+        if self.renames.contains_key(commit_id)
+            || self
+                .renames_rx
+                .as_ref()
+                .is_some_and(|(pending, _)| pending == commit_id)
+        {
+            return;
+        }
+        let (tx, rx) = mpsc::channel();
+        let commander = Commander::new(&commander.env);
+        let head = self.head.clone();
+        thread::spawn(move || {
+            if let Ok(files) = commander.get_files(&head) {
+                let _ = tx.send(commander.detect_renames(&head, files));
+            }
+        });
+        self.renames_rx = Some((commit_id.clone(), rx));
+    }
+
+    /// Replace the files of the change with the ones with the detected renames, keeping the
+    /// untracked files and the selection
+    fn apply_renames(&mut self, commander: &mut Commander, mut files: Vec<File>) -> Result<()> {
+        let Ok(current_files) = self.files_output.as_mut() else {
+            return Ok(());
+        };
+        files.extend(current_files.drain(..).filter(is_untracked));
+        if self.sort_by_churn {
+            sort_by_churn(&mut files, &self.file_stats);
+        }
+        *current_files = files;
+
+        // The selected file may have become part of a rename
+        if let Some(file) = self.file.as_ref()
+            && !current_files.contains(file)
+        {
+            self.file = current_files
+                .iter()
+                .find(|renamed| {
+                    file.path
+                        .as_ref()
+                        .is_some_and(|path| renamed.paths().contains(path))
+                })
+                .or(current_files.first())
+                .cloned();
+            self.refresh_diff(commander)?;
+        }
+        Ok(())
     }
 
     pub fn set_head(&mut self, commander: &mut Commander, head: &Head) -> Result<()> {
+        if self.head.change_id != head.change_id {
+            self.marked_files.clear();
+        }
+        if self
+            .review_range
+            .as_ref()
+            .is_some_and(|range| range.position(head).is_none())
+        {
+            self.review_range = None;
+        }
         self.head = head.clone();
         self.is_current_head = self.head == commander.get_current_head()?;
 
@@ -121,32 +364,234 @@ impl FilesTab {
         Ok(())
     }
 
+    /// Select the file at `path` of a change and scroll its diff to `line` of the new version of
+    /// the file, or to the first line containing `text` if the line isn't found, e.g. in the
+    /// output of a diff tool. A streamed diff is scrolled once the line arrives.
+    pub fn view_file_line(
+        &mut self,
+        commander: &mut Commander,
+        head: &Head,
+        path: &str,
+        line: Option<usize>,
+        text: &str,
+    ) -> Result<()> {
+        self.set_head(commander, head)?;
+        let Some(file) = self.files_output.as_ref().ok().and_then(|files| {
+            files
+                .iter()
+                .find(|file| file.path.as_deref() == Some(path))
+                .cloned()
+        }) else {
+            return Ok(());
+        };
+        self.file = Some(file);
+        self.refresh_diff(commander)?;
+        self.diff_target = Some((line, text.to_owned()));
+        self.scroll_to_diff_target();
+        Ok(())
+    }
+
+    /// Scroll the diff to the [FilesTab::diff_target] once the diff shows it. Without the line,
+    /// the first line containing the text is shown once the diff is complete.
+    fn scroll_to_diff_target(&mut self) {
+        let Some((line, text)) = self.diff_target.as_ref() else {
+            return;
+        };
+        let Ok(Some(diff)) = self.diff_output.as_ref() else {
+            if !self.is_streaming() {
+                self.diff_target = None;
+            }
+            return;
+        };
+        // In the diff of all files, only the section of the file
+        let (start, end) = match self.file_section() {
+            Some(section) if self.linked_diff => {
+                let sections = diff_sections(diff);
+                (
+                    sections.get(section).map_or(0, |(start, _)| *start),
+                    sections.get(section + 1).map(|(end, _)| *end),
+                )
+            }
+            _ => (0, None),
+        };
+        let file_diff = diff
+            .lines()
+            .skip(start)
+            .take(end.map_or(usize::MAX, |end| end - start))
+            .join("\n");
+        let found = line.and_then(|line| diff_line_index(&file_diff, line));
+        let found = match found {
+            Some(found) => Some(found),
+            None if self.is_streaming() => return,
+            // Search the text without the colors of the diff
+            None => file_diff.into_text().ok().and_then(|file_diff| {
+                file_diff.lines.iter().position(|diff_line| {
+                    diff_line
+                        .spans
+                        .iter()
+                        .map(|span| span.content.as_ref())
+                        .collect::<String>()
+                        .contains(text.as_str())
+                })
+            }),
+        };
+        if let Some(found) = found {
+            self.diff_panel.scroll_to((start + found) as u16);
+        }
+        self.diff_target = None;
+    }
+
     pub fn get_current_file_index(&self) -> Option<usize> {
         get_current_file_index(self.file.as_ref(), self.files_output.as_ref())
     }
 
     pub fn refresh_files(&mut self, commander: &mut Commander) -> Result<()> {
-        self.files_output = commander.get_files(&self.head);
+        let previous_index = self.get_current_file_index();
+        self.files_output = get_files(
+            commander,
+            &self.head,
+            self.is_current_head,
+            self.renames.get(&self.head.commit_id),
+        );
+        self.detect_renames(commander);
+        self.file_stats = get_file_stats(commander, &self.head);
+        if self.sort_by_churn
+            && let Ok(files) = self.files_output.as_mut()
+        {
+            sort_by_churn(files, &self.file_stats);
+        }
         self.conflicts_output = commander.get_conflicts(&self.head.commit_id)?;
+
+        // Keep the selection in the list when the selected file is gone, e.g. after restoring it
+        if let Ok(files) = self.files_output.as_ref()
+            && let Some(index) = self
+                .get_current_file_index()
+                .or(previous_index.map(|index| index.min(files.len().saturating_sub(1))))
+        {
+            self.file = files.get(index).cloned();
+        }
+
+        // Forget marks of files which are no longer changed, e.g. after extracting them
+        let files = self.files_output.as_ref().ok();
+        self.marked_files.retain(|marked_file| {
+            files.is_some_and(|files| {
+                files
+                    .iter()
+                    .any(|file| file.path.as_ref() == Some(marked_file))
+            })
+        });
         Ok(())
     }
 
     pub fn refresh_diff(&mut self, commander: &mut Commander) -> Result<()> {
         let inner_width = self.diff_panel.columns() as usize;
         commander.limit_width(inner_width);
+        self.diff_panel.set_content_columns();
+        self.diff_panel.content_changed();
+        self.diff_panel.scroll_to(0);
+        self.diff_panel.set_anchor_lines(vec![]);
+        self.diff_sections.clear();
+        self.diff_target = None;
+
+        // A single `jj diff` of the whole change, streamed as it can be large, see
+        // [FilesTab::update_diff_sections]
+        if self.linked_diff {
+            self.diff_stream = None;
+            self.diff_output = Ok(None);
+            if let Some(lines) = self.held_back_diff_lines(commander) {
+                self.diff_output = Ok(Some(format!(
+                    "\x1b[1;33mThe diff changes {lines} lines, more than \
+                     blazingjj.large-diff-lines. Press Enter to render it\x1b[0m\n"
+                )));
+                return Ok(());
+            }
+            match commander.stream_change_diff(&self.head, &self.diff_format) {
+                Ok(stream) => self.diff_stream = Some(stream),
+                Err(err) => self.diff_output = Err(err),
+            }
+            return Ok(());
+        }
+
+        let has_diff_override = self
+            .file
+            .as_ref()
+            .is_some_and(|file| get_diff_override(&self.config, &self.diff_format, file).is_some());
+
+        // External diff tools can be slow, so their output is streamed into the panel
+        if matches!(self.diff_format, DiffFormat::DiffTool(_)) && !has_diff_override {
+            self.diff_stream = None;
+            self.diff_output = Ok(None);
+            if let Some(current_file) = self.file.as_ref().filter(|file| !is_untracked(file)) {
+                match commander.stream_file_diff(&self.head, current_file, &self.diff_format) {
+                    Ok(stream) => self.diff_stream = stream,
+                    Err(err) => self.diff_output = Err(err),
+                }
+            }
+            return Ok(());
+        }
+        self.diff_stream = None;
+
         self.diff_output = self
             .file
             .as_ref()
+            .filter(|current_file| !is_untracked(current_file))
             .map(|current_file| {
-                commander.get_file_diff(&self.head, current_file, &self.diff_format, true)
+                get_file_diff(commander, &self.head, current_file, &self.diff_format)
             })
-            .map_or(Ok(None), |r| {
-                r.map(|diff| diff.map(|diff| tabs_to_spaces(&diff)))
-            });
-        self.diff_panel.scroll_to(0);
+            .unwrap_or(Ok(None));
         Ok(())
     }
 
+    /// Number of lines changed by the change if its diff of all files is held back as it has
+    /// more than [Config::large_diff_lines]
+    fn held_back_diff_lines(&self, commander: &Commander) -> Option<usize> {
+        let limit = self.config.large_diff_lines()?;
+        if matches!(self.diff_format, DiffFormat::Stat | DiffFormat::Summary)
+            || self.full_diff.as_ref() == Some(&self.head.commit_id)
+        {
+            return None;
+        }
+        let lines = commander
+            .get_diff_line_count(&self.head.commit_id)
+            .inspect_err(|err| warn!("{err:?}"))
+            .ok()?;
+        (lines > limit).then_some(lines)
+    }
+
+    /// Find the files in the diff of all files received so far, scrolling to the selected file
+    /// once its section arrives
+    fn update_diff_sections(&mut self) {
+        let Ok(Some(diff)) = self.diff_output.as_ref() else {
+            return;
+        };
+        let sections = diff_sections(diff);
+        if sections.len() == self.diff_sections.len() {
+            return;
+        }
+        let had_file_section = self.file_section().is_some();
+        let (lines, paths) = sections.into_iter().unzip();
+        self.diff_sections = paths;
+        self.diff_panel.set_anchor_lines(lines);
+        if !had_file_section {
+            self.scroll_diff_to_file();
+        }
+    }
+
+    /// Keys greyed out in the help in read-only mode
+    fn read_only_keys(&self) -> Vec<String> {
+        if !self.config.read_only() {
+            return vec![];
+        }
+        self.keybinds.mutating_shortcuts()
+    }
+
+    /// Whether the diff tool or the diff of all files is still running for the diff panel
+    fn is_streaming(&self) -> bool {
+        self.diff_stream
+            .as_ref()
+            .is_some_and(|stream| stream.is_running())
+    }
+
     pub fn untrack_file(&mut self, commander: &mut Commander) -> Result<()> {
         self.file
             .as_ref()
@@ -163,6 +608,295 @@ impl FilesTab {
         Ok(())
     }
 
+    /// Start tracking the selected file if it is untracked or ignored
+    pub fn track_file(&mut self, commander: &mut Commander) -> Result<()> {
+        if let Some(file) = self.file.as_ref().filter(|file| is_untracked(file))
+            && let Some(path) = file.path.as_ref()
+        {
+            commander.track_file(path, file.diff_type == Some(DiffType::Ignored))?;
+        }
+        Ok(())
+    }
+
+    /// Add the selected file to the `.gitignore` if it is untracked
+    pub fn ignore_file(&mut self, commander: &mut Commander) -> Result<()> {
+        if let Some(path) = self
+            .file
+            .as_ref()
+            .filter(|file| file.diff_type == Some(DiffType::Untracked))
+            .and_then(|file| file.path.as_ref())
+        {
+            commander.ignore_files(&[path.as_str()])?;
+        }
+        Ok(())
+    }
+
+    /// Paths of the marked files, or of the selected file if none are marked
+    fn marked_or_selected_paths(&self) -> Vec<String> {
+        if self.marked_files.is_empty() {
+            self.file
+                .as_ref()
+                .and_then(|file| file.path.clone())
+                .into_iter()
+                .collect()
+        } else {
+            self.marked_files.iter().sorted().cloned().collect()
+        }
+    }
+
+    /// Move the changes of the marked or selected files into an adjacent change, staying on this
+    /// change and near the selected file to keep untangling it
+    fn move_files(
+        &mut self,
+        commander: &mut Commander,
+        target: FileMoveTarget,
+    ) -> Result<ComponentInputResult> {
+        let paths = self.marked_or_selected_paths();
+        if paths.is_empty() {
+            return Ok(ComponentInputResult::Handled);
+        }
+        let title = match target {
+            FileMoveTarget::Parent => "Move to parent",
+            FileMoveTarget::Child => "Move to child",
+            FileMoveTarget::NewChild => "Move to new change",
+        };
+        let error = if self.head.immutable {
+            Some("The change is immutable".to_owned())
+        } else {
+            // Moving into a new change creates it first, which is rolled back if the move fails
+            match commander.run_composite_action(title, |commander| {
+                commander.run_move_files(&self.head.commit_id, &paths, target)
+            }) {
+                Ok(()) => None,
+                Err(err) => match RollbackPopup::from_error(err) {
+                    Ok(popup) => {
+                        return Ok(ComponentInputResult::HandledAction(
+                            ComponentAction::Multiple(vec![
+                                ComponentAction::SetPopup(Some(popup)),
+                                ComponentAction::RefreshTab(),
+                            ]),
+                        ));
+                    }
+                    Err(err) => Some(format!("{err:?}")),
+                },
+            }
+        };
+        if let Some(error) = error {
+            return Ok(ComponentInputResult::HandledAction(
+                ComponentAction::SetPopup(Some(Box::new(MessagePopup {
+                    title: title.into(),
+                    messages: error.into_text()?,
+                    text_align: None,
+                    ..Default::default()
+                }))),
+            ));
+        }
+
+        let index = self.get_current_file_index().unwrap_or(0);
+        self.marked_files.clear();
+        let head = commander.get_head_latest(&self.head)?;
+        self.set_head(commander, &head)?;
+        if let Ok(files) = self.files_output.as_ref()
+            && let Some(file) = files.get(index).or(files.last())
+        {
+            self.file = Some(file.clone());
+            self.refresh_diff(commander)?;
+        }
+        Ok(ComponentInputResult::Handled)
+    }
+
+    fn file_stat(&self, file: &File) -> Option<&FileStat> {
+        self.file_stats.get(file.path.as_ref()?)
+    }
+
+    /// Widths of the `+N` and `-M` columns of the stats
+    fn stat_widths(&self, files: &[File]) -> (usize, usize) {
+        files.iter().filter_map(|file| self.file_stat(file)).fold(
+            (0, 0),
+            |(added, removed), stat| {
+                (
+                    added.max(stat.added.to_string().len() + 1),
+                    removed.max(stat.removed.to_string().len() + 1),
+                )
+            },
+        )
+    }
+
+    /// Line of a file in the list: its status letter in the color of the diff type, the aligned
+    /// stats if any file has them, then the path
+    fn file_line<'a>(
+        &self,
+        file: &File,
+        line: Line<'a>,
+        added_width: usize,
+        removed_width: usize,
+    ) -> Line<'a> {
+        let Some(diff_type) = file.diff_type.as_ref() else {
+            return line;
+        };
+        let text = line.to_string();
+        let (status, path) = text.split_once(' ').unwrap_or((&text, ""));
+
+        let mut spans = vec![Span::from(status.to_owned()).fg(diff_type.color()).bold()];
+        if added_width > 0 {
+            let (added, removed) = match self.file_stat(file) {
+                Some(stat) => (format!("+{}", stat.added), format!("-{}", stat.removed)),
+                None => (String::new(), String::new()),
+            };
+            spans.push(Span::from(format!(" {added:>added_width$}")).green());
+            spans.push(Span::from(format!(" {removed:>removed_width$}")).red());
+        }
+        spans.push(Span::from(format!(" {path}")));
+        Line::from(spans)
+    }
+
+    /// Whether the file is marked as viewed in review mode
+    fn is_viewed(&self, file: &File) -> bool {
+        match (self.review.as_ref(), file.path.as_ref()) {
+            (Some(review), Some(path)) => review.is_viewed(self.head.commit_id.as_str(), path),
+            _ => false,
+        }
+    }
+
+    /// Review the changes of a revset one by one, starting with the first change with files left
+    /// to view. The progress counts the files of all changes.
+    pub fn review_range(&mut self, commander: &mut Commander, revset: &str) -> Result<()> {
+        let changes = commander.get_range_files(revset)?;
+        let review = ReviewState::load(&commander.env.root);
+        let Some((head, _)) = changes
+            .iter()
+            .find(|(head, paths)| {
+                review.viewed_count(head.commit_id.as_str(), paths.iter().map(String::as_str))
+                    < paths.len()
+            })
+            .or(changes.first())
+        else {
+            bail!("No changes to review in {revset}");
+        };
+        let head = head.clone();
+        self.review = Some(review);
+        self.review_range = Some(ReviewRange {
+            revset: revset.to_owned(),
+            changes,
+        });
+        self.set_head(commander, &head)
+    }
+
+    /// Select the next or previous change of the reviewed range
+    fn scroll_review_range(&mut self, commander: &mut Commander, scroll: isize) -> Result<()> {
+        let Some(range) = self.review_range.as_ref() else {
+            return Ok(());
+        };
+        if let Some((head, _)) = range
+            .position(&self.head)
+            .and_then(|index| index.checked_add_signed(scroll))
+            .and_then(|index| range.changes.get(index))
+        {
+            let head = head.clone();
+            self.set_head(commander, &head)?;
+        }
+        Ok(())
+    }
+
+    /// Number of viewed files and all files, if in review mode. Counts the files of all changes
+    /// when reviewing a range.
+    fn review_progress(&self) -> Option<(usize, usize)> {
+        let review = self.review.as_ref()?;
+        if let Some(range) = self.review_range.as_ref() {
+            return Some(
+                range
+                    .changes
+                    .iter()
+                    .fold((0, 0), |(viewed, total), (head, paths)| {
+                        (
+                            viewed
+                                + review.viewed_count(
+                                    head.commit_id.as_str(),
+                                    paths.iter().map(String::as_str),
+                                ),
+                            total + paths.len(),
+                        )
+                    }),
+            );
+        }
+        let paths: Vec<&str> = self
+            .files_output
+            .as_ref()
+            .ok()?
+            .iter()
+            .filter(|file| !is_untracked(file))
+            .filter_map(|file| file.path.as_deref())
+            .collect();
+        Some((
+            review.viewed_count(self.head.commit_id.as_str(), paths.iter().copied()),
+            paths.len(),
+        ))
+    }
+
+    /// Toggle whether the selected file is viewed, entering review mode if needed. Moves on to
+    /// the next file after marking it as viewed, or to the next change of the reviewed range once
+    /// all files of the change are viewed.
+    fn toggle_viewed(&mut self, commander: &mut Commander) -> Result<()> {
+        let review = self
+            .review
+            .get_or_insert_with(|| ReviewState::load(&commander.env.root));
+        let Some(path) = self.file.as_ref().and_then(|file| file.path.as_ref()) else {
+            return Ok(());
+        };
+        review.toggle_viewed(self.head.commit_id.as_str(), path)?;
+
+        if self.file.as_ref().is_some_and(|file| self.is_viewed(file)) {
+            let all_viewed = self.files_output.as_ref().is_ok_and(|files| {
+                files
+                    .iter()
+                    .filter(|file| !is_untracked(file))
+                    .all(|file| self.is_viewed(file))
+            });
+            if all_viewed && self.review_range.is_some() {
+                self.scroll_review_range(commander, 1)?;
+            } else {
+                self.scroll_files(commander, 1)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Section of the selected file in the diff of all files
+    fn file_section(&self) -> Option<usize> {
+        let path = self.file.as_ref()?.diff_path()?;
+        self.diff_sections
+            .iter()
+            .position(|section| section == path)
+    }
+
+    /// Scroll the diff of all files to the section of the selected file
+    fn scroll_diff_to_file(&mut self) {
+        if let Some(section) = self.file_section() {
+            self.diff_panel.scroll_to_anchor(section);
+        }
+    }
+
+    /// Select the file of the section at the top of the diff of all files, after scrolling it
+    fn select_file_in_diff(&mut self) {
+        if !self.linked_diff {
+            return;
+        }
+        let Some(path) = self
+            .diff_panel
+            .current_anchor()
+            .and_then(|section| self.diff_sections.get(section))
+        else {
+            return;
+        };
+        if let Some(file) = self.files_output.as_ref().ok().and_then(|files| {
+            files
+                .iter()
+                .find(|file| file.diff_path() == Some(path.as_str()))
+        }) {
+            self.file = Some(file.clone());
+        }
+    }
+
     fn scroll_files(&mut self, commander: &mut Commander, scroll: isize) -> Result<()> {
         if let Ok(files) = self.files_output.as_ref() {
             let current_file_index = self.get_current_file_index();
@@ -177,7 +911,11 @@ impl FilesTab {
             .map(|x| x.to_owned());
             if let Some(next_file) = next_file {
                 self.file = Some(next_file.to_owned());
-                self.refresh_diff(commander)?;
+                if self.linked_diff {
+                    self.scroll_diff_to_file();
+                } else {
+                    self.refresh_diff(commander)?;
+                }
             }
         }
         Ok(())
@@ -201,8 +939,8 @@ impl Component for FilesTab {
         let chunks = Layout::default()
             .direction(self.config.layout().into())
             .constraints([
-                Constraint::Percentage(self.config.layout_percent()),
-                Constraint::Percentage(100 - self.config.layout_percent()),
+                Constraint::Percentage(self.diff_panel.layout_percent()),
+                Constraint::Percentage(100 - self.diff_panel.layout_percent()),
             ])
             .split(area);
 
@@ -212,6 +950,7 @@ impl Component for FilesTab {
 
             let mut lines: Vec<Line> = match self.files_output.as_ref() {
                 Ok(files_output) => {
+                    let (added_width, removed_width) = self.stat_widths(files_output);
                     let files_lines = files_output
                         .iter()
                         .enumerate()
@@ -221,16 +960,45 @@ impl Component for FilesTab {
                                 .unwrap()
                                 .iter()
                                 .map(|line| {
-                                    let mut line = line.to_owned();
+                                    let mut line = self.file_line(
+                                        file,
+                                        line.to_owned(),
+                                        added_width,
+                                        removed_width,
+                                    );
 
-                                    // Add padding at start
-                                    line.spans.insert(0, Span::from(" "));
+                                    // Add padding at start, or a mark for files to extract
+                                    let marked = file
+                                        .path
+                                        .as_ref()
+                                        .is_some_and(|path| self.marked_files.contains(path));
+                                    line.spans.insert(
+                                        0,
+                                        if marked {
+                                            Span::from("*").bold()
+                                        } else {
+                                            Span::from(" ")
+                                        },
+                                    );
 
-                                    if let Some(diff_type) = file.diff_type.as_ref() {
+                                    // Check viewed files in review mode and collapse them
+                                    let viewed = self.is_viewed(file);
+                                    if self.review.is_some() {
+                                        line.spans.insert(
+                                            1,
+                                            if viewed {
+                                                Span::from("✓ ").green()
+                                            } else {
+                                                Span::from("  ")
+                                            },
+                                        );
+                                    }
+
+                                    if viewed {
                                         line.spans = line
                                             .spans
                                             .iter_mut()
-                                            .map(|span| span.to_owned().fg(diff_type.color()))
+                                            .map(|span| span.to_owned().dim())
                                             .collect();
                                     }
 
@@ -270,6 +1038,20 @@ impl Component for FilesTab {
             } else {
                 self.head.change_id.as_string()
             };
+            let mut title_review = match self.review_progress() {
+                Some((viewed, total)) => format!("| reviewed {viewed}/{total} "),
+                None => String::new(),
+            };
+            if let Some(range) = self.review_range.as_ref()
+                && let Some(index) = range.position(&self.head)
+            {
+                title_review.push_str(&format!(
+                    "| change {}/{} of {} ",
+                    index + 1,
+                    range.changes.len(),
+                    range.revset
+                ));
+            }
 
             if !self.conflicts_output.is_empty() {
                 lines.push(Line::default());
@@ -279,11 +1061,14 @@ impl Component for FilesTab {
                 }
             }
 
+            let focused = !self.diff_panel.is_focused();
             let files = List::new(lines)
+                .style(panel_content_style(focused))
                 .block(
                     Block::bordered()
-                        .title(" Files for ".to_owned() + &title_change + " ")
-                        .border_type(BorderType::Rounded),
+                        .title(" Files for ".to_owned() + &title_change + " " + &title_review)
+                        .border_type(BorderType::Rounded)
+                        .border_style(panel_border_style(focused, self.config.focus_color())),
                 )
                 .scroll_padding(3);
             *self.files_list_state.selected_mut() = current_file_index;
@@ -312,13 +1097,47 @@ impl Component for FilesTab {
         // Draw diff
         {
             let diff_content = match self.diff_output.as_ref() {
+                // Collapse the diff of viewed files
+                Ok(Some(_)) if self.file.as_ref().is_some_and(|file| self.is_viewed(file)) => {
+                    Text::from(" Viewed, press v to mark as not viewed")
+                        .fg(Color::DarkGray)
+                        .italic()
+                }
                 Ok(Some(diff_content)) => diff_content.into_text()?,
+                Ok(None)
+                    if self
+                        .file
+                        .as_ref()
+                        .is_some_and(|file| file.diff_type == Some(DiffType::Ignored)) =>
+                {
+                    Text::from(" Ignored file, press t to track it anyway")
+                        .fg(Color::DarkGray)
+                        .italic()
+                }
+                Ok(None) if self.file.as_ref().is_some_and(is_untracked) => {
+                    Text::from(" Untracked file, press t to track it or i to add it to .gitignore")
+                        .fg(Color::DarkGray)
+                        .italic()
+                }
                 Ok(None) => Text::default(),
                 Err(err) => err.into_text("Error getting diff")?,
             };
+            let title = match self.diff_stream.as_ref() {
+                Some(stream) if stream.is_running() && self.linked_diff => format!(
+                    " Diff of all files {} Esc to cancel ",
+                    spinner(stream.elapsed())
+                ),
+                Some(stream) if stream.is_running() => format!(
+                    " Diff {} diff tool running, Esc to cancel ",
+                    spinner(stream.elapsed())
+                ),
+                _ if self.linked_diff => " Diff of all files ".to_owned(),
+                _ => " Diff ".to_owned(),
+            };
             self.diff_panel
                 .render_context()
-                .title(" Diff ")
+                .title(title)
+                .focus_color(self.config.focus_color())
                 .content(diff_content)
                 .draw(f, chunks[1]);
         }
@@ -326,6 +1145,44 @@ impl Component for FilesTab {
         Ok(())
     }
 
+    fn update(&mut self, commander: &mut Commander) -> Result<Option<ComponentAction>> {
+        // The output of diff tools depends on the width, produce it again once resizing settled
+        if self.diff_format.depends_on_width() && self.diff_panel.is_resized() {
+            self.refresh_diff(commander)?;
+        }
+        if let Some(stream) = self.diff_stream.as_mut()
+            && stream.poll()
+        {
+            self.diff_output = stream.result().map(|diff| Some(tabs_to_spaces(diff)));
+            self.diff_panel.content_changed();
+            if self.linked_diff {
+                self.update_diff_sections();
+            }
+            self.scroll_to_diff_target();
+        }
+        if let Some((commit_id, rx)) = self.renames_rx.as_ref() {
+            match rx.try_recv() {
+                Ok(files) => {
+                    let commit_id = commit_id.clone();
+                    self.renames_rx = None;
+                    self.renames.insert(commit_id.clone(), files.clone());
+                    if commit_id == self.head.commit_id {
+                        self.apply_renames(commander, files)?;
+                    }
+                }
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => self.renames_rx = None,
+            }
+        }
+        Ok(None)
+    }
+
+    fn is_busy(&self) -> bool {
+        self.is_streaming()
+            || self.renames_rx.is_some()
+            || (self.diff_format.depends_on_width() && self.diff_panel.is_resizing())
+    }
+
     fn input(&mut self, commander: &mut Commander, event: Event) -> Result<ComponentInputResult> {
         if let Event::Key(key) = event {
             if key.kind != KeyEventKind::Press {
@@ -333,26 +1190,85 @@ impl Component for FilesTab {
             }
 
             if self.diff_panel.input(key) {
+                self.select_file_in_diff();
                 return Ok(ComponentInputResult::Handled);
             }
 
-            match key.code {
-                KeyCode::Char('j') | KeyCode::Down => self.scroll_files(commander, 1)?,
-                KeyCode::Char('k') | KeyCode::Up => self.scroll_files(commander, -1)?,
-                KeyCode::Char('J') => {
+            if key.code == KeyCode::Esc
+                && let Some(stream) = self
+                    .diff_stream
+                    .as_mut()
+                    .filter(|stream| stream.is_running())
+            {
+                stream.cancel();
+                self.diff_output = stream.result().map(|diff| Some(tabs_to_spaces(diff)));
+                self.diff_panel.content_changed();
+                return Ok(ComponentInputResult::Handled);
+            }
+
+            if has_command_modifier(&key) {
+                return Ok(ComponentInputResult::NotHandled);
+            }
+
+            let files_tab_event = self.keybinds.match_event(key);
+            if self.config.read_only() && files_tab_event.is_mutating() {
+                return Ok(ComponentInputResult::HandledAction(read_only_popup()));
+            }
+
+            match files_tab_event {
+                FilesTabEvent::ScrollDown => self.scroll_files(commander, 1)?,
+                FilesTabEvent::ScrollUp => self.scroll_files(commander, -1)?,
+                FilesTabEvent::ScrollDownHalf => {
                     self.scroll_files(commander, self.files_height as isize / 2)?;
                 }
-                KeyCode::Char('K') => {
+                FilesTabEvent::ScrollUpHalf => {
                     self.scroll_files(
                         commander,
                         (self.files_height as isize / 2).saturating_neg(),
                     )?;
                 }
-                KeyCode::Char('w') => {
+                FilesTabEvent::ToggleDiffFormat => {
                     self.diff_format = self.diff_format.get_next(self.config.diff_tool());
+                    let diff_format = self.diff_format.clone();
+                    panel_settings::save(&commander.env.root, Panel::Files, |settings| {
+                        settings.diff_format = Some(diff_format)
+                    });
                     self.refresh_diff(commander)?;
                 }
-                KeyCode::Char('x') => {
+                FilesTabEvent::OpenInBrowser => {
+                    if let Some(path) = self.file.as_ref().and_then(|file| file.path.as_ref())
+                        && let Err(err) = commander
+                            .get_forge_file_url(&self.head.commit_id, path)
+                            .and_then(|url| commander.open_in_browser(&url))
+                    {
+                        return Ok(ComponentInputResult::HandledAction(
+                            ComponentAction::SetPopup(Some(Box::new(MessagePopup {
+                                title: "Open in browser".into(),
+                                messages: commander.error_text(&err, "")?,
+                                text_align: None,
+                                ..Default::default()
+                            }))),
+                        ));
+                    }
+                }
+                FilesTabEvent::ToggleSortByChurn => {
+                    self.sort_by_churn = !self.sort_by_churn;
+                    self.refresh_files(commander)?;
+                    if self.linked_diff {
+                        self.refresh_diff(commander)?;
+                    }
+                }
+                FilesTabEvent::ToggleLinkedDiff => {
+                    self.linked_diff = !self.linked_diff;
+                    self.refresh_diff(commander)?;
+                }
+                FilesTabEvent::ShowFullDiff
+                    if self.linked_diff && self.held_back_diff_lines(commander).is_some() =>
+                {
+                    self.full_diff = Some(self.head.commit_id.clone());
+                    self.refresh_diff(commander)?;
+                }
+                FilesTabEvent::Untrack => {
                     // this works even for deleted files because jj doesn't return error in that case
                     if self.untrack_file(commander).is_err() {
                         return Ok(ComponentInputResult::HandledAction(
@@ -360,56 +1276,195 @@ impl Component for FilesTab {
                                 title: "Can't untrack file".into(),
                                 messages: "Make sure that file is ignored".into(),
                                 text_align: None,
+                                ..Default::default()
+                            }))),
+                        ));
+                    }
+                    self.set_head(commander, &commander.get_current_head()?)?;
+                }
+                FilesTabEvent::Track => {
+                    if let Err(err) = self.track_file(commander) {
+                        return Ok(ComponentInputResult::HandledAction(
+                            ComponentAction::SetPopup(Some(Box::new(MessagePopup {
+                                title: "Can't track file".into(),
+                                messages: err.to_string().into(),
+                                text_align: None,
+                                ..Default::default()
+                            }))),
+                        ));
+                    }
+                    // Keep the file selected, now as an added file
+                    self.refresh_files(commander)?;
+                    if let Some(index) = self.get_current_file_index() {
+                        self.file = self
+                            .files_output
+                            .as_ref()
+                            .ok()
+                            .and_then(|files| files.get(index))
+                            .cloned();
+                    }
+                    self.refresh_diff(commander)?;
+                }
+                FilesTabEvent::Ignore => {
+                    if let Err(err) = self.ignore_file(commander) {
+                        return Ok(ComponentInputResult::HandledAction(
+                            ComponentAction::SetPopup(Some(Box::new(MessagePopup {
+                                title: "Can't ignore file".into(),
+                                messages: err.to_string().into(),
+                                text_align: None,
+                                ..Default::default()
                             }))),
                         ));
                     }
                     self.set_head(commander, &commander.get_current_head()?)?;
                 }
-                KeyCode::Char('r') => {
+                FilesTabEvent::Restore => {
                     if let Err(err) = self.restore_file(commander) {
                         return Ok(ComponentInputResult::HandledAction(
                             ComponentAction::SetPopup(Some(Box::new(MessagePopup {
                                 title: "Can't restore file".into(),
                                 messages: err.to_string().into(),
                                 text_align: None,
+                                ..Default::default()
                             }))),
                         ));
                     }
                     self.set_head(commander, &commander.get_current_head()?)?;
                 }
-                KeyCode::Char('R') | KeyCode::F(5) => {
+                FilesTabEvent::Refresh => {
                     self.head = commander.get_head_latest(&self.head)?;
                     self.refresh_files(commander)?;
                     self.refresh_diff(commander)?;
                 }
-                KeyCode::Char('@') => {
+                FilesTabEvent::FocusCurrent => {
                     let head = &commander.get_current_head()?;
                     self.set_head(commander, head)?;
                 }
-                KeyCode::Char('?') => {
+                FilesTabEvent::ToggleMark => {
+                    if let Some(path) = self.file.as_ref().and_then(|file| file.path.clone())
+                        && !self.marked_files.remove(&path)
+                    {
+                        self.marked_files.insert(path);
+                    }
+                    self.scroll_files(commander, 1)?;
+                }
+                FilesTabEvent::ToggleViewed => {
+                    if let Err(err) = self.toggle_viewed(commander) {
+                        return Ok(ComponentInputResult::HandledAction(
+                            ComponentAction::SetPopup(Some(Box::new(MessagePopup {
+                                title: "Review error".into(),
+                                messages: err.to_string().into(),
+                                text_align: None,
+                                ..Default::default()
+                            }))),
+                        ));
+                    }
+                }
+                FilesTabEvent::Resolve => {
+                    let conflicted_path = self
+                        .file
+                        .as_ref()
+                        .and_then(|file| file.path.clone())
+                        .filter(|path| {
+                            self.conflicts_output
+                                .iter()
+                                .any(|conflict| conflict.path == *path)
+                        });
+                    let popup: Box<dyn Component> = match conflicted_path {
+                        Some(path) => {
+                            Box::new(ResolvePopup::new(commander, self.head.clone(), path))
+                        }
+                        None => Box::new(MessagePopup {
+                            title: "Resolve".into(),
+                            messages: "The selected file has no conflicts".into(),
+                            text_align: None,
+                            ..Default::default()
+                        }),
+                    };
                     return Ok(ComponentInputResult::HandledAction(
-                        ComponentAction::SetPopup(Some(Box::new(HelpPopup::new(
-                            vec![
-                                ("j/k".to_owned(), "scroll down/up".to_owned()),
-                                ("J/K".to_owned(), "scroll down by ½ page".to_owned()),
-                                ("x".to_owned(), "untrack file".to_owned()),
-                                ("r".to_owned(), "restore file".to_owned()),
-                                ("@".to_owned(), "view current change files".to_owned()),
-                            ],
-                            vec![
-                                ("Ctrl+e/Ctrl+y".to_owned(), "scroll down/up".to_owned()),
-                                (
-                                    "Ctrl+d/Ctrl+u".to_owned(),
-                                    "scroll down/up by ½ page".to_owned(),
-                                ),
-                                (
-                                    "Ctrl+f/Ctrl+b".to_owned(),
-                                    "scroll down/up by page".to_owned(),
-                                ),
-                                ("w".to_owned(), "toggle diff format".to_owned()),
-                                ("W".to_owned(), "toggle wrapping".to_owned()),
-                            ],
-                        )))),
+                        ComponentAction::SetPopup(Some(popup)),
+                    ));
+                }
+                FilesTabEvent::ToggleReview => {
+                    self.review = match self.review {
+                        Some(_) => None,
+                        None => Some(ReviewState::load(&commander.env.root)),
+                    };
+                    self.review_range = None;
+                }
+                FilesTabEvent::NextReviewChange => self.scroll_review_range(commander, 1)?,
+                FilesTabEvent::PreviousReviewChange => {
+                    self.scroll_review_range(commander, -1)?;
+                }
+                FilesTabEvent::Extract => {
+                    if self.head.immutable {
+                        return Ok(ComponentInputResult::HandledAction(
+                            ComponentAction::SetPopup(Some(Box::new(MessagePopup {
+                                title: "Extract".into(),
+                                messages: "The change is immutable".into(),
+                                text_align: None,
+                                ..Default::default()
+                            }))),
+                        ));
+                    }
+                    let paths = self.marked_or_selected_paths();
+                    if !paths.is_empty() {
+                        return Ok(ComponentInputResult::HandledAction(
+                            ComponentAction::SetPopup(Some(Box::new(ExtractPopup::new(
+                                commander,
+                                self.head.commit_id.clone(),
+                                paths,
+                            )?))),
+                        ));
+                    }
+                }
+                FilesTabEvent::MoveToParent => {
+                    return self.move_files(commander, FileMoveTarget::Parent);
+                }
+                FilesTabEvent::MoveToChild => {
+                    return self.move_files(commander, FileMoveTarget::Child);
+                }
+                FilesTabEvent::MoveToNewChild => {
+                    return self.move_files(commander, FileMoveTarget::NewChild);
+                }
+                FilesTabEvent::OpenInPager => {
+                    if let Ok(Some(diff)) = self.diff_output.as_ref() {
+                        return Ok(ComponentInputResult::HandledAction(
+                            ComponentAction::OpenInPager(diff.clone()),
+                        ));
+                    }
+                }
+                FilesTabEvent::ViewFileLog => {
+                    if let Some(path) = self.file.as_ref().and_then(|file| file.path.clone()) {
+                        return Ok(ComponentInputResult::HandledAction(
+                            ComponentAction::ViewLogForPath(path),
+                        ));
+                    }
+                }
+                FilesTabEvent::ViewDirectoryLog => {
+                    if let Some(directory) = self
+                        .file
+                        .as_ref()
+                        .and_then(|file| file.path.as_deref())
+                        .and_then(|path| Path::new(path).parent())
+                        .filter(|directory| !directory.as_os_str().is_empty())
+                    {
+                        return Ok(ComponentInputResult::HandledAction(
+                            ComponentAction::ViewLogForPath(
+                                directory.to_string_lossy().into_owned(),
+                            ),
+                        ));
+                    }
+                }
+                FilesTabEvent::OpenHelp => {
+                    return Ok(ComponentInputResult::HandledAction(
+                        ComponentAction::SetPopup(Some(Box::new(
+                            HelpPopup::new(
+                                self.keybinds.make_main_panel_help(),
+                                DetailsPanel::help(),
+                            )
+                            .read_only(&self.read_only_keys()),
+                        ))),
                     ));
                 }
                 _ => return Ok(ComponentInputResult::NotHandled),
@@ -418,6 +1473,7 @@ impl Component for FilesTab {
 
         if let Event::Mouse(mouse) = event {
             if self.diff_panel.input_mouse(mouse) {
+                self.select_file_in_diff();
                 return Ok(ComponentInputResult::Handled);
             }
             return Ok(ComponentInputResult::NotHandled);
@@ -425,4 +1481,14 @@ impl Component for FilesTab {
 
         Ok(ComponentInputResult::Handled)
     }
+
+    fn hints(&self) -> Vec<(String, String)> {
+        if self.review.is_some() {
+            return self.keybinds.make_review_hints(self.review_range.is_some());
+        }
+        if self.config.read_only() {
+            return self.keybinds.make_read_only_hints();
+        }
+        self.keybinds.make_hints()
+    }
 }
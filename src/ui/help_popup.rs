@@ -1,7 +1,7 @@
 use ratatui::{
     crossterm::event::{self, Event, KeyCode},
     layout::{Constraint, Direction, Layout},
-    style::Stylize,
+    style::{Style, Stylize},
     text::Span,
     widgets::{Block, Clear, Row, Table},
 };
@@ -11,6 +11,9 @@ use crate::{
     ui::{Component, styles::create_popup_block, utils::centered_rect},
 };
 
+/// Appended to the description of the actions which are disabled in read-only mode
+const READ_ONLY_SUFFIX: &str = " (disabled in read-only mode)";
+
 pub struct HelpPopup {
     pub left_items: Vec<(String, String)>,
     pub right_items: Vec<(String, String)>,
@@ -29,13 +32,34 @@ impl HelpPopup {
         }
     }
 
+    /// Grey out the actions of the `keys`, which change the repository and are disabled in
+    /// read-only mode
+    pub fn read_only(mut self, keys: &[String]) -> Self {
+        for (shortcuts, description) in self.left_items.iter_mut().chain(&mut self.right_items) {
+            if shortcuts
+                .split('/')
+                .any(|shortcut| keys.iter().any(|key| key == shortcut))
+            {
+                description.push_str(READ_ONLY_SUFFIX);
+            }
+        }
+        self
+    }
+
     fn create_table(&self, items: &[(String, String)], title: String) -> Table<'_> {
         let items: Vec<&(String, String)> = items.iter().skip(self.scroll).collect();
 
         let max_first_row_width = items.iter().map(|row| row.0.len()).max().unwrap_or(0);
         let rows: Vec<Row> = items
             .iter()
-            .map(|row| Row::new([row.0.clone(), row.1.clone()]))
+            .map(|row| {
+                let style = if row.1.ends_with(READ_ONLY_SUFFIX) {
+                    Style::default().dark_gray()
+                } else {
+                    Style::default()
+                };
+                Row::new([row.0.clone(), row.1.clone()]).style(style)
+            })
             .collect();
         let widths = [
             Constraint::Length(max_first_row_width as u16 + 2),
@@ -103,4 +127,11 @@ impl Component for HelpPopup {
 
         Ok(ComponentInputResult::NotHandled)
     }
+
+    fn hints(&self) -> Vec<(String, String)> {
+        vec![
+            ("j/k".to_owned(), "scroll".to_owned()),
+            ("Esc".to_owned(), "close".to_owned()),
+        ]
+    }
 }
@@ -0,0 +1,64 @@
+/*!
+Jumplist of previously selected changes in the log tab, navigated like the
+jumplist of vim.
+
+Only jumps are recorded, e.g. selecting `@`, clicking a change or following a
+change from another tab, not moving the selection line by line. Entries are
+changes rather than commits, so that they survive rewrites of the change: the
+latest commit of the change is looked up when jumping back to it.
+*/
+use crate::commander::log::Head;
+
+/// Maximum number of entries, older ones are dropped
+const MAX_ENTRIES: usize = 100;
+
+#[derive(Debug, Default)]
+pub struct JumpList {
+    entries: Vec<Head>,
+    /// Position in `entries`. Equal to the length when not navigating the list
+    index: usize,
+}
+
+impl JumpList {
+    /// Record that the selection jumped away from `from`. Drops the entries after the current
+    /// position, like a new jump in vim.
+    pub fn push(&mut self, from: Head) {
+        self.entries.truncate(self.index);
+        self.entries
+            .retain(|entry| entry.change_id != from.change_id);
+        self.entries.push(from);
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.remove(0);
+        }
+        self.index = self.entries.len();
+    }
+
+    /// Move back from `current`, returning the change to select
+    pub fn back(&mut self, current: &Head) -> Option<Head> {
+        if self.index == self.entries.len() {
+            // Remember where navigation started, so that jumping forward returns there
+            if self
+                .entries
+                .last()
+                .is_none_or(|last| last.change_id != current.change_id)
+            {
+                self.entries.push(current.clone());
+            }
+            self.index = self.entries.len() - 1;
+        }
+        if self.index == 0 {
+            return None;
+        }
+        self.index -= 1;
+        self.entries.get(self.index).cloned()
+    }
+
+    /// Move forward, returning the change to select
+    pub fn forward(&mut self) -> Option<Head> {
+        if self.index + 1 >= self.entries.len() {
+            return None;
+        }
+        self.index += 1;
+        self.entries.get(self.index).cloned()
+    }
+}
@@ -1,34 +1,66 @@
 //! The loader popup presents a cute little animation and an operation name and should be used for
-//! operations known to possibly take some time.
+//! operations known to possibly take some time. If a command of the operation runs longer than the
+//! configured timeout, it is shown with likely causes and can be killed. Operations like running
+//! tests can be made cancellable, which kills all of their commands. Progress sent by the operation,
+//! like the output of `jj git clone`, is shown below the name, as a gauge if it has a percentage.
+//! A failed [composite action][Commander::run_composite_action] offers to roll it back.
 
 use ansi_to_tui::IntoText;
 use anyhow::Result;
 use ratatui::{
     Frame,
-    crossterm::event::Event,
+    crossterm::event::{Event, KeyCode, KeyEventKind},
     layout::Rect,
-    style::{Color, Style},
-    widgets::{Block, BorderType, Clear},
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{Block, BorderType, Clear, Gauge, Paragraph, Wrap},
+};
+use regex::Regex;
+use std::sync::{
+    LazyLock,
+    mpsc::{self, Receiver, Sender},
 };
-use std::sync::mpsc::{self, Receiver, Sender};
 use std::thread;
 use std::time::{Duration, Instant};
 use throbber_widgets_tui::{Throbber, ThrobberState};
 
 use crate::{
     ComponentInputResult,
-    commander::{CommandError, Commander},
-    ui::{Component, ComponentAction, message_popup::MessagePopup, utils::centered_rect_fixed},
+    commander::{
+        CommandError, Commander,
+        output_processors::process_operation_output,
+        running::{self, HungCommand, hung_command},
+    },
+    ui::{
+        Component, ComponentAction, credentials_popup::CredentialsPopup,
+        message_popup::MessagePopup, remote_error_popup::RemoteErrorPopup,
+        rollback_popup::RollbackPopup, utils::centered_rect_fixed,
+    },
 };
 
 type OperationResult = Result<String, CommandError>;
 
+/// Percentage in a progress message, e.g. "Receiving objects:  45% (123/456)"
+static PERCENT_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(\d{1,3})%").unwrap());
+
 /// A transient popup to be shown during possibly time consuming actions
 pub struct LoaderPopup {
     operation_name: String,
-    result_rx: Receiver<OperationResult>,
+    result_rx: Receiver<Result<String>>,
     throbber_state: ThrobberState,
     last_animation_update: Instant,
+    /// jj arguments to rerun the operation in the terminal if it needs credentials
+    terminal_retry: Option<Vec<String>>,
+    /// Progress messages sent by the operation
+    progress_rx: Option<Receiver<String>>,
+    /// Latest progress message, shown below the operation name
+    progress: Option<String>,
+    /// Command running longer than the configured timeout
+    hung: Option<HungCommand>,
+    /// Id of the operation in the [running] registry
+    operation: u64,
+    /// Whether the operation can be cancelled with Esc
+    cancellable: bool,
 }
 
 impl LoaderPopup {
@@ -39,11 +71,24 @@ impl LoaderPopup {
     where
         F: FnOnce() -> OperationResult + Send + 'static,
     {
-        let (tx, rx): (Sender<OperationResult>, Receiver<OperationResult>) = mpsc::channel();
+        Self::new_action(operation_name, move || {
+            operation().map_err(anyhow::Error::from)
+        })
+    }
+
+    /// Create a new loader popup for an operation running a
+    /// [composite action][Commander::run_composite_action], offering to roll it back if a step
+    /// fails after others changed the repository
+    pub fn new_action<F>(operation_name: String, operation: F) -> Self
+    where
+        F: FnOnce() -> Result<String> + Send + 'static,
+    {
+        let (tx, rx): (Sender<Result<String>>, Receiver<Result<String>>) = mpsc::channel();
+        let operation_id = running::new_operation();
 
         // Spawn thread to run the operation
         thread::spawn(move || {
-            let result = operation();
+            let result = running::in_operation(operation_id, operation);
             tx.send(result)
         });
 
@@ -52,8 +97,33 @@ impl LoaderPopup {
             result_rx: rx,
             throbber_state: ThrobberState::default(),
             last_animation_update: Instant::now(),
+            terminal_retry: None,
+            progress_rx: None,
+            progress: None,
+            hung: None,
+            operation: operation_id,
+            cancellable: false,
         }
     }
+
+    /// Allow cancelling the operation with Esc, killing its running commands
+    pub fn cancellable(mut self) -> Self {
+        self.cancellable = true;
+        self
+    }
+
+    /// Offer to rerun the operation with these jj arguments in the terminal if it fails because
+    /// git or ssh wanted to prompt for credentials
+    pub fn with_terminal_retry(mut self, args: Vec<String>) -> Self {
+        self.terminal_retry = Some(args);
+        self
+    }
+
+    /// Show the latest message received from `progress_rx` below the operation name
+    pub fn with_progress(mut self, progress_rx: Receiver<String>) -> Self {
+        self.progress_rx = Some(progress_rx);
+        self
+    }
 }
 
 impl Component for LoaderPopup {
@@ -61,22 +131,62 @@ impl Component for LoaderPopup {
     ///
     /// This updates the animation and also polls the running operation to see if the popup may be
     /// closed. In case of an error, that will be displayed in a new popup.
-    fn update(&mut self, _commander: &mut Commander) -> Result<Option<ComponentAction>> {
+    fn update(&mut self, commander: &mut Commander) -> Result<Option<ComponentAction>> {
         if self.last_animation_update.elapsed() >= Duration::from_millis(100) {
             self.throbber_state.calc_next();
             self.last_animation_update = Instant::now();
         }
 
+        if let Some(progress_rx) = &self.progress_rx
+            && let Some(progress) = progress_rx.try_iter().last()
+        {
+            self.progress = Some(progress);
+        }
+
+        self.hung = commander
+            .env
+            .config
+            .command_timeout()
+            .and_then(|timeout| hung_command(self.operation, timeout));
+
         let Ok(result) = self.result_rx.try_recv() else {
             return Ok(None);
         };
 
+        let result = match result.map_err(RollbackPopup::from_error) {
+            Ok(output) => Ok(output),
+            Err(Ok(popup)) => {
+                return Ok(Some(ComponentAction::Multiple(vec![
+                    ComponentAction::SetPopup(Some(popup)),
+                    ComponentAction::RefreshTab(),
+                ])));
+            }
+            Err(Err(err)) => match err.downcast::<CommandError>() {
+                Ok(err) => Err(err),
+                Err(err) => {
+                    return Ok(Some(ComponentAction::Multiple(vec![
+                        ComponentAction::SetPopup(Some(Box::new(MessagePopup {
+                            title: format!("{} error", self.operation_name).into(),
+                            messages: format!("{err:?}").into(),
+                            text_align: None,
+                            ..Default::default()
+                        }))),
+                        ComponentAction::RefreshTab(),
+                    ])));
+                }
+            },
+        };
+
+        let processors = commander.env.config.output_processors();
+        let result =
+            result.map(|output| process_operation_output(processors, self.operation, &output));
         let action = match result {
-            Ok(output) if !output.is_empty() => ComponentAction::Multiple(vec![
+            Ok(output) if !output.trim().is_empty() => ComponentAction::Multiple(vec![
                 ComponentAction::SetPopup(Some(Box::new(MessagePopup {
                     title: format!("{} message", self.operation_name).into(),
                     messages: output.into_text()?,
                     text_align: None,
+                    ..Default::default()
                 }))),
                 ComponentAction::RefreshTab(),
             ]),
@@ -84,11 +194,27 @@ impl Component for LoaderPopup {
                 ComponentAction::SetPopup(None),
                 ComponentAction::RefreshTab(),
             ]),
-            Err(err) => ComponentAction::SetPopup(Some(Box::new(MessagePopup {
-                title: format!("{} error", self.operation_name).into(),
-                messages: err.into_text("")?,
-                text_align: None,
-            }))),
+            Err(err) => match (self.terminal_retry.take(), err.remote_error()) {
+                (Some(args), _) if err.needs_credentials() => ComponentAction::SetPopup(Some(
+                    Box::new(CredentialsPopup::new(&self.operation_name, &err, args)?),
+                )),
+                (args, Some(kind)) => ComponentAction::SetPopup(Some(Box::new(
+                    RemoteErrorPopup::new(commander, &self.operation_name, kind, &err, args)?,
+                ))),
+                _ => ComponentAction::SetPopup(Some(Box::new(MessagePopup {
+                    title: format!("{} error", self.operation_name).into(),
+                    messages: match err {
+                        CommandError::Status(message, code) => CommandError::Status(
+                            process_operation_output(processors, self.operation, &message),
+                            code,
+                        ),
+                        err => err,
+                    }
+                    .into_text("")?,
+                    text_align: None,
+                    ..Default::default()
+                }))),
+            },
         };
 
         Ok(Some(action))
@@ -101,8 +227,37 @@ impl Component for LoaderPopup {
             .border_style(Style::default().fg(Color::Green));
 
         let label = format!("{}...", self.operation_name);
-        let content_width = 2 + label.len() as u16;
-        let content_height = 1;
+        let progress = self.progress.as_deref().map(Line::raw);
+        let content_width = progress
+            .as_ref()
+            .map_or(0, |progress| progress.width() as u16)
+            .max(2 + label.len() as u16);
+        let mut content_height = if progress.is_some() { 2 } else { 1 };
+
+        let hung = self.hung.as_ref().map(|hung| {
+            Paragraph::new(vec![
+                Line::raw(""),
+                Line::raw(format!(
+                    "`{}` has been running for {}s",
+                    hung.command_line,
+                    hung.elapsed.as_secs()
+                ))
+                .fg(Color::Yellow),
+                Line::raw(hung.hint()),
+                Line::raw(""),
+                Line::raw("Press k to kill it, or keep waiting").fg(Color::DarkGray),
+            ])
+            .wrap(Wrap { trim: false })
+        });
+        let content_width = if hung.is_some() {
+            content_width.max(60).min(area.width.saturating_sub(4))
+        } else {
+            content_width
+        };
+        let progress_height = content_height;
+        if let Some(hung) = hung.as_ref() {
+            content_height += hung.line_count(content_width) as u16;
+        }
 
         let popup_width = content_width + 2;
         let popup_height = content_height + 2;
@@ -114,16 +269,90 @@ impl Component for LoaderPopup {
         let inner = block.inner(popup_area);
 
         let throbber = Throbber::default().label(label).style(Style::default());
-        f.render_stateful_widget(throbber, inner, &mut self.throbber_state);
+        f.render_stateful_widget(
+            throbber,
+            Rect { height: 1, ..inner },
+            &mut self.throbber_state,
+        );
+
+        if let Some(progress) = progress {
+            let progress_area = Rect {
+                y: inner.y + 1,
+                height: 1,
+                ..inner
+            };
+            let text = progress.to_string();
+            let percent = PERCENT_REGEX
+                .captures(&text)
+                .and_then(|captures| captures[1].parse::<u16>().ok())
+                .filter(|percent| *percent <= 100);
+            match percent {
+                Some(percent) => f.render_widget(
+                    Gauge::default()
+                        .percent(percent)
+                        .label(text)
+                        .gauge_style(Style::default().fg(Color::Green).bg(Color::DarkGray))
+                        .use_unicode(true),
+                    progress_area,
+                ),
+                None => f.render_widget(progress, progress_area),
+            }
+        }
+
+        if let Some(hung) = hung {
+            f.render_widget(
+                hung,
+                Rect {
+                    y: inner.y + progress_height,
+                    height: inner.height.saturating_sub(progress_height),
+                    ..inner
+                },
+            );
+        }
 
         Ok(())
     }
 
     /// Process input
     ///
-    /// As of now, all input is ignored as we don't supporting cancelling operations yet.
-    fn input(&mut self, _commander: &mut Commander, _event: Event) -> Result<ComponentInputResult> {
+    /// Only killing a hung command and cancelling a cancellable operation are supported, all
+    /// other input is ignored.
+    fn input(&mut self, _commander: &mut Commander, event: Event) -> Result<ComponentInputResult> {
+        if let Event::Key(key) = event
+            && key.kind == KeyEventKind::Press
+        {
+            match key.code {
+                KeyCode::Char('k') => {
+                    if let Some(hung) = self.hung.take() {
+                        hung.kill();
+                    }
+                }
+                KeyCode::Esc if self.cancellable => {
+                    running::cancel_operation(self.operation);
+                    return Ok(ComponentInputResult::HandledAction(
+                        ComponentAction::SetPopup(None),
+                    ));
+                }
+                _ => {}
+            }
+        }
         // Block all input while loading
         Ok(ComponentInputResult::Handled)
     }
+
+    /// The popup is replaced once the operation finished
+    fn is_busy(&self) -> bool {
+        true
+    }
+
+    fn hints(&self) -> Vec<(String, String)> {
+        let mut hints = vec![];
+        if self.hung.is_some() {
+            hints.push(("k".to_owned(), "kill hung command".to_owned()));
+        }
+        if self.cancellable {
+            hints.push(("Esc".to_owned(), "cancel".to_owned()));
+        }
+        hints
+    }
 }
@@ -1,39 +1,69 @@
 #![expect(clippy::borrow_interior_mutable_const)]
 
+use std::collections::HashMap;
+
 use ansi_to_tui::IntoText;
 use anyhow::Result;
+use itertools::Itertools;
 use ratatui::{
-    crossterm::event::{Event, KeyEventKind},
+    crossterm::event::{Event, KeyEventKind, MouseEventKind},
     layout::Rect,
     prelude::*,
     widgets::*,
 };
-use tracing::instrument;
+use tracing::{instrument, warn};
 use tui_confirm_dialog::{ButtonLabel, ConfirmDialog, ConfirmDialogState, Listener};
 use tui_textarea::{CursorMove, TextArea};
 
 use crate::{
-    ComponentInputResult,
-    commander::{CommandError, Commander, log::Head},
+    ComponentInputResult, clipboard,
+    commander::{
+        CommandError, Commander,
+        ids::CommitId,
+        jj::{render_merge_template, render_reference_template},
+        log::{Head, LogFilter, LogRevision},
+        operations::CompositeAction,
+        show_cache::{ShowCache, ShowContext},
+        stream::StreamingOutput,
+    },
     env::{Config, DiffFormat},
     keybinds::{LogTabEvent, LogTabKeybinds},
+    panel_settings::{self, Panel},
     ui::{
         Component, ComponentAction,
+        amend_popup::AmendPopup,
         bookmark_set_popup::BookmarkSetPopup,
+        bookmark_stack_popup::BookmarkStackPopup,
+        changed_files_popup::ChangedFilesPopup,
+        conflicts_popup::ConflictsPopup,
+        describe_bookmarks_popup::DescribeBookmarksPopup,
         help_popup::HelpPopup,
+        jumplist::JumpList,
         loader_popup::LoaderPopup,
         message_popup::MessagePopup,
+        new_popup::NewPopup,
         panel::DetailsPanel,
         panel::LogPanel,
+        push_preview_popup::PushPreviewPopup,
+        read_only_popup,
         rebase_popup::RebasePopup,
-        utils::{centered_rect, centered_rect_line_height, tabs_to_spaces},
+        reorder_popup::ReorderPopup,
+        rollback_popup::RollbackPopup,
+        stats_popup::StatsPopup,
+        template_popup::TemplatePopup,
+        todos_popup::TodosPopup,
+        utils::{
+            centered_rect, centered_rect_line_height, spinner, tabs_to_spaces, textarea_input,
+        },
     },
+    word_diff::{DiffPart, diff_words},
 };
 
-const NEW_POPUP_ID: u16 = 1;
 const EDIT_POPUP_ID: u16 = 2;
 const ABANDON_POPUP_ID: u16 = 3;
 const SQUASH_POPUP_ID: u16 = 4;
+const MERGE_POPUP_ID: u16 = 5;
+const EDIT_NEW_POPUP_ID: u16 = 6;
 
 /// Log tab. Shows `jj log` in main panel and shows selected change details of in details panel.
 pub struct LogTab<'a> {
@@ -46,12 +76,22 @@ pub struct LogTab<'a> {
     /// The change content shown to the right
     head_panel: DetailsPanel,
     head_output: Result<String, CommandError>,
+    /// Output of the external diff tool for a commit, shown while it is still running
+    head_stream: Option<(CommitId, StreamingOutput)>,
+    /// Details of recently selected changes and of the changes next to the selected one
+    show_cache: ShowCache,
+    /// Lines changed by commits, to hold back large diffs, see [Config::large_diff_lines]
+    diff_line_counts: HashMap<CommitId, usize>,
+    /// Commit whose large diff was requested to be rendered anyway
+    full_diff: Option<CommitId>,
 
     /// The currently selected change. Indicates what to render
     /// in head_output. It is a copy of self.log_panel.head,
     /// so if these differ, we need to update self.head and
     /// self.head_output
     head: Head,
+    /// Previously selected changes, see [JumpList]
+    jumplist: JumpList,
 
     // Location of panels on screen. [0] = log, [1] = details
     panel_rect: [Rect; 2],
@@ -66,9 +106,25 @@ pub struct LogTab<'a> {
     bookmark_set_popup_rx: std::sync::mpsc::Receiver<bool>,
 
     describe_textarea: Option<TextArea<'a>>,
-    describe_after_new: bool,
+    /// Creating the change being described, rolled back if describing it fails
+    describe_action: Option<CompositeAction>,
+    /// Whether the described change is a merge created for the description, rolled back with
+    /// the [describe_action][LogTab::describe_action] if describing it is cancelled
+    describe_merge: bool,
+    /// Description when the describe editor was opened, to preview the changes against
+    describe_original: String,
+    /// Changes to confirm shown instead of the describe editor, computed once when saving as the
+    /// description can't be edited while they are shown
+    describe_preview: Option<Vec<Line<'static>>>,
+    /// Parents of the merge change to create once confirmed
+    merge_parents: Vec<LogRevision>,
+    /// Pending output of the describe generator
+    describe_generator_rx: Option<std::sync::mpsc::Receiver<Result<String, CommandError>>>,
+    /// Earlier paths of the filtered file, found in a background thread
+    earlier_paths_rx: Option<std::sync::mpsc::Receiver<Result<String, String>>>,
 
     rebase_popup: Option<RebasePopup>,
+    new_popup: Option<NewPopup>,
 
     squash_ignore_immutable: bool,
 
@@ -81,26 +137,32 @@ pub struct LogTab<'a> {
 impl<'a> LogTab<'a> {
     #[instrument(level = "info", name = "Initializing log tab", parent = None, skip(commander))]
     pub fn new(commander: &mut Commander) -> Result<Self> {
-        let diff_format = commander.env.config.diff_format();
+        let diff_format = panel_settings::load(&commander.env.root, Panel::Log)
+            .diff_format
+            .unwrap_or_else(|| commander.env.config.diff_format());
 
         let head = commander.get_current_head()?;
 
         let head_output = commander
-            .get_commit_show(&head.commit_id, &diff_format, true)
+            .get_commit_show_with_overrides(
+                &head.commit_id,
+                &diff_format,
+                commander.env.config.diff_overrides(),
+                true,
+            )
             .map(|text| tabs_to_spaces(&text));
 
         let (popup_tx, popup_rx) = std::sync::mpsc::channel();
         let (bookmark_set_popup_tx, bookmark_set_popup_rx) = std::sync::mpsc::channel();
 
-        let mut keybinds = LogTabKeybinds::default();
-        if let Some(new_keybinds) = commander
-            .env
-            .config
-            .keybinds()
-            .and_then(|k| k.log_tab.clone())
-        {
-            keybinds.extend_from_config(&new_keybinds);
-        }
+        let keybinds = LogTabKeybinds::new(
+            commander.env.config.keybind_preset(),
+            commander
+                .env
+                .config
+                .keybinds()
+                .and_then(|k| k.log_tab.as_ref()),
+        );
 
         Ok(Self {
             log_revset_textarea: None,
@@ -108,8 +170,17 @@ impl<'a> LogTab<'a> {
             log_panel: LogPanel::new(commander)?,
 
             head,
-            head_panel: DetailsPanel::new(),
+            jumplist: JumpList::default(),
+            head_panel: DetailsPanel::with_settings(
+                &commander.env.root,
+                Panel::Log,
+                commander.env.config.layout_percent(),
+            ),
             head_output,
+            head_stream: None,
+            show_cache: ShowCache::default(),
+            diff_line_counts: HashMap::new(),
+            full_diff: None,
 
             panel_rect: [Rect::ZERO, Rect::ZERO],
 
@@ -123,9 +194,16 @@ impl<'a> LogTab<'a> {
             bookmark_set_popup_rx,
 
             describe_textarea: None,
-            describe_after_new: false,
+            describe_action: None,
+            describe_merge: false,
+            describe_original: String::new(),
+            describe_preview: None,
+            merge_parents: vec![],
+            describe_generator_rx: None,
+            earlier_paths_rx: None,
 
             rebase_popup: None,
+            new_popup: None,
 
             squash_ignore_immutable: false,
 
@@ -145,9 +223,104 @@ impl<'a> LogTab<'a> {
     fn refresh_head_output(&mut self, commander: &mut Commander) {
         let inner_width = self.head_panel.columns() as usize;
         commander.limit_width(inner_width);
-        let new_output = commander
-            .get_commit_show(&self.head.commit_id, &self.diff_format, true)
-            .map(|text| tabs_to_spaces(&text));
+        self.head_panel.set_content_columns();
+        self.head_panel.content_changed();
+
+        // External diff tools can be slow, so their output is streamed into the panel
+        if matches!(self.diff_format, DiffFormat::DiffTool(_)) {
+            let same_commit = self
+                .head_stream
+                .as_ref()
+                .is_some_and(|(commit_id, _)| commit_id == &self.head.commit_id);
+            match commander.stream_commit_show(&self.head.commit_id, &self.diff_format) {
+                Ok(stream) => {
+                    self.head_stream = Some((self.head.commit_id.clone(), stream));
+                    if !same_commit {
+                        self.head_output = Ok(String::new());
+                    }
+                }
+                Err(err) => {
+                    self.head_stream = None;
+                    self.head_output = Err(err);
+                }
+            }
+            if !same_commit {
+                self.head_panel.scroll_to(0);
+            }
+            return;
+        }
+        self.head_stream = None;
+
+        // Diff overrides only apply to the default diff format, toggling it shows all files
+        let diff_overrides = if self.diff_format == self.config.diff_format() {
+            self.config.diff_overrides().to_vec()
+        } else {
+            vec![]
+        };
+        let context = ShowContext {
+            diff_format: self.diff_format.clone(),
+            diff_overrides,
+            width: inner_width,
+            op_heads: commander.get_op_heads(),
+        };
+        // Rendering giant diffs, e.g. of vendored code, takes seconds, so only their stat is shown
+        // until requested
+        let new_output = if let Some(lines) = self.held_back_diff_lines(commander) {
+            commander
+                .get_commit_show(&self.head.commit_id, &DiffFormat::Stat, true)
+                .map(|show| {
+                    format!(
+                        "\x1b[1;33mThe diff changes {lines} lines, more than \
+                         blazingjj.large-diff-lines. Press Enter to render it\x1b[0m\n\n{show}"
+                    )
+                })
+        } else {
+            match self.show_cache.get(&context, &self.head.commit_id) {
+                Some(output) => Ok(output),
+                None => commander
+                    .get_commit_show_with_overrides(
+                        &self.head.commit_id,
+                        &self.diff_format,
+                        &context.diff_overrides,
+                        true,
+                    )
+                    .inspect(|output| {
+                        self.show_cache.insert(
+                            &context,
+                            self.head.commit_id.clone(),
+                            output.clone(),
+                        )
+                    }),
+            }
+        }
+        .map(|text| tabs_to_spaces(&text));
+
+        // Like `jj status`, the working copy starts with a summary of its changes. It isn't
+        // cached, as the conflicts are looked up separately
+        let is_working_copy = self.log_panel.selected_revision().is_some_and(|revision| {
+            revision.working_copy && revision.commit_id == self.head.commit_id
+        });
+        let new_output = match new_output {
+            Ok(text) if is_working_copy => {
+                match commander.get_working_copy_status(&self.head.commit_id) {
+                    Ok(status) => Ok(format!("{status}\n{text}")),
+                    Err(err) => {
+                        warn!("{err:?}");
+                        Ok(text)
+                    }
+                }
+            }
+            output => output,
+        };
+
+        // Prefetch the changes next to the selected one, as they are likely selected next
+        let neighbors = self
+            .log_panel
+            .neighbor_heads()
+            .into_iter()
+            .map(|head| head.commit_id)
+            .collect();
+        self.show_cache.prefetch(commander, &context, neighbors);
 
         let content_changed = match (&self.head_output, &new_output) {
             (Ok(old), Ok(new)) => old != new,
@@ -162,18 +335,265 @@ impl<'a> LogTab<'a> {
         }
     }
 
+    /// Number of lines changed by the selected change if its diff is held back as it has more
+    /// than [Config::large_diff_lines]
+    fn held_back_diff_lines(&mut self, commander: &Commander) -> Option<usize> {
+        let limit = self.config.large_diff_lines()?;
+        if matches!(self.diff_format, DiffFormat::Stat | DiffFormat::Summary)
+            || self.full_diff.as_ref() == Some(&self.head.commit_id)
+        {
+            return None;
+        }
+        let lines = match self.diff_line_counts.get(&self.head.commit_id) {
+            Some(lines) => *lines,
+            None => {
+                let lines = commander
+                    .get_diff_line_count(&self.head.commit_id)
+                    .inspect_err(|err| warn!("{err:?}"))
+                    .ok()?;
+                self.diff_line_counts
+                    .insert(self.head.commit_id.clone(), lines);
+                lines
+            }
+        };
+        (lines > limit).then_some(lines)
+    }
+
+    /// Select a change, recording the previous one in the jumplist
     pub fn set_head(&mut self, commander: &mut Commander, head: Head) {
+        if head.change_id != self.head.change_id {
+            self.jumplist.push(self.head.clone());
+        }
+        self.show_head(commander, head);
+    }
+
+    /// Descriptions of the changes in the log, for the presentation mode
+    pub fn descriptions(&self) -> impl Iterator<Item = &str> {
+        self.log_panel.descriptions()
+    }
+
+    /// Select a change from another tab, adding it to the revset if the log doesn't show it
+    pub fn view_head(&mut self, commander: &mut Commander, head: Head) {
+        self.set_head(commander, head);
+        self.log_panel.reveal_head(commander);
+    }
+
+    fn show_head(&mut self, commander: &mut Commander, head: Head) {
         self.log_panel.set_head(head);
         self.log_panel.refresh_log_output(commander);
         self.sync_head_output(commander);
     }
 
+    /// Select the previous or next change of the jumplist, skipping changes which no longer
+    /// exist
+    fn jump(&mut self, commander: &mut Commander, back: bool) -> Result<()> {
+        loop {
+            let entry = if back {
+                self.jumplist.back(&self.head)
+            } else {
+                self.jumplist.forward()
+            };
+            let Some(entry) = entry else {
+                return Ok(());
+            };
+            if let Some(head) = commander.find_head_latest(&entry)? {
+                self.show_head(commander, head);
+                return Ok(());
+            }
+        }
+    }
+
+    /// Open the describe editor with the current description of the selected change
+    fn open_describe_editor(&mut self, description: String) {
+        let mut textarea = TextArea::new(description.lines().map(String::from).collect());
+        textarea.move_cursor(CursorMove::End);
+        self.describe_textarea = Some(textarea);
+        self.describe_original = description;
+        self.describe_preview = None;
+    }
+
+    fn close_describe_editor(&mut self) {
+        self.describe_textarea = None;
+        self.describe_action = None;
+        self.describe_merge = false;
+        self.describe_generator_rx = None;
+        self.describe_preview = None;
+    }
+
+    /// Lines of the describe preview: the words removed from and added to the description, and
+    /// a warning if it is cleared
+    fn describe_preview_lines(&self, description: &str) -> Vec<Line<'static>> {
+        let mut lines = vec![];
+        if description.trim().is_empty() && !self.describe_original.trim().is_empty() {
+            lines.push(
+                Line::from("Warning: this clears the description")
+                    .yellow()
+                    .bold(),
+            );
+            lines.push(Line::default());
+        }
+        let mut line = Line::default();
+        for part in diff_words(&self.describe_original, description) {
+            let (text, style) = match part {
+                DiffPart::Unchanged(text) => (text, Style::default()),
+                DiffPart::Added(text) => (text, Style::default().green()),
+                DiffPart::Removed(text) => (text, Style::default().red().crossed_out()),
+            };
+            let mut text_lines = text.split('\n');
+            if let Some(first) = text_lines.next() {
+                line.push_span(Span::styled(first.to_owned(), style));
+            }
+            for text_line in text_lines {
+                lines.push(std::mem::take(&mut line));
+                line.push_span(Span::styled(text_line.to_owned(), style));
+            }
+        }
+        lines.push(line);
+        lines
+    }
+
+    /// Whether the external diff tool is still running for the details panel
+    fn is_streaming(&self) -> bool {
+        self.head_stream
+            .as_ref()
+            .is_some_and(|(_, stream)| stream.is_running())
+    }
+
+    /// The currently selected change
+    pub fn head(&self) -> &Head {
+        &self.head
+    }
+
+    /// Lines describing the selected change in confirmation popups
+    fn revision_lines(&self) -> Vec<Line<'static>> {
+        let Some(revision) = self.log_panel.selected_revision() else {
+            return vec![];
+        };
+
+        let mut lines = vec![
+            Line::from(format!("Title: {}", revision.title())),
+            Line::from(format!(
+                "Author: {} <{}>",
+                revision.author_name, revision.author_email
+            )),
+        ];
+        let bookmarks = revision
+            .bookmarks
+            .iter()
+            .chain(&revision.remote_bookmarks)
+            .join(", ");
+        if !bookmarks.is_empty() {
+            lines.push(Line::from(format!("Bookmarks: {bookmarks}")));
+        }
+        let flags = [
+            (revision.working_copy, "working copy"),
+            (revision.conflict, "conflict"),
+            (revision.empty, "empty"),
+        ]
+        .into_iter()
+        .filter_map(|(set, flag)| set.then_some(flag))
+        .join(", ");
+        if !flags.is_empty() {
+            lines.push(Line::from(format!("Flags: {flags}")));
+        }
+        lines
+    }
+
+    /// Lines telling what `@` is after editing the selected change, or after creating a new change
+    /// on top of it, and what happens to the current working copy
+    fn working_copy_lines(&self, commander: &Commander, new: bool) -> Vec<Line<'static>> {
+        let change_id: String = self.head.change_id.as_str().chars().take(8).collect();
+        let mut lines = vec![Line::from(if new {
+            format!("Afterwards @ is a new empty change on top of {change_id}")
+        } else {
+            format!("Afterwards @ is {change_id}")
+        })];
+
+        let summary = match commander.get_working_copy_summary() {
+            Ok(summary) => summary,
+            Err(err) => {
+                warn!("Failed getting the working copy summary: {err:?}");
+                return lines;
+            }
+        };
+        if !new && self.head.change_id.as_str().starts_with(&summary.change_id) {
+            return lines;
+        }
+        if summary.described {
+            return lines;
+        }
+        if summary.empty {
+            lines.push(
+                Line::from(format!(
+                    "The empty working copy {} is abandoned",
+                    summary.change_id
+                ))
+                .fg(Color::DarkGray),
+            );
+        } else {
+            lines.push(
+                Line::from(format!(
+                    "Warning: the working copy {} changes {} files without a description, they \
+                     stay behind in an anonymous change",
+                    summary.change_id, summary.files
+                ))
+                .fg(Color::Yellow),
+            );
+        }
+        lines
+    }
+
+    /// Only show changes touching the given file or directory, or all changes if `None`. The
+    /// history of a file continues with its earlier paths if it was renamed, which are added as
+    /// they are found in the background.
+    pub fn set_path_filter(&mut self, commander: &mut Commander, path: Option<String>) {
+        self.log_panel.log_path_renames = vec![];
+        // Dropping the receiver stops following the renames of the previous path
+        self.earlier_paths_rx = path.clone().map(|path| {
+            let (tx, rx) = std::sync::mpsc::channel();
+            let commander = Commander::new(&commander.env);
+            std::thread::spawn(move || {
+                let result = commander.follow_renames(&path, |earlier_path| {
+                    tx.send(Ok(earlier_path.to_owned())).is_ok()
+                });
+                if let Err(err) = result {
+                    let _ = tx.send(Err(format!(
+                        "Failed following the renames of {path}: {err}"
+                    )));
+                }
+            });
+            rx
+        });
+        self.log_panel.log_path = path;
+        self.log_panel.refresh_log_output(commander);
+        self.sync_head_output(commander);
+    }
+
+    /// Keys greyed out in the help in read-only mode
+    fn read_only_keys(&self) -> Vec<String> {
+        if !self.config.read_only() {
+            return vec![];
+        }
+        self.keybinds.mutating_shortcuts()
+    }
+
     fn handle_event(
         &mut self,
         commander: &mut Commander,
         log_tab_event: LogTabEvent,
     ) -> Result<ComponentInputResult> {
+        if self.config.read_only() && log_tab_event.is_mutating() {
+            return Ok(ComponentInputResult::HandledAction(read_only_popup()));
+        }
+
         match log_tab_event {
+            LogTabEvent::Cancel if self.is_streaming() => {
+                if let Some((_, stream)) = self.head_stream.as_mut() {
+                    stream.cancel();
+                    self.head_output = stream.result().map(tabs_to_spaces);
+                    self.head_panel.content_changed();
+                }
+            }
             LogTabEvent::ScrollDown
             | LogTabEvent::ScrollUp
             | LogTabEvent::ScrollDownHalf
@@ -184,30 +604,64 @@ impl<'a> LogTab<'a> {
             LogTabEvent::FocusCurrent => {
                 self.set_head(commander, commander.get_current_head()?);
             }
+            LogTabEvent::JumpBack => self.jump(commander, true)?,
+            LogTabEvent::JumpForward => self.jump(commander, false)?,
             LogTabEvent::ToggleDiffFormat => {
                 self.diff_format = self.diff_format.get_next(self.config.diff_tool());
+                let diff_format = self.diff_format.clone();
+                panel_settings::save(&commander.env.root, Panel::Log, |settings| {
+                    settings.diff_format = Some(diff_format)
+                });
                 self.refresh_head_output(commander);
             }
+            LogTabEvent::Refresh if self.log_panel.follow_working_copy => {
+                self.set_head(commander, commander.get_current_head()?);
+            }
             LogTabEvent::Refresh => {
                 self.log_panel.refresh_log_output(commander);
-                self.refresh_head_output(commander);
+                self.sync_head_output(commander);
             }
-            LogTabEvent::CreateNew { describe } => {
+            LogTabEvent::ToggleFollow => {
+                self.log_panel.follow_working_copy = !self.log_panel.follow_working_copy;
+                if self.log_panel.follow_working_copy {
+                    self.set_head(commander, commander.get_current_head()?);
+                }
+            }
+            LogTabEvent::CreateNew { .. } if self.log_panel.marked_revisions().len() > 1 => {
+                self.merge_parents = self.log_panel.marked_revisions();
+                let mut lines = vec![
+                    Line::from("Are you sure you want to create a merge change?"),
+                    Line::from("Parents:"),
+                ];
+                lines.extend(self.merge_parents.iter().map(|revision| {
+                    Line::from(format!("{} {}", revision.change_id, revision.title()))
+                }));
                 self.popup = ConfirmDialogState::new(
-                    NEW_POPUP_ID,
-                    Span::styled(" New ", Style::new().bold().cyan()),
-                    Text::from(vec![
-                        Line::from("Are you sure you want to create a new change?"),
-                        Line::from(format!("New parent: {}", self.head.change_id.as_str())),
-                    ])
-                    .fg(Color::default()),
+                    MERGE_POPUP_ID,
+                    Span::styled(" Merge ", Style::new().bold().cyan()),
+                    Text::from(lines).fg(Color::default()),
                 );
                 self.popup
                     .with_yes_button(ButtonLabel::YES.clone())
                     .with_no_button(ButtonLabel::NO.clone())
                     .with_listener(Some(self.popup_tx.clone()))
                     .open();
-                self.describe_after_new = describe;
+            }
+            LogTabEvent::CreateNew { describe } => {
+                self.new_popup = Some(NewPopup::new(commander, self.head.clone(), describe));
+            }
+            LogTabEvent::StartNewWork => {
+                // Rolled back with the description if describing fails, like `N`
+                let action = commander.begin_action("Start new work")?;
+                let created = commander
+                    .run_new_placed(commander.env.config.new_work_base(), None, true)
+                    .map_err(|err| commander.fail_action(action.clone(), err))?;
+                self.set_head(commander, created);
+                self.open_describe_editor(String::new());
+                self.describe_action = Some(action);
+                return Ok(ComponentInputResult::HandledAction(
+                    ComponentAction::ChangeHead(self.head.clone()),
+                ));
             }
             LogTabEvent::Rebase => {
                 let source_change = commander.get_current_head()?;
@@ -218,12 +672,17 @@ impl<'a> LogTab<'a> {
                 ));
             }
             LogTabEvent::Squash { ignore_immutable } => {
-                if self.head.change_id == commander.get_current_head()?.change_id {
+                let is_working_copy = match self.log_panel.selected_revision() {
+                    Some(revision) => revision.working_copy,
+                    None => self.head.change_id == commander.get_current_head()?.change_id,
+                };
+                if is_working_copy {
                     return Ok(ComponentInputResult::HandledAction(
                         ComponentAction::SetPopup(Some(Box::new(MessagePopup {
                             title: "Squash".into(),
                             messages: "Cannot squash onto current change".into_text()?,
                             text_align: None,
+                            ..Default::default()
                         }))),
                     ));
                 }
@@ -233,6 +692,7 @@ impl<'a> LogTab<'a> {
                             title: "Squash".into(),
                             messages: "Cannot squash onto immutable change".into_text()?,
                             text_align: None,
+                            ..Default::default()
                         }))),
                     ));
                 }
@@ -241,6 +701,7 @@ impl<'a> LogTab<'a> {
                     Line::from("Are you sure you want to squash @ into this change?"),
                     Line::from(format!("Squash into {}", self.head.change_id.as_str())),
                 ];
+                lines.append(&mut self.revision_lines());
                 if ignore_immutable {
                     lines.push(Line::from("This change is immutable."));
                 }
@@ -256,29 +717,60 @@ impl<'a> LogTab<'a> {
                     .open();
                 self.squash_ignore_immutable = ignore_immutable;
             }
+            LogTabEvent::Amend => {
+                let is_working_copy = match self.log_panel.selected_revision() {
+                    Some(revision) => revision.working_copy,
+                    None => self.head.change_id == commander.get_current_head()?.change_id,
+                };
+                let error = if is_working_copy {
+                    Some("Cannot amend the current change with itself")
+                } else if self.head.immutable {
+                    Some("Cannot amend an immutable change")
+                } else {
+                    None
+                };
+                let popup: Box<dyn Component> = match error {
+                    Some(error) => Box::new(MessagePopup {
+                        title: "Amend".into(),
+                        messages: error.into_text()?,
+                        text_align: None,
+                        ..Default::default()
+                    }),
+                    None => Box::new(AmendPopup::new(commander, self.head.clone())),
+                };
+                return Ok(ComponentInputResult::HandledAction(
+                    ComponentAction::SetPopup(Some(popup)),
+                ));
+            }
             LogTabEvent::EditChange { ignore_immutable } => {
-                if self.head.immutable && !ignore_immutable {
-                    return Ok(ComponentInputResult::HandledAction(
-                        ComponentAction::SetPopup(Some(Box::new(MessagePopup {
-                            title: " Edit ".into(),
-                            messages: vec![
-                                "The change cannot be edited because it is immutable.".into(),
-                            ]
-                            .into(),
-                            text_align: None,
-                        }))),
-                    ));
-                }
-
-                let mut lines = vec![
-                    Line::from("Are you sure you want to edit an existing change?"),
-                    Line::from(format!("Change: {}", self.head.change_id.as_str())),
-                ];
+                // Offer to work on top of an immutable change instead, like `jj new <rev>`
+                let new = self.head.immutable && !ignore_immutable;
+                let mut lines = if new {
+                    vec![
+                        Line::from("The change cannot be edited because it is immutable."),
+                        Line::from("Create a new change on top of it instead?"),
+                    ]
+                } else {
+                    vec![Line::from(
+                        "Are you sure you want to edit an existing change?",
+                    )]
+                };
+                lines.push(Line::from(format!(
+                    "Change: {}",
+                    self.head.change_id.as_str()
+                )));
+                lines.append(&mut self.revision_lines());
                 if ignore_immutable {
                     lines.push(Line::from("This change is immutable."))
                 }
+                lines.push(Line::default());
+                lines.append(&mut self.working_copy_lines(commander, new));
                 self.popup = ConfirmDialogState::new(
-                    EDIT_POPUP_ID,
+                    if new {
+                        EDIT_NEW_POPUP_ID
+                    } else {
+                        EDIT_POPUP_ID
+                    },
                     Span::styled(" Edit ", Style::new().bold().cyan()),
                     Text::from(lines).fg(Color::default()),
                 );
@@ -299,16 +791,23 @@ impl<'a> LogTab<'a> {
                             ]
                             .into(),
                             text_align: None,
+                            ..Default::default()
                         }))),
                     ));
                 } else {
                     self.popup = ConfirmDialogState::new(
                         ABANDON_POPUP_ID,
                         Span::styled(" Abandon ", Style::new().bold().cyan()),
-                        Text::from(vec![
-                            Line::from("Are you sure you want to abandon this change?"),
-                            Line::from(format!("Change: {}", self.head.change_id.as_str())),
-                        ])
+                        Text::from(
+                            [
+                                vec![
+                                    Line::from("Are you sure you want to abandon this change?"),
+                                    Line::from(format!("Change: {}", self.head.change_id.as_str())),
+                                ],
+                                self.revision_lines(),
+                            ]
+                            .concat(),
+                        )
                         .fg(Color::default()),
                     );
                     self.popup
@@ -328,21 +827,51 @@ impl<'a> LogTab<'a> {
                             ]
                             .into(),
                             text_align: None,
+                            ..Default::default()
                         }))),
                     ));
                 } else {
-                    let mut textarea = TextArea::new(
-                        commander
-                            .get_commit_description(&self.head.commit_id)?
-                            .split("\n")
-                            .map(|line| line.to_string())
-                            .collect(),
-                    );
-                    textarea.move_cursor(CursorMove::End);
-                    self.describe_textarea = Some(textarea);
+                    let description = match self.log_panel.selected_revision() {
+                        Some(revision) => revision.description.trim_end().to_owned(),
+                        None => commander.get_commit_description(&self.head.commit_id)?,
+                    };
+                    self.open_describe_editor(description);
                     return Ok(ComponentInputResult::Handled);
                 }
             }
+            LogTabEvent::BookmarkStack => {
+                return Ok(ComponentInputResult::HandledAction(
+                    ComponentAction::SetPopup(Some(Box::new(BookmarkStackPopup::new(commander)))),
+                ));
+            }
+            LogTabEvent::DescribeFromBookmarks => {
+                return Ok(ComponentInputResult::HandledAction(
+                    ComponentAction::SetPopup(Some(Box::new(DescribeBookmarksPopup::new(
+                        commander,
+                    )))),
+                ));
+            }
+            LogTabEvent::ExpandElided => {
+                if !self.log_panel.expand_elided(commander) {
+                    return Ok(ComponentInputResult::HandledAction(
+                        ComponentAction::SetPopup(Some(Box::new(MessagePopup {
+                            title: "Expand elided revisions".into(),
+                            messages: "No revisions are elided below the selected change"
+                                .into_text()?,
+                            text_align: None,
+                            ..Default::default()
+                        }))),
+                    ));
+                }
+            }
+            LogTabEvent::ShowConflicts => {
+                let revset = self.log_panel.full_revset(commander);
+                return Ok(ComponentInputResult::HandledAction(
+                    ComponentAction::SetPopup(Some(Box::new(ConflictsPopup::new(
+                        commander, &revset,
+                    )))),
+                ));
+            }
             LogTabEvent::EditRevset => {
                 let mut textarea = TextArea::new(
                     self.log_panel
@@ -357,6 +886,67 @@ impl<'a> LogTab<'a> {
                 self.log_revset_textarea = Some(textarea);
                 return Ok(ComponentInputResult::Handled);
             }
+            LogTabEvent::ClearPathFilter => {
+                self.set_path_filter(commander, None);
+            }
+            LogTabEvent::ToggleMineFilter => {
+                self.log_panel.toggle_filter(commander, LogFilter::Mine);
+                self.sync_head_output(commander);
+            }
+            LogTabEvent::ToggleUnmergedFilter => {
+                self.log_panel.toggle_filter(commander, LogFilter::Unmerged);
+                self.sync_head_output(commander);
+            }
+            LogTabEvent::ToggleFirstParentFilter => {
+                self.log_panel
+                    .toggle_filter(commander, LogFilter::FirstParent);
+                self.sync_head_output(commander);
+            }
+            LogTabEvent::ToggleMinimap => {
+                self.log_panel.show_minimap = !self.log_panel.show_minimap;
+            }
+            LogTabEvent::ToggleTrunkRelation => {
+                self.log_panel.show_trunk_relation = !self.log_panel.show_trunk_relation;
+                self.log_panel.refresh_log_output(commander);
+            }
+            LogTabEvent::MarkSeen => {
+                self.log_panel.mark_seen(commander);
+            }
+            LogTabEvent::OpenInPager => {
+                // Open what the focused panel shows
+                let text = if self.head_panel.is_focused() {
+                    self.head_output.as_ref().ok().cloned()
+                } else {
+                    self.log_panel.log_graph().map(str::to_owned)
+                };
+                if let Some(text) = text {
+                    return Ok(ComponentInputResult::HandledAction(
+                        ComponentAction::OpenInPager(text),
+                    ));
+                }
+            }
+            LogTabEvent::OpenInBrowser => {
+                if let Err(err) = commander
+                    .get_forge_commit_url(&self.head.commit_id)
+                    .and_then(|url| commander.open_in_browser(&url))
+                {
+                    return Ok(ComponentInputResult::HandledAction(
+                        ComponentAction::SetPopup(Some(Box::new(MessagePopup {
+                            title: "Open in browser".into(),
+                            messages: commander.error_text(&err, "")?,
+                            text_align: None,
+                            ..Default::default()
+                        }))),
+                    ));
+                }
+            }
+            LogTabEvent::ToggleMark => {
+                self.log_panel.toggle_mark();
+            }
+            LogTabEvent::CycleLogDensity => {
+                self.log_panel.log_density = self.log_panel.log_density.next();
+                self.log_panel.refresh_log_output(commander);
+            }
             LogTabEvent::SetBookmark => {
                 return Ok(ComponentInputResult::HandledAction(
                     ComponentAction::SetPopup(Some(Box::new(BookmarkSetPopup::new(
@@ -368,6 +958,120 @@ impl<'a> LogTab<'a> {
                     )))),
                 ));
             }
+            LogTabEvent::PreviewTemplate => {
+                return Ok(ComponentInputResult::HandledAction(
+                    ComponentAction::SetPopup(Some(Box::new(TemplatePopup::new(
+                        commander,
+                        self.head.clone(),
+                    )))),
+                ));
+            }
+            LogTabEvent::ShowStats => {
+                return Ok(ComponentInputResult::HandledAction(
+                    ComponentAction::SetPopup(Some(Box::new(StatsPopup::new(
+                        commander,
+                        self.log_panel.full_revset(commander),
+                        self.log_panel.log_path.clone(),
+                    )))),
+                ));
+            }
+            LogTabEvent::ShowChangedFiles => {
+                return Ok(ComponentInputResult::HandledAction(
+                    ComponentAction::SetPopup(Some(Box::new(ChangedFilesPopup::new(
+                        commander,
+                        self.head.clone(),
+                    )))),
+                ));
+            }
+            LogTabEvent::ReviewRange => {
+                // The stack of the change on trunk(), or the range spanning the marked changes
+                let marked = self.log_panel.marked_revisions();
+                let revset = if marked.is_empty() {
+                    format!("trunk()..{}", self.head.commit_id)
+                } else {
+                    let changes = marked
+                        .iter()
+                        .map(|revision| revision.commit_id.as_str())
+                        .chain([self.head.commit_id.as_str()])
+                        .join(" | ");
+                    format!("roots({changes})::heads({changes})")
+                };
+                return Ok(ComponentInputResult::HandledAction(
+                    ComponentAction::ReviewRange(revset),
+                ));
+            }
+            LogTabEvent::Reorder => {
+                return Ok(ComponentInputResult::HandledAction(
+                    ComponentAction::SetPopup(Some(Box::new(ReorderPopup::new(
+                        commander, &self.head,
+                    )))),
+                ));
+            }
+            LogTabEvent::CopyReference => {
+                let Some(revision) = self.log_panel.selected_revision() else {
+                    return Ok(ComponentInputResult::Handled);
+                };
+                let template = self.config.copy_reference_template();
+                let url = if template.contains("{url}") {
+                    commander.get_forge_commit_url(&revision.commit_id)
+                } else {
+                    Ok(String::new())
+                };
+                let (title, messages) = match url {
+                    Ok(url) => {
+                        let reference = render_reference_template(template, revision, &url);
+                        match clipboard::copy(&reference) {
+                            Ok(()) => ("Copied reference", Text::raw(reference)),
+                            Err(err) => ("Copy reference", Text::raw(err.to_string())),
+                        }
+                    }
+                    Err(err) => ("Copy reference", err.into_text("")?),
+                };
+                return Ok(ComponentInputResult::HandledAction(
+                    ComponentAction::SetPopup(Some(Box::new(MessagePopup {
+                        title: title.into(),
+                        messages,
+                        text_align: None,
+                        ..Default::default()
+                    }))),
+                ));
+            }
+            LogTabEvent::ShowTodos => {
+                return Ok(ComponentInputResult::HandledAction(
+                    ComponentAction::SetPopup(Some(Box::new(TodosPopup::new(
+                        commander,
+                        self.head.clone(),
+                    )))),
+                ));
+            }
+            LogTabEvent::ViewBookmark => {
+                let revision = self.log_panel.selected_revision();
+                let local = revision.and_then(|revision| revision.bookmarks.first());
+                let remote = revision
+                    .and_then(|revision| revision.remote_bookmarks.first())
+                    .and_then(|bookmark| bookmark.rsplit_once('@'));
+                let action = match (local, remote) {
+                    (Some(name), _) => ComponentAction::ViewBookmark {
+                        name: name.clone(),
+                        remote: None,
+                    },
+                    (None, Some((name, remote))) => ComponentAction::ViewBookmark {
+                        name: name.to_owned(),
+                        remote: Some(remote.to_owned()),
+                    },
+                    (None, None) => ComponentAction::SetPopup(Some(Box::new(MessagePopup {
+                        title: "Bookmarks".into(),
+                        messages: "The change has no bookmarks".into_text()?,
+                        text_align: None,
+                        ..Default::default()
+                    }))),
+                };
+                return Ok(ComponentInputResult::HandledAction(action));
+            }
+            LogTabEvent::OpenFiles if self.held_back_diff_lines(commander).is_some() => {
+                self.full_diff = Some(self.head.commit_id.clone());
+                self.refresh_head_output(commander);
+            }
             LogTabEvent::OpenFiles => {
                 return Ok(ComponentInputResult::HandledAction(
                     ComponentAction::ViewFiles(self.head.clone()),
@@ -377,11 +1081,34 @@ impl<'a> LogTab<'a> {
                 all_bookmarks,
                 allow_new,
             } => {
+                let preview = PushPreviewPopup::new(
+                    commander,
+                    all_bookmarks,
+                    allow_new,
+                    self.head.commit_id.clone(),
+                );
+
+                return Ok(ComponentInputResult::HandledAction(
+                    ComponentAction::SetPopup(Some(Box::new(preview))),
+                ));
+            }
+            LogTabEvent::PushDryRun { all_bookmarks } => {
+                let commander_clone = Commander::new(&commander.env);
                 let commit_id = self.head.commit_id.clone();
+
+                let loader = LoaderPopup::new("Push dry run".to_string(), move || {
+                    commander_clone.git_push_dry_run(all_bookmarks, false, &commit_id)
+                });
+
+                return Ok(ComponentInputResult::HandledAction(
+                    ComponentAction::SetPopup(Some(Box::new(loader))),
+                ));
+            }
+            LogTabEvent::FetchDryRun { all_remotes } => {
                 let commander_clone = Commander::new(&commander.env);
 
-                let loader = LoaderPopup::new("Pushing".to_string(), move || {
-                    commander_clone.git_push(all_bookmarks, allow_new, &commit_id)
+                let loader = LoaderPopup::new("Fetch dry run".to_string(), move || {
+                    commander_clone.git_fetch_dry_run(all_remotes)
                 });
 
                 return Ok(ComponentInputResult::HandledAction(
@@ -393,7 +1120,8 @@ impl<'a> LogTab<'a> {
 
                 let loader = LoaderPopup::new("Fetching".to_string(), move || {
                     commander_clone.git_fetch(all_remotes)
-                });
+                })
+                .with_terminal_retry(Commander::git_fetch_args(all_remotes));
 
                 return Ok(ComponentInputResult::HandledAction(
                     ComponentAction::SetPopup(Some(Box::new(loader))),
@@ -401,26 +1129,15 @@ impl<'a> LogTab<'a> {
             }
             LogTabEvent::OpenHelp => {
                 return Ok(ComponentInputResult::HandledAction(
-                    ComponentAction::SetPopup(Some(Box::new(HelpPopup::new(
-                        self.keybinds.make_main_panel_help(),
-                        vec![
-                            ("Ctrl+e/Ctrl+y".to_owned(), "scroll down/up".to_owned()),
-                            (
-                                "Ctrl+d/Ctrl+u".to_owned(),
-                                "scroll down/up by ½ page".to_owned(),
-                            ),
-                            (
-                                "Ctrl+f/Ctrl+b".to_owned(),
-                                "scroll down/up by page".to_owned(),
-                            ),
-                            ("w".to_owned(), "toggle diff format".to_owned()),
-                            ("W".to_owned(), "toggle wrapping".to_owned()),
-                        ],
-                    )))),
+                    ComponentAction::SetPopup(Some(Box::new(
+                        HelpPopup::new(self.keybinds.make_main_panel_help(), DetailsPanel::help())
+                            .read_only(&self.read_only_keys()),
+                    ))),
                 ));
             }
             LogTabEvent::Save
             | LogTabEvent::Cancel
+            | LogTabEvent::GenerateDescription
             | LogTabEvent::ClosePopup
             | LogTabEvent::Unbound => return Ok(ComponentInputResult::NotHandled),
         };
@@ -430,31 +1147,46 @@ impl<'a> LogTab<'a> {
 
 impl Component for LogTab<'_> {
     fn focus(&mut self, commander: &mut Commander) -> Result<()> {
-        let latest_head = commander.get_head_latest(&self.head)?;
-        self.set_head(commander, latest_head);
+        // `@` may have moved, e.g. by jj new in a terminal
+        if self.log_panel.follow_working_copy {
+            self.set_head(commander, commander.get_current_head()?);
+            return Ok(());
+        }
+        match commander.find_head_latest(&self.head)? {
+            Some(latest_head) => self.set_head(commander, latest_head),
+            // The change is gone, the log panel selects the change at the same position
+            None => {
+                self.log_panel.refresh_log_output(commander);
+                self.sync_head_output(commander);
+            }
+        }
         Ok(())
     }
 
     fn update(&mut self, commander: &mut Commander) -> Result<Option<ComponentAction>> {
+        // The output of diff tools depends on the width, produce it again once resizing settled
+        if self.diff_format.depends_on_width() && self.head_panel.is_resized() {
+            self.refresh_head_output(commander);
+        }
+
+        if let Some(new_popup) = &mut self.new_popup {
+            new_popup.update();
+        }
+
         // Check for popup action
         if let Ok(res) = self.popup_rx.try_recv()
             && res.1.unwrap_or(false)
         {
             match res.0 {
-                NEW_POPUP_ID => {
-                    commander.run_new(self.head.commit_id.as_str())?;
-                    self.set_head(commander, commander.get_current_head()?);
-                    if self.describe_after_new {
-                        self.describe_after_new = false;
-                        let textarea = TextArea::default();
-                        self.describe_textarea = Some(textarea);
-                    }
-                    return Ok(Some(ComponentAction::ChangeHead(self.head.clone())));
-                }
                 EDIT_POPUP_ID => {
                     commander.run_edit(self.head.commit_id.as_str(), self.edit_ignore_immutable)?;
                     self.log_panel.refresh_log_output(commander);
-                    self.refresh_head_output(commander);
+                    self.sync_head_output(commander);
+                    return Ok(Some(ComponentAction::ChangeHead(self.head.clone())));
+                }
+                EDIT_NEW_POPUP_ID => {
+                    commander.run_new(self.head.commit_id.as_str())?;
+                    self.set_head(commander, commander.get_current_head()?);
                     return Ok(Some(ComponentAction::ChangeHead(self.head.clone())));
                 }
                 ABANDON_POPUP_ID => {
@@ -468,19 +1200,101 @@ impl Component for LogTab<'_> {
                         self.set_head(commander, head_parent);
                     }
                 }
+                MERGE_POPUP_ID => {
+                    let parents = std::mem::take(&mut self.merge_parents);
+                    let description = render_merge_template(self.config.merge_template(), &parents);
+                    let commit_ids = parents
+                        .iter()
+                        .map(|revision| revision.commit_id.as_str())
+                        .collect::<Vec<_>>();
+                    // Rolled back if describing fails or is cancelled
+                    let action = commander.begin_action("Create merge change")?;
+                    commander
+                        .run_new_merge(&commit_ids, &description)
+                        .map_err(|err| commander.fail_action(action.clone(), err))?;
+                    self.log_panel.clear_marks();
+                    self.set_head(commander, commander.get_current_head()?);
+                    // Open the generated description in the describe editor to refine it
+                    self.open_describe_editor(description);
+                    self.describe_action = Some(action);
+                    self.describe_merge = true;
+                    return Ok(Some(ComponentAction::ChangeHead(self.head.clone())));
+                }
                 SQUASH_POPUP_ID => {
                     commander
                         .run_squash(self.head.commit_id.as_str(), self.squash_ignore_immutable)?;
-                    self.set_head(commander, commander.get_current_head()?);
+                    self.set_head(commander, commander.get_head_latest(&self.head)?);
                     return Ok(Some(ComponentAction::ChangeHead(self.head.clone())));
                 }
                 _ => {}
             }
         }
 
+        if let Some(result) = self
+            .describe_generator_rx
+            .as_ref()
+            .map(|describe_generator_rx| describe_generator_rx.try_recv())
+            && !matches!(result, Err(std::sync::mpsc::TryRecvError::Empty))
+        {
+            self.describe_generator_rx = None;
+            let messages = match result {
+                Ok(Ok(description)) => {
+                    if let Some(describe_textarea) = self.describe_textarea.as_mut() {
+                        describe_textarea.insert_str(description);
+                        // Back to editing, the preview doesn't have the generated description
+                        self.describe_preview = None;
+                    }
+                    None
+                }
+                Ok(Err(err)) => Some(err.into_text("")?),
+                Err(_) => Some("The description generator stopped without a result".into()),
+            };
+            if let Some(messages) = messages {
+                return Ok(Some(ComponentAction::SetPopup(Some(Box::new(
+                    MessagePopup {
+                        title: "Generate description error".into(),
+                        messages,
+                        text_align: None,
+                        ..Default::default()
+                    },
+                )))));
+            }
+        }
+
+        if let Some(earlier_paths_rx) = self.earlier_paths_rx.as_ref() {
+            let mut found = false;
+            let finished = loop {
+                match earlier_paths_rx.try_recv() {
+                    Ok(Ok(earlier_path)) => {
+                        self.log_panel.log_path_renames.push(earlier_path);
+                        found = true;
+                    }
+                    Ok(Err(err)) => warn!("{err}"),
+                    Err(std::sync::mpsc::TryRecvError::Empty) => break false,
+                    Err(std::sync::mpsc::TryRecvError::Disconnected) => break true,
+                }
+            };
+            if finished {
+                self.earlier_paths_rx = None;
+            }
+            if found {
+                self.log_panel.refresh_log_output(commander);
+                self.sync_head_output(commander);
+            }
+        }
+
+        self.log_panel.update(commander)?;
+
+        if let Some((_, stream)) = self.head_stream.as_mut()
+            && stream.poll()
+        {
+            self.head_output = stream.result().map(tabs_to_spaces);
+            self.head_panel.content_changed();
+        }
+
         if let Ok(true) = self.bookmark_set_popup_rx.try_recv() {
             self.log_panel.refresh_log_output(commander);
-            self.refresh_head_output(commander)
+            self.sync_head_output(commander)
         }
 
         Ok(None)
@@ -494,13 +1308,14 @@ impl Component for LogTab<'_> {
         let chunks = Layout::default()
             .direction(self.config.layout().into())
             .constraints([
-                Constraint::Percentage(self.config.layout_percent()),
-                Constraint::Percentage(100 - self.config.layout_percent()),
+                Constraint::Percentage(self.head_panel.layout_percent()),
+                Constraint::Percentage(100 - self.head_panel.layout_percent()),
             ])
             .split(area);
         self.panel_rect = [chunks[0], chunks[1]];
 
         // Draw log
+        self.log_panel.focused = !self.head_panel.is_focused();
         self.log_panel.draw(f, chunks[0])?;
 
         // Draw change details
@@ -509,9 +1324,18 @@ impl Component for LogTab<'_> {
                 Ok(head_output) => head_output.into_text()?.lines,
                 Err(err) => err.into_text("Error getting head details")?.lines,
             };
+            let title = match self.head_stream.as_ref() {
+                Some((_, stream)) if stream.is_running() => format!(
+                    " Details for {} {} diff tool running, Esc to cancel ",
+                    self.head.change_id,
+                    spinner(stream.elapsed())
+                ),
+                _ => format!(" Details for {} ", self.head.change_id),
+            };
             self.head_panel
                 .render_context()
-                .title(format!(" Details for {} ", self.head.change_id))
+                .title(title)
+                .focus_color(self.config.focus_color())
                 .content(head_content)
                 .draw(f, chunks[1])
         }
@@ -533,8 +1357,15 @@ impl Component for LogTab<'_> {
         // Draw describe textarea
         {
             if let Some(describe_textarea) = self.describe_textarea.as_mut() {
+                let title = if self.describe_generator_rx.is_some() {
+                    " Describe (generating...) "
+                } else if self.describe_preview.is_some() {
+                    " Describe (preview) "
+                } else {
+                    " Describe "
+                };
                 let block = Block::bordered()
-                    .title(Span::styled(" Describe ", Style::new().bold().cyan()))
+                    .title(Span::styled(title, Style::new().bold().cyan()))
                     .title_alignment(Alignment::Center)
                     .border_type(BorderType::Rounded)
                     .border_style(Style::default().fg(Color::Green));
@@ -547,9 +1378,18 @@ impl Component for LogTab<'_> {
                     .constraints([Constraint::Fill(1), Constraint::Length(2)])
                     .split(block.inner(area));
 
-                f.render_widget(&*describe_textarea, popup_chunks[0]);
+                let help = if let Some(describe_preview) = self.describe_preview.as_ref() {
+                    f.render_widget(
+                        Paragraph::new(describe_preview.clone()).wrap(Wrap { trim: false }),
+                        popup_chunks[0],
+                    );
+                    "Ctrl+s: confirm | Escape: back to editing"
+                } else {
+                    f.render_widget(&*describe_textarea, popup_chunks[0]);
+                    "Ctrl+s: save | Escape: cancel"
+                };
 
-                let help = Paragraph::new(vec!["Ctrl+s: save | Escape: cancel".into()])
+                let help = Paragraph::new(vec![help.into()])
                     .fg(Color::DarkGray)
                     .alignment(Alignment::Center)
                     .block(
@@ -603,31 +1443,130 @@ impl Component for LogTab<'_> {
             }
         }
 
+        // Draw new popup
+        if let Some(new_popup) = &mut self.new_popup {
+            new_popup.render_widget(f)
+        }
+
         Ok(())
     }
 
+    fn is_busy(&self) -> bool {
+        self.describe_generator_rx.is_some()
+            || self.earlier_paths_rx.is_some()
+            || self.is_streaming()
+            || self.log_panel.is_busy()
+            || self
+                .new_popup
+                .as_ref()
+                .is_some_and(|new_popup| new_popup.is_busy())
+            || (self.diff_format.depends_on_width() && self.head_panel.is_resizing())
+    }
+
+    fn hints(&self) -> Vec<(String, String)> {
+        if self.describe_textarea.is_some() {
+            return self
+                .keybinds
+                .make_describe_hints(self.config.describe_generator().is_some());
+        }
+        if self.log_revset_textarea.is_some() {
+            return self.keybinds.make_editor_hints();
+        }
+        if let Some(rebase_popup) = self.rebase_popup.as_ref() {
+            return rebase_popup.keybinds.make_hints();
+        }
+        if let Some(new_popup) = self.new_popup.as_ref() {
+            return new_popup.keybinds.make_hints();
+        }
+        if self.popup.is_opened() {
+            return self.keybinds.make_confirm_hints();
+        }
+        if self.config.read_only() {
+            return self.keybinds.make_read_only_hints();
+        }
+        self.keybinds.make_hints()
+    }
+
     fn input(&mut self, commander: &mut Commander, event: Event) -> Result<ComponentInputResult> {
         if let Some(describe_textarea) = self.describe_textarea.as_mut() {
             if let Event::Key(key) = event {
+                let description = describe_textarea.lines().join("\n");
                 match self.keybinds.match_event(key) {
+                    // Show the changes before saving, unless there are none
+                    LogTabEvent::Save
+                        if self.describe_preview.is_none()
+                            && description.trim_end() != self.describe_original.trim_end() =>
+                    {
+                        self.describe_preview = Some(self.describe_preview_lines(&description));
+                        return Ok(ComponentInputResult::Handled);
+                    }
                     LogTabEvent::Save => {
-                        // TODO: Handle error
-                        commander.run_describe(
-                            self.head.commit_id.as_str(),
-                            &describe_textarea.lines().join("\n"),
-                        )?;
+                        if let Err(err) =
+                            commander.run_describe(self.head.commit_id.as_str(), &description)
+                        {
+                            // Offer to roll back creating the change, as it was for this description
+                            if let Some(action) = self.describe_action.take() {
+                                self.close_describe_editor();
+                                return Err(commander.fail_action(action, err));
+                            }
+                            return Err(err);
+                        }
                         self.set_head(commander, commander.get_head_latest(&self.head)?);
-                        self.describe_textarea = None;
+                        self.close_describe_editor();
+                        return Ok(ComponentInputResult::Handled);
+                    }
+                    LogTabEvent::Cancel if self.describe_preview.is_some() => {
+                        self.describe_preview = None;
                         return Ok(ComponentInputResult::Handled);
                     }
                     LogTabEvent::Cancel => {
-                        self.describe_textarea = None;
+                        let merge_action =
+                            self.describe_action.take().filter(|_| self.describe_merge);
+                        self.close_describe_editor();
+                        if let Some(action) = merge_action {
+                            commander.roll_back_action(&action)?;
+                            self.set_head(commander, commander.get_current_head()?);
+                            return Ok(ComponentInputResult::HandledAction(
+                                ComponentAction::ChangeHead(self.head.clone()),
+                            ));
+                        }
+                        return Ok(ComponentInputResult::Handled);
+                    }
+                    _ if self.describe_preview.is_some() => {
+                        return Ok(ComponentInputResult::Handled);
+                    }
+                    LogTabEvent::GenerateDescription => {
+                        let Some(generator) = self.config.describe_generator() else {
+                            return Ok(ComponentInputResult::HandledAction(
+                                ComponentAction::SetPopup(Some(Box::new(MessagePopup {
+                                    title: "Generate description".into(),
+                                    messages: "Set blazingjj.describe-generator to a command which reads a diff from stdin".into(),
+                                    text_align: None,
+                                    ..Default::default()
+                                }))),
+                            ));
+                        };
+                        if self.describe_generator_rx.is_none() {
+                            let (tx, rx) = std::sync::mpsc::channel();
+                            let generator = generator.to_owned();
+                            let commit_id = self.head.commit_id.clone();
+                            let commander_clone = Commander::new(&commander.env);
+                            std::thread::spawn(move || {
+                                tx.send(
+                                    commander_clone.generate_description(&generator, &commit_id),
+                                )
+                            });
+                            self.describe_generator_rx = Some(rx);
+                        }
                         return Ok(ComponentInputResult::Handled);
                     }
                     _ => (),
                 }
             }
-            describe_textarea.input(event);
+            // The preview shows the description as it was saved, pastes are ignored meanwhile
+            if self.describe_preview.is_none() {
+                textarea_input(describe_textarea, event, true);
+            }
             return Ok(ComponentInputResult::Handled);
         }
 
@@ -642,6 +1581,7 @@ impl Component for LogTab<'_> {
                             Some(log_revset)
                         };
                         self.log_panel.refresh_log_output(commander);
+                        self.sync_head_output(commander);
                         self.log_revset_textarea = None;
                         return Ok(ComponentInputResult::Handled);
                     }
@@ -652,7 +1592,44 @@ impl Component for LogTab<'_> {
                     _ => (),
                 }
             }
-            log_revset_textarea.input(event);
+            textarea_input(log_revset_textarea, event, true);
+            return Ok(ComponentInputResult::Handled);
+        }
+
+        if let Some(new_popup) = &mut self.new_popup {
+            match new_popup.handle_input(commander, event.clone()) {
+                Err(err) => {
+                    self.new_popup = None;
+                    let popup: Box<dyn Component> = match RollbackPopup::from_error(err) {
+                        Ok(popup) => popup,
+                        Err(err) => Box::new(MessagePopup {
+                            title: "Error".into(),
+                            messages: err.to_string().into_text()?,
+                            text_align: None,
+                            ..Default::default()
+                        }),
+                    };
+                    return Ok(ComponentInputResult::HandledAction(
+                        ComponentAction::SetPopup(Some(popup)),
+                    ));
+                }
+                Ok(true) => {
+                    let new_popup = self.new_popup.take();
+                    if let Some(new_popup) = new_popup
+                        && let Some(created) = new_popup.created
+                    {
+                        self.set_head(commander, created);
+                        if new_popup.describe {
+                            self.open_describe_editor(String::new());
+                            self.describe_action = new_popup.action;
+                        }
+                        return Ok(ComponentInputResult::HandledAction(
+                            ComponentAction::ChangeHead(self.head.clone()),
+                        ));
+                    }
+                }
+                Ok(false) => {}
+            }
             return Ok(ComponentInputResult::Handled);
         }
 
@@ -668,6 +1645,7 @@ impl Component for LogTab<'_> {
                         title: "Error".into(),
                         messages: error_message,
                         text_align: None,
+                        ..Default::default()
                     }))),
                 ));
             }
@@ -718,6 +1696,14 @@ impl Component for LogTab<'_> {
         if let Event::Mouse(mouse_event) = event {
             let input_result = self.log_panel.input(commander, event.clone())?;
             if input_result.is_handled() {
+                // Clicking a change is a jump, scrolling is not
+                if !matches!(
+                    mouse_event.kind,
+                    MouseEventKind::ScrollUp | MouseEventKind::ScrollDown
+                ) && self.log_panel.head.change_id != self.head.change_id
+                {
+                    self.jumplist.push(self.head.clone());
+                }
                 self.sync_head_output(commander);
                 return Ok(input_result);
             }
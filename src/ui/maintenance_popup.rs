@@ -0,0 +1,266 @@
+//! Popup with maintenance actions trimming the operation log, collecting garbage and syncing
+//! with the colocated git repository, along with the size of the operation log and the stores.
+//! The sizes are queried in a background thread, as a long operation log takes a while to list.
+
+use std::{
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+    time::Instant,
+};
+
+use anyhow::Result;
+use ratatui::{
+    Frame,
+    crossterm::event::{Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{Clear, List, ListState, Paragraph},
+};
+
+use crate::{
+    ComponentInputResult,
+    commander::{CommandError, Commander, operations::MaintenanceInfo},
+    ui::{
+        Component, ComponentAction,
+        loader_popup::LoaderPopup,
+        read_only_popup,
+        styles::create_popup_block,
+        utils::{centered_rect, human_size, spinner},
+    },
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum MaintenanceAction {
+    /// Abandon all but this many of the newest operations
+    KeepOperations(usize),
+    CollectGarbage,
+    GitImport,
+    GitExport,
+}
+
+const ACTIONS: [MaintenanceAction; 5] = [
+    MaintenanceAction::KeepOperations(1000),
+    MaintenanceAction::KeepOperations(100),
+    MaintenanceAction::CollectGarbage,
+    MaintenanceAction::GitImport,
+    MaintenanceAction::GitExport,
+];
+
+impl MaintenanceAction {
+    fn description(&self) -> String {
+        match self {
+            MaintenanceAction::KeepOperations(keep) => {
+                format!("Abandon all but the latest {keep} operations (jj op abandon)")
+            }
+            MaintenanceAction::CollectGarbage => {
+                "Remove objects of abandoned operations (jj util gc --expire=now)".to_owned()
+            }
+            MaintenanceAction::GitImport => {
+                "Import refs changed by git tools into jj (jj git import)".to_owned()
+            }
+            MaintenanceAction::GitExport => {
+                "Export bookmarks to the git repository (jj git export)".to_owned()
+            }
+        }
+    }
+
+    /// Whether the action can't be undone with `jj undo` and has to be confirmed
+    fn needs_confirmation(&self) -> bool {
+        matches!(
+            self,
+            MaintenanceAction::KeepOperations(_) | MaintenanceAction::CollectGarbage
+        )
+    }
+
+    fn loader(self, commander: &Commander) -> LoaderPopup {
+        let commander = Commander::new(&commander.env);
+        match self {
+            MaintenanceAction::KeepOperations(keep) => {
+                LoaderPopup::new("Abandoning operations".to_owned(), move || {
+                    commander.abandon_old_operations(keep)
+                })
+            }
+            MaintenanceAction::CollectGarbage => {
+                LoaderPopup::new("Collecting garbage".to_owned(), move || commander.run_gc())
+            }
+            MaintenanceAction::GitImport => {
+                LoaderPopup::new("Importing git refs".to_owned(), move || {
+                    commander.git_import()
+                })
+            }
+            MaintenanceAction::GitExport => {
+                LoaderPopup::new("Exporting to git".to_owned(), move || {
+                    commander.git_export()
+                })
+            }
+        }
+    }
+}
+
+pub struct MaintenancePopup {
+    /// None while the info is being queried
+    info: Option<Result<MaintenanceInfo, CommandError>>,
+    info_rx: Option<Receiver<Result<MaintenanceInfo, CommandError>>>,
+    started: Instant,
+    list_state: ListState,
+    /// Whether the selected action waits for confirmation
+    confirming: bool,
+}
+
+impl MaintenancePopup {
+    pub fn new(commander: &Commander) -> Self {
+        let (tx, rx) = mpsc::channel();
+        let commander = Commander::new(&commander.env);
+        thread::spawn(move || tx.send(commander.get_maintenance_info()));
+        Self {
+            info: None,
+            info_rx: Some(rx),
+            started: Instant::now(),
+            list_state: ListState::default().with_selected(Some(0)),
+            confirming: false,
+        }
+    }
+
+    fn selected_action(&self) -> Option<MaintenanceAction> {
+        self.list_state
+            .selected()
+            .and_then(|selected| ACTIONS.get(selected).copied())
+    }
+
+    fn info_lines(&self) -> Vec<Line<'static>> {
+        let info = match self.info.as_ref() {
+            None => {
+                return vec![Line::from(format!(
+                    "{} Getting the size of the operation log",
+                    spinner(self.started.elapsed())
+                ))];
+            }
+            Some(Ok(info)) => info,
+            Some(Err(err)) => {
+                return vec![Line::from(format!("Error getting operations: {err}"))];
+            }
+        };
+        let size = |size: Option<u64>| size.map_or("unknown".to_owned(), human_size);
+        let mut lines = vec![Line::from(format!("Operations: {}", info.operations.len()))];
+        if let (Some(newest), Some(oldest)) = (info.operations.first(), info.operations.last()) {
+            lines.push(Line::from(format!(
+                "Oldest operation: {}, newest: {}",
+                oldest.age, newest.age
+            )));
+        }
+        lines.push(Line::from(format!(
+            "Operation store: {}, object store: {}",
+            size(info.op_store_size),
+            size(info.store_size)
+        )));
+        lines
+    }
+}
+
+impl Component for MaintenancePopup {
+    fn update(&mut self, _commander: &mut Commander) -> Result<Option<ComponentAction>> {
+        let Some(rx) = self.info_rx.as_ref() else {
+            return Ok(None);
+        };
+        match rx.try_recv() {
+            Ok(info) => self.info = Some(info),
+            Err(TryRecvError::Empty) => return Ok(None),
+            Err(TryRecvError::Disconnected) => {
+                self.info = Some(Err(CommandError::Status(
+                    "the maintenance info thread stopped".to_owned(),
+                    None,
+                )))
+            }
+        }
+        self.info_rx = None;
+        Ok(None)
+    }
+
+    fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+        let area = centered_rect(area, 60, 40);
+        f.render_widget(Clear, area);
+
+        let block = create_popup_block("Maintenance");
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let info_lines = self.info_lines();
+        let [info_area, list_area, help_area] = Layout::vertical([
+            Constraint::Length(info_lines.len() as u16 + 1),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+        ])
+        .areas(inner);
+
+        f.render_widget(Paragraph::new(info_lines), info_area);
+
+        let list = List::new(ACTIONS.iter().map(MaintenanceAction::description))
+            .highlight_style(Style::default().reversed());
+        f.render_stateful_widget(list, list_area, &mut self.list_state);
+
+        let help = if self.confirming {
+            Line::from("This can't be undone. Press y to confirm, n to cancel").fg(Color::Yellow)
+        } else {
+            Line::from("Enter: run | Esc: close").fg(Color::DarkGray)
+        };
+        f.render_widget(Paragraph::new(help).centered(), help_area);
+
+        Ok(())
+    }
+
+    fn input(&mut self, commander: &mut Commander, event: Event) -> Result<ComponentInputResult> {
+        let Event::Key(key) = event else {
+            return Ok(ComponentInputResult::Handled);
+        };
+        if key.kind != KeyEventKind::Press {
+            return Ok(ComponentInputResult::Handled);
+        }
+
+        if self.confirming {
+            self.confirming = false;
+            if let (KeyCode::Char('y'), Some(action)) = (key.code, self.selected_action()) {
+                return Ok(ComponentInputResult::HandledAction(
+                    ComponentAction::SetPopup(Some(Box::new(action.loader(commander)))),
+                ));
+            }
+            return Ok(ComponentInputResult::Handled);
+        }
+
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => self.list_state.select_next(),
+            KeyCode::Char('k') | KeyCode::Up => self.list_state.select_previous(),
+            KeyCode::Enter if commander.env.config.read_only() => {
+                return Ok(ComponentInputResult::HandledAction(read_only_popup()));
+            }
+            KeyCode::Enter => match self.selected_action() {
+                Some(action) if !action.needs_confirmation() => {
+                    return Ok(ComponentInputResult::HandledAction(
+                        ComponentAction::SetPopup(Some(Box::new(action.loader(commander)))),
+                    ));
+                }
+                _ => self.confirming = true,
+            },
+            _ => return Ok(ComponentInputResult::NotHandled),
+        }
+        Ok(ComponentInputResult::Handled)
+    }
+
+    fn is_busy(&self) -> bool {
+        self.info_rx.is_some()
+    }
+
+    fn hints(&self) -> Vec<(String, String)> {
+        if self.confirming {
+            return vec![
+                ("y".to_owned(), "confirm".to_owned()),
+                ("n".to_owned(), "cancel".to_owned()),
+            ];
+        }
+        vec![
+            ("j/k".to_owned(), "select".to_owned()),
+            ("Enter".to_owned(), "run".to_owned()),
+            ("Esc".to_owned(), "close".to_owned()),
+        ]
+    }
+}
@@ -1,20 +1,139 @@
 use anyhow::Result;
 use ratatui::{
     Frame,
-    crossterm::event::Event,
-    layout::{Alignment, Rect},
+    crossterm::event::{Event, KeyCode, KeyEventKind, MouseEventKind},
+    layout::{Alignment, Margin, Rect},
     style::{Color, Style, Stylize},
-    text::{Span, Text},
-    widgets::{BorderType, Borders, block::Title},
+    text::{Line, Span, Text},
+    widgets::{
+        Block, BorderType, Clear, Padding, Paragraph, Scrollbar, ScrollbarOrientation,
+        ScrollbarState, Wrap, block::Title,
+    },
 };
-use tui_confirm_dialog::PopupMessage;
+use regex::Regex;
 
-use crate::{ComponentInputResult, commander::Commander, ui::Component};
+use crate::{
+    ComponentInputResult,
+    commander::Commander,
+    ui::{Component, utils::centered_rect_fixed},
+};
+
+/// Maximum size of the popup relative to the terminal
+const MAX_SIZE_PERCENT: u16 = 80;
 
+#[derive(Default)]
 pub struct MessagePopup<'a> {
     pub title: Title<'a>,
     pub messages: Text<'a>,
     pub text_align: Option<Alignment>,
+    pub state: MessagePopupState,
+}
+
+/// Scroll position and search of a [MessagePopup]
+#[derive(Debug, Default)]
+pub struct MessagePopupState {
+    scroll: u16,
+    /// Number of wrapped lines and visible lines, from the last draw. Long outputs can have more
+    /// wrapped lines than fit in a u16, they can only be scrolled to the first u16::MAX lines.
+    line_count: usize,
+    height: u16,
+    /// Wrapped line where each message line starts, from the last draw
+    line_offsets: Vec<usize>,
+    search: String,
+    /// Whether the search is being typed
+    searching: bool,
+    /// Result of saving the message to a file
+    status: Option<String>,
+}
+
+impl MessagePopupState {
+    /// Whether the search is being typed, so keys go to the search instead of the popup
+    pub fn is_searching(&self) -> bool {
+        self.searching
+    }
+
+    fn scroll(&mut self, lines: isize) {
+        let max_scroll = self
+            .line_count
+            .saturating_sub(self.height as usize)
+            .min(u16::MAX as usize);
+        self.scroll = (self.scroll as isize + lines).clamp(0, max_scroll as isize) as u16;
+    }
+}
+
+/// Highlight the matches of `search` in the line
+fn highlight_matches<'a>(line: &Line<'a>, search: &Regex) -> Line<'a> {
+    let mut spans = vec![];
+    for span in &line.spans {
+        let mut start = 0;
+        for found in search.find_iter(&span.content) {
+            spans.push(Span::styled(
+                span.content[start..found.start()].to_owned(),
+                span.style,
+            ));
+            spans.push(Span::styled(found.as_str().to_owned(), span.style).reversed());
+            start = found.end();
+        }
+        spans.push(Span::styled(span.content[start..].to_owned(), span.style));
+    }
+    let mut highlighted = Line::from(spans).style(line.style);
+    highlighted.alignment = line.alignment;
+    highlighted
+}
+
+impl MessagePopup<'_> {
+    fn search_regex(&self) -> Option<Regex> {
+        if self.state.search.is_empty() {
+            return None;
+        }
+        Regex::new(&format!("(?i){}", regex::escape(&self.state.search))).ok()
+    }
+
+    /// Scroll to the next (or previous) line matching the search
+    fn jump_to_match(&mut self, forward: bool, include_current: bool) {
+        let Some(search) = self.search_regex() else {
+            return;
+        };
+        let current = self.state.scroll as usize;
+        let matches = self
+            .messages
+            .lines
+            .iter()
+            .zip(self.state.line_offsets.iter().copied())
+            .filter(|(line, _)| search.is_match(&line.to_string()))
+            .map(|(_, offset)| offset);
+        let next = if forward {
+            matches
+                .filter(|offset| *offset > current || (include_current && *offset == current))
+                .min()
+        } else {
+            matches.filter(|offset| *offset < current).max()
+        };
+        match next {
+            Some(offset) => {
+                self.state.scroll = 0;
+                self.state.scroll(offset as isize);
+                self.state.status = None;
+            }
+            None => self.state.status = Some(format!("No more matches for {}", self.state.search)),
+        }
+    }
+
+    /// Write the message as plain text to the temp directory
+    fn save(&self) -> Result<String> {
+        let path = std::env::temp_dir().join(format!(
+            "blazingjj-popup-{}.txt",
+            chrono::Local::now().format("%Y%m%d-%H%M%S")
+        ));
+        let content: String = self
+            .messages
+            .lines
+            .iter()
+            .map(|line| line.to_string() + "\n")
+            .collect();
+        std::fs::write(&path, content)?;
+        Ok(path.display().to_string())
+    }
 }
 
 impl Component for MessagePopup<'_> {
@@ -35,20 +154,169 @@ impl Component for MessagePopup<'_> {
             None => Alignment::Center,
         };
 
-        // TODO: Support scrolling long messages
-        let popup = PopupMessage::new(title, self.messages.clone())
-            .title_alignment(Alignment::Center)
-            .text_alignment(text_align)
-            .borders(Borders::ALL)
-            .border_type(BorderType::Rounded)
-            .border_style(Style::default().fg(Color::Green));
+        // Fit the message, up to a maximum size. Borders and padding take 4 columns
+        let max_width = area.width * MAX_SIZE_PERCENT / 100;
+        let width =
+            (self.messages.width().max(title.content.width()) + 4).min(max_width as usize) as u16;
+        let width = width.max(20.min(area.width));
+        let inner_width = width.saturating_sub(4).max(1) as usize;
+
+        // Counted like the paragraph wraps them, which breaks lines at word boundaries
+        self.state.line_offsets.clear();
+        let mut line_count = 0;
+        for line in &self.messages.lines {
+            self.state.line_offsets.push(line_count);
+            line_count += Paragraph::new(line.clone())
+                .wrap(Wrap { trim: false })
+                .line_count(inner_width as u16);
+        }
+        self.state.line_count = line_count;
+
+        let height = (line_count + 2).min((area.height * MAX_SIZE_PERCENT / 100) as usize) as u16;
+        self.state.height = height.saturating_sub(2);
+        self.state.scroll(0);
+
+        let bottom_title = if self.state.searching {
+            format!(" /{}_ ", self.state.search)
+        } else if let Some(status) = self.state.status.as_ref() {
+            format!(" {status} ")
+        } else if line_count > self.state.height as usize {
+            " j/k: scroll | /: search | s: save ".to_owned()
+        } else {
+            String::new()
+        };
+
+        let text = match self.search_regex() {
+            Some(search) => Text::from(
+                self.messages
+                    .lines
+                    .iter()
+                    .map(|line| highlight_matches(line, &search))
+                    .collect::<Vec<Line>>(),
+            ),
+            None => self.messages.clone(),
+        };
+
+        let popup_area = centered_rect_fixed(area, width, height);
+        let popup = Paragraph::new(text)
+            .alignment(text_align)
+            .wrap(Wrap { trim: false })
+            .scroll((self.state.scroll, 0))
+            .block(
+                Block::bordered()
+                    .title(title)
+                    .title_alignment(Alignment::Center)
+                    .title_bottom(Line::from(bottom_title).right_aligned())
+                    .border_type(BorderType::Rounded)
+                    .border_style(Style::default().fg(Color::Green))
+                    .padding(Padding::horizontal(1)),
+            );
 
-        f.render_widget(popup, area);
+        f.render_widget(Clear, popup_area);
+        f.render_widget(popup, popup_area);
+
+        if line_count > self.state.height as usize {
+            let mut scrollbar_state = ScrollbarState::default()
+                .content_length(line_count - self.state.height as usize)
+                .position(self.state.scroll as usize);
+            f.render_stateful_widget(
+                Scrollbar::new(ScrollbarOrientation::VerticalRight),
+                popup_area.inner(Margin {
+                    vertical: 1,
+                    horizontal: 0,
+                }),
+                &mut scrollbar_state,
+            );
+        }
 
         Ok(())
     }
 
-    fn input(&mut self, _commander: &mut Commander, _event: Event) -> Result<ComponentInputResult> {
-        Ok(ComponentInputResult::NotHandled)
+    fn input(&mut self, _commander: &mut Commander, event: Event) -> Result<ComponentInputResult> {
+        if let Event::Mouse(mouse) = event {
+            match mouse.kind {
+                MouseEventKind::ScrollDown => self.state.scroll(3),
+                MouseEventKind::ScrollUp => self.state.scroll(-3),
+                _ => return Ok(ComponentInputResult::NotHandled),
+            }
+            return Ok(ComponentInputResult::Handled);
+        }
+
+        if let Event::Paste(text) = &event
+            && self.state.searching
+        {
+            self.state.search.push_str(&text.replace(['\r', '\n'], " "));
+            return Ok(ComponentInputResult::Handled);
+        }
+
+        let Event::Key(key) = event else {
+            return Ok(ComponentInputResult::NotHandled);
+        };
+        if key.kind != KeyEventKind::Press {
+            return Ok(ComponentInputResult::NotHandled);
+        }
+
+        if self.state.searching {
+            match key.code {
+                KeyCode::Char(c) => self.state.search.push(c),
+                KeyCode::Backspace => {
+                    self.state.search.pop();
+                }
+                KeyCode::Enter => {
+                    self.state.searching = false;
+                    self.jump_to_match(true, true);
+                }
+                KeyCode::Esc => {
+                    self.state.searching = false;
+                    self.state.search.clear();
+                }
+                _ => {}
+            }
+            return Ok(ComponentInputResult::Handled);
+        }
+
+        let half_page = (self.state.height / 2).max(1) as isize;
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => self.state.scroll(1),
+            KeyCode::Char('k') | KeyCode::Up => self.state.scroll(-1),
+            KeyCode::Char('J') | KeyCode::PageDown => self.state.scroll(half_page),
+            KeyCode::Char('K') | KeyCode::PageUp => self.state.scroll(-half_page),
+            KeyCode::Char('g') | KeyCode::Home => self.state.scroll = 0,
+            KeyCode::Char('G') | KeyCode::End => self.state.scroll = u16::MAX,
+            KeyCode::Char('/') => {
+                self.state.searching = true;
+                self.state.search.clear();
+                self.state.status = None;
+            }
+            // Without a search, n still closes the popup
+            KeyCode::Char('n') if !self.state.search.is_empty() => {
+                self.jump_to_match(true, false);
+            }
+            KeyCode::Char('N') => self.jump_to_match(false, false),
+            KeyCode::Char('s') => {
+                self.state.status = Some(match self.save() {
+                    Ok(path) => format!("Saved to {path}"),
+                    Err(err) => format!("Failed saving: {err}"),
+                });
+            }
+            _ => return Ok(ComponentInputResult::NotHandled),
+        }
+        Ok(ComponentInputResult::Handled)
+    }
+
+    fn hints(&self) -> Vec<(String, String)> {
+        if self.state.searching {
+            return vec![
+                ("Enter".to_owned(), "search".to_owned()),
+                ("Esc".to_owned(), "cancel".to_owned()),
+            ];
+        }
+        vec![
+            ("j/k".to_owned(), "scroll".to_owned()),
+            ("/".to_owned(), "search".to_owned()),
+            ("n/N".to_owned(), "next/previous match".to_owned()),
+            ("s".to_owned(), "save to file".to_owned()),
+            ("Enter/Esc".to_owned(), "close".to_owned()),
+        ]
     }
 }
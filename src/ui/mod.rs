@@ -1,20 +1,56 @@
+pub mod amend_popup;
 pub mod bookmark_set_popup;
+pub mod bookmark_stack_popup;
 pub mod bookmarks_tab;
+pub mod changed_files_popup;
+pub mod command_log;
 pub mod command_popup;
+pub mod conflicts_popup;
+pub mod credentials_popup;
+pub mod delete_bookmark_popup;
+pub mod describe_bookmarks_popup;
+pub mod extract_popup;
 pub mod files_tab;
 pub mod help_popup;
+pub mod jumplist;
 pub mod loader_popup;
 pub mod log_tab;
+pub mod maintenance_popup;
 pub mod message_popup;
+pub mod new_popup;
+pub mod operations_popup;
 pub mod panel;
+pub mod push_preview_popup;
 pub mod rebase_popup;
+pub mod recovery_popup;
+pub mod remote_error_popup;
+pub mod remote_settings_popup;
+pub mod reorder_popup;
+pub mod repository_popup;
+pub mod resolve_popup;
+pub mod rollback_popup;
+pub mod settings_popup;
+pub mod snapshot_popup;
+pub mod stats_popup;
 pub mod styles;
+pub mod template_popup;
+pub mod todos_popup;
 pub mod utils;
 
 use crate::{
     ComponentInputResult,
+    anonymize::anonymize_buffer,
     app::{App, Tab},
-    commander::{Commander, log::Head},
+    commander::{
+        CommandError, Commander,
+        log::{Head, WorkingCopySummary},
+        warnings::JjWarning,
+    },
+    env::Favorite,
+    ui::{
+        command_log::{COMMAND_LOG_STRIP_COMMANDS, draw_command_log_strip},
+        message_popup::MessagePopup,
+    },
 };
 use anyhow::Result;
 use ratatui::{
@@ -25,15 +61,51 @@ use ratatui::{
     symbols,
 };
 use ratatui::{prelude::*, widgets::*};
+use std::path::Path;
 use tracing::instrument;
 
 pub enum ComponentAction {
     ViewFiles(Head),
+    /// Review the changes of the revset one by one in the files tab
+    ReviewRange(String),
+    /// Select a file of a change in the files tab, scrolling its diff to the line of the new
+    /// version of the file, or else to the first line containing the text
+    ViewFileLine {
+        head: Head,
+        path: String,
+        line: Option<usize>,
+        text: String,
+    },
     ViewLog(Head),
+    /// Show the log filtered to changes touching a file or directory
+    ViewLogForPath(String),
+    /// Select the bookmark in the bookmarks tab, a remote bookmark if the remote is set
+    ViewBookmark {
+        name: String,
+        remote: Option<String>,
+    },
     ChangeHead(Head),
     SetPopup(Option<Box<dyn Component>>),
     Multiple(Vec<ComponentAction>),
     RefreshTab(),
+    /// Switch to the jj repository at this path
+    SwitchRepository(String),
+    /// Suspend the TUI and run the jj command with these arguments attached to the terminal
+    RunInTerminal(Vec<String>),
+    /// Suspend the TUI and show the text in the configured pager
+    OpenInPager(String),
+}
+
+/// Popup explaining that an action is disabled in read-only mode
+pub fn read_only_popup() -> ComponentAction {
+    ComponentAction::SetPopup(Some(Box::new(MessagePopup {
+        title: "Read-only mode".into(),
+        messages: CommandError::ReadOnly("This action".to_owned())
+            .to_string()
+            .into(),
+        text_align: None,
+        ..Default::default()
+    })))
 }
 
 pub trait Component {
@@ -49,13 +121,128 @@ pub trait Component {
     fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()>;
 
     fn input(&mut self, commander: &mut Commander, event: Event) -> Result<ComponentInputResult>;
+
+    /// Whether background work is pending, so `update` must be polled without waiting for input
+    fn is_busy(&self) -> bool {
+        false
+    }
+
+    /// Most relevant keybinds for the current state, shown in the hint bar
+    fn hints(&self) -> Vec<(String, String)> {
+        vec![]
+    }
+}
+
+/// Last path component of the repository root, shown in the header
+fn repository_name(root: &str) -> &str {
+    Path::new(root)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(root)
+}
+
+/// Single line listing `keys: description` pairs
+fn hint_bar(hints: &[(String, String)]) -> Paragraph<'_> {
+    let mut spans = vec![Span::raw(" ")];
+    for (i, (keys, description)) in hints.iter().enumerate() {
+        if i > 0 {
+            spans.push(Span::raw(" | ").fg(Color::DarkGray));
+        }
+        spans.push(Span::raw(keys.as_str()).bold());
+        spans.push(Span::raw(format!(": {description}")).fg(Color::DarkGray));
+    }
+    Paragraph::new(Line::from(spans))
+}
+
+/// Number of warning banners shown at once, the others follow once these are dismissed
+const MAX_WARNING_BANNERS: usize = 3;
+
+/// Banners with the oldest warnings of jj, one line each
+fn warning_banners(warnings: &[JjWarning]) -> Paragraph<'_> {
+    let mut lines: Vec<Line> = warnings
+        .iter()
+        .take(MAX_WARNING_BANNERS)
+        .map(|warning| {
+            let message = warning.message.replace('\n', " ");
+            Line::from(vec![
+                Span::raw(format!(" ⚠ {}: ", warning.kind.label())).bold(),
+                Span::raw(message),
+            ])
+        })
+        .collect();
+    let more = warnings.len().saturating_sub(MAX_WARNING_BANNERS);
+    if let Some(first) = lines.first_mut() {
+        let dismiss = if more > 0 {
+            format!(" Ctrl+x: dismiss ({more} more) ")
+        } else {
+            " Ctrl+x: dismiss ".to_owned()
+        };
+        first.spans.push(Span::raw(" |").fg(Color::DarkGray));
+        first.spans.push(Span::raw(dismiss).fg(Color::DarkGray));
+    }
+    Paragraph::new(lines).style(Style::default().fg(Color::Black).bg(Color::Yellow))
+}
+
+/// The favorite actions with the keys running them, e.g. `Alt+1 rebase onto trunk`
+fn favorites_bar(favorites: &[Favorite]) -> Paragraph<'_> {
+    let mut spans = vec![];
+    for (i, favorite) in favorites.iter().enumerate() {
+        if i > 0 {
+            spans.push(Span::raw(" | ").fg(Color::DarkGray));
+        }
+        spans.push(Span::raw(format!(" Alt+{} ", i + 1)).bold().cyan());
+        spans.push(Span::raw(favorite.label.as_str()));
+    }
+    Paragraph::new(Line::from(spans))
+}
+
+/// Compact summary of the working copy, e.g. `@ kkmpptxz · 3 files · no description`
+fn working_copy_line(summary: &WorkingCopySummary) -> Line<'static> {
+    let mut spans = vec![
+        Span::raw(" @ "),
+        Span::raw(summary.change_id.clone()).magenta(),
+    ];
+    let separator = || Span::raw(" · ").fg(Color::DarkGray);
+    spans.push(separator());
+    spans.push(if summary.empty {
+        Span::raw("empty").fg(Color::Green)
+    } else if summary.files == 1 {
+        Span::raw("1 file").fg(Color::Yellow)
+    } else {
+        Span::raw(format!("{} files", summary.files)).fg(Color::Yellow)
+    });
+    spans.push(separator());
+    spans.push(if summary.described {
+        Span::raw("described").fg(Color::Green)
+    } else {
+        Span::raw("no description").fg(Color::Yellow)
+    });
+    if summary.conflict {
+        spans.push(separator());
+        spans.push(Span::raw("conflict").fg(Color::Red).bold());
+    }
+    spans.push(Span::raw(" "));
+    Line::from(spans).right_aligned()
 }
 
 #[instrument(level = "trace", name = "draw", skip(f, app))]
 pub fn ui(f: &mut Frame, app: &mut App) -> Result<()> {
+    let show_hint_bar = app.env.config.hint_bar();
+    let favorites = app.env.config.favorites();
     let chunks = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Length(3), Constraint::Min(1)])
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Length(if favorites.is_empty() { 0 } else { 1 }),
+            Constraint::Length(app.warnings.len().min(MAX_WARNING_BANNERS) as u16),
+            Constraint::Min(1),
+            Constraint::Length(if app.show_command_log {
+                COMMAND_LOG_STRIP_COMMANDS + 2
+            } else {
+                0
+            }),
+            Constraint::Length(if show_hint_bar { 1 } else { 0 }),
+        ])
         .split(f.area());
 
     let header_chunks = Layout::default()
@@ -87,26 +274,75 @@ pub fn ui(f: &mut Frame, app: &mut App) -> Result<()> {
         f.render_widget(tabs, header_chunks[0]);
     }
     {
+        let mut block = Block::bordered()
+            .title(format!(
+                " blazingjj: {}{}{}{} ",
+                repository_name(&app.env.root),
+                app.env
+                    .workspace
+                    .as_ref()
+                    .map(|workspace| format!(" (workspace {workspace})"))
+                    .unwrap_or_default(),
+                if app.env.config.read_only() {
+                    " [read-only]"
+                } else {
+                    ""
+                },
+                if app.mouse_capture {
+                    ""
+                } else {
+                    " [mouse off]"
+                }
+            ))
+            .border_type(BorderType::Rounded)
+            .fg(Color::default());
+        if let Some(working_copy) = app.working_copy.as_ref() {
+            block = block.title_bottom(working_copy_line(working_copy));
+        }
         let tabs = Paragraph::new("q: quit | ?: help | R: refresh | 1/2/3: change tab")
             .fg(Color::DarkGray)
-            .block(
-                Block::bordered()
-                    .title(" blazingjj ")
-                    .border_type(BorderType::Rounded)
-                    .fg(Color::default()),
-            );
+            .block(block);
 
         f.render_widget(tabs, header_chunks[1]);
     }
 
+    if !favorites.is_empty() {
+        f.render_widget(favorites_bar(favorites), chunks[1]);
+    }
+
+    if !app.warnings.is_empty() {
+        f.render_widget(warning_banners(&app.warnings), chunks[2]);
+    }
+
     if let Some(current_tab) = app.get_current_tab() {
-        current_tab.draw(f, chunks[1])?;
+        current_tab.draw(f, chunks[3])?;
+    }
+
+    if app.show_command_log {
+        draw_command_log_strip(f, chunks[4]);
+    }
+
+    if show_hint_bar {
+        let hints = match app.popup.as_ref() {
+            Some(popup) => popup.hints(),
+            None => app
+                .get_current_tab()
+                .map(|current_tab| current_tab.hints())
+                .unwrap_or_default(),
+        };
+        f.render_widget(hint_bar(&hints), chunks[5]);
     }
 
     if let Some(popup) = app.popup.as_mut() {
         popup.draw(f, f.area())?;
     }
 
+    // Replace the text once everything is drawn, see [crate::anonymize]
+    if let Some(descriptions) = app.anonymized_descriptions() {
+        let area = f.area();
+        anonymize_buffer(f.buffer_mut(), area, &descriptions);
+    }
+
     {
         let paragraph = Paragraph::new(format!("{}ms", app.stats.start_time.elapsed().as_millis()))
             .alignment(Alignment::Right);
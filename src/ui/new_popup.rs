@@ -0,0 +1,302 @@
+/*! The new popup allows the user to pick where to create a new change and
+ whether to edit it, or cancel the operation.
+
+ The UI looks like this
+ ~~~
+    Revision (zsztoxlv)
+    (*) c  new child
+    ( ) -A insert after
+    ( ) -B insert before
+    [x] e  edit the new change
+    [x] a  advance bookmarks
+        moves main
+
+    Esc: Cancel    Enter: New
+~~~
+It has keyboard shortcuts c, shift+a, shift+b for selecting a radiobutton,
+e for toggling editing, and shortcuts Enter, Esc, q for closing the popup.
+
+The advance checkbox shows whether `experimental-advance-branches` is enabled,
+and the line below it which bookmarks of the parents `jj new` moves to the
+revision. a toggles the option in the repo config. The options are read and
+toggled in a background thread, as they take a few jj commands.
+*/
+
+use std::{
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+    time::Instant,
+};
+
+use anyhow::Result;
+use ratatui::{
+    Frame,
+    crossterm::event::Event,
+    layout::{Alignment, Rect},
+    prelude::{Constraint, Direction, Layout},
+    style::{Color, Style, Stylize},
+    text::{Line, Span, Text},
+    widgets::{Block, BorderType, Clear, Paragraph},
+};
+
+use crate::{
+    ComponentInputResult,
+    commander::{
+        CommandError, Commander, bookmarks::BookmarkAdvance, log::Head, operations::CompositeAction,
+    },
+    keybinds::new_popup::{NewPlacement, PopupAction},
+    ui::{
+        Component,
+        rebase_popup::RadioButton,
+        utils::{centered_rect_fixed, spinner},
+    },
+};
+
+type Keybinds = crate::keybinds::new_popup::Keybinds;
+
+/// A transient popup for configuring a `jj new` command
+pub struct NewPopup {
+    pub keybinds: Keybinds,
+
+    pub revision: Head,
+    pub placement: NewPlacement,
+    /// Move `@` to the new change, `--no-edit` otherwise
+    pub edit: bool,
+    /// Open the describe editor for the new change once created
+    pub describe: bool,
+    /// Bookmarks `jj new` advances to the revision, None while they are being read
+    advance: Option<Result<BookmarkAdvance, CommandError>>,
+    advance_rx: Option<Receiver<Result<BookmarkAdvance, CommandError>>>,
+    advance_started: Instant,
+
+    /// The change created when the popup was confirmed
+    pub created: Option<Head>,
+    /// Creating and describing the change, rolled back together if describing fails
+    pub action: Option<CompositeAction>,
+}
+
+impl NewPopup {
+    pub fn new(commander: &Commander, revision: Head, describe: bool) -> Self {
+        let mut popup = Self {
+            keybinds: Keybinds::default(),
+            revision,
+            placement: NewPlacement::Child,
+            edit: true,
+            describe,
+            advance: None,
+            advance_rx: None,
+            advance_started: Instant::now(),
+            created: None,
+            action: None,
+        };
+        popup.load_advance(commander, false);
+        popup
+    }
+
+    /// Read the bookmarks `jj new` advances in the background, after toggling advancing with
+    /// `toggle`
+    fn load_advance(&mut self, commander: &Commander, toggle: bool) {
+        let (tx, rx) = mpsc::channel();
+        let commander = Commander::new(&commander.env);
+        let commit_id = self.revision.commit_id.clone();
+        thread::spawn(move || {
+            let advance = if toggle {
+                commander.toggle_bookmark_advance().map(|_| ())
+            } else {
+                Ok(())
+            }
+            .and_then(|()| commander.get_bookmark_advance(&commit_id));
+            let _ = tx.send(advance);
+        });
+        self.advance = None;
+        self.advance_rx = Some(rx);
+        self.advance_started = Instant::now();
+    }
+
+    /// Poll the background thread of [Self::load_advance]
+    pub fn update(&mut self) {
+        let Some(rx) = self.advance_rx.as_ref() else {
+            return;
+        };
+        match rx.try_recv() {
+            Ok(advance) => {
+                self.advance = Some(advance);
+                self.advance_rx = None;
+            }
+            Err(TryRecvError::Empty) => {}
+            Err(TryRecvError::Disconnected) => {
+                self.advance = Some(Err(CommandError::Status(
+                    "The advance thread stopped".to_owned(),
+                    None,
+                )));
+                self.advance_rx = None;
+            }
+        }
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.advance_rx.is_some()
+    }
+
+    pub fn render_widget(&mut self, frame: &mut Frame) {
+        let area = centered_rect_fixed(frame.area(), 40, 12);
+        self.draw(frame, area)
+            .expect("Expected drawing without failues");
+    }
+
+    fn match_event(&self, event: Event) -> PopupAction {
+        if let Event::Key(key) = event {
+            return self.keybinds.match_event(key);
+        }
+        PopupAction::None
+    }
+
+    /// Whether advancing is enabled and the line describing its effect
+    fn advance_lines(&self) -> (bool, Line<'static>) {
+        let advance = match self.advance.as_ref() {
+            None => {
+                let line = format!(
+                    "    {} reading the config",
+                    spinner(self.advance_started.elapsed())
+                );
+                return (false, Line::raw(line).fg(Color::DarkGray));
+            }
+            Some(Ok(advance)) => advance,
+            Some(Err(err)) => {
+                return (false, Line::raw(format!("    {err}")).fg(Color::Red));
+            }
+        };
+        let enabled = !advance.enabled.is_empty();
+        let advanced = advance.advanced();
+        let line = if self.placement != NewPlacement::Child {
+            Line::raw("    -A/-B moves no bookmarks").fg(Color::DarkGray)
+        } else if !advanced.is_empty() {
+            Line::raw(format!("    moves {}", advanced.join(", "))).fg(Color::Green)
+        } else if advance.parent_bookmarks.is_empty() {
+            Line::raw("    parents have no bookmarks").fg(Color::DarkGray)
+        } else {
+            Line::raw(format!("    keeps {}", advance.parent_bookmarks.join(", ")))
+                .fg(Color::Yellow)
+        };
+        (enabled, line)
+    }
+
+    /// Run the command that the popup is currently configured to do
+    fn run_command(&mut self, commander: &mut Commander) -> Result<()> {
+        let placement = match self.placement {
+            NewPlacement::Child => None,
+            NewPlacement::InsertAfter => Some("--insert-after"),
+            NewPlacement::InsertBefore => Some("--insert-before"),
+        };
+        if !self.describe {
+            self.created = Some(commander.run_new_placed(
+                self.revision.commit_id.as_str(),
+                placement,
+                self.edit,
+            )?);
+            return Ok(());
+        }
+
+        let action = commander.begin_action("New and describe")?;
+        self.created = Some(
+            commander
+                .run_new_placed(self.revision.commit_id.as_str(), placement, self.edit)
+                .map_err(|err| commander.fail_action(action.clone(), err))?,
+        );
+        self.action = Some(action);
+        Ok(())
+    }
+
+    /// Process the input event. If this function returns Ok(true),
+    /// then the popup should be closed. Either a change was created,
+    /// see [NewPopup::created], or the operation was cancelled.
+    /// Err(_) will be returned if the jj command failed.
+    pub fn handle_input(&mut self, commander: &mut Commander, event: Event) -> Result<bool> {
+        match self.match_event(event) {
+            PopupAction::Ok => {
+                self.run_command(commander)?;
+                return Ok(true);
+            }
+            PopupAction::Cancel => return Ok(true),
+            PopupAction::SetPlacement(placement) => self.placement = placement,
+            PopupAction::ToggleEdit => self.edit = !self.edit,
+            // Toggling again before the config was read would toggle it back
+            PopupAction::ToggleAdvance if self.advance_rx.is_none() => {
+                self.load_advance(commander, true);
+            }
+            PopupAction::ToggleAdvance => (),
+            PopupAction::None => (),
+        }
+        Ok(false)
+    }
+}
+
+impl Component for NewPopup {
+    fn draw(&mut self, frame: &mut Frame<'_>, area: Rect) -> Result<()> {
+        let block = Block::bordered()
+            .title(Span::styled(" New ", Style::new().bold().cyan()))
+            .title_alignment(Alignment::Center)
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(Color::Green));
+        frame.render_widget(Clear, area);
+        frame.render_widget(&block, area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .vertical_margin(1)
+            .horizontal_margin(2)
+            .constraints([
+                Constraint::Length(1), // title "Revision"
+                Constraint::Min(3),    // buttons for placement
+                Constraint::Length(1), // edit checkbox
+                Constraint::Length(2), // advance checkbox and its effect
+                Constraint::Length(2), // help text
+            ])
+            .split(area);
+
+        let change_id: String = self.revision.change_id.as_str().chars().take(8).collect();
+        let commit_id: String = self.revision.commit_id.as_str().chars().take(8).collect();
+        frame.render_widget(
+            Paragraph::new(Span::raw(format!("Revision {change_id} {commit_id}"))),
+            chunks[0],
+        );
+
+        let options = vec!["c  new child", "-A insert after", "-B insert before"];
+        let mut select: usize = match self.placement {
+            NewPlacement::Child => 0,
+            NewPlacement::InsertAfter => 1,
+            NewPlacement::InsertBefore => 2,
+        };
+        frame.render_stateful_widget(RadioButton::new(options), chunks[1], &mut select);
+
+        let checkbox = if self.edit { "[x]" } else { "[ ]" };
+        frame.render_widget(
+            Paragraph::new(format!("{checkbox} e  edit the new change")).fg(Color::White),
+            chunks[2],
+        );
+
+        let (advance, effect) = self.advance_lines();
+        let checkbox = if advance { "[x]" } else { "[ ]" };
+        frame.render_widget(
+            Paragraph::new(Text::from(vec![
+                Line::raw(format!("{checkbox} a  advance bookmarks")).fg(Color::White),
+                effect,
+            ])),
+            chunks[3],
+        );
+
+        frame.render_widget(
+            Paragraph::new(Text::from(vec![
+                Line::raw(""),
+                Line::raw("Esc: Cancel    Enter: New"),
+            ])),
+            chunks[4],
+        );
+
+        Ok(())
+    }
+
+    fn input(&mut self, _commander: &mut Commander, _event: Event) -> Result<ComponentInputResult> {
+        unreachable!();
+    }
+}
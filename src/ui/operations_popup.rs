@@ -0,0 +1,385 @@
+//! Popup listing the operation log with the heads and bookmarks as of the selected operation, to
+//! judge which operation to restore to with `jj op restore`. The operations are listed in pages in
+//! a background thread, the next page once the selection gets close to the end. The summary of an
+//! operation is loaded once the selection rests on it, so that scrolling doesn't run two commands
+//! per operation passed.
+
+use std::{
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use ratatui::{
+    Frame,
+    crossterm::event::{Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListState, Paragraph},
+};
+
+use crate::{
+    ComponentInputResult,
+    commander::{
+        Commander,
+        operations::{Operation, OperationSummary},
+    },
+    ui::{
+        Component, ComponentAction,
+        message_popup::MessagePopup,
+        read_only_popup,
+        styles::create_popup_block,
+        utils::{centered_rect, spinner},
+    },
+};
+
+/// Number of operations listed at a time
+const PAGE_SIZE: usize = 100;
+/// Distance of the selection to the end of the list at which the next page is listed
+const PAGE_MARGIN: usize = 10;
+/// Time the selection has to rest on an operation before its summary is loaded
+const SUMMARY_DELAY: Duration = Duration::from_millis(150);
+
+type SummaryResult = (String, Result<OperationSummary, String>);
+
+pub struct OperationsPopup {
+    /// None while the first page is being listed
+    operations: Option<Result<Vec<Operation>, String>>,
+    operations_rx: Option<Receiver<Result<Vec<Operation>, String>>>,
+    /// Number of operations asked for by the latest listing
+    limit: usize,
+    /// Whether the listing returned all operations
+    complete: bool,
+    started: Instant,
+    list_state: ListState,
+    /// Id of the operation the summary was loaded for, with the summary
+    summary: Option<SummaryResult>,
+    summary_rx: Option<Receiver<SummaryResult>>,
+    /// When the selection last moved
+    selection_changed: Instant,
+    /// Whether restoring the selected operation waits for confirmation
+    confirming: bool,
+}
+
+impl OperationsPopup {
+    pub fn new(commander: &Commander) -> Self {
+        let mut popup = Self {
+            operations: None,
+            operations_rx: None,
+            limit: 0,
+            complete: false,
+            started: Instant::now(),
+            list_state: ListState::default().with_selected(Some(0)),
+            summary: None,
+            summary_rx: None,
+            // The summary of the first operation is loaded right away
+            selection_changed: Instant::now()
+                .checked_sub(SUMMARY_DELAY)
+                .unwrap_or_else(Instant::now),
+            confirming: false,
+        };
+        popup.list_operations(commander, PAGE_SIZE);
+        popup
+    }
+
+    /// List the `limit` newest operations in a background thread
+    fn list_operations(&mut self, commander: &Commander, limit: usize) {
+        let (tx, rx) = mpsc::channel();
+        let commander = Commander::new(&commander.env);
+        thread::spawn(move || {
+            let _ = tx.send(
+                commander
+                    .get_recent_operations(limit)
+                    .map_err(|err| err.to_string()),
+            );
+        });
+        self.operations_rx = Some(rx);
+        self.limit = limit;
+    }
+
+    fn set_operations(&mut self, operations: Result<Vec<Operation>, String>) {
+        self.operations_rx = None;
+        // Operations run in the meantime are listed above, keep the selected one selected
+        let selected_id = self
+            .selected_operation()
+            .map(|operation| operation.id.clone());
+        if let Ok(operations) = operations.as_ref() {
+            self.complete = operations.len() < self.limit;
+            let selected = selected_id
+                .and_then(|id| operations.iter().position(|operation| operation.id == id))
+                .or(self.list_state.selected())
+                .map(|selected| selected.min(operations.len().saturating_sub(1)));
+            self.list_state.select(selected);
+        }
+        self.operations = Some(operations);
+    }
+
+    fn selected_operation(&self) -> Option<&Operation> {
+        self.operations
+            .as_ref()?
+            .as_ref()
+            .ok()?
+            .get(self.list_state.selected()?)
+    }
+
+    /// Load the summary of the selected operation in a background thread, once the selection
+    /// rested on it for [SUMMARY_DELAY] and unless it is already loaded or loading
+    fn load_summary(&mut self, commander: &Commander) {
+        if self.summary_rx.is_some() || self.selection_changed.elapsed() < SUMMARY_DELAY {
+            return;
+        }
+        let Some(id) = self
+            .selected_operation()
+            .map(|operation| operation.id.clone())
+        else {
+            return;
+        };
+        if self
+            .summary
+            .as_ref()
+            .is_some_and(|(summary_id, _)| *summary_id == id)
+        {
+            return;
+        }
+        let (tx, rx) = mpsc::channel();
+        let commander = Commander::new(&commander.env);
+        thread::spawn(move || {
+            let summary = commander
+                .get_operation_summary(&id)
+                .map_err(|err| err.to_string());
+            let _ = tx.send((id, summary));
+        });
+        self.summary_rx = Some(rx);
+    }
+
+    /// Whether the summary shown is the one of the selected operation
+    fn summary_is_current(&self) -> bool {
+        match (self.summary.as_ref(), self.selected_operation()) {
+            (Some((id, _)), Some(operation)) => *id == operation.id,
+            _ => false,
+        }
+    }
+
+    fn summary_lines(&self) -> Vec<Line<'static>> {
+        if self.selected_operation().is_none() {
+            return vec![];
+        }
+        if !self.summary_is_current() {
+            return vec![Line::from(format!(
+                "{} Loading",
+                spinner(self.started.elapsed())
+            ))];
+        }
+        let summary = match self.summary.as_ref() {
+            Some((_, Ok(summary))) => summary,
+            Some((_, Err(err))) => {
+                return vec![
+                    Line::from(format!("Error getting the repository: {err}")).fg(Color::Red),
+                ];
+            }
+            None => return vec![],
+        };
+        let mut lines = vec![Line::from("Heads").bold()];
+        lines.extend(summary.heads.iter().map(|head| Line::from(head.clone())));
+        lines.push(Line::default());
+        lines.push(Line::from("Bookmarks").bold());
+        if summary.bookmarks.is_empty() {
+            lines.push(Line::from("No bookmarks").fg(Color::DarkGray));
+        }
+        lines.extend(
+            summary
+                .bookmarks
+                .iter()
+                .map(|bookmark| Line::from(bookmark.clone())),
+        );
+        lines
+    }
+
+    /// Restore the selected operation, returning a popup with the error if it failed
+    fn restore(&self, commander: &Commander) -> Result<ComponentAction> {
+        let Some(operation) = self.selected_operation() else {
+            return Ok(ComponentAction::SetPopup(None));
+        };
+        let action = match commander.restore_operation(&operation.id) {
+            Ok(_) => ComponentAction::SetPopup(None),
+            Err(err) => ComponentAction::SetPopup(Some(Box::new(MessagePopup {
+                title: "Restore error".into(),
+                messages: commander.error_text(&err, "")?,
+                text_align: None,
+                ..Default::default()
+            }))),
+        };
+        Ok(ComponentAction::Multiple(vec![
+            action,
+            ComponentAction::RefreshTab(),
+        ]))
+    }
+}
+
+impl Component for OperationsPopup {
+    fn update(&mut self, commander: &mut Commander) -> Result<Option<ComponentAction>> {
+        if let Some(rx) = self.operations_rx.as_ref() {
+            match rx.try_recv() {
+                Ok(operations) => self.set_operations(operations),
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => {
+                    self.set_operations(Err("the operations thread stopped".to_owned()))
+                }
+            }
+        }
+        if let Some(rx) = self.summary_rx.as_ref() {
+            match rx.try_recv() {
+                Ok(summary) => {
+                    self.summary = Some(summary);
+                    self.summary_rx = None;
+                }
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => self.summary_rx = None,
+            }
+        }
+
+        if self.operations_rx.is_none()
+            && !self.complete
+            && let Some(Ok(operations)) = self.operations.as_ref()
+            && self.list_state.selected().unwrap_or_default() + PAGE_MARGIN >= operations.len()
+        {
+            self.list_operations(commander, self.limit + PAGE_SIZE);
+        }
+        self.load_summary(commander);
+        Ok(None)
+    }
+
+    fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+        let area = centered_rect(area, 80, 70);
+        f.render_widget(Clear, area);
+
+        let block = create_popup_block("Operations");
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let [content_area, help_area] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(inner);
+        let [list_area, summary_area] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .areas(content_area);
+
+        match self.operations.as_ref() {
+            None => {
+                f.render_widget(
+                    Paragraph::new(format!(
+                        "{} Listing the operations",
+                        spinner(self.started.elapsed())
+                    )),
+                    list_area,
+                );
+            }
+            Some(Ok(operations)) => {
+                let mut items: Vec<Line> = operations
+                    .iter()
+                    .map(|operation| {
+                        Line::from(vec![
+                            Span::raw(operation.id.clone()).blue(),
+                            Span::raw(" "),
+                            Span::raw(operation.age.clone()).fg(Color::DarkGray),
+                            Span::raw(" "),
+                            Span::raw(operation.description.clone()),
+                        ])
+                    })
+                    .collect();
+                if self.operations_rx.is_some() {
+                    items.push(
+                        Line::from(format!("{} Listing more", spinner(self.started.elapsed())))
+                            .fg(Color::DarkGray),
+                    );
+                }
+                let list = List::new(items).highlight_style(Style::default().reversed());
+                f.render_stateful_widget(list, list_area, &mut self.list_state);
+            }
+            Some(Err(err)) => {
+                f.render_widget(
+                    Paragraph::new(format!("Error getting operations: {err}")).fg(Color::Red),
+                    list_area,
+                );
+            }
+        }
+
+        let title = match self.selected_operation() {
+            Some(operation) => format!(" As of {} ", operation.id),
+            None => String::new(),
+        };
+        f.render_widget(
+            Paragraph::new(self.summary_lines())
+                .block(Block::default().borders(Borders::LEFT).title(title)),
+            summary_area,
+        );
+
+        let help = if self.confirming {
+            Line::from("Restore the repository to this operation? Press y to confirm, n to cancel")
+                .fg(Color::Yellow)
+        } else {
+            Line::from("Enter: restore | Esc: close").fg(Color::DarkGray)
+        };
+        f.render_widget(Paragraph::new(help).centered(), help_area);
+
+        Ok(())
+    }
+
+    fn input(&mut self, commander: &mut Commander, event: Event) -> Result<ComponentInputResult> {
+        let Event::Key(key) = event else {
+            return Ok(ComponentInputResult::Handled);
+        };
+        if key.kind != KeyEventKind::Press {
+            return Ok(ComponentInputResult::Handled);
+        }
+
+        if self.confirming {
+            self.confirming = false;
+            if key.code == KeyCode::Char('y') {
+                return Ok(ComponentInputResult::HandledAction(
+                    self.restore(commander)?,
+                ));
+            }
+            return Ok(ComponentInputResult::Handled);
+        }
+
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => self.list_state.select_next(),
+            KeyCode::Char('k') | KeyCode::Up => self.list_state.select_previous(),
+            KeyCode::Enter if commander.env.config.read_only() => {
+                return Ok(ComponentInputResult::HandledAction(read_only_popup()));
+            }
+            KeyCode::Enter if self.selected_operation().is_some() => self.confirming = true,
+            KeyCode::Esc | KeyCode::Char('q') => {
+                return Ok(ComponentInputResult::HandledAction(
+                    ComponentAction::SetPopup(None),
+                ));
+            }
+            _ => return Ok(ComponentInputResult::NotHandled),
+        }
+        // select_next can move past the last operation, clamp it before loading the summary
+        if let Some(Ok(operations)) = self.operations.as_ref()
+            && let Some(selected) = self.list_state.selected()
+            && selected >= operations.len()
+        {
+            self.list_state.select(operations.len().checked_sub(1));
+        }
+        self.selection_changed = Instant::now();
+        Ok(ComponentInputResult::Handled)
+    }
+
+    fn hints(&self) -> Vec<(String, String)> {
+        if self.confirming {
+            return vec![
+                ("y".to_owned(), "confirm".to_owned()),
+                ("n".to_owned(), "cancel".to_owned()),
+            ];
+        }
+        vec![
+            ("j/k".to_owned(), "select".to_owned()),
+            ("Enter".to_owned(), "restore".to_owned()),
+            ("Esc".to_owned(), "close".to_owned()),
+        ]
+    }
+}
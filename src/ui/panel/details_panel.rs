@@ -1,14 +1,21 @@
 use ratatui::{
     crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseEvent, MouseEventKind},
     layout::{Margin, Position, Rect},
+    style::{Color, Stylize},
     text::{Line, Text},
     widgets::{
         Block, BorderType, Padding, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState,
         Wrap,
     },
 };
+use std::time::{Duration, Instant};
 use tracing::trace;
 
+use crate::{
+    panel_settings::{self, Panel},
+    ui::styles::{panel_border_style, panel_content_style},
+};
+
 /// Details panel used for the right side of each tab.
 /// This handles scrolling and wrapping.
 pub struct DetailsPanel {
@@ -22,13 +29,46 @@ pub struct DetailsPanel {
     lines: u16,
     /// Wrap long lines of content into multiple lines
     wrap: bool,
+    /// Whether this panel has focus instead of the main panel of the tab
+    focused: bool,
+    /// Lines of the conflict markers (`<<<<<<<`) in the content, found at the first render after
+    /// the content changed, see [DetailsPanel::content_changed]
+    conflict_lines: Option<Vec<usize>>,
+    /// Rows of the [conflict_lines][DetailsPanel::conflict_lines], including extra lines for
+    /// wrapped lines
+    conflict_rows: Vec<u16>,
+    /// Lines starting the sections of the content, e.g. the diffs of the files of a change
+    anchor_lines: Vec<usize>,
+    /// Rows of the [anchor_lines][DetailsPanel::anchor_lines], including extra lines for wrapped
+    /// lines
+    anchor_rows: Vec<u16>,
+    /// Section to scroll to once the rows of the content are known at the next render
+    scroll_to_anchor: Option<usize>,
+    /// Columns the content was produced for, see [DetailsPanel::set_content_columns]
+    content_columns: u16,
+    /// When the number of columns last changed
+    resized_at: Instant,
+    /// Percentage of the tab used by the main panel, changed with `<`/`>`
+    layout_percent: u16,
+    /// Repository root and tab the wrapping and size are saved for, see [panel_settings]
+    settings: Option<(String, Panel)>,
 }
 
+/// Percentage points the split between the panels of a tab moves with `<`/`>`
+const LAYOUT_PERCENT_STEP: u16 = 5;
+/// Smallest percentage of the tab for either panel when resizing, so that both stay visible
+const LAYOUT_PERCENT_MIN: u16 = 10;
+
+/// Time without further resizing before width dependent content is produced again, so that
+/// resizing a window doesn't run a command for every intermediate size
+pub(super) const RESIZE_SETTLE_TIME: Duration = Duration::from_millis(150);
+
 /// Transient object holding render data
 pub struct DetailsPanelRenderContext<'a> {
     panel: &'a mut DetailsPanel,
     title: Option<Line<'a>>,
     content: Option<Text<'a>>,
+    focus_color: Color,
 }
 
 /// Commands that can be handled by the details panel
@@ -40,6 +80,13 @@ pub enum DetailsPanelEvent {
     ScrollDownPage,
     ScrollUpPage,
     ToggleWrap,
+    ToggleFocus,
+    /// Grow the details panel by shrinking the main panel of the tab
+    Grow,
+    /// Shrink the details panel by growing the main panel of the tab
+    Shrink,
+    NextConflict,
+    PreviousConflict,
 }
 
 impl<'a> DetailsPanelRenderContext<'a> {
@@ -48,6 +95,7 @@ impl<'a> DetailsPanelRenderContext<'a> {
             panel,
             title: None,
             content: None,
+            focus_color: Color::Green,
         }
     }
     /// Set the title on the frame that surrounds the content
@@ -58,6 +106,11 @@ impl<'a> DetailsPanelRenderContext<'a> {
         self.title = Some(title.into());
         self
     }
+    /// Set the border color used while the panel has focus
+    pub fn focus_color(&mut self, focus_color: Color) -> &mut Self {
+        self.focus_color = focus_color;
+        self
+    }
     /// Set the text inside the panel
     pub fn content<T>(&mut self, content: T) -> &mut Self
     where
@@ -74,6 +127,7 @@ impl<'a> DetailsPanelRenderContext<'a> {
         // Define border block
         let mut border = Block::bordered()
             .border_type(BorderType::Rounded)
+            .border_style(panel_border_style(self.panel.focused, self.focus_color))
             .padding(Padding::horizontal(1));
         // Apply title if provided
         if let Some(title) = &self.title {
@@ -90,7 +144,19 @@ impl<'a> DetailsPanelRenderContext<'a> {
         let paragraph = self
             .panel
             .render(content_text.clone(), paragraph_area)
-            .block(border);
+            .style(panel_content_style(self.panel.focused));
+
+        // Show the number of conflicts, now that render found them
+        let conflicts = self.panel.conflict_rows.len();
+        if conflicts > 0 {
+            let plural = if conflicts == 1 { "" } else { "s" };
+            border = border.title_top(
+                Line::from(format!(" {conflicts} conflict{plural} ([/]) "))
+                    .right_aligned()
+                    .red(),
+            );
+        }
+        let paragraph = paragraph.block(border);
 
         // render content and border
         f.render_widget(paragraph, area);
@@ -114,6 +180,42 @@ impl<'a> DetailsPanelRenderContext<'a> {
     }
 }
 
+/// Lines of the content with a conflict marker (`<<<<<<<`)
+fn find_conflict_lines(content: &Text) -> Vec<usize> {
+    content
+        .lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| {
+            line.spans
+                .iter()
+                .map(|span| span.content.as_ref())
+                .collect::<String>()
+                .contains("<<<<<<<")
+        })
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Rows of the lines of the content, given in ascending order. With wrapping, the rows of the
+/// lines before each line are counted.
+fn line_rows(content: &Text, lines: &[usize], width: u16, wrap: bool) -> Vec<u16> {
+    if !wrap {
+        return lines.iter().map(|&i| i as u16).collect();
+    }
+    let mut rows = vec![];
+    let (mut counted_lines, mut counted_rows) = (0, 0);
+    for &i in lines {
+        let i = i.clamp(counted_lines, content.lines.len());
+        counted_rows += Paragraph::new(Text::from(content.lines[counted_lines..i].to_vec()))
+            .wrap(Wrap { trim: false })
+            .line_count(width) as u16;
+        counted_lines = i;
+        rows.push(counted_rows);
+    }
+    rows
+}
+
 impl DetailsPanel {
     pub fn new() -> Self {
         Self {
@@ -122,6 +224,76 @@ impl DetailsPanel {
             scroll: 0,
             lines: 0,
             wrap: true,
+            focused: false,
+            conflict_lines: None,
+            conflict_rows: vec![],
+            anchor_lines: vec![],
+            anchor_rows: vec![],
+            scroll_to_anchor: None,
+            content_columns: 0,
+            resized_at: Instant::now(),
+            layout_percent: 50,
+            settings: None,
+        }
+    }
+
+    /// Details panel of a tab of the repository at `root`, wrapping and sized as it was last
+    /// changed there. `layout_percent` is the size from the config. The size is clamped, as
+    /// the saved settings may have been edited by hand.
+    pub fn with_settings(root: &str, panel: Panel, layout_percent: u16) -> Self {
+        let settings = panel_settings::load(root, panel);
+        Self {
+            wrap: settings.wrap.unwrap_or(true),
+            layout_percent: settings
+                .layout_percent
+                .unwrap_or(layout_percent)
+                .clamp(LAYOUT_PERCENT_MIN, 100),
+            settings: Some((root.to_owned(), panel)),
+            ..Self::new()
+        }
+    }
+
+    /// Help of the keys handled by the details panel, shown next to the keys of the main panel
+    pub fn help() -> Vec<(String, String)> {
+        vec![
+            (
+                "Tab/Shift+Tab".to_owned(),
+                "focus details panel, then scroll with j/k/J/K".to_owned(),
+            ),
+            ("Ctrl+e/Ctrl+y".to_owned(), "scroll down/up".to_owned()),
+            (
+                "Ctrl+d/Ctrl+u".to_owned(),
+                "scroll down/up by ½ page".to_owned(),
+            ),
+            (
+                "Ctrl+f/Ctrl+b".to_owned(),
+                "scroll down/up by page".to_owned(),
+            ),
+            ("w".to_owned(), "toggle diff format".to_owned()),
+            ("W".to_owned(), "toggle wrapping".to_owned()),
+            ("</>".to_owned(), "grow/shrink details panel".to_owned()),
+            ("]/[".to_owned(), "next/previous conflict".to_owned()),
+        ]
+    }
+
+    /// Percentage of the tab used by the main panel, the details panel uses the rest
+    pub fn layout_percent(&self) -> u16 {
+        self.layout_percent
+    }
+
+    /// Move the split between the panels of the tab and save it
+    fn resize(&mut self, main_panel_step: i16) {
+        let min = LAYOUT_PERCENT_MIN.min(self.layout_percent);
+        let max = (100 - LAYOUT_PERCENT_MIN).max(self.layout_percent);
+        self.layout_percent = self
+            .layout_percent
+            .saturating_add_signed(main_panel_step)
+            .clamp(min, max);
+        if let Some((root, panel)) = self.settings.as_ref() {
+            let layout_percent = self.layout_percent;
+            panel_settings::save(root, *panel, |settings| {
+                settings.layout_percent = Some(layout_percent)
+            });
         }
     }
 
@@ -134,15 +306,31 @@ impl DetailsPanel {
     where
         T: Into<Text<'a>>,
     {
+        let content: Text = content.into();
+        let conflict_lines = self
+            .conflict_lines
+            .get_or_insert_with(|| find_conflict_lines(&content));
+        self.conflict_rows = line_rows(&content, conflict_lines, area.width, self.wrap);
+        self.anchor_rows = line_rows(&content, &self.anchor_lines, area.width, self.wrap);
+
         let mut paragraph = Paragraph::new(content);
 
         if self.wrap {
             paragraph = paragraph.wrap(Wrap { trim: false });
         }
 
+        if area.width != self.content_rect.width {
+            self.resized_at = Instant::now();
+        }
         self.content_rect = area;
         self.lines = paragraph.line_count(area.width) as u16;
 
+        if let Some(anchor) = self.scroll_to_anchor.take()
+            && let Some(row) = self.anchor_rows.get(anchor)
+        {
+            self.scroll = (*row).min(self.lines.saturating_sub(1));
+        }
+
         paragraph = paragraph.scroll((self.scroll.min(self.lines.saturating_sub(1)), 0));
 
         paragraph
@@ -154,13 +342,57 @@ impl DetailsPanel {
         self.content_rect.width
     }
 
+    /// Remember that the content is produced for the current number of columns, e.g. passed to
+    /// a diff tool with [Commander::limit_width][crate::commander::Commander::limit_width]
+    pub fn set_content_columns(&mut self) {
+        self.content_columns = self.columns();
+    }
+
+    /// Whether the panel was resized since the content was produced, possibly still resizing
+    pub fn is_resizing(&self) -> bool {
+        self.columns() != self.content_columns
+    }
+
+    /// Whether the panel was resized since the content was produced and the size settled
+    pub fn is_resized(&self) -> bool {
+        self.is_resizing() && self.resized_at.elapsed() >= RESIZE_SETTLE_TIME
+    }
+
     /// Return number of rows available for content at last call to render.
     /// Will return 0 if render has not been called.
     pub fn rows(&self) -> u16 {
         self.content_rect.height
     }
 
+    /// Whether this panel has focus instead of the main panel of the tab
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+
+    /// Find the conflict markers in the content again at the next render, as it was refreshed
+    pub fn content_changed(&mut self) {
+        self.conflict_lines = None;
+    }
+
+    /// Set the lines starting the sections of the content, see [DetailsPanel::scroll_to_anchor]
+    pub fn set_anchor_lines(&mut self, anchor_lines: Vec<usize>) {
+        self.anchor_lines = anchor_lines;
+        self.anchor_rows.clear();
+        self.scroll_to_anchor = None;
+    }
+
+    /// Scroll to the start of a section of the content at the next render, once its row is known
+    pub fn scroll_to_anchor(&mut self, anchor: usize) {
+        self.scroll_to_anchor = Some(anchor);
+    }
+
+    /// The section at the top of the panel, the last one starting at or above it
+    pub fn current_anchor(&self) -> Option<usize> {
+        self.anchor_rows.iter().rposition(|row| *row <= self.scroll)
+    }
+
     pub fn scroll_to(&mut self, line_no: u16) {
+        self.scroll_to_anchor = None;
         self.scroll = line_no.min(self.lines.saturating_sub(1))
     }
 
@@ -178,13 +410,66 @@ impl DetailsPanel {
             }
             DetailsPanelEvent::ScrollDownPage => self.scroll(self.rows() as isize),
             DetailsPanelEvent::ScrollUpPage => self.scroll((self.rows() as isize).saturating_neg()),
-            DetailsPanelEvent::ToggleWrap => self.wrap = !self.wrap,
+            DetailsPanelEvent::ToggleWrap => {
+                self.wrap = !self.wrap;
+                if let Some((root, panel)) = self.settings.as_ref() {
+                    let wrap = self.wrap;
+                    panel_settings::save(root, *panel, |settings| settings.wrap = Some(wrap));
+                }
+            }
+            DetailsPanelEvent::ToggleFocus => self.focused = !self.focused,
+            DetailsPanelEvent::Grow => self.resize(-(LAYOUT_PERCENT_STEP as i16)),
+            DetailsPanelEvent::Shrink => self.resize(LAYOUT_PERCENT_STEP as i16),
+            DetailsPanelEvent::NextConflict => {
+                if let Some(row) = self
+                    .conflict_rows
+                    .iter()
+                    .find(|row| **row > self.scroll)
+                    .or(self.conflict_rows.first())
+                {
+                    self.scroll_to(*row);
+                }
+            }
+            DetailsPanelEvent::PreviousConflict => {
+                if let Some(row) = self
+                    .conflict_rows
+                    .iter()
+                    .rev()
+                    .find(|row| **row < self.scroll)
+                    .or(self.conflict_rows.last())
+                {
+                    self.scroll_to(*row);
+                }
+            }
         }
     }
 
     /// Handle input. Returns bool of if event was handled
+    ///
+    /// `Tab`/`Shift+Tab` move the focus between the main panel and this panel. While this panel
+    /// has focus, it also handles the keys for scrolling the main panel. `<`/`>` move the split
+    /// between the panels towards the start or end, growing or shrinking this panel.
     pub fn input(&mut self, key: KeyEvent) -> bool {
         match key.code {
+            KeyCode::Tab | KeyCode::BackTab => self.handle_event(DetailsPanelEvent::ToggleFocus),
+            KeyCode::Char(']') if !self.conflict_rows.is_empty() => {
+                self.handle_event(DetailsPanelEvent::NextConflict)
+            }
+            KeyCode::Char('[') if !self.conflict_rows.is_empty() => {
+                self.handle_event(DetailsPanelEvent::PreviousConflict)
+            }
+            KeyCode::Char('j') | KeyCode::Down if self.focused => {
+                self.handle_event(DetailsPanelEvent::ScrollDown)
+            }
+            KeyCode::Char('k') | KeyCode::Up if self.focused => {
+                self.handle_event(DetailsPanelEvent::ScrollUp)
+            }
+            KeyCode::Char('J') if self.focused => {
+                self.handle_event(DetailsPanelEvent::ScrollDownHalfPage)
+            }
+            KeyCode::Char('K') if self.focused => {
+                self.handle_event(DetailsPanelEvent::ScrollUpHalfPage)
+            }
             KeyCode::Char('e') if key.modifiers.contains(KeyModifiers::CONTROL) => {
                 self.handle_event(DetailsPanelEvent::ScrollDown)
             }
@@ -204,6 +489,8 @@ impl DetailsPanel {
                 self.handle_event(DetailsPanelEvent::ScrollUpPage)
             }
             KeyCode::Char('W') => self.handle_event(DetailsPanelEvent::ToggleWrap),
+            KeyCode::Char('<') => self.handle_event(DetailsPanelEvent::Grow),
+            KeyCode::Char('>') => self.handle_event(DetailsPanelEvent::Shrink),
             _ => return false,
         };
 
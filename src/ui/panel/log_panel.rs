@@ -1,26 +1,42 @@
 /*! The log panel shows the list of changes on the left side of the
 log tab. */
 
+use std::{
+    collections::HashMap,
+    sync::mpsc::{Receiver, channel},
+    thread,
+    time::Instant,
+};
+
 use ansi_to_tui::IntoText;
 use anyhow::Result;
 use ratatui::{
     crossterm::event::{Event, MouseEvent, MouseEventKind},
     layout::Rect,
     prelude::*,
-    text::ToText,
     widgets::*,
 };
+use tracing::warn;
 
 use crate::{
     commander::{
         CommandError, Commander,
-        log::{Head, LogOutput},
+        ci_status::{CiStatus, CiStatusCache},
+        hindsight::RevisionChange,
+        ids::CommitId,
+        log::{Head, LogDensity, LogFilter, LogOutput, LogRevision, TrunkRelation},
     },
     env::Config,
-    keybinds::{LogTabEvent, LogTabKeybinds},
+    keybinds::LogTabEvent,
     ui::Component,
     ui::ComponentAction,
     ui::ComponentInputResult,
+    ui::panel::details_panel::RESIZE_SETTLE_TIME,
+    ui::panel::log_wrap::WrapCache,
+    ui::panel::minimap::{
+        MINIMAP_MIN_SCREENS, MINIMAP_WIDTH, MinimapCache, draw_minimap, minimap_line,
+    },
+    ui::styles::{panel_border_style, panel_content_style},
 };
 
 /**
@@ -47,12 +63,75 @@ pub struct LogPanel<'a> {
     /// The revision set to show in the log
     pub log_revset: Option<String>,
 
+    /// Only show changes touching this file or directory
+    pub log_path: Option<String>,
+
+    /// Earlier paths of the file of `log_path`, from the newest, so its history continues
+    /// before it was renamed
+    pub log_path_renames: Vec<String>,
+
+    /// Quick filters combined with the revset, in the order they were enabled
+    pub log_filters: Vec<LogFilter>,
+
+    /// Amount of information shown per change
+    pub log_density: LogDensity,
+
     /// Currently selected change
     pub head: Head,
 
+    /// Changes marked as parents of a merge, in the order they were marked
+    marked: Vec<LogRevision>,
+
     /// Rect used last time draw was called. Can be used to check if mouse clicks
     panel_rect: Rect,
 
+    /// Whether to show the minimap when the log is much longer than the screen
+    pub show_minimap: bool,
+
+    /// Whether to show a column with the relationship of each change to `trunk()`
+    pub show_trunk_relation: bool,
+
+    /// Whether the selection moves to `@` whenever the log is refreshed
+    pub follow_working_copy: bool,
+
+    /// Relationship of each change to `trunk()`, only queried while it is shown
+    trunk_relations: HashMap<CommitId, TrunkRelation>,
+
+    /// Index of the `blazingjj.highlight-revsets` entry coloring each change
+    highlights: HashMap<CommitId, usize>,
+
+    /// Operation the log was last marked as seen at, the changes new or rewritten since then
+    /// are marked. Only set with `blazingjj.hindsight` or after marking the log as seen
+    hindsight_baseline: Option<String>,
+
+    /// Changes new or rewritten since the hindsight baseline
+    hindsight_changes: HashMap<CommitId, RevisionChange>,
+
+    /// Changes since the hindsight baseline queried in the background, see
+    /// [LogPanel::refresh_hindsight]
+    hindsight_rx: Option<Receiver<HashMap<CommitId, RevisionChange>>>,
+
+    /// CI status of each change, only queried if `blazingjj.ci-status-command` is set
+    ci_statuses: CiStatusCache,
+
+    /// Log lines wrapped to the width of the panel, only with `ui.log-word-wrap`
+    wrap_cache: WrapCache<'a>,
+
+    /// Width the log lines are wrapped to, without the badges
+    wrap_width: u16,
+
+    /// When the width the log lines are wrapped to last changed
+    resized_at: Instant,
+
+    /// Rect of the minimap last time it was drawn, if it was shown
+    minimap_rect: Option<Rect>,
+
+    /// Lanes of the minimap rows, computed once per log output
+    minimap_cache: MinimapCache,
+
+    /// Whether the panel has focus, set by the log tab
+    pub focused: bool,
+
     config: Config,
 }
 
@@ -91,21 +170,11 @@ fn get_head_index(head: &Head, log_output: &Result<LogOutput, CommandError>) ->
 impl<'a> LogPanel<'a> {
     pub fn new(commander: &mut Commander) -> Result<Self> {
         let log_revset = commander.env.default_revset.clone();
-        let log_output = commander.get_log(&log_revset);
+        let log_output = commander.get_log(&log_revset, &[], LogDensity::default(), false);
         let head = commander.get_current_head()?;
 
         let log_list_state = ListState::default().with_selected(get_head_index(&head, &log_output));
 
-        let mut keybinds = LogTabKeybinds::default();
-        if let Some(new_keybinds) = commander
-            .env
-            .config
-            .keybinds()
-            .and_then(|k| k.log_tab.clone())
-        {
-            keybinds.extend_from_config(&new_keybinds);
-        }
-
         let log_output_text = match log_output.as_ref() {
             Ok(log_output) => log_output
                 .graph
@@ -114,20 +183,51 @@ impl<'a> LogPanel<'a> {
             Err(_) => Text::default(),
         };
 
-        Ok(Self {
+        let mut log_panel = Self {
             log_output_text,
             log_output,
             log_list_state,
             log_rect: Rect::ZERO,
 
             log_revset,
+            log_path: None,
+            log_path_renames: vec![],
+            log_filters: vec![],
+            log_density: LogDensity::default(),
 
             head,
+            marked: vec![],
 
             panel_rect: Rect::ZERO,
 
+            show_minimap: true,
+            show_trunk_relation: false,
+            follow_working_copy: false,
+            trunk_relations: HashMap::new(),
+            highlights: HashMap::new(),
+            hindsight_baseline: None,
+            hindsight_changes: HashMap::new(),
+            hindsight_rx: None,
+            ci_statuses: CiStatusCache::default(),
+            minimap_rect: None,
+            minimap_cache: MinimapCache::default(),
+            wrap_cache: WrapCache::default(),
+            wrap_width: 0,
+            resized_at: Instant::now(),
+
+            focused: true,
+
             config: commander.env.config.clone(),
-        })
+        };
+        if commander.env.config.trunk_relation() {
+            log_panel.show_trunk_relation = true;
+            log_panel.refresh_trunk_relations(commander);
+        }
+        log_panel.refresh_highlights(commander);
+        if commander.env.config.hindsight() {
+            log_panel.mark_seen(commander);
+        }
+        Ok(log_panel)
     }
 
     //
@@ -136,7 +236,14 @@ impl<'a> LogPanel<'a> {
 
     /// Run jj log and store output for display
     pub fn refresh_log_output(&mut self, commander: &mut Commander) {
-        self.log_output = commander.get_log(&self.log_revset);
+        let head_index = self.get_current_head_index();
+        self.log_output = commander.get_log(
+            &self.filtered_revset(commander),
+            &self.log_paths(),
+            self.log_density,
+            self.log_filters.contains(&LogFilter::FirstParent),
+        );
+        self.keep_selection(head_index);
         self.log_output_text = match self.log_output.as_ref() {
             Ok(log_output) => log_output
                 .graph
@@ -144,10 +251,207 @@ impl<'a> LogPanel<'a> {
                 .unwrap_or(Text::from("Could not turn text into TUI text (coloring)")),
             Err(_) => Text::default(),
         };
+        self.wrap_cache.clear();
+        self.minimap_cache.clear();
+        self.refresh_trunk_relations(commander);
+        self.refresh_highlights(commander);
+        self.refresh_hindsight(commander);
+    }
+
+    /// Add the selected change to the revset if the log doesn't show it, e.g. after jumping to
+    /// a bookmark outside of `revsets.log`
+    pub fn reveal_head(&mut self, commander: &mut Commander) {
+        if self.log_output.is_err() || self.get_current_head_index().is_some() {
+            return;
+        }
+        // A divergent change id is ambiguous in a revset
+        let id = if self.head.divergent {
+            self.head.commit_id.as_str()
+        } else {
+            self.head.change_id.as_str()
+        };
+        let revset = commander.get_log_revset(&self.log_revset);
+        self.log_revset = Some(format!("({revset}) | {id}"));
+        self.refresh_log_output(commander);
+    }
+
+    /// Reveal the revisions elided between the selected change and its nearest ancestors in the
+    /// log by adding the range connecting them to the revset, or its parents if the log shows
+    /// none of its ancestors. Returns whether the log shows more changes, the revset is left as
+    /// it was otherwise
+    pub fn expand_elided(&mut self, commander: &mut Commander) -> bool {
+        let Ok(log_output) = self.log_output.as_ref() else {
+            return false;
+        };
+        let change_count = log_output.heads.len();
+        let revset = commander.get_expanded_revset(&self.log_revset, &self.head);
+        let previous_revset = self.log_revset.replace(revset);
+        self.refresh_log_output(commander);
+        if self
+            .log_output
+            .as_ref()
+            .is_ok_and(|log_output| log_output.heads.len() > change_count)
+        {
+            return true;
+        }
+        self.log_revset = previous_revset;
+        self.refresh_log_output(commander);
+        false
+    }
+
+    /// Revset of the log with the quick filters applied
+    fn filtered_revset(&self, commander: &Commander) -> Option<String> {
+        commander.get_filtered_revset(&self.log_revset, &self.log_filters)
+    }
+
+    /// The revset of the log with its filters, or `revsets.log` without one
+    pub fn full_revset(&self, commander: &Commander) -> String {
+        commander.get_log_revset(&self.filtered_revset(commander))
+    }
+
+    /// Enable the quick filter, or disable it if it is enabled
+    pub fn toggle_filter(&mut self, commander: &mut Commander, filter: LogFilter) {
+        if self.log_filters.contains(&filter) {
+            self.log_filters.retain(|log_filter| *log_filter != filter);
+        } else {
+            self.log_filters.push(filter);
+        }
+        self.refresh_log_output(commander);
+    }
+
+    /// The paths the log is filtered by, the path with its earlier paths
+    fn log_paths(&self) -> Vec<String> {
+        self.log_path
+            .iter()
+            .chain(&self.log_path_renames)
+            .cloned()
+            .collect()
+    }
+
+    /// Query the relationship to `trunk()` of the changes in the log, if it is shown
+    fn refresh_trunk_relations(&mut self, commander: &mut Commander) {
+        self.trunk_relations = if self.show_trunk_relation {
+            commander
+                .get_trunk_relations(&self.filtered_revset(commander), &self.log_paths())
+                .unwrap_or_else(|err| {
+                    warn!("Failed getting trunk relations: {err}");
+                    HashMap::new()
+                })
+        } else {
+            HashMap::new()
+        };
+    }
+
+    /// Query which changes in the log match the `blazingjj.highlight-revsets`
+    fn refresh_highlights(&mut self, commander: &mut Commander) {
+        let revsets: Vec<String> = self
+            .config
+            .highlight_revsets()
+            .iter()
+            .map(|highlight| highlight.revset.clone())
+            .collect();
+        self.highlights = commander
+            .get_highlighted_revisions(
+                &self.filtered_revset(commander),
+                &self.log_paths(),
+                &revsets,
+            )
+            .unwrap_or_else(|err| {
+                warn!("Failed getting highlighted revisions: {err}");
+                HashMap::new()
+            });
+    }
+
+    /// Query which changes in the log are new or rewritten since the hindsight baseline, in a
+    /// background thread as it takes a few jj commands. The marks of the previous query are shown
+    /// until it is done.
+    fn refresh_hindsight(&mut self, commander: &mut Commander) {
+        let Some(baseline) = self.hindsight_baseline.clone() else {
+            return;
+        };
+        let revset = self.filtered_revset(commander);
+        let paths = self.log_paths();
+        let commander = Commander::new(&commander.env);
+        let (tx, rx) = channel();
+        // Replacing the receiver drops the result of a query still running
+        self.hindsight_rx = Some(rx);
+        thread::spawn(move || {
+            let changes = commander
+                .get_changes_since(&baseline, &revset, &paths)
+                .unwrap_or_else(|err| {
+                    warn!("Failed getting the changes since {baseline}: {err}");
+                    HashMap::new()
+                });
+            let _ = tx.send(changes);
+        });
+    }
+
+    /// Move the changes since the hindsight baseline into the panel once they were queried
+    fn receive_hindsight(&mut self) {
+        if let Some(rx) = self.hindsight_rx.as_ref()
+            && let Ok(changes) = rx.try_recv()
+        {
+            self.hindsight_changes = changes;
+            self.hindsight_rx = None;
+        }
+    }
+
+    /// Move the hindsight baseline to the current operation, clearing the marks of the new and
+    /// rewritten changes
+    pub fn mark_seen(&mut self, commander: &mut Commander) {
+        match commander.get_current_operation() {
+            Ok(operation) => self.hindsight_baseline = Some(operation),
+            Err(err) => warn!("Failed getting the current operation: {err}"),
+        }
+        self.hindsight_rx = None;
+        self.hindsight_changes.clear();
+    }
+
+    /// Color of a change new or rewritten since the hindsight baseline
+    fn hindsight_color(change: RevisionChange) -> Color {
+        match change {
+            RevisionChange::New => Color::Green,
+            RevisionChange::Rewritten => Color::Yellow,
+        }
+    }
+
+    /// Badge of a change new or rewritten since the hindsight baseline, shown on the first line
+    /// of each change
+    fn hindsight_badge(&self, head: Option<&Head>) -> Span<'a> {
+        match head.and_then(|head| self.hindsight_changes.get(&head.commit_id)) {
+            Some(change @ RevisionChange::New) => {
+                Span::from("+ ").fg(Self::hindsight_color(*change))
+            }
+            Some(change @ RevisionChange::Rewritten) => {
+                Span::from("± ").fg(Self::hindsight_color(*change))
+            }
+            None => Span::from("  "),
+        }
+    }
+
+    /// Badge with the relationship to `trunk()`, shown on the first line of each change
+    fn trunk_relation_badge(&self, head: Option<&Head>) -> Span<'a> {
+        match head.and_then(|head| self.trunk_relations.get(&head.commit_id)) {
+            Some(TrunkRelation::Merged) => Span::from("✓ ").fg(Color::Green),
+            Some(TrunkRelation::Ahead) => Span::from("↑ ").fg(Color::Cyan),
+            Some(TrunkRelation::Unrelated) => Span::from("~ ").fg(Color::Yellow),
+            None => Span::from("  "),
+        }
+    }
+
+    /// Badge with the CI status, shown on the first line of each change
+    fn ci_status_badge(&self, head: Option<&Head>) -> Span<'a> {
+        match head.and_then(|head| self.ci_statuses.get(&head.commit_id)) {
+            Some(CiStatus::Success) => Span::from("✓ ").fg(Color::Green),
+            Some(CiStatus::Failure) => Span::from("✗ ").fg(Color::Red),
+            Some(CiStatus::Pending) => Span::from("● ").fg(Color::Yellow),
+            None => Span::from("  "),
+        }
     }
 
-    /// Convert log output to a list of formatted lines
-    fn output_to_lines(&self, log_output: &LogOutput) -> Vec<Line<'a>> {
+    /// Convert log output to a list of formatted items, the lines of each log line wrapped to
+    /// the width of the panel with `ui.log-word-wrap`
+    fn output_to_lines(&self, log_output: &LogOutput) -> Vec<Text<'a>> {
         // Set the background color of the line
         fn set_bg(line: &mut Line, bg_color: Color) {
             // Set background to use when no Span is present
@@ -159,33 +463,110 @@ impl<'a> LogPanel<'a> {
             }
         }
 
+        let wrapped = self.wrap_cache.get(self.wrap_width);
         self.log_output_text
             .iter()
             .enumerate()
             .map(|(i, line)| {
-                let mut line = line.to_owned();
+                let mut lines = match wrapped.and_then(|wrapped| wrapped.get(i)) {
+                    Some(wrapped_lines) => wrapped_lines.clone(),
+                    None => vec![line.to_owned()],
+                };
+                let line_head = log_output.graph_heads.get(i).unwrap_or(&None);
 
-                // Add padding at start
-                line.spans.insert(0, Span::from(" "));
+                // Color all lines of changes matching a highlight revset, or else new or
+                // rewritten since the hindsight baseline
+                let highlight_color = line_head
+                    .as_ref()
+                    .and_then(|line_head| self.highlights.get(&line_head.commit_id))
+                    .and_then(|index| self.config.highlight_revsets().get(*index))
+                    .map(|highlight| highlight.color);
+                let hindsight_color = line_head
+                    .as_ref()
+                    .and_then(|line_head| self.hindsight_changes.get(&line_head.commit_id))
+                    .map(|change| Self::hindsight_color(*change));
+                if let Some(color) = highlight_color.or(hindsight_color) {
+                    for span in lines.iter_mut().flat_map(|line| line.spans.iter_mut()) {
+                        span.style = span.style.fg(color);
+                    }
+                }
 
-                // Highlight lines that correspond to self.head
-                let line_head = log_output.graph_heads.get(i).unwrap_or(&None);
-                if let Some(line_change) = line_head
-                    && line_change == &self.head
-                {
-                    set_bg(&mut line, self.config.highlight_color());
-                };
+                let first_line =
+                    i == 0 || log_output.graph_heads.get(i - 1).unwrap_or(&None) != line_head;
+                let is_marked = line_head.as_ref().is_some_and(|line_head| {
+                    self.marked
+                        .iter()
+                        .any(|marked| marked.change_id == line_head.change_id)
+                });
+                for (row, line) in lines.iter_mut().enumerate() {
+                    // Add the CI status, the relationship to trunk() and whether it changed
+                    // since the hindsight baseline on the first line of each change
+                    let badge_head = line_head.as_ref().filter(|_| first_line && row == 0);
+                    if self.config.ci_status_command().is_some() {
+                        line.spans.insert(0, self.ci_status_badge(badge_head));
+                    }
+                    if self.show_trunk_relation {
+                        line.spans.insert(0, self.trunk_relation_badge(badge_head));
+                    }
+                    if self.hindsight_baseline.is_some() {
+                        line.spans.insert(0, self.hindsight_badge(badge_head));
+                    }
+
+                    // Add padding at start, or a mark for marked changes
+                    if is_marked && row == 0 {
+                        line.spans
+                            .insert(0, Span::from("*").fg(Color::Magenta).bold());
+                    } else {
+                        line.spans.insert(0, Span::from(" "));
+                    }
 
-                line
+                    // Highlight lines that correspond to self.head
+                    if let Some(line_change) = line_head
+                        && line_change == &self.head
+                    {
+                        set_bg(line, self.config.highlight_color());
+                    };
+                }
+
+                Text::from(lines)
             })
             .collect()
     }
 
-    /// Get lines to show in log list
-    fn log_lines(&self) -> Vec<Line<'a>> {
+    /// Number of columns added before the log lines, the mark and the badges
+    fn badges_width(&self) -> u16 {
+        let badges = [
+            self.config.ci_status_command().is_some(),
+            self.show_trunk_relation,
+            self.hindsight_baseline.is_some(),
+        ];
+        1 + 2 * badges.iter().filter(|shown| **shown).count() as u16
+    }
+
+    /// Whether the log lines are still to be wrapped to the width of the panel
+    fn is_wrapping(&self) -> bool {
+        self.config.log_word_wrap() && !self.wrap_cache.contains(self.wrap_width)
+    }
+
+    /// The rendered log graph, with colors
+    pub fn log_graph(&self) -> Option<&str> {
+        self.log_output
+            .as_ref()
+            .ok()
+            .map(|log_output| log_output.graph.as_str())
+    }
+
+    /// Get items to show in log list, one per log line
+    fn log_lines(&self) -> Vec<Text<'a>> {
         match self.log_output.as_ref() {
             Ok(log_output) => self.output_to_lines(log_output),
-            Err(err) => err.into_text("Error getting log").unwrap().lines,
+            Err(err) => err
+                .into_text("Error getting log")
+                .unwrap()
+                .lines
+                .into_iter()
+                .map(Text::from)
+                .collect(),
         }
     }
 
@@ -212,6 +593,20 @@ impl<'a> LogPanel<'a> {
         graph_head.clone()
     }
 
+    /// Find the first head at or after the provided log_output line
+    fn head_at_or_after_log_line(&self, log_line: usize) -> Option<Head> {
+        let log_output = self.log_output.as_ref().ok()?;
+
+        log_output
+            .graph_heads
+            .iter()
+            .skip(log_line)
+            .flatten()
+            .next()
+            .or_else(|| log_output.heads.last())
+            .cloned()
+    }
+
     // Return the head-index for the selection
     fn get_current_head_index(&self) -> Option<usize> {
         get_head_index(&self.head, &self.log_output)
@@ -221,9 +616,99 @@ impl<'a> LogPanel<'a> {
     /// in unit head-index. Moving the head-index this much causes a
     /// full page scroll.
     fn visible_heads(&self) -> u16 {
-        // Every item in the log list is 2 lines high, so divide screen rows
-        // by 2 to get the number of log items that fit in it.
-        self.log_rect.height / 2
+        // Divide screen rows by the (approximate) height of an item in the
+        // log list to get the number of log items that fit in it.
+        self.log_rect.height / self.log_density.lines_per_change()
+    }
+
+    /// The changes above and below the selected one
+    pub fn neighbor_heads(&self) -> Vec<Head> {
+        let (Ok(log_output), Some(index)) =
+            (self.log_output.as_ref(), self.get_current_head_index())
+        else {
+            return vec![];
+        };
+        [index.checked_sub(1), index.checked_add(1)]
+            .into_iter()
+            .flatten()
+            .filter_map(|index| log_output.heads.get(index).cloned())
+            .collect()
+    }
+
+    /// Descriptions of the changes in the log
+    pub fn descriptions(&self) -> impl Iterator<Item = &str> {
+        self.log_output
+            .iter()
+            .flat_map(|log_output| &log_output.revisions)
+            .map(|revision| revision.description.as_str())
+    }
+
+    /// Structured information about the selected change, if the log could be parsed
+    pub fn selected_revision(&self) -> Option<&LogRevision> {
+        self.log_output.as_ref().ok()?.revision(&self.head)
+    }
+
+    /// Mark the selected change as a parent of a merge, or unmark it if it is marked
+    pub fn toggle_mark(&mut self) {
+        let Some(revision) = self.selected_revision().cloned() else {
+            return;
+        };
+        let marked_count = self.marked.len();
+        self.marked
+            .retain(|marked| marked.change_id != revision.change_id);
+        if self.marked.len() == marked_count {
+            self.marked.push(revision);
+        }
+    }
+
+    /// The marked changes, updated to their latest revision in the log
+    pub fn marked_revisions(&self) -> Vec<LogRevision> {
+        self.marked
+            .iter()
+            .map(|marked| {
+                self.log_output
+                    .as_ref()
+                    .ok()
+                    .and_then(|log_output| {
+                        log_output
+                            .revisions
+                            .iter()
+                            .find(|revision| revision.change_id == marked.change_id)
+                    })
+                    .unwrap_or(marked)
+                    .clone()
+            })
+            .collect()
+    }
+
+    pub fn clear_marks(&mut self) {
+        self.marked.clear();
+    }
+
+    /// Keep the selection on the same change after the log was refreshed. If the selected commit
+    /// was rewritten, the new commit of the change is selected. If the change is gone, the change
+    /// now at the previous position `head_index` is selected.
+    fn keep_selection(&mut self, head_index: Option<usize>) {
+        let (Ok(log_output), Some(head_index)) = (self.log_output.as_ref(), head_index) else {
+            return;
+        };
+        if log_output.heads.contains(&self.head) {
+            return;
+        }
+
+        let mut same_change = log_output
+            .heads
+            .iter()
+            .filter(|head| head.change_id == self.head.change_id);
+        let next_head = match (same_change.next(), same_change.next()) {
+            (Some(head), None) => Some(head),
+            _ => log_output
+                .heads
+                .get(head_index.min(log_output.heads.len().saturating_sub(1))),
+        };
+        if let Some(next_head) = next_head {
+            self.set_head(next_head.clone());
+        }
     }
 
     /// Move selection to a specific head. This may cause the next draw to
@@ -297,30 +782,129 @@ impl Component for LogPanel<'_> {
         Ok(())
     }
 
-    fn update(&mut self, _commander: &mut Commander) -> Result<Option<ComponentAction>> {
+    fn update(&mut self, commander: &mut Commander) -> Result<Option<ComponentAction>> {
+        self.receive_hindsight();
+        if self.config.ci_status_command().is_some()
+            && let Ok(log_output) = self.log_output.as_ref()
+        {
+            // Only the changes on screen, the command is slow and may be rate limited
+            let offset = self.log_list_state.offset();
+            self.ci_statuses.prefetch(
+                commander,
+                log_output
+                    .graph_heads
+                    .iter()
+                    .skip(offset)
+                    .take(self.log_rect.height as usize)
+                    .flatten()
+                    .map(|head| &head.commit_id),
+            );
+        }
         Ok(None)
     }
 
+    /// Not busy while querying CI statuses, which may take long. They are shown once the screen
+    /// is drawn again.
+    fn is_busy(&self) -> bool {
+        self.is_wrapping()
+    }
+
     fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
         self.panel_rect = area;
 
-        let title = match &self.log_revset {
-            Some(log_revset) => &format!(" Log for: {log_revset} "),
-            None => " Log ",
+        let title = match (&self.log_revset, &self.log_path) {
+            (Some(log_revset), Some(log_path)) => &format!(" Log for: {log_revset} › {log_path} "),
+            (Some(log_revset), None) => &format!(" Log for: {log_revset} "),
+            (None, Some(log_path)) => &format!(" Log › {log_path} "),
+            (None, None) => " Log ",
+        };
+        let mut title = match self.log_density {
+            LogDensity::Normal => title.to_owned(),
+            log_density => format!("{title}({log_density}) "),
         };
+        for filter in &self.log_filters {
+            title = format!("{title}[{filter}] ");
+        }
+        if !self.marked.is_empty() {
+            title = format!("{title}({} marked) ", self.marked.len());
+        }
+        if !self.log_path_renames.is_empty() {
+            title = format!(
+                "{title}(renamed from {}) ",
+                self.log_path_renames.join(" ← ")
+            );
+        }
+        if self.follow_working_copy {
+            title = format!("{title}(following @) ");
+        }
+        if !self.hindsight_changes.is_empty() {
+            title = format!(
+                "{title}({} changed since seen) ",
+                self.hindsight_changes.len()
+            );
+        }
 
-        let log_lines = self.log_lines();
-        let log_length: usize = log_lines.len();
+        let log_length = match self.log_output.as_ref() {
+            Ok(_) => self.log_output_text.lines.len(),
+            Err(_) => self.log_lines().len(),
+        };
         let log_block = Block::bordered()
             .title(title)
-            .border_type(BorderType::Rounded);
-        self.log_rect = log_block.inner(area);
-        self.log_list_state.select(self.selected_log_line());
-        let log = List::new(log_lines).block(log_block).scroll_padding(7);
-        f.render_stateful_widget(log, area, &mut self.log_list_state);
+            .style(panel_content_style(self.focused))
+            .border_type(BorderType::Rounded)
+            .border_style(panel_border_style(self.focused, self.config.focus_color()));
+        let inner = log_block.inner(area);
+        f.render_widget(log_block, area);
+
+        // Replace the scrollbar with the minimap if the log is much longer than the screen
+        self.minimap_rect = None;
+        self.log_rect = inner;
+        if self.show_minimap
+            && log_length > inner.height as usize * MINIMAP_MIN_SCREENS
+            && inner.width > MINIMAP_WIDTH * 4
+        {
+            let [log_rect, minimap_rect] =
+                Layout::horizontal([Constraint::Fill(1), Constraint::Length(MINIMAP_WIDTH)])
+                    .areas(inner);
+            self.log_rect = log_rect;
+            self.minimap_rect = Some(minimap_rect);
+        }
 
-        // Show scrollbar if lines don't fit the screen height
-        if log_length > self.log_rect.height.into() {
+        // The log lines are wrapped again once resizing settled, wrapping every intermediate
+        // width of a window being resized would be slow for long logs
+        if self.config.log_word_wrap() {
+            let wrap_width = self.log_rect.width.saturating_sub(self.badges_width());
+            if wrap_width != self.wrap_width {
+                self.wrap_width = wrap_width;
+                self.resized_at = Instant::now();
+            }
+            if self.is_wrapping() && self.resized_at.elapsed() >= RESIZE_SETTLE_TIME {
+                self.wrap_cache
+                    .wrap(&self.log_output_text.lines, self.wrap_width);
+            }
+        }
+        let log_lines = self.log_lines();
+
+        self.log_list_state.select(self.selected_log_line());
+        let log = List::new(log_lines)
+            .style(panel_content_style(self.focused))
+            .scroll_padding(7);
+        f.render_stateful_widget(log, self.log_rect, &mut self.log_list_state);
+
+        if let Some(minimap_rect) = self.minimap_rect {
+            let offset = self.log_list_state.offset();
+            let selected = self.selected_log_line();
+            draw_minimap(
+                f,
+                minimap_rect,
+                &mut self.minimap_cache,
+                &self.log_output_text.lines,
+                offset..offset + self.log_rect.height as usize,
+                selected,
+                self.config.highlight_color(),
+            );
+        } else if log_length > self.log_rect.height.into() {
+            // Show scrollbar if lines don't fit the screen height
             let index = self.log_list_state.selected().unwrap_or(0);
             let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight);
             let mut scrollbar_state = ScrollbarState::default()
@@ -348,6 +932,25 @@ impl Component for LogPanel<'_> {
                 return Ok(ComponentInputResult::NotHandled);
             }
 
+            // Jump to the change at the clicked row of the minimap
+            if let Some(minimap_rect) = self.minimap_rect
+                && minimap_rect.contains(mouse_pos)
+                && matches!(
+                    mouse_event.kind,
+                    MouseEventKind::Down(_) | MouseEventKind::Drag(_)
+                )
+            {
+                let line = minimap_line(
+                    self.log_output_text.lines.len(),
+                    minimap_rect.height,
+                    mouse_pos.y - minimap_rect.y,
+                );
+                if let Some(head) = self.head_at_or_after_log_line(line) {
+                    self.set_head(head);
+                }
+                return Ok(ComponentInputResult::Handled);
+            }
+
             // Execute command dependent on panel and event kind
             match mouse_event.kind {
                 MouseEventKind::ScrollUp => {
@@ -363,10 +966,8 @@ impl Component for LogPanel<'_> {
 
                     // TODO make a function that constructs the log list
                     let log_lines = self.log_lines();
-                    let log_items: Vec<ListItem> = log_lines
-                        .iter()
-                        .map(|line| ListItem::from(line.to_text()))
-                        .collect();
+                    let log_items: Vec<ListItem> =
+                        log_lines.into_iter().map(ListItem::from).collect();
 
                     // Select the clicked change
                     if let Some(inx) = list_item_from_mouse_event(
@@ -400,12 +1001,13 @@ fn list_item_from_mouse_event(
         return None;
     }
 
-    // Assume that each item is exactly one line.
-    // This is not true in the general case, but it is in this module.
-    let mouse_offset = mouse_pos.y - list_rect.y;
-    let item_index = list_state.offset() + mouse_offset as usize;
-    if item_index >= list.len() {
-        return None;
+    // Items are several lines high if they are wrapped
+    let mut mouse_offset = (mouse_pos.y - list_rect.y) as usize;
+    for (item_index, item) in list.iter().enumerate().skip(list_state.offset()) {
+        if mouse_offset < item.height() {
+            return Some(item_index);
+        }
+        mouse_offset -= item.height();
     }
-    Some(item_index)
+    None
 }
@@ -0,0 +1,284 @@
+/*! Wrapping of the log lines to the width of the log panel, with
+`ui.log-word-wrap`. jj can't wrap the log itself, as it is queried with
+several templates which must have the same number of lines per change.
+
+The continuation lines repeat the edges of the graph, like jj does for the
+lines of a description. Each log line stays a single item of the list, so the
+selection and the minimap don't depend on the width. The wrapped lines of the
+last few widths are kept, so switching between a full screen and a split
+terminal doesn't wrap the whole log again. */
+
+use std::collections::VecDeque;
+
+use ratatui::{
+    style::Style,
+    text::{Line, Span, StyledGrapheme},
+};
+
+use crate::ui::panel::minimap::NODE_CHARS;
+
+/// Number of widths the wrapped lines are kept for
+const CACHED_WIDTHS: usize = 4;
+
+/// Lines whose content would be narrower than this aren't wrapped, they are cut off instead
+const MIN_CONTENT_WIDTH: usize = 10;
+
+/// Whether the grapheme is part of the graph before the content of a log line
+fn is_graph(symbol: &str) -> bool {
+    symbol.chars().all(|c| {
+        c == ' ' || c == '~' || NODE_CHARS.contains(&c) || ('\u{2500}'..='\u{257f}').contains(&c)
+    })
+}
+
+fn grapheme_width(symbol: &str) -> usize {
+    Span::raw(symbol).width()
+}
+
+/// Spans of the graphemes, joining neighbors of the same style
+fn graphemes_to_line<'a>(graphemes: &[StyledGrapheme]) -> Line<'a> {
+    let mut spans: Vec<Span<'a>> = vec![];
+    for grapheme in graphemes {
+        match spans.last_mut() {
+            Some(span) if span.style == grapheme.style => {
+                span.content.to_mut().push_str(grapheme.symbol)
+            }
+            _ => spans.push(Span::styled(grapheme.symbol.to_owned(), grapheme.style)),
+        }
+    }
+    Line::from(spans)
+}
+
+/// Wrap a log line to `width` columns. `next` is the line below, the edges of the graph
+/// continuing into it are repeated on the continuation lines.
+pub fn wrap_line<'a>(line: &Line<'a>, next: Option<&Line>, width: usize) -> Vec<Line<'a>> {
+    let graphemes: Vec<StyledGrapheme> = line.styled_graphemes(Style::default()).collect();
+    let graph_len = graphemes
+        .iter()
+        .take_while(|grapheme| is_graph(grapheme.symbol))
+        .count();
+    let content_width = width.saturating_sub(graph_len);
+    if line.width() <= width || content_width < MIN_CONTENT_WIDTH {
+        return vec![line.clone()];
+    }
+
+    let next_graph: Vec<char> = next
+        .map(|next| next.to_string().chars().take(graph_len).collect())
+        .unwrap_or_default();
+    let continuation: String = graphemes[..graph_len]
+        .iter()
+        .enumerate()
+        .map(|(column, grapheme)| {
+            let is_edge =
+                grapheme.symbol == "│" || grapheme.symbol.chars().any(|c| NODE_CHARS.contains(&c));
+            let continues = next_graph.get(column).is_some_and(|c| !c.is_whitespace());
+            if is_edge && continues { '│' } else { ' ' }
+        })
+        .collect();
+
+    // Break after the last space fitting the row, or within the word if there is none
+    let content = &graphemes[graph_len..];
+    let mut rows = vec![];
+    let mut start = 0;
+    while start < content.len() {
+        let mut end = start;
+        let mut used = 0;
+        let mut last_space = None;
+        while let Some(grapheme) = content.get(end) {
+            let symbol_width = grapheme_width(grapheme.symbol);
+            if used + symbol_width > content_width {
+                break;
+            }
+            used += symbol_width;
+            if grapheme.symbol == " " {
+                last_space = Some(end);
+            }
+            end += 1;
+        }
+        if end < content.len()
+            && let Some(space) = last_space
+            && space > start
+        {
+            end = space + 1;
+        }
+        // A grapheme wider than the row
+        end = end.max(start + 1);
+        rows.push(&content[start..end]);
+        start = end;
+    }
+
+    rows.into_iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let mut wrapped = graphemes_to_line(row);
+            if i == 0 {
+                let graph = graphemes_to_line(&graphemes[..graph_len]);
+                wrapped.spans.splice(0..0, graph.spans);
+            } else {
+                wrapped.spans.insert(0, Span::raw(continuation.clone()));
+            }
+            wrapped
+        })
+        .collect()
+}
+
+/// Wrapped log lines of the last few widths, the most recently used last
+#[derive(Default)]
+pub struct WrapCache<'a> {
+    entries: VecDeque<(u16, Vec<Vec<Line<'a>>>)>,
+}
+
+impl<'a> WrapCache<'a> {
+    /// Drop the wrapped lines, e.g. when the log changed
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn contains(&self, width: u16) -> bool {
+        self.entries.iter().any(|(other, _)| *other == width)
+    }
+
+    /// The wrapped lines of each log line for the width, if they were wrapped for it
+    pub fn get(&self, width: u16) -> Option<&[Vec<Line<'a>>]> {
+        self.entries
+            .iter()
+            .find(|(other, _)| *other == width)
+            .map(|(_, wrapped)| wrapped.as_slice())
+    }
+
+    /// Wrap the lines for the width, unless they were already wrapped for it
+    pub fn wrap(&mut self, lines: &[Line<'a>], width: u16) {
+        if let Some(index) = self.entries.iter().position(|(other, _)| *other == width) {
+            if let Some(entry) = self.entries.remove(index) {
+                self.entries.push_back(entry);
+            }
+            return;
+        }
+        let wrapped = lines
+            .iter()
+            .enumerate()
+            .map(|(i, line)| wrap_line(line, lines.get(i + 1), width as usize))
+            .collect();
+        self.entries.push_back((width, wrapped));
+        while self.entries.len() > CACHED_WIDTHS {
+            self.entries.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::style::Stylize;
+
+    use super::*;
+
+    fn rows(lines: &[Line]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn wrap_at_width() {
+        let line = Line::raw("○  0123456789 012345");
+        assert_eq!(wrap_line(&line, None, 20), vec![line.clone()]);
+        assert_eq!(
+            rows(&wrap_line(&line, None, 19)),
+            ["○  0123456789 ", "   012345"]
+        );
+    }
+
+    #[test]
+    fn wrap_within_word() {
+        let line = Line::raw("○  0123456789abcdef");
+        assert_eq!(
+            rows(&wrap_line(&line, None, 13)),
+            ["○  0123456789", "   abcdef"]
+        );
+    }
+
+    #[test]
+    fn no_wrap_below_min_content_width() {
+        let line = Line::raw("○  0123456789 012345");
+        assert_eq!(wrap_line(&line, None, 12), vec![line.clone()]);
+    }
+
+    #[test]
+    fn wrap_wide_chars() {
+        let line = Line::raw("○  日本語日本語日本語");
+        let next = Line::raw("│  next");
+        assert_eq!(
+            rows(&wrap_line(&line, Some(&next), 13)),
+            ["○  日本語日本", "│  語日本語"]
+        );
+        // A wide char is not split, even if one column is left
+        assert_eq!(
+            rows(&wrap_line(&line, None, 14)),
+            ["○  日本語日本", "   語日本語"]
+        );
+    }
+
+    #[test]
+    fn wrap_graph_edges() {
+        let line = Line::raw("│ ○  0123456789 012345");
+        let next = Line::raw("├─╯  next");
+        assert_eq!(
+            rows(&wrap_line(&line, Some(&next), 21)),
+            ["│ ○  0123456789 ", "│ │  012345"]
+        );
+        let next = Line::raw("  ○  next");
+        assert_eq!(
+            rows(&wrap_line(&line, Some(&next), 21)),
+            ["│ ○  0123456789 ", "  │  012345"]
+        );
+    }
+
+    #[test]
+    fn wrap_styled_spans() {
+        let line = Line::from(vec![
+            "○".magenta(),
+            "  ".into(),
+            "0123".bold(),
+            "456789 ".bold(),
+            "012345".red(),
+        ]);
+        let wrapped = wrap_line(&line, None, 19);
+        assert_eq!(
+            wrapped[0].spans,
+            ["○".magenta(), Span::raw("  "), "0123456789 ".bold()]
+        );
+        assert_eq!(wrapped[1].spans, [Span::raw("   "), "012345".red()]);
+    }
+
+    #[test]
+    fn cache_per_width() {
+        let lines = [Line::raw("○  0123456789 012345"), Line::raw("~")];
+        let mut cache = WrapCache::default();
+        cache.wrap(&lines, 19);
+        assert!(cache.contains(19));
+        assert!(!cache.contains(20));
+        assert_eq!(cache.get(19).map(|wrapped| wrapped[0].len()), Some(2));
+        assert!(cache.get(20).is_none());
+
+        cache.wrap(&lines, 20);
+        assert_eq!(cache.get(20).map(|wrapped| wrapped[0].len()), Some(1));
+        assert_eq!(cache.get(19).map(|wrapped| wrapped[0].len()), Some(2));
+
+        cache.clear();
+        assert!(!cache.contains(19));
+        assert!(!cache.contains(20));
+    }
+
+    #[test]
+    fn cache_evicts_least_recently_used() {
+        let lines = [Line::raw("○  0123456789 012345")];
+        let mut cache = WrapCache::default();
+        for width in [19, 20, 21, 22] {
+            cache.wrap(&lines, width);
+        }
+        cache.wrap(&lines, 19);
+        cache.wrap(&lines, 23);
+        assert!(cache.contains(19));
+        assert!(!cache.contains(20));
+        assert!(cache.contains(21));
+        assert!(cache.contains(22));
+        assert!(cache.contains(23));
+    }
+}
@@ -0,0 +1,208 @@
+/*! A minimap of the log graph, shown next to the log when it is much
+longer than the screen. Each row of the minimap stands for a range of log
+lines and shows which lanes of the graph are used in it, the rows in view
+are highlighted. */
+
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Color, Style, Stylize},
+    text::{Line, Span, Text},
+    widgets::Paragraph,
+};
+
+/// Number of graph lanes shown in the minimap
+const MINIMAP_LANES: usize = 4;
+
+/// Width of the minimap column, the lanes and a separating space
+pub const MINIMAP_WIDTH: u16 = MINIMAP_LANES as u16 + 1;
+
+/// The minimap is shown if the log is this many times longer than the screen
+pub const MINIMAP_MIN_SCREENS: usize = 3;
+
+/// Characters of graph nodes, as opposed to edges
+pub(super) const NODE_CHARS: [char; 6] = ['@', '○', '◆', '×', '●', '◉'];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+enum Lane {
+    #[default]
+    Empty,
+    Edge,
+    Node,
+}
+
+/// Lanes used by a log line, from the graph before the template output. Lanes are 2 columns
+/// apart in jj's graph.
+fn line_lanes(line: &Line) -> [Lane; MINIMAP_LANES] {
+    let mut lanes = [Lane::Empty; MINIMAP_LANES];
+    let text = line.to_string();
+    let graph = text
+        .chars()
+        .take_while(|c| !c.is_alphanumeric())
+        .step_by(2)
+        .take(MINIMAP_LANES);
+    for (lane, c) in lanes.iter_mut().zip(graph) {
+        *lane = if NODE_CHARS.contains(&c) {
+            Lane::Node
+        } else if c.is_whitespace() {
+            Lane::Empty
+        } else {
+            Lane::Edge
+        };
+    }
+    lanes
+}
+
+/// Number of log lines per minimap row
+fn lines_per_row(line_count: usize, height: u16) -> usize {
+    line_count.div_ceil(height.max(1) as usize).max(1)
+}
+
+/// Lanes used by each minimap row of `height` rows, a node if any of its lines has one
+fn row_lanes(lines: &[Line], height: u16) -> Vec<[Lane; MINIMAP_LANES]> {
+    lines
+        .chunks(lines_per_row(lines.len(), height))
+        .map(|chunk| {
+            let mut lanes = [Lane::Empty; MINIMAP_LANES];
+            for line in chunk {
+                for (lane, line_lane) in lanes.iter_mut().zip(line_lanes(line)) {
+                    if line_lane != Lane::Empty && *lane != Lane::Node {
+                        *lane = line_lane;
+                    }
+                }
+            }
+            lanes
+        })
+        .collect()
+}
+
+/// Lanes of the minimap rows, computed once per log output and height of the minimap
+#[derive(Default)]
+pub struct MinimapCache {
+    rows: Option<(u16, Vec<[Lane; MINIMAP_LANES]>)>,
+}
+
+impl MinimapCache {
+    /// Drop the lanes, e.g. when the log changed
+    pub fn clear(&mut self) {
+        self.rows = None;
+    }
+
+    fn rows(&mut self, lines: &[Line], height: u16) -> &[[Lane; MINIMAP_LANES]] {
+        if self.rows.as_ref().is_none_or(|(other, _)| *other != height) {
+            self.rows = Some((height, row_lanes(lines, height)));
+        }
+        self.rows.as_ref().map_or(&[], |(_, rows)| rows.as_slice())
+    }
+}
+
+/// Draw the minimap of `lines` into `area`. `viewport` is the range of visible log lines and
+/// `selected` the line of the selected change.
+pub fn draw_minimap(
+    f: &mut Frame<'_>,
+    area: Rect,
+    cache: &mut MinimapCache,
+    lines: &[Line],
+    viewport: std::ops::Range<usize>,
+    selected: Option<usize>,
+    highlight_color: Color,
+) {
+    let per_row = lines_per_row(lines.len(), area.height);
+    let rows: Vec<Line> = cache
+        .rows(lines, area.height)
+        .iter()
+        .enumerate()
+        .map(|(row, lanes)| {
+            let start = row * per_row;
+            let rows_lines = start..(start + per_row).min(lines.len());
+
+            let is_selected = selected.is_some_and(|selected| rows_lines.contains(&selected));
+            let in_view = rows_lines.start < viewport.end && viewport.start < rows_lines.end;
+            let mut spans = vec![Span::raw(" ")];
+            spans.extend(lanes.iter().map(|lane| {
+                let span = match lane {
+                    Lane::Empty => Span::raw(" "),
+                    Lane::Edge => Span::raw("│").fg(Color::DarkGray),
+                    Lane::Node => Span::raw("•"),
+                };
+                if is_selected {
+                    span.fg(Color::Yellow).bold()
+                } else {
+                    span
+                }
+            }));
+            let line = Line::from(spans);
+            if in_view {
+                line.style(Style::default().bg(highlight_color))
+            } else {
+                line
+            }
+        })
+        .collect();
+
+    f.render_widget(Paragraph::new(Text::from(rows)), area);
+}
+
+/// Log line shown at `row` of a minimap of `height` rows
+pub fn minimap_line(line_count: usize, height: u16, row: u16) -> usize {
+    (row as usize * lines_per_row(line_count, height)).min(line_count.saturating_sub(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use Lane::{Edge, Empty, Node};
+
+    #[test]
+    fn lanes_of_line() {
+        assert_eq!(
+            line_lanes(&Line::raw("@  abc 1 hour ago")),
+            [Node, Empty, Empty, Empty]
+        );
+        assert_eq!(
+            line_lanes(&Line::raw("│ ○  abc 1 hour ago")),
+            [Edge, Node, Empty, Empty]
+        );
+        assert_eq!(line_lanes(&Line::raw("├─╯")), [Edge, Edge, Empty, Empty]);
+        // Only the graph before the template output counts
+        assert_eq!(
+            line_lanes(&Line::raw("│  │ description")),
+            [Edge, Empty, Empty, Empty]
+        );
+        // Lanes beyond the minimap are left out
+        assert_eq!(
+            line_lanes(&Line::raw("│ │ │ │ ◆  abc")),
+            [Edge, Edge, Edge, Edge]
+        );
+    }
+
+    #[test]
+    fn lanes_of_rows() {
+        let lines = [
+            Line::raw("@  abc"),
+            Line::raw("│  description"),
+            Line::raw("│ ○  def"),
+            Line::raw("├─╯  description"),
+            Line::raw("◆  ghi"),
+        ];
+        assert_eq!(
+            row_lanes(&lines, 2),
+            vec![[Node, Node, Empty, Empty], [Node, Edge, Empty, Empty]]
+        );
+        assert_eq!(row_lanes(&lines, 10).len(), lines.len());
+
+        let mut cache = MinimapCache::default();
+        assert_eq!(cache.rows(&lines, 2).len(), 2);
+        assert_eq!(cache.rows(&lines, 5).len(), 5);
+    }
+
+    #[test]
+    fn line_at_row() {
+        assert_eq!(minimap_line(100, 10, 0), 0);
+        assert_eq!(minimap_line(100, 10, 3), 30);
+        assert_eq!(minimap_line(95, 10, 9), 90);
+        // Rows past the last line jump to it
+        assert_eq!(minimap_line(5, 10, 8), 4);
+    }
+}
@@ -1,5 +1,7 @@
 mod details_panel;
 mod log_panel;
+mod log_wrap;
+mod minimap;
 
 pub use details_panel::DetailsPanel;
 pub use log_panel::LogPanel;
@@ -0,0 +1,275 @@
+//! The push preview popup shows what a `jj git push` would do before running it: the bookmark
+//! updates, the commits not yet on the remote and whether the push rewrites remote history.
+//! If `blazingjj.push-test-command` is set, it runs before pushing and a failure aborts the push,
+//! unless the tests are skipped with `s`.
+
+use anyhow::Result;
+use ratatui::{
+    Frame,
+    crossterm::event::{Event, KeyCode, KeyEventKind},
+    layout::Rect,
+    style::{Color, Style, Stylize},
+    text::{Line, Span, Text},
+    widgets::{Clear, Paragraph, Wrap},
+};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+use crate::{
+    ComponentInputResult,
+    commander::{
+        CommandError, Commander,
+        ids::CommitId,
+        push::{BookmarkPushKind, PushPreview},
+    },
+    ui::{
+        Component, ComponentAction, credentials_popup::CredentialsPopup, loader_popup::LoaderPopup,
+        message_popup::MessagePopup, remote_error_popup::RemoteErrorPopup,
+        styles::create_popup_block, utils::centered_rect,
+    },
+};
+
+type PreviewResult = Result<PushPreview, CommandError>;
+
+pub struct PushPreviewPopup {
+    all_bookmarks: bool,
+    allow_new: bool,
+    commit_id: CommitId,
+    result_rx: Receiver<PreviewResult>,
+    preview: Option<PushPreview>,
+    /// Command run before pushing
+    test_command: Option<String>,
+}
+
+impl PushPreviewPopup {
+    /// Create a new push preview popup. The dry run is started immediately in a background thread.
+    pub fn new(
+        commander: &Commander,
+        all_bookmarks: bool,
+        allow_new: bool,
+        commit_id: CommitId,
+    ) -> Self {
+        let (tx, rx) = mpsc::channel();
+
+        let commander_clone = Commander::new(&commander.env);
+        let preview_commit_id = commit_id.clone();
+        thread::spawn(move || {
+            tx.send(commander_clone.git_push_preview(all_bookmarks, allow_new, &preview_commit_id))
+        });
+
+        Self {
+            all_bookmarks,
+            allow_new,
+            commit_id,
+            result_rx: rx,
+            preview: None,
+            test_command: commander.env.config.push_test_command().map(String::from),
+        }
+    }
+
+    fn push_args(&self) -> Vec<String> {
+        Commander::git_push_args(self.all_bookmarks, self.allow_new, &self.commit_id)
+    }
+
+    /// Replace the preview with a loader running the tests, unless they are skipped, and the
+    /// actual push. The tests can be cancelled.
+    fn push(&self, commander: &Commander, skip_tests: bool) -> ComponentAction {
+        let (all_bookmarks, allow_new) = (self.all_bookmarks, self.allow_new);
+        let commit_id = self.commit_id.clone();
+        let commander_clone = Commander::new(&commander.env);
+
+        let test_command = self.test_command.clone().filter(|_| !skip_tests);
+        let loader = match test_command {
+            Some(test_command) => {
+                let (progress_tx, progress_rx) = mpsc::channel();
+                LoaderPopup::new("Testing and pushing".to_string(), move || {
+                    let _ = progress_tx.send(format!("Running {test_command}"));
+                    let revision = (!all_bookmarks).then_some(&commit_id);
+                    commander_clone.run_push_test(revision)?;
+                    let _ = progress_tx.send("Pushing".to_owned());
+                    commander_clone.git_push(all_bookmarks, allow_new, &commit_id)
+                })
+                .with_progress(progress_rx)
+                .cancellable()
+            }
+            None => LoaderPopup::new("Pushing".to_string(), move || {
+                commander_clone.git_push(all_bookmarks, allow_new, &commit_id)
+            }),
+        }
+        .with_terminal_retry(self.push_args());
+
+        ComponentAction::SetPopup(Some(Box::new(loader)))
+    }
+}
+
+fn preview_text(preview: &PushPreview, test_command: Option<&str>) -> Text<'static> {
+    let mut lines = vec![];
+
+    let remote = preview.remote.as_deref().unwrap_or("remote");
+    lines.push(Line::from(format!("Bookmarks to push to {remote}:")).bold());
+    for bookmark in &preview.bookmarks {
+        let (action, color) = match bookmark.kind {
+            BookmarkPushKind::Add => ("new", Color::Green),
+            BookmarkPushKind::MoveForward => ("move forward", Color::Cyan),
+            BookmarkPushKind::MoveSideways => ("move sideways (forced)", Color::Red),
+            BookmarkPushKind::MoveBackward => ("move backward (forced)", Color::Red),
+            BookmarkPushKind::Force => ("forced", Color::Red),
+            BookmarkPushKind::Delete => ("delete", Color::Yellow),
+        };
+        let target = match (&bookmark.old_target, &bookmark.new_target) {
+            (Some(old), Some(new)) => format!("{old} -> {new}"),
+            (None, Some(new)) => format!("-> {new}"),
+            (Some(old), None) => format!("{old} ->"),
+            (None, None) => String::new(),
+        };
+        lines.push(Line::from(vec![
+            Span::raw("  "),
+            Span::raw(bookmark.name.clone()).bold(),
+            Span::raw(" "),
+            Span::styled(action, Style::default().fg(color)),
+            Span::raw(format!(" {target}")).fg(Color::DarkGray),
+        ]));
+    }
+
+    lines.push(Line::raw(""));
+    if preview.commits.is_empty() {
+        lines.push(Line::from("No new commits").bold());
+    } else {
+        lines.push(Line::from(format!("Commits to push ({}):", preview.commits.len())).bold());
+        for commit in &preview.commits {
+            lines.push(Line::raw(format!("  {commit}")));
+        }
+    }
+
+    if preview.is_forced() {
+        lines.push(Line::raw(""));
+        lines.push(
+            Line::from("Warning: this push rewrites history on the remote")
+                .red()
+                .bold(),
+        );
+    }
+
+    lines.push(Line::raw(""));
+    match test_command {
+        Some(test_command) => {
+            lines.push(Line::from(format!("Tests to run first: {test_command}")));
+            lines.push(Line::from("Test and push? (y/n, s: push without testing)").bold());
+        }
+        None => lines.push(Line::from("Push? (y/n)").bold()),
+    }
+
+    Text::from(lines)
+}
+
+impl Component for PushPreviewPopup {
+    fn update(&mut self, commander: &mut Commander) -> Result<Option<ComponentAction>> {
+        if self.preview.is_some() {
+            return Ok(None);
+        }
+
+        let Ok(result) = self.result_rx.try_recv() else {
+            return Ok(None);
+        };
+
+        match result {
+            Ok(preview) if preview.is_empty() => Ok(Some(ComponentAction::SetPopup(Some(
+                Box::new(MessagePopup {
+                    title: "Push".into(),
+                    messages: "Nothing to push".into(),
+                    text_align: None,
+                    ..Default::default()
+                }),
+            )))),
+            Ok(preview) => {
+                self.preview = Some(preview);
+                Ok(None)
+            }
+            Err(err) if err.needs_credentials() => {
+                Ok(Some(ComponentAction::SetPopup(Some(Box::new(
+                    CredentialsPopup::new("Push preview", &err, self.push_args())?,
+                )))))
+            }
+            Err(err) => {
+                if let Some(kind) = err.remote_error() {
+                    return Ok(Some(ComponentAction::SetPopup(Some(Box::new(
+                        RemoteErrorPopup::new(
+                            commander,
+                            "Push preview",
+                            kind,
+                            &err,
+                            Some(self.push_args()),
+                        )?,
+                    )))));
+                }
+                Ok(Some(ComponentAction::SetPopup(Some(Box::new(
+                    MessagePopup {
+                        title: "Push preview error".into(),
+                        messages: commander.error_text(&err, "")?,
+                        text_align: None,
+                        ..Default::default()
+                    },
+                )))))
+            }
+        }
+    }
+
+    fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+        let area = centered_rect(area, 60, 60);
+        f.render_widget(Clear, area);
+
+        let text = match &self.preview {
+            Some(preview) => preview_text(preview, self.test_command.as_deref()),
+            None => Text::from("Preparing push..."),
+        };
+        let paragraph = Paragraph::new(text)
+            .block(create_popup_block("Push preview"))
+            .wrap(Wrap { trim: false });
+        f.render_widget(paragraph, area);
+
+        Ok(())
+    }
+
+    fn input(&mut self, commander: &mut Commander, event: Event) -> Result<ComponentInputResult> {
+        if let Event::Key(key) = event
+            && key.kind == KeyEventKind::Press
+        {
+            match key.code {
+                KeyCode::Char('y') | KeyCode::Enter if self.preview.is_some() => {
+                    return Ok(ComponentInputResult::HandledAction(
+                        self.push(commander, false),
+                    ));
+                }
+                KeyCode::Char('s') if self.preview.is_some() && self.test_command.is_some() => {
+                    return Ok(ComponentInputResult::HandledAction(
+                        self.push(commander, true),
+                    ));
+                }
+                // Let the app close the popup
+                KeyCode::Char('n') | KeyCode::Char('q') | KeyCode::Esc => {
+                    return Ok(ComponentInputResult::NotHandled);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(ComponentInputResult::Handled)
+    }
+
+    fn hints(&self) -> Vec<(String, String)> {
+        if self.preview.is_none() {
+            return vec![("Esc".to_owned(), "cancel".to_owned())];
+        }
+        if self.test_command.is_some() {
+            return vec![
+                ("y/Enter".to_owned(), "test and push".to_owned()),
+                ("s".to_owned(), "push without testing".to_owned()),
+                ("n/Esc".to_owned(), "cancel".to_owned()),
+            ];
+        }
+        vec![
+            ("y/Enter".to_owned(), "push".to_owned()),
+            ("n/Esc".to_owned(), "cancel".to_owned()),
+        ]
+    }
+}
@@ -222,7 +222,7 @@ Example:
 (*) lemons
 ~~~
 */
-struct RadioButton {
+pub struct RadioButton {
     /// Button labels
     pub labels: Vec<String>,
     /// Button style can be modified before drawing
@@ -0,0 +1,206 @@
+//! Popup with the actions recovering a working copy jj refuses to use, each with an explanation of
+//! when it helps, so they don't have to be looked up while things are broken.
+
+use anyhow::Result;
+use ratatui::{
+    Frame,
+    crossterm::event::{Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{Clear, List, ListState, Paragraph, Wrap},
+};
+
+use crate::{
+    ComponentInputResult,
+    commander::Commander,
+    ui::{
+        Component, ComponentAction, loader_popup::LoaderPopup, operations_popup::OperationsPopup,
+        read_only_popup, styles::create_popup_block, utils::centered_rect,
+    },
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RecoveryAction {
+    UpdateStale,
+    RestoreOperation,
+    Recheckout,
+}
+
+const ACTIONS: [RecoveryAction; 3] = [
+    RecoveryAction::UpdateStale,
+    RecoveryAction::RestoreOperation,
+    RecoveryAction::Recheckout,
+];
+
+impl RecoveryAction {
+    fn description(&self) -> &'static str {
+        match self {
+            RecoveryAction::UpdateStale => {
+                "Update a stale working copy (jj workspace update-stale)"
+            }
+            RecoveryAction::RestoreOperation => "Restore an earlier operation (jj op restore)",
+            RecoveryAction::Recheckout => "Check out the working copy anew",
+        }
+    }
+
+    /// When the action helps, in plain language
+    fn explanation(&self) -> &'static str {
+        match self {
+            RecoveryAction::UpdateStale => {
+                "Helps when jj says the working copy is stale, e.g. after the repository was \
+                 changed from another workspace or a command was interrupted. The files on disk \
+                 are updated to the change the working copy should be at."
+            }
+            RecoveryAction::RestoreOperation => {
+                "Helps when a command left the repository in a state you don't want, e.g. \
+                 changes are missing or were rewritten. Pick the last operation that looks right \
+                 from the operation log; the operations after it can be restored again later."
+            }
+            RecoveryAction::Recheckout => {
+                "Helps when jj fails reading the working copy itself, e.g. after a crash or a \
+                 full disk. The working copy state in .jj/working_copy is moved aside and \
+                 recreated, and files differing from the change are kept in a recovery change. \
+                 Try updating the stale working copy first."
+            }
+        }
+    }
+
+    /// Whether the action changes the repository right away and has to be confirmed
+    fn needs_confirmation(&self) -> bool {
+        !matches!(self, RecoveryAction::RestoreOperation)
+    }
+
+    fn popup(self, commander: &Commander) -> Box<dyn Component> {
+        let commander_clone = Commander::new(&commander.env);
+        match self {
+            RecoveryAction::UpdateStale => Box::new(LoaderPopup::new(
+                "Updating the working copy".to_owned(),
+                move || commander_clone.update_stale_workspace(),
+            )),
+            RecoveryAction::RestoreOperation => Box::new(OperationsPopup::new(commander)),
+            RecoveryAction::Recheckout => Box::new(LoaderPopup::new(
+                "Checking out the working copy".to_owned(),
+                move || commander_clone.recheckout_working_copy(),
+            )),
+        }
+    }
+}
+
+pub struct RecoveryPopup {
+    list_state: ListState,
+    /// Whether the selected action waits for confirmation
+    confirming: bool,
+}
+
+impl RecoveryPopup {
+    pub fn new() -> Self {
+        Self {
+            list_state: ListState::default().with_selected(Some(0)),
+            confirming: false,
+        }
+    }
+
+    fn selected_action(&self) -> Option<RecoveryAction> {
+        self.list_state
+            .selected()
+            .and_then(|selected| ACTIONS.get(selected).copied())
+    }
+}
+
+impl Component for RecoveryPopup {
+    fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+        let area = centered_rect(area, 60, 40);
+        f.render_widget(Clear, area);
+
+        let block = create_popup_block("Recovery");
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let [list_area, explanation_area, help_area] = Layout::vertical([
+            Constraint::Length(ACTIONS.len() as u16 + 1),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+        ])
+        .areas(inner);
+
+        let list = List::new(ACTIONS.iter().map(RecoveryAction::description))
+            .highlight_style(Style::default().reversed());
+        f.render_stateful_widget(list, list_area, &mut self.list_state);
+
+        if let Some(action) = self.selected_action() {
+            f.render_widget(
+                Paragraph::new(action.explanation()).wrap(Wrap { trim: true }),
+                explanation_area,
+            );
+        }
+
+        let help = if self.confirming {
+            Line::from("Press y to confirm, n to cancel").fg(Color::Yellow)
+        } else {
+            Line::from("Enter: run | Esc: close").fg(Color::DarkGray)
+        };
+        f.render_widget(Paragraph::new(help).centered(), help_area);
+
+        Ok(())
+    }
+
+    fn input(&mut self, commander: &mut Commander, event: Event) -> Result<ComponentInputResult> {
+        let Event::Key(key) = event else {
+            return Ok(ComponentInputResult::Handled);
+        };
+        if key.kind != KeyEventKind::Press {
+            return Ok(ComponentInputResult::Handled);
+        }
+
+        if self.confirming {
+            self.confirming = false;
+            if let (KeyCode::Char('y'), Some(action)) = (key.code, self.selected_action()) {
+                return Ok(ComponentInputResult::HandledAction(
+                    ComponentAction::SetPopup(Some(action.popup(commander))),
+                ));
+            }
+            return Ok(ComponentInputResult::Handled);
+        }
+
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => self.list_state.select_next(),
+            KeyCode::Char('k') | KeyCode::Up => self.list_state.select_previous(),
+            KeyCode::Enter if commander.env.config.read_only() => {
+                return Ok(ComponentInputResult::HandledAction(read_only_popup()));
+            }
+            KeyCode::Enter => match self.selected_action() {
+                Some(action) if !action.needs_confirmation() => {
+                    return Ok(ComponentInputResult::HandledAction(
+                        ComponentAction::SetPopup(Some(action.popup(commander))),
+                    ));
+                }
+                _ => self.confirming = true,
+            },
+            _ => return Ok(ComponentInputResult::NotHandled),
+        }
+        // select_next can move past the last action
+        if self
+            .list_state
+            .selected()
+            .is_some_and(|selected| selected >= ACTIONS.len())
+        {
+            self.list_state.select(Some(ACTIONS.len() - 1));
+        }
+        Ok(ComponentInputResult::Handled)
+    }
+
+    fn hints(&self) -> Vec<(String, String)> {
+        if self.confirming {
+            return vec![
+                ("y".to_owned(), "confirm".to_owned()),
+                ("n".to_owned(), "cancel".to_owned()),
+            ];
+        }
+        vec![
+            ("j/k".to_owned(), "select".to_owned()),
+            ("Enter".to_owned(), "run".to_owned()),
+            ("Esc".to_owned(), "close".to_owned()),
+        ]
+    }
+}
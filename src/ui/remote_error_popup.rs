@@ -0,0 +1,196 @@
+//! Shown when a fetch or push failed for a common reason, see [RemoteError]. Explains the cause
+//! above the output of git and offers what usually fixes it: retrying in the terminal, where git
+//! and ssh can prompt, fixing the URL of a remote, and fetching the changes a push would
+//! overwrite or, after a confirmation, overwriting them.
+
+use anyhow::Result;
+use ratatui::{
+    Frame,
+    crossterm::event::{Event, KeyCode, KeyEventKind},
+    layout::Rect,
+    style::{Color, Stylize},
+    text::{Line, Text},
+};
+
+use crate::{
+    ComponentInputResult,
+    commander::{CommandError, Commander, remote_errors::RemoteError},
+    ui::{
+        Component, ComponentAction, loader_popup::LoaderPopup, message_popup::MessagePopup,
+        remote_settings_popup::RemoteSettingsPopup,
+    },
+};
+
+pub struct RemoteErrorPopup {
+    message: MessagePopup<'static>,
+    kind: RemoteError,
+    /// jj arguments to rerun in the terminal
+    args: Option<Vec<String>>,
+    /// Bookmarks the rejected push would have overwritten on the remote
+    rejected_bookmarks: Vec<String>,
+    /// Whether overwriting the rejected bookmarks waits for confirmation
+    confirming_force_push: bool,
+}
+
+impl RemoteErrorPopup {
+    pub fn new(
+        commander: &Commander,
+        operation_name: &str,
+        kind: RemoteError,
+        err: &CommandError,
+        args: Option<Vec<String>>,
+    ) -> Result<Self> {
+        let mut messages = Text::from(vec![
+            Line::raw(kind.title()).bold().fg(Color::Red),
+            Line::raw(kind.hint()),
+        ]);
+        if kind.shows_remotes()
+            && let Ok(remotes) = commander.get_git_remotes()
+            && !remotes.is_empty()
+        {
+            messages.push_line(Line::raw(""));
+            messages.push_line(Line::raw("Remotes:").bold());
+            for (name, url) in remotes {
+                messages.push_line(Line::raw(format!("  {name} {url}")));
+            }
+        }
+        messages.push_line(Line::raw(""));
+        messages.push_line(Line::raw("Output:").bold());
+        messages.extend(commander.error_text(err, "")?.lines);
+
+        let mut actions = vec![];
+        if args.is_some() {
+            actions.push("r to retry in the terminal");
+        }
+        if kind.shows_remotes() {
+            actions.push("u to edit the remote URLs");
+        }
+        if kind == RemoteError::Rejected {
+            actions.push("f to fetch");
+        }
+        let rejected_bookmarks = err.rejected_bookmarks();
+        if kind == RemoteError::Rejected && !rejected_bookmarks.is_empty() {
+            actions.push("F to force-push");
+        }
+        if !actions.is_empty() {
+            messages.push_line(Line::raw(""));
+            messages.push_line(Line::raw(format!("Press {}", actions.join(", "))).bold());
+        }
+
+        Ok(Self {
+            message: MessagePopup {
+                title: format!("{operation_name} failed").into(),
+                messages,
+                text_align: None,
+                ..Default::default()
+            },
+            kind,
+            args,
+            rejected_bookmarks,
+            confirming_force_push: false,
+        })
+    }
+}
+
+impl Component for RemoteErrorPopup {
+    fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+        self.message.draw(f, area)
+    }
+
+    fn input(&mut self, commander: &mut Commander, event: Event) -> Result<ComponentInputResult> {
+        let Event::Key(key) = event else {
+            return self.message.input(commander, event);
+        };
+        if key.kind != KeyEventKind::Press || self.message.state.is_searching() {
+            return self.message.input(commander, event);
+        }
+
+        if self.confirming_force_push {
+            self.confirming_force_push = false;
+            let lines = &mut self.message.messages.lines;
+            lines.truncate(lines.len().saturating_sub(2));
+            if key.code != KeyCode::Char('y') {
+                return Ok(ComponentInputResult::Handled);
+            }
+            let commander_clone = Commander::new(&commander.env);
+            let bookmarks = self.rejected_bookmarks.clone();
+            let loader = LoaderPopup::new("Force-pushing".to_string(), move || {
+                commander_clone.git_force_push_bookmarks(&bookmarks)
+            });
+            return Ok(ComponentInputResult::HandledAction(
+                ComponentAction::SetPopup(Some(Box::new(loader))),
+            ));
+        }
+        if key.code == KeyCode::Char('F')
+            && self.kind == RemoteError::Rejected
+            && !self.rejected_bookmarks.is_empty()
+        {
+            self.confirming_force_push = true;
+            self.message.messages.push_line(Line::raw(""));
+            self.message.messages.push_line(
+                Line::raw(format!(
+                    "Overwrite {} on the remote with the local commits, dropping the changes \
+                     pushed there? Press y to force-push, any other key to cancel",
+                    self.rejected_bookmarks.join(", ")
+                ))
+                .bold()
+                .fg(Color::Red),
+            );
+            return Ok(ComponentInputResult::Handled);
+        }
+        if key.code == KeyCode::Char('u') && self.kind.shows_remotes() {
+            return Ok(ComponentInputResult::HandledAction(
+                ComponentAction::SetPopup(Some(Box::new(RemoteSettingsPopup::new(commander)?))),
+            ));
+        }
+        if key.code == KeyCode::Char('r')
+            && let Some(args) = self.args.clone()
+        {
+            return Ok(ComponentInputResult::HandledAction(
+                ComponentAction::Multiple(vec![
+                    ComponentAction::SetPopup(None),
+                    ComponentAction::RunInTerminal(args),
+                ]),
+            ));
+        }
+        if key.code == KeyCode::Char('f') && self.kind == RemoteError::Rejected {
+            let commander_clone = Commander::new(&commander.env);
+            let loader = LoaderPopup::new("Fetching".to_string(), move || {
+                commander_clone.git_fetch(false)
+            })
+            .with_terminal_retry(Commander::git_fetch_args(false));
+            return Ok(ComponentInputResult::HandledAction(
+                ComponentAction::SetPopup(Some(Box::new(loader))),
+            ));
+        }
+
+        self.message.input(commander, event)
+    }
+
+    fn hints(&self) -> Vec<(String, String)> {
+        if self.message.state.is_searching() {
+            return self.message.hints();
+        }
+        if self.confirming_force_push {
+            return vec![
+                ("y".to_owned(), "force-push".to_owned()),
+                ("n".to_owned(), "cancel".to_owned()),
+            ];
+        }
+        let mut hints = vec![];
+        if self.args.is_some() {
+            hints.push(("r".to_owned(), "retry in terminal".to_owned()));
+        }
+        if self.kind.shows_remotes() {
+            hints.push(("u".to_owned(), "edit remote URLs".to_owned()));
+        }
+        if self.kind == RemoteError::Rejected {
+            hints.push(("f".to_owned(), "fetch".to_owned()));
+            if !self.rejected_bookmarks.is_empty() {
+                hints.push(("F".to_owned(), "force-push".to_owned()));
+            }
+        }
+        hints.push(("Enter/Esc".to_owned(), "close".to_owned()));
+        hints
+    }
+}
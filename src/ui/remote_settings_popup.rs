@@ -0,0 +1,170 @@
+//! Popup listing the git remotes of the repository, to fix the URL of a remote a fetch or push
+//! failed with.
+
+use anyhow::Result;
+use ratatui::{
+    Frame,
+    crossterm::event::{Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{Block, BorderType, Borders, Clear, List, ListState},
+};
+use tui_textarea::TextArea;
+
+use crate::{
+    ComponentInputResult,
+    commander::Commander,
+    ui::{
+        Component, ComponentAction,
+        message_popup::MessagePopup,
+        styles::create_popup_block,
+        utils::{centered_rect, textarea_input},
+    },
+};
+
+pub struct RemoteSettingsPopup<'a> {
+    /// Names and URLs of the remotes
+    remotes: Vec<(String, String)>,
+    list_state: ListState,
+    /// URL of the selected remote being edited
+    url_textarea: Option<TextArea<'a>>,
+}
+
+impl RemoteSettingsPopup<'_> {
+    pub fn new(commander: &Commander) -> Result<Self> {
+        Ok(Self {
+            remotes: commander.get_git_remotes()?,
+            list_state: ListState::default().with_selected(Some(0)),
+            url_textarea: None,
+        })
+    }
+
+    fn selected(&self) -> Option<&(String, String)> {
+        self.list_state
+            .selected()
+            .and_then(|selected| self.remotes.get(selected))
+    }
+}
+
+impl Component for RemoteSettingsPopup<'_> {
+    fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+        let area = centered_rect(area, 60, 50);
+        f.render_widget(Clear, area);
+        let block = create_popup_block("Remotes");
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Fill(1), Constraint::Length(2)])
+            .split(inner);
+
+        if self.remotes.is_empty() {
+            f.render_widget(
+                Line::raw("No git remotes, add one with `jj git remote add <name> <url>`")
+                    .fg(Color::DarkGray),
+                chunks[0],
+            );
+        } else {
+            let items = self
+                .remotes
+                .iter()
+                .map(|(name, url)| Line::from(vec![name.clone().bold(), format!(" {url}").into()]));
+            let list = List::new(items).highlight_style(Style::default().reversed());
+            f.render_stateful_widget(list, chunks[0], &mut self.list_state);
+        }
+
+        if let Some(url_textarea) = self.url_textarea.as_mut() {
+            let name = self
+                .list_state
+                .selected()
+                .and_then(|selected| self.remotes.get(selected));
+            url_textarea.set_block(
+                Block::default()
+                    .borders(Borders::TOP)
+                    .border_type(BorderType::Rounded)
+                    .border_style(Style::default().fg(Color::Green))
+                    .title(format!(
+                        " URL of {} ",
+                        name.map_or("", |(name, _)| name.as_str())
+                    )),
+            );
+            f.render_widget(&*url_textarea, chunks[1]);
+        }
+
+        Ok(())
+    }
+
+    fn input(&mut self, commander: &mut Commander, event: Event) -> Result<ComponentInputResult> {
+        let Event::Key(key) = event else {
+            if let Some(url_textarea) = self.url_textarea.as_mut() {
+                textarea_input(url_textarea, event, false);
+            }
+            return Ok(ComponentInputResult::Handled);
+        };
+        if key.kind != KeyEventKind::Press {
+            return Ok(ComponentInputResult::Handled);
+        }
+
+        if let Some(url_textarea) = self.url_textarea.as_mut() {
+            match key.code {
+                KeyCode::Esc => self.url_textarea = None,
+                KeyCode::Enter => {
+                    let url = url_textarea.lines().join("").trim().to_owned();
+                    let Some((name, _)) = self.selected().cloned() else {
+                        return Ok(ComponentInputResult::Handled);
+                    };
+                    if let Err(err) = commander.set_git_remote_url(&name, &url) {
+                        return Ok(ComponentInputResult::HandledAction(
+                            ComponentAction::SetPopup(Some(Box::new(MessagePopup {
+                                title: "Remote URL error".into(),
+                                messages: commander.error_text(&err, "")?,
+                                text_align: None,
+                                ..Default::default()
+                            }))),
+                        ));
+                    }
+                    self.remotes = commander.get_git_remotes()?;
+                    self.url_textarea = None;
+                }
+                _ => textarea_input(url_textarea, event, false),
+            }
+            return Ok(ComponentInputResult::Handled);
+        }
+
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => self.list_state.select_next(),
+            KeyCode::Char('k') | KeyCode::Up => self.list_state.select_previous(),
+            KeyCode::Char('e') | KeyCode::Enter => {
+                if let Some((_, url)) = self.selected() {
+                    let mut url_textarea = TextArea::new(vec![url.clone()]);
+                    url_textarea.move_cursor(tui_textarea::CursorMove::End);
+                    self.url_textarea = Some(url_textarea);
+                }
+            }
+            KeyCode::Esc | KeyCode::Char('q') => {
+                return Ok(ComponentInputResult::HandledAction(
+                    ComponentAction::SetPopup(None),
+                ));
+            }
+            _ => {}
+        }
+        Ok(ComponentInputResult::Handled)
+    }
+
+    fn hints(&self) -> Vec<(String, String)> {
+        if self.url_textarea.is_some() {
+            vec![
+                ("Enter".to_owned(), "save URL".to_owned()),
+                ("Esc".to_owned(), "cancel".to_owned()),
+            ]
+        } else {
+            vec![
+                ("j/k".to_owned(), "select".to_owned()),
+                ("e/Enter".to_owned(), "edit URL".to_owned()),
+                ("Esc".to_owned(), "close".to_owned()),
+            ]
+        }
+    }
+}
@@ -0,0 +1,218 @@
+//! Popup reordering the linear stack of a change. The changes are moved up and down in the list
+//! first, and the new order is applied with as few rebases as possible once confirmed, instead of
+//! one rebase per step.
+
+use anyhow::Result;
+use ratatui::{
+    Frame,
+    crossterm::event::{Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Clear, List, ListState, Paragraph, Wrap},
+};
+
+use crate::{
+    ComponentInputResult,
+    commander::{
+        Commander,
+        ids::ChangeId,
+        log::Head,
+        reorder::{StackChange, reorder_moves},
+    },
+    ui::{
+        Component, ComponentAction, loader_popup::LoaderPopup, read_only_popup,
+        styles::create_popup_block, utils::centered_rect,
+    },
+};
+
+pub struct ReorderPopup {
+    /// The stack from the bottom
+    stack: Result<Vec<StackChange>, String>,
+    /// The new order as indices into the stack, from the bottom
+    order: Vec<usize>,
+    /// Selected row, the list shows the top of the stack first like the log
+    list_state: ListState,
+}
+
+impl ReorderPopup {
+    pub fn new(commander: &Commander, head: &Head) -> Self {
+        let stack = commander
+            .get_stack(head.commit_id.as_str())
+            .map_err(|err| format!("{err:#}"));
+        let len = stack.as_ref().map_or(0, Vec::len);
+        let selected = stack.as_ref().ok().and_then(|stack| {
+            stack
+                .iter()
+                .rev()
+                .position(|change| change.change_id == head.change_id)
+        });
+        Self {
+            stack,
+            order: (0..len).collect(),
+            list_state: ListState::default().with_selected(selected.or(Some(0))),
+        }
+    }
+
+    /// Number of rebases applying the new order
+    fn rebase_count(&self) -> usize {
+        let original: Vec<usize> = (0..self.order.len()).collect();
+        reorder_moves(&original, &self.order).len()
+    }
+
+    /// Move the selected change towards the top of the stack, or the bottom if `up` is false
+    fn move_selected(&mut self, up: bool) {
+        let Some(row) = self.list_state.selected() else {
+            return;
+        };
+        let Some(position) = self.order.len().checked_sub(row + 1) else {
+            return;
+        };
+        let other = if up {
+            position + 1
+        } else {
+            match position.checked_sub(1) {
+                Some(other) => other,
+                None => return,
+            }
+        };
+        if other >= self.order.len() {
+            return;
+        }
+        self.order.swap(position, other);
+        self.list_state.select(Some(self.order.len() - 1 - other));
+    }
+
+    fn apply(&self, commander: &Commander) -> ComponentAction {
+        let Ok(stack) = self.stack.as_ref() else {
+            return ComponentAction::SetPopup(None);
+        };
+        if self.rebase_count() == 0 {
+            return ComponentAction::SetPopup(None);
+        }
+        let original: Vec<ChangeId> = stack
+            .iter()
+            .map(|change| change.change_id.clone())
+            .collect();
+        let reordered: Vec<ChangeId> = self
+            .order
+            .iter()
+            .map(|&i| stack[i].change_id.clone())
+            .collect();
+        let commander = Commander::new(&commander.env);
+        // Each move is a rebase, a failed one offers to roll back the earlier ones
+        ComponentAction::SetPopup(Some(Box::new(LoaderPopup::new_action(
+            "Reordering the stack".to_owned(),
+            move || {
+                commander.run_composite_action("Reorder the stack", |commander| {
+                    Ok(commander.run_reorder(&original, &reordered)?)
+                })
+            },
+        ))))
+    }
+}
+
+impl Component for ReorderPopup {
+    fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+        let area = centered_rect(area, 60, 60);
+        f.render_widget(Clear, area);
+
+        let block = create_popup_block("Reorder stack");
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let [list_area, help_area] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(inner);
+
+        let stack = match self.stack.as_ref() {
+            Ok(stack) => stack,
+            Err(err) => {
+                f.render_widget(
+                    Paragraph::new(err.as_str())
+                        .fg(Color::Red)
+                        .wrap(Wrap { trim: true }),
+                    list_area,
+                );
+                return Ok(());
+            }
+        };
+
+        let items = self.order.iter().enumerate().rev().map(|(position, &i)| {
+            let change = &stack[i];
+            let change_id: String = change.change_id.as_str().chars().take(8).collect();
+            let title = if change.title.is_empty() {
+                Span::raw("(no description set)").fg(Color::DarkGray)
+            } else {
+                Span::raw(change.title.clone())
+            };
+            // Changes away from their original position
+            let marker = if position == i {
+                Span::raw("  ")
+            } else {
+                Span::raw("↕ ").fg(Color::Yellow)
+            };
+            Line::from(vec![
+                marker,
+                Span::raw(change_id).fg(Color::Magenta),
+                Span::raw(" "),
+                title,
+            ])
+        });
+        let list = List::new(items).highlight_style(Style::default().reversed());
+        f.render_stateful_widget(list, list_area, &mut self.list_state);
+
+        let help = match self.rebase_count() {
+            0 => Line::from("J/K: move change | Esc: close").fg(Color::DarkGray),
+            1 => Line::from("Enter: apply with 1 rebase | Esc: cancel").fg(Color::Yellow),
+            count => Line::from(format!("Enter: apply with {count} rebases | Esc: cancel"))
+                .fg(Color::Yellow),
+        };
+        f.render_widget(Paragraph::new(help).centered(), help_area);
+
+        Ok(())
+    }
+
+    fn input(&mut self, commander: &mut Commander, event: Event) -> Result<ComponentInputResult> {
+        let Event::Key(key) = event else {
+            return Ok(ComponentInputResult::Handled);
+        };
+        if key.kind != KeyEventKind::Press {
+            return Ok(ComponentInputResult::Handled);
+        }
+
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => self.list_state.select_next(),
+            KeyCode::Char('k') | KeyCode::Up => self.list_state.select_previous(),
+            KeyCode::Char('J') => self.move_selected(false),
+            KeyCode::Char('K') => self.move_selected(true),
+            KeyCode::Enter if commander.env.config.read_only() => {
+                return Ok(ComponentInputResult::HandledAction(read_only_popup()));
+            }
+            KeyCode::Enter => {
+                return Ok(ComponentInputResult::HandledAction(self.apply(commander)));
+            }
+            KeyCode::Esc | KeyCode::Char('q') => {
+                return Ok(ComponentInputResult::HandledAction(
+                    ComponentAction::SetPopup(None),
+                ));
+            }
+            _ => return Ok(ComponentInputResult::NotHandled),
+        }
+        // select_next can move past the last change
+        if let Some(selected) = self.list_state.selected()
+            && selected >= self.order.len()
+        {
+            self.list_state.select(self.order.len().checked_sub(1));
+        }
+        Ok(ComponentInputResult::Handled)
+    }
+
+    fn hints(&self) -> Vec<(String, String)> {
+        vec![
+            ("j/k".to_owned(), "select".to_owned()),
+            ("J/K".to_owned(), "move down/up".to_owned()),
+            ("Enter".to_owned(), "apply".to_owned()),
+            ("Esc".to_owned(), "cancel".to_owned()),
+        ]
+    }
+}
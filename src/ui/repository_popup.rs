@@ -0,0 +1,94 @@
+//! Popup listing configured and recently opened repositories to switch to.
+
+use anyhow::Result;
+use ratatui::{
+    Frame,
+    crossterm::event::{Event, KeyCode, KeyEventKind},
+    layout::Rect,
+    style::{Style, Stylize},
+    widgets::{Clear, List, ListState},
+};
+
+use crate::{
+    ComponentInputResult,
+    commander::Commander,
+    ui::{Component, ComponentAction, styles::create_popup_block, utils::centered_rect},
+};
+
+pub struct RepositoryPopup {
+    repositories: Vec<String>,
+    current: String,
+    list_state: ListState,
+}
+
+impl RepositoryPopup {
+    pub fn new(repositories: Vec<String>, current: String) -> Self {
+        let selected = repositories
+            .iter()
+            .position(|repository| repository == &current)
+            .unwrap_or(0);
+        Self {
+            repositories,
+            current,
+            list_state: ListState::default().with_selected(Some(selected)),
+        }
+    }
+}
+
+impl Component for RepositoryPopup {
+    fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+        let area = centered_rect(area, 60, 50);
+        f.render_widget(Clear, area);
+
+        let items = self.repositories.iter().map(|repository| {
+            if repository == &self.current {
+                format!("{repository} (current)")
+            } else {
+                repository.clone()
+            }
+        });
+        let list = List::new(items)
+            .block(create_popup_block("Repositories"))
+            .highlight_style(Style::default().reversed());
+        f.render_stateful_widget(list, area, &mut self.list_state);
+
+        Ok(())
+    }
+
+    fn input(&mut self, _commander: &mut Commander, event: Event) -> Result<ComponentInputResult> {
+        if let Event::Key(key) = event
+            && key.kind == KeyEventKind::Press
+        {
+            match key.code {
+                KeyCode::Char('j') | KeyCode::Down => self.list_state.select_next(),
+                KeyCode::Char('k') | KeyCode::Up => self.list_state.select_previous(),
+                KeyCode::Enter => {
+                    let Some(repository) = self
+                        .list_state
+                        .selected()
+                        .and_then(|selected| self.repositories.get(selected))
+                    else {
+                        return Ok(ComponentInputResult::NotHandled);
+                    };
+                    return Ok(ComponentInputResult::HandledAction(
+                        ComponentAction::Multiple(vec![
+                            ComponentAction::SetPopup(None),
+                            ComponentAction::SwitchRepository(repository.clone()),
+                        ]),
+                    ));
+                }
+                _ => return Ok(ComponentInputResult::NotHandled),
+            }
+        }
+
+        Ok(ComponentInputResult::Handled)
+    }
+
+    fn hints(&self) -> Vec<(String, String)> {
+        vec![
+            ("j/k".to_owned(), "select".to_owned()),
+            ("Enter".to_owned(), "switch".to_owned()),
+            ("Esc".to_owned(), "close".to_owned()),
+        ]
+    }
+}
@@ -0,0 +1,197 @@
+//! Popup choosing the merge tool to resolve the conflicts of a file with `jj resolve --tool`.
+//! Merge tools run with the terminal suspended. The popup stays open until the file has no
+//! conflicts left, so another tool can be tried if one didn't resolve them.
+
+use anyhow::Result;
+use ratatui::{
+    Frame,
+    crossterm::event::{Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Clear, List, ListState, Paragraph},
+};
+
+use crate::{
+    ComponentInputResult,
+    commander::{CommandError, Commander, log::Head, resolve::is_non_interactive_tool},
+    ui::{
+        Component, ComponentAction, message_popup::MessagePopup, styles::create_popup_block,
+        utils::centered_rect,
+    },
+};
+
+pub struct ResolvePopup {
+    head: Head,
+    path: String,
+    tools: Result<Vec<String>, CommandError>,
+    list_state: ListState,
+    /// Whether a merge tool ran in the terminal and the conflicts are checked again
+    resolving: bool,
+    /// Set when the file still has conflicts after running a tool
+    still_conflicted: bool,
+}
+
+impl ResolvePopup {
+    pub fn new(commander: &Commander, head: Head, path: String) -> Self {
+        Self {
+            head,
+            path,
+            tools: commander.get_merge_tools(),
+            list_state: ListState::default().with_selected(Some(0)),
+            resolving: false,
+            still_conflicted: false,
+        }
+    }
+
+    fn selected_tool(&self) -> Option<&String> {
+        self.tools.as_ref().ok()?.get(self.list_state.selected()?)
+    }
+
+    /// Resolve with the selected tool. Tools which don't need the terminal run right away,
+    /// others with the terminal suspended.
+    fn resolve(&mut self, commander: &Commander) -> Result<ComponentAction> {
+        let Some(tool) = self.selected_tool().cloned() else {
+            return Ok(ComponentAction::SetPopup(None));
+        };
+        if !is_non_interactive_tool(&tool) {
+            self.resolving = true;
+            return Ok(ComponentAction::RunInTerminal(Commander::resolve_args(
+                &self.head.commit_id,
+                &self.path,
+                &tool,
+            )));
+        }
+
+        let action = match commander.run_resolve(&self.head.commit_id, &self.path, &tool) {
+            Ok(()) => ComponentAction::SetPopup(None),
+            Err(err) => ComponentAction::SetPopup(Some(Box::new(MessagePopup {
+                title: "Resolve error".into(),
+                messages: commander.error_text(&err, "")?,
+                text_align: None,
+                ..Default::default()
+            }))),
+        };
+        Ok(ComponentAction::Multiple(vec![
+            action,
+            ComponentAction::RefreshTab(),
+        ]))
+    }
+}
+
+impl Component for ResolvePopup {
+    fn update(&mut self, commander: &mut Commander) -> Result<Option<ComponentAction>> {
+        if !self.resolving {
+            return Ok(None);
+        }
+        self.resolving = false;
+
+        // Resolving rewrites the change
+        self.head = commander.get_head_latest(&self.head)?;
+        let conflicted = commander
+            .get_conflicts(&self.head.commit_id)?
+            .iter()
+            .any(|conflict| conflict.path == self.path);
+        if conflicted {
+            self.still_conflicted = true;
+            return Ok(None);
+        }
+        Ok(Some(ComponentAction::SetPopup(None)))
+    }
+
+    fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+        let area = centered_rect(area, 50, 50);
+        f.render_widget(Clear, area);
+
+        let block = create_popup_block("Resolve");
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let [title_area, list_area, help_area] = Layout::vertical([
+            Constraint::Length(2),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+        ])
+        .areas(inner);
+
+        let title = if self.still_conflicted {
+            Line::from(vec![
+                Span::raw(self.path.clone()).bold(),
+                Span::raw(" still has conflicts, choose a tool to try again"),
+            ])
+            .fg(Color::Yellow)
+        } else {
+            Line::from(vec![
+                Span::raw("Resolve the conflicts of "),
+                Span::raw(self.path.clone()).bold(),
+                Span::raw(" with"),
+            ])
+        };
+        f.render_widget(Paragraph::new(title), title_area);
+
+        match self.tools.as_ref() {
+            Ok(tools) => {
+                let items = tools.iter().map(|tool| {
+                    if is_non_interactive_tool(tool) {
+                        Line::from(vec![
+                            Span::raw(tool.clone()),
+                            Span::raw(" (without a tool)").fg(Color::DarkGray),
+                        ])
+                    } else {
+                        Line::from(tool.clone())
+                    }
+                });
+                let list = List::new(items).highlight_style(Style::default().reversed());
+                f.render_stateful_widget(list, list_area, &mut self.list_state);
+            }
+            Err(err) => {
+                f.render_widget(
+                    Paragraph::new(format!("Error getting merge tools: {err}")).fg(Color::Red),
+                    list_area,
+                );
+            }
+        }
+
+        f.render_widget(
+            Paragraph::new(Line::from("Enter: resolve | Esc: cancel").fg(Color::DarkGray))
+                .centered(),
+            help_area,
+        );
+
+        Ok(())
+    }
+
+    fn input(&mut self, commander: &mut Commander, event: Event) -> Result<ComponentInputResult> {
+        let Event::Key(key) = event else {
+            return Ok(ComponentInputResult::Handled);
+        };
+        if key.kind != KeyEventKind::Press {
+            return Ok(ComponentInputResult::Handled);
+        }
+
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => self.list_state.select_next(),
+            KeyCode::Char('k') | KeyCode::Up => self.list_state.select_previous(),
+            KeyCode::Enter => {
+                return Ok(ComponentInputResult::HandledAction(
+                    self.resolve(commander)?,
+                ));
+            }
+            KeyCode::Esc | KeyCode::Char('q') => {
+                return Ok(ComponentInputResult::HandledAction(
+                    ComponentAction::SetPopup(None),
+                ));
+            }
+            _ => return Ok(ComponentInputResult::NotHandled),
+        }
+        Ok(ComponentInputResult::Handled)
+    }
+
+    fn hints(&self) -> Vec<(String, String)> {
+        vec![
+            ("j/k".to_owned(), "select".to_owned()),
+            ("Enter".to_owned(), "resolve".to_owned()),
+            ("Esc".to_owned(), "cancel".to_owned()),
+        ]
+    }
+}
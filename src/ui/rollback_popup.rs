@@ -0,0 +1,136 @@
+//! Popup shown when a step of an action failed after earlier steps changed the repository,
+//! offering to roll back the completed steps together with `jj op restore`.
+
+use ansi_to_tui::IntoText;
+use anyhow::Result;
+use ratatui::{
+    Frame,
+    crossterm::event::{Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Stylize},
+    text::{Line, Span, Text},
+    widgets::{Clear, Paragraph, Wrap},
+};
+
+use crate::{
+    ComponentInputResult,
+    commander::{Commander, operations::PartialActionError},
+    ui::{
+        Component, ComponentAction, message_popup::MessagePopup, styles::create_popup_block,
+        utils::centered_rect,
+    },
+};
+
+pub struct RollbackPopup {
+    partial: PartialActionError,
+}
+
+impl RollbackPopup {
+    pub fn new(partial: PartialActionError) -> Self {
+        Self { partial }
+    }
+
+    /// The popup for a [PartialActionError], or the error back if it is another one
+    pub fn from_error(err: anyhow::Error) -> Result<Box<dyn Component>, anyhow::Error> {
+        let partial = err.downcast::<PartialActionError>()?;
+        Ok(Box::new(Self::new(partial)))
+    }
+
+    /// Restore the operation before the action, returning a popup with the error if it failed
+    fn roll_back(&self, commander: &Commander) -> Result<ComponentAction> {
+        let action = match commander.roll_back_action(&self.partial.action) {
+            Ok(_) => ComponentAction::SetPopup(None),
+            Err(err) => ComponentAction::SetPopup(Some(Box::new(MessagePopup {
+                title: "Roll back error".into(),
+                messages: commander.error_text(&err, "")?,
+                text_align: None,
+                ..Default::default()
+            }))),
+        };
+        Ok(ComponentAction::Multiple(vec![
+            action,
+            ComponentAction::RefreshTab(),
+        ]))
+    }
+}
+
+impl Component for RollbackPopup {
+    fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+        let area = centered_rect(area, 70, 60);
+        f.render_widget(Clear, area);
+
+        let title = format!("{} failed", self.partial.action.name);
+        let block = create_popup_block(&title);
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let [content_area, help_area] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(inner);
+
+        let error = format!("{:?}", self.partial.error);
+        let mut text = Text::from_iter(
+            error
+                .into_text()
+                .unwrap_or_else(|_| Text::from(error.clone()))
+                .lines
+                .into_iter()
+                .map(|line| line.fg(Color::Red)),
+        );
+        text.push_line(Line::default());
+        text.push_line(Line::from("These operations of the action were completed:").bold());
+        for operation in &self.partial.operations {
+            text.push_line(Line::from(vec![
+                Span::raw(operation.id.clone()).blue(),
+                Span::raw(" "),
+                Span::raw(operation.description.clone()),
+            ]));
+        }
+        text.push_line(Line::default());
+        text.push_line(Line::from(format!(
+            "Rolling back restores the repository to operation {}, as before the action",
+            self.partial.action.start_operation
+        )));
+        f.render_widget(
+            Paragraph::new(text).wrap(Wrap { trim: false }),
+            content_area,
+        );
+
+        f.render_widget(
+            Paragraph::new(
+                Line::from("y: roll back | n/Esc: keep the completed steps").fg(Color::Yellow),
+            )
+            .centered(),
+            help_area,
+        );
+
+        Ok(())
+    }
+
+    fn input(&mut self, commander: &mut Commander, event: Event) -> Result<ComponentInputResult> {
+        let Event::Key(key) = event else {
+            return Ok(ComponentInputResult::Handled);
+        };
+        if key.kind != KeyEventKind::Press {
+            return Ok(ComponentInputResult::Handled);
+        }
+        match key.code {
+            KeyCode::Char('y') => Ok(ComponentInputResult::HandledAction(
+                self.roll_back(commander)?,
+            )),
+            KeyCode::Char('n') | KeyCode::Char('q') | KeyCode::Esc => Ok(
+                ComponentInputResult::HandledAction(ComponentAction::Multiple(vec![
+                    ComponentAction::SetPopup(None),
+                    ComponentAction::RefreshTab(),
+                ])),
+            ),
+            _ => Ok(ComponentInputResult::Handled),
+        }
+    }
+
+    fn hints(&self) -> Vec<(String, String)> {
+        vec![
+            ("y".to_owned(), "roll back".to_owned()),
+            ("n".to_owned(), "keep".to_owned()),
+        ]
+    }
+}
@@ -0,0 +1,266 @@
+//! Popup to view and change common jj and blazingjj config options, written to the user or repo
+//! config file with `jj config set`.
+
+use anyhow::Result;
+use ratatui::{
+    Frame,
+    crossterm::event::{Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Clear, List, ListState, Paragraph},
+};
+use tui_textarea::{CursorMove, TextArea};
+
+use crate::{
+    ComponentInputResult,
+    commander::{CommandError, Commander, config::ConfigScope},
+    ui::{
+        Component,
+        styles::create_popup_block,
+        utils::{centered_rect, textarea_input},
+    },
+};
+
+/// Options shown in the popup
+const OPTIONS: [&str; 12] = [
+    "user.name",
+    "user.email",
+    "ui.editor",
+    "ui.diff-editor",
+    "ui.merge-editor",
+    "ui.default-command",
+    "revsets.log",
+    "blazingjj.diff-format",
+    "blazingjj.diff-tool",
+    "blazingjj.layout",
+    "blazingjj.highlight-color",
+    "blazingjj.hint-bar",
+];
+
+pub struct SettingsPopup<'a> {
+    /// Effective value of each option, `None` if not set
+    values: Vec<Result<Option<String>, String>>,
+    list_state: ListState,
+    scope: ConfigScope,
+    /// Input for the new value of the selected option
+    textarea: Option<TextArea<'a>>,
+    /// Result of the last change
+    status: Option<Result<String, String>>,
+}
+
+impl SettingsPopup<'_> {
+    pub fn new(commander: &Commander) -> Self {
+        let mut popup = Self {
+            values: vec![],
+            list_state: ListState::default().with_selected(Some(0)),
+            scope: ConfigScope::User,
+            textarea: None,
+            status: None,
+        };
+        popup.refresh(commander);
+        popup
+    }
+
+    fn refresh(&mut self, commander: &Commander) {
+        self.values = match commander.get_config_values(&OPTIONS) {
+            Ok(values) => values.into_iter().map(Ok).collect(),
+            Err(err) => OPTIONS.iter().map(|_| Err(err.to_string())).collect(),
+        };
+    }
+
+    fn selected_option(&self) -> Option<&'static str> {
+        self.list_state
+            .selected()
+            .and_then(|selected| OPTIONS.get(selected).copied())
+    }
+
+    fn set_status(
+        &mut self,
+        commander: &mut Commander,
+        result: Result<(), CommandError>,
+        done: &str,
+    ) {
+        self.status = Some(match result {
+            Ok(()) => {
+                self.refresh(commander);
+                // The log reads `revsets.log` from the config read at startup
+                if self.selected_option() == Some("revsets.log")
+                    && let Some(Ok(revset)) = self
+                        .list_state
+                        .selected()
+                        .and_then(|selected| self.values.get(selected))
+                {
+                    commander.env.config.set_log_revset(revset.clone());
+                }
+                // blazingjj reads its config once at startup
+                if self
+                    .selected_option()
+                    .is_some_and(|name| name.starts_with("blazingjj."))
+                {
+                    Ok(format!("{done}, restart blazingjj to apply it"))
+                } else {
+                    Ok(done.to_owned())
+                }
+            }
+            Err(err) => Err(err.to_string()),
+        });
+    }
+}
+
+impl Component for SettingsPopup<'_> {
+    fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+        let area = centered_rect(area, 70, 60);
+        f.render_widget(Clear, area);
+
+        let block = create_popup_block("Settings");
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let [scope_area, list_area, input_area, status_area, help_area] = Layout::vertical([
+            Constraint::Length(2),
+            Constraint::Fill(1),
+            Constraint::Length(if self.textarea.is_some() { 3 } else { 0 }),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .areas(inner);
+
+        f.render_widget(
+            Paragraph::new(Line::from(vec![
+                Span::raw("Write to: "),
+                Span::raw(format!("{} config", self.scope.name())).bold(),
+                Span::raw(format!(" (jj config set {})", self.scope.flag())).fg(Color::DarkGray),
+            ])),
+            scope_area,
+        );
+
+        let name_width = OPTIONS.iter().map(|name| name.len()).max().unwrap_or(0);
+        let items = OPTIONS.iter().zip(&self.values).map(|(name, value)| {
+            let value = match value {
+                Ok(Some(value)) => Span::raw(value.clone()),
+                Ok(None) => Span::raw("(not set)").fg(Color::DarkGray),
+                Err(err) => Span::raw(format!("error: {err}")).fg(Color::Red),
+            };
+            Line::from(vec![Span::raw(format!("{name:name_width$}  ")), value])
+        });
+        let list = List::new(items).highlight_style(Style::default().reversed());
+        f.render_stateful_widget(list, list_area, &mut self.list_state);
+
+        let title = format!(" {} ", self.selected_option().unwrap_or_default());
+        if let Some(textarea) = self.textarea.as_mut() {
+            textarea.set_block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded)
+                    .title(title),
+            );
+            f.render_widget(&*textarea, input_area);
+        }
+
+        if let Some(status) = self.status.as_ref() {
+            let status = match status {
+                Ok(message) => Line::from(message.as_str()).fg(Color::Green),
+                Err(message) => Line::from(message.as_str()).fg(Color::Red),
+            };
+            f.render_widget(Paragraph::new(status), status_area);
+        }
+
+        let help = if self.textarea.is_some() {
+            "Enter: save | Esc: cancel"
+        } else {
+            "Enter: edit | s: switch user/repo | d: unset | Esc: close"
+        };
+        f.render_widget(
+            Paragraph::new(Line::from(help).fg(Color::DarkGray)).centered(),
+            help_area,
+        );
+
+        Ok(())
+    }
+
+    fn input(&mut self, commander: &mut Commander, event: Event) -> Result<ComponentInputResult> {
+        if let Some(textarea) = self.textarea.as_mut() {
+            if let Event::Key(key) = event {
+                if key.kind != KeyEventKind::Press {
+                    return Ok(ComponentInputResult::Handled);
+                }
+                match key.code {
+                    KeyCode::Enter => {
+                        let value = textarea.lines().join(" ");
+                        self.textarea = None;
+                        if let Some(name) = self.selected_option() {
+                            let result = commander.set_config_value(self.scope, name, &value);
+                            self.set_status(
+                                commander,
+                                result,
+                                &format!("Set {name} in the {} config", self.scope.name()),
+                            );
+                        }
+                        return Ok(ComponentInputResult::Handled);
+                    }
+                    KeyCode::Esc => {
+                        self.textarea = None;
+                        return Ok(ComponentInputResult::Handled);
+                    }
+                    _ => {}
+                }
+            }
+            textarea_input(textarea, event, false);
+            return Ok(ComponentInputResult::Handled);
+        }
+
+        let Event::Key(key) = event else {
+            return Ok(ComponentInputResult::Handled);
+        };
+        if key.kind != KeyEventKind::Press {
+            return Ok(ComponentInputResult::Handled);
+        }
+
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => self.list_state.select_next(),
+            KeyCode::Char('k') | KeyCode::Up => self.list_state.select_previous(),
+            KeyCode::Char('s') | KeyCode::Tab => self.scope = self.scope.toggle(),
+            KeyCode::Enter => {
+                let value = self
+                    .list_state
+                    .selected()
+                    .and_then(|selected| self.values.get(selected))
+                    .and_then(|value| value.as_ref().ok().cloned().flatten())
+                    .unwrap_or_default();
+                let mut textarea = TextArea::new(vec![value]);
+                textarea.move_cursor(CursorMove::End);
+                self.textarea = Some(textarea);
+                self.status = None;
+            }
+            KeyCode::Char('d') => {
+                if let Some(name) = self.selected_option() {
+                    let result = commander.unset_config_value(self.scope, name);
+                    self.set_status(
+                        commander,
+                        result,
+                        &format!("Unset {name} in the {} config", self.scope.name()),
+                    );
+                }
+            }
+            _ => return Ok(ComponentInputResult::NotHandled),
+        }
+        Ok(ComponentInputResult::Handled)
+    }
+
+    fn hints(&self) -> Vec<(String, String)> {
+        if self.textarea.is_some() {
+            return vec![
+                ("Enter".to_owned(), "save".to_owned()),
+                ("Esc".to_owned(), "cancel".to_owned()),
+            ];
+        }
+        vec![
+            ("j/k".to_owned(), "select".to_owned()),
+            ("Enter".to_owned(), "edit".to_owned()),
+            ("s".to_owned(), "switch user/repo".to_owned()),
+            ("d".to_owned(), "unset".to_owned()),
+            ("Esc".to_owned(), "close".to_owned()),
+        ]
+    }
+}
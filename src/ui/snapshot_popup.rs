@@ -0,0 +1,108 @@
+//! Shown when jj refused to snapshot new files because they exceed `snapshot.max-new-file-size`.
+//! Offers to track them anyway or to add them to the `.gitignore`.
+
+use anyhow::Result;
+use ratatui::{
+    Frame,
+    crossterm::event::{Event, KeyCode, KeyEventKind},
+    layout::{Alignment, Rect},
+    style::Stylize,
+    text::{Line, Text},
+};
+
+use crate::{
+    ComponentInputResult,
+    commander::{Commander, snapshot::RefusedFile},
+    ui::{Component, ComponentAction, message_popup::MessagePopup, utils::human_size},
+};
+
+pub struct SnapshotPopup {
+    message: MessagePopup<'static>,
+    files: Vec<RefusedFile>,
+}
+
+impl SnapshotPopup {
+    pub fn new(files: Vec<RefusedFile>) -> Self {
+        let max_size = files.first().map_or(0, |file| file.max_size);
+        let mut messages = Text::from(vec![
+            Line::raw(format!(
+                "jj did not track these new files because they are larger than {} (snapshot.max-new-file-size):",
+                human_size(max_size)
+            )),
+            Line::raw(""),
+        ]);
+        for file in &files {
+            messages.push_line(Line::raw(format!(
+                "  {} ({})",
+                file.path,
+                human_size(file.size)
+            )));
+        }
+        messages.push_line(Line::raw(""));
+        messages.push_line(Line::raw("Press t to track them anyway").bold());
+        messages.push_line(Line::raw("Press i to add them to .gitignore").bold());
+
+        Self {
+            message: MessagePopup {
+                title: "Files too large to snapshot".into(),
+                messages,
+                text_align: Some(Alignment::Left),
+                ..Default::default()
+            },
+            files,
+        }
+    }
+
+    fn error_popup(title: &str, err: impl ToString) -> ComponentAction {
+        ComponentAction::SetPopup(Some(Box::new(MessagePopup {
+            title: title.to_owned().into(),
+            messages: err.to_string().into(),
+            text_align: None,
+            ..Default::default()
+        })))
+    }
+}
+
+impl Component for SnapshotPopup {
+    fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+        self.message.draw(f, area)
+    }
+
+    fn input(&mut self, commander: &mut Commander, event: Event) -> Result<ComponentInputResult> {
+        let Event::Key(key) = event else {
+            return Ok(ComponentInputResult::NotHandled);
+        };
+        if key.kind != KeyEventKind::Press {
+            return Ok(ComponentInputResult::NotHandled);
+        }
+
+        let result = match key.code {
+            KeyCode::Char('t') => commander
+                .track_refused_files(&self.files)
+                .map_err(|err| Self::error_popup("Track error", err)),
+            KeyCode::Char('i') => {
+                let paths: Vec<&str> = self.files.iter().map(|file| file.path.as_str()).collect();
+                commander
+                    .ignore_files(&paths)
+                    .map_err(|err| Self::error_popup("Ignore error", err))
+            }
+            _ => return Ok(ComponentInputResult::NotHandled),
+        };
+
+        Ok(ComponentInputResult::HandledAction(match result {
+            Ok(()) => ComponentAction::Multiple(vec![
+                ComponentAction::SetPopup(None),
+                ComponentAction::RefreshTab(),
+            ]),
+            Err(action) => action,
+        }))
+    }
+
+    fn hints(&self) -> Vec<(String, String)> {
+        vec![
+            ("t".to_owned(), "track anyway".to_owned()),
+            ("i".to_owned(), "add to .gitignore".to_owned()),
+            ("Enter/Esc".to_owned(), "close".to_owned()),
+        ]
+    }
+}
@@ -0,0 +1,226 @@
+//! Popup summarizing the changes of the log revset as bar charts: commits per author, per week
+//! and the most frequently changed files. The stats are counted in a background thread, as
+//! diffing every commit of a large revset takes a while.
+
+use std::{
+    sync::mpsc::{self, Receiver},
+    thread,
+    time::Instant,
+};
+
+use anyhow::Result;
+use ratatui::{
+    Frame,
+    crossterm::event::{Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::{Line, Text},
+    widgets::{Bar, BarChart, BarGroup, Block, Borders, Clear, Paragraph},
+};
+
+use crate::{
+    ComponentInputResult,
+    commander::{
+        CommandError, Commander,
+        stats::{RevsetStats, STATS_COMMIT_LIMIT},
+    },
+    ui::{
+        Component, ComponentAction,
+        styles::create_popup_block,
+        utils::{centered_rect, spinner},
+    },
+};
+
+/// Width of the labels of the horizontal bar charts
+const LABEL_WIDTH: usize = 28;
+
+pub struct StatsPopup {
+    revset: String,
+    stats: Option<Result<RevsetStats, CommandError>>,
+    stats_rx: Option<Receiver<Result<RevsetStats, CommandError>>>,
+    started: Instant,
+}
+
+impl StatsPopup {
+    pub fn new(commander: &Commander, revset: String, path: Option<String>) -> Self {
+        let (tx, rx) = mpsc::channel();
+        let commander = Commander::new(&commander.env);
+        let thread_revset = revset.clone();
+        thread::spawn(move || {
+            let _ = tx.send(commander.get_revset_stats(&thread_revset, path.as_deref()));
+        });
+        Self {
+            revset,
+            stats: None,
+            stats_rx: Some(rx),
+            started: Instant::now(),
+        }
+    }
+}
+
+/// Label shortened from the start to fit in [LABEL_WIDTH], keeping the end of paths
+fn shorten_label(label: &str) -> String {
+    let chars = label.chars().count();
+    if chars <= LABEL_WIDTH {
+        return label.to_owned();
+    }
+    let end: String = label.chars().skip(chars - (LABEL_WIDTH - 1)).collect();
+    format!("…{end}")
+}
+
+/// Bar chart with a bar per row, labelled on the left
+fn horizontal_chart<'a>(title: &'a str, counts: &[(String, u64)], height: u16) -> BarChart<'a> {
+    let bars: Vec<Bar> = counts
+        .iter()
+        .take(height.saturating_sub(1) as usize)
+        .map(|(label, count)| {
+            Bar::default()
+                .label(Line::from(shorten_label(label)))
+                .value(*count)
+                .text_value(count.to_string())
+        })
+        .collect();
+    BarChart::default()
+        .block(Block::default().borders(Borders::TOP).title(title))
+        .direction(Direction::Horizontal)
+        .bar_width(1)
+        .bar_gap(0)
+        .bar_style(Style::default().fg(Color::Cyan))
+        .value_style(Style::default().fg(Color::Black).bg(Color::Cyan))
+        .data(BarGroup::default().bars(&bars))
+}
+
+/// Bar chart with a column per week, the latest weeks which fit in the width
+fn weeks_chart(weeks: &[(String, u64)], width: u16) -> BarChart<'_> {
+    // Labelled `W07`, with a gap between the columns
+    let columns = (width / 4) as usize;
+    let bars: Vec<Bar> = weeks
+        .iter()
+        .skip(weeks.len().saturating_sub(columns))
+        .map(|(week, count)| {
+            let label = week
+                .rsplit_once('-')
+                .map_or(week.as_str(), |(_, week)| week);
+            Bar::default()
+                .label(Line::from(label.to_owned()))
+                .value(*count)
+                .text_value(count.to_string())
+        })
+        .collect();
+    let title = match (weeks.first(), weeks.last()) {
+        (Some((first, _)), Some((last, _))) => format!("Commits per week ({first} – {last})"),
+        _ => "Commits per week".to_owned(),
+    };
+    BarChart::default()
+        .block(Block::default().borders(Borders::TOP).title(title))
+        .bar_width(3)
+        .bar_gap(1)
+        .bar_style(Style::default().fg(Color::Green))
+        .value_style(Style::default().fg(Color::Black).bg(Color::Green))
+        .data(BarGroup::default().bars(&bars))
+}
+
+impl Component for StatsPopup {
+    fn update(&mut self, _commander: &mut Commander) -> Result<Option<ComponentAction>> {
+        if let Some(stats) = self.stats_rx.as_ref().and_then(|rx| rx.try_recv().ok()) {
+            self.stats = Some(stats);
+            self.stats_rx = None;
+        }
+        Ok(None)
+    }
+
+    fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+        let area = centered_rect(area, 80, 80);
+        f.render_widget(Clear, area);
+
+        let block = create_popup_block("Stats");
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let [header_area, charts_area, help_area] = Layout::vertical([
+            Constraint::Length(2),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+        ])
+        .areas(inner);
+
+        f.render_widget(
+            Paragraph::new(Line::from("Esc: close").fg(Color::DarkGray)).centered(),
+            help_area,
+        );
+
+        let stats = match self.stats.as_ref() {
+            None => {
+                f.render_widget(
+                    Paragraph::new(format!(
+                        "{} Counting the commits of {}",
+                        spinner(self.started.elapsed()),
+                        self.revset
+                    )),
+                    header_area,
+                );
+                return Ok(());
+            }
+            Some(Err(err)) => {
+                f.render_widget(Paragraph::new(err.into_text("")?), charts_area);
+                return Ok(());
+            }
+            Some(Ok(stats)) => stats,
+        };
+
+        let mut header = format!("{} commits in {}", stats.commits, self.revset);
+        if stats.truncated {
+            header.push_str(&format!(
+                " (only the first {STATS_COMMIT_LIMIT} are counted)"
+            ));
+        }
+        f.render_widget(Paragraph::new(Text::from(header).bold()), header_area);
+
+        if stats.commits == 0 {
+            return Ok(());
+        }
+
+        let [top_area, weeks_area] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(10)]).areas(charts_area);
+        let [authors_area, files_area] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .spacing(2)
+                .areas(top_area);
+
+        f.render_widget(
+            horizontal_chart("Commits per author", &stats.authors, authors_area.height),
+            authors_area,
+        );
+        f.render_widget(
+            horizontal_chart(
+                "Most frequently changed files",
+                &stats.files,
+                files_area.height,
+            ),
+            files_area,
+        );
+        f.render_widget(weeks_chart(&stats.weeks, weeks_area.width), weeks_area);
+
+        Ok(())
+    }
+
+    fn input(&mut self, _commander: &mut Commander, event: Event) -> Result<ComponentInputResult> {
+        if let Event::Key(key) = event
+            && key.kind == KeyEventKind::Press
+            && matches!(key.code, KeyCode::Esc | KeyCode::Char('q'))
+        {
+            return Ok(ComponentInputResult::HandledAction(
+                ComponentAction::SetPopup(None),
+            ));
+        }
+        Ok(ComponentInputResult::Handled)
+    }
+
+    fn is_busy(&self) -> bool {
+        self.stats_rx.is_some()
+    }
+
+    fn hints(&self) -> Vec<(String, String)> {
+        vec![("Esc".to_owned(), "close".to_owned())]
+    }
+}
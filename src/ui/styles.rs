@@ -15,6 +15,24 @@ pub static POPUP_BLOCK: LazyLock<Block<'static>> = LazyLock::new(|| {
 });
 pub static POPUP_BLOCK_TITLE_STYLE: LazyLock<Style> = LazyLock::new(|| Style::new().bold().cyan());
 
+/// Border style of a panel. The focused panel is highlighted with the focus color.
+pub fn panel_border_style(focused: bool, focus_color: Color) -> Style {
+    if focused {
+        Style::default().fg(focus_color).bold()
+    } else {
+        Style::default()
+    }
+}
+
+/// Base style of the content of a panel. The content of unfocused panels is dimmed.
+pub fn panel_content_style(focused: bool) -> Style {
+    if focused {
+        Style::default()
+    } else {
+        Style::default().dim()
+    }
+}
+
 pub fn create_popup_block(title: &str) -> Block<'_> {
     POPUP_BLOCK
         .clone()
@@ -0,0 +1,185 @@
+//! Popup rendering a jj template for the selected change while it is edited, to try out
+//! templates like `blazingjj.bookmark-template` or `templates.log` without restarting.
+
+use std::time::{Duration, Instant};
+
+use ansi_to_tui::IntoText;
+use anyhow::Result;
+use ratatui::{
+    Frame,
+    crossterm::event::{Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::{Line, Text},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+};
+use tui_textarea::{CursorMove, TextArea};
+
+use crate::{
+    ComponentInputResult,
+    commander::{CommandError, Commander, log::Head},
+    ui::{
+        Component, ComponentAction,
+        styles::create_popup_block,
+        utils::{centered_rect, textarea_input},
+    },
+};
+
+/// Time without typing after which the template is rendered again
+const RENDER_DELAY: Duration = Duration::from_millis(250);
+
+pub struct TemplatePopup<'a> {
+    head: Head,
+    /// Config options the template can be loaded from, with their current value
+    sources: Vec<(String, String)>,
+    source: usize,
+    textarea: TextArea<'a>,
+    output: Result<String, CommandError>,
+    /// When the template was last edited, if it wasn't rendered since
+    edited: Option<Instant>,
+}
+
+impl TemplatePopup<'_> {
+    pub fn new(commander: &Commander, head: Head) -> Self {
+        let log_template = commander
+            .get_config_value("templates.log")
+            .ok()
+            .flatten()
+            .unwrap_or_else(|| "builtin_log_compact".to_owned());
+        let sources = vec![
+            (
+                "blazingjj.bookmark-template".to_owned(),
+                commander.env.config.bookmark_template(),
+            ),
+            ("templates.log".to_owned(), log_template),
+        ];
+        let mut popup = Self {
+            head,
+            sources,
+            source: 0,
+            textarea: TextArea::default(),
+            output: Ok(String::new()),
+            edited: None,
+        };
+        popup.load_source(commander);
+        popup
+    }
+
+    /// Replace the template with the value of the selected config option
+    fn load_source(&mut self, commander: &Commander) {
+        let template = &self.sources[self.source].1;
+        self.textarea = TextArea::new(template.lines().map(String::from).collect());
+        self.textarea.move_cursor(CursorMove::Bottom);
+        self.textarea.move_cursor(CursorMove::End);
+        self.render(commander);
+    }
+
+    fn render(&mut self, commander: &Commander) {
+        self.output = commander.render_template(&self.head.commit_id, &self.template());
+        self.edited = None;
+    }
+
+    fn template(&self) -> String {
+        self.textarea.lines().join("\n")
+    }
+}
+
+impl Component for TemplatePopup<'_> {
+    fn update(&mut self, commander: &mut Commander) -> Result<Option<ComponentAction>> {
+        if self
+            .edited
+            .is_some_and(|edited| edited.elapsed() >= RENDER_DELAY)
+        {
+            self.render(commander);
+        }
+        Ok(None)
+    }
+
+    fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+        let area = centered_rect(area, 70, 60);
+        f.render_widget(Clear, area);
+
+        let block = create_popup_block("Template");
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let [template_area, output_area, help_area] = Layout::vertical([
+            Constraint::Length(6),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+        ])
+        .areas(inner);
+
+        let change_id: String = self.head.change_id.as_str().chars().take(8).collect();
+        self.textarea.set_block(
+            Block::default()
+                .borders(Borders::BOTTOM)
+                .border_style(Style::default().fg(Color::DarkGray))
+                .title(format!(" {} ", self.sources[self.source].0)),
+        );
+        f.render_widget(&self.textarea, template_area);
+
+        let output: Text = match self.output.as_ref() {
+            Ok(output) => output.into_text()?,
+            Err(err) => err.into_text("")?,
+        };
+        let output_title = if self.edited.is_some() {
+            format!(" Rendered for {change_id} (editing) ")
+        } else {
+            format!(" Rendered for {change_id} ")
+        };
+        f.render_widget(
+            Paragraph::new(output)
+                .wrap(Wrap { trim: false })
+                .block(Block::default().title(output_title)),
+            output_area,
+        );
+
+        f.render_widget(
+            Paragraph::new(Line::from("Tab: next config option | Esc: close").fg(Color::DarkGray))
+                .centered(),
+            help_area,
+        );
+
+        Ok(())
+    }
+
+    fn input(&mut self, commander: &mut Commander, event: Event) -> Result<ComponentInputResult> {
+        if let Event::Key(key) = event {
+            if key.kind != KeyEventKind::Press {
+                return Ok(ComponentInputResult::Handled);
+            }
+            match key.code {
+                KeyCode::Esc => {
+                    return Ok(ComponentInputResult::HandledAction(
+                        ComponentAction::SetPopup(None),
+                    ));
+                }
+                KeyCode::Tab => {
+                    self.source = (self.source + 1) % self.sources.len();
+                    self.load_source(commander);
+                    return Ok(ComponentInputResult::Handled);
+                }
+                _ => {}
+            }
+        }
+
+        let template = self.template();
+        textarea_input(&mut self.textarea, event, true);
+        if self.template() != template {
+            self.edited = Some(Instant::now());
+        }
+        Ok(ComponentInputResult::Handled)
+    }
+
+    fn is_busy(&self) -> bool {
+        self.edited.is_some()
+    }
+
+    fn hints(&self) -> Vec<(String, String)> {
+        vec![
+            ("Tab".to_owned(), "next config option".to_owned()),
+            ("Esc".to_owned(), "close".to_owned()),
+        ]
+    }
+}
@@ -0,0 +1,195 @@
+//! Popup listing the TODO/FIXME/XXX markers added by a change, as a quick review before pushing.
+//! Enter shows the marker in the diff of its file in the files tab. The markers are listed in a
+//! background thread, as the diff of a large change takes a while.
+
+use std::{
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+    time::Instant,
+};
+
+use anyhow::Result;
+use ratatui::{
+    Frame,
+    crossterm::event::{Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Clear, List, ListState, Paragraph},
+};
+
+use crate::{
+    ComponentInputResult,
+    commander::{CommandError, Commander, log::Head, todos::TodoMarker},
+    ui::{
+        Component, ComponentAction,
+        styles::create_popup_block,
+        utils::{centered_rect, spinner},
+    },
+};
+
+pub struct TodosPopup {
+    head: Head,
+    /// None while the markers are being listed
+    markers: Option<Result<Vec<TodoMarker>, CommandError>>,
+    markers_rx: Option<Receiver<Result<Vec<TodoMarker>, CommandError>>>,
+    started: Instant,
+    list_state: ListState,
+}
+
+impl TodosPopup {
+    pub fn new(commander: &Commander, head: Head) -> Self {
+        let (tx, rx) = mpsc::channel();
+        let commander = Commander::new(&commander.env);
+        let commit_id = head.commit_id.clone();
+        thread::spawn(move || {
+            let _ = tx.send(commander.get_added_todos(&commit_id));
+        });
+        Self {
+            head,
+            markers: None,
+            markers_rx: Some(rx),
+            started: Instant::now(),
+            list_state: ListState::default().with_selected(Some(0)),
+        }
+    }
+
+    fn selected_marker(&self) -> Option<&TodoMarker> {
+        self.markers
+            .as_ref()?
+            .as_ref()
+            .ok()?
+            .get(self.list_state.selected()?)
+    }
+}
+
+impl Component for TodosPopup {
+    fn update(&mut self, _commander: &mut Commander) -> Result<Option<ComponentAction>> {
+        let Some(rx) = self.markers_rx.as_ref() else {
+            return Ok(None);
+        };
+        match rx.try_recv() {
+            Ok(markers) => {
+                self.markers = Some(markers);
+                self.markers_rx = None;
+            }
+            Err(TryRecvError::Empty) => {}
+            Err(TryRecvError::Disconnected) => {
+                self.markers = Some(Err(CommandError::Status(
+                    "The todos thread stopped".to_owned(),
+                    None,
+                )));
+                self.markers_rx = None;
+            }
+        }
+        Ok(None)
+    }
+
+    fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+        let area = centered_rect(area, 80, 60);
+        f.render_widget(Clear, area);
+
+        let change_id: String = self.head.change_id.as_str().chars().take(8).collect();
+        let title = format!("Todos added by {change_id}");
+        let block = create_popup_block(&title);
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        let [list_area, help_area] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(inner);
+
+        match self.markers.as_ref() {
+            None => {
+                f.render_widget(
+                    Paragraph::new(format!(
+                        "{} Listing the markers",
+                        spinner(self.started.elapsed())
+                    )),
+                    list_area,
+                );
+            }
+            Some(Ok(markers)) if markers.is_empty() => {
+                f.render_widget(
+                    Paragraph::new("The change adds no markers of blazingjj.todo-patterns")
+                        .fg(Color::DarkGray),
+                    list_area,
+                );
+            }
+            Some(Ok(markers)) => {
+                let items = markers.iter().map(|marker| {
+                    Line::from(vec![
+                        Span::raw(format!("{}:{}", marker.path, marker.line)).blue(),
+                        Span::raw(" "),
+                        Span::raw(marker.marker.clone()).yellow().bold(),
+                        Span::raw(" "),
+                        Span::raw(marker.text.clone()),
+                    ])
+                });
+                let list = List::new(items).highlight_style(Style::default().reversed());
+                f.render_stateful_widget(list, list_area, &mut self.list_state);
+            }
+            Some(Err(err)) => {
+                f.render_widget(Paragraph::new(err.into_text("")?), list_area);
+            }
+        }
+
+        f.render_widget(
+            Paragraph::new(Line::from("Enter: show in diff | Esc: close").fg(Color::DarkGray))
+                .centered(),
+            help_area,
+        );
+
+        Ok(())
+    }
+
+    fn input(&mut self, _commander: &mut Commander, event: Event) -> Result<ComponentInputResult> {
+        let Event::Key(key) = event else {
+            return Ok(ComponentInputResult::Handled);
+        };
+        if key.kind != KeyEventKind::Press {
+            return Ok(ComponentInputResult::Handled);
+        }
+
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => self.list_state.select_next(),
+            KeyCode::Char('k') | KeyCode::Up => self.list_state.select_previous(),
+            KeyCode::Enter => {
+                if let Some(marker) = self.selected_marker() {
+                    return Ok(ComponentInputResult::HandledAction(
+                        ComponentAction::Multiple(vec![
+                            ComponentAction::SetPopup(None),
+                            ComponentAction::ViewFileLine {
+                                head: self.head.clone(),
+                                path: marker.path.clone(),
+                                line: Some(marker.line),
+                                text: marker.text.clone(),
+                            },
+                        ]),
+                    ));
+                }
+            }
+            KeyCode::Esc | KeyCode::Char('q') => {
+                return Ok(ComponentInputResult::HandledAction(
+                    ComponentAction::SetPopup(None),
+                ));
+            }
+            _ => return Ok(ComponentInputResult::NotHandled),
+        }
+        // select_next can move past the last marker
+        if let Some(Ok(markers)) = self.markers.as_ref()
+            && let Some(selected) = self.list_state.selected()
+            && selected >= markers.len()
+        {
+            self.list_state.select(markers.len().checked_sub(1));
+        }
+        Ok(ComponentInputResult::Handled)
+    }
+
+    fn hints(&self) -> Vec<(String, String)> {
+        vec![
+            ("j/k".to_owned(), "select".to_owned()),
+            ("Enter".to_owned(), "show in diff".to_owned()),
+            ("Esc".to_owned(), "close".to_owned()),
+        ]
+    }
+}
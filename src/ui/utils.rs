@@ -1,4 +1,10 @@
-use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use std::time::Duration;
+
+use ratatui::{
+    crossterm::event::{Event, KeyEvent, KeyModifiers},
+    layout::{Constraint, Direction, Layout, Rect},
+};
+use tui_textarea::TextArea;
 
 pub fn centered_rect(r: Rect, percent_x: u16, percent_y: u16) -> Rect {
     let popup_layout = Layout::default()
@@ -115,3 +121,49 @@ pub fn tabs_to_spaces(line: &str) -> String {
     }
     out
 }
+
+/// Size in bytes with a binary unit, like jj prints it
+pub fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{bytes}B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1}{}", UNITS[unit])
+}
+
+/// Frame of a spinner animation, for an operation running for `elapsed`
+pub fn spinner(elapsed: Duration) -> char {
+    const FRAMES: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+    FRAMES[(elapsed.as_millis() / 100) as usize % FRAMES.len()]
+}
+
+/// Whether the key is pressed with Ctrl or Alt. The tabs which match only the key code leave
+/// these to the global keys, e.g. so `Ctrl+x` doesn't run the action of `x`.
+pub fn has_command_modifier(key: &KeyEvent) -> bool {
+    key.modifiers
+        .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+}
+
+/// Pass an event to a text input. Pasted text is inserted at once instead of key by key, with
+/// its line breaks normalized, or replaced by spaces in single line inputs.
+pub fn textarea_input(textarea: &mut TextArea, event: Event, multi_line: bool) {
+    match event {
+        Event::Paste(text) => {
+            let text = text.replace("\r\n", "\n").replace('\r', "\n");
+            if multi_line {
+                textarea.insert_str(text);
+            } else {
+                textarea.insert_str(text.trim_end_matches('\n').replace('\n', " "));
+            }
+        }
+        event => {
+            textarea.input(event);
+        }
+    }
+}
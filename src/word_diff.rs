@@ -0,0 +1,122 @@
+/*!
+Word diff of two texts, used to preview the changes to a description in the
+describe editor of the [log tab][crate::ui::log_tab] before saving it.
+
+Texts are split into words and runs of whitespace, which are compared with a
+longest common subsequence. Descriptions are short, so the quadratic table is
+not a concern.
+*/
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DiffPart {
+    Unchanged(String),
+    Added(String),
+    Removed(String),
+}
+
+/// Split a text into words and runs of whitespace, keeping all characters
+fn tokenize(text: &str) -> Vec<&str> {
+    let mut tokens = vec![];
+    let mut start = 0;
+    let mut last_whitespace = None;
+    for (index, char) in text.char_indices() {
+        let whitespace = char.is_whitespace();
+        if last_whitespace.is_some_and(|last| last != whitespace) {
+            tokens.push(&text[start..index]);
+            start = index;
+        }
+        last_whitespace = Some(whitespace);
+    }
+    if start < text.len() {
+        tokens.push(&text[start..]);
+    }
+    tokens
+}
+
+/// Append a token to the parts, merging it into the last part of the same kind
+fn push_part(parts: &mut Vec<DiffPart>, part: DiffPart) {
+    match (parts.last_mut(), part) {
+        (Some(DiffPart::Unchanged(last)), DiffPart::Unchanged(token))
+        | (Some(DiffPart::Added(last)), DiffPart::Added(token))
+        | (Some(DiffPart::Removed(last)), DiffPart::Removed(token)) => last.push_str(&token),
+        (_, part) => parts.push(part),
+    }
+}
+
+/// Diff `old` and `new` by words. Joining the unchanged and removed parts gives `old`, joining
+/// the unchanged and added parts gives `new`.
+pub fn diff_words(old: &str, new: &str) -> Vec<DiffPart> {
+    let old = tokenize(old);
+    let new = tokenize(new);
+
+    // lengths[i][j]: length of the longest common subsequence of old[i..] and new[j..]
+    let mut lengths = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i][j] = if old[i] == new[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut parts = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            push_part(&mut parts, DiffPart::Unchanged(old[i].to_owned()));
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            push_part(&mut parts, DiffPart::Removed(old[i].to_owned()));
+            i += 1;
+        } else {
+            push_part(&mut parts, DiffPart::Added(new[j].to_owned()));
+            j += 1;
+        }
+    }
+    for token in &old[i..] {
+        push_part(&mut parts, DiffPart::Removed((*token).to_owned()));
+    }
+    for token in &new[j..] {
+        push_part(&mut parts, DiffPart::Added((*token).to_owned()));
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokenize_words() {
+        assert_eq!(tokenize("fix  the\nbug"), ["fix", "  ", "the", "\n", "bug"]);
+        assert_eq!(tokenize(" a "), [" ", "a", " "]);
+        assert!(tokenize("").is_empty());
+    }
+
+    #[test]
+    fn diff() {
+        assert_eq!(
+            diff_words("fix the bug", "fix the crash"),
+            [
+                DiffPart::Unchanged("fix the ".to_owned()),
+                DiffPart::Removed("bug".to_owned()),
+                DiffPart::Added("crash".to_owned()),
+            ]
+        );
+        assert_eq!(
+            diff_words("title\n\nbody", "title"),
+            [
+                DiffPart::Unchanged("title".to_owned()),
+                DiffPart::Removed("\n\nbody".to_owned()),
+            ]
+        );
+        assert_eq!(diff_words("", "new"), [DiffPart::Added("new".to_owned())]);
+        assert_eq!(
+            diff_words("same", "same"),
+            [DiffPart::Unchanged("same".to_owned())]
+        );
+    }
+}